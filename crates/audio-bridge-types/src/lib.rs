@@ -1,5 +1,11 @@
 use serde::{Deserialize, Serialize};
 
+/// Version of the bridge HTTP API, reported by `/health`.
+///
+/// Raised when a hub and bridge need each other's changes to work together; the hub flags
+/// bridges reporting a different version.
+pub const BRIDGE_PROTOCOL_VERSION: u32 = 1;
+
 /// Reason why playback ended on the receiver side.
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -13,11 +19,87 @@ pub enum PlaybackEndReason {
     Stopped,
 }
 
+/// Output level of one channel, in dBFS (floored at [`ChannelLevel::FLOOR_DBFS`]).
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ChannelLevel {
+    /// Peak level with a short hold/release, suitable for a peak meter.
+    pub peak_dbfs: f32,
+    /// RMS level averaged over ~300 ms, suitable for a VU-style meter.
+    pub rms_dbfs: f32,
+}
+
+impl ChannelLevel {
+    /// Level reported for silence.
+    pub const FLOOR_DBFS: f32 = -120.0;
+}
+
+/// One chapter of the playing file (an audiobook or long recording).
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Chapter {
+    /// Chapter title, when the file names it.
+    pub title: Option<String>,
+    /// Chapter start, in milliseconds from the start of the file.
+    pub start_ms: u64,
+    /// Chapter end; `None` for the last chapter, which runs to the end of the file.
+    pub end_ms: Option<u64>,
+}
+
+/// Direction of a relative chapter move.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ChapterStep {
+    /// Start of the next chapter.
+    Next,
+    /// Start of the current chapter, or of the previous one right after a chapter starts.
+    Previous,
+}
+
+/// Chapter selection request: an absolute `index` or a relative `step`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ChapterRequest {
+    /// Zero-based chapter to jump to.
+    #[serde(default)]
+    pub index: Option<usize>,
+    /// Move relative to the current chapter (ignored when `index` is set).
+    #[serde(default)]
+    pub step: Option<ChapterStep>,
+}
+
+/// How far into a chapter "previous" restarts it instead of going back one.
+pub const CHAPTER_RESTART_MS: u64 = 3000;
+
+/// Index of the chapter containing `elapsed_ms`.
+pub fn chapter_at(chapters: &[Chapter], elapsed_ms: u64) -> Option<usize> {
+    chapters.iter().rposition(|c| c.start_ms <= elapsed_ms)
+}
+
+/// Position (ms) a chapter request seeks to, or `None` when it names no chapter.
+pub fn chapter_seek_ms(chapters: &[Chapter], elapsed_ms: u64, req: &ChapterRequest) -> Option<u64> {
+    let index = match (req.index, req.step) {
+        (Some(index), _) => index,
+        (None, Some(ChapterStep::Next)) => chapter_at(chapters, elapsed_ms).map_or(0, |i| i + 1),
+        (None, Some(ChapterStep::Previous)) => {
+            let current = chapter_at(chapters, elapsed_ms)?;
+            if elapsed_ms - chapters[current].start_ms >= CHAPTER_RESTART_MS {
+                current
+            } else {
+                current.saturating_sub(1)
+            }
+        }
+        (None, None) => return None,
+    };
+    chapters.get(index).map(|c| c.start_ms)
+}
+
 /// Low-level playback status reported by a bridge/receiver instance.
 ///
 /// This payload is focused on transport and renderer details and does not include
 /// library metadata identifiers (album/track ids).
-#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct BridgeStatus {
     /// Current file/path being played, if available.
@@ -62,6 +144,41 @@ pub struct BridgeStatus {
     pub buffer_capacity_frames: Option<u64>,
     /// End reason when playback transitions to idle.
     pub end_reason: Option<PlaybackEndReason>,
+    /// `true` while the output device is unplugged and playback is holding buffered audio.
+    #[serde(default)]
+    pub output_disconnected: Option<bool>,
+    /// Device access mode actually in use: `exclusive` (WASAPI exclusive / CoreAudio hog) or `shared`.
+    #[serde(default)]
+    pub output_mode: Option<String>,
+    /// Resampler in use while resampling: the sinc preset (`fast`, `balanced`, `high`),
+    /// `linear`, `cubic`, or `soxr-<preset>`.
+    #[serde(default)]
+    pub resample_quality: Option<String>,
+    /// Per-channel output levels (post volume) while playing.
+    #[serde(default)]
+    pub levels: Option<Vec<ChannelLevel>>,
+    /// `true` while source samples reach the device unaltered (no resampling, channel mixing,
+    /// gain or volume below 100%).
+    #[serde(default)]
+    pub bit_perfect: Option<bool>,
+    /// For DSD sources: `true` when played as DSD-over-PCM, `false` when converted to PCM.
+    #[serde(default)]
+    pub dop: Option<bool>,
+    /// `true` for an endless live stream (internet radio): no duration and no seeking.
+    #[serde(default)]
+    pub live: Option<bool>,
+    /// Now-playing title announced by a live stream's ICY metadata.
+    #[serde(default)]
+    pub stream_title: Option<String>,
+    /// Packets of the current track the decoder rejected as corrupt.
+    #[serde(default)]
+    pub corrupt_packets: Option<u64>,
+    /// Chapters of the current file, when it has any.
+    #[serde(default)]
+    pub chapters: Option<Vec<Chapter>>,
+    /// Index into `chapters` of the chapter being played.
+    #[serde(default)]
+    pub chapter_index: Option<usize>,
 }
 
 /// Session-level playback status exposed by the hub API.
@@ -128,4 +245,104 @@ pub struct PlaybackStatus {
     pub buffer_capacity_frames: Option<u64>,
     /// Whether a previous track is available in session history.
     pub has_previous: Option<bool>,
+    /// Per-channel output levels from the renderer, when it reports them.
+    #[serde(default)]
+    pub levels: Option<Vec<ChannelLevel>>,
+    /// Whether the renderer reports bit-perfect output, when it reports it.
+    #[serde(default)]
+    pub bit_perfect: Option<bool>,
+    /// For DSD sources: whether the renderer plays them as DSD-over-PCM.
+    #[serde(default)]
+    pub dop: Option<bool>,
+    /// Chapters of the current file, when the renderer reports them.
+    #[serde(default)]
+    pub chapters: Option<Vec<Chapter>>,
+    /// Index into `chapters` of the chapter being played.
+    #[serde(default)]
+    pub chapter_index: Option<usize>,
+}
+
+/// Parse a DSCP value shared by hub and bridge network settings.
+///
+/// Accepts a class name (`EF`, `CS0`-`CS7`, `AF11`-`AF43`, case-insensitive) or a raw
+/// code point `0`-`63`. Returns the 6-bit code point (shift left by 2 for the TOS byte).
+pub fn parse_dscp(value: &str) -> Option<u8> {
+    let value = value.trim().to_ascii_uppercase();
+    if let Ok(raw) = value.parse::<u8>() {
+        return (raw < 64).then_some(raw);
+    }
+    if value == "EF" {
+        return Some(46);
+    }
+    if let Some(class) = value.strip_prefix("CS") {
+        let class = class.parse::<u8>().ok().filter(|c| *c <= 7)?;
+        return Some(class << 3);
+    }
+    let digits = value.strip_prefix("AF")?.as_bytes();
+    match digits {
+        [class @ b'1'..=b'4', drop @ b'1'..=b'3'] => {
+            Some(((class - b'0') << 3) | ((drop - b'0') << 1))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapters() -> Vec<Chapter> {
+        [0, 60_000, 120_000]
+            .into_iter()
+            .map(|start_ms| Chapter {
+                title: None,
+                start_ms,
+                end_ms: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chapter_seek_steps_and_restarts() {
+        let chapters = chapters();
+        let seek = |elapsed, index, step| {
+            chapter_seek_ms(&chapters, elapsed, &ChapterRequest { index, step })
+        };
+        assert_eq!(chapter_at(&chapters, 61_000), Some(1));
+        assert_eq!(seek(61_000, None, Some(ChapterStep::Next)), Some(120_000));
+        assert_eq!(seek(125_000, None, Some(ChapterStep::Next)), None);
+        // Early in a chapter "previous" goes back one; later it restarts the chapter.
+        assert_eq!(seek(61_000, None, Some(ChapterStep::Previous)), Some(0));
+        assert_eq!(
+            seek(90_000, None, Some(ChapterStep::Previous)),
+            Some(60_000)
+        );
+        assert_eq!(seek(1_000, None, Some(ChapterStep::Previous)), Some(0));
+        assert_eq!(
+            seek(90_000, Some(2), Some(ChapterStep::Previous)),
+            Some(120_000)
+        );
+        assert_eq!(seek(0, Some(3), None), None);
+        assert_eq!(seek(0, None, None), None);
+    }
+
+    #[test]
+    fn parse_dscp_accepts_names_and_code_points() {
+        assert_eq!(parse_dscp("EF"), Some(46));
+        assert_eq!(parse_dscp("ef"), Some(46));
+        assert_eq!(parse_dscp("AF41"), Some(34));
+        assert_eq!(parse_dscp("af11"), Some(10));
+        assert_eq!(parse_dscp("CS6"), Some(48));
+        assert_eq!(parse_dscp("CS0"), Some(0));
+        assert_eq!(parse_dscp("46"), Some(46));
+    }
+
+    #[test]
+    fn parse_dscp_rejects_invalid_values() {
+        assert_eq!(parse_dscp("64"), None);
+        assert_eq!(parse_dscp("CS8"), None);
+        assert_eq!(parse_dscp("AF51"), None);
+        assert_eq!(parse_dscp("AF14"), None);
+        assert_eq!(parse_dscp("fast"), None);
+    }
 }
@@ -0,0 +1,251 @@
+//! AcoustID fingerprint matching for files without usable tags.
+//!
+//! Fingerprints come from Chromaprint's `fpcalc` tool and are looked up on the AcoustID web
+//! service, which links them to MusicBrainz recordings.
+
+use std::path::Path;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+use crate::config::MusicBrainzConfig;
+use crate::metadata_db::AcoustIdMatch;
+
+const LOOKUP_URL: &str = "https://api.acoustid.org/v2/lookup";
+/// AcoustID allows three requests per second.
+const RATE_LIMIT_MS: u64 = 340;
+/// Lowest AcoustID score accepted as the same recording.
+const MIN_SCORE: f64 = 0.8;
+
+/// Chromaprint fingerprint of one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// Compressed fingerprint string.
+    pub fingerprint: String,
+    /// Duration of the file in whole seconds.
+    pub duration_s: u32,
+}
+
+/// Rate-limited AcoustID client plus the `fpcalc` binary it fingerprints with.
+pub struct AcoustIdClient {
+    api_key: String,
+    fpcalc: String,
+    agent: ureq::Agent,
+    last_request: Mutex<Instant>,
+}
+
+impl AcoustIdClient {
+    /// Build a client from config; `None` when no AcoustID key is set.
+    pub fn new(cfg: &MusicBrainzConfig, user_agent: &str) -> Option<Self> {
+        let api_key = cfg
+            .acoustid_api_key
+            .as_deref()
+            .map(str::trim)
+            .filter(|key| !key.is_empty())?;
+        let config = ureq::Agent::config_builder().user_agent(user_agent).build();
+        Some(Self {
+            api_key: api_key.to_string(),
+            fpcalc: cfg
+                .fpcalc_path
+                .clone()
+                .unwrap_or_else(|| "fpcalc".to_string()),
+            agent: ureq::Agent::new_with_config(config),
+            last_request: Mutex::new(Instant::now() - Duration::from_millis(RATE_LIMIT_MS)),
+        })
+    }
+
+    /// Fingerprint the first two minutes of `path` with `fpcalc`.
+    ///
+    /// `Ok(None)` means `fpcalc` ran but could not decode the file; errors mean it could not run.
+    pub fn fingerprint(&self, path: &Path) -> Result<Option<Fingerprint>> {
+        let output = Command::new(&self.fpcalc)
+            .arg("-json")
+            .arg(path)
+            .output()
+            .with_context(|| format!("failed to run {}", self.fpcalc))?;
+        if !output.status.success() {
+            tracing::debug!(
+                path = %path.display(),
+                status = %output.status,
+                stderr = %String::from_utf8_lossy(&output.stderr).trim(),
+                "fpcalc could not fingerprint file"
+            );
+            return Ok(None);
+        }
+        parse_fpcalc_output(&output.stdout).map(Some)
+    }
+
+    /// Look a fingerprint up; `Ok(None)` when no recording matches closely enough.
+    pub fn lookup(&self, fingerprint: &Fingerprint) -> Result<Option<(AcoustIdMatch, f64)>> {
+        self.wait_rate_limit();
+        let duration = fingerprint.duration_s.to_string();
+        // Fingerprints run to a few kilobytes, so they go in a form body rather than the URL.
+        let resp = self
+            .agent
+            .post(LOOKUP_URL)
+            .send_form([
+                ("client", self.api_key.as_str()),
+                ("meta", "recordings"),
+                ("duration", duration.as_str()),
+                ("fingerprint", fingerprint.fingerprint.as_str()),
+            ])
+            .context("acoustid request failed")?;
+        let body = resp
+            .into_body()
+            .with_config()
+            .limit(1_000_000)
+            .read_to_string()
+            .context("acoustid response read failed")?;
+        let body: LookupResponse =
+            serde_json::from_str(&body).context("acoustid response parse failed")?;
+        if body.status != "ok" {
+            let message = body
+                .error
+                .map(|err| err.message)
+                .unwrap_or_else(|| body.status.clone());
+            bail!("acoustid lookup failed: {message}");
+        }
+        Ok(best_match(body.results))
+    }
+
+    fn wait_rate_limit(&self) {
+        let mut last = self.last_request.lock().expect("acoustid rate limit lock");
+        let elapsed = last.elapsed();
+        let limit = Duration::from_millis(RATE_LIMIT_MS);
+        if elapsed < limit {
+            std::thread::sleep(limit - elapsed);
+        }
+        *last = Instant::now();
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FpcalcOutput {
+    duration: f64,
+    fingerprint: String,
+}
+
+/// Parse `fpcalc -json` output.
+fn parse_fpcalc_output(stdout: &[u8]) -> Result<Fingerprint> {
+    let output: FpcalcOutput =
+        serde_json::from_slice(stdout).context("fpcalc output parse failed")?;
+    if output.fingerprint.is_empty() {
+        bail!("fpcalc returned an empty fingerprint");
+    }
+    Ok(Fingerprint {
+        fingerprint: output.fingerprint,
+        duration_s: output.duration.round() as u32,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct LookupResponse {
+    status: String,
+    #[serde(default)]
+    error: Option<LookupError>,
+    #[serde(default)]
+    results: Vec<LookupResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LookupError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LookupResult {
+    id: String,
+    score: f64,
+    #[serde(default)]
+    recordings: Vec<LookupRecording>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LookupRecording {
+    id: String,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    artists: Vec<LookupArtist>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LookupArtist {
+    id: String,
+    name: String,
+}
+
+/// Highest-scoring result linked to a recording, preferring recordings with a title.
+fn best_match(results: Vec<LookupResult>) -> Option<(AcoustIdMatch, f64)> {
+    let result = results
+        .into_iter()
+        .filter(|result| result.score >= MIN_SCORE && !result.recordings.is_empty())
+        .max_by(|a, b| a.score.total_cmp(&b.score))?;
+    let score = result.score;
+    let mut recordings = result.recordings;
+    let index = recordings
+        .iter()
+        .position(|recording| recording.title.is_some())
+        .unwrap_or(0);
+    let recording = recordings.swap_remove(index);
+    let artist = recording.artists.into_iter().next();
+    Some((
+        AcoustIdMatch {
+            acoustid_id: result.id,
+            recording_mbid: recording.id,
+            title: recording.title,
+            artist: artist.as_ref().map(|artist| artist.name.clone()),
+            artist_mbid: artist.map(|artist| artist.id),
+        },
+        score,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_fpcalc_output_rounds_duration() {
+        let fingerprint =
+            parse_fpcalc_output(br#"{"duration": 213.47, "fingerprint": "AQADtE"}"#).unwrap();
+        assert_eq!(
+            fingerprint,
+            Fingerprint {
+                fingerprint: "AQADtE".to_string(),
+                duration_s: 213,
+            }
+        );
+        assert!(parse_fpcalc_output(br#"{"duration": 1.0, "fingerprint": ""}"#).is_err());
+    }
+
+    #[test]
+    fn best_match_picks_top_scoring_titled_recording() {
+        let body = r#"{
+            "status": "ok",
+            "results": [
+                {"id": "low", "score": 0.95, "recordings": []},
+                {"id": "weak", "score": 0.5, "recordings": [{"id": "r0", "title": "Weak"}]},
+                {"id": "good", "score": 0.91, "recordings": [
+                    {"id": "r1"},
+                    {"id": "r2", "title": "Song", "artists": [{"id": "a1", "name": "Artist"}]}
+                ]}
+            ]
+        }"#;
+        let body: LookupResponse = serde_json::from_str(body).unwrap();
+        let (found, score) = best_match(body.results).expect("match");
+        assert_eq!(score, 0.91);
+        assert_eq!(found.acoustid_id, "good");
+        assert_eq!(found.recording_mbid, "r2");
+        assert_eq!(found.title.as_deref(), Some("Song"));
+        assert_eq!(found.artist.as_deref(), Some("Artist"));
+        assert_eq!(found.artist_mbid.as_deref(), Some("a1"));
+
+        let body: LookupResponse =
+            serde_json::from_str(r#"{"status": "ok", "results": []}"#).unwrap();
+        assert!(best_match(body.results).is_none());
+    }
+}
@@ -0,0 +1,905 @@
+//! Minimal AirPlay (RAOP) sender for AirPlay 1 receivers.
+//!
+//! The hub decodes the track itself, converts it to 44.1 kHz 16-bit stereo and streams it over
+//! RTP as uncompressed ALAC or L16 PCM, whichever the receiver advertises. Only receivers
+//! that accept unencrypted audio (`et` includes `0`) and need no password are supported, which
+//! covers AirPort Express, shairport-sync and most AirPlay-enabled AV receivers.
+//!
+//! Receivers buffer about two seconds of audio; elapsed time is reported for what is audible,
+//! so it trails what has been sent by that latency. Pause and seek flush the receiver and
+//! restart the decode at the audible position.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, anyhow};
+use audio_bridge_types::{BridgeStatus, PlaybackEndReason};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+
+use crate::bridge::BridgeCommand;
+use crate::events::EventBus;
+use crate::metadata_db::MetadataDb;
+use crate::pcm_track::PcmTrack;
+use crate::playback_transport::ChannelTransport;
+use crate::queue_service::QueueService;
+use crate::state::{AirplayProviderState, QueueState};
+use crate::status_store::StatusStore;
+
+/// Sample rate every RAOP stream uses.
+const SAMPLE_RATE: u32 = 44_100;
+/// Frames per RTP audio packet.
+const FRAMES_PER_PACKET: usize = 352;
+/// Receiver latency assumed when `RECORD` does not report one (2 s).
+const DEFAULT_LATENCY_FRAMES: u32 = 88_200;
+/// How far ahead of real time audio is sent.
+const LEAD_FRAMES: u64 = 4_410;
+/// Worker tick while streaming (about one packet).
+const STREAM_TICK: Duration = Duration::from_millis(8);
+/// Worker tick while idle.
+const IDLE_TICK: Duration = Duration::from_millis(250);
+/// How often status is published while playing.
+const STATUS_INTERVAL: Duration = Duration::from_millis(500);
+/// RTSP connect and read timeout.
+const RTSP_TIMEOUT: Duration = Duration::from_secs(5);
+/// Seconds between the NTP (1900) and unix (1970) epochs.
+const NTP_EPOCH_OFFSET: u64 = 2_208_988_800;
+
+/// Audio encoding sent to a receiver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AirplayCodec {
+    /// Uncompressed ALAC frames (`cn` 1).
+    Alac,
+    /// Big-endian 16-bit PCM (`cn` 0).
+    Pcm,
+}
+
+impl AirplayCodec {
+    /// Pick the codec from the `cn` TXT record; ALAC unless only PCM is listed.
+    pub fn from_txt(cn: Option<&str>) -> Self {
+        let Some(cn) = cn else {
+            return Self::Alac;
+        };
+        let codecs: Vec<&str> = cn.split(',').map(str::trim).collect();
+        if !codecs.contains(&"1") && codecs.contains(&"0") {
+            Self::Pcm
+        } else {
+            Self::Alac
+        }
+    }
+}
+
+/// Whether a receiver accepts unencrypted audio, from its `et` TXT record.
+pub fn accepts_unencrypted(et: Option<&str>) -> bool {
+    et.is_none_or(|et| et.split(',').any(|kind| kind.trim() == "0"))
+}
+
+#[derive(Debug, Clone)]
+/// Connection descriptor for a discovered AirPlay receiver.
+pub struct AirplayDeviceDescriptor {
+    /// Stable receiver id (hardware address).
+    pub id: String,
+    /// Human-readable receiver name.
+    pub name: String,
+    /// Hostname or IP address of the RTSP service.
+    pub host: String,
+    /// RTSP port.
+    pub port: u16,
+    /// Audio encoding to send.
+    pub codec: AirplayCodec,
+}
+
+/// Current time as a 64-bit NTP timestamp.
+fn ntp_now() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = now.as_secs() + NTP_EPOCH_OFFSET;
+    let frac = (u64::from(now.subsec_nanos()) << 32) / 1_000_000_000;
+    (secs << 32) | frac
+}
+
+/// Writes big-endian bit fields, as ALAC frames need.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    used: u32,
+}
+
+impl BitWriter {
+    /// Append the low `count` bits of `value`, most significant first.
+    fn write(&mut self, value: u32, count: u32) {
+        for bit in (0..count).rev() {
+            if self.used.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if (value >> bit) & 1 == 1 {
+                let last = self.bytes.len() - 1;
+                self.bytes[last] |= 0x80 >> (self.used % 8);
+            }
+            self.used += 1;
+        }
+    }
+}
+
+/// Encode interleaved stereo samples as one uncompressed ALAC frame.
+fn encode_alac(samples: &[i16]) -> Vec<u8> {
+    let frames = samples.len() / 2;
+    let mut out = BitWriter::default();
+    out.write(1, 3); // channel pair element
+    out.write(0, 4); // element instance tag
+    out.write(0, 12); // unused
+    out.write(1, 1); // frame size follows
+    out.write(0, 2); // no shift
+    out.write(1, 1); // not compressed
+    out.write(frames as u32, 32);
+    for sample in samples {
+        out.write(u32::from(*sample as u16), 16);
+    }
+    out.write(7, 3); // end of frame
+    out.bytes
+}
+
+/// Encode interleaved stereo samples as big-endian L16.
+fn encode_pcm(samples: &[i16]) -> Vec<u8> {
+    samples.iter().flat_map(|s| s.to_be_bytes()).collect()
+}
+
+/// RTP audio packet; the first packet after `RECORD` or a flush carries the marker bit.
+fn audio_packet(marker: bool, seq: u16, timestamp: u32, ssrc: u32, payload: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(12 + payload.len());
+    packet.push(0x80);
+    packet.push(if marker { 0xe0 } else { 0x60 });
+    packet.extend_from_slice(&seq.to_be_bytes());
+    packet.extend_from_slice(&timestamp.to_be_bytes());
+    packet.extend_from_slice(&ssrc.to_be_bytes());
+    packet.extend_from_slice(payload);
+    packet
+}
+
+/// Sync packet tying the RTP clock to NTP time, sent on the control channel.
+fn sync_packet(first: bool, next_timestamp: u32, latency: u32, ntp: u64) -> [u8; 20] {
+    let mut packet = [0u8; 20];
+    packet[0] = if first { 0x90 } else { 0x80 };
+    packet[1] = 0xd4;
+    packet[2..4].copy_from_slice(&7u16.to_be_bytes());
+    packet[4..8].copy_from_slice(&next_timestamp.wrapping_sub(latency).to_be_bytes());
+    packet[8..16].copy_from_slice(&ntp.to_be_bytes());
+    packet[16..20].copy_from_slice(&next_timestamp.to_be_bytes());
+    packet
+}
+
+/// Answer to a timing request (`0xd2`), or `None` for anything else.
+fn timing_reply(request: &[u8], received: u64, sent: u64) -> Option<[u8; 32]> {
+    if request.len() < 32 || request[1] & 0x7f != 0x52 {
+        return None;
+    }
+    let mut reply = [0u8; 32];
+    reply[0] = 0x80;
+    reply[1] = 0xd3;
+    reply[2..4].copy_from_slice(&7u16.to_be_bytes());
+    reply[8..16].copy_from_slice(&request[24..32]);
+    reply[16..24].copy_from_slice(&received.to_be_bytes());
+    reply[24..32].copy_from_slice(&sent.to_be_bytes());
+    Some(reply)
+}
+
+/// SDP body for `ANNOUNCE`.
+fn announce_sdp(codec: AirplayCodec, session: u32, local_ip: IpAddr, remote_ip: IpAddr) -> String {
+    let family = |ip: IpAddr| if ip.is_ipv4() { "IP4" } else { "IP6" };
+    let media = match codec {
+        AirplayCodec::Alac => format!(
+            "a=rtpmap:96 AppleLossless\r\na=fmtp:96 {FRAMES_PER_PACKET} 0 16 40 10 14 2 255 0 0 {SAMPLE_RATE}\r\n"
+        ),
+        AirplayCodec::Pcm => format!("a=rtpmap:96 L16/{SAMPLE_RATE}/2\r\n"),
+    };
+    format!(
+        "v=0\r\no=AudioHub {session} 0 IN {} {local_ip}\r\ns=AudioHub\r\nc=IN {} {remote_ip}\r\nt=0 0\r\nm=audio 0 RTP/AVP 96\r\n{media}",
+        family(local_ip),
+        family(remote_ip),
+    )
+}
+
+/// Value of `key` in a `;`-separated RTSP header such as `Transport`.
+fn header_param(header: &str, key: &str) -> Option<u16> {
+    header.split(';').find_map(|part| {
+        let (name, value) = part.trim().split_once('=')?;
+        (name == key).then(|| value.parse().ok()).flatten()
+    })
+}
+
+/// AirPlay volume (-30 dB to 0 dB, -144 for mute) for a percent value.
+fn airplay_volume(value: u8, muted: bool) -> f32 {
+    if muted || value == 0 {
+        -144.0
+    } else {
+        -30.0 + 30.0 * f32::from(value.min(100)) / 100.0
+    }
+}
+
+/// Response to an RTSP request.
+struct RtspResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+}
+
+impl RtspResponse {
+    /// Header value by case-insensitive name.
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// RTSP control connection of a RAOP session.
+struct Rtsp {
+    reader: BufReader<TcpStream>,
+    url: String,
+    cseq: u32,
+    client_instance: String,
+    session: Option<String>,
+}
+
+impl Rtsp {
+    /// Send a request and read its response; non-2xx answers are errors.
+    fn request(
+        &mut self,
+        method: &str,
+        headers: &[(&str, String)],
+        body: Option<(&str, &str)>,
+    ) -> Result<RtspResponse> {
+        self.cseq += 1;
+        let mut request = format!(
+            "{method} {} RTSP/1.0\r\nCSeq: {}\r\nUser-Agent: AudioHub/{}\r\nClient-Instance: {}\r\n",
+            self.url,
+            self.cseq,
+            env!("CARGO_PKG_VERSION"),
+            self.client_instance
+        );
+        if let Some(session) = &self.session {
+            request.push_str(&format!("Session: {session}\r\n"));
+        }
+        for (name, value) in headers {
+            request.push_str(&format!("{name}: {value}\r\n"));
+        }
+        if let Some((content_type, body)) = body {
+            request.push_str(&format!(
+                "Content-Type: {content_type}\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len()
+            ));
+        } else {
+            request.push_str("\r\n");
+        }
+        self.reader
+            .get_mut()
+            .write_all(request.as_bytes())
+            .with_context(|| format!("send {method}"))?;
+        let response = self
+            .read_response()
+            .with_context(|| format!("read {method}"))?;
+        if !(200..300).contains(&response.status) {
+            return Err(anyhow!("{method} answered {}", response.status));
+        }
+        Ok(response)
+    }
+
+    /// Read a response status line, headers and (discarded) body.
+    fn read_response(&mut self) -> Result<RtspResponse> {
+        let mut line = String::new();
+        self.reader.read_line(&mut line)?;
+        let status = line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| anyhow!("bad status line {:?}", line.trim()))?;
+        let mut headers = Vec::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                break;
+            }
+            let trimmed = line.trim_end();
+            if trimmed.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = trimmed.split_once(':') {
+                headers.push((name.trim().to_string(), value.trim().to_string()));
+            }
+        }
+        let response = RtspResponse { status, headers };
+        let length: usize = response
+            .header("Content-Length")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        if length > 0 {
+            let mut body = vec![0u8; length];
+            self.reader.read_exact(&mut body)?;
+        }
+        Ok(response)
+    }
+}
+
+/// An established RAOP session: RTSP control plus the UDP audio and control channels.
+struct RaopSession {
+    rtsp: Rtsp,
+    codec: AirplayCodec,
+    audio: UdpSocket,
+    control: UdpSocket,
+    server: SocketAddr,
+    control_peer: SocketAddr,
+    timing_stop: Arc<AtomicBool>,
+    ssrc: u32,
+    seq: u16,
+    timestamp: u32,
+    latency: u32,
+    /// Next audio packet carries the marker bit and is preceded by a first sync.
+    restart: bool,
+    last_sync: Instant,
+    /// Volume last sent to the receiver.
+    volume: Option<(u8, bool)>,
+}
+
+impl RaopSession {
+    /// Connect to a receiver and run `ANNOUNCE`, `SETUP` and `RECORD`.
+    fn connect(device: &AirplayDeviceDescriptor) -> Result<Self> {
+        let addr = (device.host.as_str(), device.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| anyhow!("no address for {}", device.host))?;
+        let stream = TcpStream::connect_timeout(&addr, RTSP_TIMEOUT).context("connect")?;
+        stream.set_read_timeout(Some(RTSP_TIMEOUT))?;
+        stream.set_nodelay(true)?;
+        let local_ip = stream.local_addr()?.ip();
+        let remote_ip = addr.ip();
+        let bind = SocketAddr::new(local_ip, 0);
+        let audio = UdpSocket::bind(bind).context("bind audio socket")?;
+        let control = UdpSocket::bind(bind).context("bind control socket")?;
+        let timing = UdpSocket::bind(bind).context("bind timing socket")?;
+
+        let ids = uuid::Uuid::new_v4().as_u128();
+        let session_num = ids as u32;
+        let mut rtsp = Rtsp {
+            reader: BufReader::new(stream),
+            url: format!("rtsp://{local_ip}/{session_num}"),
+            cseq: 0,
+            client_instance: format!("{:016X}", (ids >> 64) as u64),
+            session: None,
+        };
+        // Some receivers reject OPTIONS without an Apple-Challenge; it is informational only.
+        let _ = rtsp.request("OPTIONS", &[], None);
+        let sdp = announce_sdp(device.codec, session_num, local_ip, remote_ip);
+        rtsp.request("ANNOUNCE", &[], Some(("application/sdp", &sdp)))?;
+        let transport = format!(
+            "RTP/AVP/UDP;unicast;interleaved=0-1;mode=record;control_port={};timing_port={}",
+            control.local_addr()?.port(),
+            timing.local_addr()?.port()
+        );
+        let setup = rtsp.request("SETUP", &[("Transport", transport)], None)?;
+        let session = setup
+            .header("Session")
+            .map(|s| s.split(';').next().unwrap_or(s).to_string())
+            .ok_or_else(|| anyhow!("SETUP answered without a session"))?;
+        let transport = setup
+            .header("Transport")
+            .ok_or_else(|| anyhow!("SETUP answered without a transport"))?;
+        let server_port = header_param(transport, "server_port")
+            .ok_or_else(|| anyhow!("SETUP answered without server_port"))?;
+        let control_port = header_param(transport, "control_port").unwrap_or(server_port + 1);
+        rtsp.session = Some(session);
+
+        let seq = (ids >> 32) as u16;
+        let timestamp = (ids >> 48) as u32;
+        let record = rtsp.request(
+            "RECORD",
+            &[
+                ("Range", "npt=0-".to_string()),
+                ("RTP-Info", format!("seq={seq};rtptime={timestamp}")),
+            ],
+            None,
+        )?;
+        let latency = record
+            .header("Audio-Latency")
+            .and_then(|v| v.parse().ok())
+            .filter(|frames| *frames > 0)
+            .unwrap_or(DEFAULT_LATENCY_FRAMES);
+
+        let timing_stop = Arc::new(AtomicBool::new(false));
+        spawn_timing_responder(timing, timing_stop.clone());
+        Ok(Self {
+            rtsp,
+            codec: device.codec,
+            audio,
+            control,
+            server: SocketAddr::new(remote_ip, server_port),
+            control_peer: SocketAddr::new(remote_ip, control_port),
+            timing_stop,
+            ssrc: ids as u32 ^ 0x5a5a_5a5a,
+            seq,
+            timestamp,
+            latency,
+            restart: true,
+            last_sync: Instant::now(),
+            volume: None,
+        })
+    }
+
+    /// Send one packet of interleaved stereo samples.
+    fn send_audio(&mut self, samples: &[i16]) -> Result<()> {
+        if self.restart || self.last_sync.elapsed() >= Duration::from_secs(1) {
+            let sync = sync_packet(self.restart, self.timestamp, self.latency, ntp_now());
+            self.control.send_to(&sync, self.control_peer)?;
+            self.last_sync = Instant::now();
+        }
+        let payload = match self.codec {
+            AirplayCodec::Alac => encode_alac(samples),
+            AirplayCodec::Pcm => encode_pcm(samples),
+        };
+        let packet = audio_packet(self.restart, self.seq, self.timestamp, self.ssrc, &payload);
+        self.audio.send_to(&packet, self.server)?;
+        self.restart = false;
+        self.seq = self.seq.wrapping_add(1);
+        self.timestamp = self.timestamp.wrapping_add((samples.len() / 2) as u32);
+        Ok(())
+    }
+
+    /// Drop audio the receiver has buffered.
+    fn flush(&mut self) -> Result<()> {
+        let info = format!("seq={};rtptime={}", self.seq, self.timestamp);
+        self.rtsp.request("FLUSH", &[("RTP-Info", info)], None)?;
+        self.restart = true;
+        Ok(())
+    }
+
+    /// Send the volume when it differs from what the receiver has.
+    fn apply_volume(&mut self, volume: (u8, bool)) -> Result<()> {
+        if self.volume == Some(volume) {
+            return Ok(());
+        }
+        let body = format!("volume: {:.6}\r\n", airplay_volume(volume.0, volume.1));
+        self.rtsp
+            .request("SET_PARAMETER", &[], Some(("text/parameters", &body)))?;
+        self.volume = Some(volume);
+        Ok(())
+    }
+
+    /// Close the session (best-effort).
+    fn teardown(mut self) {
+        let _ = self.rtsp.request("TEARDOWN", &[], None);
+    }
+}
+
+impl Drop for RaopSession {
+    fn drop(&mut self) {
+        self.timing_stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Answer the receiver's clock requests until `stop` is set.
+fn spawn_timing_responder(socket: UdpSocket, stop: Arc<AtomicBool>) {
+    std::thread::spawn(move || {
+        let _ = socket.set_read_timeout(Some(Duration::from_millis(500)));
+        let mut buf = [0u8; 128];
+        while !stop.load(Ordering::Relaxed) {
+            let Ok((len, peer)) = socket.recv_from(&mut buf) else {
+                continue;
+            };
+            let received = ntp_now();
+            if let Some(reply) = timing_reply(&buf[..len], received, ntp_now()) {
+                let _ = socket.send_to(&reply, peer);
+            }
+        }
+    });
+}
+
+/// Shared handles the worker reports through.
+pub struct AirplayWorkerContext {
+    pub provider: Arc<AirplayProviderState>,
+    pub status: StatusStore,
+    pub queue: Arc<Mutex<QueueState>>,
+    pub events: EventBus,
+    pub metadata: Option<MetadataDb>,
+    pub bridge_state: Arc<Mutex<crate::state::BridgeState>>,
+}
+
+/// Per-output worker state.
+struct Worker {
+    output_id: String,
+    device: AirplayDeviceDescriptor,
+    cmd_tx: Sender<BridgeCommand>,
+    ctx: AirplayWorkerContext,
+    queue_service: QueueService,
+    session: Option<RaopSession>,
+    track: Option<PcmTrack>,
+    /// Frames sent since `anchor`; paces sending in real time.
+    anchor: Instant,
+    anchor_frames: u64,
+    stream_frames: u64,
+    last_status: Instant,
+    last_duration_ms: Option<u64>,
+    auto_advance_in_flight: bool,
+}
+
+/// Spawn the worker streaming to one AirPlay receiver.
+pub fn spawn_airplay_worker(
+    output_id: String,
+    device: AirplayDeviceDescriptor,
+    cmd_rx: Receiver<BridgeCommand>,
+    cmd_tx: Sender<BridgeCommand>,
+    ctx: AirplayWorkerContext,
+) {
+    std::thread::spawn(move || {
+        let queue_service =
+            QueueService::new(ctx.queue.clone(), ctx.status.clone(), ctx.events.clone());
+        let mut worker = Worker {
+            output_id,
+            device,
+            cmd_tx,
+            ctx,
+            queue_service,
+            session: None,
+            track: None,
+            anchor: Instant::now(),
+            anchor_frames: 0,
+            stream_frames: 0,
+            last_status: Instant::now(),
+            last_duration_ms: None,
+            auto_advance_in_flight: false,
+        };
+        loop {
+            let streaming = worker
+                .track
+                .as_ref()
+                .is_some_and(|t| !t.paused && !t.drained);
+            let tick = if streaming { STREAM_TICK } else { IDLE_TICK };
+            match cmd_rx.recv_timeout(tick) {
+                Ok(BridgeCommand::Quit) | Err(RecvTimeoutError::Disconnected) => break,
+                Ok(cmd) => worker.handle(cmd),
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+            worker.apply_volume();
+            worker.pump();
+            if worker.last_status.elapsed() >= STATUS_INTERVAL {
+                worker.publish(None);
+            }
+        }
+        worker.track = None;
+        if let Some(session) = worker.session.take() {
+            session.teardown();
+        }
+        let provider = &worker.ctx.provider;
+        if let Ok(mut statuses) = provider.status_by_output.lock() {
+            statuses.remove(&worker.output_id);
+        }
+        if let Ok(mut updates) = provider.status_updated_at.lock() {
+            updates.remove(&worker.output_id);
+        }
+        if let Ok(mut workers) = provider.workers.lock() {
+            workers.remove(&worker.output_id);
+        }
+        tracing::info!(airplay_id = %worker.device.id, "airplay worker stopped");
+    });
+}
+
+impl Worker {
+    /// Apply a control command.
+    fn handle(&mut self, cmd: BridgeCommand) {
+        match cmd {
+            BridgeCommand::Play {
+                path,
+                seek_ms,
+                start_paused,
+                ..
+            } => self.play(path, seek_ms.unwrap_or(0), start_paused),
+            BridgeCommand::PauseToggle => {
+                let Some(track) = self.track.as_ref() else {
+                    return;
+                };
+                if track.paused {
+                    let (path, position) = (track.path.clone(), track.start_ms);
+                    self.play(path, position, false);
+                } else {
+                    let latency = self.latency();
+                    let position = track.elapsed_ms(latency);
+                    self.flush();
+                    if let Some(track) = self.track.as_mut() {
+                        // Keep the position; the decode restarts there on resume.
+                        track.start_ms = position;
+                        track.frames_sent = 0;
+                        track.paused = true;
+                    }
+                    self.publish(None);
+                }
+            }
+            BridgeCommand::Seek { ms } => {
+                if let Some(track) = self.track.as_ref() {
+                    let (path, paused) = (track.path.clone(), track.paused);
+                    self.flush();
+                    self.play(path, ms, paused);
+                }
+            }
+            BridgeCommand::Stop | BridgeCommand::StopSilent => {
+                self.track = None;
+                if let Some(session) = self.session.take() {
+                    session.teardown();
+                }
+                self.publish(Some(PlaybackEndReason::Stopped));
+            }
+            BridgeCommand::Quit => {}
+        }
+    }
+
+    /// Start streaming `path` from `start_ms`, connecting first if needed.
+    fn play(&mut self, path: PathBuf, start_ms: u64, start_paused: bool) {
+        self.auto_advance_in_flight = false;
+        if self.track.as_ref().is_some_and(|t| !t.drained && !t.paused) {
+            self.flush();
+        }
+        self.track = None;
+        let mut track = match PcmTrack::open(path.clone(), start_ms, SAMPLE_RATE) {
+            Ok(track) => track,
+            Err(err) => {
+                tracing::warn!(error = %format!("{err:#}"), path = %path.display(), "airplay: open track failed");
+                self.publish(Some(PlaybackEndReason::Error));
+                return;
+            }
+        };
+        track.paused = start_paused;
+        if self.session.is_none() && !start_paused {
+            match RaopSession::connect(&self.device) {
+                Ok(session) => {
+                    tracing::info!(
+                        airplay_id = %self.device.id,
+                        codec = ?session.codec,
+                        latency_frames = session.latency,
+                        "airplay session started"
+                    );
+                    self.session = Some(session);
+                }
+                Err(err) => {
+                    tracing::warn!(error = %format!("{err:#}"), airplay_id = %self.device.id, "airplay: connect failed");
+                    self.publish(Some(PlaybackEndReason::Error));
+                    return;
+                }
+            }
+        }
+        self.anchor = Instant::now();
+        self.anchor_frames = self.stream_frames;
+        self.track = Some(track);
+        if !self.is_session_bound() {
+            self.ctx.status.on_play(path, start_paused);
+        }
+        self.apply_volume();
+        self.publish(None);
+    }
+
+    /// Flush the receiver, dropping the session on failure.
+    fn flush(&mut self) {
+        if let Some(session) = self.session.as_mut()
+            && let Err(err) = session.flush()
+        {
+            tracing::warn!(error = %format!("{err:#}"), airplay_id = %self.device.id, "airplay: flush failed");
+            self.session = None;
+        }
+    }
+
+    /// Receiver latency in frames.
+    fn latency(&self) -> u32 {
+        self.session
+            .as_ref()
+            .map_or(DEFAULT_LATENCY_FRAMES, |s| s.latency)
+    }
+
+    /// Send the volume requested through the provider.
+    fn apply_volume(&mut self) {
+        let Some(session) = self.session.as_mut() else {
+            return;
+        };
+        let volume = self
+            .ctx
+            .provider
+            .volume
+            .lock()
+            .ok()
+            .and_then(|map| map.get(&self.output_id).copied());
+        if let Some(volume) = volume
+            && let Err(err) = session.apply_volume(volume)
+        {
+            tracing::warn!(error = %format!("{err:#}"), airplay_id = %self.device.id, "airplay: set volume failed");
+        }
+    }
+
+    /// Send audio up to the real-time schedule; finishes the track once it is drained.
+    fn pump(&mut self) {
+        let (Some(session), Some(track)) = (self.session.as_mut(), self.track.as_mut()) else {
+            return;
+        };
+        if track.paused || track.drained {
+            return;
+        }
+        let due = self.anchor_frames
+            + self.anchor.elapsed().as_millis() as u64 * u64::from(SAMPLE_RATE) / 1000
+            + LEAD_FRAMES;
+        while self.stream_frames < due {
+            let Some(samples) = track.next_chunk(FRAMES_PER_PACKET) else {
+                break;
+            };
+            if let Err(err) = session.send_audio(&samples) {
+                tracing::warn!(error = %err, airplay_id = %self.device.id, "airplay: send failed");
+                self.session = None;
+                self.track = None;
+                self.publish(Some(PlaybackEndReason::Error));
+                return;
+            }
+            let frames = (samples.len() / 2) as u64;
+            track.frames_sent += frames;
+            self.stream_frames += frames;
+        }
+        if track.drained {
+            // The receiver keeps playing what it buffered; the next track continues the same
+            // stream, so advancing now keeps playback gapless.
+            self.publish(Some(PlaybackEndReason::Eof));
+        }
+    }
+
+    /// Whether a session holds this output.
+    fn is_session_bound(&self) -> bool {
+        crate::session_registry::output_lock_owner(&self.output_id).is_some()
+    }
+
+    /// Store the output status and handle the end of a track.
+    fn publish(&mut self, end_reason: Option<PlaybackEndReason>) {
+        self.last_status = Instant::now();
+        let latency = self.latency();
+        let mut remote = BridgeStatus {
+            device: Some(self.device.name.clone()),
+            end_reason,
+            paused: true,
+            ..Default::default()
+        };
+        if end_reason.is_none()
+            && let Some(track) = self.track.as_ref()
+        {
+            remote.now_playing = Some(track.path.to_string_lossy().to_string());
+            remote.paused = track.paused;
+            remote.elapsed_ms = Some(track.elapsed_ms(latency));
+            remote.duration_ms = track.duration_ms;
+            remote.source_codec = track.codec.clone();
+            remote.sample_rate = Some(SAMPLE_RATE);
+            remote.channels = Some(2);
+            remote.output_sample_format = Some("S16".to_string());
+            remote.resampling = Some(track.source_rate != SAMPLE_RATE);
+            remote.resample_from_hz = Some(track.source_rate);
+            remote.resample_to_hz = Some(SAMPLE_RATE);
+        }
+        if end_reason.is_some() {
+            self.track = None;
+        }
+        let provider = &self.ctx.provider;
+        if let Ok(mut statuses) = provider.status_by_output.lock() {
+            statuses.insert(self.output_id.clone(), remote.clone());
+        }
+        if let Ok(mut updates) = provider.status_updated_at.lock() {
+            updates.insert(self.output_id.clone(), Instant::now());
+        }
+
+        let bound_session = crate::session_registry::output_lock_owner(&self.output_id);
+        if end_reason.is_some() {
+            self.ctx.events.status_changed();
+        }
+        if end_reason == Some(PlaybackEndReason::Eof)
+            && !self.auto_advance_in_flight
+            && let Some(session_id) = bound_session.as_deref()
+        {
+            self.advance_session(session_id);
+        }
+        let is_active = self
+            .ctx
+            .bridge_state
+            .lock()
+            .map(|b| b.active_output_id.as_deref() == Some(self.output_id.as_str()))
+            .unwrap_or(false);
+        if !is_active {
+            return;
+        }
+        let (inputs, changed) = self
+            .ctx
+            .status
+            .reduce_remote_and_inputs(&remote, self.last_duration_ms);
+        self.ctx.status.emit_if_changed(changed);
+        self.last_duration_ms = remote.duration_ms;
+        if bound_session.is_some() {
+            return;
+        }
+        let transport = ChannelTransport::new(self.cmd_tx.clone());
+        let _ = self.queue_service.maybe_auto_advance(&transport, inputs);
+    }
+
+    /// Play the next queue entry of the session holding this output.
+    fn advance_session(&mut self, session_id: &str) {
+        match crate::session_registry::queue_next_track_id(session_id) {
+            Ok(Some(track_id)) => {
+                let Some(path) = self
+                    .ctx
+                    .metadata
+                    .as_ref()
+                    .and_then(|db| db.track_path_for_id(track_id).ok().flatten())
+                    .map(PathBuf::from)
+                else {
+                    tracing::warn!(
+                        output_id = %self.output_id,
+                        session_id = %session_id,
+                        track_id,
+                        "airplay session auto-advance track not found"
+                    );
+                    return;
+                };
+                self.auto_advance_in_flight = true;
+                let _ = self.cmd_tx.send(BridgeCommand::Play {
+                    path,
+                    ext_hint: String::new(),
+                    seek_ms: None,
+                    start_paused: false,
+                });
+            }
+            Ok(None) => {
+                if let Ok(true) = crate::session_registry::queue_finish_now_playing(session_id) {
+                    self.ctx.events.queue_changed();
+                    self.ctx.events.status_changed();
+                }
+            }
+            Err(()) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alac_frame_has_uncompressed_header_and_samples() {
+        let frame = encode_alac(&[0x1234, -2, 0x0102, 0x0304]);
+        assert_eq!(&frame[..3], &[0x20, 0x00, 0x12]);
+        // 23 header bits + 32-bit frame count + 4 samples + end tag = 122 bits.
+        assert_eq!(frame.len(), 16);
+        assert_eq!(encode_pcm(&[0x1234, -2]), vec![0x12, 0x34, 0xff, 0xfe]);
+    }
+
+    #[test]
+    fn codec_and_encryption_follow_txt_records() {
+        assert_eq!(AirplayCodec::from_txt(Some("0,1,2")), AirplayCodec::Alac);
+        assert_eq!(AirplayCodec::from_txt(Some("0")), AirplayCodec::Pcm);
+        assert_eq!(AirplayCodec::from_txt(None), AirplayCodec::Alac);
+        assert!(accepts_unencrypted(Some("0,3,5")));
+        assert!(accepts_unencrypted(None));
+        assert!(!accepts_unencrypted(Some("1,3")));
+    }
+
+    #[test]
+    fn packets_and_headers_are_well_formed() {
+        let packet = audio_packet(true, 7, 0x0102_0304, 9, &[0xaa]);
+        assert_eq!(&packet[..4], &[0x80, 0xe0, 0x00, 0x07]);
+        assert_eq!(packet.len(), 13);
+        let sync = sync_packet(false, 100_000, 88_200, 1);
+        assert_eq!(&sync[..2], &[0x80, 0xd4]);
+        assert_eq!(u32::from_be_bytes(sync[4..8].try_into().unwrap()), 11_800);
+
+        let mut request = [0u8; 32];
+        request[1] = 0xd2;
+        request[24..32].copy_from_slice(&42u64.to_be_bytes());
+        let reply = timing_reply(&request, 1, 2).expect("reply");
+        assert_eq!(u64::from_be_bytes(reply[8..16].try_into().unwrap()), 42);
+        assert!(timing_reply(&[0u8; 8], 1, 2).is_none());
+
+        let transport = "RTP/AVP/UDP;unicast;mode=record;server_port=6000;control_port=6001";
+        assert_eq!(header_param(transport, "server_port"), Some(6000));
+        assert_eq!(header_param(transport, "timing_port"), None);
+        assert_eq!(airplay_volume(100, false), 0.0);
+        assert_eq!(airplay_volume(50, true), -144.0);
+    }
+}
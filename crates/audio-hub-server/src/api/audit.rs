@@ -0,0 +1,81 @@
+//! Audit log API handlers.
+
+use actix_web::{HttpResponse, Responder, get, web};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::metadata_db::AuditFilter;
+use crate::models::AuditListResponse;
+use crate::state::AppState;
+
+#[derive(Deserialize, ToSchema)]
+/// Audit log query parameters.
+pub struct AuditQuery {
+    /// Optional API token name filter.
+    #[serde(default)]
+    pub actor: Option<String>,
+    /// Optional client id filter.
+    #[serde(default)]
+    pub client_id: Option<String>,
+    /// Optional session id filter.
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Optional action filter (e.g. `next`).
+    #[serde(default)]
+    pub action: Option<String>,
+    /// Only rows at or after this time (unix millis).
+    #[serde(default)]
+    pub since_ms: Option<i64>,
+    /// Only rows before this time (unix millis).
+    #[serde(default)]
+    pub until_ms: Option<i64>,
+    /// Max returned items.
+    #[serde(default)]
+    pub limit: Option<i64>,
+    /// Row offset for pagination.
+    #[serde(default)]
+    pub offset: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/audit",
+    params(
+        ("actor" = Option<String>, Query, description = "API token name"),
+        ("client_id" = Option<String>, Query, description = "Client id"),
+        ("session_id" = Option<String>, Query, description = "Session id"),
+        ("action" = Option<String>, Query, description = "Action name"),
+        ("since_ms" = Option<i64>, Query, description = "From time (unix millis, inclusive)"),
+        ("until_ms" = Option<i64>, Query, description = "To time (unix millis, exclusive)"),
+        ("limit" = Option<i64>, Query, description = "Max rows"),
+        ("offset" = Option<i64>, Query, description = "Offset rows")
+    ),
+    responses(
+        (status = 200, description = "Audit log, newest first", body = AuditListResponse)
+    )
+)]
+#[get("/audit")]
+/// List recorded control actions, newest first.
+pub async fn audit_list(
+    state: web::Data<AppState>,
+    query: web::Query<AuditQuery>,
+) -> impl Responder {
+    let query = query.into_inner();
+    let limit = query.limit.unwrap_or(200).clamp(1, 1000);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let filter = AuditFilter {
+        actor: query.actor,
+        client_id: query.client_id,
+        session_id: query.session_id,
+        action: query.action,
+        since_ms: query.since_ms,
+        until_ms: query.until_ms,
+    };
+    match state.metadata.db.list_audit(&filter, limit, offset) {
+        Ok(items) => HttpResponse::Ok().json(AuditListResponse { items }),
+        Err(err) => {
+            tracing::warn!(error = %err, "audit list failed");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! API token management handlers.
+
+use actix_web::{HttpResponse, Responder, get, post, web};
+
+use crate::auth::{ApiAuth, TokenRole};
+use crate::models::{
+    ApiTokenCreateRequest, ApiTokenCreateResponse, ApiTokenListResponse, ConfigTokenEntry,
+};
+
+/// Longest accepted token name.
+const MAX_TOKEN_NAME_LEN: usize = 100;
+
+/// True when deleting `token_id` would leave listener tokens but no admin to manage them.
+fn strands_listeners(auth: &ApiAuth, token_id: i64) -> anyhow::Result<bool> {
+    let config = auth.config_tokens();
+    let stored = auth.list_tokens()?;
+    let remaining = config
+        .iter()
+        .map(|(_, role)| *role)
+        .chain(
+            stored
+                .iter()
+                .filter(|token| token.id != token_id)
+                .map(|token| token.role),
+        )
+        .collect::<Vec<_>>();
+    Ok(!remaining.is_empty() && !remaining.contains(&TokenRole::Admin))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/tokens",
+    responses(
+        (status = 200, description = "API tokens", body = ApiTokenListResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Admin token required")
+    )
+)]
+#[get("/auth/tokens")]
+/// List API tokens (secrets are never returned).
+pub async fn auth_tokens_list(auth: web::Data<ApiAuth>) -> impl Responder {
+    match auth.list_tokens() {
+        Ok(items) => HttpResponse::Ok().json(ApiTokenListResponse {
+            enabled: auth.enabled(),
+            items,
+            config_tokens: auth
+                .config_tokens()
+                .into_iter()
+                .map(|(name, role)| ConfigTokenEntry { name, role })
+                .collect(),
+        }),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/tokens",
+    request_body = ApiTokenCreateRequest,
+    responses(
+        (status = 201, description = "Token created", body = ApiTokenCreateResponse),
+        (status = 400, description = "Missing or overlong name, or listener token without an admin"),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Admin token required")
+    )
+)]
+#[post("/auth/tokens")]
+/// Create an API token; the first token switches authentication on and must be an admin token.
+pub async fn auth_tokens_create(
+    auth: web::Data<ApiAuth>,
+    body: web::Json<ApiTokenCreateRequest>,
+) -> impl Responder {
+    let name = body.name.trim();
+    if name.is_empty() || name.len() > MAX_TOKEN_NAME_LEN {
+        return HttpResponse::BadRequest().body(format!(
+            "token name must be 1-{MAX_TOKEN_NAME_LEN} characters"
+        ));
+    }
+    if body.role != TokenRole::Admin {
+        match auth.has_admin() {
+            Ok(true) => {}
+            Ok(false) => {
+                return HttpResponse::BadRequest()
+                    .body("create an admin token before listener tokens");
+            }
+            Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+        }
+    }
+    match auth.create_token(name, body.role) {
+        Ok((token, secret)) => {
+            tracing::info!(
+                token_id = token.id,
+                name = %token.name,
+                role = token.role.as_str(),
+                "api token created"
+            );
+            HttpResponse::Created().json(ApiTokenCreateResponse { token, secret })
+        }
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/auth/tokens/{id}",
+    params(
+        ("id" = i64, Path, description = "Token id")
+    ),
+    responses(
+        (status = 204, description = "Token deleted"),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Admin token required"),
+        (status = 404, description = "Token not found"),
+        (status = 409, description = "Last admin token while listener tokens remain")
+    )
+)]
+#[actix_web::delete("/auth/tokens/{id}")]
+/// Delete an API token; config tokens can only be removed from the config file.
+pub async fn auth_tokens_delete(auth: web::Data<ApiAuth>, id: web::Path<i64>) -> impl Responder {
+    let token_id = id.into_inner();
+    match strands_listeners(&auth, token_id) {
+        Ok(false) => {}
+        Ok(true) => {
+            return HttpResponse::Conflict()
+                .body("delete the listener tokens before the last admin token");
+        }
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    }
+    match auth.delete_token(token_id) {
+        Ok(true) => {
+            tracing::info!(token_id, "api token deleted");
+            HttpResponse::NoContent().finish()
+        }
+        Ok(false) => HttpResponse::NotFound().body("token not found"),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
@@ -0,0 +1,84 @@
+//! Metadata export/import API handlers.
+
+use actix_web::{HttpResponse, Responder, get, post, web};
+use futures_util::StreamExt;
+
+use crate::metadata_db::{METADATA_EXPORT_VERSION, MetadataExport, MetadataImportSummary};
+use crate::state::AppState;
+
+/// Largest accepted import body.
+const MAX_IMPORT_BYTES: usize = 64 * 1024 * 1024;
+
+#[utoipa::path(
+    get,
+    path = "/metadata/export",
+    responses(
+        (status = 200, description = "User-authored metadata", body = MetadataExport),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Admin token required")
+    )
+)]
+#[get("/metadata/export")]
+/// Export ratings, field locks, hand-written bios and notes, MBIDs and playlists.
+pub async fn metadata_export(state: web::Data<AppState>) -> impl Responder {
+    match state.metadata.db.export_user_data() {
+        Ok(export) => HttpResponse::Ok()
+            .insert_header((
+                actix_web::http::header::CONTENT_DISPOSITION,
+                "attachment; filename=\"audio-hub-metadata.json\"",
+            ))
+            .json(export),
+        Err(err) => {
+            tracing::warn!(error = %err, "metadata export failed");
+            HttpResponse::InternalServerError().body(err.to_string())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/metadata/import",
+    request_body = MetadataExport,
+    responses(
+        (status = 200, description = "Import merged", body = MetadataImportSummary),
+        (status = 400, description = "Malformed export or unsupported version"),
+        (status = 413, description = "Export too large")
+    )
+)]
+#[post("/metadata/import")]
+/// Merge a metadata export into this library.
+pub async fn metadata_import(
+    state: web::Data<AppState>,
+    mut payload: web::Payload,
+) -> impl Responder {
+    let mut data = Vec::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+        };
+        if data.len() + chunk.len() > MAX_IMPORT_BYTES {
+            return HttpResponse::PayloadTooLarge()
+                .body(format!("export exceeds {MAX_IMPORT_BYTES} bytes"));
+        }
+        data.extend_from_slice(&chunk);
+    }
+    let export: MetadataExport = match serde_json::from_slice(&data) {
+        Ok(export) => export,
+        Err(err) => return HttpResponse::BadRequest().body(format!("invalid export: {err}")),
+    };
+    if export.version == 0 || export.version > METADATA_EXPORT_VERSION {
+        return HttpResponse::BadRequest()
+            .body(format!("unsupported export version {}", export.version));
+    }
+    match state.metadata.db.import_user_data(&export) {
+        Ok(summary) => {
+            tracing::info!(?summary, "metadata import merged");
+            HttpResponse::Ok().json(summary)
+        }
+        Err(err) => {
+            tracing::warn!(error = %err, "metadata import failed");
+            HttpResponse::InternalServerError().body(err.to_string())
+        }
+    }
+}
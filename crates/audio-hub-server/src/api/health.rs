@@ -1,14 +1,43 @@
-use actix_web::{HttpResponse, Responder, get};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use actix_web::{HttpResponse, Responder, get, web};
+use audio_bridge_types::BRIDGE_PROTOCOL_VERSION;
 use serde::Serialize;
 use utoipa::ToSchema;
 
+use crate::bridge_manager::merge_bridges;
+use crate::bridge_transport::{BridgeTransportClient, HttpHealthResponse};
+use crate::models::BridgeLogEntry;
+use crate::state::{AppState, BridgeHealth};
+
+/// A bridge error logged within this window counts as a current issue.
+const RECENT_ERROR_WINDOW: Duration = Duration::from_secs(10 * 60);
+
 #[derive(Serialize, ToSchema)]
 pub struct HealthResponse {
     /// Service status marker (`ok`).
     pub status: &'static str,
+    /// Reachability of configured and discovered bridges.
+    pub bridges: Vec<BridgeHealthEntry>,
+}
+
+/// Reachability of one bridge, as last seen by its background watcher.
+#[derive(Serialize, ToSchema)]
+pub struct BridgeHealthEntry {
+    /// Bridge id.
+    pub id: String,
+    /// Bridge display name.
+    pub name: String,
+    /// `pending` (not probed yet), `online` or `offline`.
+    pub state: &'static str,
+    /// Last connection error while offline.
+    pub error: Option<String>,
 }
 
 /// Basic health check for clients and discovery.
+///
+/// The hub serves requests before any bridge is probed; bridges stay `pending` until their
+/// watcher has tried to connect.
 #[utoipa::path(
     get,
     path = "/health",
@@ -17,6 +46,226 @@ pub struct HealthResponse {
     )
 )]
 #[get("/health")]
-pub async fn health() -> impl Responder {
-    HttpResponse::Ok().json(HealthResponse { status: "ok" })
+pub async fn health(state: web::Data<AppState>) -> impl Responder {
+    let bridges = {
+        let bridges_state = state.providers.bridge.bridges.lock().unwrap();
+        let discovered = state.providers.bridge.discovered_bridges.lock().unwrap();
+        merge_bridges(&bridges_state.bridges, &discovered)
+    };
+    let health = state.providers.bridge.health.lock().unwrap();
+    let bridges = bridges
+        .into_iter()
+        .map(|bridge| {
+            let entry = health.get(&bridge.id);
+            BridgeHealthEntry {
+                state: health_state(entry),
+                error: entry.and_then(|entry| entry.last_error.clone()),
+                id: bridge.id,
+                name: bridge.name,
+            }
+        })
+        .collect();
+    HttpResponse::Ok().json(HealthResponse {
+        status: "ok",
+        bridges,
+    })
+}
+
+/// `pending` before the first connection attempt, then `online` or `offline`.
+pub(crate) fn health_state(entry: Option<&BridgeHealth>) -> &'static str {
+    match entry {
+        None => "pending",
+        Some(entry) if entry.online => "online",
+        Some(_) => "offline",
+    }
+}
+
+/// Live health of one bridge: reachability, versions, uptime, underruns and its last error.
+#[derive(Serialize, ToSchema)]
+pub struct BridgeHealthDetail {
+    /// Bridge id.
+    pub id: String,
+    /// Bridge display name.
+    pub name: String,
+    /// Device stream state: `pending`, `online` or `offline`.
+    pub state: &'static str,
+    /// Last device stream connection error while offline.
+    pub stream_error: Option<String>,
+    /// Whether the bridge answered `/health` for this request.
+    pub reachable: bool,
+    /// Why the bridge could not be reached.
+    pub error: Option<String>,
+    /// Time until the bridge answered (or failed), in milliseconds.
+    pub latency_ms: u64,
+    /// Bridge build version.
+    pub version: Option<String>,
+    /// Bridge HTTP API version; absent for bridges that predate reporting it.
+    pub protocol_version: Option<u32>,
+    /// Bridge HTTP API version this hub speaks.
+    pub hub_protocol_version: u32,
+    /// Time since the bridge's HTTP API started, in milliseconds.
+    pub uptime_ms: Option<u64>,
+    /// Frames of silence played for underruns in the current (or last) track.
+    pub underrun_frames: Option<u64>,
+    /// Underruns in the current (or last) track.
+    pub underrun_events: Option<u64>,
+    /// Newest error the bridge logged since it started.
+    pub last_error: Option<BridgeLogEntry>,
+    /// Why the bridge is degraded: `unreachable`, `stream_offline`, `protocol_mismatch`,
+    /// `protocol_unknown`, `underruns` or `recent_error`. Empty when healthy.
+    pub issues: Vec<&'static str>,
+}
+
+/// Query one bridge live and explain why it is degraded.
+///
+/// Unlike `/health`, which reports the hub's cached view, this asks the bridge for its
+/// `/health` and `/status` on every call.
+#[utoipa::path(
+    get,
+    path = "/bridges/{id}/health",
+    params(
+        ("id" = String, Path, description = "Bridge id")
+    ),
+    responses(
+        (status = 200, description = "Bridge health", body = BridgeHealthDetail),
+        (status = 404, description = "Unknown bridge")
+    )
+)]
+#[get("/bridges/{id}/health")]
+pub async fn bridge_health(state: web::Data<AppState>, id: web::Path<String>) -> impl Responder {
+    let bridge = {
+        let bridges_state = state.providers.bridge.bridges.lock().unwrap();
+        let discovered = state.providers.bridge.discovered_bridges.lock().unwrap();
+        merge_bridges(&bridges_state.bridges, &discovered)
+            .into_iter()
+            .find(|b| b.id == id.as_str())
+    };
+    let Some(bridge) = bridge else {
+        return HttpResponse::NotFound().body("unknown bridge");
+    };
+    let cached = state
+        .providers
+        .bridge
+        .health
+        .lock()
+        .unwrap()
+        .get(&bridge.id)
+        .cloned();
+
+    let client = BridgeTransportClient::new(bridge.http_addr);
+    let started = Instant::now();
+    let (answer, status) = futures_util::future::join(client.health(), client.status()).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let (report, error) = match answer {
+        Ok(report) => (Some(report), None),
+        Err(err) => (None, Some(format!("{err:#}"))),
+    };
+    let status = status.ok();
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    let state_label = health_state(cached.as_ref());
+    let underrun_events = status.as_ref().and_then(|s| s.underrun_events);
+    let issues = health_issues(report.as_ref(), state_label, underrun_events, now_ms);
+    let report = report.unwrap_or(HttpHealthResponse {
+        version: None,
+        protocol_version: None,
+        uptime_ms: None,
+        last_error: None,
+    });
+    HttpResponse::Ok().json(BridgeHealthDetail {
+        id: bridge.id,
+        name: bridge.name,
+        state: state_label,
+        stream_error: cached.and_then(|entry| entry.last_error),
+        reachable: error.is_none(),
+        error,
+        latency_ms,
+        version: report.version,
+        protocol_version: report.protocol_version,
+        hub_protocol_version: BRIDGE_PROTOCOL_VERSION,
+        uptime_ms: report.uptime_ms,
+        underrun_frames: status.as_ref().and_then(|s| s.underrun_frames),
+        underrun_events,
+        last_error: report.last_error,
+        issues,
+    })
+}
+
+/// Reasons a bridge is degraded, from its `/health` report (`None` when unreachable).
+fn health_issues(
+    report: Option<&HttpHealthResponse>,
+    state: &str,
+    underrun_events: Option<u64>,
+    now_ms: i64,
+) -> Vec<&'static str> {
+    let mut issues = Vec::new();
+    let Some(report) = report else {
+        issues.push("unreachable");
+        return issues;
+    };
+    if state == "offline" {
+        issues.push("stream_offline");
+    }
+    match report.protocol_version {
+        Some(version) if version != BRIDGE_PROTOCOL_VERSION => issues.push("protocol_mismatch"),
+        Some(_) => {}
+        None => issues.push("protocol_unknown"),
+    }
+    if underrun_events.is_some_and(|events| events > 0) {
+        issues.push("underruns");
+    }
+    if report
+        .last_error
+        .as_ref()
+        .is_some_and(|entry| now_ms - entry.timestamp_ms < RECENT_ERROR_WINDOW.as_millis() as i64)
+    {
+        issues.push("recent_error");
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(protocol_version: Option<u32>, error_at_ms: Option<i64>) -> HttpHealthResponse {
+        HttpHealthResponse {
+            version: Some("0.16.0".to_string()),
+            protocol_version,
+            uptime_ms: Some(1_000),
+            last_error: error_at_ms.map(|timestamp_ms| BridgeLogEntry {
+                seq: 1,
+                level: "ERROR".to_string(),
+                target: "bridge".to_string(),
+                message: "output stream error".to_string(),
+                timestamp_ms,
+            }),
+        }
+    }
+
+    #[test]
+    fn health_issues_explain_degraded_bridges() {
+        let now_ms = 10_000_000;
+        let healthy = report(Some(BRIDGE_PROTOCOL_VERSION), Some(0));
+        assert!(health_issues(Some(&healthy), "online", Some(0), now_ms).is_empty());
+        assert_eq!(health_issues(None, "online", None, now_ms), ["unreachable"]);
+        assert_eq!(
+            health_issues(Some(&report(None, None)), "offline", Some(2), now_ms),
+            ["stream_offline", "protocol_unknown", "underruns"]
+        );
+        assert_eq!(
+            health_issues(
+                Some(&report(
+                    Some(BRIDGE_PROTOCOL_VERSION + 1),
+                    Some(now_ms - 1_000)
+                )),
+                "online",
+                None,
+                now_ms
+            ),
+            ["protocol_mismatch", "recent_error"]
+        );
+    }
 }
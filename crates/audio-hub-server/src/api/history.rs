@@ -0,0 +1,60 @@
+//! Listening history API handlers.
+
+use actix_web::{HttpResponse, Responder, get, web};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::models::PlayHistoryResponse;
+use crate::state::AppState;
+
+#[derive(Deserialize, ToSchema)]
+/// Listening history query parameters.
+pub struct HistoryQuery {
+    /// Optional track id filter.
+    #[serde(default)]
+    pub track_id: Option<i64>,
+    /// Optional session id filter.
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Max returned items.
+    #[serde(default)]
+    pub limit: Option<i64>,
+    /// Row offset for pagination.
+    #[serde(default)]
+    pub offset: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/history",
+    params(
+        ("track_id" = Option<i64>, Query, description = "Track id"),
+        ("session_id" = Option<String>, Query, description = "Session id"),
+        ("limit" = Option<i64>, Query, description = "Max rows"),
+        ("offset" = Option<i64>, Query, description = "Offset rows")
+    ),
+    responses(
+        (status = 200, description = "Listening history, newest first", body = PlayHistoryResponse)
+    )
+)]
+#[get("/history")]
+/// List finished and skipped plays, newest first.
+pub async fn history_list(
+    state: web::Data<AppState>,
+    query: web::Query<HistoryQuery>,
+) -> impl Responder {
+    let limit = query.limit.unwrap_or(200).clamp(1, 1000);
+    let offset = query.offset.unwrap_or(0).max(0);
+    match state.metadata.db.list_play_history(
+        query.track_id,
+        query.session_id.as_deref(),
+        limit,
+        offset,
+    ) {
+        Ok(items) => HttpResponse::Ok().json(PlayHistoryResponse { items }),
+        Err(err) => {
+            tracing::warn!(error = %err, "history list failed");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Home Assistant `media_player` integration handlers.
+//!
+//! Each output is one media player. State and commands go through the session holding the
+//! output (see `output_remote`); an output nobody is using reports `idle` and rejects commands.
+
+use actix_web::{HttpResponse, Responder, get, post, web};
+
+use crate::models::{
+    HaCommand, HaCommandRequest, HaDiscoveryResponse, HaPlayerInfo, HaPlayerState, OutputInfo,
+};
+use crate::output_remote::{self, OutputSnapshot, RemoteCommand, RemoteError};
+use crate::state::AppState;
+
+/// Volume change for `volume_up`/`volume_down`, in percent.
+const VOLUME_STEP: u8 = 5;
+
+/// Features every output supports.
+const BASE_FEATURES: [&str; 5] = ["play", "pause", "stop", "next_track", "previous_track"];
+/// Features added for outputs with volume control.
+const VOLUME_FEATURES: [&str; 3] = ["volume_set", "volume_mute", "volume_step"];
+
+/// Whether an output's reported state means it can play.
+fn output_available(output: &OutputInfo) -> bool {
+    !output.state.eq_ignore_ascii_case("offline")
+}
+
+/// Look up an output by id in the current listing.
+async fn find_output(state: &web::Data<AppState>, output_id: &str) -> Option<OutputInfo> {
+    state
+        .output
+        .controller
+        .list_outputs(state)
+        .await
+        .outputs
+        .into_iter()
+        .find(|output| output.id == output_id)
+}
+
+/// Describe `output` as a media player.
+fn player_info(output: OutputInfo) -> HaPlayerInfo {
+    let mut supported_features: Vec<String> = BASE_FEATURES.iter().map(|f| f.to_string()).collect();
+    if output.capabilities.volume {
+        supported_features.extend(VOLUME_FEATURES.iter().map(|f| f.to_string()));
+    }
+    HaPlayerInfo {
+        available: output_available(&output),
+        unique_id: output.id,
+        name: output.name,
+        kind: output.kind,
+        room: output.room,
+        supported_features,
+    }
+}
+
+/// Build the media player state of an output.
+fn player_state(
+    output_id: &str,
+    available: bool,
+    snapshot: &OutputSnapshot,
+    public_base_url: &str,
+) -> HaPlayerState {
+    let status = snapshot.status.as_ref();
+    let volume = snapshot.volume();
+    let track_id = status.and_then(|s| s.now_playing_track_id);
+    HaPlayerState {
+        unique_id: output_id.to_string(),
+        state: if available {
+            snapshot.playback_state().to_string()
+        } else {
+            "off".to_string()
+        },
+        session_id: snapshot.session_id.clone(),
+        volume_level: volume.map(|v| f32::from(v.value) / 100.0),
+        is_volume_muted: volume.map(|v| v.muted),
+        media_content_id: track_id.map(|id| id.to_string()),
+        media_content_type: track_id.map(|_| "music".to_string()),
+        media_title: status.and_then(|s| s.title.clone()),
+        media_artist: status.and_then(|s| s.artist.clone()),
+        media_album_name: status.and_then(|s| s.album.clone()),
+        media_duration: status
+            .and_then(|s| s.duration_ms)
+            .map(|ms| ms as f64 / 1000.0),
+        media_position: status
+            .and_then(|s| s.elapsed_ms)
+            .map(|ms| ms as f64 / 1000.0),
+        media_image_url: track_id.map(|id| {
+            format!(
+                "{}/tracks/{id}/cover",
+                public_base_url.trim_end_matches('/')
+            )
+        }),
+    }
+}
+
+/// Map a service call onto a remote command; `None` when a required field is missing.
+fn remote_command(body: &HaCommandRequest, snapshot: &OutputSnapshot) -> Option<RemoteCommand> {
+    let current = || snapshot.volume().map(|v| v.value).unwrap_or(0);
+    Some(match body.command {
+        HaCommand::MediaPlay => RemoteCommand::Play,
+        HaCommand::MediaPause => RemoteCommand::Pause,
+        HaCommand::MediaPlayPause => RemoteCommand::Toggle,
+        HaCommand::MediaStop => RemoteCommand::Stop,
+        HaCommand::MediaNextTrack => RemoteCommand::Next,
+        HaCommand::MediaPreviousTrack => RemoteCommand::Previous,
+        HaCommand::VolumeSet => {
+            let level = body.volume_level?.clamp(0.0, 1.0);
+            RemoteCommand::Volume((level * 100.0).round() as u8)
+        }
+        HaCommand::VolumeMute => RemoteCommand::Mute(body.is_volume_muted?),
+        HaCommand::VolumeUp => {
+            RemoteCommand::Volume(current().saturating_add(VOLUME_STEP).min(100))
+        }
+        HaCommand::VolumeDown => RemoteCommand::Volume(current().saturating_sub(VOLUME_STEP)),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/integrations/homeassistant",
+    responses(
+        (status = 200, description = "Media players to create", body = HaDiscoveryResponse)
+    )
+)]
+#[get("/integrations/homeassistant")]
+/// List outputs as Home Assistant media players.
+pub async fn homeassistant_discovery(state: web::Data<AppState>) -> impl Responder {
+    let outputs = state.output.controller.list_outputs(&state).await.outputs;
+    HttpResponse::Ok().json(HaDiscoveryResponse {
+        name: "Audio Hub".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        players: outputs.into_iter().map(player_info).collect(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/integrations/homeassistant/players/{id}",
+    params(
+        ("id" = String, Path, description = "Output id")
+    ),
+    responses(
+        (status = 200, description = "Media player state", body = HaPlayerState),
+        (status = 404, description = "Output not found")
+    )
+)]
+#[get("/integrations/homeassistant/players/{id}")]
+/// Return the media player state of an output.
+pub async fn homeassistant_player_state(
+    state: web::Data<AppState>,
+    id: web::Path<String>,
+) -> impl Responder {
+    let output_id = id.into_inner();
+    let Some(output) = find_output(&state, &output_id).await else {
+        return HttpResponse::NotFound().body("output not found");
+    };
+    let snapshot = output_remote::snapshot(&state, &output_id).await;
+    HttpResponse::Ok().json(player_state(
+        &output_id,
+        output_available(&output),
+        &snapshot,
+        &state.providers.bridge.public_base_url,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/integrations/homeassistant/players/{id}/command",
+    params(
+        ("id" = String, Path, description = "Output id")
+    ),
+    request_body = HaCommandRequest,
+    responses(
+        (status = 200, description = "Command applied; new state", body = HaPlayerState),
+        (status = 204, description = "No next/previous track"),
+        (status = 400, description = "Missing volume_level or is_volume_muted"),
+        (status = 404, description = "Output not found"),
+        (status = 409, description = "No session is using the output, or skip on a browser session"),
+        (status = 503, description = "Output is unavailable")
+    )
+)]
+#[post("/integrations/homeassistant/players/{id}/command")]
+/// Run a media player service call on an output.
+pub async fn homeassistant_player_command(
+    state: web::Data<AppState>,
+    id: web::Path<String>,
+    body: web::Json<HaCommandRequest>,
+) -> impl Responder {
+    let output_id = id.into_inner();
+    let Some(output) = find_output(&state, &output_id).await else {
+        return HttpResponse::NotFound().body("output not found");
+    };
+    let snapshot = output_remote::snapshot(&state, &output_id).await;
+    let Some(command) = remote_command(&body, &snapshot) else {
+        return HttpResponse::BadRequest()
+            .body("volume_set needs volume_level; volume_mute needs is_volume_muted");
+    };
+    match output_remote::apply(&state, &output_id, command).await {
+        Ok(_) => {
+            let snapshot = output_remote::snapshot(&state, &output_id).await;
+            HttpResponse::Ok().json(player_state(
+                &output_id,
+                output_available(&output),
+                &snapshot,
+                &state.providers.bridge.public_base_url,
+            ))
+        }
+        Err(RemoteError::NotHeld) => HttpResponse::Conflict()
+            .body("no session is using this output; start playback from a client first"),
+        Err(RemoteError::LocalSession) => HttpResponse::Conflict()
+            .body("the session on this output plays in its client; skip from there"),
+        Err(RemoteError::NothingToPlay) => HttpResponse::NoContent().finish(),
+        Err(RemoteError::Playback(err)) => err.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{SessionVolumeResponse, StatusResponse};
+
+    fn snapshot(value: u8) -> OutputSnapshot {
+        OutputSnapshot {
+            session_id: Some("s1".to_string()),
+            status: Some(StatusResponse {
+                now_playing_track_id: Some(12),
+                title: Some("Song".to_string()),
+                elapsed_ms: Some(1_500),
+                duration_ms: Some(200_000),
+                ..Default::default()
+            }),
+            volume: Some(SessionVolumeResponse {
+                value,
+                muted: false,
+                source: "bridge".to_string(),
+                available: true,
+            }),
+        }
+    }
+
+    #[test]
+    fn player_state_uses_home_assistant_units() {
+        let state = player_state("bridge:den:dac", true, &snapshot(40), "http://hub:8080/");
+        assert_eq!(state.state, "playing");
+        assert_eq!(state.volume_level, Some(0.4));
+        assert_eq!(state.media_position, Some(1.5));
+        assert_eq!(state.media_duration, Some(200.0));
+        assert_eq!(state.media_content_id.as_deref(), Some("12"));
+        assert_eq!(
+            state.media_image_url.as_deref(),
+            Some("http://hub:8080/tracks/12/cover")
+        );
+        let off = player_state("bridge:den:dac", false, &snapshot(40), "http://hub:8080");
+        assert_eq!(off.state, "off");
+    }
+
+    #[test]
+    fn remote_command_maps_service_calls() {
+        let call = |command, volume_level, is_volume_muted| HaCommandRequest {
+            command,
+            volume_level,
+            is_volume_muted,
+        };
+        let snap = snapshot(98);
+        assert_eq!(
+            remote_command(&call(HaCommand::VolumeSet, Some(0.255), None), &snap),
+            Some(RemoteCommand::Volume(26))
+        );
+        assert_eq!(
+            remote_command(&call(HaCommand::VolumeUp, None, None), &snap),
+            Some(RemoteCommand::Volume(100))
+        );
+        assert_eq!(
+            remote_command(&call(HaCommand::VolumeDown, None, None), &snap),
+            Some(RemoteCommand::Volume(93))
+        );
+        assert_eq!(
+            remote_command(&call(HaCommand::VolumeMute, None, Some(true)), &snap),
+            Some(RemoteCommand::Mute(true))
+        );
+        assert_eq!(
+            remote_command(&call(HaCommand::VolumeSet, None, None), &snap),
+            None
+        );
+        assert_eq!(
+            remote_command(&call(HaCommand::MediaPlayPause, None, None), &snap),
+            Some(RemoteCommand::Toggle)
+        );
+    }
+}
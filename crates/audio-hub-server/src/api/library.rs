@@ -12,8 +12,12 @@ use tokio::process::Command;
 use tokio_util::io::ReaderStream;
 use utoipa::ToSchema;
 
+use crate::hls;
+use crate::library_scan::spawn_library_scan;
 use crate::models::LibraryResponse;
 use crate::state::AppState;
+use crate::stream_capture;
+use crate::stream_limits;
 
 /// Query parameters for library listing.
 #[derive(Deserialize, ToSchema)]
@@ -132,6 +136,14 @@ async fn stream_file(state: &web::Data<AppState>, req: HttpRequest, path: PathBu
         Ok(dir) => dir,
         Err(err) => return err.into_response(),
     };
+    // A cue track streams its whole audio file; players apply the track range.
+    let path = match crate::cue_tracks::source_file(&path) {
+        Ok(path) => path,
+        Err(err) => {
+            tracing::warn!(path = %path.display(), error = %err, reason = "cue_resolve_failed", "stream file open failed");
+            return HttpResponse::NotFound().finish();
+        }
+    };
 
     let mut file = match tokio::fs::File::open(&path).await {
         Ok(f) => f,
@@ -176,9 +188,6 @@ async fn stream_file(state: &web::Data<AppState>, req: HttpRequest, path: PathBu
         }
     }
 
-    let stream = ReaderStream::new(file.take(len));
-    let body = SizedStream::new(len, stream);
-
     let content_type = match path
         .extension()
         .and_then(|ext| ext.to_str())
@@ -189,23 +198,38 @@ async fn stream_file(state: &web::Data<AppState>, req: HttpRequest, path: PathBu
         "flac" => "audio/flac",
         "mp3" => "audio/mpeg",
         "aac" => "audio/aac",
-        "m4a" => "audio/mp4",
-        "ogg" => "audio/ogg",
-        "opus" => "audio/ogg",
+        "m4a" | "alac" => "audio/mp4",
+        "ogg" | "oga" | "opus" => "audio/ogg",
         "wav" => "audio/wav",
+        "dsf" => "audio/x-dsf",
+        "dff" => "audio/x-dff",
         _ => "application/octet-stream",
     };
 
-    let mut resp = HttpResponse::build(status_code);
-    resp.insert_header((header::ACCEPT_RANGES, "bytes"));
-    resp.insert_header((header::CONTENT_TYPE, content_type));
+    let mut headers = vec![
+        (header::ACCEPT_RANGES, "bytes".to_string()),
+        (header::CONTENT_TYPE, content_type.to_string()),
+    ];
     if let Some((start, end)) = range {
-        resp.insert_header((
+        headers.push((
             header::CONTENT_RANGE,
             format!("bytes {start}-{end}/{total_len}"),
         ));
     }
-    resp.insert_header((header::CONTENT_LENGTH, len.to_string()));
+    headers.push((header::CONTENT_LENGTH, len.to_string()));
+
+    let stream = ReaderStream::new(file.take(len));
+    let peer = req.peer_addr().map(|addr| addr.ip());
+    let stream = stream_limits::throttle(state, peer, stream);
+    let body = SizedStream::new(
+        len,
+        stream_capture::tee(state, &req, status_code, &headers, stream),
+    );
+
+    let mut resp = HttpResponse::build(status_code);
+    for header in headers {
+        resp.insert_header(header);
+    }
     resp.body(body)
 }
 
@@ -228,6 +252,7 @@ async fn stream_file(state: &web::Data<AppState>, req: HttpRequest, path: PathBu
 /// Stream a transcoded audio track by track id (requires ffmpeg in PATH).
 pub async fn transcode_track_id(
     state: web::Data<AppState>,
+    req: HttpRequest,
     id: web::Path<i64>,
     query: web::Query<TranscodeByIdQuery>,
 ) -> impl Responder {
@@ -237,20 +262,37 @@ pub async fn transcode_track_id(
     };
     let format = query.format.as_deref().unwrap_or("mp3");
     let bitrate_kbps = query.bitrate_kbps;
-    transcode_file(path, format, bitrate_kbps).await
+    transcode_file(&state, &req, path, format, bitrate_kbps).await
 }
 
-async fn transcode_file(path: PathBuf, format: &str, bitrate_kbps: Option<u32>) -> HttpResponse {
+async fn transcode_file(
+    state: &AppState,
+    req: &HttpRequest,
+    path: PathBuf,
+    format: &str,
+    bitrate_kbps: Option<u32>,
+) -> HttpResponse {
+    let (path, range) = match crate::cue_tracks::resolve(&path) {
+        Ok(Some(src)) => (src.audio_path, src.range),
+        Ok(None) => (path, Default::default()),
+        Err(err) => {
+            tracing::warn!(path = %path.display(), error = %err, "cue track resolve failed");
+            return HttpResponse::NotFound().finish();
+        }
+    };
     let mut cmd = Command::new("ffmpeg");
     cmd.arg("-hide_banner")
         .arg("-loglevel")
         .arg("error")
-        .arg("-nostdin")
-        .arg("-i")
-        .arg(&path)
-        .arg("-vn")
-        .arg("-sn")
-        .arg("-dn");
+        .arg("-nostdin");
+    // Cut cue tracks out of their audio file.
+    if range.start_ms > 0 {
+        cmd.arg("-ss").arg(format!("{}ms", range.start_ms));
+    }
+    if let Some(end_ms) = range.end_ms {
+        cmd.arg("-to").arg(format!("{end_ms}ms"));
+    }
+    cmd.arg("-i").arg(&path).arg("-vn").arg("-sn").arg("-dn");
 
     let content_type = match format.to_ascii_lowercase().as_str() {
         "mp3" => {
@@ -315,34 +357,183 @@ async fn transcode_file(path: PathBuf, format: &str, bitrate_kbps: Option<u32>)
         let _ = child.wait().await;
     });
 
-    let stream = ReaderStream::new(stdout);
+    let peer = req.peer_addr().map(|addr| addr.ip());
+    let stream = stream_limits::throttle(state, peer, ReaderStream::new(stdout));
+    let headers = [(header::CONTENT_TYPE, content_type.to_string())];
+    let stream = stream_capture::tee(state, req, StatusCode::OK, &headers, stream);
     HttpResponse::Ok()
-        .insert_header((header::CONTENT_TYPE, content_type))
+        .insert_header(headers[0].clone())
         .streaming(stream)
 }
 
+#[derive(Clone, Debug, Deserialize, ToSchema)]
+/// Query parameters for HLS streams; segment URIs in the playlist repeat them.
+pub struct HlsQuery {
+    /// Segment codec: `aac` (default, MPEG-TS segments) or `opus` (fMP4 segments).
+    pub format: Option<String>,
+    /// Audio bitrate in kbps (32-320, snapped to 64/96/128/160/256/320; defaults to 160 for AAC
+    /// and 128 for Opus).
+    pub bitrate_kbps: Option<u32>,
+}
+
+/// Accepted HLS bitrates (kbps).
+const HLS_BITRATE_RANGE_KBPS: std::ops::RangeInclusive<u32> = 32..=320;
+
+#[utoipa::path(
+    get,
+    path = "/stream/track/{id}/hls/playlist.m3u8",
+    params(
+        ("id" = i64, Path, description = "Track id"),
+        ("format" = Option<String>, Query, description = "Segment codec: aac or opus"),
+        ("bitrate_kbps" = Option<u32>, Query, description = "Optional bitrate in kbps")
+    ),
+    responses(
+        (status = 200, description = "HLS media playlist"),
+        (status = 400, description = "Invalid request"),
+        (status = 404, description = "Track not found"),
+        (status = 500, description = "Packaging failed"),
+        (status = 503, description = "Too many tracks are being packaged"),
+        (status = 504, description = "No segment was ready in time")
+    )
+)]
+#[get("/stream/track/{id}/hls/playlist.m3u8")]
+/// HLS playlist for a track, packaged on first request (requires ffmpeg in PATH).
+///
+/// An alternative to progressive streaming for flaky mobile connections: players fetch short
+/// segments and retry the ones that fail.
+pub async fn hls_playlist(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    id: web::Path<i64>,
+    query: web::Query<HlsQuery>,
+) -> impl Responder {
+    let job = match hls_job(&state, id.into_inner(), &query) {
+        Ok(job) => job,
+        Err(resp) => return resp,
+    };
+    let Some(path) = hls::wait_for_file(&job, hls::PLAYLIST_NAME).await else {
+        return if job.is_running() {
+            HttpResponse::GatewayTimeout().body("hls packaging is not ready")
+        } else {
+            HttpResponse::InternalServerError().body("hls packaging failed")
+        };
+    };
+    let playlist = match tokio::fs::read_to_string(&path).await {
+        Ok(playlist) => playlist,
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+    HttpResponse::Ok()
+        .insert_header((header::CONTENT_TYPE, hls::content_type(hls::PLAYLIST_NAME)))
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .body(hls::rewrite_playlist(&playlist, req.query_string()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/stream/track/{id}/hls/{file}",
+    params(
+        ("id" = i64, Path, description = "Track id"),
+        ("file" = String, Path, description = "Segment or init file named in the playlist"),
+        ("format" = Option<String>, Query, description = "Segment codec: aac or opus"),
+        ("bitrate_kbps" = Option<u32>, Query, description = "Optional bitrate in kbps")
+    ),
+    responses(
+        (status = 200, description = "HLS segment"),
+        (status = 400, description = "Invalid request"),
+        (status = 404, description = "Segment not found"),
+        (status = 503, description = "Too many tracks are being packaged")
+    )
+)]
+#[get("/stream/track/{id}/hls/{file}")]
+/// One segment (or the fMP4 init segment) of a track's HLS playlist.
+pub async fn hls_segment(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<(i64, String)>,
+    query: web::Query<HlsQuery>,
+) -> impl Responder {
+    let (track_id, file) = path.into_inner();
+    if file == hls::PLAYLIST_NAME || !hls::is_packaged_file(&file) {
+        return HttpResponse::NotFound().finish();
+    }
+    // Packaging restarts if the track was evicted while a player still had its playlist.
+    let job = match hls_job(&state, track_id, &query) {
+        Ok(job) => job,
+        Err(resp) => return resp,
+    };
+    let Some(path) = hls::wait_for_file(&job, &file).await else {
+        return HttpResponse::NotFound().finish();
+    };
+    let Ok(file_handle) = tokio::fs::File::open(&path).await else {
+        return HttpResponse::NotFound().finish();
+    };
+    let Ok(len) = file_handle.metadata().await.map(|meta| meta.len()) else {
+        return HttpResponse::NotFound().finish();
+    };
+    let peer = req.peer_addr().map(|addr| addr.ip());
+    let stream = stream_limits::throttle(&state, peer, ReaderStream::new(file_handle));
+    HttpResponse::Ok()
+        .insert_header((header::CONTENT_TYPE, hls::content_type(&file)))
+        .insert_header((header::CACHE_CONTROL, "max-age=3600"))
+        .body(SizedStream::new(len, stream))
+}
+
+/// Packaging job for the track and variant a HLS request names.
+fn hls_job(
+    state: &web::Data<AppState>,
+    track_id: i64,
+    query: &HlsQuery,
+) -> Result<hls::HlsJob, HttpResponse> {
+    let format = match query.format.as_deref() {
+        None => hls::HlsFormat::Aac,
+        Some(value) => hls::HlsFormat::parse(value)
+            .ok_or_else(|| HttpResponse::BadRequest().body("invalid format (use aac or opus)"))?,
+    };
+    let bitrate_kbps = query
+        .bitrate_kbps
+        .unwrap_or_else(|| format.default_bitrate_kbps());
+    if !HLS_BITRATE_RANGE_KBPS.contains(&bitrate_kbps) {
+        return Err(HttpResponse::BadRequest().body(format!(
+            "bitrate_kbps must be between {} and {}",
+            HLS_BITRATE_RANGE_KBPS.start(),
+            HLS_BITRATE_RANGE_KBPS.end()
+        )));
+    }
+    let path = path_for_track_id(state, track_id)?;
+    let (path, range) = match crate::cue_tracks::resolve(&path) {
+        Ok(Some(src)) => (src.audio_path, src.range),
+        Ok(None) => (path, Default::default()),
+        Err(err) => {
+            tracing::warn!(path = %path.display(), error = %err, "cue track resolve failed");
+            return Err(HttpResponse::NotFound().finish());
+        }
+    };
+    let variant = hls::HlsVariant::new(track_id, format, bitrate_kbps);
+    hls::ensure_job(variant, &path, range).map_err(|err| match err {
+        hls::HlsError::Busy => HttpResponse::ServiceUnavailable()
+            .insert_header((header::RETRY_AFTER, "10"))
+            .body(err.to_string()),
+        hls::HlsError::Start(_) => HttpResponse::InternalServerError().body(err.to_string()),
+    })
+}
+
 #[utoipa::path(
     post,
     path = "/library/rescan",
     responses(
-        (status = 200, description = "Rescan started"),
-        (status = 500, description = "Rescan failed")
+        (status = 202, description = "Rescan started"),
+        (status = 409, description = "A library scan is already running")
     )
 )]
 #[post("/library/rescan")]
-/// Trigger a full library rescan.
+/// Start a full library rescan in the background; follow it on `/library/scan/progress`.
 pub async fn rescan_library(state: web::Data<AppState>) -> impl Responder {
     let root = state.library.read().unwrap().root().to_path_buf();
-    let metadata_service = state.metadata_service();
     tracing::info!(root = %root.display(), "rescan requested");
-    match metadata_service.rescan_library(true) {
-        Ok(new_index) => {
-            *state.library.write().unwrap() = new_index;
-            state.events.library_changed();
-            state.metadata.wake.notify();
-            HttpResponse::Ok().finish()
-        }
-        Err(e) => HttpResponse::InternalServerError().body(format!("scan failed: {e:#}")),
+    if spawn_library_scan(state, true) {
+        HttpResponse::Accepted().finish()
+    } else {
+        HttpResponse::Conflict().body("library scan already running")
     }
 }
 
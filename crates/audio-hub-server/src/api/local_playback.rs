@@ -106,11 +106,7 @@ pub async fn local_playback_play(
 
     let conn = req.connection_info();
     let base_url = format!("{}://{}", conn.scheme(), conn.host());
-    let url = format!(
-        "{}/stream/track/{}",
-        base_url.trim_end_matches('/'),
-        payload.track_id
-    );
+    let url = crate::stream_url::track_stream_url(&base_url, payload.track_id);
 
     HttpResponse::Ok().json(LocalPlaybackPlayResponse {
         url,
@@ -1,7 +1,10 @@
 //! Logs-related API handlers.
 
-use actix_web::{HttpResponse, Responder, post, web};
-use serde::Serialize;
+use std::collections::BTreeMap;
+
+use actix_web::{HttpResponse, Responder, get, post, web};
+use audio_player::log_filter::LogFilterControl;
+use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
 use crate::state::AppState;
@@ -29,3 +32,72 @@ pub async fn logs_clear(state: web::Data<AppState>) -> impl Responder {
         .unwrap_or(0);
     HttpResponse::Ok().json(LogsClearResponse { cleared_at_ms })
 }
+
+#[derive(Deserialize, ToSchema)]
+pub struct LogLevelRequest {
+    /// Full `EnvFilter` directive string replacing the current one.
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// Global level (`off`, `error`, `warn`, `info`, `debug`, `trace`).
+    #[serde(default)]
+    pub level: Option<String>,
+    /// Per-target levels; `null` removes a target override.
+    #[serde(default)]
+    pub targets: BTreeMap<String, Option<String>>,
+    /// Restore the filter the server started with (wins over other fields).
+    #[serde(default)]
+    pub reset: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LogLevelResponse {
+    /// Active tracing filter directives.
+    pub filter: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/log-level",
+    responses(
+        (status = 200, description = "Active log filter", body = LogLevelResponse)
+    )
+)]
+#[get("/admin/log-level")]
+/// Return the active tracing filter directives.
+pub async fn log_level_get(log_filter: web::Data<LogFilterControl>) -> impl Responder {
+    HttpResponse::Ok().json(LogLevelResponse {
+        filter: log_filter.current(),
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/log-level",
+    request_body = LogLevelRequest,
+    responses(
+        (status = 200, description = "Log filter updated", body = LogLevelResponse),
+        (status = 400, description = "Invalid level or filter")
+    )
+)]
+#[post("/admin/log-level")]
+/// Adjust the tracing filter at runtime (global and per-target).
+pub async fn log_level_set(
+    log_filter: web::Data<LogFilterControl>,
+    body: web::Json<LogLevelRequest>,
+) -> impl Responder {
+    let req = body.into_inner();
+    let result = if req.reset {
+        log_filter.reset()
+    } else if let Some(filter) = req.filter.as_deref() {
+        log_filter.set(filter)
+    } else {
+        log_filter.update(req.level.as_deref(), &req.targets)
+    };
+    match result {
+        Ok(filter) => {
+            tracing::info!(filter = %filter, "log filter updated");
+            HttpResponse::Ok().json(LogLevelResponse { filter })
+        }
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
@@ -1,29 +1,36 @@
 //! Metadata-related API handlers.
 
 use actix_files::NamedFile;
-use actix_web::{HttpRequest, HttpResponse, Responder, get, post, web};
+use actix_web::{HttpRequest, HttpResponse, Responder, get, post, put, web};
+use futures_util::StreamExt;
 use serde::Deserialize;
 use std::time::Instant;
 use utoipa::{IntoParams, ToSchema};
 
+use crate::clock::now_ms;
+use crate::lyrics::{parse_lrc, plain_from_lines};
 use crate::media_assets::MediaAssetStore;
-use crate::metadata_db::{MediaAssetRecord, TextEntry};
+use crate::metadata_db::{
+    AlbumFilter, AlbumSort, ListCursor, MediaAssetRecord, PageStart, Rating, SearchKind,
+    SortDirection, TextEntry, TrackFilter, TrackLyrics, TrackSort,
+};
 use crate::models::{
     AlbumImageClearRequest, AlbumImageSetRequest, AlbumListResponse, AlbumMetadataResponse,
     AlbumMetadataUpdateRequest, AlbumMetadataUpdateResponse, AlbumProfileResponse,
     AlbumProfileUpdateRequest, ArtistImageClearRequest, ArtistImageSetRequest, ArtistListResponse,
-    ArtistProfileResponse, ArtistProfileUpdateRequest, MediaAssetInfo,
+    ArtistProfileResponse, ArtistProfileUpdateRequest, ComposerListResponse, GenreListResponse,
+    LossyReportResponse, MediaAssetInfo, MetadataUpdatePreviewResponse,
     MusicBrainzMatchApplyRequest, MusicBrainzMatchCandidate, MusicBrainzMatchKind,
-    MusicBrainzMatchSearchRequest, MusicBrainzMatchSearchResponse, TextMetadata,
-    TrackAnalysisHeuristics, TrackAnalysisRequest, TrackAnalysisResponse, TrackListResponse,
-    TrackMetadataFieldsResponse, TrackMetadataResponse, TrackMetadataUpdateRequest,
-    TrackResolveResponse,
+    MusicBrainzMatchSearchRequest, MusicBrainzMatchSearchResponse, RatingUpdateRequest,
+    SearchResponse, TextMetadata, TrackAnalysisHeuristics, TrackAnalysisRequest,
+    TrackAnalysisResponse, TrackListResponse, TrackLyricsResponse, TrackMetadataFieldsResponse,
+    TrackMetadataResponse, TrackMetadataUpdateRequest, TrackResolveResponse, TrackTagPreview,
 };
 use crate::musicbrainz::MusicBrainzMatch;
 use crate::state::AppState;
 use crate::tag_writer::{
-    TrackTagUpdate, read_editable_vorbis_tags, supported_track_fields, tag_type_label,
-    write_track_tags,
+    TrackTagUpdate, is_lockable_field, preview_track_tags, read_editable_vorbis_tags,
+    supported_track_fields, tag_type_label, write_track_tags,
 };
 use crate::track_analysis::{AnalysisOptions, analyze_track};
 use base64::{Engine as _, engine::general_purpose};
@@ -40,6 +47,23 @@ pub struct ListQuery {
     /// Row offset for pagination.
     #[serde(default)]
     pub offset: Option<i64>,
+    /// `next_cursor` of the previous page; takes precedence over `offset`.
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+/// Pagination-only query parameters.
+pub struct PageQuery {
+    /// Max returned items.
+    #[serde(default)]
+    pub limit: Option<i64>,
+    /// Row offset for pagination.
+    #[serde(default)]
+    pub offset: Option<i64>,
+    /// `next_cursor` of the previous page; takes precedence over `offset`.
+    #[serde(default)]
+    pub cursor: Option<String>,
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -48,9 +72,44 @@ pub struct AlbumListQuery {
     /// Optional artist id filter.
     #[serde(default)]
     pub artist_id: Option<i64>,
+    /// Optional genre id filter.
+    #[serde(default)]
+    pub genre_id: Option<i64>,
+    /// Optional composer name filter (case-insensitive).
+    #[serde(default)]
+    pub composer: Option<String>,
     /// Optional case-insensitive search filter.
     #[serde(default)]
     pub search: Option<String>,
+    /// Only favorite (`true`) or non-favorite (`false`) albums.
+    #[serde(default)]
+    pub favorite: Option<bool>,
+    /// Optional minimum rating (1-5).
+    #[serde(default)]
+    pub min_rating: Option<u8>,
+    /// Max returned items.
+    #[serde(default)]
+    pub limit: Option<i64>,
+    /// Row offset for pagination.
+    #[serde(default)]
+    pub offset: Option<i64>,
+    /// `next_cursor` of the previous page; takes precedence over `offset`.
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Result order.
+    #[serde(default)]
+    pub sort: Option<AlbumSort>,
+    /// Result direction; defaults per sort.
+    #[serde(default)]
+    pub direction: Option<SortDirection>,
+}
+
+#[derive(Deserialize, ToSchema)]
+/// Lossy-source report query parameters.
+pub struct LossyReportQuery {
+    /// Minimum confidence (0..1) to include (default: 0.5).
+    #[serde(default)]
+    pub min_confidence: Option<f32>,
     /// Max returned items.
     #[serde(default)]
     pub limit: Option<i64>,
@@ -59,6 +118,19 @@ pub struct AlbumListQuery {
     pub offset: Option<i64>,
 }
 
+#[derive(Deserialize, ToSchema)]
+/// Library search query parameters.
+pub struct SearchQuery {
+    /// Search words; each must prefix-match the item or its artist/album.
+    pub q: String,
+    /// Only return items of this kind.
+    #[serde(default)]
+    pub kind: Option<SearchKind>,
+    /// Max returned items (default: 20).
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
+
 #[derive(Deserialize, ToSchema)]
 /// Track listing query parameters.
 pub struct TrackListQuery {
@@ -68,15 +140,45 @@ pub struct TrackListQuery {
     /// Optional artist id filter.
     #[serde(default)]
     pub artist_id: Option<i64>,
+    /// Optional genre id filter.
+    #[serde(default)]
+    pub genre_id: Option<i64>,
+    /// Optional composer name filter (case-insensitive).
+    #[serde(default)]
+    pub composer: Option<String>,
     /// Optional case-insensitive search filter.
     #[serde(default)]
     pub search: Option<String>,
+    /// Optional minimum analyzed tempo (BPM).
+    #[serde(default)]
+    pub min_bpm: Option<f64>,
+    /// Optional maximum analyzed tempo (BPM).
+    #[serde(default)]
+    pub max_bpm: Option<f64>,
+    /// Optional analyzed key filter, e.g. `A minor` (case-insensitive).
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Only favorite (`true`) or non-favorite (`false`) tracks.
+    #[serde(default)]
+    pub favorite: Option<bool>,
+    /// Optional minimum rating (1-5).
+    #[serde(default)]
+    pub min_rating: Option<u8>,
     /// Max returned items.
     #[serde(default)]
     pub limit: Option<i64>,
     /// Row offset for pagination.
     #[serde(default)]
     pub offset: Option<i64>,
+    /// `next_cursor` of the previous page; takes precedence over `offset`.
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Result order.
+    #[serde(default)]
+    pub sort: Option<TrackSort>,
+    /// Result direction; defaults per sort.
+    #[serde(default)]
+    pub direction: Option<SortDirection>,
 }
 
 #[derive(Clone, Debug, Deserialize, IntoParams, ToSchema)]
@@ -150,14 +252,6 @@ fn map_media_asset_info(entry: MediaAssetRecord) -> MediaAssetInfo {
     }
 }
 
-/// Return current UNIX time in milliseconds for metadata write timestamps.
-fn now_ms() -> i64 {
-    std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map(|d| d.as_millis() as i64)
-        .unwrap_or(0)
-}
-
 #[utoipa::path(
     get,
     path = "/tracks/resolve",
@@ -229,6 +323,12 @@ pub async fn tracks_metadata(
                     }
                 }
             }
+            let mut locked_fields: Vec<String> =
+                match state.metadata.db.track_field_locks(query.track_id) {
+                    Ok(fields) => fields.into_iter().collect(),
+                    Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+                };
+            locked_fields.sort();
             HttpResponse::Ok().json(TrackMetadataResponse {
                 track_id: query.track_id,
                 title: record.title,
@@ -239,6 +339,7 @@ pub async fn tracks_metadata(
                 track_number: record.track_number,
                 disc_number: record.disc_number,
                 extra_tags,
+                locked_fields,
             })
         }
         Ok(None) => {
@@ -300,7 +401,7 @@ pub async fn tracks_metadata_fields(
     path = "/tracks/metadata/update",
     request_body = TrackMetadataUpdateRequest,
     responses(
-        (status = 200, description = "Track metadata updated"),
+        (status = 200, description = "Track metadata updated (dry runs return the planned changes)", body = MetadataUpdatePreviewResponse),
         (status = 400, description = "Bad request"),
         (status = 404, description = "Track not found")
     )
@@ -391,47 +492,65 @@ pub async fn tracks_metadata_update(
         .filter(|key| !key.is_empty())
         .collect::<std::collections::HashSet<_>>();
 
-    if title.is_none()
-        && artist.is_none()
-        && album.is_none()
-        && album_artist.is_none()
-        && year.is_none()
-        && track_number.is_none()
-        && disc_number.is_none()
-        && extra_tags.is_empty()
-        && !clear_title
-        && !clear_artist
-        && !clear_album
-        && !clear_album_artist
-        && !clear_year
-        && !clear_track_number
-        && !clear_disc_number
-        && clear_extra_tags.is_empty()
+    let lock_fields = normalize_lock_fields(request.lock_fields);
+    let unlock_fields = normalize_lock_fields(request.unlock_fields);
+    if let Some(field) = lock_fields
+        .iter()
+        .chain(&unlock_fields)
+        .find(|field| !is_lockable_field(field))
     {
+        return HttpResponse::BadRequest().body(format!("field cannot be locked: {field}"));
+    }
+
+    let update = TrackTagUpdate {
+        title,
+        artist,
+        album,
+        album_artist,
+        year,
+        track_number,
+        disc_number,
+        extra_tags: Some(&extra_tags),
+        clear_title,
+        clear_artist,
+        clear_album,
+        clear_album_artist,
+        clear_year,
+        clear_track_number,
+        clear_disc_number,
+        clear_extra_tags: Some(&clear_extra_tags),
+    };
+    if update.is_empty() && lock_fields.is_empty() && unlock_fields.is_empty() {
         return HttpResponse::BadRequest().body("no metadata fields provided");
     }
 
-    if let Err(err) = write_track_tags(
-        &full_path,
-        TrackTagUpdate {
-            title,
-            artist,
-            album,
-            album_artist,
-            year,
-            track_number,
-            disc_number,
-            extra_tags: Some(&extra_tags),
-            clear_title,
-            clear_artist,
-            clear_album,
-            clear_album_artist,
-            clear_year,
-            clear_track_number,
-            clear_disc_number,
-            clear_extra_tags: Some(&clear_extra_tags),
-        },
-    ) {
+    if request.dry_run.unwrap_or(false) {
+        return match preview_track_tags(&full_path, &update) {
+            Ok(changes) => HttpResponse::Ok().json(MetadataUpdatePreviewResponse {
+                tracks: vec![TrackTagPreview {
+                    track_id: request.track_id,
+                    path,
+                    changes,
+                    skipped_locked: Vec::new(),
+                }],
+            }),
+            Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+        };
+    }
+
+    if let Err(err) =
+        state
+            .metadata
+            .db
+            .update_track_field_locks(request.track_id, &lock_fields, &unlock_fields)
+    {
+        return HttpResponse::InternalServerError().body(err.to_string());
+    }
+    if update.is_empty() {
+        return HttpResponse::Ok().finish();
+    }
+
+    if let Err(err) = write_track_tags(&full_path, update) {
         tracing::warn!(error = %err, path = %path, "track metadata update failed");
         return HttpResponse::InternalServerError().body(err.to_string());
     }
@@ -443,6 +562,19 @@ pub async fn tracks_metadata_update(
     HttpResponse::Ok().finish()
 }
 
+/// Trim, lowercase and dedupe field names from a lock/unlock request.
+fn normalize_lock_fields(fields: Option<Vec<String>>) -> Vec<String> {
+    let mut fields: Vec<String> = fields
+        .unwrap_or_default()
+        .into_iter()
+        .map(|field| field.trim().to_ascii_lowercase())
+        .filter(|field| !field.is_empty())
+        .collect();
+    fields.sort();
+    fields.dedup();
+    fields
+}
+
 #[utoipa::path(
     post,
     path = "/tracks/analysis",
@@ -525,11 +657,51 @@ pub async fn tracks_analysis(
             ultrasonic_ratio: analysis.heuristics.ultrasonic_ratio,
             upper_audible_ratio: analysis.heuristics.upper_audible_ratio,
             dynamic_range_db: analysis.heuristics.dynamic_range_db,
+            cutoff_hz: analysis.heuristics.cutoff_hz,
+            cutoff_drop_db: analysis.heuristics.cutoff_drop_db,
+            lossy_confidence: analysis.heuristics.lossy_confidence,
             notes: analysis.heuristics.notes,
         },
     })
 }
 
+#[utoipa::path(
+    get,
+    path = "/tracks/lossy-report",
+    params(
+        ("min_confidence" = Option<f32>, Query, description = "Minimum confidence (0..1)"),
+        ("limit" = Option<i64>, Query, description = "Max rows"),
+        ("offset" = Option<i64>, Query, description = "Offset rows")
+    ),
+    responses(
+        (status = 200, description = "Tracks likely transcoded from lossy sources", body = LossyReportResponse)
+    )
+)]
+#[get("/tracks/lossy-report")]
+/// List FLAC tracks the background lossy-source check flagged.
+pub async fn tracks_lossy_report(
+    state: web::Data<AppState>,
+    query: web::Query<LossyReportQuery>,
+) -> impl Responder {
+    let min_confidence = query
+        .min_confidence
+        .unwrap_or(crate::track_analysis::LOSSY_NOTE_CONFIDENCE)
+        .clamp(0.0, 1.0);
+    let limit = query.limit.unwrap_or(200).clamp(1, 1000);
+    let offset = query.offset.unwrap_or(0).max(0);
+    match state
+        .metadata
+        .db
+        .list_lossy_report(min_confidence, limit, offset)
+    {
+        Ok(items) => HttpResponse::Ok().json(LossyReportResponse { items }),
+        Err(err) => {
+            tracing::warn!(error = %err, "lossy report failed");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/albums/metadata",
@@ -546,16 +718,23 @@ pub async fn albums_metadata(
     query: web::Query<AlbumMetadataQuery>,
 ) -> impl Responder {
     let metadata_service = state.metadata_service();
-    match metadata_service.album_summary_by_id(query.album_id) {
-        Ok(Some(album)) => HttpResponse::Ok().json(AlbumMetadataResponse {
-            album_id: album.id,
-            title: Some(album.title),
-            album_artist: album.artist,
-            year: album.year,
-        }),
-        Ok(None) => HttpResponse::NotFound().finish(),
-        Err(err) => HttpResponse::InternalServerError().body(err),
-    }
+    let album = match metadata_service.album_summary_by_id(query.album_id) {
+        Ok(Some(album)) => album,
+        Ok(None) => return HttpResponse::NotFound().finish(),
+        Err(err) => return HttpResponse::InternalServerError().body(err),
+    };
+    let discs = match state.metadata.db.album_discs(album.id) {
+        Ok(discs) => discs,
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+    HttpResponse::Ok().json(AlbumMetadataResponse {
+        album_id: album.id,
+        title: Some(album.title),
+        album_artist: album.artist,
+        year: album.year,
+        disc_count: discs.len(),
+        discs,
+    })
 }
 
 #[utoipa::path(
@@ -563,7 +742,7 @@ pub async fn albums_metadata(
     path = "/albums/metadata/update",
     request_body = AlbumMetadataUpdateRequest,
     responses(
-        (status = 200, description = "Album metadata updated", body = AlbumMetadataUpdateResponse),
+        (status = 200, description = "Album metadata updated (dry runs return the planned changes as a MetadataUpdatePreviewResponse)", body = AlbumMetadataUpdateResponse),
         (status = 400, description = "Bad request"),
         (status = 404, description = "Album not found")
     )
@@ -581,6 +760,11 @@ pub async fn albums_metadata_update(
         Internal(String),
     }
 
+    enum AlbumUpdateOutcome {
+        Preview(Vec<TrackTagPreview>),
+        Updated(AlbumMetadataUpdateResponse),
+    }
+
     impl AlbumMetadataUpdateError {
         fn into_response(self) -> HttpResponse {
             match self {
@@ -637,9 +821,13 @@ pub async fn albums_metadata_update(
     let started_at = Instant::now();
     tracing::info!(album_id, track_count, "album metadata update started");
 
+    let dry_run = request.dry_run.unwrap_or(false);
     let state_for_update = state.clone();
-    let update_result =
-        tokio::task::spawn_blocking(move || -> Result<i64, AlbumMetadataUpdateError> {
+    let update_result = tokio::task::spawn_blocking(
+        move || -> Result<AlbumUpdateOutcome, AlbumMetadataUpdateError> {
+            let db = &state_for_update.metadata.db;
+            let mut previews = Vec::new();
+            let mut locked_tracks = 0;
             for path in paths {
                 let full_path =
                     crate::metadata_service::MetadataService::resolve_track_path(&root, &path)
@@ -654,28 +842,59 @@ pub async fn albums_metadata_update(
                                 "failed to resolve track path for album update: {path}"
                             )),
                         })?;
+                let track_id = db
+                    .track_id_for_path(&path)
+                    .map_err(|err| AlbumMetadataUpdateError::Internal(err.to_string()))?
+                    .ok_or_else(|| {
+                        AlbumMetadataUpdateError::NotFound(format!(
+                            "track disappeared during album update: {path}"
+                        ))
+                    })?;
+                let locked = db
+                    .track_field_locks(track_id)
+                    .map_err(|err| AlbumMetadataUpdateError::Internal(err.to_string()))?;
+
+                let mut update = TrackTagUpdate {
+                    title: None,
+                    artist: track_artist_owned.as_deref(),
+                    album: album_owned.as_deref(),
+                    album_artist: album_artist_owned.as_deref(),
+                    year,
+                    track_number: None,
+                    disc_number: None,
+                    extra_tags: None,
+                    clear_title: false,
+                    clear_artist: false,
+                    clear_album: false,
+                    clear_album_artist: false,
+                    clear_year: false,
+                    clear_track_number: false,
+                    clear_disc_number: false,
+                    clear_extra_tags: None,
+                };
+                let skipped_locked = update.skip_locked(&locked);
+                if dry_run {
+                    let changes = preview_track_tags(&full_path, &update).map_err(|err| {
+                        AlbumMetadataUpdateError::Internal(format!(
+                            "album metadata preview failed for {path}: {err}"
+                        ))
+                    })?;
+                    previews.push(TrackTagPreview {
+                        track_id,
+                        path,
+                        changes,
+                        skipped_locked,
+                    });
+                    continue;
+                }
+                if !skipped_locked.is_empty() {
+                    locked_tracks += 1;
+                }
+                if update.is_empty() {
+                    continue;
+                }
 
-                if let Err(err) = write_track_tags(
-                    &full_path,
-                    TrackTagUpdate {
-                        title: None,
-                        artist: track_artist_owned.as_deref(),
-                        album: album_owned.as_deref(),
-                        album_artist: album_artist_owned.as_deref(),
-                        year,
-                        track_number: None,
-                        disc_number: None,
-                        extra_tags: None,
-                        clear_title: false,
-                        clear_artist: false,
-                        clear_album: false,
-                        clear_album_artist: false,
-                        clear_year: false,
-                        clear_track_number: false,
-                        clear_disc_number: false,
-                        clear_extra_tags: None,
-                    },
-                ) {
+                if let Err(err) = write_track_tags(&full_path, update) {
                     return Err(AlbumMetadataUpdateError::Internal(format!(
                         "album metadata update failed for {path}: {err}"
                     )));
@@ -697,6 +916,9 @@ pub async fn albums_metadata_update(
                     });
                 }
             }
+            if dry_run {
+                return Ok(AlbumUpdateOutcome::Preview(previews));
+            }
 
             let mut updated_album_id = album_id;
             if album_owned.is_some() || album_artist_owned.is_some() || year.is_some() {
@@ -722,22 +944,28 @@ pub async fn albums_metadata_update(
                 }
             }
 
-            Ok(updated_album_id)
-        })
-        .await;
+            Ok(AlbumUpdateOutcome::Updated(AlbumMetadataUpdateResponse {
+                album_id: updated_album_id,
+                locked_tracks,
+            }))
+        },
+    )
+    .await;
 
     match update_result {
-        Ok(Ok(updated_album_id)) => {
+        Ok(Ok(AlbumUpdateOutcome::Preview(tracks))) => {
+            HttpResponse::Ok().json(MetadataUpdatePreviewResponse { tracks })
+        }
+        Ok(Ok(AlbumUpdateOutcome::Updated(response))) => {
             let elapsed_ms = started_at.elapsed().as_millis() as u64;
             tracing::info!(
                 album_id,
                 track_count,
                 elapsed_ms,
+                locked_tracks = response.locked_tracks,
                 "album metadata update finished"
             );
-            HttpResponse::Ok().json(AlbumMetadataUpdateResponse {
-                album_id: updated_album_id,
-            })
+            HttpResponse::Ok().json(response)
         }
         Ok(Err(err)) => {
             let elapsed_ms = started_at.elapsed().as_millis() as u64;
@@ -1275,6 +1503,142 @@ pub async fn media_asset(
     }
 }
 
+#[derive(Clone, Debug, Deserialize, IntoParams, ToSchema)]
+/// Path parameter for the artist image endpoint.
+pub struct ArtistImagePath {
+    /// Artist id.
+    pub id: i64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/artists/{id}/image",
+    params(ArtistImagePath),
+    responses(
+        (status = 200, description = "Artist image"),
+        (status = 404, description = "Artist has no image")
+    )
+)]
+#[get("/artists/{id}/image")]
+/// Serve the artist's image, whether set by hand or fetched in the background.
+pub async fn artist_image(
+    state: web::Data<AppState>,
+    path: web::Path<ArtistImagePath>,
+    req: HttpRequest,
+) -> impl Responder {
+    let record = match state
+        .metadata
+        .db
+        .media_asset_for("artist", path.id, "image")
+    {
+        Ok(Some(value)) => value,
+        Ok(None) => return HttpResponse::NotFound().finish(),
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+    let root = state.library.read().unwrap().root().to_path_buf();
+    let store = MediaAssetStore::new(root);
+    let full_path = match store.resolve_asset_path(&record.local_path) {
+        Ok(path) => path,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+    match NamedFile::open(full_path) {
+        Ok(file) => file.into_response(&req),
+        Err(_) => HttpResponse::NotFound().finish(),
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, IntoParams, ToSchema)]
+/// Path parameter for the track lyrics endpoint.
+pub struct TrackLyricsPath {
+    /// Track id.
+    pub id: i64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/tracks/{id}/lyrics",
+    params(TrackLyricsPath),
+    responses(
+        (status = 200, description = "Track lyrics", body = TrackLyricsResponse),
+        (status = 404, description = "Track not found or has no lyrics"),
+        (status = 502, description = "Lyrics provider request failed")
+    )
+)]
+#[get("/tracks/{id}/lyrics")]
+/// Return stored lyrics, looking them up online once when enabled and none are stored.
+pub async fn track_lyrics(
+    state: web::Data<AppState>,
+    path: web::Path<TrackLyricsPath>,
+) -> impl Responder {
+    let track_id = path.id;
+    let stored = match state.metadata.db.track_lyrics(track_id) {
+        Ok(value) => value,
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+    let lyrics = match stored {
+        Some(lyrics) => lyrics,
+        None => match fetch_track_lyrics(&state, track_id).await {
+            Ok(Some(lyrics)) => lyrics,
+            Ok(None) => return HttpResponse::NotFound().finish(),
+            Err(response) => return response,
+        },
+    };
+    match lyrics_response(track_id, lyrics) {
+        Some(response) => HttpResponse::Ok().json(response),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+/// Look lyrics up online and store the answer, including a miss.
+async fn fetch_track_lyrics(
+    state: &AppState,
+    track_id: i64,
+) -> Result<Option<TrackLyrics>, HttpResponse> {
+    let record = match state.metadata.db.track_record_by_id(track_id) {
+        Ok(Some(record)) => record,
+        Ok(None) => return Ok(None),
+        Err(err) => return Err(HttpResponse::InternalServerError().body(err.to_string())),
+    };
+    let Some(client) = state.metadata.lyrics.get() else {
+        return Ok(None);
+    };
+    let (Some(artist), Some(title)) = (record.artist.as_deref(), record.title.as_deref()) else {
+        return Ok(None);
+    };
+    let fetched = client
+        .fetch(artist, title, record.album.as_deref(), record.duration_ms)
+        .await
+        .map_err(|err| {
+            tracing::warn!(error = %err, track_id, "lyrics lookup failed");
+            HttpResponse::BadGateway().body(err.to_string())
+        })?;
+    let lyrics = fetched.unwrap_or_else(|| TrackLyrics {
+        plain: None,
+        synced: None,
+        source: crate::lyrics::SOURCE_LRCLIB.to_string(),
+        updated_at_ms: Some(now_ms()),
+    });
+    if let Err(err) = state.metadata.db.store_track_lyrics(track_id, &lyrics) {
+        tracing::warn!(error = %err, track_id, "lyrics store failed");
+    }
+    Ok(Some(lyrics))
+}
+
+/// Build the API response, or `None` when the stored row records a provider miss.
+fn lyrics_response(track_id: i64, lyrics: TrackLyrics) -> Option<TrackLyricsResponse> {
+    let lines = lyrics.synced.as_deref().map(parse_lrc).unwrap_or_default();
+    let plain = lyrics
+        .plain
+        .or_else(|| (!lines.is_empty()).then(|| plain_from_lines(&lines)))?;
+    Some(TrackLyricsResponse {
+        track_id,
+        source: lyrics.source,
+        synced: !lines.is_empty(),
+        plain,
+        lines,
+    })
+}
+
 #[derive(Clone, Debug, Deserialize, IntoParams, ToSchema)]
 /// Path parameter for track/album cover-art endpoints.
 pub struct CoverPath {
@@ -1346,6 +1710,73 @@ pub async fn album_cover(
     serve_cover_art(&state, &cover_rel, &req)
 }
 
+#[utoipa::path(
+    put,
+    path = "/albums/{id}/cover",
+    params(CoverPath),
+    request_body(content = Vec<u8>, content_type = "image/jpeg", description = "JPEG, PNG or WebP image"),
+    responses(
+        (status = 204, description = "Cover art replaced"),
+        (status = 400, description = "Image data does not match its content type"),
+        (status = 404, description = "Album not found"),
+        (status = 413, description = "Image too large"),
+        (status = 415, description = "Unsupported image type")
+    )
+)]
+#[put("/albums/{id}/cover")]
+/// Replace an album's cover art with an uploaded image.
+pub async fn album_cover_upload(
+    state: web::Data<AppState>,
+    path: web::Path<CoverPath>,
+    req: HttpRequest,
+    mut payload: web::Payload,
+) -> impl Responder {
+    let content_type = req
+        .headers()
+        .get(actix_web::http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+    let content_type = match content_type.as_str() {
+        "image/jpg" => "image/jpeg".to_string(),
+        "image/jpeg" | "image/png" | "image/webp" => content_type,
+        _ => {
+            return HttpResponse::UnsupportedMediaType()
+                .body("content-type must be image/jpeg, image/png or image/webp");
+        }
+    };
+    match state.metadata.db.album_exists(path.id) {
+        Ok(true) => {}
+        Ok(false) => return HttpResponse::NotFound().finish(),
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    }
+    let mut data = Vec::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+        };
+        if data.len() + chunk.len() > crate::cover_art::MAX_COVER_BYTES {
+            return HttpResponse::PayloadTooLarge().body(format!(
+                "image exceeds {} bytes",
+                crate::cover_art::MAX_COVER_BYTES
+            ));
+        }
+        data.extend_from_slice(&chunk);
+    }
+    if crate::cover_art::sniff_image_mime(&data) != Some(content_type.as_str()) {
+        return HttpResponse::BadRequest().body("image data does not match content-type");
+    }
+    match state
+        .metadata_service()
+        .replace_album_cover(path.id, &content_type, &data)
+    {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(err) => HttpResponse::InternalServerError().body(err),
+    }
+}
+
 /// Resolve, validate, and serve a cover file under `.audio-hub/art`.
 fn serve_cover_art(state: &AppState, cover_rel: &str, req: &HttpRequest) -> HttpResponse {
     let root = state.library.read().unwrap().root().to_path_buf();
@@ -1371,16 +1802,51 @@ fn serve_cover_art(state: &AppState, cover_rel: &str, req: &HttpRequest) -> Http
     }
 }
 
+/// Encode a list cursor as an opaque `next_cursor` token.
+fn encode_cursor(cursor: &ListCursor) -> String {
+    let json = serde_json::to_vec(cursor).unwrap_or_default();
+    general_purpose::URL_SAFE_NO_PAD.encode(json)
+}
+
+/// Decode a `cursor` query value; `None` when it is not a token this hub issued.
+fn decode_cursor(token: &str) -> Option<ListCursor> {
+    let json = general_purpose::URL_SAFE_NO_PAD.decode(token).ok()?;
+    serde_json::from_slice(&json).ok()
+}
+
+/// Where a list page starts: after `cursor` when given, else at `offset`.
+fn page_start<'a>(cursor: &'a Option<ListCursor>, offset: Option<i64>) -> PageStart<'a> {
+    match cursor {
+        Some(cursor) => PageStart::After(cursor),
+        None => PageStart::Offset(offset.unwrap_or(0).max(0)),
+    }
+}
+
+/// Parse the optional `cursor` query value.
+fn parse_cursor(token: Option<&str>) -> Result<Option<ListCursor>, HttpResponse> {
+    match token {
+        Some(token) => decode_cursor(token).map(Some).ok_or_else(invalid_cursor),
+        None => Ok(None),
+    }
+}
+
+/// 400 for a cursor that is malformed or was issued for another list or order.
+fn invalid_cursor() -> HttpResponse {
+    HttpResponse::BadRequest().body("invalid cursor for this list")
+}
+
 #[utoipa::path(
     get,
     path = "/artists",
     params(
         ("search" = Option<String>, Query, description = "Search term"),
         ("limit" = Option<i64>, Query, description = "Max rows"),
-        ("offset" = Option<i64>, Query, description = "Offset rows")
+        ("offset" = Option<i64>, Query, description = "Offset rows"),
+        ("cursor" = Option<String>, Query, description = "`next_cursor` of the previous page; replaces `offset`")
     ),
     responses(
-        (status = 200, description = "Artist list", body = ArtistListResponse)
+        (status = 200, description = "Artist list", body = ArtistListResponse),
+        (status = 400, description = "Invalid cursor")
     )
 )]
 #[get("/artists")]
@@ -1390,13 +1856,21 @@ pub async fn artists_list(
     query: web::Query<ListQuery>,
 ) -> impl Responder {
     let limit = query.limit.unwrap_or(200).clamp(1, 1000);
-    let offset = query.offset.unwrap_or(0).max(0);
+    let cursor = match parse_cursor(query.cursor.as_deref()) {
+        Ok(cursor) => cursor,
+        Err(resp) => return resp,
+    };
+    let start = page_start(&cursor, query.offset);
     match state
         .metadata
         .db
-        .list_artists(query.search.as_deref(), limit, offset)
+        .list_artists_page(query.search.as_deref(), limit, start)
     {
-        Ok(items) => HttpResponse::Ok().json(ArtistListResponse { items }),
+        Ok(Some(page)) => HttpResponse::Ok().json(ArtistListResponse {
+            items: page.items,
+            next_cursor: page.next_cursor.as_ref().map(encode_cursor),
+        }),
+        Ok(None) => invalid_cursor(),
         Err(err) => {
             tracing::warn!(error = %err, "artists list failed");
             HttpResponse::InternalServerError().finish()
@@ -1406,31 +1880,119 @@ pub async fn artists_list(
 
 #[utoipa::path(
     get,
-    path = "/albums",
+    path = "/genres",
     params(
-        ("artist_id" = Option<i64>, Query, description = "Artist id"),
         ("search" = Option<String>, Query, description = "Search term"),
         ("limit" = Option<i64>, Query, description = "Max rows"),
         ("offset" = Option<i64>, Query, description = "Offset rows")
     ),
     responses(
-        (status = 200, description = "Album list", body = AlbumListResponse)
+        (status = 200, description = "Genre list", body = GenreListResponse)
     )
 )]
-#[get("/albums")]
-/// List albums from the metadata database.
-pub async fn albums_list(
+#[get("/genres")]
+/// List genres found in file tags, with album and track counts.
+pub async fn genres_list(
     state: web::Data<AppState>,
-    query: web::Query<AlbumListQuery>,
+    query: web::Query<ListQuery>,
 ) -> impl Responder {
     let limit = query.limit.unwrap_or(200).clamp(1, 1000);
     let offset = query.offset.unwrap_or(0).max(0);
     match state
         .metadata
         .db
-        .list_albums(query.artist_id, query.search.as_deref(), limit, offset)
+        .list_genres(query.search.as_deref(), limit, offset)
     {
-        Ok(items) => HttpResponse::Ok().json(AlbumListResponse { items }),
+        Ok(items) => HttpResponse::Ok().json(GenreListResponse { items }),
+        Err(err) => {
+            tracing::warn!(error = %err, "genres list failed");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/composers",
+    params(
+        ("search" = Option<String>, Query, description = "Search term"),
+        ("limit" = Option<i64>, Query, description = "Max rows"),
+        ("offset" = Option<i64>, Query, description = "Offset rows")
+    ),
+    responses(
+        (status = 200, description = "Composer list", body = ComposerListResponse)
+    )
+)]
+#[get("/composers")]
+/// List composers from track tags, with album, track and work counts.
+pub async fn composers_list(
+    state: web::Data<AppState>,
+    query: web::Query<ListQuery>,
+) -> impl Responder {
+    let limit = query.limit.unwrap_or(200).clamp(1, 1000);
+    let offset = query.offset.unwrap_or(0).max(0);
+    match state
+        .metadata
+        .db
+        .list_composers(query.search.as_deref(), limit, offset)
+    {
+        Ok(items) => HttpResponse::Ok().json(ComposerListResponse { items }),
+        Err(err) => {
+            tracing::warn!(error = %err, "composers list failed");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/albums",
+    params(
+        ("artist_id" = Option<i64>, Query, description = "Artist id"),
+        ("genre_id" = Option<i64>, Query, description = "Genre id"),
+        ("composer" = Option<String>, Query, description = "Composer name"),
+        ("search" = Option<String>, Query, description = "Search term"),
+        ("favorite" = Option<bool>, Query, description = "Only favorite (true) or non-favorite (false) albums"),
+        ("min_rating" = Option<u8>, Query, description = "Minimum rating (1-5)"),
+        ("limit" = Option<i64>, Query, description = "Max rows"),
+        ("offset" = Option<i64>, Query, description = "Offset rows"),
+        ("cursor" = Option<String>, Query, description = "`next_cursor` of the previous page; replaces `offset`"),
+        ("sort" = Option<AlbumSort>, Query, description = "Order by album artist (default), composer, rating, recently added, title, year or random"),
+        ("direction" = Option<SortDirection>, Query, description = "asc or desc; defaults to desc for rating and recently added, asc otherwise")
+    ),
+    responses(
+        (status = 200, description = "Album list", body = AlbumListResponse),
+        (status = 400, description = "Invalid cursor")
+    )
+)]
+#[get("/albums")]
+/// List albums from the metadata database.
+pub async fn albums_list(
+    state: web::Data<AppState>,
+    query: web::Query<AlbumListQuery>,
+) -> impl Responder {
+    let limit = query.limit.unwrap_or(200).clamp(1, 1000);
+    let cursor = match parse_cursor(query.cursor.as_deref()) {
+        Ok(cursor) => cursor,
+        Err(resp) => return resp,
+    };
+    let filter = AlbumFilter {
+        artist_id: query.artist_id,
+        genre_id: query.genre_id,
+        composer: query.composer.as_deref(),
+        search: query.search.as_deref(),
+        favorite: query.favorite,
+        min_rating: query.min_rating,
+        sort: query.sort.unwrap_or_default(),
+        direction: query.direction,
+    };
+    let start = page_start(&cursor, query.offset);
+    match state.metadata.db.list_albums_page(&filter, limit, start) {
+        Ok(Some(page)) => HttpResponse::Ok().json(AlbumListResponse {
+            items: page.items,
+            next_cursor: page.next_cursor.as_ref().map(encode_cursor),
+        }),
+        Ok(None) => invalid_cursor(),
         Err(err) => {
             tracing::warn!(error = %err, "albums list failed");
             HttpResponse::InternalServerError().finish()
@@ -1444,12 +2006,23 @@ pub async fn albums_list(
     params(
         ("album_id" = Option<i64>, Query, description = "Album id"),
         ("artist_id" = Option<i64>, Query, description = "Artist id"),
+        ("genre_id" = Option<i64>, Query, description = "Genre id"),
+        ("composer" = Option<String>, Query, description = "Composer name"),
         ("search" = Option<String>, Query, description = "Search term"),
+        ("min_bpm" = Option<f64>, Query, description = "Minimum analyzed tempo (BPM)"),
+        ("max_bpm" = Option<f64>, Query, description = "Maximum analyzed tempo (BPM)"),
+        ("key" = Option<String>, Query, description = "Analyzed key, e.g. `A minor`"),
+        ("favorite" = Option<bool>, Query, description = "Only favorite (true) or non-favorite (false) tracks"),
+        ("min_rating" = Option<u8>, Query, description = "Minimum rating (1-5)"),
         ("limit" = Option<i64>, Query, description = "Max rows"),
-        ("offset" = Option<i64>, Query, description = "Offset rows")
+        ("offset" = Option<i64>, Query, description = "Offset rows"),
+        ("cursor" = Option<String>, Query, description = "`next_cursor` of the previous page; replaces `offset`"),
+        ("sort" = Option<TrackSort>, Query, description = "Order by disc/track (default), by composer, work and movement, by rating, recently added, title, artist, year or random"),
+        ("direction" = Option<SortDirection>, Query, description = "asc or desc; defaults to desc for rating and recently added, asc otherwise")
     ),
     responses(
-        (status = 200, description = "Track list", body = TrackListResponse)
+        (status = 200, description = "Track list", body = TrackListResponse),
+        (status = 400, description = "Invalid cursor")
     )
 )]
 #[get("/tracks")]
@@ -1459,15 +2032,31 @@ pub async fn tracks_list(
     query: web::Query<TrackListQuery>,
 ) -> impl Responder {
     let limit = query.limit.unwrap_or(200).clamp(1, 1000);
-    let offset = query.offset.unwrap_or(0).max(0);
-    match state.metadata.db.list_tracks(
-        query.album_id,
-        query.artist_id,
-        query.search.as_deref(),
-        limit,
-        offset,
-    ) {
-        Ok(items) => HttpResponse::Ok().json(TrackListResponse { items }),
+    let cursor = match parse_cursor(query.cursor.as_deref()) {
+        Ok(cursor) => cursor,
+        Err(resp) => return resp,
+    };
+    let filter = TrackFilter {
+        album_id: query.album_id,
+        artist_id: query.artist_id,
+        genre_id: query.genre_id,
+        composer: query.composer.as_deref(),
+        search: query.search.as_deref(),
+        min_bpm: query.min_bpm,
+        max_bpm: query.max_bpm,
+        key: query.key.as_deref(),
+        favorite: query.favorite,
+        min_rating: query.min_rating,
+        sort: query.sort.unwrap_or_default(),
+        direction: query.direction,
+    };
+    let start = page_start(&cursor, query.offset);
+    match state.metadata.db.list_tracks_page(&filter, limit, start) {
+        Ok(Some(page)) => HttpResponse::Ok().json(TrackListResponse {
+            items: page.items,
+            next_cursor: page.next_cursor.as_ref().map(encode_cursor),
+        }),
+        Ok(None) => invalid_cursor(),
         Err(err) => {
             tracing::warn!(error = %err, "tracks list failed");
             HttpResponse::InternalServerError().finish()
@@ -1475,6 +2064,212 @@ pub async fn tracks_list(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/albums/recent",
+    params(
+        ("limit" = Option<i64>, Query, description = "Max rows"),
+        ("offset" = Option<i64>, Query, description = "Offset rows"),
+        ("cursor" = Option<String>, Query, description = "`next_cursor` of the previous page; replaces `offset`")
+    ),
+    responses(
+        (status = 200, description = "Albums, most recently added first", body = AlbumListResponse),
+        (status = 400, description = "Invalid cursor")
+    )
+)]
+#[get("/albums/recent")]
+/// List albums by when they were first added to the library, newest first.
+pub async fn albums_recent(
+    state: web::Data<AppState>,
+    query: web::Query<PageQuery>,
+) -> impl Responder {
+    let limit = query.limit.unwrap_or(50).clamp(1, 1000);
+    let cursor = match parse_cursor(query.cursor.as_deref()) {
+        Ok(cursor) => cursor,
+        Err(resp) => return resp,
+    };
+    let filter = AlbumFilter {
+        sort: AlbumSort::RecentlyAdded,
+        ..AlbumFilter::default()
+    };
+    let start = page_start(&cursor, query.offset);
+    match state.metadata.db.list_albums_page(&filter, limit, start) {
+        Ok(Some(page)) => HttpResponse::Ok().json(AlbumListResponse {
+            items: page.items,
+            next_cursor: page.next_cursor.as_ref().map(encode_cursor),
+        }),
+        Ok(None) => invalid_cursor(),
+        Err(err) => {
+            tracing::warn!(error = %err, "recent albums list failed");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/tracks/recent",
+    params(
+        ("limit" = Option<i64>, Query, description = "Max rows"),
+        ("offset" = Option<i64>, Query, description = "Offset rows"),
+        ("cursor" = Option<String>, Query, description = "`next_cursor` of the previous page; replaces `offset`")
+    ),
+    responses(
+        (status = 200, description = "Tracks, most recently added first", body = TrackListResponse),
+        (status = 400, description = "Invalid cursor")
+    )
+)]
+#[get("/tracks/recent")]
+/// List tracks by when they were first added to the library, newest first.
+pub async fn tracks_recent(
+    state: web::Data<AppState>,
+    query: web::Query<PageQuery>,
+) -> impl Responder {
+    let limit = query.limit.unwrap_or(50).clamp(1, 1000);
+    let cursor = match parse_cursor(query.cursor.as_deref()) {
+        Ok(cursor) => cursor,
+        Err(resp) => return resp,
+    };
+    let filter = TrackFilter {
+        sort: TrackSort::RecentlyAdded,
+        ..TrackFilter::default()
+    };
+    let start = page_start(&cursor, query.offset);
+    match state.metadata.db.list_tracks_page(&filter, limit, start) {
+        Ok(Some(page)) => HttpResponse::Ok().json(TrackListResponse {
+            items: page.items,
+            next_cursor: page.next_cursor.as_ref().map(encode_cursor),
+        }),
+        Ok(None) => invalid_cursor(),
+        Err(err) => {
+            tracing::warn!(error = %err, "recent tracks list failed");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Validate a rating update, mapping `rating: 0` to "clear".
+fn rating_update(body: &RatingUpdateRequest) -> Result<Option<Option<u8>>, HttpResponse> {
+    match body.rating {
+        None => Ok(None),
+        Some(0) => Ok(Some(None)),
+        Some(value @ 1..=5) => Ok(Some(Some(value))),
+        Some(_) => Err(HttpResponse::BadRequest().body("rating must be between 0 and 5")),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/tracks/{id}/rating",
+    params(
+        ("id" = i64, Path, description = "Track id")
+    ),
+    request_body = RatingUpdateRequest,
+    responses(
+        (status = 200, description = "Updated favorite flag and rating", body = Rating),
+        (status = 400, description = "Rating out of range"),
+        (status = 404, description = "Track not found")
+    )
+)]
+#[put("/tracks/{id}/rating")]
+/// Set a track's favorite flag and/or star rating.
+pub async fn track_rating_set(
+    state: web::Data<AppState>,
+    id: web::Path<i64>,
+    body: web::Json<RatingUpdateRequest>,
+) -> impl Responder {
+    let track_id = id.into_inner();
+    let rating = match rating_update(&body) {
+        Ok(rating) => rating,
+        Err(resp) => return resp,
+    };
+    match state.metadata.db.track_path_for_id(track_id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().body("track not found"),
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    }
+    match state
+        .metadata
+        .db
+        .set_track_rating(track_id, body.favorite, rating)
+    {
+        Ok(current) => HttpResponse::Ok().json(current),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/albums/{id}/rating",
+    params(
+        ("id" = i64, Path, description = "Album id")
+    ),
+    request_body = RatingUpdateRequest,
+    responses(
+        (status = 200, description = "Updated favorite flag and rating", body = Rating),
+        (status = 400, description = "Rating out of range"),
+        (status = 404, description = "Album not found")
+    )
+)]
+#[put("/albums/{id}/rating")]
+/// Set an album's favorite flag and/or star rating.
+pub async fn album_rating_set(
+    state: web::Data<AppState>,
+    id: web::Path<i64>,
+    body: web::Json<RatingUpdateRequest>,
+) -> impl Responder {
+    let album_id = id.into_inner();
+    let rating = match rating_update(&body) {
+        Ok(rating) => rating,
+        Err(resp) => return resp,
+    };
+    match state.metadata.db.album_exists(album_id) {
+        Ok(true) => {}
+        Ok(false) => return HttpResponse::NotFound().body("album not found"),
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    }
+    match state
+        .metadata
+        .db
+        .set_album_rating(album_id, body.favorite, rating)
+    {
+        Ok(current) => HttpResponse::Ok().json(current),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/search",
+    params(
+        ("q" = String, Query, description = "Search words"),
+        ("kind" = Option<SearchKind>, Query, description = "Only artists, albums or tracks"),
+        ("limit" = Option<i64>, Query, description = "Max rows")
+    ),
+    responses(
+        (status = 200, description = "Ranked search results", body = SearchResponse),
+        (status = 400, description = "Empty query")
+    )
+)]
+#[get("/search")]
+/// Full-text search across artists, albums and tracks.
+pub async fn library_search(
+    state: web::Data<AppState>,
+    query: web::Query<SearchQuery>,
+) -> impl Responder {
+    if !query.q.chars().any(char::is_alphanumeric) {
+        return HttpResponse::BadRequest().body("q must contain a word");
+    }
+    let limit = query.limit.unwrap_or(20).clamp(1, 200);
+    match state.metadata.db.search(&query.q, query.kind, limit) {
+        Ok(items) => HttpResponse::Ok().json(SearchResponse { items }),
+        Err(err) => {
+            tracing::warn!(error = %err, "library search failed");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/metadata/match/search",
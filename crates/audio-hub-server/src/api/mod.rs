@@ -2,41 +2,66 @@
 //!
 //! Defines the Actix routes for library, playback, queue, and output control.
 
+pub mod audit;
+pub mod auth;
+pub mod export;
 pub mod health;
+pub mod history;
+pub mod homeassistant;
 pub mod library;
 pub mod local_playback;
 pub mod logs;
 pub mod metadata;
 pub mod outputs;
+pub mod playlists;
 pub mod sessions;
 pub mod streams;
 
-pub use health::HealthResponse;
+pub use audit::audit_list;
+pub use auth::{auth_tokens_create, auth_tokens_delete, auth_tokens_list};
+pub use export::{metadata_export, metadata_import};
+pub use health::{BridgeHealthDetail, BridgeHealthEntry, HealthResponse};
+pub use history::history_list;
+pub use homeassistant::{
+    homeassistant_discovery, homeassistant_player_command, homeassistant_player_state,
+};
 pub use library::{
-    list_library, rescan_library, rescan_track, stream_track_id, transcode_track_id,
+    hls_playlist, hls_segment, list_library, rescan_library, rescan_track, stream_track_id,
+    transcode_track_id,
 };
 pub use local_playback::{local_playback_play, local_playback_register, local_playback_sessions};
-pub use logs::{LogsClearResponse, logs_clear};
+pub use logs::{
+    LogLevelRequest, LogLevelResponse, LogsClearResponse, log_level_get, log_level_set, logs_clear,
+};
 pub use metadata::{
-    album_cover, album_image_clear, album_image_set, album_profile, album_profile_update,
-    albums_list, albums_metadata, albums_metadata_update, artist_image_clear, artist_image_set,
-    artist_profile, artist_profile_update, artists_list, media_asset, musicbrainz_match_apply,
-    musicbrainz_match_search, track_cover, tracks_analysis, tracks_list, tracks_metadata,
-    tracks_metadata_fields, tracks_metadata_update, tracks_resolve,
+    album_cover, album_cover_upload, album_image_clear, album_image_set, album_profile,
+    album_profile_update, album_rating_set, albums_list, albums_metadata, albums_metadata_update,
+    albums_recent, artist_image, artist_image_clear, artist_image_set, artist_profile,
+    artist_profile_update, artists_list, composers_list, genres_list, library_search, media_asset,
+    musicbrainz_match_apply, musicbrainz_match_search, track_cover, track_lyrics, track_rating_set,
+    tracks_analysis, tracks_list, tracks_lossy_report, tracks_metadata, tracks_metadata_fields,
+    tracks_metadata_update, tracks_recent, tracks_resolve,
 };
 pub use outputs::{
-    bridge_unregister, outputs_list, outputs_select, outputs_settings, outputs_settings_update,
-    provider_outputs_list, provider_refresh, providers_list,
+    bridge_unregister, bridges_list, outputs_hide, outputs_list, outputs_preferences,
+    outputs_preferences_update, outputs_select, outputs_settings, outputs_settings_update,
+    provider_logs, provider_outputs_list, provider_refresh, providers_list,
+};
+pub use playlists::{
+    playlists_create, playlists_delete, playlists_get, playlists_list, playlists_update,
 };
 pub use sessions::{
-    sessions_create, sessions_delete, sessions_get, sessions_heartbeat, sessions_list,
-    sessions_locks, sessions_mute_set, sessions_pause, sessions_queue_add, sessions_queue_add_next,
-    sessions_queue_clear, sessions_queue_list, sessions_queue_next, sessions_queue_play_from,
-    sessions_queue_previous, sessions_queue_remove, sessions_queue_stream, sessions_release_output,
-    sessions_seek, sessions_select_output, sessions_status, sessions_status_stream, sessions_stop,
+    sessions_chapter, sessions_create, sessions_delete, sessions_get, sessions_heartbeat,
+    sessions_list, sessions_locks, sessions_mute_set, sessions_pause, sessions_queue_add,
+    sessions_queue_add_next, sessions_queue_clear, sessions_queue_list, sessions_queue_next,
+    sessions_queue_play_from, sessions_queue_playlist, sessions_queue_previous,
+    sessions_queue_remove, sessions_queue_stream, sessions_release_output, sessions_seek,
+    sessions_select_output, sessions_status, sessions_status_stream, sessions_stop,
     sessions_volume, sessions_volume_set,
 };
-pub use streams::{albums_stream, logs_stream, metadata_stream, outputs_stream};
+pub use streams::{
+    albums_stream, library_scan_progress_stream, logs_stream, metadata_stream, outputs_stream,
+};
 
 #[cfg(test)]
 mod tests {
@@ -142,6 +167,875 @@ mod tests {
         assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
     }
 
+    #[actix_web::test]
+    async fn rescan_runs_in_background_and_refuses_overlap() {
+        let state = make_state();
+        let root = state.library.read().unwrap().root().to_path_buf();
+        std::fs::write(root.join("song.flac"), b"test").expect("write track");
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .service(api::rescan_library),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/library/rescan")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::ACCEPTED);
+        for _ in 0..200 {
+            if !state.library_scan.snapshot().running {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let progress = state.library_scan.snapshot();
+        assert!(!progress.running);
+        assert_eq!((progress.files_scanned, progress.files_total), (1, 1));
+        assert!(
+            state
+                .library
+                .read()
+                .unwrap()
+                .find_track_by_path(&root.join("song.flac"))
+                .is_some()
+        );
+
+        assert!(state.library_scan.begin());
+        let req = test::TestRequest::post()
+            .uri("/library/rescan")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+    }
+
+    #[actix_web::test]
+    async fn genres_list_and_genre_filters() {
+        let state = make_state();
+        for (path, album, genre) in [
+            ("/music/a.flac", "A", "Jazz"),
+            ("/music/b.flac", "B", "Rock"),
+        ] {
+            state
+                .metadata
+                .db
+                .upsert_track(&crate::metadata_db::TrackRecord {
+                    path: path.to_string(),
+                    file_name: path.trim_start_matches("/music/").to_string(),
+                    title: None,
+                    artist: Some("Artist".to_string()),
+                    album_artist: None,
+                    album: Some(album.to_string()),
+                    album_uuid: None,
+                    track_number: None,
+                    disc_number: None,
+                    disc_title: None,
+                    year: None,
+                    genres: vec![genre.to_string()],
+                    composer: None,
+                    conductor: None,
+                    work: None,
+                    movement: None,
+                    movement_number: None,
+                    duration_ms: None,
+                    sample_rate: None,
+                    bit_depth: None,
+                    format: None,
+                    mtime_ms: 1,
+                    size_bytes: 1,
+                })
+                .expect("upsert track");
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .service(api::genres_list)
+                .service(api::albums_list)
+                .service(api::tracks_list),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/genres?search=roc")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["items"].as_array().unwrap().len(), 1);
+        assert_eq!(body["items"][0]["name"], "Rock");
+        assert_eq!(body["items"][0]["track_count"], 1);
+        let rock = body["items"][0]["id"].as_i64().unwrap();
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/albums?genre_id={rock}"))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["items"].as_array().unwrap().len(), 1);
+        assert_eq!(body["items"][0]["title"], "B");
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/tracks?genre_id={rock}"))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["items"].as_array().unwrap().len(), 1);
+        assert_eq!(body["items"][0]["file_name"], "b.flac");
+
+        let req = test::TestRequest::get().uri("/tracks?limit=1").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["items"][0]["file_name"], "a.flac");
+        let cursor = body["next_cursor"]
+            .as_str()
+            .expect("next_cursor")
+            .to_string();
+        let req = test::TestRequest::get()
+            .uri(&format!("/tracks?limit=1&cursor={cursor}"))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["items"][0]["file_name"], "b.flac");
+
+        for uri in [
+            "/tracks?cursor=not-a-cursor".to_string(),
+            format!("/albums?cursor={cursor}"),
+        ] {
+            let req = test::TestRequest::get().uri(&uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[actix_web::test]
+    async fn token_auth_guards_mutations_once_a_token_exists() {
+        let state = make_state();
+        let auth = actix_web::web::Data::new(
+            crate::auth::ApiAuth::new(state.metadata.db.clone(), None).expect("auth"),
+        );
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .app_data(auth.clone())
+                .wrap(crate::auth::TokenAuth)
+                .service(api::playlists_list)
+                .service(api::playlists_create)
+                .service(api::stream_track_id)
+                .service(api::auth_tokens_list)
+                .service(api::auth_tokens_create)
+                .service(api::auth_tokens_delete),
+        )
+        .await;
+
+        // Open until the first token is created.
+        let req = test::TestRequest::post()
+            .uri("/auth/tokens")
+            .set_json(serde_json::json!({ "name": "phone" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let secret = body["secret"].as_str().unwrap().to_string();
+        let token_id = body["token"]["id"].as_i64().unwrap();
+        assert!(auth.enabled());
+
+        let req = test::TestRequest::get().uri("/playlists").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+        let req = test::TestRequest::post()
+            .uri("/playlists")
+            .set_json(serde_json::json!({ "name": "Mix" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 401);
+        assert_eq!(resp.headers().get("www-authenticate").unwrap(), "Bearer");
+        let req = test::TestRequest::post()
+            .uri("/playlists")
+            .insert_header(("Authorization", "Bearer wrong"))
+            .set_json(serde_json::json!({ "name": "Mix" }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 401);
+        let req = test::TestRequest::post()
+            .uri("/playlists")
+            .insert_header(("Authorization", format!("Bearer {secret}")))
+            .set_json(serde_json::json!({ "name": "Mix" }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 201);
+
+        // The stream token opens track streams only.
+        let stream_token = crate::auth::stream_token();
+        let req = test::TestRequest::get().uri("/stream/track/1").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 401);
+        let req = test::TestRequest::get()
+            .uri(&format!("/stream/track/1?access_token={stream_token}"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+        let req = test::TestRequest::get()
+            .uri(&format!("/auth/tokens?access_token={stream_token}"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 401);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/auth/tokens?access_token={secret}"))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["enabled"], true);
+        assert_eq!(body["items"][0]["name"], "phone");
+        assert!(body["items"][0]["last_used_at_ms"].is_i64());
+        assert!(body["items"][0].get("secret").is_none());
+
+        // Deleting the last token switches authentication off again.
+        let req = test::TestRequest::delete()
+            .uri(&format!("/auth/tokens/{token_id}"))
+            .insert_header(("Authorization", format!("Bearer {secret}")))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 204);
+        assert!(!auth.enabled());
+    }
+
+    #[actix_web::test]
+    async fn metadata_export_needs_an_admin_token_once_auth_is_on() {
+        let state = make_state();
+        let auth = actix_web::web::Data::new(
+            crate::auth::ApiAuth::new(state.metadata.db.clone(), None).expect("auth"),
+        );
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .app_data(auth.clone())
+                .wrap(crate::auth::TokenAuth)
+                .service(api::metadata_export)
+                .service(api::auth_tokens_create),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/metadata/export")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+
+        let req = test::TestRequest::post()
+            .uri("/auth/tokens")
+            .set_json(serde_json::json!({ "name": "owner" }))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let secret = body["secret"].as_str().unwrap().to_string();
+
+        let req = test::TestRequest::get()
+            .uri("/metadata/export")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 401);
+        let req = test::TestRequest::get()
+            .uri("/metadata/export")
+            .insert_header(("Authorization", format!("Bearer {secret}")))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+    }
+
+    #[actix_web::test]
+    async fn listener_tokens_control_playback_but_not_metadata() {
+        let state = make_state();
+        let auth = actix_web::web::Data::new(
+            crate::auth::ApiAuth::new(state.metadata.db.clone(), None).expect("auth"),
+        );
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .app_data(auth.clone())
+                .wrap(crate::auth::TokenAuth)
+                .route(
+                    "/sessions",
+                    actix_web::web::post().to(actix_web::HttpResponse::Ok),
+                )
+                .service(api::playlists_create)
+                .service(api::auth_tokens_list)
+                .service(api::auth_tokens_create)
+                .service(api::auth_tokens_delete),
+        )
+        .await;
+        let create = |name: &str, role: &str, bearer: Option<&str>| {
+            let mut req = test::TestRequest::post()
+                .uri("/auth/tokens")
+                .set_json(serde_json::json!({ "name": name, "role": role }));
+            if let Some(bearer) = bearer {
+                req = req.insert_header(("Authorization", format!("Bearer {bearer}")));
+            }
+            req.to_request()
+        };
+
+        // Without an admin, nobody could manage a listener-only hub.
+        let resp = test::call_service(&app, create("guest", "listener", None)).await;
+        assert_eq!(resp.status(), 400);
+        let body: serde_json::Value =
+            test::call_and_read_body_json(&app, create("owner", "admin", None)).await;
+        let admin = body["secret"].as_str().unwrap().to_string();
+        let admin_id = body["token"]["id"].as_i64().unwrap();
+        let body: serde_json::Value =
+            test::call_and_read_body_json(&app, create("guest", "listener", Some(&admin))).await;
+        assert_eq!(body["token"]["role"], "listener");
+        let guest = body["secret"].as_str().unwrap().to_string();
+        let guest_id = body["token"]["id"].as_i64().unwrap();
+
+        let req = test::TestRequest::post()
+            .uri("/sessions")
+            .insert_header(("Authorization", format!("Bearer {guest}")))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+        let req = test::TestRequest::post()
+            .uri("/playlists")
+            .insert_header(("Authorization", format!("Bearer {guest}")))
+            .set_json(serde_json::json!({ "name": "Mix" }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 403);
+        let resp = test::call_service(&app, create("other", "listener", Some(&guest))).await;
+        assert_eq!(resp.status(), 403);
+
+        let delete = |id: i64| {
+            test::TestRequest::delete()
+                .uri(&format!("/auth/tokens/{id}"))
+                .insert_header(("Authorization", format!("Bearer {admin}")))
+                .to_request()
+        };
+        assert_eq!(
+            test::call_service(&app, delete(admin_id)).await.status(),
+            409
+        );
+        assert_eq!(
+            test::call_service(&app, delete(guest_id)).await.status(),
+            204
+        );
+        assert_eq!(
+            test::call_service(&app, delete(admin_id)).await.status(),
+            204
+        );
+        assert!(!auth.enabled());
+    }
+
+    #[actix_web::test]
+    async fn playlists_crud_keeps_order_and_validates_tracks() {
+        let state = make_state();
+        let mut ids = Vec::new();
+        for path in ["/music/a.flac", "/music/b.flac"] {
+            state
+                .metadata
+                .db
+                .upsert_track(&crate::metadata_db::TrackRecord {
+                    path: path.to_string(),
+                    file_name: path.trim_start_matches("/music/").to_string(),
+                    title: None,
+                    artist: Some("Artist".to_string()),
+                    album_artist: None,
+                    album: Some("Album".to_string()),
+                    album_uuid: None,
+                    track_number: None,
+                    disc_number: None,
+                    disc_title: None,
+                    year: None,
+                    genres: Vec::new(),
+                    composer: None,
+                    conductor: None,
+                    work: None,
+                    movement: None,
+                    movement_number: None,
+                    duration_ms: Some(1000),
+                    sample_rate: None,
+                    bit_depth: None,
+                    format: None,
+                    mtime_ms: 1,
+                    size_bytes: 1,
+                })
+                .expect("upsert track");
+            ids.push(state.metadata.db.track_id_for_path(path).unwrap().unwrap());
+        }
+        let (a, b) = (ids[0], ids[1]);
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .service(api::playlists_list)
+                .service(api::playlists_create)
+                .service(api::playlists_get)
+                .service(api::playlists_update)
+                .service(api::playlists_delete),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/playlists")
+            .set_json(serde_json::json!({ "name": "  ", "track_ids": [a] }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+        let req = test::TestRequest::post()
+            .uri("/playlists")
+            .set_json(serde_json::json!({ "name": "Mix", "track_ids": [a, 9999] }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        let req = test::TestRequest::post()
+            .uri("/playlists")
+            .set_json(serde_json::json!({ "name": "Mix", "track_ids": [b, a, b] }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let playlist_id = body["playlist"]["id"].as_i64().unwrap();
+        assert_eq!(body["playlist"]["track_count"], 3);
+        assert_eq!(body["playlist"]["duration_ms"], 3000);
+        let order: Vec<i64> = body["tracks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["id"].as_i64().unwrap())
+            .collect();
+        assert_eq!(order, vec![b, a, b]);
+
+        let req = test::TestRequest::put()
+            .uri(&format!("/playlists/{playlist_id}"))
+            .set_json(serde_json::json!({ "name": "Renamed", "track_ids": [a] }))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["playlist"]["name"], "Renamed");
+        assert_eq!(body["tracks"].as_array().unwrap().len(), 1);
+
+        let req = test::TestRequest::get().uri("/playlists").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["items"].as_array().unwrap().len(), 1);
+        assert_eq!(body["items"][0]["track_count"], 1);
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/playlists/{playlist_id}"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 204);
+        let req = test::TestRequest::get()
+            .uri(&format!("/playlists/{playlist_id}"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn album_update_dry_run_reports_changes_and_respects_track_locks() {
+        let state = make_state();
+        let root = state.library.read().unwrap().root().to_path_buf();
+        for name in ["a.flac", "b.flac"] {
+            let path = root.join(name);
+            std::fs::write(&path, crate::tag_writer::minimal_flac()).expect("write flac");
+            crate::tag_writer::write_track_tags(
+                &path,
+                crate::tag_writer::TrackTagUpdate {
+                    title: None,
+                    artist: None,
+                    album: Some("Album"),
+                    album_artist: None,
+                    year: None,
+                    track_number: None,
+                    disc_number: None,
+                    extra_tags: None,
+                    clear_title: false,
+                    clear_artist: false,
+                    clear_album: false,
+                    clear_album_artist: false,
+                    clear_year: false,
+                    clear_track_number: false,
+                    clear_disc_number: false,
+                    clear_extra_tags: None,
+                },
+            )
+            .expect("write tags");
+            state
+                .metadata
+                .db
+                .upsert_track(&crate::metadata_db::TrackRecord {
+                    path: path.to_string_lossy().to_string(),
+                    file_name: name.to_string(),
+                    title: None,
+                    artist: Some("Artist".to_string()),
+                    album_artist: None,
+                    album: Some("Album".to_string()),
+                    album_uuid: None,
+                    track_number: None,
+                    disc_number: None,
+                    disc_title: None,
+                    year: None,
+                    genres: Vec::new(),
+                    composer: None,
+                    conductor: None,
+                    work: None,
+                    movement: None,
+                    movement_number: None,
+                    duration_ms: None,
+                    sample_rate: None,
+                    bit_depth: None,
+                    format: None,
+                    mtime_ms: 1,
+                    size_bytes: 1,
+                })
+                .expect("upsert track");
+        }
+        let db = &state.metadata.db;
+        let locked_id = db
+            .track_id_for_path(&root.join("b.flac").to_string_lossy())
+            .expect("track id")
+            .expect("track");
+        let album_id = db
+            .list_albums(&crate::metadata_db::AlbumFilter::default(), 10, 0)
+            .expect("albums")[0]
+            .id;
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .service(api::tracks_metadata_update)
+                .service(api::tracks_metadata)
+                .service(api::albums_metadata_update),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/tracks/metadata/update")
+            .set_json(serde_json::json!({ "track_id": locked_id, "lock_fields": ["Album"] }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status().as_u16(), 200);
+        let req = test::TestRequest::post()
+            .uri("/tracks/metadata/update")
+            .set_json(serde_json::json!({ "track_id": locked_id, "lock_fields": ["mood"] }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status().as_u16(), 400);
+        let req = test::TestRequest::get()
+            .uri(&format!("/tracks/metadata?track_id={locked_id}"))
+            .to_request();
+        let body: crate::models::TrackMetadataResponse =
+            test::read_body_json(test::call_service(&app, req).await).await;
+        assert_eq!(body.locked_fields, ["album"]);
+
+        let req = test::TestRequest::post()
+            .uri("/albums/metadata/update")
+            .set_json(serde_json::json!({ "album_id": album_id, "album": "New", "dry_run": true }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status().as_u16(), 200);
+        let body: crate::models::MetadataUpdatePreviewResponse = test::read_body_json(resp).await;
+        assert_eq!(body.tracks.len(), 2);
+        for track in &body.tracks {
+            if track.track_id == locked_id {
+                assert!(track.changes.is_empty());
+                assert_eq!(track.skipped_locked, ["album"]);
+            } else {
+                assert_eq!(track.changes.len(), 1);
+                assert_eq!(track.changes[0].field, "album");
+                assert_eq!(track.changes[0].current.as_deref(), Some("Album"));
+                assert_eq!(track.changes[0].new.as_deref(), Some("New"));
+            }
+        }
+        let tags =
+            crate::tag_writer::read_vorbis_comment_tags(&root.join("a.flac")).expect("read tags");
+        assert_eq!(tags.get("ALBUM").map(String::as_str), Some("Album"));
+    }
+
+    #[actix_web::test]
+    async fn track_lyrics_serves_synced_lines_and_keeps_fetched_rows() {
+        let state = make_state();
+        state
+            .metadata
+            .db
+            .upsert_track(&crate::metadata_db::TrackRecord {
+                path: "/music/a.flac".to_string(),
+                file_name: "a.flac".to_string(),
+                title: Some("Song".to_string()),
+                artist: Some("Artist".to_string()),
+                album_artist: None,
+                album: None,
+                album_uuid: None,
+                track_number: None,
+                disc_number: None,
+                disc_title: None,
+                year: None,
+                genres: Vec::new(),
+                composer: None,
+                conductor: None,
+                work: None,
+                movement: None,
+                movement_number: None,
+                duration_ms: None,
+                sample_rate: None,
+                bit_depth: None,
+                format: None,
+                mtime_ms: 1,
+                size_bytes: 1,
+            })
+            .expect("upsert track");
+        let db = &state.metadata.db;
+        let track_id = db
+            .track_id_for_path("/music/a.flac")
+            .expect("track id")
+            .expect("track");
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .service(api::track_lyrics),
+        )
+        .await;
+        let uri = format!("/tracks/{track_id}/lyrics");
+
+        let resp = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(resp.status().as_u16(), 404);
+
+        let sidecar = crate::metadata_db::TrackLyrics {
+            plain: None,
+            synced: Some("[00:02.00]Second\n[00:01.00]First".to_string()),
+            source: crate::lyrics::SOURCE_SIDECAR.to_string(),
+            updated_at_ms: Some(1),
+        };
+        db.sync_local_lyrics("/music/a.flac", Some(&sidecar))
+            .expect("sync lyrics");
+        let resp = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(resp.status().as_u16(), 200);
+        let body: crate::models::TrackLyricsResponse = test::read_body_json(resp).await;
+        assert!(body.synced);
+        assert_eq!(body.source, "sidecar");
+        assert_eq!(body.plain, "First\nSecond");
+        assert_eq!(body.lines[0].time_ms, 1000);
+
+        // Rescans without local lyrics drop local rows but keep fetched ones.
+        db.sync_local_lyrics("/music/a.flac", None)
+            .expect("clear lyrics");
+        assert!(db.track_lyrics(track_id).expect("lyrics").is_none());
+        let fetched = crate::metadata_db::TrackLyrics {
+            plain: Some("Online".to_string()),
+            synced: None,
+            source: crate::lyrics::SOURCE_LRCLIB.to_string(),
+            updated_at_ms: Some(2),
+        };
+        db.store_track_lyrics(track_id, &fetched)
+            .expect("store lyrics");
+        db.sync_local_lyrics("/music/a.flac", None)
+            .expect("clear lyrics");
+        let resp = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        let body: crate::models::TrackLyricsResponse = test::read_body_json(resp).await;
+        assert!(!body.synced);
+        assert_eq!(body.plain, "Online");
+        assert!(body.lines.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn artist_image_serves_stored_asset() {
+        let state = make_state();
+        state
+            .metadata
+            .db
+            .upsert_track(&crate::metadata_db::TrackRecord {
+                path: "/music/a.flac".to_string(),
+                file_name: "a.flac".to_string(),
+                title: None,
+                artist: Some("Artist".to_string()),
+                album_artist: None,
+                album: None,
+                album_uuid: None,
+                track_number: None,
+                disc_number: None,
+                disc_title: None,
+                year: None,
+                genres: Vec::new(),
+                composer: None,
+                conductor: None,
+                work: None,
+                movement: None,
+                movement_number: None,
+                duration_ms: None,
+                sample_rate: None,
+                bit_depth: None,
+                format: None,
+                mtime_ms: 1,
+                size_bytes: 1,
+            })
+            .expect("upsert track");
+        let artist_id = state
+            .metadata
+            .db
+            .list_artists(None, 10, 0)
+            .expect("artists")[0]
+            .id;
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .service(api::artist_image),
+        )
+        .await;
+        let uri = format!("/artists/{artist_id}/image");
+
+        let resp = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(resp.status().as_u16(), 404);
+
+        let root = state.library.read().unwrap().root().to_path_buf();
+        let stored = crate::media_assets::MediaAssetStore::new(root)
+            .store_image_bytes(
+                "artist",
+                artist_id,
+                "image",
+                "image/jpeg",
+                b"jpeg-bytes",
+                "https://example.org/a.jpg",
+            )
+            .expect("store image");
+        state
+            .metadata
+            .db
+            .upsert_media_asset(
+                "artist",
+                artist_id,
+                "image",
+                &stored.local_path,
+                Some(&stored.checksum),
+                Some(&stored.source_url),
+                Some(stored.updated_at_ms),
+            )
+            .expect("upsert asset");
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        assert_eq!(
+            test::call_and_read_body(&app, req).await,
+            b"jpeg-bytes".as_slice()
+        );
+    }
+
+    #[actix_web::test]
+    async fn album_cover_upload_validates_and_replaces_cover() {
+        let state = make_state();
+        state
+            .metadata
+            .db
+            .upsert_track(&crate::metadata_db::TrackRecord {
+                path: "/music/a.flac".to_string(),
+                file_name: "a.flac".to_string(),
+                title: None,
+                artist: Some("Artist".to_string()),
+                album_artist: None,
+                album: Some("A".to_string()),
+                album_uuid: None,
+                track_number: None,
+                disc_number: None,
+                disc_title: None,
+                year: None,
+                genres: Vec::new(),
+                composer: None,
+                conductor: None,
+                work: None,
+                movement: None,
+                movement_number: None,
+                duration_ms: None,
+                sample_rate: None,
+                bit_depth: None,
+                format: None,
+                mtime_ms: 1,
+                size_bytes: 1,
+            })
+            .expect("upsert track");
+        let album_id = state
+            .metadata
+            .db
+            .list_albums(&crate::metadata_db::AlbumFilter::default(), 10, 0)
+            .expect("albums")[0]
+            .id;
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .service(api::album_cover)
+                .service(api::album_cover_upload),
+        )
+        .await;
+        let upload = |id: i64, content_type: &str, body: Vec<u8>| {
+            test::TestRequest::put()
+                .uri(&format!("/albums/{id}/cover"))
+                .insert_header(("content-type", content_type))
+                .set_payload(body)
+                .to_request()
+        };
+        let png = [b"\x89PNG\r\n\x1a\n".as_slice(), b"first"].concat();
+        let jpeg = [[0xff, 0xd8, 0xff].as_slice(), b"second"].concat();
+
+        for (req, status) in [
+            (upload(album_id, "text/plain", png.clone()), 415),
+            (upload(album_id, "image/png", jpeg.clone()), 400),
+            (upload(album_id + 100, "image/png", png.clone()), 404),
+            (
+                upload(
+                    album_id,
+                    "image/jpeg",
+                    vec![0xff; crate::cover_art::MAX_COVER_BYTES + 1],
+                ),
+                413,
+            ),
+            (upload(album_id, "image/png", png.clone()), 204),
+        ] {
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status().as_u16(), status);
+        }
+        let first = state
+            .metadata
+            .db
+            .cover_path_for_album_id(album_id)
+            .expect("cover path")
+            .expect("cover set");
+        let req = test::TestRequest::get()
+            .uri(&format!("/albums/{album_id}/cover"))
+            .to_request();
+        assert_eq!(test::call_and_read_body(&app, req).await, png);
+
+        let resp = test::call_service(&app, upload(album_id, "image/jpeg", jpeg.clone())).await;
+        assert_eq!(resp.status().as_u16(), 204);
+        let req = test::TestRequest::get()
+            .uri(&format!("/albums/{album_id}/cover"))
+            .to_request();
+        assert_eq!(test::call_and_read_body(&app, req).await, jpeg);
+        let root = state.library.read().unwrap().root().to_path_buf();
+        assert!(!root.join(first).exists());
+    }
+
+    #[actix_web::test]
+    async fn search_returns_typed_hits_and_rejects_empty_query() {
+        let state = make_state();
+        state
+            .metadata
+            .db
+            .upsert_track(&crate::metadata_db::TrackRecord {
+                path: "/music/so-what.flac".to_string(),
+                file_name: "so-what.flac".to_string(),
+                title: Some("So What".to_string()),
+                artist: Some("Miles Davis".to_string()),
+                album_artist: None,
+                album: Some("Kind of Blue".to_string()),
+                album_uuid: None,
+                track_number: None,
+                disc_number: None,
+                disc_title: None,
+                year: None,
+                genres: Vec::new(),
+                composer: None,
+                conductor: None,
+                work: None,
+                movement: None,
+                movement_number: None,
+                duration_ms: None,
+                sample_rate: None,
+                bit_depth: None,
+                format: None,
+                mtime_ms: 1,
+                size_bytes: 1,
+            })
+            .expect("upsert track");
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .service(api::library_search),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/search?q=miles&kind=artist")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["items"].as_array().unwrap().len(), 1);
+        assert_eq!(body["items"][0]["kind"], "artist");
+        assert_eq!(body["items"][0]["name"], "Miles Davis");
+
+        let req = test::TestRequest::get().uri("/search?q=miles").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["items"].as_array().unwrap().len(), 3);
+
+        let req = test::TestRequest::get().uri("/search?q=%20*").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
     #[actix_web::test]
     async fn library_list_root_ok() {
         let state = make_state();
@@ -156,4 +1050,194 @@ mod tests {
         let resp = test::call_service(&app, req).await;
         assert!(resp.status().is_success());
     }
+
+    #[actix_web::test]
+    async fn health_reports_bridges_without_probing() {
+        let state = make_state();
+        for (port, id) in (5556..).zip(["a", "b", "c"]) {
+            state.providers.bridge.bridges.lock().unwrap().bridges.push(
+                crate::config::BridgeConfigResolved {
+                    id: id.to_string(),
+                    name: id.to_uppercase(),
+                    http_addr: ([127, 0, 0, 1], port).into(),
+                    room: None,
+                    zone: None,
+                },
+            );
+        }
+        {
+            let mut health = state.providers.bridge.health.lock().unwrap();
+            health.insert(
+                "a".to_string(),
+                crate::state::BridgeHealth {
+                    online: false,
+                    last_error: Some("connection refused".to_string()),
+                },
+            );
+            health.insert(
+                "b".to_string(),
+                crate::state::BridgeHealth {
+                    online: true,
+                    last_error: None,
+                },
+            );
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .service(api::health::health),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/health").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["status"], "ok");
+        let states: Vec<_> = body["bridges"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| (b["id"].as_str().unwrap(), b["state"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            states,
+            [("a", "offline"), ("b", "online"), ("c", "pending")]
+        );
+        assert_eq!(body["bridges"][0]["error"], "connection refused");
+    }
+
+    #[actix_web::test]
+    async fn bridges_list_reports_timing_and_last_probe() {
+        let state = make_state();
+        state
+            .providers
+            .bridge
+            .discovered_bridges
+            .lock()
+            .unwrap()
+            .insert(
+                "den".to_string(),
+                crate::state::DiscoveredBridge {
+                    bridge: crate::config::BridgeConfigResolved {
+                        id: "den".to_string(),
+                        name: "Den".to_string(),
+                        http_addr: ([192, 168, 1, 60], 5556).into(),
+                        room: Some("Den".to_string()),
+                        zone: None,
+                    },
+                    last_seen: std::time::Instant::now(),
+                },
+            );
+        state.providers.bridge.probes.lock().unwrap().insert(
+            "den".to_string(),
+            crate::state::BridgeProbe {
+                ok: false,
+                checked_at: std::time::UNIX_EPOCH + std::time::Duration::from_secs(5),
+                latency: std::time::Duration::from_millis(12),
+                error: Some("timed out".to_string()),
+            },
+        );
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .service(api::bridges_list),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/bridges").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["discovery"]["health_interval_ms"], 5000);
+        assert_eq!(body["discovery"]["jitter_percent"], 20);
+        let bridge = &body["bridges"][0];
+        assert_eq!(bridge["id"], "den");
+        assert_eq!(bridge["discovered"], true);
+        assert_eq!(bridge["state"], "pending");
+        assert_eq!(bridge["http_addr"], "192.168.1.60:5556");
+        assert_eq!(bridge["room"], "Den");
+        assert!(bridge["zone"].is_null());
+        assert_eq!(bridge["last_probe"]["checked_at_ms"], 5000);
+        assert_eq!(bridge["last_probe"]["latency_ms"], 12);
+        assert_eq!(bridge["last_probe"]["error"], "timed out");
+    }
+
+    #[actix_web::test]
+    async fn outputs_filter_and_sort_by_room() {
+        let output = |id: &str, room: Option<&str>, zone: Option<&str>| crate::models::OutputInfo {
+            id: id.to_string(),
+            kind: "bridge".to_string(),
+            name: id.to_string(),
+            state: "online".to_string(),
+            provider_id: None,
+            provider_name: None,
+            supported_rates: None,
+            formats: None,
+            capabilities: crate::models::OutputCapabilities {
+                device_select: true,
+                volume: false,
+            },
+            room: room.map(str::to_string),
+            zone: zone.map(str::to_string),
+        };
+        let resp = crate::models::OutputsResponse {
+            active_id: Some("porch".to_string()),
+            outputs: vec![
+                output("porch", None, None),
+                output("study", Some("Study"), Some("Upstairs")),
+                output("tv", Some("Lounge"), Some("Downstairs")),
+                output("amp", Some("Lounge"), Some("Downstairs")),
+            ],
+        };
+        let ids = |resp: &crate::models::OutputsResponse| {
+            resp.outputs
+                .iter()
+                .map(|o| o.id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let sorted = api::outputs::filter_outputs(
+            resp.clone(),
+            &api::outputs::OutputsQuery {
+                sort: Some(api::outputs::OutputsSort::Room),
+                ..Default::default()
+            },
+        );
+        assert_eq!(ids(&sorted), ["amp", "tv", "study", "porch"]);
+
+        let lounge = api::outputs::filter_outputs(
+            resp,
+            &api::outputs::OutputsQuery {
+                room: Some("lounge".to_string()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(ids(&lounge), ["tv", "amp"]);
+        assert_eq!(lounge.active_id, None);
+    }
+
+    #[actix_web::test]
+    async fn log_level_set_updates_filter_and_rejects_bad_level() {
+        let log_filter =
+            actix_web::web::Data::new(audio_player::log_filter::LogFilterControl::detached("info"));
+        let app = test::init_service(
+            App::new()
+                .app_data(log_filter.clone())
+                .service(api::log_level_get)
+                .service(api::log_level_set),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/admin/log-level")
+            .set_json(serde_json::json!({ "targets": { "audio_server::library": "debug" } }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert_eq!(log_filter.current(), "info,audio_server::library=debug");
+
+        let req = test::TestRequest::post()
+            .uri("/admin/log-level")
+            .set_json(serde_json::json!({ "level": "loud" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
 }
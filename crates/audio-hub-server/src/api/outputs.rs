@@ -1,15 +1,21 @@
 //! Output-related API handlers.
 
 use actix_web::{HttpResponse, Responder, get, post, web};
+use audio_player::resample::ResampleQuality;
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
 
 use crate::bridge_manager::parse_output_id;
 use crate::bridge_manager::{merge_bridges, parse_provider_id};
 use crate::bridge_transport::BridgeTransportClient;
+use crate::metadata_db::OutputPreferences;
 use crate::models::{
-    BridgeUnregisterRequest, BridgeUnregisterResponse, OutputSelectRequest, OutputSettings,
-    OutputSettingsResponse, OutputsResponse, ProviderOutputs, ProvidersResponse,
+    BridgeLogsResponse, BridgeProbeResult, BridgeSummary, BridgeUnregisterRequest,
+    BridgeUnregisterResponse, BridgesResponse, DiscoverySettings, OutputHideRequest,
+    OutputSelectRequest, OutputSettings, OutputSettingsResponse, OutputsResponse, ProviderOutputs,
+    ProvidersResponse,
 };
-use crate::state::AppState;
+use crate::state::{AppState, OutputSettingsState};
 
 #[utoipa::path(
     get,
@@ -50,19 +56,74 @@ pub async fn provider_outputs_list(
     }
 }
 
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, ToSchema)]
+/// Query for filtering and ordering the output list.
+pub struct OutputsQuery {
+    /// Only outputs in this room (case-insensitive).
+    #[serde(default)]
+    pub room: Option<String>,
+    /// Only outputs in this zone (case-insensitive).
+    #[serde(default)]
+    pub zone: Option<String>,
+    /// Ordering: `room` (zone, room, then name; unlabeled outputs last) or `name`.
+    #[serde(default)]
+    pub sort: Option<OutputsSort>,
+}
+
+/// Output list ordering.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputsSort {
+    /// Group by zone and room, then by name.
+    Room,
+    /// Alphabetical by name.
+    Name,
+}
+
 #[utoipa::path(
     get,
     path = "/outputs",
+    params(OutputsQuery),
     responses(
-        (status = 200, description = "Available outputs", body = OutputsResponse)
+        (status = 200, description = "Available outputs", body = OutputsResponse),
+        (status = 400, description = "Invalid sort")
     )
 )]
 #[get("/outputs")]
-/// List all outputs across providers.
-pub async fn outputs_list(state: web::Data<AppState>) -> impl Responder {
-    HttpResponse::Ok().json(normalize_outputs_response(
-        state.output.controller.list_outputs(&state).await,
-    ))
+/// List all outputs across providers, optionally filtered and sorted by room/zone.
+pub async fn outputs_list(
+    state: web::Data<AppState>,
+    query: web::Query<OutputsQuery>,
+) -> impl Responder {
+    let resp = normalize_outputs_response(state.output.controller.list_outputs(&state).await);
+    HttpResponse::Ok().json(filter_outputs(resp, &query))
+}
+
+/// Apply room/zone filters and ordering to an output listing.
+pub(crate) fn filter_outputs(mut resp: OutputsResponse, query: &OutputsQuery) -> OutputsResponse {
+    let matches = |label: Option<&str>, wanted: Option<&str>| match wanted.map(str::trim) {
+        Some(wanted) if !wanted.is_empty() => label.is_some_and(|l| l.eq_ignore_ascii_case(wanted)),
+        _ => true,
+    };
+    resp.outputs.retain(|o| {
+        matches(o.room.as_deref(), query.room.as_deref())
+            && matches(o.zone.as_deref(), query.zone.as_deref())
+    });
+    let name_key = |o: &crate::models::OutputInfo| o.name.to_lowercase();
+    match query.sort {
+        Some(OutputsSort::Room) => resp.outputs.sort_by_cached_key(|o| {
+            (
+                o.zone.is_none(),
+                o.zone.as_deref().map(str::to_lowercase),
+                o.room.is_none(),
+                o.room.as_deref().map(str::to_lowercase),
+                name_key(o),
+            )
+        }),
+        Some(OutputsSort::Name) => resp.outputs.sort_by_cached_key(name_key),
+        None => {}
+    }
+    normalize_outputs_response(resp)
 }
 
 #[utoipa::path(
@@ -110,46 +171,155 @@ pub async fn outputs_settings(state: web::Data<AppState>) -> impl Responder {
     path = "/outputs/settings",
     request_body = OutputSettings,
     responses(
-        (status = 200, description = "Settings saved", body = OutputSettings)
+        (status = 200, description = "Settings saved", body = OutputSettings),
+        (status = 400, description = "Invalid settings")
     )
 )]
 #[post("/outputs/settings")]
-/// Update output settings (disabled outputs and renames).
+/// Update output settings (disabled outputs, renames, exclusive mode, pre-roll and volume trim).
 pub async fn outputs_settings_update(
     state: web::Data<AppState>,
     body: web::Json<OutputSettings>,
 ) -> impl Responder {
-    let new_settings = crate::state::OutputSettingsState::from_api(&body);
+    if let Some((id, ms)) = body
+        .preroll_silence_ms
+        .iter()
+        .find(|(_, ms)| **ms > crate::config::MAX_PREROLL_SILENCE_MS)
+    {
+        return HttpResponse::BadRequest().body(format!(
+            "preroll_silence_ms for {id} must be at most {} (got {ms})",
+            crate::config::MAX_PREROLL_SILENCE_MS
+        ));
+    }
+    if let Some((id, db)) = body
+        .volume_trim_db
+        .iter()
+        .find(|(_, db)| !crate::config::volume_trim_in_range(**db))
     {
+        return HttpResponse::BadRequest().body(format!(
+            "volume_trim_db for {id} must be between {} and {} dB (got {db})",
+            crate::config::MIN_VOLUME_TRIM_DB,
+            crate::config::MAX_VOLUME_TRIM_DB
+        ));
+    }
+    let mut new_settings = OutputSettingsState::from_api(&body);
+    new_settings.preferences = state
+        .output_settings
+        .lock()
+        .map(|settings| settings.preferences.clone())
+        .unwrap_or_default();
+    if let Err(resp) = store_output_settings(&state, &new_settings) {
+        return resp;
+    }
+    stop_disabled_active_output(&state, &new_settings);
+    reapply_active_bridge_options(&state, &new_settings, None).await;
+
+    state.events.outputs_changed();
+    HttpResponse::Ok().json(new_settings.to_api())
+}
+
+#[utoipa::path(
+    get,
+    path = "/outputs/{id}/preferences",
+    params(
+        ("id" = String, Path, description = "Output id")
+    ),
+    responses(
+        (status = 200, description = "Output preferences", body = OutputPreferences)
+    )
+)]
+#[get("/outputs/{id}/preferences")]
+/// Return the playback preferences stored for an output (all unset when none are).
+pub async fn outputs_preferences(
+    state: web::Data<AppState>,
+    id: web::Path<String>,
+) -> impl Responder {
+    let prefs = state
+        .output_settings
+        .lock()
+        .ok()
+        .and_then(|settings| settings.preferences.get(id.trim()).cloned())
+        .unwrap_or_default();
+    HttpResponse::Ok().json(prefs)
+}
+
+#[utoipa::path(
+    post,
+    path = "/outputs/{id}/preferences",
+    params(
+        ("id" = String, Path, description = "Output id")
+    ),
+    request_body = OutputPreferences,
+    responses(
+        (status = 200, description = "Preferences saved", body = OutputPreferences),
+        (status = 400, description = "Invalid preferences"),
+        (status = 500, description = "Failed to store preferences")
+    )
+)]
+#[post("/outputs/{id}/preferences")]
+/// Replace the playback preferences for an output.
+///
+/// They are stored in the metadata DB and sent to the bridge each time the output is selected
+/// (and right away when it is the active output). Unset fields use the bridge's defaults.
+pub async fn outputs_preferences_update(
+    state: web::Data<AppState>,
+    id: web::Path<String>,
+    body: web::Json<OutputPreferences>,
+) -> impl Responder {
+    let output_id = id.trim().to_string();
+    if output_id.is_empty() {
+        return HttpResponse::BadRequest().body("output id is required");
+    }
+    let prefs = body.into_inner();
+    if let Some(name) = prefs.resample_quality.as_deref()
+        && ResampleQuality::from_name(name).is_none()
+    {
+        return HttpResponse::BadRequest().body(format!(
+            "resample_quality must be fast, balanced or high (got {name})"
+        ));
+    }
+    if let Some(bits) = prefs.bit_depth.filter(|bits| ![16, 24, 32].contains(bits)) {
+        return HttpResponse::BadRequest()
+            .body(format!("bit_depth must be 16, 24 or 32 (got {bits})"));
+    }
+    if let Some(rate) = prefs
+        .max_sample_rate
+        .filter(|rate| *rate < MIN_PREFERRED_SAMPLE_RATE)
+    {
+        return HttpResponse::BadRequest().body(format!(
+            "max_sample_rate must be at least {MIN_PREFERRED_SAMPLE_RATE} (got {rate})"
+        ));
+    }
+    if let Err(err) = state.metadata.db.set_output_preferences(&output_id, &prefs) {
+        return HttpResponse::InternalServerError().body(format!("{err:#}"));
+    }
+    let new_settings = {
         let mut guard = state
             .output_settings
             .lock()
             .unwrap_or_else(|err| err.into_inner());
-        *guard = new_settings.clone();
-    }
-
-    if let Some(path) = state.config_path.as_ref() {
-        if let Err(err) = crate::config::update_output_settings(path, &new_settings.to_config()) {
-            return HttpResponse::InternalServerError().body(format!("{err:#}"));
+        if prefs.is_empty() {
+            guard.preferences.remove(&output_id);
+        } else {
+            guard.preferences.insert(output_id.clone(), prefs.clone());
         }
-    } else {
-        return HttpResponse::InternalServerError().body("config path unavailable");
-    }
+        guard.clone()
+    };
+    tracing::info!(output_id = %output_id, ?prefs, "output preferences updated");
+    reapply_active_bridge_options(&state, &new_settings, Some(&output_id)).await;
+    HttpResponse::Ok().json(prefs)
+}
 
-    if let Ok(mut bridges) = state.providers.bridge.bridges.lock() {
-        if let Some(active_id) = bridges.active_output_id.as_ref() {
-            if new_settings.disabled.contains(active_id) {
-                bridges.active_output_id = None;
-                bridges.active_bridge_id = None;
-                if let Ok(player) = state.providers.bridge.player.lock() {
-                    let _ = player.cmd_tx.send(crate::bridge::BridgeCommand::Stop);
-                }
-            }
-        }
-    }
+/// Lowest `max_sample_rate` accepted in output preferences.
+const MIN_PREFERRED_SAMPLE_RATE: u32 = 8_000;
 
-    // Re-apply exclusive mode for the active bridge output immediately so users
-    // don't need to reselect the output for the change to take effect.
+/// Send `settings` to the active bridge output's bridge so changes apply without reselecting
+/// the output. With `only` set, nothing is sent unless that output is the active one.
+async fn reapply_active_bridge_options(
+    state: &AppState,
+    settings: &OutputSettingsState,
+    only: Option<&str>,
+) {
     let active_bridge_target = {
         let bridges = state
             .providers
@@ -172,10 +342,12 @@ pub async fn outputs_settings_update(
             None
         }
     };
-    if let Some((http_addr, device_id, active_output_id)) = active_bridge_target {
-        let exclusive = new_settings.is_exclusive(&active_output_id);
+    if let Some((http_addr, device_id, active_output_id)) = active_bridge_target
+        && only.is_none_or(|id| id == active_output_id)
+    {
+        let options = settings.select_options(&active_output_id);
         if let Err(err) = BridgeTransportClient::new(http_addr)
-            .set_device_by_id(&device_id, Some(exclusive))
+            .set_device_by_id(&device_id, options)
             .await
         {
             tracing::warn!(
@@ -183,15 +355,92 @@ pub async fn outputs_settings_update(
                 device_id = %device_id,
                 bridge_addr = %http_addr,
                 error = %err,
-                "failed to re-apply exclusive mode for active bridge output"
+                "failed to re-apply output settings for active bridge output"
             );
         }
     }
+}
 
+#[utoipa::path(
+    post,
+    path = "/outputs/{id}/hide",
+    params(
+        ("id" = String, Path, description = "Output id")
+    ),
+    request_body(content = OutputHideRequest, description = "Defaults to hiding the output"),
+    responses(
+        (status = 200, description = "Settings saved", body = OutputSettings),
+        (status = 400, description = "Invalid output id")
+    )
+)]
+#[post("/outputs/{id}/hide")]
+/// Hide an output (or show it again with `hidden: false`).
+///
+/// Hidden outputs are left out of `/outputs` and output streams, cannot be selected or bound
+/// to a session, and stop playing if they were active.
+pub async fn outputs_hide(
+    state: web::Data<AppState>,
+    id: web::Path<String>,
+    body: Option<web::Json<OutputHideRequest>>,
+) -> impl Responder {
+    let output_id = id.trim().to_string();
+    if output_id.is_empty() {
+        return HttpResponse::BadRequest().body("output id is required");
+    }
+    let hidden = body.map(|b| b.hidden).unwrap_or(true);
+    let new_settings = {
+        let guard = state
+            .output_settings
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        let mut settings = guard.clone();
+        if !settings.set_disabled(&output_id, hidden) {
+            return HttpResponse::Ok().json(settings.to_api());
+        }
+        settings
+    };
+    if let Err(resp) = store_output_settings(&state, &new_settings) {
+        return resp;
+    }
+    stop_disabled_active_output(&state, &new_settings);
+    tracing::info!(output_id = %output_id, hidden, "output visibility updated");
     state.events.outputs_changed();
     HttpResponse::Ok().json(new_settings.to_api())
 }
 
+/// Apply `settings` and persist them to the config file.
+fn store_output_settings(
+    state: &AppState,
+    settings: &OutputSettingsState,
+) -> Result<(), HttpResponse> {
+    {
+        let mut guard = state
+            .output_settings
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        *guard = settings.clone();
+    }
+    let Some(path) = state.config_path.as_ref() else {
+        return Err(HttpResponse::InternalServerError().body("config path unavailable"));
+    };
+    crate::config::update_output_settings(path, &settings.to_config())
+        .map_err(|err| HttpResponse::InternalServerError().body(format!("{err:#}")))
+}
+
+/// Stop and deselect the active output when `settings` hide it.
+fn stop_disabled_active_output(state: &AppState, settings: &OutputSettingsState) {
+    if let Ok(mut bridges) = state.providers.bridge.bridges.lock()
+        && let Some(active_id) = bridges.active_output_id.as_ref()
+        && settings.is_disabled(active_id)
+    {
+        bridges.active_output_id = None;
+        bridges.active_bridge_id = None;
+        if let Ok(player) = state.providers.bridge.player.lock() {
+            let _ = player.cmd_tx.send(crate::bridge::BridgeCommand::Stop);
+        }
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/providers/{id}/refresh",
@@ -218,6 +467,52 @@ pub async fn provider_refresh(state: web::Data<AppState>, id: web::Path<String>)
     }
 }
 
+#[derive(Clone, Debug, Deserialize, IntoParams, ToSchema)]
+/// Query for fetching buffered bridge logs.
+pub struct BridgeLogsQuery {
+    /// Minimum level (`error`, `warn`, `info`, `debug`, `trace`).
+    #[serde(default)]
+    pub level: Option<String>,
+    /// Only return entries newer than this bridge sequence number.
+    #[serde(default)]
+    pub after: Option<u64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/providers/{id}/logs",
+    params(
+        ("id" = String, Path, description = "Bridge provider id"),
+        BridgeLogsQuery
+    ),
+    responses(
+        (status = 200, description = "Buffered bridge logs", body = BridgeLogsResponse),
+        (status = 400, description = "Unknown provider"),
+        (status = 502, description = "Bridge unreachable")
+    )
+)]
+#[get("/providers/{id}/logs")]
+/// Fetch the in-memory log ring of a bridge provider.
+pub async fn provider_logs(
+    state: web::Data<AppState>,
+    id: web::Path<String>,
+    query: web::Query<BridgeLogsQuery>,
+) -> impl Responder {
+    let Some(address) = provider_address(&state, id.as_str()) else {
+        return HttpResponse::BadRequest().body("unknown bridge provider");
+    };
+    let Ok(http_addr) = address.parse::<std::net::SocketAddr>() else {
+        return HttpResponse::BadRequest().body("invalid bridge address");
+    };
+    match BridgeTransportClient::new(http_addr)
+        .logs(query.level.as_deref(), query.after)
+        .await
+    {
+        Ok(resp) => HttpResponse::Ok().json(resp),
+        Err(err) => HttpResponse::BadGateway().body(format!("{err:#}")),
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/outputs/select",
@@ -282,6 +577,9 @@ pub async fn bridge_unregister(
     if let Ok(mut cache) = state.providers.bridge.status_cache.lock() {
         cache.remove(&bridge_id);
     }
+    if let Ok(mut probes) = state.providers.bridge.probes.lock() {
+        probes.remove(&bridge_id);
+    }
     if let Ok(mut done) = state.providers.bridge.stop_on_join_done.lock() {
         done.remove(&bridge_id);
     }
@@ -340,6 +638,61 @@ pub async fn bridge_unregister(
     })
 }
 
+#[utoipa::path(
+    get,
+    path = "/bridges",
+    responses(
+        (status = 200, description = "Known bridges and discovery timing", body = BridgesResponse)
+    )
+)]
+#[get("/bridges")]
+/// List bridges with their reachability, last health check and the discovery timing in effect.
+pub async fn bridges_list(state: web::Data<AppState>) -> impl Responder {
+    let (merged, configured) = {
+        let bridges_state = state.providers.bridge.bridges.lock().unwrap();
+        let discovered = state.providers.bridge.discovered_bridges.lock().unwrap();
+        let configured: Vec<String> = bridges_state.bridges.iter().map(|b| b.id.clone()).collect();
+        (
+            merge_bridges(&bridges_state.bridges, &discovered),
+            configured,
+        )
+    };
+    let health = state.providers.bridge.health.lock().unwrap().clone();
+    let probes = state.providers.bridge.probes.lock().unwrap().clone();
+    let bridges = merged
+        .into_iter()
+        .map(|bridge| BridgeSummary {
+            discovered: !configured.contains(&bridge.id),
+            room: bridge.room.clone(),
+            zone: bridge.zone.clone(),
+            state: crate::api::health::health_state(health.get(&bridge.id)).to_string(),
+            last_probe: probes.get(&bridge.id).map(|probe| BridgeProbeResult {
+                ok: probe.ok,
+                checked_at_ms: probe
+                    .checked_at
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_millis() as u64)
+                    .unwrap_or(0),
+                latency_ms: probe.latency.as_millis() as u64,
+                error: probe.error.clone(),
+            }),
+            http_addr: bridge.http_addr.to_string(),
+            id: bridge.id,
+            name: bridge.name,
+        })
+        .collect();
+    let timing = crate::discovery::timing();
+    HttpResponse::Ok().json(BridgesResponse {
+        discovery: DiscoverySettings {
+            health_interval_ms: timing.health_interval.as_millis() as u64,
+            health_timeout_ms: timing.health_timeout.as_millis() as u64,
+            stale_after_ms: timing.stale_after.as_millis() as u64,
+            jitter_percent: timing.jitter_percent,
+        },
+        bridges,
+    })
+}
+
 /// Ensure `active_id` points to an existing output entry.
 pub(crate) fn normalize_outputs_response(mut resp: OutputsResponse) -> OutputsResponse {
     if let Some(active_id) = resp.active_id.as_deref() {
@@ -0,0 +1,173 @@
+//! Playlist API handlers.
+
+use actix_web::{HttpResponse, Responder, get, post, put, web};
+
+use crate::models::{PlaylistDetailResponse, PlaylistListResponse, PlaylistWriteRequest};
+use crate::state::AppState;
+
+/// Validate a create/replace payload, returning the trimmed name.
+fn validate_playlist_request(
+    state: &web::Data<AppState>,
+    body: &PlaylistWriteRequest,
+) -> Result<String, HttpResponse> {
+    let name = body.name.trim();
+    if name.is_empty() {
+        return Err(HttpResponse::BadRequest().body("playlist name is required"));
+    }
+    let mut referenced = body.track_ids.clone();
+    referenced.extend(body.cover_track_id);
+    match state.metadata.db.unknown_track_ids(&referenced) {
+        Ok(unknown) if unknown.is_empty() => Ok(name.to_string()),
+        Ok(unknown) => {
+            let ids = unknown
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            Err(HttpResponse::BadRequest().body(format!("unknown track ids: {ids}")))
+        }
+        Err(err) => Err(HttpResponse::InternalServerError().body(err.to_string())),
+    }
+}
+
+/// Build the detail response for a playlist, or 404 when it does not exist.
+fn playlist_detail(state: &web::Data<AppState>, playlist_id: i64) -> HttpResponse {
+    let playlist = match state.metadata.db.playlist_summary(playlist_id) {
+        Ok(Some(playlist)) => playlist,
+        Ok(None) => return HttpResponse::NotFound().body("playlist not found"),
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+    match state.metadata.db.playlist_tracks(playlist_id) {
+        Ok(tracks) => HttpResponse::Ok().json(PlaylistDetailResponse { playlist, tracks }),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/playlists",
+    responses(
+        (status = 200, description = "Playlist list", body = PlaylistListResponse)
+    )
+)]
+#[get("/playlists")]
+/// List playlists ordered by name.
+pub async fn playlists_list(state: web::Data<AppState>) -> impl Responder {
+    match state.metadata.db.list_playlists() {
+        Ok(items) => HttpResponse::Ok().json(PlaylistListResponse { items }),
+        Err(err) => {
+            tracing::warn!(error = %err, "playlists list failed");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/playlists",
+    request_body = PlaylistWriteRequest,
+    responses(
+        (status = 201, description = "Playlist created", body = PlaylistDetailResponse),
+        (status = 400, description = "Missing name or unknown track ids")
+    )
+)]
+#[post("/playlists")]
+/// Create a playlist.
+pub async fn playlists_create(
+    state: web::Data<AppState>,
+    body: web::Json<PlaylistWriteRequest>,
+) -> impl Responder {
+    let name = match validate_playlist_request(&state, &body) {
+        Ok(name) => name,
+        Err(resp) => return resp,
+    };
+    let playlist_id =
+        match state
+            .metadata
+            .db
+            .create_playlist(&name, &body.track_ids, body.cover_track_id)
+        {
+            Ok(id) => id,
+            Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+        };
+    let mut resp = playlist_detail(&state, playlist_id);
+    if resp.status().is_success() {
+        *resp.status_mut() = actix_web::http::StatusCode::CREATED;
+    }
+    resp
+}
+
+#[utoipa::path(
+    get,
+    path = "/playlists/{id}",
+    params(
+        ("id" = i64, Path, description = "Playlist id")
+    ),
+    responses(
+        (status = 200, description = "Playlist detail", body = PlaylistDetailResponse),
+        (status = 404, description = "Playlist not found")
+    )
+)]
+#[get("/playlists/{id}")]
+/// Return a playlist with its entries in order.
+pub async fn playlists_get(state: web::Data<AppState>, id: web::Path<i64>) -> impl Responder {
+    playlist_detail(&state, id.into_inner())
+}
+
+#[utoipa::path(
+    put,
+    path = "/playlists/{id}",
+    params(
+        ("id" = i64, Path, description = "Playlist id")
+    ),
+    request_body = PlaylistWriteRequest,
+    responses(
+        (status = 200, description = "Playlist replaced", body = PlaylistDetailResponse),
+        (status = 400, description = "Missing name or unknown track ids"),
+        (status = 404, description = "Playlist not found")
+    )
+)]
+#[put("/playlists/{id}")]
+/// Replace a playlist's name, entries and cover.
+pub async fn playlists_update(
+    state: web::Data<AppState>,
+    id: web::Path<i64>,
+    body: web::Json<PlaylistWriteRequest>,
+) -> impl Responder {
+    let playlist_id = id.into_inner();
+    let name = match validate_playlist_request(&state, &body) {
+        Ok(name) => name,
+        Err(resp) => return resp,
+    };
+    match state.metadata.db.update_playlist(
+        playlist_id,
+        &name,
+        &body.track_ids,
+        body.cover_track_id,
+    ) {
+        Ok(true) => playlist_detail(&state, playlist_id),
+        Ok(false) => HttpResponse::NotFound().body("playlist not found"),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/playlists/{id}",
+    params(
+        ("id" = i64, Path, description = "Playlist id")
+    ),
+    responses(
+        (status = 204, description = "Playlist deleted"),
+        (status = 404, description = "Playlist not found")
+    )
+)]
+#[actix_web::delete("/playlists/{id}")]
+/// Delete a playlist.
+pub async fn playlists_delete(state: web::Data<AppState>, id: web::Path<i64>) -> impl Responder {
+    match state.metadata.db.delete_playlist(id.into_inner()) {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().body("playlist not found"),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
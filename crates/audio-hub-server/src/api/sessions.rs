@@ -3,6 +3,7 @@
 use actix_web::http::header;
 use actix_web::web::Bytes;
 use actix_web::{Error, HttpRequest, HttpResponse, Responder, get, post, web};
+use audio_bridge_types::ChapterRequest;
 use futures_util::{Stream, stream::unfold};
 use serde::Deserialize;
 use std::collections::HashSet;
@@ -17,11 +18,12 @@ use utoipa::ToSchema;
 use crate::events::HubEvent;
 use crate::models::{
     LocalPlaybackPlayResponse, OutputInUseError, QueueAddRequest, QueueClearRequest,
-    QueuePlayFromRequest, QueueRemoveRequest, QueueResponse, SessionCreateRequest,
-    SessionCreateResponse, SessionDeleteResponse, SessionDetailResponse, SessionHeartbeatRequest,
-    SessionLockInfo, SessionLocksResponse, SessionMuteRequest, SessionReleaseOutputResponse,
-    SessionSelectOutputRequest, SessionSelectOutputResponse, SessionSummary, SessionVolumeResponse,
-    SessionVolumeSetRequest, SessionsListResponse, StatusResponse,
+    QueuePlayFromRequest, QueuePlaylistRequest, QueueRemoveRequest, QueueResponse,
+    SessionCreateRequest, SessionCreateResponse, SessionDeleteResponse, SessionDetailResponse,
+    SessionHeartbeatRequest, SessionLockInfo, SessionLocksResponse, SessionMuteRequest,
+    SessionReleaseOutputResponse, SessionSelectOutputRequest, SessionSelectOutputResponse,
+    SessionSummary, SessionVolumeResponse, SessionVolumeSetRequest, SessionsListResponse,
+    StatusResponse,
 };
 use crate::session_playback_manager::SessionPlaybackError;
 use crate::state::AppState;
@@ -335,6 +337,14 @@ pub async fn sessions_select_output(
     if output_id.is_empty() {
         return HttpResponse::BadRequest().body("output_id is required");
     }
+    if state
+        .output_settings
+        .lock()
+        .map(|s| s.is_disabled(&output_id))
+        .unwrap_or(false)
+    {
+        return HttpResponse::BadRequest().body("output is disabled");
+    }
     if output_id.starts_with("browser:") {
         let Some(session) = crate::session_registry::get_session(&session_id) else {
             tracing::warn!(session_id = %session_id, output_id = %output_id, reason = "session_not_found", "select output failed");
@@ -917,6 +927,39 @@ pub async fn sessions_seek(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/sessions/{id}/chapter",
+    params(
+        ("id" = String, Path, description = "Session id")
+    ),
+    request_body = ChapterRequest,
+    responses(
+        (status = 200, description = "Chapter seek requested"),
+        (status = 404, description = "Session or chapter not found"),
+        (status = 409, description = "Current track has no chapters, or session output is in use by another session"),
+        (status = 503, description = "Session has no output selected or output is unavailable")
+    )
+)]
+#[post("/sessions/{id}/chapter")]
+/// Jump to a chapter of the current track, by index or relative to the current chapter.
+pub async fn sessions_chapter(
+    state: web::Data<AppState>,
+    id: web::Path<String>,
+    body: web::Json<ChapterRequest>,
+) -> impl Responder {
+    let session_id = id.into_inner();
+    match state
+        .output
+        .session_playback
+        .chapter(&state, &session_id, &body)
+        .await
+    {
+        Ok(()) => HttpResponse::Ok().finish(),
+        Err(err) => err.into_response(),
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/sessions/{id}/stop",
@@ -1068,6 +1111,16 @@ fn log_session_status_error(
                 "session status request failed"
             );
         }
+        SessionPlaybackError::NoChapters { .. } | SessionPlaybackError::ChapterNotFound { .. } => {
+            tracing::warn!(
+                endpoint,
+                session_id,
+                active_output_id,
+                has_cached_status,
+                reason = "chapter_unavailable",
+                "session status request failed"
+            );
+        }
     }
 }
 
@@ -1075,12 +1128,16 @@ fn log_session_status_error(
 fn session_should_periodic_refresh(session_id: &str) -> bool {
     crate::session_registry::get_session(session_id)
         .and_then(|s| s.active_output_id)
-        .map(|id| id.starts_with("cast:"))
+        .map(|id| {
+            ["cast:", "airplay:", "dlna:", "snapcast:", "group:"]
+                .iter()
+                .any(|prefix| id.starts_with(prefix))
+        })
         .unwrap_or(false)
 }
 
 /// Return whether session is in local playback mode.
-fn is_local_session(session_id: &str) -> bool {
+pub(crate) fn is_local_session(session_id: &str) -> bool {
     matches!(
         crate::session_registry::get_session(session_id).map(|s| s.mode),
         Some(crate::models::SessionMode::Local)
@@ -1088,7 +1145,10 @@ fn is_local_session(session_id: &str) -> bool {
 }
 
 /// Resolve and canonicalize track path for a metadata track id.
-fn canonical_track_path_by_id(state: &web::Data<AppState>, track_id: i64) -> Option<PathBuf> {
+pub(crate) fn canonical_track_path_by_id(
+    state: &web::Data<AppState>,
+    track_id: i64,
+) -> Option<PathBuf> {
     let raw_path = match state.metadata.db.track_path_for_id(track_id) {
         Ok(Some(path)) => path,
         Ok(None) => {
@@ -1142,11 +1202,7 @@ fn build_local_playback_response(
 ) -> Result<LocalPlaybackPlayResponse, HttpResponse> {
     let conn = req.connection_info();
     let base_url = format!("{}://{}", conn.scheme(), conn.host());
-    let url = format!(
-        "{}/stream/track/{}",
-        base_url.trim_end_matches('/'),
-        track_id
-    );
+    let url = crate::stream_url::track_stream_url(&base_url, track_id);
     Ok(LocalPlaybackPlayResponse { url, track_id })
 }
 
@@ -1246,6 +1302,50 @@ pub async fn sessions_queue_add_next(
     HttpResponse::Ok().body(format!("added {added}"))
 }
 
+#[utoipa::path(
+    post,
+    path = "/sessions/{id}/queue/playlist",
+    params(
+        ("id" = String, Path, description = "Session id")
+    ),
+    request_body = QueuePlaylistRequest,
+    responses(
+        (status = 200, description = "Queue updated"),
+        (status = 404, description = "Session or playlist not found")
+    )
+)]
+#[post("/sessions/{id}/queue/playlist")]
+/// Append (or insert next) every entry of a playlist to a session queue.
+pub async fn sessions_queue_playlist(
+    state: web::Data<AppState>,
+    id: web::Path<String>,
+    body: web::Json<QueuePlaylistRequest>,
+) -> impl Responder {
+    let session_id = id.into_inner();
+    if let Err(resp) = require_session(&session_id) {
+        return resp;
+    }
+    let track_ids = match state.metadata.db.playlist_track_ids(body.playlist_id) {
+        Ok(Some(track_ids)) => track_ids,
+        Ok(None) => return HttpResponse::NotFound().body("playlist not found"),
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+    let resolved = resolve_queue_add_track_ids(&state, &QueueAddRequest { track_ids });
+    let result = if body.next {
+        crate::session_registry::queue_add_next_track_ids(&session_id, resolved)
+    } else {
+        crate::session_registry::queue_add_track_ids(&session_id, resolved)
+    };
+    let added = match result {
+        Ok(added) => added,
+        Err(()) => return HttpResponse::NotFound().body("session not found"),
+    };
+    if added > 0 {
+        state.events.queue_changed();
+    }
+    HttpResponse::Ok().body(format!("added {added}"))
+}
+
 #[utoipa::path(
     post,
     path = "/sessions/{id}/queue/remove",
@@ -1774,7 +1874,14 @@ mod tests {
                     album_uuid: None,
                     track_number: Some(1),
                     disc_number: Some(1),
+                    disc_title: None,
                     year: Some(2024),
+                    genres: Vec::new(),
+                    composer: None,
+                    conductor: None,
+                    work: None,
+                    movement: None,
+                    movement_number: None,
                     duration_ms: Some(180_000),
                     sample_rate: Some(44_100),
                     bit_depth: Some(16),
@@ -12,6 +12,7 @@ use tokio::sync::broadcast::error::RecvError;
 use tokio::time::{Duration, Interval, MissedTickBehavior};
 
 use crate::events::{HubEvent, LogEvent};
+use crate::models::LibraryScanProgress;
 use crate::state::AppState;
 
 use super::outputs::normalize_outputs_response;
@@ -49,6 +50,15 @@ struct AlbumsStreamState {
     last_ping: Instant,
 }
 
+/// SSE loop state for library scan progress stream.
+struct LibraryScanStreamState {
+    state: web::Data<AppState>,
+    interval: Interval,
+    pending: VecDeque<Bytes>,
+    last_progress: LibraryScanProgress,
+    last_ping: Instant,
+}
+
 /// Internal signal emitted by stream poll loop.
 enum StreamSignal<E> {
     Tick,
@@ -323,3 +333,52 @@ pub async fn logs_stream(state: web::Data<AppState>) -> impl Responder {
 
     sse_response(stream)
 }
+
+#[utoipa::path(
+    get,
+    path = "/library/scan/progress",
+    responses(
+        (status = 200, description = "Library scan progress stream", body = LibraryScanProgress)
+    )
+)]
+#[get("/library/scan/progress")]
+/// Stream library scan progress (files scanned/total, current folder) via server-sent events.
+pub async fn library_scan_progress_stream(state: web::Data<AppState>) -> impl Responder {
+    let initial = state.library_scan.snapshot();
+    let initial_json = serde_json::to_string(&initial).unwrap_or_else(|_| "null".to_string());
+    let mut pending = VecDeque::new();
+    pending.push_back(sse_event("progress", &initial_json));
+
+    let mut interval = tokio::time::interval(Duration::from_millis(500));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    let stream = unfold(
+        LibraryScanStreamState {
+            state: state.clone(),
+            interval,
+            pending,
+            last_progress: initial,
+            last_ping: Instant::now(),
+        },
+        |mut ctx| async move {
+            loop {
+                if let Some(bytes) = ctx.pending.pop_front() {
+                    return Some((Ok::<Bytes, Error>(bytes), ctx));
+                }
+
+                ctx.interval.tick().await;
+                let progress = ctx.state.library_scan.snapshot();
+                if progress != ctx.last_progress {
+                    let json =
+                        serde_json::to_string(&progress).unwrap_or_else(|_| "null".to_string());
+                    ctx.pending.push_back(sse_event("progress", &json));
+                    ctx.last_progress = progress;
+                }
+
+                push_ping_if_needed(&mut ctx.pending, &mut ctx.last_ping);
+            }
+        },
+    );
+
+    sse_response(stream)
+}
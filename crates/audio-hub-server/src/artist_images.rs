@@ -0,0 +1,389 @@
+//! Artist image enrichment from fanart.tv and Wikidata.
+//!
+//! Mirrors the cover art pipeline: a background worker picks artists that have a MusicBrainz
+//! artist MBID but no image yet, looks one up (fanart.tv when an API key is configured, then
+//! the Wikidata "image" property served from Wikimedia Commons), and stores it as the artist's
+//! `image` media asset.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use crate::clock::now_ms;
+use crate::events::{EventBus, MetadataEvent};
+use crate::media_assets::{MAX_IMAGE_BYTES, MediaAssetStore};
+use crate::metadata_db::{ArtistImageCandidate, MetadataDb};
+use crate::state::MetadataWake;
+
+const FANART_BASE_URL: &str = "https://webservice.fanart.tv/v3/music";
+const WIKIDATA_API_URL: &str = "https://www.wikidata.org/w/api.php";
+const WIKIDATA_ENTITY_URL: &str = "https://www.wikidata.org/wiki/Special:EntityData";
+const COMMONS_FILE_URL: &str = "https://commons.wikimedia.org/wiki/Special:FilePath";
+const IMAGE_RATE_LIMIT_MS: u64 = 1000;
+const MAX_JSON_BYTES: u64 = 2_000_000;
+
+/// Background worker that fetches missing artist images.
+pub struct ArtistImageFetcher {
+    db: MetadataDb,
+    store: MediaAssetStore,
+    user_agent: String,
+    fanart_api_key: Option<String>,
+    events: EventBus,
+    wake: MetadataWake,
+}
+
+impl ArtistImageFetcher {
+    /// Build the fetcher; `fanart_api_key` enables fanart.tv ahead of Wikidata.
+    pub fn new(
+        db: MetadataDb,
+        root: std::path::PathBuf,
+        user_agent: String,
+        fanart_api_key: Option<String>,
+        events: EventBus,
+        wake: MetadataWake,
+    ) -> Self {
+        Self {
+            db,
+            store: MediaAssetStore::new(root),
+            user_agent,
+            fanart_api_key,
+            events,
+            wake,
+        }
+    }
+
+    /// Run the fetch loop on its own thread, sleeping until metadata changes when idle.
+    pub fn spawn(self) {
+        std::thread::spawn(move || {
+            let client = ArtistImageClient::new(&self.user_agent, self.fanart_api_key.clone());
+            let mut wake_seq = 0u64;
+            loop {
+                match self.db.list_artist_image_candidates(25) {
+                    Ok(candidates) if candidates.is_empty() => self.wake.wait(&mut wake_seq),
+                    Ok(candidates) => {
+                        tracing::info!(count = candidates.len(), "artist image candidates fetched");
+                        for candidate in candidates {
+                            if let Err(err) = self.fetch_and_store(&client, &candidate) {
+                                tracing::warn!(
+                                    error = %err,
+                                    artist_id = candidate.artist_id,
+                                    "artist image fetch failed"
+                                );
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        tracing::warn!(error = %err, "artist image candidate query failed");
+                        std::thread::sleep(Duration::from_secs(10));
+                    }
+                }
+            }
+        });
+    }
+
+    /// Look up, download, and store one artist's image.
+    fn fetch_and_store(
+        &self,
+        client: &ArtistImageClient,
+        candidate: &ArtistImageCandidate,
+    ) -> Result<()> {
+        let stored = client.find_image(&candidate.mbid).and_then(|found| {
+            found
+                .map(|image| {
+                    let stored = self.store.store_image_bytes(
+                        "artist",
+                        candidate.artist_id,
+                        "image",
+                        &image.mime_type,
+                        &image.data,
+                        &image.url,
+                    )?;
+                    Ok((image.source, stored))
+                })
+                .transpose()
+        });
+        let (source, stored) = match stored {
+            Ok(Some(value)) => value,
+            Ok(None) => {
+                tracing::info!(
+                    artist_id = candidate.artist_id,
+                    mbid = %candidate.mbid,
+                    "artist image not found"
+                );
+                return self
+                    .db
+                    .mark_artist_image_checked(candidate.artist_id, now_ms());
+            }
+            Err(err) => {
+                let attempts = self
+                    .db
+                    .increment_artist_image_fail(candidate.artist_id, &err.to_string())?;
+                self.events
+                    .metadata_event(MetadataEvent::ArtistImageFetchFailure {
+                        artist_id: candidate.artist_id,
+                        mbid: candidate.mbid.clone(),
+                        error: err.to_string(),
+                        attempts,
+                    });
+                return Ok(());
+            }
+        };
+        // An image set by hand while this one downloaded wins.
+        if self
+            .db
+            .media_asset_for("artist", candidate.artist_id, "image")?
+            .is_none()
+        {
+            self.db.upsert_media_asset(
+                "artist",
+                candidate.artist_id,
+                "image",
+                &stored.local_path,
+                Some(&stored.checksum),
+                Some(&stored.source_url),
+                Some(stored.updated_at_ms),
+            )?;
+            tracing::info!(
+                artist_id = candidate.artist_id,
+                source,
+                path = %stored.local_path,
+                "artist image stored"
+            );
+            self.events
+                .metadata_event(MetadataEvent::ArtistImageFetchSuccess {
+                    artist_id: candidate.artist_id,
+                    source: source.to_string(),
+                });
+            self.events.library_changed();
+        }
+        self.db
+            .mark_artist_image_checked(candidate.artist_id, now_ms())
+    }
+}
+
+/// Downloaded artist image and where it came from.
+struct FetchedImage {
+    source: &'static str,
+    url: String,
+    mime_type: String,
+    data: Vec<u8>,
+}
+
+/// Rate-limited HTTP client for the artist image sources.
+struct ArtistImageClient {
+    agent: ureq::Agent,
+    fanart_api_key: Option<String>,
+    last_request: Mutex<Instant>,
+}
+
+impl ArtistImageClient {
+    fn new(user_agent: &str, fanart_api_key: Option<String>) -> Self {
+        let config = ureq::Agent::config_builder().user_agent(user_agent).build();
+        Self {
+            agent: ureq::Agent::new_with_config(config),
+            fanart_api_key,
+            last_request: Mutex::new(Instant::now() - Duration::from_millis(IMAGE_RATE_LIMIT_MS)),
+        }
+    }
+
+    /// Find and download an image for an artist MBID, trying fanart.tv first.
+    fn find_image(&self, mbid: &str) -> Result<Option<FetchedImage>> {
+        // MBIDs end up in a search query; anything but a UUID is not worth sending.
+        if mbid.is_empty() || !mbid.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+            return Ok(None);
+        }
+        let mut found = None;
+        if let Some(key) = self.fanart_api_key.as_deref() {
+            let url = format!("{FANART_BASE_URL}/{mbid}");
+            found = self
+                .get_json(&url, &[("api_key", key)])?
+                .and_then(|body| fanart_thumb_url(&body))
+                .map(|url| ("fanart.tv", url));
+        }
+        if found.is_none() {
+            found = self.wikidata_image_url(mbid)?.map(|url| ("wikidata", url));
+        }
+        let Some((source, url)) = found else {
+            return Ok(None);
+        };
+        let (mime_type, data) = self.download(&url)?;
+        Ok(Some(FetchedImage {
+            source,
+            url,
+            mime_type,
+            data,
+        }))
+    }
+
+    /// Resolve the Commons image of the Wikidata item carrying this MusicBrainz artist id.
+    fn wikidata_image_url(&self, mbid: &str) -> Result<Option<String>> {
+        let search = format!("haswbstatement:P434={mbid}");
+        let Some(entity_id) = self
+            .get_json(
+                WIKIDATA_API_URL,
+                &[
+                    ("action", "query"),
+                    ("list", "search"),
+                    ("srsearch", &search),
+                    ("format", "json"),
+                ],
+            )?
+            .and_then(|body| wikidata_entity_id(&body))
+        else {
+            return Ok(None);
+        };
+        let url = format!("{WIKIDATA_ENTITY_URL}/{entity_id}.json");
+        Ok(self
+            .get_json(&url, &[])?
+            .and_then(|body| wikidata_image_file(&body, &entity_id))
+            .map(|file| commons_file_url(&file)))
+    }
+
+    /// GET a JSON document; a 404 means the source knows nothing about the artist.
+    fn get_json(&self, url: &str, query: &[(&str, &str)]) -> Result<Option<Value>> {
+        self.wait_rate_limit();
+        let request = query
+            .iter()
+            .fold(self.agent.get(url), |request, (key, value)| {
+                request.query(*key, *value)
+            });
+        let resp = match request.call() {
+            Ok(resp) => resp,
+            Err(ureq::Error::StatusCode(404)) => return Ok(None),
+            Err(err) => {
+                return Err(err).with_context(|| format!("artist image request failed url={url}"));
+            }
+        };
+        let body = resp
+            .into_body()
+            .with_config()
+            .limit(MAX_JSON_BYTES)
+            .read_to_string()
+            .context("artist image response read failed")?;
+        serde_json::from_str(&body)
+            .map(Some)
+            .context("artist image response parse failed")
+    }
+
+    /// Download image bytes and their content type.
+    fn download(&self, url: &str) -> Result<(String, Vec<u8>)> {
+        self.wait_rate_limit();
+        let resp = self
+            .agent
+            .get(url)
+            .call()
+            .with_context(|| format!("artist image download failed url={url}"))?;
+        let mime_type = resp
+            .headers()
+            .get("content-type")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|value| value.trim().to_ascii_lowercase())
+            .unwrap_or_default();
+        let data = resp
+            .into_body()
+            .with_config()
+            .limit(MAX_IMAGE_BYTES as u64)
+            .read_to_vec()
+            .context("artist image read failed")?;
+        Ok((mime_type, data))
+    }
+
+    fn wait_rate_limit(&self) {
+        let mut last = self
+            .last_request
+            .lock()
+            .expect("artist image rate limit lock");
+        let elapsed = last.elapsed();
+        let limit = Duration::from_millis(IMAGE_RATE_LIMIT_MS);
+        if elapsed < limit {
+            std::thread::sleep(limit - elapsed);
+        }
+        *last = Instant::now();
+    }
+}
+
+/// Most-liked artist thumbnail from a fanart.tv music response.
+fn fanart_thumb_url(body: &Value) -> Option<String> {
+    body.get("artistthumb")?
+        .as_array()?
+        .iter()
+        .filter(|thumb| thumb.get("url").and_then(Value::as_str).is_some())
+        .max_by_key(|thumb| {
+            thumb
+                .get("likes")
+                .and_then(Value::as_str)
+                .and_then(|likes| likes.parse::<u32>().ok())
+                .unwrap_or(0)
+        })
+        .and_then(|thumb| thumb.get("url")?.as_str())
+        .map(str::to_string)
+}
+
+/// First item id from a Wikidata `list=search` response.
+fn wikidata_entity_id(body: &Value) -> Option<String> {
+    body.pointer("/query/search/0/title")?
+        .as_str()
+        .filter(|id| id.starts_with('Q'))
+        .map(str::to_string)
+}
+
+/// File name of the item's "image" (P18) claim from a Wikidata entity document.
+fn wikidata_image_file(body: &Value, entity_id: &str) -> Option<String> {
+    body.get("entities")?
+        .get(entity_id)?
+        .pointer("/claims/P18/0/mainsnak/datavalue/value")?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Commons URL serving a scaled copy of an image file.
+fn commons_file_url(file: &str) -> String {
+    format!(
+        "{COMMONS_FILE_URL}/{}?width=1000",
+        urlencoding::encode(&file.replace(' ', "_"))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn fanart_thumb_prefers_most_liked() {
+        let body = json!({
+            "artistthumb": [
+                {"url": "https://assets.fanart.tv/a.jpg", "likes": "2"},
+                {"url": "https://assets.fanart.tv/b.jpg", "likes": "7"},
+                {"likes": "9"}
+            ]
+        });
+        assert_eq!(
+            fanart_thumb_url(&body).as_deref(),
+            Some("https://assets.fanart.tv/b.jpg")
+        );
+        assert_eq!(fanart_thumb_url(&json!({"name": "Artist"})), None);
+    }
+
+    #[test]
+    fn wikidata_responses_resolve_to_commons_url() {
+        let search = json!({"query": {"search": [{"title": "Q93346"}]}});
+        assert_eq!(wikidata_entity_id(&search).as_deref(), Some("Q93346"));
+        assert_eq!(wikidata_entity_id(&json!({"query": {"search": []}})), None);
+
+        let entity = json!({
+            "entities": {"Q93346": {"claims": {"P18": [
+                {"mainsnak": {"datavalue": {"value": "Miles Davis by Palumbo.jpg"}}}
+            ]}}}
+        });
+        let file = wikidata_image_file(&entity, "Q93346").expect("image claim");
+        assert_eq!(
+            commons_file_url(&file),
+            "https://commons.wikimedia.org/wiki/Special:FilePath/Miles_Davis_by_Palumbo.jpg?width=1000"
+        );
+        assert_eq!(wikidata_image_file(&entity, "Q1"), None);
+    }
+}
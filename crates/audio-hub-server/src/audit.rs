@@ -0,0 +1,278 @@
+//! Audit log of control actions, listed by `GET /audit`.
+//!
+//! [`AuditLog`] records every mutating API request that reaches a handler: who sent it (the
+//! API token name, once authentication is on), the client and session it acted on, a short
+//! action name and the response status. MQTT commands are recorded with actor `mqtt`.
+//! Session keep-alives and registrations are left out. Rows are buffered in memory, stored by
+//! a background loop and pruned to the newest [`MAX_ROWS`].
+
+use std::sync::{Mutex, OnceLock};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use actix_web::Error;
+use actix_web::HttpMessage;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::Method;
+use futures_util::future::{LocalBoxFuture, Ready, ok};
+
+use crate::auth::Caller;
+use crate::clock::now_ms;
+use crate::metadata_db::{AuditRecord, MetadataDb};
+
+/// Rows kept in memory until the writer loop stores them.
+const MAX_PENDING: usize = 1000;
+/// How often the writer loop stores buffered rows.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+/// Rows kept in the database; older ones are pruned.
+const MAX_ROWS: i64 = 100_000;
+
+/// Return the global buffer of rows waiting to be stored.
+fn pending() -> &'static Mutex<Vec<AuditRecord>> {
+    static PENDING: OnceLock<Mutex<Vec<AuditRecord>>> = OnceLock::new();
+    PENDING.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Queue a row for the writer loop; fills in the client of its session when missing.
+pub(crate) fn record(mut record: AuditRecord) {
+    if record.client_id.is_none()
+        && let Some(session) = record
+            .session_id
+            .as_deref()
+            .and_then(crate::session_registry::get_session)
+    {
+        record.client_id = Some(session.client_id);
+        if record.output_id.is_none() {
+            record.output_id = session.active_output_id;
+        }
+    }
+    if let Ok(mut pending) = pending().lock() {
+        if pending.len() >= MAX_PENDING {
+            pending.remove(0);
+        }
+        pending.push(record);
+    }
+}
+
+/// Store buffered rows in the background.
+pub fn spawn_audit_loop(db: MetadataDb) {
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(FLUSH_INTERVAL);
+            let rows = match pending().lock() {
+                Ok(mut pending) => std::mem::take(&mut *pending),
+                Err(_) => continue,
+            };
+            if rows.is_empty() {
+                continue;
+            }
+            if let Err(err) = db.record_audit(&rows) {
+                tracing::warn!(error = %err, rows = rows.len(), "audit log store failed");
+                continue;
+            }
+            if let Err(err) = db.prune_audit(MAX_ROWS) {
+                tracing::warn!(error = %err, "audit log prune failed");
+            }
+        }
+    });
+}
+
+/// Action name of a request, or `None` when it is not audited.
+pub(crate) fn classify(method: &Method, path: &str) -> Option<&'static str> {
+    if *method == Method::GET || *method == Method::HEAD || *method == Method::OPTIONS {
+        return None;
+    }
+    if path == "/sessions"
+        || path.ends_with("/heartbeat")
+        || path == "/local-playback/register"
+        || path == "/providers/bridge/unregister"
+    {
+        return None;
+    }
+    if let Some(rest) = path.strip_prefix("/sessions/") {
+        let action = match rest.split_once('/').map(|(_, action)| action) {
+            None if *method == Method::DELETE => "session_delete",
+            Some("pause") => "pause_toggle",
+            Some("stop") => "stop",
+            Some("seek") => "seek",
+            Some("chapter") => "chapter",
+            Some("volume") => "volume",
+            Some("mute") => "mute",
+            Some("select-output") => "output_select",
+            Some("release-output") => "output_release",
+            Some("queue/next") => "next",
+            Some("queue/previous") => "previous",
+            Some("queue/play_from") => "play",
+            Some("queue/clear") => "queue_clear",
+            Some("queue/remove") => "queue_remove",
+            Some("queue" | "queue/next/add" | "queue/playlist") => "queue_add",
+            _ => "request",
+        };
+        return Some(action);
+    }
+    if path.starts_with("/local-playback/") && path.ends_with("/play") {
+        return Some("play");
+    }
+    if path == "/outputs/select" {
+        return Some("output_select");
+    }
+    if path.starts_with("/integrations/homeassistant/players/") {
+        return Some("remote_command");
+    }
+    if path.ends_with("/metadata/update")
+        || path.ends_with("/profile/update")
+        || path.ends_with("/rating")
+        || path.ends_with("/cover")
+        || path.contains("/image/")
+        || path == "/metadata/match/apply"
+        || path == "/metadata/import"
+    {
+        return Some("metadata_edit");
+    }
+    if path.starts_with("/library/rescan") {
+        return Some("rescan");
+    }
+    if path.starts_with("/playlists") {
+        return Some("playlist_edit");
+    }
+    if path.starts_with("/auth/") {
+        return Some("token_change");
+    }
+    Some("request")
+}
+
+/// Session id in a `/sessions/{id}/...` or `/local-playback/{id}/...` path.
+fn path_session_id(path: &str) -> Option<&str> {
+    let rest = path
+        .strip_prefix("/sessions/")
+        .or_else(|| path.strip_prefix("/local-playback/"))?;
+    let id = rest.split('/').next().unwrap_or(rest);
+    (!id.is_empty()).then_some(id)
+}
+
+/// Output id in a Home Assistant player path.
+fn path_output_id(path: &str) -> Option<String> {
+    let rest = path.strip_prefix("/integrations/homeassistant/players/")?;
+    let id = rest.split('/').next().unwrap_or(rest);
+    urlencoding::decode(id).ok().map(|id| id.into_owned())
+}
+
+/// Actix middleware that records audited requests (see [`classify`]).
+pub(crate) struct AuditLog;
+
+impl<S, B> Transform<S, ServiceRequest> for AuditLog
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AuditLogMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    /// Build middleware instance around inner service.
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(AuditLogMiddleware { service })
+    }
+}
+
+/// Service wrapper that records audited requests.
+pub(crate) struct AuditLogMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for AuditLogMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    /// Delegate readiness polling to wrapped service.
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    /// Run the request, then queue an audit row with its status.
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let Some(action) = classify(req.method(), req.path()) else {
+            return Box::pin(self.service.call(req));
+        };
+        let path = req.path().to_string();
+        let row = AuditRecord {
+            at_ms: now_ms(),
+            actor: req
+                .extensions()
+                .get::<Caller>()
+                .map(|caller| caller.0.clone()),
+            session_id: path_session_id(&path).map(str::to_string),
+            output_id: path_output_id(&path),
+            action: action.to_string(),
+            method: req.method().to_string(),
+            path,
+            ..Default::default()
+        };
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await?;
+            record(AuditRecord {
+                status: res.status().as_u16(),
+                ..row
+            });
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_names_control_actions_and_skips_noise() {
+        let post = Method::POST;
+        assert_eq!(classify(&Method::GET, "/sessions/s1/queue"), None);
+        assert_eq!(classify(&post, "/sessions"), None);
+        assert_eq!(classify(&post, "/sessions/s1/heartbeat"), None);
+        assert_eq!(classify(&post, "/sessions/s1/queue/next"), Some("next"));
+        assert_eq!(
+            classify(&post, "/sessions/s1/queue/next/add"),
+            Some("queue_add")
+        );
+        assert_eq!(classify(&post, "/sessions/s1/stop"), Some("stop"));
+        assert_eq!(
+            classify(&post, "/sessions/s1/select-output"),
+            Some("output_select")
+        );
+        assert_eq!(
+            classify(&Method::DELETE, "/sessions/s1"),
+            Some("session_delete")
+        );
+        assert_eq!(
+            classify(&post, "/tracks/metadata/update"),
+            Some("metadata_edit")
+        );
+        assert_eq!(
+            classify(&Method::PUT, "/tracks/4/rating"),
+            Some("metadata_edit")
+        );
+        assert_eq!(classify(&post, "/outputs/settings"), Some("request"));
+    }
+
+    #[test]
+    fn path_ids_are_extracted() {
+        assert_eq!(path_session_id("/sessions/sess:1/stop"), Some("sess:1"));
+        assert_eq!(
+            path_session_id("/local-playback/local:ios:2/play"),
+            Some("local:ios:2")
+        );
+        assert_eq!(path_session_id("/outputs/select"), None);
+        assert_eq!(
+            path_output_id("/integrations/homeassistant/players/bridge%3Aden/command").as_deref(),
+            Some("bridge:den")
+        );
+    }
+}
@@ -0,0 +1,548 @@
+//! API token authentication from `[auth]` config and `/auth/tokens`.
+//!
+//! Authentication switches on once any token exists (config or database). From then on every
+//! mutating request, the SSE streams and the `/auth` endpoints need a token, sent as
+//! `Authorization: Bearer <token>` or as an `access_token` query parameter (for `EventSource`
+//! and `<audio>` elements, which cannot set headers). Reads of library data stay open.
+//!
+//! Tokens have a [`TokenRole`]. Listener tokens may drive playback (sessions, queues, local
+//! playback, output selection) and follow the streams; everything else that changes state
+//! (library, metadata, playlists, output settings, logs, tokens) needs an admin token.
+//!
+//! Track stream URLs handed to bridges, cast devices and local players carry a per-process
+//! stream token instead, which only unlocks `/stream/track` and `/stream/transcode/track`.
+
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use actix_web::HttpMessage;
+use actix_web::body::EitherBody;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::{Method, header};
+use actix_web::{Error, HttpResponse, web};
+use anyhow::Result;
+use futures_util::future::{LocalBoxFuture, Ready, ok};
+use sha2::{Digest, Sha256};
+use std::task::{Context, Poll};
+
+use crate::config::AuthConfig;
+use crate::metadata_db::{ApiTokenInfo, MetadataDb};
+
+/// Prefix of generated token secrets, so leaked tokens are easy to recognize.
+const TOKEN_PREFIX: &str = "ahub_";
+
+/// Permission level of an API token.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenRole {
+    /// Full access, including library, metadata and output configuration.
+    #[default]
+    Admin,
+    /// Playback control only: sessions, queues, local playback and output selection.
+    Listener,
+}
+
+impl TokenRole {
+    /// Stored form of the role.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Admin => "admin",
+            Self::Listener => "listener",
+        }
+    }
+
+    /// Parse a stored role; anything unknown gets the narrower listener role.
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "admin" => Self::Admin,
+            _ => Self::Listener,
+        }
+    }
+}
+
+/// What a request needs to get through [`TokenAuth`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Access {
+    /// An admin token.
+    Admin,
+    /// Any API token.
+    Listener,
+    /// Any API token or the stream token.
+    Stream,
+}
+
+/// Outcome of checking a request's token against the [`Access`] it needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+    Allowed,
+    /// No token, or one that is not known (401).
+    Unauthenticated,
+    /// A valid token whose role is too narrow (403).
+    Forbidden,
+}
+
+/// Name of the API token a request was authenticated with, kept in its extensions.
+#[derive(Debug, Clone)]
+pub(crate) struct Caller(pub String);
+
+/// Config token, kept by the hash of its secret.
+struct ConfigToken {
+    name: String,
+    hash: String,
+    role: TokenRole,
+}
+
+/// Known API tokens and whether authentication is switched on.
+pub(crate) struct ApiAuth {
+    db: MetadataDb,
+    config_tokens: Vec<ConfigToken>,
+    enabled: AtomicBool,
+}
+
+impl ApiAuth {
+    /// Build from `[auth]` config and the tokens stored in `db`.
+    pub(crate) fn new(db: MetadataDb, cfg: Option<&AuthConfig>) -> Result<Self> {
+        let config_tokens = cfg
+            .and_then(|auth| auth.tokens.as_ref())
+            .into_iter()
+            .flatten()
+            .map(|token| ConfigToken {
+                name: token.name.trim().to_string(),
+                hash: hash_token(token.token.trim()),
+                role: token.role.unwrap_or_default(),
+            })
+            .collect();
+        let auth = Self {
+            db,
+            config_tokens,
+            enabled: AtomicBool::new(false),
+        };
+        auth.refresh()?;
+        Ok(auth)
+    }
+
+    /// Re-evaluate whether authentication is on after tokens were added or removed.
+    pub(crate) fn refresh(&self) -> Result<()> {
+        let enabled = !self.config_tokens.is_empty() || self.db.count_api_tokens(None)? > 0;
+        self.enabled.store(enabled, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// True when requests must present a token.
+    pub(crate) fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Names and roles of the tokens defined in config.
+    pub(crate) fn config_tokens(&self) -> Vec<(String, TokenRole)> {
+        self.config_tokens
+            .iter()
+            .map(|token| (token.name.clone(), token.role))
+            .collect()
+    }
+
+    /// True when some admin token exists, so tokens can still be managed.
+    pub(crate) fn has_admin(&self) -> Result<bool> {
+        Ok(self
+            .config_tokens
+            .iter()
+            .any(|token| token.role == TokenRole::Admin)
+            || self.db.count_api_tokens(Some(TokenRole::Admin))? > 0)
+    }
+
+    /// Return the name and role of the token `secret` belongs to, if it is valid.
+    pub(crate) fn verify(&self, secret: &str) -> Option<(String, TokenRole)> {
+        let hash = hash_token(secret);
+        if let Some(token) = self.config_tokens.iter().find(|token| token.hash == hash) {
+            return Some((token.name.clone(), token.role));
+        }
+        match self.db.use_api_token(&hash) {
+            Ok(token) => token.map(|token| (token.name, token.role)),
+            Err(err) => {
+                tracing::warn!(error = %err, "api token lookup failed");
+                None
+            }
+        }
+    }
+
+    /// Check a presented token against the access a request needs; also returns the token name.
+    fn check(&self, access: Access, secret: Option<&str>) -> (Verdict, Option<String>) {
+        let Some(secret) = secret else {
+            return (Verdict::Unauthenticated, None);
+        };
+        if access == Access::Stream && secret == stream_token() {
+            return (Verdict::Allowed, None);
+        }
+        match self.verify(secret) {
+            None => (Verdict::Unauthenticated, None),
+            Some((name, TokenRole::Listener)) if access == Access::Admin => {
+                (Verdict::Forbidden, Some(name))
+            }
+            Some((name, _)) => (Verdict::Allowed, Some(name)),
+        }
+    }
+
+    /// Tokens stored in the database, oldest first.
+    pub(crate) fn list_tokens(&self) -> Result<Vec<ApiTokenInfo>> {
+        self.db.list_api_tokens()
+    }
+
+    /// Create a token and return it with its secret (shown only once).
+    pub(crate) fn create_token(
+        &self,
+        name: &str,
+        role: TokenRole,
+    ) -> Result<(ApiTokenInfo, String)> {
+        let secret = format!(
+            "{TOKEN_PREFIX}{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+        let info = self.db.create_api_token(name, role, &hash_token(&secret))?;
+        self.refresh()?;
+        Ok((info, secret))
+    }
+
+    /// Delete a stored token; returns false if it did not exist.
+    pub(crate) fn delete_token(&self, token_id: i64) -> Result<bool> {
+        let deleted = self.db.delete_api_token(token_id)?;
+        self.refresh()?;
+        Ok(deleted)
+    }
+}
+
+/// SHA-256 of a token secret, hex encoded.
+fn hash_token(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+/// Per-process secret embedded in track stream URLs handed out by the hub.
+pub(crate) fn stream_token() -> &'static str {
+    static TOKEN: OnceLock<String> = OnceLock::new();
+    TOKEN.get_or_init(|| uuid::Uuid::new_v4().simple().to_string())
+}
+
+/// Decide which token, if any, a request needs once authentication is on.
+pub(crate) fn access_required(method: &Method, path: &str) -> Option<Access> {
+    if path == "/auth" || path.starts_with("/auth/") {
+        return Some(Access::Admin);
+    }
+    if *method == Method::GET || *method == Method::HEAD {
+        if path.starts_with("/stream/track/") || path.starts_with("/stream/transcode/track/") {
+            return Some(Access::Stream);
+        }
+        // Bridge logs hold stream URLs; the export is a dump of every user-authored edit.
+        if path == "/logs/stream"
+            || path == "/audit"
+            || path == "/metadata/export"
+            || is_item_route(path, "/providers/", "/logs")
+        {
+            return Some(Access::Admin);
+        }
+        if path.ends_with("/stream") || path == "/library/scan/progress" {
+            return Some(Access::Listener);
+        }
+        return None;
+    }
+    if *method == Method::OPTIONS {
+        return None;
+    }
+    // Bridges announce their shutdown without credentials; this only drops a discovered entry.
+    if *method == Method::POST && path == "/providers/bridge/unregister" {
+        return None;
+    }
+    if path == "/sessions"
+        || path.starts_with("/sessions/")
+        || path.starts_with("/local-playback/")
+        || path == "/outputs/select"
+        || is_item_route(path, "/integrations/homeassistant/players/", "/command")
+        || is_item_route(path, "/tracks/", "/rating")
+        || is_item_route(path, "/albums/", "/rating")
+    {
+        return Some(Access::Listener);
+    }
+    Some(Access::Admin)
+}
+
+/// Whether `path` is `{prefix}{id}{suffix}` with a single non-empty `id` segment.
+fn is_item_route(path: &str, prefix: &str, suffix: &str) -> bool {
+    path.strip_prefix(prefix)
+        .and_then(|rest| rest.strip_suffix(suffix))
+        .is_some_and(|id| !id.is_empty() && !id.contains('/'))
+}
+
+/// Token presented by a request: bearer header first, then `access_token` query parameter.
+fn request_token(req: &ServiceRequest) -> Option<String> {
+    if let Some(value) = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+    {
+        return value
+            .strip_prefix("Bearer ")
+            .map(|token| token.trim().to_string());
+    }
+    req.query_string().split('&').find_map(|pair| {
+        let value = pair.strip_prefix("access_token=")?;
+        urlencoding::decode(value).ok().map(|v| v.into_owned())
+    })
+}
+
+/// Actix middleware that rejects requests without a valid token (see [`access_required`]).
+pub(crate) struct TokenAuth;
+
+impl<S, B> Transform<S, ServiceRequest> for TokenAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = TokenAuthMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    /// Build middleware instance around inner service.
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(TokenAuthMiddleware { service })
+    }
+}
+
+/// Service wrapper that enforces API tokens.
+pub(crate) struct TokenAuthMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for TokenAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    /// Delegate readiness polling to wrapped service.
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    /// Reject the request with 401 (no valid token) or 403 (listener token on an admin route).
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let (verdict, caller) = match (
+            access_required(req.method(), req.path()),
+            req.app_data::<web::Data<ApiAuth>>(),
+        ) {
+            (Some(access), Some(auth)) if auth.enabled() => {
+                auth.check(access, request_token(&req).as_deref())
+            }
+            _ => (Verdict::Allowed, None),
+        };
+        if verdict != Verdict::Allowed {
+            let (reason, res) = if verdict == Verdict::Forbidden {
+                (
+                    "admin_token_required",
+                    HttpResponse::Forbidden().body("admin token required"),
+                )
+            } else {
+                (
+                    "missing_or_invalid_token",
+                    HttpResponse::Unauthorized()
+                        .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+                        .body("valid api token required"),
+                )
+            };
+            tracing::warn!(
+                method = %req.method(),
+                path = %req.path(),
+                peer = %req.connection_info().realip_remote_addr().unwrap_or("-"),
+                reason,
+                "http request rejected"
+            );
+            return Box::pin(async move { Ok(req.into_response(res).map_into_right_body()) });
+        }
+        if let Some(name) = caller {
+            req.extensions_mut().insert(Caller(name));
+        }
+        let fut = self.service.call(req);
+        Box::pin(async move { Ok(fut.await?.map_into_left_body()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn access_required_splits_admin_and_listener_routes() {
+        let get = Method::GET;
+        let post = Method::POST;
+        assert_eq!(access_required(&get, "/albums"), None);
+        assert_eq!(access_required(&get, "/sessions/abc/queue"), None);
+        assert_eq!(
+            access_required(&post, "/library/rescan"),
+            Some(Access::Admin)
+        );
+        assert_eq!(
+            access_required(&post, "/tracks/metadata/update"),
+            Some(Access::Admin)
+        );
+        assert_eq!(
+            access_required(&Method::DELETE, "/playlists/3"),
+            Some(Access::Admin)
+        );
+        assert_eq!(
+            access_required(&post, "/outputs/settings"),
+            Some(Access::Admin)
+        );
+        assert_eq!(access_required(&get, "/auth/tokens"), Some(Access::Admin));
+        assert_eq!(access_required(&get, "/logs/stream"), Some(Access::Admin));
+        assert_eq!(access_required(&get, "/audit"), Some(Access::Admin));
+        assert_eq!(
+            access_required(&get, "/providers/bridge:den/logs"),
+            Some(Access::Admin)
+        );
+        assert_eq!(
+            access_required(&get, "/metadata/export"),
+            Some(Access::Admin)
+        );
+        assert_eq!(access_required(&get, "/providers/bridge:den/outputs"), None);
+        assert_eq!(access_required(&post, "/sessions"), Some(Access::Listener));
+        assert_eq!(
+            access_required(
+                &post,
+                "/integrations/homeassistant/players/local:default/command"
+            ),
+            Some(Access::Listener)
+        );
+        assert_eq!(
+            access_required(
+                &post,
+                "/integrations/homeassistant/players/local:default/other"
+            ),
+            Some(Access::Admin)
+        );
+        assert_eq!(
+            access_required(&Method::PUT, "/tracks/12/rating"),
+            Some(Access::Listener)
+        );
+        assert_eq!(
+            access_required(&Method::PUT, "/albums/4/rating"),
+            Some(Access::Listener)
+        );
+        assert_eq!(
+            access_required(&Method::PUT, "/albums/4/cover"),
+            Some(Access::Admin)
+        );
+        assert_eq!(
+            access_required(&post, "/sessions/abc/queue/clear"),
+            Some(Access::Listener)
+        );
+        assert_eq!(
+            access_required(&Method::DELETE, "/sessions/abc"),
+            Some(Access::Listener)
+        );
+        assert_eq!(
+            access_required(&post, "/local-playback/abc/play"),
+            Some(Access::Listener)
+        );
+        assert_eq!(
+            access_required(&post, "/outputs/select"),
+            Some(Access::Listener)
+        );
+        assert_eq!(
+            access_required(&get, "/library/scan/progress"),
+            Some(Access::Listener)
+        );
+        assert_eq!(
+            access_required(&get, "/stream/track/7"),
+            Some(Access::Stream)
+        );
+        assert_eq!(
+            access_required(&Method::HEAD, "/stream/transcode/track/7"),
+            Some(Access::Stream)
+        );
+        assert_eq!(access_required(&post, "/providers/bridge/unregister"), None);
+        assert_eq!(access_required(&Method::OPTIONS, "/sessions"), None);
+    }
+
+    #[test]
+    fn tokens_are_checked_against_role_and_access() {
+        let root = std::env::temp_dir().join(format!(
+            "audio-hub-server-auth-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&root).unwrap();
+        let db = MetadataDb::new(&root).unwrap();
+        let auth = ApiAuth::new(db.clone(), None).unwrap();
+        assert!(!auth.enabled());
+
+        let cfg: AuthConfig = toml::from_str(
+            r#"
+            [[tokens]]
+            name = "phone"
+            token = "0123456789abcdef"
+            [[tokens]]
+            name = "guest"
+            token = "guest-0123456789"
+            role = "listener"
+        "#,
+        )
+        .unwrap();
+        let auth = ApiAuth::new(db, Some(&cfg)).unwrap();
+        assert!(auth.enabled());
+        assert!(auth.has_admin().unwrap());
+        assert_eq!(
+            auth.verify("0123456789abcdef"),
+            Some(("phone".to_string(), TokenRole::Admin))
+        );
+        assert_eq!(auth.verify("0123456789abcdeX"), None);
+        assert_eq!(
+            auth.check(Access::Admin, Some("0123456789abcdef")),
+            (Verdict::Allowed, Some("phone".to_string()))
+        );
+        assert_eq!(
+            auth.check(Access::Admin, Some("guest-0123456789")).0,
+            Verdict::Forbidden
+        );
+        assert_eq!(
+            auth.check(Access::Listener, Some("guest-0123456789")).0,
+            Verdict::Allowed
+        );
+        assert_eq!(
+            auth.check(Access::Listener, None).0,
+            Verdict::Unauthenticated
+        );
+        assert_eq!(
+            auth.check(Access::Listener, Some(stream_token())).0,
+            Verdict::Unauthenticated
+        );
+        assert_eq!(
+            auth.check(Access::Stream, Some(stream_token())).0,
+            Verdict::Allowed
+        );
+
+        let (info, secret) = auth.create_token("tablet", TokenRole::Listener).unwrap();
+        assert!(secret.starts_with(TOKEN_PREFIX));
+        assert_eq!(info.role, TokenRole::Listener);
+        assert_eq!(
+            auth.verify(&secret),
+            Some(("tablet".to_string(), TokenRole::Listener))
+        );
+        assert!(auth.delete_token(info.id).unwrap());
+        assert_eq!(auth.verify(&secret), None);
+    }
+}
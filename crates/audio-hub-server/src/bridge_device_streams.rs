@@ -11,9 +11,11 @@ use actix_web::web;
 
 use crate::bridge::update_online_and_should_emit;
 use crate::bridge_manager::parse_output_id;
-use crate::bridge_transport::{BridgeTransportClient, HttpDevicesSnapshot, HttpStatusResponse};
+use crate::bridge_transport::{
+    BridgeTransportClient, DeviceSelectOptions, HttpDevicesSnapshot, HttpStatusResponse,
+};
 use crate::playback_transport::ChannelTransport;
-use crate::state::AppState;
+use crate::state::{AppState, BridgeHealth};
 
 const MAX_DISCOVERED_FAILURES: usize = 5;
 const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
@@ -99,12 +101,20 @@ fn spawn_bridge_device_stream(state: web::Data<AppState>, bridge_id: String) {
                             cache.insert(bridge_id.clone(), snapshot.devices.clone());
                         }
                         seen_event.store(true, Ordering::Relaxed);
+                        record_bridge_health(&state, &bridge_id, None);
+                        // Hidden outputs coming and going do not change any listing.
+                        let snapshot = without_hidden_outputs(&state, &bridge_id, snapshot);
                         if last_snapshot.as_ref() != Some(&snapshot) {
                             last_snapshot = Some(snapshot);
                             events.outputs_changed();
                         }
                     })
                     .await;
+                let error = match &result {
+                    Ok(()) => "device stream closed".to_string(),
+                    Err(e) => e.to_string(),
+                };
+                record_bridge_health(&state, &bridge_id, Some(error));
                 if let Err(e) = result {
                     if seen_event.load(Ordering::Relaxed) {
                         failures = 0;
@@ -128,6 +138,9 @@ fn spawn_bridge_device_stream(state: web::Data<AppState>, bridge_id: String) {
                     if let Ok(mut cache) = state.providers.bridge.status_cache.lock() {
                         cache.remove(&bridge_id);
                     }
+                    if let Ok(mut health) = state.providers.bridge.health.lock() {
+                        health.remove(&bridge_id);
+                    }
                     state.events.outputs_changed();
                     tracing::info!(
                         bridge_id = %bridge_id,
@@ -142,7 +155,7 @@ fn spawn_bridge_device_stream(state: web::Data<AppState>, bridge_id: String) {
                     .as_secs()
                     .saturating_mul(failures.max(1) as u64);
                 let delay = Duration::from_secs(delay_secs).min(RETRY_MAX_DELAY);
-                tokio::time::sleep(delay).await;
+                tokio::time::sleep(crate::discovery::timing().jittered(delay)).await;
             }
 
             if let Ok(mut active) = state.providers.bridge.device_streams.lock() {
@@ -151,10 +164,42 @@ fn spawn_bridge_device_stream(state: web::Data<AppState>, bridge_id: String) {
             if let Ok(mut cache) = state.providers.bridge.device_cache.lock() {
                 cache.remove(&bridge_id);
             }
+            if let Ok(mut health) = state.providers.bridge.health.lock() {
+                health.remove(&bridge_id);
+            }
         });
     });
 }
 
+/// Record the outcome of a device stream connection (`None` = connected) for `/health`.
+fn record_bridge_health(state: &AppState, bridge_id: &str, error: Option<String>) {
+    let next = BridgeHealth {
+        online: error.is_none(),
+        last_error: error,
+    };
+    let Ok(mut health) = state.providers.bridge.health.lock() else {
+        return;
+    };
+    let previous = health.insert(bridge_id.to_string(), next.clone());
+    if next.online && previous.is_none_or(|prev| !prev.online) {
+        tracing::info!(bridge_id = %bridge_id, "bridge reachable");
+    }
+}
+
+/// Drop devices whose output ids are hidden in the output settings.
+fn without_hidden_outputs(
+    state: &AppState,
+    bridge_id: &str,
+    mut snapshot: HttpDevicesSnapshot,
+) -> HttpDevicesSnapshot {
+    if let Ok(settings) = state.output_settings.lock() {
+        snapshot
+            .devices
+            .retain(|d| !settings.is_disabled(&format!("bridge:{bridge_id}:{}", d.id)));
+    }
+    snapshot
+}
+
 /// Spawn and maintain the per-bridge status stream reconnect loop.
 fn spawn_bridge_status_stream(state: web::Data<AppState>, bridge_id: String) {
     if let Ok(mut active) = state.providers.bridge.status_streams.lock() {
@@ -248,7 +293,7 @@ fn spawn_bridge_status_stream(state: web::Data<AppState>, bridge_id: String) {
                     .as_secs()
                     .saturating_mul(failures.max(1) as u64);
                 let delay = Duration::from_secs(delay_secs).min(RETRY_MAX_DELAY);
-                tokio::time::sleep(delay).await;
+                tokio::time::sleep(crate::discovery::timing().jittered(delay)).await;
             }
 
             if let Ok(mut active) = state.providers.bridge.status_streams.lock() {
@@ -343,12 +388,18 @@ fn apply_remote_status(
                             Some(state_cloned.metadata.db.clone()),
                         );
                         if let Ok(devices) = client.list_devices().await {
-                            if let Some(device_name) = devices
-                                .iter()
-                                .find(|d| d.id == device_id)
-                                .map(|d| d.name.clone())
-                            {
-                                let _ = client.set_device(&device_name, None).await;
+                            if devices.iter().any(|d| d.id == device_id) {
+                                // Leave exclusive mode as-is; re-send the pre-roll so the
+                                // bridge keeps the output's setting for the next track.
+                                let options = state_cloned
+                                    .output_settings
+                                    .lock()
+                                    .map(|s| DeviceSelectOptions {
+                                        exclusive: None,
+                                        ..s.select_options(&output_id_cloned)
+                                    })
+                                    .unwrap_or_default();
+                                let _ = client.set_device_by_id(&device_id, options).await;
                                 let ext_hint = next_path
                                     .extension()
                                     .and_then(|ext| ext.to_str())
@@ -381,7 +432,8 @@ fn apply_remote_status(
                     *session_auto_advance_in_flight = true;
                 }
                 Ok(None) => {
-                    if let Ok(true) = crate::session_registry::queue_finish_now_playing(&session_id) {
+                    if let Ok(true) = crate::session_registry::queue_finish_now_playing(&session_id)
+                    {
                         state.events.queue_changed();
                         state.events.status_changed();
                     }
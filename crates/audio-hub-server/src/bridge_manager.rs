@@ -37,7 +37,17 @@ pub(crate) fn merge_bridges(
     for b in configured {
         seen.insert(b.id.clone());
         seen_addrs.insert(b.http_addr);
-        merged.push(b.clone());
+        let mut b = b.clone();
+        // Labels from config win; otherwise keep what the bridge advertises.
+        if let Some(found) = discovered.get(&b.id).or_else(|| {
+            discovered
+                .values()
+                .find(|d| d.bridge.http_addr == b.http_addr)
+        }) {
+            b.room = b.room.or_else(|| found.bridge.room.clone());
+            b.zone = b.zone.or_else(|| found.bridge.zone.clone());
+        }
+        merged.push(b);
     }
     for (id, b) in discovered {
         if seen.contains(id) {
@@ -67,6 +77,8 @@ mod tests {
             id: id.to_string(),
             name: id.to_string(),
             http_addr: addr.parse().unwrap(),
+            room: None,
+            zone: None,
         }
     }
 
@@ -129,4 +141,30 @@ mod tests {
         assert!(ids.contains(&"c"));
         assert_eq!(merged.len(), 2);
     }
+
+    #[test]
+    fn merge_bridges_keeps_advertised_labels_unless_configured() {
+        let mut configured = vec![bridge("a", "127.0.0.1:5556"), bridge("b", "127.0.0.1:5557")];
+        configured[1].room = Some("Office".to_string());
+        let mut discovered = std::collections::HashMap::new();
+        for (id, addr) in [("a", "127.0.0.1:5556"), ("b", "127.0.0.1:5557")] {
+            let mut advertised = bridge(id, addr);
+            advertised.room = Some("Kitchen".to_string());
+            advertised.zone = Some("Downstairs".to_string());
+            discovered.insert(
+                id.to_string(),
+                crate::state::DiscoveredBridge {
+                    bridge: advertised,
+                    last_seen: std::time::Instant::now(),
+                },
+            );
+        }
+
+        let merged = merge_bridges(&configured, &discovered);
+
+        assert_eq!(merged[0].room.as_deref(), Some("Kitchen"));
+        assert_eq!(merged[0].zone.as_deref(), Some("Downstairs"));
+        assert_eq!(merged[1].room.as_deref(), Some("Office"));
+        assert_eq!(merged[1].zone.as_deref(), Some("Downstairs"));
+    }
 }
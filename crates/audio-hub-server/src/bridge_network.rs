@@ -0,0 +1,162 @@
+//! Per-bridge network settings from `[[bridges]]` config.
+//!
+//! Control requests to a bridge can bind to a local address (`bind_addr`), and audio stream
+//! connections accepted from that bridge are marked with its DSCP class (`dscp`).
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Mutex, OnceLock};
+
+use actix_web::dev::Extensions;
+use actix_web::rt::net::TcpStream;
+use anyhow::{Context, Result};
+use socket2::SockRef;
+
+use crate::config::ServerConfig;
+
+/// Network settings for one configured bridge.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BridgeNetwork {
+    /// Local address hub connections to the bridge bind to.
+    pub bind_addr: Option<IpAddr>,
+    /// DSCP code point for stream connections from the bridge.
+    pub dscp: Option<u8>,
+}
+
+/// Settings keyed by bridge IP (configured `http_addr`).
+fn store() -> &'static Mutex<HashMap<IpAddr, BridgeNetwork>> {
+    static STORE: OnceLock<Mutex<HashMap<IpAddr, BridgeNetwork>>> = OnceLock::new();
+    STORE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Parse `bind_addr`/`dscp` of every configured bridge that sets either.
+pub fn from_config(cfg: &ServerConfig) -> Result<HashMap<IpAddr, BridgeNetwork>> {
+    let mut out = HashMap::new();
+    for bridge in cfg.bridges.iter().flatten() {
+        if bridge.bind_addr.is_none() && bridge.dscp.is_none() {
+            continue;
+        }
+        let http_addr: std::net::SocketAddr = bridge
+            .http_addr
+            .parse()
+            .with_context(|| format!("parse bridge http_addr {}", bridge.http_addr))?;
+        let bind_addr = bridge
+            .bind_addr
+            .as_deref()
+            .map(|ip| {
+                ip.parse::<IpAddr>()
+                    .with_context(|| format!("parse bridge bind_addr {ip}"))
+            })
+            .transpose()?;
+        let dscp = bridge
+            .dscp
+            .as_deref()
+            .map(|value| {
+                audio_bridge_types::parse_dscp(value)
+                    .ok_or_else(|| anyhow::anyhow!("invalid bridge dscp {value}"))
+            })
+            .transpose()?;
+        out.insert(http_addr.ip(), BridgeNetwork { bind_addr, dscp });
+    }
+    Ok(out)
+}
+
+/// Replace the active per-bridge settings.
+pub fn install(settings: HashMap<IpAddr, BridgeNetwork>) {
+    if let Ok(mut guard) = store().lock() {
+        *guard = settings;
+    }
+}
+
+/// Settings for the bridge at `ip` (defaults when not configured).
+pub fn for_bridge(ip: IpAddr) -> BridgeNetwork {
+    store()
+        .lock()
+        .ok()
+        .and_then(|guard| guard.get(&ip).copied())
+        .unwrap_or_default()
+}
+
+/// Actix `on_connect` hook: mark plain-TCP connections from a configured bridge with its DSCP.
+///
+/// TLS listeners are left unmarked since the raw socket is not exposed to the hook.
+pub fn mark_connection(conn: &dyn Any, _ext: &mut Extensions) {
+    let Some(stream) = conn.downcast_ref::<TcpStream>() else {
+        return;
+    };
+    let Ok(peer) = stream.peer_addr() else {
+        return;
+    };
+    let Some(dscp) = for_bridge(peer.ip()).dscp else {
+        return;
+    };
+    let socket = SockRef::from(stream);
+    let tos = u32::from(dscp) << 2;
+    let result = if peer.is_ipv4() {
+        socket.set_tos_v4(tos)
+    } else {
+        set_tclass_v6(&socket, tos)
+    };
+    if let Err(err) = result {
+        tracing::warn!(peer = %peer, dscp, error = %err, "bridge network: failed to set DSCP");
+    }
+}
+
+#[cfg(unix)]
+fn set_tclass_v6(socket: &SockRef<'_>, tclass: u32) -> std::io::Result<()> {
+    socket.set_tclass_v6(tclass)
+}
+
+#[cfg(not(unix))]
+fn set_tclass_v6(_socket: &SockRef<'_>, _tclass: u32) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(raw: &str) -> ServerConfig {
+        toml::from_str(raw).expect("parse config")
+    }
+
+    #[test]
+    fn from_config_resolves_bind_addr_and_dscp() {
+        let cfg = config(
+            r#"
+            [[bridges]]
+            id = "living-room"
+            http_addr = "192.168.1.20:5556"
+            bind_addr = "192.168.1.2"
+            dscp = "EF"
+
+            [[bridges]]
+            id = "office"
+            http_addr = "192.168.1.21:5556"
+            "#,
+        );
+        let settings = from_config(&cfg).unwrap();
+        assert_eq!(settings.len(), 1);
+        assert_eq!(
+            settings[&"192.168.1.20".parse::<IpAddr>().unwrap()],
+            BridgeNetwork {
+                bind_addr: Some("192.168.1.2".parse().unwrap()),
+                dscp: Some(46),
+            }
+        );
+    }
+
+    #[test]
+    fn from_config_rejects_bad_dscp() {
+        let cfg = config(
+            r#"
+            [[bridges]]
+            id = "living-room"
+            http_addr = "192.168.1.20:5556"
+            dscp = "fastest"
+            "#,
+        );
+        assert!(from_config(&cfg).is_err());
+    }
+}
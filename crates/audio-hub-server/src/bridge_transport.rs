@@ -10,8 +10,46 @@ use anyhow::Result;
 use reqwest::Client;
 
 use crate::metadata_db::MetadataDb;
+use crate::models::{BridgeLogEntry, BridgeLogsResponse};
+use crate::stream_url::track_stream_url;
 use audio_bridge_types::BridgeStatus;
 
+/// Per-output options sent with a bridge device selection.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceSelectOptions {
+    /// Exclusive mode to apply (`None` leaves the bridge's current mode).
+    pub exclusive: Option<bool>,
+    /// Pre-roll silence for the output (`None` uses the bridge default).
+    pub preroll_silence_ms: Option<u32>,
+    /// Resampler preset for the output (`None` uses the bridge default).
+    pub resample_quality: Option<String>,
+    /// Preferred sample width in bits (`None` uses the bridge default).
+    pub bit_depth: Option<u16>,
+    /// Highest sample rate to open the output at (`None` leaves it uncapped).
+    pub max_sample_rate: Option<u32>,
+}
+
+impl DeviceSelectOptions {
+    /// Add the options to a `/devices/select` payload.
+    fn apply(self, payload: &mut serde_json::Value) {
+        if let Some(exclusive) = self.exclusive {
+            payload["exclusive"] = serde_json::json!(exclusive);
+        }
+        if let Some(ms) = self.preroll_silence_ms {
+            payload["preroll_silence_ms"] = serde_json::json!(ms);
+        }
+        if let Some(quality) = self.resample_quality {
+            payload["resample_quality"] = serde_json::json!(quality);
+        }
+        if let Some(bits) = self.bit_depth {
+            payload["bit_depth"] = serde_json::json!(bits);
+        }
+        if let Some(rate) = self.max_sample_rate {
+            payload["max_sample_rate"] = serde_json::json!(rate);
+        }
+    }
+}
+
 /// HTTP response payload for the bridge device list.
 #[derive(Debug, serde::Deserialize)]
 pub struct HttpDevicesResponse {
@@ -22,14 +60,43 @@ pub struct HttpDevicesResponse {
 /// Device info returned by the bridge HTTP API.
 #[derive(Debug, serde::Deserialize, Clone, PartialEq, Eq)]
 pub struct HttpDeviceInfo {
-    /// Device identifier reported by the bridge.
+    /// Device identifier reported by the bridge (its alias or stable id on current bridges).
     pub id: String,
+    /// User-assigned alias on the bridge, if any.
+    #[serde(default)]
+    pub alias: Option<String>,
     /// Human-friendly device name.
     pub name: String,
     /// Minimum supported sample rate (Hz).
     pub min_rate: u32,
     /// Maximum supported sample rate (Hz).
     pub max_rate: u32,
+    /// Standard sample rates accepted natively (Hz); empty for older bridges.
+    #[serde(default)]
+    pub sample_rates: Vec<u32>,
+    /// Supported sample formats; empty for older bridges.
+    #[serde(default)]
+    pub sample_formats: Vec<String>,
+    /// Supported channel counts; empty for older bridges.
+    #[serde(default)]
+    pub channels: Vec<u16>,
+}
+
+/// HTTP payload returned by bridge `/health`; older bridges send only `status` and `version`.
+#[derive(Debug, serde::Deserialize, Clone)]
+pub struct HttpHealthResponse {
+    /// Bridge build version.
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Bridge HTTP API version.
+    #[serde(default)]
+    pub protocol_version: Option<u32>,
+    /// Time since the bridge's HTTP API started.
+    #[serde(default)]
+    pub uptime_ms: Option<u64>,
+    /// Newest error the bridge logged since startup.
+    #[serde(default)]
+    pub last_error: Option<BridgeLogEntry>,
 }
 
 /// HTTP payload type returned by bridge `/status`.
@@ -56,6 +123,12 @@ struct HttpPlayRequest<'a> {
     title: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     seek_ms: Option<u64>,
+    /// Start of a cue track within its audio file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_ms: Option<u64>,
+    /// End of a cue track within its audio file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_ms: Option<u64>,
 }
 
 /// JSON payload for bridge seek requests.
@@ -97,7 +170,10 @@ pub struct BridgeTransportClient {
 impl BridgeTransportClient {
     /// Create a new async client for a bridge HTTP address.
     pub fn new(http_addr: SocketAddr) -> Self {
-        let client = Client::builder().build().expect("build reqwest client");
+        let client = Client::builder()
+            .local_address(crate::bridge_network::for_bridge(http_addr.ip()).bind_addr)
+            .build()
+            .expect("build reqwest client");
         Self {
             http_addr,
             client,
@@ -138,13 +214,11 @@ impl BridgeTransportClient {
         Ok(payload.devices)
     }
 
-    /// Select an output device by name on the bridge.
-    pub async fn set_device(&self, name: &str, exclusive: Option<bool>) -> Result<()> {
+    /// Select an output device by stable device id on the bridge.
+    pub async fn set_device_by_id(&self, id: &str, options: DeviceSelectOptions) -> Result<()> {
         let url = format!("http://{}/devices/select", self.http_addr);
-        let mut payload = serde_json::json!({ "name": name });
-        if let Some(exclusive) = exclusive {
-            payload["exclusive"] = serde_json::json!(exclusive);
-        }
+        let mut payload = serde_json::json!({ "id": id });
+        options.apply(&mut payload);
         self.client
             .post(&url)
             .timeout(Duration::from_secs(2))
@@ -157,23 +231,22 @@ impl BridgeTransportClient {
         Ok(())
     }
 
-    /// Select an output device by stable device id on the bridge.
-    pub async fn set_device_by_id(&self, id: &str, exclusive: Option<bool>) -> Result<()> {
-        let url = format!("http://{}/devices/select", self.http_addr);
-        let mut payload = serde_json::json!({ "id": id });
-        if let Some(exclusive) = exclusive {
-            payload["exclusive"] = serde_json::json!(exclusive);
-        }
-        self.client
-            .post(&url)
+    /// Fetch the bridge's `/health` report.
+    pub async fn health(&self) -> Result<HttpHealthResponse> {
+        let url = format!("http://{}/health", self.http_addr);
+        let resp = self
+            .client
+            .get(&url)
             .timeout(Duration::from_secs(2))
-            .json(&payload)
             .send()
             .await
-            .map_err(|e| anyhow::anyhow!("http set device failed: {e}"))?
+            .map_err(|e| anyhow::anyhow!("http health request failed: {e}"))?;
+        let resp = resp
             .error_for_status()
-            .map_err(|e| anyhow::anyhow!("http set device failed: {e}"))?;
-        Ok(())
+            .map_err(|e| anyhow::anyhow!("http health request failed: {e}"))?;
+        resp.json::<HttpHealthResponse>()
+            .await
+            .map_err(|e| anyhow::anyhow!("http health decode failed: {e}"))
     }
 
     /// Fetch the current bridge status snapshot.
@@ -295,7 +368,39 @@ impl BridgeTransportClient {
             .map_err(|e| anyhow::anyhow!("http set mute decode failed: {e}"))
     }
 
+    /// Fetch buffered bridge log entries filtered by level and sequence.
+    pub async fn logs(
+        &self,
+        level: Option<&str>,
+        after: Option<u64>,
+    ) -> Result<BridgeLogsResponse> {
+        let endpoint = format!("http://{}/logs", self.http_addr);
+        let mut query: Vec<(&str, String)> = Vec::new();
+        if let Some(level) = level {
+            query.push(("level", level.to_string()));
+        }
+        if let Some(after) = after {
+            query.push(("after", after.to_string()));
+        }
+        let resp = self
+            .client
+            .get(&endpoint)
+            .query(&query)
+            .timeout(Duration::from_secs(2))
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("http logs request failed: {e}"))?;
+        let resp = resp
+            .error_for_status()
+            .map_err(|e| anyhow::anyhow!("http logs request failed: {e}"))?;
+        resp.json::<BridgeLogsResponse>()
+            .await
+            .map_err(|e| anyhow::anyhow!("http logs decode failed: {e}"))
+    }
+
     /// Ask the bridge to play the specified path via the hub stream URL.
+    ///
+    /// Cue tracks stream their whole audio file with the track's range in the request.
     pub async fn play_path(
         &self,
         path: &PathBuf,
@@ -311,13 +416,23 @@ impl BridgeTransportClient {
         let track_id = self
             .track_id_for_path(path)
             .ok_or_else(|| anyhow::anyhow!("track id not found for path {}", path.display()))?;
-        let url = build_stream_url_for_id(track_id, base_url);
+        let url = track_stream_url(base_url, track_id);
         let endpoint = format!("http://{}/play", self.http_addr);
+        let cue_source = crate::cue_tracks::resolve(path)?;
+        let cue_ext = cue_source.as_ref().and_then(|src| {
+            src.audio_path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(str::to_ascii_lowercase)
+        });
+        let range = cue_source.map(|src| src.range).unwrap_or_default();
         let payload = HttpPlayRequest {
             url: &url,
-            ext_hint,
+            ext_hint: cue_ext.as_deref().or(ext_hint),
             title,
             seek_ms,
+            start_ms: (range.start_ms > 0).then_some(range.start_ms),
+            end_ms: range.end_ms,
         };
         self.client
             .post(&endpoint)
@@ -455,21 +570,19 @@ impl BridgeTransportClient {
     }
 }
 
-/// Build hub `/stream/track/{id}` URL from public base URL.
-fn build_stream_url_for_id(track_id: i64, public_base_url: &str) -> String {
-    format!(
-        "{}/stream/track/{track_id}",
-        public_base_url.trim_end_matches('/')
-    )
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn build_stream_url_for_id_uses_track_endpoint() {
-        let url = build_stream_url_for_id(42, "http://host/");
-        assert_eq!(url, "http://host/stream/track/42");
+    fn track_stream_url_uses_track_endpoint_with_stream_token() {
+        let url = track_stream_url("http://host/", 42);
+        assert_eq!(
+            url,
+            format!(
+                "http://host/stream/track/42?access_token={}",
+                crate::auth::stream_token()
+            )
+        );
     }
 }
@@ -734,7 +734,8 @@ fn apply_media_status(
                     }
                 }
                 Ok(None) => {
-                    if let Ok(true) = crate::session_registry::queue_finish_now_playing(session_id) {
+                    if let Ok(true) = crate::session_registry::queue_finish_now_playing(session_id)
+                    {
                         events.queue_changed();
                         events.status_changed();
                     }
@@ -898,15 +899,14 @@ fn track_metadata(path: &PathBuf, metadata: Option<&MetadataDb>) -> TrackMetadat
 }
 
 /// Map extension hint to media content-type for Cast receiver.
-fn content_type_for_ext(ext_hint: &str) -> &'static str {
+pub(crate) fn content_type_for_ext(ext_hint: &str) -> &'static str {
     match ext_hint.to_ascii_lowercase().as_str() {
         "flac" => "audio/flac",
         "mp3" => "audio/mpeg",
         "wav" => "audio/wav",
         "aac" => "audio/mp4",
-        "m4a" => "audio/mp4",
-        "ogg" => "audio/ogg",
-        "opus" => "audio/ogg",
+        "m4a" | "alac" => "audio/mp4",
+        "ogg" | "oga" | "opus" => "audio/ogg",
         _ => "audio/mpeg",
     }
 }
@@ -1,10 +1,12 @@
 //! Configuration loading and parsing.
 //!
-//! Defines the server config schema and resolves defaults.
+//! Defines the server config schema, resolves defaults, and validates config files
+//! (unknown fields, address/port ranges, referenced paths).
 
 use std::path::Path;
 
 use anyhow::{Context, Result};
+use serde::de::{self, DeserializeOwned, Visitor};
 use serde::{Deserialize, Serialize};
 
 use std::net::SocketAddr;
@@ -36,8 +38,32 @@ pub struct ServerConfig {
     pub tls_cert: Option<String>,
     /// Optional TLS private key path (PEM).
     pub tls_key: Option<String>,
-    /// Output device settings (disabled devices, renames).
+    /// Output device settings (disabled devices, renames, exclusive mode, pre-roll).
     pub outputs: Option<OutputSettingsConfig>,
+    /// Bandwidth limits for library stream/transcode endpoints.
+    pub stream_limits: Option<StreamLimitsConfig>,
+    /// Debug capture of stream responses sent to bridges.
+    pub stream_capture: Option<StreamCaptureConfig>,
+    /// Background library analysis jobs.
+    pub analysis: Option<AnalysisConfig>,
+    /// Bridge health-check and discovery timing.
+    pub discovery: Option<DiscoveryConfig>,
+    /// Lyrics lookup settings.
+    pub lyrics: Option<LyricsConfig>,
+    /// API token authentication settings.
+    pub auth: Option<AuthConfig>,
+    /// Cross-origin policy for browser frontends hosted elsewhere.
+    pub cors: Option<CorsConfig>,
+    /// Per-client request limits for expensive endpoints.
+    pub rate_limits: Option<RateLimitsConfig>,
+    /// Outgoing webhook targets for playback and library events.
+    pub webhooks: Option<Vec<WebhookConfig>>,
+    /// MQTT broker for home-automation status and control.
+    pub mqtt: Option<MqttConfig>,
+    /// Snapcast server fed as a synchronized multi-room output.
+    pub snapcast: Option<SnapcastConfig>,
+    /// Bridge outputs that play together as one output.
+    pub output_groups: Option<Vec<OutputGroupConfig>>,
 }
 
 /// Bridge config from TOML.
@@ -49,6 +75,14 @@ pub struct BridgeConfig {
     pub name: Option<String>,
     /// Bridge HTTP address (host:port).
     pub http_addr: String,
+    /// Local IP the hub binds to when connecting to this bridge (multi-homed hosts).
+    pub bind_addr: Option<String>,
+    /// DSCP class for audio streams to this bridge: `EF`, `AF11`-`AF43`, `CS0`-`CS7`, or `0`-`63`.
+    pub dscp: Option<String>,
+    /// Room label used to group outputs (overrides the bridge's advertised room).
+    pub room: Option<String>,
+    /// Zone label (e.g. floor or area) grouping several rooms.
+    pub zone: Option<String>,
 }
 
 /// MusicBrainz configuration.
@@ -62,6 +96,166 @@ pub struct MusicBrainzConfig {
     pub base_url: Option<String>,
     /// Minimum delay between requests in milliseconds (default: 1000).
     pub rate_limit_ms: Option<u64>,
+    /// fanart.tv API key; artist images come from Wikidata alone without one.
+    pub fanart_api_key: Option<String>,
+    /// AcoustID application key; enables fingerprint matching of untagged files.
+    pub acoustid_api_key: Option<String>,
+    /// Chromaprint `fpcalc` binary used for fingerprints (default: `fpcalc` on `PATH`).
+    pub fpcalc_path: Option<String>,
+}
+
+/// Bandwidth limits for `/stream` and `/stream/transcode` (bridges and cast devices are exempt).
+#[derive(Debug, Deserialize)]
+pub struct StreamLimitsConfig {
+    /// Cap for each stream response, in kilobits per second.
+    pub per_connection_kbps: Option<u64>,
+    /// Cap shared by all throttled stream responses, in kilobits per second.
+    pub global_kbps: Option<u64>,
+    /// Additional client IPs that are never throttled.
+    pub exempt_ips: Option<Vec<String>>,
+}
+
+/// Per-client limits for rescans, search, transcodes and MusicBrainz lookups (see `rate_limits`).
+#[derive(Debug, Deserialize)]
+pub struct RateLimitsConfig {
+    /// Turn limiting on or off without removing the section (default: true).
+    pub enabled: Option<bool>,
+    /// Library rescans per client per minute (default: 10).
+    pub rescan_per_minute: Option<u32>,
+    /// Searches per client per minute (default: 120).
+    pub search_per_minute: Option<u32>,
+    /// Transcode streams started per client per minute (default: 30).
+    pub transcode_per_minute: Option<u32>,
+    /// MusicBrainz match searches/applies per client per minute (default: 20).
+    pub musicbrainz_per_minute: Option<u32>,
+    /// Additional client IPs that are never limited.
+    pub exempt_ips: Option<Vec<String>>,
+}
+
+/// Debug capture of `/stream` responses served to bridges (see `stream_capture`).
+#[derive(Debug, Deserialize)]
+pub struct StreamCaptureConfig {
+    /// Turn capture on or off without removing the section (default: true).
+    pub enabled: Option<bool>,
+    /// Directory receiving one `.log`/`.bin` pair per stream response.
+    pub dir: String,
+    /// Payload bytes kept per response (default: 16 MiB).
+    pub max_bytes: Option<u64>,
+    /// Bridge ids to capture (default: all bridges).
+    pub bridges: Option<Vec<String>>,
+}
+
+/// Background library analysis settings.
+#[derive(Debug, Deserialize)]
+pub struct AnalysisConfig {
+    /// Check FLAC tracks for spectral signs of a lossy source (default: false).
+    pub lossy_check: Option<bool>,
+    /// Measure track/album loudness and true peak for volume normalization (default: false).
+    pub loudness: Option<bool>,
+    /// Estimate tempo (BPM) and musical key of every track (default: false).
+    pub tempo_key: Option<bool>,
+}
+
+/// Bridge health-check and discovery timing (see `discovery`).
+#[derive(Debug, Deserialize)]
+pub struct DiscoveryConfig {
+    /// How often each discovered bridge is health-checked, in milliseconds (default: 5000).
+    pub health_interval_ms: Option<u64>,
+    /// Timeout of one health check, in milliseconds (default: 2000).
+    pub health_timeout_ms: Option<u64>,
+    /// Drop a discovered bridge unseen for this long, in milliseconds (default: 20000).
+    pub stale_after_ms: Option<u64>,
+    /// Random spread applied to check and reconnect delays, in percent (default: 20).
+    pub jitter_percent: Option<u8>,
+}
+
+/// Lyrics lookup settings.
+#[derive(Debug, Deserialize)]
+pub struct LyricsConfig {
+    /// Look up lyrics on LRCLIB for tracks without local lyrics (default: false).
+    pub fetch_online: Option<bool>,
+    /// LRCLIB-compatible API base URL (default: https://lrclib.net/api).
+    pub provider_url: Option<String>,
+}
+
+/// API token authentication (see `auth`).
+#[derive(Debug, Deserialize)]
+pub struct AuthConfig {
+    /// Tokens accepted in addition to those created through `/auth/tokens`.
+    pub tokens: Option<Vec<AuthTokenConfig>>,
+}
+
+/// One API token from config.
+#[derive(Debug, Deserialize)]
+pub struct AuthTokenConfig {
+    /// Label shown in `/auth/tokens` and logs.
+    pub name: String,
+    /// Secret sent as `Authorization: Bearer <token>`.
+    pub token: String,
+    /// `admin` (default) or `listener` (playback control only).
+    pub role: Option<crate::auth::TokenRole>,
+}
+
+/// Cross-origin settings (see `cors`).
+#[derive(Debug, Deserialize)]
+pub struct CorsConfig {
+    /// Origins (`scheme://host[:port]`) allowed besides the desktop app and localhost; `*` allows any.
+    pub allowed_origins: Option<Vec<String>>,
+    /// Methods allowed from those origins (default: GET, POST, PUT, DELETE, HEAD).
+    pub allowed_methods: Option<Vec<String>>,
+}
+
+/// Webhook target (see `webhooks`).
+#[derive(Debug, Deserialize)]
+pub struct WebhookConfig {
+    /// http(s) URL that receives a JSON `POST` per event.
+    pub url: String,
+    /// Events to send (default: all of `track_started`, `track_stopped`, `queue_changed`, `scan_completed`).
+    pub events: Option<Vec<String>>,
+    /// Key for the `X-Audio-Hub-Signature: sha256=<hmac>` header over the body.
+    pub secret: Option<String>,
+}
+
+/// MQTT broker connection (see `mqtt`).
+#[derive(Debug, Deserialize)]
+pub struct MqttConfig {
+    /// Broker address, `host[:port]` (default port 1883; an `mqtt://` prefix is accepted).
+    pub broker: String,
+    /// Client id presented to the broker (default: `audio-hub`).
+    pub client_id: Option<String>,
+    /// Broker username.
+    pub username: Option<String>,
+    /// Broker password (requires `username`).
+    pub password: Option<String>,
+    /// Prefix for every topic (default: `audio-hub`).
+    pub topic_prefix: Option<String>,
+}
+
+/// Output group (see `output_groups`).
+#[derive(Debug, Deserialize)]
+pub struct OutputGroupConfig {
+    /// Stable group id used in the output id (`group:<id>`).
+    pub id: String,
+    /// Display name (defaults to id).
+    pub name: Option<String>,
+    /// Bridge output ids played together, at most one per bridge; the first leads alignment.
+    pub members: Vec<String>,
+}
+
+/// Snapcast server the hub feeds (see `snapcast`).
+#[derive(Debug, Deserialize)]
+pub struct SnapcastConfig {
+    /// Snapserver JSON-RPC control address, `host[:port]` (default port 1705).
+    pub server: String,
+    /// Stream id groups are switched to when selected (default: `audio-hub`).
+    pub stream: Option<String>,
+    /// Stream source the hub writes PCM into: `pipe:///path` or `tcp://host:port`
+    /// (default: `tcp://<server host>:4953`).
+    pub sink: Option<String>,
+    /// Sample rate of the stream source (default: 48000; format is always 16-bit stereo).
+    pub sample_rate: Option<u32>,
+    /// Snapserver buffer in ms, used to report elapsed time (default: 1000).
+    pub buffer_ms: Option<u32>,
 }
 
 /// Output settings persisted in config.
@@ -73,6 +267,10 @@ pub struct OutputSettingsConfig {
     pub renames: Option<std::collections::HashMap<String, String>>,
     /// Output ids that should use exclusive mode (bridge-only).
     pub exclusive: Option<Vec<String>>,
+    /// Output id -> silence (ms) played when the output's stream opens (bridge-only).
+    pub preroll_silence_ms: Option<std::collections::HashMap<String, u32>>,
+    /// Output id -> gain offset (dB) applied on top of the session volume.
+    pub volume_trim_db: Option<std::collections::HashMap<String, f32>>,
 }
 
 /// Resolved bridge config with parsed socket address.
@@ -84,19 +282,511 @@ pub struct BridgeConfigResolved {
     pub name: String,
     /// Parsed HTTP address.
     pub http_addr: SocketAddr,
+    /// Room label, if set.
+    pub room: Option<String>,
+    /// Zone label, if set.
+    pub zone: Option<String>,
 }
 
 impl ServerConfig {
     /// Load configuration from disk.
-    pub fn load(path: &Path) -> Result<Self> {
+    ///
+    /// Unknown fields are logged as warnings; with `strict` they fail the load instead.
+    pub fn load(path: &Path, strict: bool) -> Result<Self> {
         let raw =
             std::fs::read_to_string(path).with_context(|| format!("read config {:?}", path))?;
+        let table = raw
+            .parse::<toml::Table>()
+            .with_context(|| format!("parse config {:?}", path))?;
+        let unknown = unknown_fields(&table);
+        if strict && !unknown.is_empty() {
+            return Err(anyhow::anyhow!(
+                "invalid config {:?}:\n  {}",
+                path,
+                unknown.join("\n  ")
+            ));
+        }
+        for problem in &unknown {
+            tracing::warn!(config = ?path, "{problem}");
+        }
         let cfg = toml::from_str::<ServerConfig>(&raw)
             .with_context(|| format!("parse config {:?}", path))?;
         Ok(cfg)
     }
 }
 
+/// Check a config file and return every problem found (empty when valid).
+///
+/// Reports syntax/type errors, unknown fields, and range/path problems in one pass.
+pub fn check_config_file(path: &Path) -> Vec<String> {
+    let raw = match std::fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) => return vec![format!("read config {:?}: {e}", path)],
+    };
+    let table = match raw.parse::<toml::Table>() {
+        Ok(table) => table,
+        Err(e) => return vec![format!("syntax error: {}", e.to_string().trim())],
+    };
+    let mut problems = unknown_fields(&table);
+    match toml::from_str::<ServerConfig>(&raw) {
+        Ok(cfg) => problems.extend(validate_config(&cfg)),
+        Err(e) => problems.push(format!("type error: {}", e.to_string().trim())),
+    }
+    problems
+}
+
+/// List fields that are not part of the config schema (typos, stale options).
+pub fn unknown_fields(table: &toml::Table) -> Vec<String> {
+    let mut problems = Vec::new();
+    collect_unknown("", table, struct_fields::<ServerConfig>(), &mut problems);
+    if let Some(toml::Value::Array(bridges)) = table.get("bridges") {
+        for (idx, bridge) in bridges.iter().enumerate() {
+            if let toml::Value::Table(bridge) = bridge {
+                collect_unknown(
+                    &format!("bridges[{idx}]."),
+                    bridge,
+                    struct_fields::<BridgeConfig>(),
+                    &mut problems,
+                );
+            }
+        }
+    }
+    if let Some(toml::Value::Table(mb)) = table.get("musicbrainz") {
+        collect_unknown(
+            "musicbrainz.",
+            mb,
+            struct_fields::<MusicBrainzConfig>(),
+            &mut problems,
+        );
+    }
+    if let Some(toml::Value::Table(outputs)) = table.get("outputs") {
+        collect_unknown(
+            "outputs.",
+            outputs,
+            struct_fields::<OutputSettingsConfig>(),
+            &mut problems,
+        );
+    }
+    if let Some(toml::Value::Table(limits)) = table.get("stream_limits") {
+        collect_unknown(
+            "stream_limits.",
+            limits,
+            struct_fields::<StreamLimitsConfig>(),
+            &mut problems,
+        );
+    }
+    if let Some(toml::Value::Table(capture)) = table.get("stream_capture") {
+        collect_unknown(
+            "stream_capture.",
+            capture,
+            struct_fields::<StreamCaptureConfig>(),
+            &mut problems,
+        );
+    }
+    if let Some(toml::Value::Table(analysis)) = table.get("analysis") {
+        collect_unknown(
+            "analysis.",
+            analysis,
+            struct_fields::<AnalysisConfig>(),
+            &mut problems,
+        );
+    }
+    if let Some(toml::Value::Table(discovery)) = table.get("discovery") {
+        collect_unknown(
+            "discovery.",
+            discovery,
+            struct_fields::<DiscoveryConfig>(),
+            &mut problems,
+        );
+    }
+    if let Some(toml::Value::Table(lyrics)) = table.get("lyrics") {
+        collect_unknown(
+            "lyrics.",
+            lyrics,
+            struct_fields::<LyricsConfig>(),
+            &mut problems,
+        );
+    }
+    if let Some(toml::Value::Table(limits)) = table.get("rate_limits") {
+        collect_unknown(
+            "rate_limits.",
+            limits,
+            struct_fields::<RateLimitsConfig>(),
+            &mut problems,
+        );
+    }
+    if let Some(toml::Value::Array(hooks)) = table.get("webhooks") {
+        for (idx, hook) in hooks.iter().enumerate() {
+            if let toml::Value::Table(hook) = hook {
+                collect_unknown(
+                    &format!("webhooks[{idx}]."),
+                    hook,
+                    struct_fields::<WebhookConfig>(),
+                    &mut problems,
+                );
+            }
+        }
+    }
+    if let Some(toml::Value::Array(groups)) = table.get("output_groups") {
+        for (idx, group) in groups.iter().enumerate() {
+            if let toml::Value::Table(group) = group {
+                collect_unknown(
+                    &format!("output_groups[{idx}]."),
+                    group,
+                    struct_fields::<OutputGroupConfig>(),
+                    &mut problems,
+                );
+            }
+        }
+    }
+    if let Some(toml::Value::Table(mqtt)) = table.get("mqtt") {
+        collect_unknown("mqtt.", mqtt, struct_fields::<MqttConfig>(), &mut problems);
+    }
+    if let Some(toml::Value::Table(snapcast)) = table.get("snapcast") {
+        collect_unknown(
+            "snapcast.",
+            snapcast,
+            struct_fields::<SnapcastConfig>(),
+            &mut problems,
+        );
+    }
+    if let Some(toml::Value::Table(cors)) = table.get("cors") {
+        collect_unknown("cors.", cors, struct_fields::<CorsConfig>(), &mut problems);
+    }
+    if let Some(toml::Value::Table(auth)) = table.get("auth") {
+        collect_unknown("auth.", auth, struct_fields::<AuthConfig>(), &mut problems);
+        if let Some(toml::Value::Array(tokens)) = auth.get("tokens") {
+            for (idx, token) in tokens.iter().enumerate() {
+                if let toml::Value::Table(token) = token {
+                    collect_unknown(
+                        &format!("auth.tokens[{idx}]."),
+                        token,
+                        struct_fields::<AuthTokenConfig>(),
+                        &mut problems,
+                    );
+                }
+            }
+        }
+    }
+    problems
+}
+
+/// Validate value ranges and referenced paths of a parsed config.
+pub fn validate_config(cfg: &ServerConfig) -> Vec<String> {
+    let mut problems = Vec::new();
+    if let Some(bind) = cfg.bind.as_deref() {
+        check_socket_addr("bind", bind, &mut problems);
+    }
+    check_http_url(
+        "public_base_url",
+        cfg.public_base_url.as_deref(),
+        &mut problems,
+    );
+    if let Some(dir) = cfg.media_dir.as_deref()
+        && !Path::new(dir).is_dir()
+    {
+        problems.push(format!("media_dir: directory does not exist: {dir}"));
+    }
+    match (cfg.tls_cert.as_deref(), cfg.tls_key.as_deref()) {
+        (Some(_), None) => problems.push("tls_cert: set without tls_key".to_string()),
+        (None, Some(_)) => problems.push("tls_key: set without tls_cert".to_string()),
+        _ => {}
+    }
+    for (field, path) in [("tls_cert", &cfg.tls_cert), ("tls_key", &cfg.tls_key)] {
+        if let Some(path) = path.as_deref()
+            && !Path::new(path).is_file()
+        {
+            problems.push(format!("{field}: file does not exist: {path}"));
+        }
+    }
+    let mut seen_ids = std::collections::HashSet::new();
+    for (idx, bridge) in cfg.bridges.iter().flatten().enumerate() {
+        let id = bridge.id.trim();
+        if id.is_empty() {
+            problems.push(format!("bridges[{idx}].id: must not be empty"));
+        } else if !seen_ids.insert(id.to_string()) {
+            problems.push(format!("bridges[{idx}].id: duplicate bridge id `{id}`"));
+        }
+        check_socket_addr(
+            &format!("bridges[{idx}].http_addr"),
+            &bridge.http_addr,
+            &mut problems,
+        );
+        if let Some(ip) = bridge.bind_addr.as_deref()
+            && ip.parse::<std::net::IpAddr>().is_err()
+        {
+            problems.push(format!(
+                "bridges[{idx}].bind_addr: invalid IP address `{ip}`"
+            ));
+        }
+        if let Some(dscp) = bridge.dscp.as_deref()
+            && audio_bridge_types::parse_dscp(dscp).is_none()
+        {
+            problems.push(format!(
+                "bridges[{idx}].dscp: expected EF, AF11-AF43, CS0-CS7, or 0-63 (got `{dscp}`)"
+            ));
+        }
+    }
+    if let Some(mb) = cfg.musicbrainz.as_ref() {
+        let has_user_agent = mb
+            .user_agent
+            .as_deref()
+            .map(|ua| !ua.trim().is_empty())
+            .unwrap_or(false);
+        if mb.enabled.unwrap_or(false) && !has_user_agent {
+            problems.push("musicbrainz.user_agent: required when enabled = true".to_string());
+        }
+        if let Some(ms) = mb.rate_limit_ms
+            && !(MIN_MUSICBRAINZ_RATE_LIMIT_MS..=MAX_MUSICBRAINZ_RATE_LIMIT_MS).contains(&ms)
+        {
+            problems.push(format!(
+                "musicbrainz.rate_limit_ms: must be between {MIN_MUSICBRAINZ_RATE_LIMIT_MS} and {MAX_MUSICBRAINZ_RATE_LIMIT_MS} (got {ms})"
+            ));
+        }
+        check_http_url(
+            "musicbrainz.base_url",
+            mb.base_url.as_deref(),
+            &mut problems,
+        );
+    }
+    if let Some(outputs) = cfg.outputs.as_ref() {
+        for (field, ids) in [
+            ("outputs.disabled", &outputs.disabled),
+            ("outputs.exclusive", &outputs.exclusive),
+        ] {
+            if ids.iter().flatten().any(|id| id.trim().is_empty()) {
+                problems.push(format!("{field}: output ids must not be empty"));
+            }
+        }
+        for (id, ms) in outputs.preroll_silence_ms.iter().flatten() {
+            if id.trim().is_empty() {
+                problems
+                    .push("outputs.preroll_silence_ms: output ids must not be empty".to_string());
+            }
+            if *ms > MAX_PREROLL_SILENCE_MS {
+                problems.push(format!(
+                    "outputs.preroll_silence_ms.{id}: must be at most {MAX_PREROLL_SILENCE_MS} (got {ms})"
+                ));
+            }
+        }
+        for (id, db) in outputs.volume_trim_db.iter().flatten() {
+            if id.trim().is_empty() {
+                problems.push("outputs.volume_trim_db: output ids must not be empty".to_string());
+            }
+            if !volume_trim_in_range(*db) {
+                problems.push(format!(
+                    "outputs.volume_trim_db.{id}: must be between {MIN_VOLUME_TRIM_DB} and {MAX_VOLUME_TRIM_DB} dB (got {db})"
+                ));
+            }
+        }
+    }
+    if let Some(limits) = cfg.stream_limits.as_ref() {
+        for (field, kbps) in [
+            (
+                "stream_limits.per_connection_kbps",
+                limits.per_connection_kbps,
+            ),
+            ("stream_limits.global_kbps", limits.global_kbps),
+        ] {
+            if kbps == Some(0) {
+                problems.push(format!("{field}: must be greater than 0"));
+            }
+        }
+        for (idx, ip) in limits.exempt_ips.iter().flatten().enumerate() {
+            if ip.parse::<std::net::IpAddr>().is_err() {
+                problems.push(format!(
+                    "stream_limits.exempt_ips[{idx}]: invalid IP address `{ip}`"
+                ));
+            }
+        }
+    }
+    if let Some(limits) = cfg.rate_limits.as_ref() {
+        for (field, value) in [
+            ("rate_limits.rescan_per_minute", limits.rescan_per_minute),
+            ("rate_limits.search_per_minute", limits.search_per_minute),
+            (
+                "rate_limits.transcode_per_minute",
+                limits.transcode_per_minute,
+            ),
+            (
+                "rate_limits.musicbrainz_per_minute",
+                limits.musicbrainz_per_minute,
+            ),
+        ] {
+            if value == Some(0) {
+                problems.push(format!("{field}: must be greater than 0"));
+            }
+        }
+        for (idx, ip) in limits.exempt_ips.iter().flatten().enumerate() {
+            if ip.parse::<std::net::IpAddr>().is_err() {
+                problems.push(format!(
+                    "rate_limits.exempt_ips[{idx}]: invalid IP address `{ip}`"
+                ));
+            }
+        }
+    }
+    if let Some(capture) = cfg.stream_capture.as_ref() {
+        if capture.dir.trim().is_empty() {
+            problems.push("stream_capture.dir: must not be empty".to_string());
+        }
+        if capture.max_bytes == Some(0) {
+            problems.push("stream_capture.max_bytes: must be greater than 0".to_string());
+        }
+        if capture
+            .bridges
+            .iter()
+            .flatten()
+            .any(|id| id.trim().is_empty())
+        {
+            problems.push("stream_capture.bridges: bridge ids must not be empty".to_string());
+        }
+    }
+    if let Some(discovery) = cfg.discovery.as_ref()
+        && let Err(errors) = crate::discovery::timing_from_section(discovery)
+    {
+        problems.extend(errors);
+    }
+    if let Some(cors) = cfg.cors.as_ref()
+        && let Err(errors) = crate::cors::policy_from_section(cors)
+    {
+        problems.extend(errors);
+    }
+    if let Err(errors) = crate::webhooks::targets_from_config(cfg) {
+        problems.extend(errors);
+    }
+    if let Some(mqtt) = cfg.mqtt.as_ref()
+        && let Err(errors) = crate::mqtt::settings_from_section(mqtt)
+    {
+        problems.extend(errors);
+    }
+    if let Some(snapcast) = cfg.snapcast.as_ref()
+        && let Err(errors) = crate::snapcast::settings_from_section(snapcast)
+    {
+        problems.extend(errors);
+    }
+    if let Err(errors) = crate::output_groups::groups_from_config(cfg) {
+        problems.extend(errors);
+    }
+    let mut seen_names = std::collections::HashSet::new();
+    let mut seen_tokens = std::collections::HashSet::new();
+    for (idx, token) in cfg
+        .auth
+        .iter()
+        .flat_map(|auth| auth.tokens.iter().flatten())
+        .enumerate()
+    {
+        let name = token.name.trim();
+        if name.is_empty() {
+            problems.push(format!("auth.tokens[{idx}].name: must not be empty"));
+        } else if !seen_names.insert(name.to_string()) {
+            problems.push(format!(
+                "auth.tokens[{idx}].name: duplicate token name `{name}`"
+            ));
+        }
+        if token.token.trim().len() < MIN_AUTH_TOKEN_LEN {
+            problems.push(format!(
+                "auth.tokens[{idx}].token: must be at least {MIN_AUTH_TOKEN_LEN} characters"
+            ));
+        } else if !seen_tokens.insert(token.token.trim()) {
+            problems.push(format!("auth.tokens[{idx}].token: duplicate token"));
+        }
+    }
+    problems
+}
+
+/// MusicBrainz asks clients to stay at or below one request per second.
+const MIN_MUSICBRAINZ_RATE_LIMIT_MS: u64 = 1000;
+/// Upper bound to catch values entered in the wrong unit (e.g. microseconds).
+const MAX_MUSICBRAINZ_RATE_LIMIT_MS: u64 = 60_000;
+
+/// Shortest accepted config token, to rule out guessable secrets.
+const MIN_AUTH_TOKEN_LEN: usize = 16;
+
+/// Longest pre-roll silence an output may request (matches the bridge's limit).
+pub(crate) const MAX_PREROLL_SILENCE_MS: u32 = 5_000;
+/// Lowest per-output volume trim.
+pub(crate) const MIN_VOLUME_TRIM_DB: f32 = -30.0;
+/// Highest per-output volume trim.
+pub(crate) const MAX_VOLUME_TRIM_DB: f32 = 12.0;
+
+/// Whether a per-output volume trim is finite and within range.
+pub(crate) fn volume_trim_in_range(db: f32) -> bool {
+    (MIN_VOLUME_TRIM_DB..=MAX_VOLUME_TRIM_DB).contains(&db)
+}
+
+/// Validate that an optional URL uses an http(s) scheme.
+fn check_http_url(field: &str, value: Option<&str>, problems: &mut Vec<String>) {
+    if let Some(url) = value
+        && !(url.starts_with("http://") || url.starts_with("https://"))
+    {
+        problems.push(format!(
+            "{field}: must start with http:// or https:// (got `{url}`)"
+        ));
+    }
+}
+
+/// Validate a `host:port` address with a non-zero port.
+fn check_socket_addr(field: &str, value: &str, problems: &mut Vec<String>) {
+    match value.parse::<SocketAddr>() {
+        Ok(addr) if addr.port() == 0 => {
+            problems.push(format!("{field}: port must be between 1 and 65535"))
+        }
+        Ok(_) => {}
+        Err(e) => problems.push(format!("{field}: invalid address `{value}` ({e})")),
+    }
+}
+
+/// Record keys of `table` that are not in `expected`.
+fn collect_unknown(
+    prefix: &str,
+    table: &toml::Table,
+    expected: &[&str],
+    problems: &mut Vec<String>,
+) {
+    for key in table.keys() {
+        if !expected.contains(&key.as_str()) {
+            problems.push(format!(
+                "{prefix}{key}: unknown field (expected one of: {})",
+                expected.join(", ")
+            ));
+        }
+    }
+}
+
+/// Field names of a `#[derive(Deserialize)]` struct, used for unknown-field detection.
+fn struct_fields<T: DeserializeOwned>() -> &'static [&'static str] {
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(FieldProbe(&mut fields));
+    fields
+}
+
+/// Deserializer that only captures the field list a struct asks for.
+struct FieldProbe<'a>(&'a mut &'static [&'static str]);
+
+impl<'de> de::Deserializer<'de> for FieldProbe<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("field probe"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = fields;
+        Err(de::Error::custom("field probe"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+        ignored_any
+    }
+}
+
 /// Resolve bridge configs and parse their addresses.
 pub fn bridges_from_config(cfg: &ServerConfig) -> Result<Vec<BridgeConfigResolved>> {
     let mut bridges = Vec::new();
@@ -111,6 +801,8 @@ pub fn bridges_from_config(cfg: &ServerConfig) -> Result<Vec<BridgeConfigResolve
                 id: bridge.id.clone(),
                 name,
                 http_addr,
+                room: location_label(bridge.room.as_deref()),
+                zone: location_label(bridge.zone.as_deref()),
             });
         }
     }
@@ -118,6 +810,14 @@ pub fn bridges_from_config(cfg: &ServerConfig) -> Result<Vec<BridgeConfigResolve
     Ok(bridges)
 }
 
+/// Normalize a room/zone label: trimmed, with blanks treated as unset.
+pub fn location_label(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
 /// Extract the media directory from config.
 pub fn media_dir_from_config(cfg: &ServerConfig) -> Result<std::path::PathBuf> {
     let dir = cfg
@@ -197,6 +897,25 @@ pub fn update_output_settings(path: &Path, settings: &OutputSettingsConfig) -> R
         }
         outputs["exclusive"] = toml_edit::value(arr);
     }
+    if let Some(preroll) = settings
+        .preroll_silence_ms
+        .as_ref()
+        .filter(|m| !m.is_empty())
+    {
+        let mut preroll_table = toml_edit::Table::new();
+        for (id, ms) in preroll {
+            preroll_table[id.as_str()] = toml_edit::value(i64::from(*ms));
+        }
+        outputs["preroll_silence_ms"] = toml_edit::Item::Table(preroll_table);
+    }
+    if let Some(trims) = settings.volume_trim_db.as_ref().filter(|m| !m.is_empty()) {
+        let mut trim_table = toml_edit::Table::new();
+        for (id, db) in trims {
+            // Stored at 0.1 dB so f32 values don't print as -6.099999904632568.
+            trim_table[id.as_str()] = toml_edit::value((f64::from(*db) * 10.0).round() / 10.0);
+        }
+        outputs["volume_trim_db"] = toml_edit::Item::Table(trim_table);
+    }
 
     if outputs.is_empty() {
         doc.remove("outputs");
@@ -228,6 +947,18 @@ mod tests {
             tls_cert: None,
             tls_key: None,
             outputs: None,
+            stream_limits: None,
+            stream_capture: None,
+            analysis: None,
+            discovery: None,
+            lyrics: None,
+            auth: None,
+            cors: None,
+            rate_limits: None,
+            webhooks: None,
+            mqtt: None,
+            snapcast: None,
+            output_groups: None,
         };
         let bind: std::net::SocketAddr = "127.0.0.1:8080".parse().unwrap();
         let url = public_base_url_from_config(&cfg, bind, false).unwrap();
@@ -250,11 +981,146 @@ mod tests {
             tls_cert: None,
             tls_key: None,
             outputs: None,
+            stream_limits: None,
+            stream_capture: None,
+            analysis: None,
+            discovery: None,
+            lyrics: None,
+            auth: None,
+            cors: None,
+            rate_limits: None,
+            webhooks: None,
+            mqtt: None,
+            snapcast: None,
+            output_groups: None,
         };
         let bind: std::net::SocketAddr = "0.0.0.0:8080".parse().unwrap();
         assert!(public_base_url_from_config(&cfg, bind, false).is_err());
     }
 
+    #[test]
+    fn unknown_fields_reports_every_typo_with_path() {
+        let table = r#"
+            medai_dir = "/srv/music"
+            [[bridges]]
+            id = "a"
+            http_adr = "127.0.0.1:5556"
+            [musicbrainz]
+            enable = true
+        "#
+        .parse::<toml::Table>()
+        .unwrap();
+        let problems = unknown_fields(&table);
+        assert_eq!(problems.len(), 3);
+        assert!(problems[0].starts_with("medai_dir: unknown field"));
+        assert!(problems[0].contains("media_dir"));
+        assert!(problems[1].starts_with("bridges[0].http_adr: unknown field"));
+        assert!(problems[2].starts_with("musicbrainz.enable: unknown field"));
+    }
+
+    #[test]
+    fn validate_config_reports_ranges_and_paths() {
+        let cfg: ServerConfig = toml::from_str(
+            r#"
+            bind = "127.0.0.1:0"
+            media_dir = "/definitely/missing/audio-hub"
+            tls_cert = "/definitely/missing/cert.pem"
+            [[bridges]]
+            id = "a"
+            http_addr = "not-an-addr"
+            [[bridges]]
+            id = "a"
+            http_addr = "127.0.0.1:5556"
+            [musicbrainz]
+            enabled = true
+            rate_limit_ms = 10
+        "#,
+        )
+        .unwrap();
+        let problems = validate_config(&cfg);
+        let fields: Vec<&str> = problems
+            .iter()
+            .map(|p| p.split(':').next().unwrap())
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                "bind",
+                "media_dir",
+                "tls_cert",
+                "tls_cert",
+                "bridges[0].http_addr",
+                "bridges[1].id",
+                "musicbrainz.user_agent",
+                "musicbrainz.rate_limit_ms",
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_config_bounds_output_preroll() {
+        let cfg: ServerConfig = toml::from_str(
+            r#"
+            bind = "127.0.0.1:8080"
+            [outputs.preroll_silence_ms]
+            "bridge:a:dac" = 250
+            "bridge:a:usb" = 9000
+        "#,
+        )
+        .unwrap();
+        let problems = validate_config(&cfg);
+        assert_eq!(
+            problems,
+            vec!["outputs.preroll_silence_ms.bridge:a:usb: must be at most 5000 (got 9000)"]
+        );
+    }
+
+    #[test]
+    fn validate_config_bounds_output_volume_trim() {
+        let cfg: ServerConfig = toml::from_str(
+            r#"
+            bind = "127.0.0.1:8080"
+            [outputs.volume_trim_db]
+            "bridge:a:dac" = -6.0
+            "bridge:a:usb" = 20.0
+        "#,
+        )
+        .unwrap();
+        let problems = validate_config(&cfg);
+        assert_eq!(
+            problems,
+            vec!["outputs.volume_trim_db.bridge:a:usb: must be between -30 and 12 dB (got 20)"]
+        );
+    }
+
+    #[test]
+    fn validate_config_checks_auth_tokens() {
+        let cfg: ServerConfig = toml::from_str(
+            r#"
+            [[auth.tokens]]
+            name = "phone"
+            token = "0123456789abcdef0123"
+            [[auth.tokens]]
+            name = "phone"
+            token = "short"
+            [[auth.tokens]]
+            name = "tablet"
+            token = "0123456789abcdef0123"
+            role = "listener"
+        "#,
+        )
+        .unwrap();
+        let problems = validate_config(&cfg);
+        assert_eq!(
+            problems,
+            vec![
+                "auth.tokens[1].name: duplicate token name `phone`",
+                "auth.tokens[1].token: must be at least 16 characters",
+                "auth.tokens[2].token: duplicate token",
+            ]
+        );
+    }
+
     #[test]
     fn bind_from_config_parses_when_present() {
         let cfg = ServerConfig {
@@ -271,6 +1137,18 @@ mod tests {
             tls_cert: None,
             tls_key: None,
             outputs: None,
+            stream_limits: None,
+            stream_capture: None,
+            analysis: None,
+            discovery: None,
+            lyrics: None,
+            auth: None,
+            cors: None,
+            rate_limits: None,
+            webhooks: None,
+            mqtt: None,
+            snapcast: None,
+            output_groups: None,
         };
         let addr = bind_from_config(&cfg).unwrap().unwrap();
         assert_eq!(addr, "127.0.0.1:9000".parse().unwrap());
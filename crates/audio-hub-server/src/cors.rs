@@ -0,0 +1,215 @@
+//! Cross-origin policy from `[cors]` config.
+//!
+//! The desktop app and dev servers on `localhost`/`127.0.0.1` are always allowed.
+//! `allowed_origins` adds frontends hosted elsewhere (`"*"` allows any origin). The policy wraps
+//! every route, SSE streams included.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use actix_cors::Cors;
+use actix_web::http::{Method, header};
+use anyhow::Result;
+
+use crate::config::{CorsConfig, ServerConfig};
+
+/// Methods allowed when `allowed_methods` is not set.
+const DEFAULT_METHODS: [Method; 5] = [
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::DELETE,
+    Method::HEAD,
+];
+
+/// Seconds browsers may cache a preflight answer.
+const PREFLIGHT_MAX_AGE_SECS: usize = 3600;
+
+/// Resolved cross-origin policy.
+#[derive(Debug, Clone)]
+pub struct CorsPolicy {
+    /// Allow every origin (`"*"` in `allowed_origins`).
+    any_origin: bool,
+    /// Extra origins, lowercase `scheme://host[:port]`.
+    origins: Arc<HashSet<String>>,
+    /// Methods answered in preflight responses.
+    methods: Vec<Method>,
+}
+
+impl Default for CorsPolicy {
+    fn default() -> Self {
+        Self {
+            any_origin: false,
+            origins: Arc::new(HashSet::new()),
+            methods: DEFAULT_METHODS.to_vec(),
+        }
+    }
+}
+
+impl CorsPolicy {
+    /// Build the Actix middleware (one per worker).
+    pub fn build(&self) -> Cors {
+        let any_origin = self.any_origin;
+        let origins = self.origins.clone();
+        Cors::default()
+            .allowed_origin_fn(move |origin, _req_head| {
+                let value = origin.as_bytes();
+                any_origin
+                    || builtin_origin(value)
+                    || std::str::from_utf8(value).is_ok_and(|origin| origins.contains(origin))
+            })
+            .allowed_methods(self.methods.clone())
+            .allowed_headers(vec![header::CONTENT_TYPE, header::AUTHORIZATION])
+            .max_age(PREFLIGHT_MAX_AGE_SECS)
+    }
+}
+
+/// Desktop app and local dev server origins.
+fn builtin_origin(value: &[u8]) -> bool {
+    value == b"tauri://localhost"
+        || value == b"http://tauri.localhost"
+        || value.starts_with(b"http://localhost:")
+        || value.starts_with(b"http://127.0.0.1:")
+        || value.starts_with(b"https://localhost:")
+        || value.starts_with(b"https://127.0.0.1:")
+}
+
+/// Resolve `[cors]` from config (built-in origins and default methods when absent).
+pub fn from_config(cfg: &ServerConfig) -> Result<CorsPolicy> {
+    match cfg.cors.as_ref() {
+        Some(section) => {
+            policy_from_section(section).map_err(|errors| anyhow::anyhow!(errors.join("; ")))
+        }
+        None => Ok(CorsPolicy::default()),
+    }
+}
+
+/// Resolve a `[cors]` section, listing every invalid field.
+pub(crate) fn policy_from_section(
+    section: &CorsConfig,
+) -> std::result::Result<CorsPolicy, Vec<String>> {
+    let mut errors = Vec::new();
+    let mut policy = CorsPolicy::default();
+    let mut origins = HashSet::new();
+    for (idx, origin) in section.allowed_origins.iter().flatten().enumerate() {
+        let origin = origin.trim();
+        if origin == "*" {
+            policy.any_origin = true;
+        } else if valid_origin(origin) {
+            origins.insert(origin.to_ascii_lowercase());
+        } else {
+            errors.push(format!(
+                "cors.allowed_origins[{idx}]: expected `*` or scheme://host[:port] without a path (got `{origin}`)"
+            ));
+        }
+    }
+    policy.origins = Arc::new(origins);
+    if let Some(methods) = section.allowed_methods.as_ref() {
+        if methods.is_empty() {
+            errors.push("cors.allowed_methods: must not be empty".to_string());
+        }
+        policy.methods = Vec::new();
+        for (idx, method) in methods.iter().enumerate() {
+            match Method::from_bytes(method.trim().to_ascii_uppercase().as_bytes()) {
+                Ok(method) if !method.as_str().is_empty() => policy.methods.push(method),
+                _ => errors.push(format!(
+                    "cors.allowed_methods[{idx}]: invalid method `{method}`"
+                )),
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(policy)
+    } else {
+        Err(errors)
+    }
+}
+
+/// True for `scheme://host[:port]` with no path, query or trailing slash.
+fn valid_origin(origin: &str) -> bool {
+    let Some((scheme, authority)) = origin.split_once("://") else {
+        return false;
+    };
+    !scheme.is_empty()
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        && !authority.is_empty()
+        && !authority.contains(['/', '?', '#', ' '])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::{TestRequest, call_service, init_service};
+    use actix_web::{App, HttpResponse, web};
+
+    #[test]
+    fn policy_from_section_reports_bad_origins_and_methods() {
+        let section: CorsConfig = toml::from_str(
+            r#"
+            allowed_origins = ["https://music.example.com", "https://x.example.com/app", "*"]
+            allowed_methods = ["get", "PO ST"]
+        "#,
+        )
+        .unwrap();
+        assert_eq!(
+            policy_from_section(&section).unwrap_err(),
+            vec![
+                "cors.allowed_origins[1]: expected `*` or scheme://host[:port] without a path (got `https://x.example.com/app`)",
+                "cors.allowed_methods[1]: invalid method `PO ST`",
+            ]
+        );
+    }
+
+    #[actix_web::test]
+    async fn configured_origins_are_allowed_on_every_route() {
+        let section: CorsConfig = toml::from_str(
+            r#"
+            allowed_origins = ["https://Music.Example.com"]
+            allowed_methods = ["GET"]
+        "#,
+        )
+        .unwrap();
+        let policy = policy_from_section(&section).unwrap();
+        let app = init_service(
+            App::new()
+                .wrap(policy.build())
+                .route("/outputs/stream", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+        let from_origin = |origin: &'static str| {
+            TestRequest::get()
+                .uri("/outputs/stream")
+                .insert_header((header::ORIGIN, origin))
+                .to_request()
+        };
+
+        let resp = call_service(&app, from_origin("https://music.example.com")).await;
+        assert_eq!(
+            resp.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "https://music.example.com"
+        );
+        let resp = call_service(&app, from_origin("http://localhost:5173")).await;
+        assert!(
+            resp.headers()
+                .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+        );
+        let resp = call_service(&app, from_origin("https://evil.example.com")).await;
+        assert!(
+            !resp
+                .headers()
+                .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+        );
+
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri("/outputs/stream")
+            .insert_header((header::ORIGIN, "https://music.example.com"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "POST"))
+            .to_request();
+        assert_eq!(call_service(&app, req).await.status(), 400);
+    }
+}
@@ -29,7 +29,8 @@ const COVER_FILENAMES: [&str; 10] = [
 ];
 const CAA_BASE_URL: &str = "https://coverartarchive.org/release";
 const CAA_RATE_LIMIT_MS: u64 = 1000;
-const MAX_COVER_BYTES: usize = 5_000_000;
+/// Largest cover image accepted from the Cover Art Archive or an upload.
+pub(crate) const MAX_COVER_BYTES: usize = 5_000_000;
 
 #[derive(Clone)]
 /// Resolves and persists album cover art from embedded tags/folder art/Cover Art Archive.
@@ -85,6 +86,29 @@ impl CoverArtResolver {
             .set_album_cover_if_empty(album, artist, &relative_path)?;
         Ok(())
     }
+
+    /// Store an uploaded image as the album's cover, replacing whatever was resolved before.
+    pub fn replace_album_cover(&self, album_id: i64, mime_type: &str, data: &[u8]) -> Result<()> {
+        let hint = format!("album-{album_id}");
+        let relative_path = self.store.store_cover_art(&hint, mime_type, data)?;
+        if let Some(previous) = self.db.replace_album_cover(album_id, &relative_path)? {
+            self.store.remove_cover_art(&previous);
+        }
+        Ok(())
+    }
+}
+
+/// Image type of `data` from its magic bytes, limited to the formats covers are served in.
+pub(crate) fn sniff_image_mime(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("image/jpeg")
+    } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
 }
 
 /// Read first matching folder cover file from a track directory.
@@ -205,6 +229,21 @@ impl CoverArtStore {
         }
         Ok(relative.to_string_lossy().to_string())
     }
+
+    /// Delete a cached cover file; paths outside the cache are left alone.
+    fn remove_cover_art(&self, relative: &str) {
+        let path = Path::new(relative);
+        if !path.starts_with(COVER_CACHE_DIR)
+            || path
+                .components()
+                .any(|c| matches!(c, std::path::Component::ParentDir))
+        {
+            return;
+        }
+        if let Err(err) = fs::remove_file(self.root.join(path)) {
+            tracing::debug!(error = %err, path = relative, "old cover art not removed");
+        }
+    }
 }
 
 /// Map MIME type to cache filename extension.
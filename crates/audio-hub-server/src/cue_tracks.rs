@@ -0,0 +1,134 @@
+//! Virtual tracks backed by cue sheets.
+//!
+//! A single-file album rip with a `.cue` sheet next to it is listed as one track per sheet
+//! entry. A virtual track's path is the sheet path plus a `#NN` track suffix
+//! (`/music/Album/Album.cue#03`); these helpers map it back to the audio file and the part
+//! of it to play.
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow};
+use audio_player::cue::{self, CueSheet, TrackRange};
+
+/// Separates the sheet path from the track number in a virtual track path.
+const TRACK_SEPARATOR: char = '#';
+
+/// Audio file and range a virtual track plays.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CueTrackSource {
+    /// Audio file named by the sheet's `FILE`.
+    pub audio_path: PathBuf,
+    /// Part of `audio_path` holding the track.
+    pub range: TrackRange,
+}
+
+/// Return whether `path` names a cue sheet.
+pub fn is_cue_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("cue"))
+}
+
+/// Virtual path of track `number` of the sheet at `cue_path`.
+pub fn track_path(cue_path: &Path, number: u32) -> PathBuf {
+    let mut path = cue_path.as_os_str().to_os_string();
+    path.push(format!("{TRACK_SEPARATOR}{number:02}"));
+    PathBuf::from(path)
+}
+
+/// Split a virtual track path into the sheet path and track number.
+///
+/// Returns `None` for ordinary file paths.
+pub fn split_track_path(path: &Path) -> Option<(PathBuf, u32)> {
+    let name = path.file_name()?.to_str()?;
+    let (sheet, number) = name.rsplit_once(TRACK_SEPARATOR)?;
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let cue_path = path.with_file_name(sheet);
+    is_cue_file(&cue_path).then_some((cue_path, number.parse().ok()?))
+}
+
+/// Audio file of a single-file sheet, resolved next to the sheet.
+///
+/// `None` when the sheet spans several files (one file per track needs no splitting) or
+/// its file is missing.
+pub fn audio_file(cue_path: &Path, sheet: &CueSheet) -> Option<PathBuf> {
+    let file = sheet.single_file()?;
+    let audio = cue_path.parent()?.join(file);
+    audio.is_file().then_some(audio)
+}
+
+/// Resolve a virtual track path to its audio file and range.
+///
+/// Returns `Ok(None)` for ordinary file paths.
+pub fn resolve(path: &Path) -> Result<Option<CueTrackSource>> {
+    let Some((cue_path, number)) = split_track_path(path) else {
+        return Ok(None);
+    };
+    let sheet = cue::read(&cue_path)?;
+    let track = sheet
+        .track(number)
+        .ok_or_else(|| anyhow!("track {number} not found in {:?}", cue_path))?;
+    let audio_path = audio_file(&cue_path, &sheet)
+        .ok_or_else(|| anyhow!("audio file for {:?} not found", cue_path))?;
+    Ok(Some(CueTrackSource {
+        audio_path,
+        range: track.range,
+    }))
+}
+
+/// Return the file to read for `path`: the backing audio file of a virtual track, or `path`.
+pub fn source_file(path: &Path) -> Result<PathBuf> {
+    Ok(resolve(path)?.map_or_else(|| path.to_path_buf(), |src| src.audio_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn track_path_round_trips() {
+        let cue_path = PathBuf::from("/music/Album/Album.cue");
+        let path = track_path(&cue_path, 3);
+        assert_eq!(path, PathBuf::from("/music/Album/Album.cue#03"));
+        assert_eq!(split_track_path(&path), Some((cue_path, 3)));
+        assert!(split_track_path(Path::new("/music/Album/01 #1.flac")).is_none());
+        assert!(split_track_path(Path::new("/music/Album/Album.cue#")).is_none());
+        assert!(split_track_path(Path::new("/music/Album/Album.cue")).is_none());
+    }
+
+    #[test]
+    fn resolve_maps_virtual_track_to_audio_file() {
+        let root = std::env::temp_dir().join(format!(
+            "audio-hub-cue-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let _ = std::fs::create_dir_all(&root);
+        let audio = root.join("Album.flac");
+        let _ = std::fs::write(&audio, b"test");
+        let cue_path = root.join("Album.cue");
+        let _ = std::fs::write(
+            &cue_path,
+            "FILE \"Album.flac\" WAVE\nTRACK 01 AUDIO\nINDEX 01 00:00:00\n\
+             TRACK 02 AUDIO\nINDEX 01 01:00:00\n",
+        );
+
+        let src = resolve(&track_path(&cue_path, 2)).unwrap().unwrap();
+        assert_eq!(src.audio_path, audio);
+        assert_eq!(
+            src.range,
+            TrackRange {
+                start_ms: 60_000,
+                end_ms: None
+            }
+        );
+        assert!(resolve(&track_path(&cue_path, 9)).is_err());
+        assert!(resolve(&audio).unwrap().is_none());
+        assert_eq!(source_file(&track_path(&cue_path, 1)).unwrap(), audio);
+    }
+}
@@ -1,17 +1,152 @@
-//! mDNS discovery for bridge instances.
+//! mDNS discovery for bridge instances, Cast devices and AirPlay receivers, plus SSDP discovery of
+//! DLNA renderers.
 //!
 //! Runs a background task that updates the bridge registry from mDNS events.
+//!
+//! Discovered bridges are health-checked on the `[discovery]` schedule. Every check (and every
+//! bridge stream reconnect) is spread by `jitter_percent` so many bridges, or many hubs, do not
+//! probe in lockstep.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
 
 use actix_web::web;
+use anyhow::Result;
 use mdns_sd::{ServiceDaemon, ServiceEvent};
 
 use crate::bridge_device_streams::{
     spawn_bridge_device_stream_for_discovered, spawn_bridge_status_stream_for_discovered,
 };
-use crate::state::{AppState, DiscoveredCast};
+use crate::config::{DiscoveryConfig, ServerConfig};
+use crate::state::{AppState, BridgeProbe, DiscoveredAirplay, DiscoveredCast, DiscoveredDlna};
+
+/// Largest accepted `jitter_percent`.
+const MAX_JITTER_PERCENT: u8 = 50;
+
+/// Effective health-check and discovery timing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiscoveryTiming {
+    /// Delay between health checks of one discovered bridge.
+    pub health_interval: Duration,
+    /// Timeout of one health check.
+    pub health_timeout: Duration,
+    /// Discovered bridges unseen for this long are dropped.
+    pub stale_after: Duration,
+    /// Random spread of check and reconnect delays, in percent.
+    pub jitter_percent: u8,
+}
+
+impl Default for DiscoveryTiming {
+    fn default() -> Self {
+        Self {
+            health_interval: Duration::from_secs(5),
+            health_timeout: Duration::from_secs(2),
+            stale_after: Duration::from_secs(20),
+            jitter_percent: 20,
+        }
+    }
+}
+
+impl DiscoveryTiming {
+    /// `delay` moved by a random amount of up to `jitter_percent` either way.
+    pub fn jittered(&self, delay: Duration) -> Duration {
+        jitter_with(delay, self.jitter_percent, random_unit())
+    }
+}
+
+fn store() -> &'static Mutex<DiscoveryTiming> {
+    static STORE: OnceLock<Mutex<DiscoveryTiming>> = OnceLock::new();
+    STORE.get_or_init(|| Mutex::new(DiscoveryTiming::default()))
+}
+
+/// Parse `[discovery]` (defaults when absent).
+pub fn from_config(cfg: &ServerConfig) -> Result<DiscoveryTiming> {
+    match cfg.discovery.as_ref() {
+        Some(section) => {
+            timing_from_section(section).map_err(|errors| anyhow::anyhow!(errors.join("; ")))
+        }
+        None => Ok(DiscoveryTiming::default()),
+    }
+}
+
+/// Resolve a `[discovery]` section, listing every invalid field.
+pub(crate) fn timing_from_section(
+    section: &DiscoveryConfig,
+) -> std::result::Result<DiscoveryTiming, Vec<String>> {
+    let defaults = DiscoveryTiming::default();
+    let mut errors = Vec::new();
+    let mut millis = |field: &str, value: Option<u64>, default: Duration| match value {
+        Some(0) => {
+            errors.push(format!("discovery.{field}: must be greater than 0"));
+            default
+        }
+        Some(ms) => Duration::from_millis(ms),
+        None => default,
+    };
+    let timing = DiscoveryTiming {
+        health_interval: millis(
+            "health_interval_ms",
+            section.health_interval_ms,
+            defaults.health_interval,
+        ),
+        health_timeout: millis(
+            "health_timeout_ms",
+            section.health_timeout_ms,
+            defaults.health_timeout,
+        ),
+        stale_after: millis(
+            "stale_after_ms",
+            section.stale_after_ms,
+            defaults.stale_after,
+        ),
+        jitter_percent: section.jitter_percent.unwrap_or(defaults.jitter_percent),
+    };
+    if timing.stale_after < timing.health_interval {
+        errors.push(format!(
+            "discovery.stale_after_ms: must be at least health_interval_ms ({})",
+            timing.health_interval.as_millis()
+        ));
+    }
+    if timing.jitter_percent > MAX_JITTER_PERCENT {
+        errors.push(format!(
+            "discovery.jitter_percent: must be at most {MAX_JITTER_PERCENT} (got {})",
+            timing.jitter_percent
+        ));
+    }
+    if errors.is_empty() {
+        Ok(timing)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Replace the active timing.
+pub fn install(timing: DiscoveryTiming) {
+    if let Ok(mut guard) = store().lock() {
+        *guard = timing;
+    }
+}
+
+/// Active timing.
+pub fn timing() -> DiscoveryTiming {
+    store().lock().map(|guard| *guard).unwrap_or_default()
+}
 
-const DISCOVERED_HEALTH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
-const DISCOVERED_STALE_AFTER: std::time::Duration = std::time::Duration::from_secs(20);
+/// Scale `delay` by `1 ± percent` using `unit` in `[0, 1)`.
+fn jitter_with(delay: Duration, percent: u8, unit: f64) -> Duration {
+    let spread = f64::from(percent) / 100.0 * (unit * 2.0 - 1.0);
+    delay.mul_f64(1.0 + spread)
+}
+
+/// Random value in `[0, 1)` from std's per-instance randomized hasher keys.
+fn random_unit() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+    let bits = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
 
 /// Spawn mDNS discovery loop for bridge devices.
 pub(crate) fn spawn_mdns_discovery(state: web::Data<AppState>) {
@@ -77,6 +212,12 @@ pub(crate) fn spawn_mdns_discovery(state: web::Data<AppState>) {
                         id: id.clone(),
                         name,
                         http_addr: http,
+                        room: crate::config::location_label(
+                            property_value(&info, "room").as_deref(),
+                        ),
+                        zone: crate::config::location_label(
+                            property_value(&info, "zone").as_deref(),
+                        ),
                     };
                     if let Ok(mut map) = state.providers.bridge.discovered_bridges.lock() {
                         let now = std::time::Instant::now();
@@ -192,11 +333,202 @@ pub(crate) fn spawn_cast_mdns_discovery(state: web::Data<AppState>) {
     });
 }
 
+/// Spawn mDNS discovery loop for AirPlay (RAOP) receivers.
+///
+/// Receivers that require a password or only accept encrypted audio are skipped.
+pub(crate) fn spawn_airplay_mdns_discovery(state: web::Data<AppState>) {
+    std::thread::spawn(move || {
+        let daemon = match ServiceDaemon::new() {
+            Ok(d) => d,
+            Err(e) => {
+                tracing::warn!(error = %e, "mdns: failed to start airplay daemon");
+                return;
+            }
+        };
+        let receiver = match daemon.browse("_raop._tcp.local.") {
+            Ok(r) => r,
+            Err(e) => {
+                tracing::warn!(error = %e, "mdns: airplay browse failed");
+                return;
+            }
+        };
+        tracing::info!("mdns: browsing for _raop._tcp.local.");
+        let mut fullname_to_id: HashMap<String, String> = HashMap::new();
+        for event in receiver {
+            match event {
+                ServiceEvent::ServiceFound(_ty, fullname) => {
+                    if let Some(id) = fullname_to_id.get(&fullname)
+                        && let Ok(mut map) = state.providers.airplay.discovered.lock()
+                        && let Some(entry) = map.get_mut(id)
+                    {
+                        entry.last_seen = Instant::now();
+                    }
+                }
+                ServiceEvent::ServiceResolved(info) => {
+                    let Some((id, name)) = raop_instance(info.get_fullname()) else {
+                        continue;
+                    };
+                    if property_value(&info, "pw").as_deref() == Some("true")
+                        || !crate::airplay::accepts_unencrypted(
+                            property_value(&info, "et").as_deref(),
+                        )
+                    {
+                        tracing::debug!(airplay_id = %id, "mdns: airplay receiver unsupported");
+                        continue;
+                    }
+                    let Some(host) =
+                        first_ipv4_addr(&info).map(|ip| ip.to_string()).or_else(|| {
+                            info.get_hostname()
+                                .to_string()
+                                .strip_suffix('.')
+                                .map(|s| s.to_string())
+                        })
+                    else {
+                        continue;
+                    };
+                    let codec = crate::airplay::AirplayCodec::from_txt(
+                        property_value(&info, "cn").as_deref(),
+                    );
+                    if let Ok(mut map) = state.providers.airplay.discovered.lock() {
+                        map.insert(
+                            id.clone(),
+                            DiscoveredAirplay {
+                                id: id.clone(),
+                                name,
+                                host,
+                                port: info.get_port(),
+                                codec,
+                                last_seen: Instant::now(),
+                            },
+                        );
+                    }
+                    state.events.outputs_changed();
+                    fullname_to_id.insert(info.get_fullname().to_string(), id);
+                }
+                ServiceEvent::ServiceRemoved(name, _) => {
+                    if let Some(id) = fullname_to_id.remove(&name) {
+                        if let Ok(mut map) = state.providers.airplay.discovered.lock() {
+                            map.remove(&id);
+                        }
+                        state.events.outputs_changed();
+                        tracing::info!(airplay_id = %id, "mdns: airplay removed");
+                    }
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+/// Interval between SSDP searches for DLNA renderers.
+const DLNA_SEARCH_INTERVAL: Duration = Duration::from_secs(30);
+/// How long each SSDP search collects answers.
+const DLNA_SEARCH_WAIT: Duration = Duration::from_secs(3);
+/// Drop a renderer missing from this many consecutive searches.
+const DLNA_MISSED_SEARCHES: u32 = 3;
+
+/// Spawn the SSDP search loop for UPnP/DLNA MediaRenderers.
+///
+/// Each new `LOCATION` is described once; renderers that stop answering are dropped after
+/// [`DLNA_MISSED_SEARCHES`] searches.
+pub(crate) fn spawn_dlna_ssdp_discovery(state: web::Data<AppState>) {
+    std::thread::spawn(move || {
+        tracing::info!("ssdp: searching for DLNA media renderers");
+        let mut location_to_id: HashMap<String, String> = HashMap::new();
+        loop {
+            let locations = match crate::dlna::ssdp_search(DLNA_SEARCH_WAIT) {
+                Ok(locations) => locations,
+                Err(err) => {
+                    tracing::warn!(error = %format!("{err:#}"), "ssdp: search failed");
+                    std::thread::sleep(DLNA_SEARCH_INTERVAL);
+                    continue;
+                }
+            };
+            let mut changed = false;
+            for location in locations {
+                let id = match location_to_id.get(&location) {
+                    Some(id) => id.clone(),
+                    None => match crate::dlna::fetch_renderer(&location) {
+                        Ok(device) => {
+                            tracing::info!(dlna_id = %device.id, name = %device.name, "ssdp: renderer found");
+                            if let Ok(mut map) = state.providers.dlna.discovered.lock() {
+                                map.insert(
+                                    device.id.clone(),
+                                    DiscoveredDlna {
+                                        id: device.id.clone(),
+                                        name: device.name,
+                                        host: device.host,
+                                        av_transport_url: device.av_transport_url,
+                                        rendering_control_url: device.rendering_control_url,
+                                        last_seen: Instant::now(),
+                                    },
+                                );
+                            }
+                            changed = true;
+                            location_to_id.insert(location, device.id.clone());
+                            continue;
+                        }
+                        Err(err) => {
+                            tracing::debug!(error = %format!("{err:#}"), location = %location, "ssdp: renderer description failed");
+                            continue;
+                        }
+                    },
+                };
+                if let Ok(mut map) = state.providers.dlna.discovered.lock()
+                    && let Some(entry) = map.get_mut(&id)
+                {
+                    entry.last_seen = Instant::now();
+                }
+            }
+            let stale_after = (DLNA_SEARCH_INTERVAL + DLNA_SEARCH_WAIT) * DLNA_MISSED_SEARCHES;
+            if let Ok(mut map) = state.providers.dlna.discovered.lock() {
+                let before = map.len();
+                map.retain(|id, entry| {
+                    let keep = entry.last_seen.elapsed() < stale_after;
+                    if !keep {
+                        tracing::info!(dlna_id = %id, "ssdp: renderer removed");
+                    }
+                    keep
+                });
+                changed |= map.len() != before;
+                location_to_id.retain(|_, id| map.contains_key(id));
+            }
+            if changed {
+                state.events.outputs_changed();
+            }
+            std::thread::sleep(DLNA_SEARCH_INTERVAL);
+        }
+    });
+}
+
+/// Split a RAOP instance name (`AABBCCDDEEFF@Living Room._raop._tcp.local.`) into the
+/// lowercase hardware address and the display name.
+fn raop_instance(fullname: &str) -> Option<(String, String)> {
+    let instance = fullname
+        .strip_suffix("._raop._tcp.local.")
+        .unwrap_or(fullname);
+    let (mac, name) = instance.split_once('@')?;
+    if mac.is_empty() || !mac.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some((mac.to_ascii_lowercase(), name.to_string()))
+}
+
 /// Spawn periodic health checker for discovered bridges.
+///
+/// Each bridge keeps its own jittered schedule, so bridges discovered together drift apart.
 pub(crate) fn spawn_discovered_health_watcher(state: web::Data<AppState>) {
     std::thread::spawn(move || {
+        let mut next_check: HashMap<String, Instant> = HashMap::new();
         loop {
-            std::thread::sleep(DISCOVERED_HEALTH_INTERVAL);
+            let timing = timing();
+            let sleep = next_check
+                .values()
+                .min()
+                .map(|due| due.saturating_duration_since(Instant::now()))
+                .unwrap_or(timing.health_interval)
+                .clamp(Duration::from_millis(50), timing.health_interval);
+            std::thread::sleep(sleep);
             let snapshot = match state.providers.bridge.discovered_bridges.lock() {
                 Ok(map) => map
                     .iter()
@@ -204,17 +536,37 @@ pub(crate) fn spawn_discovered_health_watcher(state: web::Data<AppState>) {
                     .collect::<Vec<_>>(),
                 Err(_) => continue,
             };
+            next_check.retain(|id, _| snapshot.iter().any(|(known, ..)| known == id));
 
-            let now = std::time::Instant::now();
             for (id, http_addr, last_seen) in snapshot {
-                let ok = ping_bridge(http_addr);
-                if ok {
+                let now = Instant::now();
+                let due = next_check
+                    .entry(id.clone())
+                    .or_insert_with(|| now + timing.jittered(timing.health_interval));
+                if *due > now {
+                    continue;
+                }
+                *due = now + timing.jittered(timing.health_interval);
+                let result = ping_bridge(http_addr, timing.health_timeout);
+                if let Ok(mut probes) = state.providers.bridge.probes.lock() {
+                    probes.insert(
+                        id.clone(),
+                        BridgeProbe {
+                            ok: result.is_ok(),
+                            checked_at: SystemTime::now(),
+                            latency: now.elapsed(),
+                            error: result.as_ref().err().cloned(),
+                        },
+                    );
+                }
+                let now = Instant::now();
+                if result.is_ok() {
                     if let Ok(mut map) = state.providers.bridge.discovered_bridges.lock() {
                         if let Some(entry) = map.get_mut(&id) {
                             entry.last_seen = now;
                         }
                     }
-                } else if now.duration_since(last_seen) > DISCOVERED_STALE_AFTER {
+                } else if now.duration_since(last_seen) > timing.stale_after {
                     let active_bridge_id = state
                         .providers
                         .bridge
@@ -234,6 +586,9 @@ pub(crate) fn spawn_discovered_health_watcher(state: web::Data<AppState>) {
                     if let Ok(mut cache) = state.providers.bridge.status_cache.lock() {
                         cache.remove(&id);
                     }
+                    if let Ok(mut probes) = state.providers.bridge.probes.lock() {
+                        probes.remove(&id);
+                    }
                     state.events.outputs_changed();
                     tracing::info!(bridge_id = %id, "mdns: bridge removed (health check)");
                 }
@@ -242,15 +597,20 @@ pub(crate) fn spawn_discovered_health_watcher(state: web::Data<AppState>) {
     });
 }
 
-/// Return true when `/health` endpoint responds with success.
-fn ping_bridge(http_addr: std::net::SocketAddr) -> bool {
+/// Check the bridge `/health` endpoint; the error describes why it failed.
+fn ping_bridge(http_addr: std::net::SocketAddr, timeout: Duration) -> Result<(), String> {
     let url = format!("http://{http_addr}/health");
     let resp = ureq::get(&url)
         .config()
-        .timeout_per_call(Some(std::time::Duration::from_secs(2)))
+        .timeout_per_call(Some(timeout))
         .build()
-        .call();
-    resp.map(|r| r.status().is_success()).unwrap_or(false)
+        .call()
+        .map_err(|e| e.to_string())?;
+    if resp.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("status {}", resp.status()))
+    }
 }
 
 /// Read TXT property value and strip optional `key=` prefix.
@@ -294,7 +654,55 @@ mod tests {
     #[test]
     fn ping_bridge_returns_false_on_unreachable() {
         let addr: std::net::SocketAddr = "127.0.0.1:1".parse().unwrap();
-        assert!(!ping_bridge(addr));
+        assert!(ping_bridge(addr, Duration::from_secs(2)).is_err());
+    }
+
+    #[test]
+    fn timing_from_section_applies_defaults_and_reports_problems() {
+        let section = |raw: &str| -> DiscoveryConfig { toml::from_str(raw).unwrap() };
+        let timing = timing_from_section(&section("health_interval_ms = 15000")).unwrap();
+        assert_eq!(timing.health_interval, Duration::from_secs(15));
+        assert_eq!(timing.health_timeout, Duration::from_secs(2));
+        assert_eq!(timing.jitter_percent, 20);
+
+        let errors = timing_from_section(&section(
+            "health_timeout_ms = 0\nstale_after_ms = 1000\njitter_percent = 80",
+        ))
+        .unwrap_err();
+        let fields: Vec<&str> = errors
+            .iter()
+            .map(|e| e.split(':').next().unwrap())
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                "discovery.health_timeout_ms",
+                "discovery.stale_after_ms",
+                "discovery.jitter_percent"
+            ]
+        );
+    }
+
+    #[test]
+    fn jitter_stays_within_percent() {
+        let base = Duration::from_secs(10);
+        assert_eq!(jitter_with(base, 20, 0.0), Duration::from_secs(8));
+        assert_eq!(jitter_with(base, 20, 0.5), base);
+        assert_eq!(jitter_with(base, 0, 0.9), base);
+        let timing = DiscoveryTiming::default();
+        for _ in 0..100 {
+            let delay = timing.jittered(base);
+            assert!(delay >= Duration::from_secs(8) && delay <= Duration::from_secs(12));
+        }
+    }
+
+    #[test]
+    fn raop_instance_splits_address_and_name() {
+        assert_eq!(
+            raop_instance("AABBCCDDEEFF@Living Room._raop._tcp.local."),
+            Some(("aabbccddeeff".to_string(), "Living Room".to_string()))
+        );
+        assert_eq!(raop_instance("Living Room._raop._tcp.local."), None);
     }
 
     #[test]
@@ -0,0 +1,829 @@
+//! Minimal UPnP/DLNA MediaRenderer control point.
+//!
+//! Renderers are found with SSDP `M-SEARCH` and described by their device XML. Playback
+//! hands the renderer the hub's `/stream/track/{id}` URL through `AVTransport`
+//! (`SetAVTransportURI`, `Play`, `Pause`, `Seek`, `Stop`) and polls `GetTransportInfo` /
+//! `GetPositionInfo` for status. Volume and mute use `RenderingControl` when the renderer
+//! offers it. The renderer fetches and decodes the file itself, like a Cast receiver.
+
+use std::net::UdpSocket;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow};
+use audio_bridge_types::{BridgeStatus, PlaybackEndReason};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+
+use crate::bridge::BridgeCommand;
+use crate::events::EventBus;
+use crate::metadata_db::MetadataDb;
+use crate::playback_transport::ChannelTransport;
+use crate::queue_service::QueueService;
+use crate::state::{DlnaProviderState, QueueState};
+use crate::status_store::StatusStore;
+use crate::stream_url::build_stream_url_for;
+
+/// SSDP multicast address.
+const SSDP_ADDR: &str = "239.255.255.250:1900";
+/// Device type searched for.
+const RENDERER_URN: &str = "urn:schemas-upnp-org:device:MediaRenderer:1";
+/// Transport control service.
+const AV_TRANSPORT_URN: &str = "urn:schemas-upnp-org:service:AVTransport:1";
+/// Volume control service.
+const RENDERING_CONTROL_URN: &str = "urn:schemas-upnp-org:service:RenderingControl:1";
+/// Timeout of one SOAP or description request.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often a playing renderer is polled for status.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone)]
+/// Control endpoints of a discovered MediaRenderer.
+pub struct DlnaDeviceDescriptor {
+    /// Stable renderer id (UDN without the `uuid:` prefix).
+    pub id: String,
+    /// Friendly name from the device description.
+    pub name: String,
+    /// Host serving the description (used to recognize the renderer's stream requests).
+    pub host: String,
+    /// Absolute `AVTransport` control URL.
+    pub av_transport_url: String,
+    /// Absolute `RenderingControl` control URL, when offered.
+    pub rendering_control_url: Option<String>,
+}
+
+/// Send `M-SEARCH` for MediaRenderers and collect the `LOCATION` of every answer.
+pub fn ssdp_search(wait: Duration) -> Result<Vec<String>> {
+    let socket = UdpSocket::bind("0.0.0.0:0").context("bind ssdp socket")?;
+    socket.set_read_timeout(Some(Duration::from_millis(200)))?;
+    let request = format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {SSDP_ADDR}\r\nMAN: \"ssdp:discover\"\r\nMX: {}\r\nST: {RENDERER_URN}\r\n\r\n",
+        wait.as_secs().max(1)
+    );
+    // UDP is lossy; renderers ignore the duplicate.
+    for _ in 0..2 {
+        socket
+            .send_to(request.as_bytes(), SSDP_ADDR)
+            .context("send m-search")?;
+    }
+    let deadline = Instant::now() + wait;
+    let mut buf = [0u8; 2048];
+    let mut locations = Vec::new();
+    while Instant::now() < deadline {
+        let Ok((len, _)) = socket.recv_from(&mut buf) else {
+            continue;
+        };
+        if let Some(location) = ssdp_location(&String::from_utf8_lossy(&buf[..len]))
+            && !locations.contains(&location)
+        {
+            locations.push(location);
+        }
+    }
+    Ok(locations)
+}
+
+/// `LOCATION` header of an SSDP response.
+fn ssdp_location(response: &str) -> Option<String> {
+    response.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case("location")
+            .then(|| value.trim().to_string())
+    })
+}
+
+/// Fetch and parse a renderer's device description.
+pub fn fetch_renderer(location: &str) -> Result<DlnaDeviceDescriptor> {
+    let xml = agent()
+        .get(location)
+        .call()
+        .context("fetch device description")?
+        .body_mut()
+        .read_to_string()?;
+    parse_description(&xml, location).ok_or_else(|| anyhow!("not a MediaRenderer: {location}"))
+}
+
+/// Build the descriptor from device description XML; `None` without an `AVTransport`.
+fn parse_description(xml: &str, location: &str) -> Option<DlnaDeviceDescriptor> {
+    let base = xml_text(xml, "URLBase")
+        .filter(|base| !base.is_empty())
+        .unwrap_or_else(|| location.to_string());
+    let mut av_transport_url = None;
+    let mut rendering_control_url = None;
+    for service in xml_blocks(xml, "service") {
+        let kind = xml_text(service, "serviceType").unwrap_or_default();
+        let Some(control) = xml_text(service, "controlURL") else {
+            continue;
+        };
+        if kind.contains(":service:AVTransport:") {
+            av_transport_url.get_or_insert_with(|| resolve_url(&base, &control));
+        } else if kind.contains(":service:RenderingControl:") {
+            rendering_control_url.get_or_insert_with(|| resolve_url(&base, &control));
+        }
+    }
+    let udn = xml_text(xml, "UDN")?;
+    let id = udn
+        .strip_prefix("uuid:")
+        .unwrap_or(&udn)
+        .to_ascii_lowercase();
+    Some(DlnaDeviceDescriptor {
+        name: xml_text(xml, "friendlyName").unwrap_or_else(|| id.clone()),
+        id,
+        host: url_host(location)?.to_string(),
+        av_transport_url: av_transport_url?,
+        rendering_control_url,
+    })
+}
+
+/// `scheme://host:port` part of a URL.
+fn url_origin(url: &str) -> &str {
+    let after_scheme = url.find("://").map_or(0, |pos| pos + 3);
+    match url[after_scheme..].find('/') {
+        Some(pos) => &url[..after_scheme + pos],
+        None => url,
+    }
+}
+
+/// Host of a URL, without port.
+fn url_host(url: &str) -> Option<&str> {
+    let origin = url_origin(url);
+    let authority = &origin[origin.find("://")? + 3..];
+    let host = authority
+        .rsplit_once(':')
+        .map_or(authority, |(host, _)| host);
+    (!host.is_empty()).then_some(host)
+}
+
+/// Resolve a (possibly relative) control URL against the description base.
+fn resolve_url(base: &str, path: &str) -> String {
+    if path.starts_with("http://") || path.starts_with("https://") {
+        return path.to_string();
+    }
+    let origin = url_origin(base);
+    if path.starts_with('/') {
+        format!("{origin}{path}")
+    } else {
+        format!("{origin}/{path}")
+    }
+}
+
+/// Text of the first `<tag>` element (namespace prefixes are not matched).
+fn xml_text(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{tag}");
+    let close = format!("</{tag}>");
+    let mut from = 0;
+    while let Some(pos) = xml[from..].find(&open) {
+        let start = from + pos + open.len();
+        match xml[start..].chars().next() {
+            Some('>') | Some(' ') | Some('\t') | Some('\r') | Some('\n') | Some('/') => {}
+            _ => {
+                from = start;
+                continue;
+            }
+        }
+        let body_start = start + xml[start..].find('>')? + 1;
+        if xml[..body_start].ends_with("/>") {
+            return Some(String::new());
+        }
+        let end = body_start + xml[body_start..].find(&close)?;
+        return Some(xml_unescape(xml[body_start..end].trim()));
+    }
+    None
+}
+
+/// Inner XML of every `<tag>...</tag>` element.
+fn xml_blocks<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    let mut blocks = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let body = &rest[start + open.len()..];
+        let Some(end) = body.find(&close) else {
+            break;
+        };
+        blocks.push(&body[..end]);
+        rest = &body[end + close.len()..];
+    }
+    blocks
+}
+
+/// Escape text for an XML element or attribute.
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Undo [`xml_escape`].
+fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// `H:MM:SS` for `AVTransport` seek targets.
+fn format_hms(ms: u64) -> String {
+    let secs = ms / 1000;
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Parse `H:MM:SS[.fff]`; `None` for `NOT_IMPLEMENTED` and other non-times.
+fn parse_hms(text: &str) -> Option<u64> {
+    let mut parts = text.trim().split(':');
+    let hours: u64 = parts.next()?.parse().ok()?;
+    let minutes: u64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((hours * 3600 + minutes * 60) * 1000 + (seconds * 1000.0) as u64)
+}
+
+/// DIDL-Lite item describing the track for the renderer's display.
+fn didl_metadata(
+    url: &str,
+    content_type: &str,
+    title: &str,
+    artist: Option<&str>,
+    album: Option<&str>,
+) -> String {
+    let mut item = format!("<dc:title>{}</dc:title>", xml_escape(title));
+    if let Some(artist) = artist {
+        item.push_str(&format!(
+            "<upnp:artist>{}</upnp:artist>",
+            xml_escape(artist)
+        ));
+    }
+    if let Some(album) = album {
+        item.push_str(&format!("<upnp:album>{}</upnp:album>", xml_escape(album)));
+    }
+    format!(
+        "<DIDL-Lite xmlns=\"urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:upnp=\"urn:schemas-upnp-org:metadata-1-0/upnp/\"><item id=\"0\" parentID=\"-1\" restricted=\"1\">{item}<upnp:class>object.item.audioItem.musicTrack</upnp:class><res protocolInfo=\"http-get:*:{content_type}:*\">{}</res></item></DIDL-Lite>",
+        xml_escape(url)
+    )
+}
+
+/// SOAP envelope for one action; argument values are escaped.
+fn soap_body(service: &str, action: &str, args: &[(&str, String)]) -> String {
+    let args: String = args
+        .iter()
+        .map(|(name, value)| format!("<{name}>{}</{name}>", xml_escape(value)))
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?><s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\"><s:Body><u:{action} xmlns:u=\"{service}\">{args}</u:{action}></s:Body></s:Envelope>"
+    )
+}
+
+/// HTTP agent for descriptions and SOAP calls.
+fn agent() -> ureq::Agent {
+    let config = ureq::Agent::config_builder()
+        .user_agent(concat!("audio-hub/", env!("CARGO_PKG_VERSION")))
+        .timeout_global(Some(HTTP_TIMEOUT))
+        .build();
+    ureq::Agent::new_with_config(config)
+}
+
+/// SOAP client for one renderer.
+pub struct DlnaClient {
+    agent: ureq::Agent,
+    device: DlnaDeviceDescriptor,
+}
+
+impl DlnaClient {
+    /// Create a client for `device`.
+    pub fn new(device: DlnaDeviceDescriptor) -> Self {
+        Self {
+            agent: agent(),
+            device,
+        }
+    }
+
+    /// Invoke an action and return the response body; SOAP faults are errors.
+    fn call(
+        &self,
+        url: &str,
+        service: &str,
+        action: &str,
+        args: &[(&str, String)],
+    ) -> Result<String> {
+        let mut args_with_instance = vec![("InstanceID", "0".to_string())];
+        args_with_instance.extend(args.iter().cloned());
+        let body = soap_body(service, action, &args_with_instance);
+        let mut response = self
+            .agent
+            .post(url)
+            .header("Content-Type", "text/xml; charset=\"utf-8\"")
+            .header("SOAPACTION", format!("\"{service}#{action}\""))
+            .send(body)
+            .with_context(|| format!("{action} failed"))?;
+        Ok(response.body_mut().read_to_string()?)
+    }
+
+    /// Invoke an `AVTransport` action.
+    fn transport(&self, action: &str, args: &[(&str, String)]) -> Result<String> {
+        self.call(
+            &self.device.av_transport_url,
+            AV_TRANSPORT_URN,
+            action,
+            args,
+        )
+    }
+
+    /// Invoke a `RenderingControl` action.
+    fn rendering(&self, action: &str, args: &[(&str, String)]) -> Result<String> {
+        let url = self
+            .device
+            .rendering_control_url
+            .as_deref()
+            .ok_or_else(|| anyhow!("renderer has no volume control"))?;
+        self.call(url, RENDERING_CONTROL_URN, action, args)
+    }
+
+    /// Load `url` with its DIDL-Lite metadata.
+    fn set_uri(&self, url: &str, metadata: &str) -> Result<()> {
+        self.transport(
+            "SetAVTransportURI",
+            &[
+                ("CurrentURI", url.to_string()),
+                ("CurrentURIMetaData", metadata.to_string()),
+            ],
+        )
+        .map(|_| ())
+    }
+
+    fn play(&self) -> Result<()> {
+        self.transport("Play", &[("Speed", "1".to_string())])
+            .map(|_| ())
+    }
+
+    fn pause(&self) -> Result<()> {
+        self.transport("Pause", &[]).map(|_| ())
+    }
+
+    fn stop(&self) -> Result<()> {
+        self.transport("Stop", &[]).map(|_| ())
+    }
+
+    fn seek(&self, ms: u64) -> Result<()> {
+        self.transport(
+            "Seek",
+            &[("Unit", "REL_TIME".to_string()), ("Target", format_hms(ms))],
+        )
+        .map(|_| ())
+    }
+
+    /// Transport state (`PLAYING`, `PAUSED_PLAYBACK`, `STOPPED`, ...).
+    fn transport_state(&self) -> Result<String> {
+        let body = self.transport("GetTransportInfo", &[])?;
+        xml_text(&body, "CurrentTransportState").ok_or_else(|| anyhow!("no transport state"))
+    }
+
+    /// Elapsed and duration of the current track.
+    fn position(&self) -> Result<(Option<u64>, Option<u64>)> {
+        let body = self.transport("GetPositionInfo", &[])?;
+        Ok((
+            xml_text(&body, "RelTime").and_then(|t| parse_hms(&t)),
+            xml_text(&body, "TrackDuration")
+                .and_then(|t| parse_hms(&t))
+                .filter(|ms| *ms > 0),
+        ))
+    }
+
+    /// Master volume (0..100) and mute.
+    pub fn volume(&self) -> Result<(u8, bool)> {
+        let channel = [("Channel", "Master".to_string())];
+        let volume = xml_text(&self.rendering("GetVolume", &channel)?, "CurrentVolume")
+            .and_then(|v| v.parse::<u16>().ok())
+            .unwrap_or(0);
+        let muted = xml_text(&self.rendering("GetMute", &channel)?, "CurrentMute")
+            .is_some_and(|m| m == "1" || m.eq_ignore_ascii_case("true"));
+        Ok((volume.min(100) as u8, muted))
+    }
+
+    /// Set master volume (0..100).
+    pub fn set_volume(&self, value: u8) -> Result<()> {
+        self.rendering(
+            "SetVolume",
+            &[
+                ("Channel", "Master".to_string()),
+                ("DesiredVolume", value.min(100).to_string()),
+            ],
+        )
+        .map(|_| ())
+    }
+
+    /// Set master mute.
+    pub fn set_mute(&self, muted: bool) -> Result<()> {
+        self.rendering(
+            "SetMute",
+            &[
+                ("Channel", "Master".to_string()),
+                ("DesiredMute", if muted { "1" } else { "0" }.to_string()),
+            ],
+        )
+        .map(|_| ())
+    }
+}
+
+/// Shared handles the worker reports through.
+pub struct DlnaWorkerContext {
+    pub provider: Arc<DlnaProviderState>,
+    pub status: StatusStore,
+    pub queue: Arc<Mutex<QueueState>>,
+    pub events: EventBus,
+    pub public_base_url: String,
+    pub metadata: Option<MetadataDb>,
+    pub bridge_state: Arc<Mutex<crate::state::BridgeState>>,
+}
+
+/// Per-output worker state.
+struct Worker {
+    output_id: String,
+    client: DlnaClient,
+    cmd_tx: Sender<BridgeCommand>,
+    ctx: DlnaWorkerContext,
+    queue_service: QueueService,
+    current_path: Option<PathBuf>,
+    paused: bool,
+    /// The renderer reported `PLAYING` for the current track; a later `STOPPED` is its end.
+    started: bool,
+    last_poll: Instant,
+    last_duration_ms: Option<u64>,
+    auto_advance_in_flight: bool,
+}
+
+/// Spawn the worker controlling one renderer.
+pub fn spawn_dlna_worker(
+    output_id: String,
+    device: DlnaDeviceDescriptor,
+    cmd_rx: Receiver<BridgeCommand>,
+    cmd_tx: Sender<BridgeCommand>,
+    ctx: DlnaWorkerContext,
+) {
+    std::thread::spawn(move || {
+        let queue_service =
+            QueueService::new(ctx.queue.clone(), ctx.status.clone(), ctx.events.clone());
+        let mut worker = Worker {
+            output_id,
+            client: DlnaClient::new(device),
+            cmd_tx,
+            ctx,
+            queue_service,
+            current_path: None,
+            paused: false,
+            started: false,
+            last_poll: Instant::now(),
+            last_duration_ms: None,
+            auto_advance_in_flight: false,
+        };
+        loop {
+            match cmd_rx.recv_timeout(POLL_INTERVAL) {
+                Ok(BridgeCommand::Quit) | Err(RecvTimeoutError::Disconnected) => {
+                    if worker.current_path.is_some() {
+                        let _ = worker.client.stop();
+                    }
+                    break;
+                }
+                Ok(cmd) => worker.handle(cmd),
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+            if worker.current_path.is_some() && worker.last_poll.elapsed() >= POLL_INTERVAL {
+                worker.poll();
+            }
+        }
+        let provider = &worker.ctx.provider;
+        if let Ok(mut statuses) = provider.status_by_output.lock() {
+            statuses.remove(&worker.output_id);
+        }
+        if let Ok(mut updates) = provider.status_updated_at.lock() {
+            updates.remove(&worker.output_id);
+        }
+        if let Ok(mut workers) = provider.workers.lock() {
+            workers.remove(&worker.output_id);
+        }
+        tracing::info!(dlna_id = %worker.client.device.id, "dlna worker stopped");
+    });
+}
+
+impl Worker {
+    /// Apply a control command.
+    fn handle(&mut self, cmd: BridgeCommand) {
+        let result = match cmd {
+            BridgeCommand::Play {
+                path,
+                ext_hint,
+                seek_ms,
+                start_paused,
+            } => self.play(path, &ext_hint, seek_ms, start_paused),
+            BridgeCommand::PauseToggle => {
+                if self.current_path.is_none() {
+                    return;
+                }
+                let result = if self.paused {
+                    self.client.play()
+                } else {
+                    self.client.pause()
+                };
+                if result.is_ok() {
+                    self.paused = !self.paused;
+                    self.poll();
+                }
+                result
+            }
+            BridgeCommand::Seek { ms } => {
+                let result = self.client.seek(ms);
+                if result.is_ok() {
+                    self.ctx.status.mark_seek_in_flight();
+                }
+                result
+            }
+            BridgeCommand::Stop | BridgeCommand::StopSilent => {
+                let result = self.client.stop();
+                self.current_path = None;
+                self.publish(BridgeStatus::default(), Some(PlaybackEndReason::Stopped));
+                result
+            }
+            BridgeCommand::Quit => Ok(()),
+        };
+        if let Err(err) = result {
+            tracing::warn!(
+                error = %format!("{err:#}"),
+                dlna_id = %self.client.device.id,
+                "dlna: command failed"
+            );
+        }
+    }
+
+    /// Hand the renderer the track's stream URL and start it.
+    fn play(
+        &mut self,
+        path: PathBuf,
+        ext_hint: &str,
+        seek_ms: Option<u64>,
+        start_paused: bool,
+    ) -> Result<()> {
+        self.auto_advance_in_flight = false;
+        let url =
+            build_stream_url_for(&path, &self.ctx.public_base_url, self.ctx.metadata.as_ref())?;
+        let ext = if ext_hint.is_empty() {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or("")
+                .to_ascii_lowercase()
+        } else {
+            ext_hint.to_string()
+        };
+        let record = self.ctx.metadata.as_ref().and_then(|db| {
+            db.track_record_by_path(&path.to_string_lossy())
+                .ok()
+                .flatten()
+        });
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let title = record
+            .as_ref()
+            .and_then(|r| r.title.clone())
+            .unwrap_or(file_name);
+        let metadata = didl_metadata(
+            &url,
+            crate::cast_v2::content_type_for_ext(&ext),
+            &title,
+            record.as_ref().and_then(|r| r.artist.as_deref()),
+            record.as_ref().and_then(|r| r.album.as_deref()),
+        );
+        if self.current_path.is_some() {
+            // Some renderers refuse a new URI while playing.
+            let _ = self.client.stop();
+        }
+        self.current_path = None;
+        self.client.set_uri(&url, &metadata)?;
+        self.client.play()?;
+        if let Some(ms) = seek_ms.filter(|ms| *ms > 0)
+            && let Err(err) = self.client.seek(ms)
+        {
+            tracing::warn!(error = %format!("{err:#}"), dlna_id = %self.client.device.id, "dlna: seek failed");
+        }
+        if start_paused {
+            self.client.pause()?;
+        }
+        self.current_path = Some(path.clone());
+        self.paused = start_paused;
+        self.started = false;
+        self.ctx.status.on_play(path, start_paused);
+        self.poll();
+        Ok(())
+    }
+
+    /// Read transport state and position; detects the end of the track.
+    fn poll(&mut self) {
+        self.last_poll = Instant::now();
+        let Some(path) = self.current_path.clone() else {
+            return;
+        };
+        let state = match self.client.transport_state() {
+            Ok(state) => state,
+            Err(err) => {
+                tracing::debug!(error = %format!("{err:#}"), dlna_id = %self.client.device.id, "dlna: status poll failed");
+                return;
+            }
+        };
+        match state.as_str() {
+            "PLAYING" => {
+                self.started = true;
+                self.paused = false;
+            }
+            "PAUSED_PLAYBACK" => self.paused = true,
+            "STOPPED" | "NO_MEDIA_PRESENT" if self.started => {
+                self.current_path = None;
+                self.publish(BridgeStatus::default(), Some(PlaybackEndReason::Eof));
+                return;
+            }
+            _ => {}
+        }
+        let (elapsed_ms, duration_ms) = self.client.position().unwrap_or((None, None));
+        let remote = BridgeStatus {
+            now_playing: Some(path.to_string_lossy().to_string()),
+            paused: self.paused,
+            elapsed_ms,
+            duration_ms,
+            ..Default::default()
+        };
+        self.publish(remote, None);
+    }
+
+    /// Store the output status and handle the end of a track.
+    fn publish(&mut self, mut remote: BridgeStatus, end_reason: Option<PlaybackEndReason>) {
+        remote.device = Some(self.client.device.name.clone());
+        remote.end_reason = end_reason;
+        if end_reason.is_some() {
+            remote.paused = true;
+        }
+        let provider = &self.ctx.provider;
+        if let Ok(mut statuses) = provider.status_by_output.lock() {
+            statuses.insert(self.output_id.clone(), remote.clone());
+        }
+        if let Ok(mut updates) = provider.status_updated_at.lock() {
+            updates.insert(self.output_id.clone(), Instant::now());
+        }
+
+        let bound_session = crate::session_registry::output_lock_owner(&self.output_id);
+        if end_reason.is_some() {
+            self.ctx.events.status_changed();
+        }
+        if end_reason == Some(PlaybackEndReason::Eof)
+            && !self.auto_advance_in_flight
+            && let Some(session_id) = bound_session.as_deref()
+        {
+            self.advance_session(session_id);
+        }
+        let is_active = self
+            .ctx
+            .bridge_state
+            .lock()
+            .map(|b| b.active_output_id.as_deref() == Some(self.output_id.as_str()))
+            .unwrap_or(false);
+        if !is_active {
+            return;
+        }
+        let (inputs, changed) = self
+            .ctx
+            .status
+            .reduce_remote_and_inputs(&remote, self.last_duration_ms);
+        self.ctx.status.emit_if_changed(changed);
+        self.last_duration_ms = remote.duration_ms;
+        if bound_session.is_some() {
+            return;
+        }
+        let transport = ChannelTransport::new(self.cmd_tx.clone());
+        let _ = self.queue_service.maybe_auto_advance(&transport, inputs);
+    }
+
+    /// Play the next queue entry of the session holding this output.
+    fn advance_session(&mut self, session_id: &str) {
+        match crate::session_registry::queue_next_track_id(session_id) {
+            Ok(Some(track_id)) => {
+                let Some(path) = self
+                    .ctx
+                    .metadata
+                    .as_ref()
+                    .and_then(|db| db.track_path_for_id(track_id).ok().flatten())
+                    .map(PathBuf::from)
+                else {
+                    tracing::warn!(
+                        output_id = %self.output_id,
+                        session_id = %session_id,
+                        track_id,
+                        "dlna session auto-advance track not found"
+                    );
+                    return;
+                };
+                self.auto_advance_in_flight = true;
+                let _ = self.cmd_tx.send(BridgeCommand::Play {
+                    path,
+                    ext_hint: String::new(),
+                    seek_ms: None,
+                    start_paused: false,
+                });
+            }
+            Ok(None) => {
+                if let Ok(true) = crate::session_registry::queue_finish_now_playing(session_id) {
+                    self.ctx.events.queue_changed();
+                    self.ctx.events.status_changed();
+                }
+            }
+            Err(()) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESCRIPTION: &str = r#"<?xml version="1.0"?>
+<root xmlns="urn:schemas-upnp-org:device-1-0">
+  <device>
+    <deviceType>urn:schemas-upnp-org:device:MediaRenderer:1</deviceType>
+    <friendlyName>Kitchen &amp; Dining</friendlyName>
+    <UDN>uuid:5F9EC1B6-0000-1000-8000-001122334455</UDN>
+    <serviceList>
+      <service>
+        <serviceType>urn:schemas-upnp-org:service:RenderingControl:1</serviceType>
+        <controlURL>/upnp/control/rc</controlURL>
+      </service>
+      <service>
+        <serviceType>urn:schemas-upnp-org:service:AVTransport:1</serviceType>
+        <controlURL>upnp/control/avt</controlURL>
+      </service>
+    </serviceList>
+  </device>
+</root>"#;
+
+    #[test]
+    fn description_yields_control_urls() {
+        let device =
+            parse_description(DESCRIPTION, "http://192.168.1.40:49152/description.xml").unwrap();
+        assert_eq!(device.id, "5f9ec1b6-0000-1000-8000-001122334455");
+        assert_eq!(device.name, "Kitchen & Dining");
+        assert_eq!(device.host, "192.168.1.40");
+        assert_eq!(
+            device.av_transport_url,
+            "http://192.168.1.40:49152/upnp/control/avt"
+        );
+        assert_eq!(
+            device.rendering_control_url.as_deref(),
+            Some("http://192.168.1.40:49152/upnp/control/rc")
+        );
+        let without_transport = DESCRIPTION.replace("AVTransport", "ConnectionManager");
+        assert!(parse_description(&without_transport, "http://h/d.xml").is_none());
+    }
+
+    #[test]
+    fn ssdp_and_time_helpers() {
+        let response = "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=1800\r\nLocation: http://10.0.0.5:1400/xml/device.xml\r\n\r\n";
+        assert_eq!(
+            ssdp_location(response).as_deref(),
+            Some("http://10.0.0.5:1400/xml/device.xml")
+        );
+        assert_eq!(format_hms(3_723_000), "1:02:03");
+        assert_eq!(parse_hms("1:02:03.500"), Some(3_723_500));
+        assert_eq!(parse_hms("NOT_IMPLEMENTED"), None);
+    }
+
+    #[test]
+    fn soap_and_didl_escape_values() {
+        let didl = didl_metadata(
+            "http://hub/stream/track/1?access_token=a&b",
+            "audio/flac",
+            "Rock & Roll",
+            None,
+            None,
+        );
+        assert!(didl.contains("<dc:title>Rock &amp; Roll</dc:title>"));
+        assert!(didl.contains("access_token=a&amp;b</res>"));
+        let body = soap_body(
+            AV_TRANSPORT_URN,
+            "SetAVTransportURI",
+            &[("CurrentURIMetaData", didl)],
+        );
+        assert!(body.contains(
+            "<u:SetAVTransportURI xmlns:u=\"urn:schemas-upnp-org:service:AVTransport:1\">"
+        ));
+        assert!(body.contains("&lt;dc:title&gt;Rock &amp;amp; Roll"));
+        assert_eq!(
+            xml_text(
+                "<s:Body><CurrentTransportState>PLAYING</CurrentTransportState>",
+                "CurrentTransportState"
+            )
+            .as_deref(),
+            Some("PLAYING")
+        );
+    }
+}
@@ -55,6 +55,18 @@ pub enum MetadataEvent {
         track_id: Option<i64>,
         error: String,
     },
+    AcoustIdLookupSuccess {
+        track_id: i64,
+        recording_mbid: String,
+        score: f64,
+    },
+    AcoustIdLookupNoMatch {
+        track_id: i64,
+    },
+    AcoustIdLookupFailure {
+        track_id: i64,
+        error: String,
+    },
     CoverArtBatch {
         count: usize,
     },
@@ -71,6 +83,16 @@ pub enum MetadataEvent {
         error: String,
         attempts: i64,
     },
+    ArtistImageFetchSuccess {
+        artist_id: i64,
+        source: String,
+    },
+    ArtistImageFetchFailure {
+        artist_id: i64,
+        mbid: String,
+        error: String,
+        attempts: i64,
+    },
     AlbumNormalization {
         track_id: Option<i64>,
         original_album: String,
@@ -78,6 +100,11 @@ pub enum MetadataEvent {
         disc_number: Option<u32>,
         source: String,
     },
+    TrackMoved {
+        track_id: i64,
+        from: String,
+        to: String,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
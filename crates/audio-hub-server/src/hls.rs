@@ -0,0 +1,415 @@
+//! HLS packaging of library tracks for browsers and mobile clients.
+//!
+//! `GET /stream/track/{id}/hls/playlist.m3u8` runs ffmpeg's HLS muxer for the track once per
+//! format/bitrate into a temp directory and serves its playlist and segments from there. The
+//! playlist is an `EVENT` playlist while ffmpeg is still encoding and ends with
+//! `#EXT-X-ENDLIST`; a dropped segment request can simply be retried, which progressive
+//! streaming cannot recover from mid-file.
+//!
+//! AAC uses MPEG-TS segments; Opus uses fMP4 segments with an `init.mp4` header. Bitrates are
+//! snapped to [`BITRATES_KBPS`], at most [`MAX_RUNNING_JOBS`] encoders run at once, and a
+//! background loop deletes packaged tracks idle for [`IDLE_TTL`].
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow};
+use audio_player::cue::TrackRange;
+use tokio::process::Command;
+
+/// Target segment length handed to ffmpeg (`-hls_time`).
+const SEGMENT_SECONDS: u32 = 6;
+/// Packaged tracks unused for this long are removed.
+const IDLE_TTL: Duration = Duration::from_secs(30 * 60);
+/// How often the eviction loop looks for idle packaged tracks.
+const EVICT_INTERVAL: Duration = Duration::from_secs(60);
+/// A failed packaging run is reported as failed for this long before it is retried.
+const FAILED_RETRY_AFTER: Duration = Duration::from_secs(5 * 60);
+/// Upper bound on ffmpeg packagers running at the same time.
+const MAX_RUNNING_JOBS: usize = 4;
+/// Bitrates a variant may use; requested values snap to the nearest one.
+const BITRATES_KBPS: [u32; 6] = [64, 96, 128, 160, 256, 320];
+/// How long a request waits for ffmpeg to write the file it asks for.
+pub(crate) const FILE_WAIT: Duration = Duration::from_secs(20);
+/// Poll interval while waiting for a file.
+const FILE_POLL: Duration = Duration::from_millis(100);
+/// Playlist file name written by ffmpeg.
+pub(crate) const PLAYLIST_NAME: &str = "playlist.m3u8";
+/// fMP4 header segment name (Opus only).
+const INIT_NAME: &str = "init.mp4";
+
+/// Audio codec of an HLS variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum HlsFormat {
+    /// AAC-LC in MPEG-TS segments; plays everywhere, including Safari and iOS.
+    Aac,
+    /// Opus in fMP4 segments; smaller at the same quality, not on every Apple device.
+    Opus,
+}
+
+impl HlsFormat {
+    /// Parse the `format` query value.
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "aac" => Some(Self::Aac),
+            "opus" => Some(Self::Opus),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Aac => "aac",
+            Self::Opus => "opus",
+        }
+    }
+
+    /// Bitrate used when the request does not set one.
+    pub(crate) fn default_bitrate_kbps(self) -> u32 {
+        match self {
+            Self::Aac => 160,
+            Self::Opus => 128,
+        }
+    }
+
+    fn segment_extension(self) -> &'static str {
+        match self {
+            Self::Aac => "ts",
+            Self::Opus => "m4s",
+        }
+    }
+}
+
+/// One packaged rendition of a track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct HlsVariant {
+    pub(crate) track_id: i64,
+    pub(crate) format: HlsFormat,
+    pub(crate) bitrate_kbps: u32,
+}
+
+impl HlsVariant {
+    /// Variant of `track_id` at the supported bitrate closest to `bitrate_kbps`.
+    pub(crate) fn new(track_id: i64, format: HlsFormat, bitrate_kbps: u32) -> Self {
+        let bitrate_kbps = BITRATES_KBPS
+            .into_iter()
+            .min_by_key(|kbps| kbps.abs_diff(bitrate_kbps))
+            .unwrap_or(bitrate_kbps);
+        Self {
+            track_id,
+            format,
+            bitrate_kbps,
+        }
+    }
+
+    fn dir_name(&self) -> String {
+        format!(
+            "{}-{}-{}",
+            self.track_id,
+            self.format.as_str(),
+            self.bitrate_kbps
+        )
+    }
+}
+
+/// An ffmpeg packaging run and its output directory.
+#[derive(Debug, Clone)]
+pub(crate) struct HlsJob {
+    pub(crate) dir: PathBuf,
+    started_at: Instant,
+    /// Milliseconds after `started_at` at which ffmpeg exited ([`RUNNING`] until then).
+    finished_after_ms: Arc<AtomicU64>,
+    /// ffmpeg exited with an error.
+    failed: Arc<AtomicBool>,
+    last_used: Instant,
+}
+
+/// `finished_after_ms` value while ffmpeg is running.
+const RUNNING: u64 = u64::MAX;
+
+impl HlsJob {
+    /// Whether ffmpeg is still writing segments.
+    pub(crate) fn is_running(&self) -> bool {
+        self.finished_after_ms.load(Ordering::Relaxed) == RUNNING
+    }
+
+    /// Whether this run failed recently enough that it should not be retried yet.
+    fn in_failure_backoff(&self) -> bool {
+        if !self.failed.load(Ordering::Relaxed) {
+            return false;
+        }
+        let finished_at =
+            self.started_at + Duration::from_millis(self.finished_after_ms.load(Ordering::Relaxed));
+        finished_at.elapsed() < FAILED_RETRY_AFTER
+    }
+}
+
+/// Why no packaging job could be handed out.
+#[derive(Debug)]
+pub(crate) enum HlsError {
+    /// [`MAX_RUNNING_JOBS`] packagers are already running.
+    Busy,
+    /// ffmpeg could not be started.
+    Start(anyhow::Error),
+}
+
+impl std::fmt::Display for HlsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Busy => write!(f, "too many hls packaging jobs running"),
+            Self::Start(err) => write!(f, "{err:#}"),
+        }
+    }
+}
+
+fn jobs() -> &'static Mutex<HashMap<HlsVariant, HlsJob>> {
+    static JOBS: OnceLock<Mutex<HashMap<HlsVariant, HlsJob>>> = OnceLock::new();
+    JOBS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cache_root() -> PathBuf {
+    std::env::temp_dir().join("audio-hub-hls")
+}
+
+/// Return the packaging job for `variant`, starting ffmpeg on `source` when there is none.
+///
+/// A failed run is handed out as is for [`FAILED_RETRY_AFTER`] before ffmpeg is tried again.
+pub(crate) fn ensure_job(
+    variant: HlsVariant,
+    source: &Path,
+    range: TrackRange,
+) -> std::result::Result<HlsJob, HlsError> {
+    let mut jobs = jobs()
+        .lock()
+        .map_err(|_| HlsError::Start(anyhow!("hls job table poisoned")))?;
+    if let Some(job) = jobs.get_mut(&variant)
+        && (!job.failed.load(Ordering::Relaxed) || job.in_failure_backoff())
+    {
+        job.last_used = Instant::now();
+        return Ok(job.clone());
+    }
+    if jobs.values().filter(|job| job.is_running()).count() >= MAX_RUNNING_JOBS {
+        return Err(HlsError::Busy);
+    }
+    let job = start_job(variant, source, range).map_err(HlsError::Start)?;
+    jobs.insert(variant, job.clone());
+    Ok(job)
+}
+
+/// Spawn ffmpeg for `variant` into a fresh directory.
+fn start_job(variant: HlsVariant, source: &Path, range: TrackRange) -> Result<HlsJob> {
+    let dir = cache_root().join(variant.dir_name());
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).with_context(|| format!("create {}", dir.display()))?;
+    let mut child = packager_command(variant, source, range)
+        .current_dir(&dir)
+        .spawn()
+        .context("failed to start ffmpeg")?;
+    let job = HlsJob {
+        dir,
+        started_at: Instant::now(),
+        finished_after_ms: Arc::new(AtomicU64::new(RUNNING)),
+        failed: Arc::new(AtomicBool::new(false)),
+        last_used: Instant::now(),
+    };
+    let started_at = job.started_at;
+    let (finished_after_ms, failed) = (job.finished_after_ms.clone(), job.failed.clone());
+    actix_web::rt::spawn(async move {
+        let ok = child.wait().await.is_ok_and(|status| status.success());
+        if !ok {
+            tracing::warn!(track_id = variant.track_id, "hls packaging failed");
+            failed.store(true, Ordering::Relaxed);
+        }
+        let elapsed_ms = u64::try_from(started_at.elapsed().as_millis()).unwrap_or(RUNNING);
+        finished_after_ms.store(elapsed_ms.min(RUNNING - 1), Ordering::Relaxed);
+    });
+    tracing::info!(
+        track_id = variant.track_id,
+        format = variant.format.as_str(),
+        bitrate_kbps = variant.bitrate_kbps,
+        "hls packaging started"
+    );
+    Ok(job)
+}
+
+/// Delete packaged tracks idle for [`IDLE_TTL`], checking every [`EVICT_INTERVAL`].
+pub fn spawn_hls_eviction_loop() {
+    std::thread::spawn(|| {
+        loop {
+            std::thread::sleep(EVICT_INTERVAL);
+            if let Ok(mut jobs) = jobs().lock() {
+                evict_idle(&mut jobs);
+            }
+        }
+    });
+}
+
+/// Drop finished jobs that have not been used for [`IDLE_TTL`], with their files.
+///
+/// Failed runs stay until their retry backoff has passed so they are not restarted early.
+fn evict_idle(jobs: &mut HashMap<HlsVariant, HlsJob>) {
+    jobs.retain(|_, job| {
+        let keep =
+            job.is_running() || job.last_used.elapsed() < IDLE_TTL || job.in_failure_backoff();
+        if !keep {
+            let _ = std::fs::remove_dir_all(&job.dir);
+        }
+        keep
+    });
+}
+
+/// ffmpeg invocation writing `variant` of `source` as HLS into the working directory.
+fn packager_command(variant: HlsVariant, source: &Path, range: TrackRange) -> Command {
+    let mut cmd = Command::new("ffmpeg");
+    cmd.arg("-hide_banner")
+        .arg("-loglevel")
+        .arg("error")
+        .arg("-nostdin");
+    // Cut cue tracks out of their audio file.
+    if range.start_ms > 0 {
+        cmd.arg("-ss").arg(format!("{}ms", range.start_ms));
+    }
+    if let Some(end_ms) = range.end_ms {
+        cmd.arg("-to").arg(format!("{end_ms}ms"));
+    }
+    cmd.arg("-i").arg(source).arg("-vn").arg("-sn").arg("-dn");
+    match variant.format {
+        HlsFormat::Aac => {
+            cmd.arg("-c:a").arg("aac");
+        }
+        HlsFormat::Opus => {
+            // Opus runs at 48 kHz only.
+            cmd.arg("-c:a").arg("libopus").arg("-ar").arg("48000");
+        }
+    }
+    cmd.arg("-b:a")
+        .arg(format!("{}k", variant.bitrate_kbps))
+        .arg("-ac")
+        .arg("2")
+        .arg("-f")
+        .arg("hls")
+        .arg("-hls_time")
+        .arg(SEGMENT_SECONDS.to_string())
+        .arg("-hls_list_size")
+        .arg("0")
+        .arg("-hls_playlist_type")
+        .arg("event")
+        // Segments and playlist appear under their final name only once complete.
+        .arg("-hls_flags")
+        .arg("temp_file");
+    let extension = variant.format.segment_extension();
+    if variant.format == HlsFormat::Opus {
+        cmd.arg("-hls_segment_type")
+            .arg("fmp4")
+            .arg("-hls_fmp4_init_filename")
+            .arg(INIT_NAME);
+    }
+    cmd.arg("-hls_segment_filename")
+        .arg(format!("seg%05d.{extension}"))
+        .arg(PLAYLIST_NAME)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    cmd
+}
+
+/// Whether `name` is a file the packager writes (guards the segment route against paths).
+pub(crate) fn is_packaged_file(name: &str) -> bool {
+    if name == PLAYLIST_NAME || name == INIT_NAME {
+        return true;
+    }
+    let Some(index) = name.strip_prefix("seg").and_then(|rest| {
+        rest.strip_suffix(".ts")
+            .or_else(|| rest.strip_suffix(".m4s"))
+    }) else {
+        return false;
+    };
+    !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Content type of a packaged file.
+pub(crate) fn content_type(name: &str) -> &'static str {
+    if name.ends_with(".m3u8") {
+        "application/vnd.apple.mpegurl"
+    } else if name.ends_with(".ts") {
+        "video/mp2t"
+    } else {
+        "audio/mp4"
+    }
+}
+
+/// Wait until ffmpeg has written `name` for `job`; `None` when it never appears.
+pub(crate) async fn wait_for_file(job: &HlsJob, name: &str) -> Option<PathBuf> {
+    let path = job.dir.join(name);
+    let deadline = Instant::now() + FILE_WAIT;
+    loop {
+        if path.is_file() {
+            return Some(path);
+        }
+        if !job.is_running() || Instant::now() >= deadline {
+            return None;
+        }
+        actix_web::rt::time::sleep(FILE_POLL).await;
+    }
+}
+
+/// Append `query` to every URI in `playlist` so segment requests carry the same variant and
+/// access token as the playlist request.
+pub(crate) fn rewrite_playlist(playlist: &str, query: &str) -> String {
+    let mut out = String::with_capacity(playlist.len());
+    for line in playlist.lines() {
+        if query.is_empty() {
+            out.push_str(line);
+        } else if let Some(rest) = line.strip_prefix("#EXT-X-MAP:URI=\"") {
+            let (uri, tail) = rest.split_once('"').unwrap_or((rest, ""));
+            out.push_str(&format!("#EXT-X-MAP:URI=\"{uri}?{query}\"{tail}"));
+        } else if !line.is_empty() && !line.starts_with('#') {
+            out.push_str(&format!("{line}?{query}"));
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrite_playlist_carries_query_to_segments_and_init() {
+        let playlist = "#EXTM3U\n#EXT-X-VERSION:7\n#EXT-X-MAP:URI=\"init.mp4\"\n#EXTINF:6.000000,\nseg00000.m4s\n#EXT-X-ENDLIST\n";
+        let rewritten = rewrite_playlist(playlist, "format=opus&access_token=abc");
+        assert_eq!(
+            rewritten,
+            "#EXTM3U\n#EXT-X-VERSION:7\n#EXT-X-MAP:URI=\"init.mp4?format=opus&access_token=abc\"\n#EXTINF:6.000000,\nseg00000.m4s?format=opus&access_token=abc\n#EXT-X-ENDLIST\n"
+        );
+        assert_eq!(rewrite_playlist(playlist, ""), playlist);
+    }
+
+    #[test]
+    fn is_packaged_file_rejects_other_names() {
+        assert!(is_packaged_file("seg00012.ts"));
+        assert!(is_packaged_file("seg00012.m4s"));
+        assert!(is_packaged_file("init.mp4"));
+        assert!(!is_packaged_file("seg.ts"));
+        assert!(!is_packaged_file("../seg00001.ts"));
+        assert!(!is_packaged_file("seg00001.ts.tmp"));
+        assert_eq!(HlsFormat::parse("OPUS"), Some(HlsFormat::Opus));
+        assert_eq!(HlsFormat::parse("mp3"), None);
+    }
+
+    #[test]
+    fn variant_bitrate_snaps_to_supported_set() {
+        assert_eq!(HlsVariant::new(1, HlsFormat::Aac, 160).bitrate_kbps, 160);
+        assert_eq!(HlsVariant::new(1, HlsFormat::Aac, 170).bitrate_kbps, 160);
+        assert_eq!(HlsVariant::new(1, HlsFormat::Opus, 32).bitrate_kbps, 64);
+        assert_eq!(HlsVariant::new(1, HlsFormat::Opus, 300).bitrate_kbps, 320);
+        assert!(BITRATES_KBPS.contains(&HlsFormat::Aac.default_bitrate_kbps()));
+        assert!(BITRATES_KBPS.contains(&HlsFormat::Opus.default_bitrate_kbps()));
+    }
+}
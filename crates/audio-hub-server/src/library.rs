@@ -1,18 +1,25 @@
 //! Library scanning and indexing.
 //!
-//! Walks the media root, extracts metadata, and builds lookup maps.
+//! Walks the media root, extracts metadata, and builds lookup maps. A single-file rip with
+//! a cue sheet is listed as the sheet's tracks (see [`crate::cue_tracks`]) instead of the
+//! audio file itself.
 
 use std::ffi::OsStr;
 use std::fs::{self, File};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use anyhow::{Context, Result};
+use audio_player::cue::{CueSheet, CueTrack};
 use symphonia::core::formats::FormatOptions;
 use symphonia::core::io::MediaSourceStream;
 use symphonia::core::meta::{MetadataOptions, StandardVisualKey};
 use symphonia::core::probe::Hint;
 
-use crate::models::LibraryEntry;
+use crate::clock::now_ms;
+use crate::cue_tracks;
+use crate::models::{LibraryEntry, LibraryScanProgress};
 
 /// In-memory index of the media library rooted at a directory.
 #[derive(Clone, Debug)]
@@ -22,6 +29,20 @@ pub struct LibraryIndex {
 }
 
 impl LibraryIndex {
+    /// Create an index with no entries, to serve until the first scan completes.
+    pub fn empty(root: &Path) -> Result<Self> {
+        let root = root
+            .canonicalize()
+            .with_context(|| format!("canonicalize root {:?}", root))?;
+        if !root.is_dir() {
+            return Err(anyhow::anyhow!("root is not a directory: {:?}", root));
+        }
+        Ok(Self {
+            root,
+            entries_by_dir: std::collections::HashMap::new(),
+        })
+    }
+
     /// Return the canonical library root path.
     pub fn root(&self) -> &Path {
         self.root.as_path()
@@ -162,22 +183,85 @@ impl LibraryIndex {
     }
 }
 
+/// Upper bound on worker threads probing files during a scan.
+const MAX_SCAN_WORKERS: usize = 8;
+
+/// Live progress of a library scan, shared with the progress stream.
+///
+/// Also serves as the scan lock: [`ScanProgress::begin`] refuses while a scan is running.
+#[derive(Debug, Default)]
+pub struct ScanProgress {
+    inner: Mutex<LibraryScanProgress>,
+}
+
+impl ScanProgress {
+    /// Return a copy of the current progress.
+    pub fn snapshot(&self) -> LibraryScanProgress {
+        self.inner.lock().unwrap().clone()
+    }
+
+    /// Mark a scan as started; returns false when one is already running.
+    pub fn begin(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.running {
+            return false;
+        }
+        *inner = LibraryScanProgress {
+            running: true,
+            started_at_ms: Some(now_ms()),
+            ..LibraryScanProgress::default()
+        };
+        true
+    }
+
+    /// Mark the running scan as finished, recording the error that stopped it.
+    pub fn finish(&self, error: Option<String>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.running = false;
+        inner.current_dir = None;
+        inner.finished_at_ms = Some(now_ms());
+        inner.error = error;
+    }
+
+    fn set_total(&self, total: usize) {
+        self.inner.lock().unwrap().files_total = total;
+    }
+
+    fn enter_dir(&self, dir: &Path) {
+        self.inner.lock().unwrap().current_dir = Some(dir.to_string_lossy().to_string());
+    }
+
+    fn add_scanned(&self, count: usize) {
+        self.inner.lock().unwrap().files_scanned += count;
+    }
+}
+
 /// Scan the media root and build a new library index.
 pub fn scan_library(root: &Path) -> Result<LibraryIndex> {
     scan_library_with_meta(
         root,
+        &ScanProgress::default(),
+        |_path, _fs_meta| None,
         |_path, _file_name, _ext, _meta, _fs_meta| {},
         |_dir, _count| {},
     )
 }
 
 /// Scan the media root and build a new library index, invoking `on_track` per file.
-pub fn scan_library_with_meta<F, D>(
+///
+/// Folders are listed first so `progress` knows the total, then a worker pool probes them
+/// in parallel. `known` may return metadata for a file that has not changed since an
+/// earlier scan, which skips probing it; this is what lets an interrupted scan resume
+/// cheaply. `on_track` and `on_dir` run on the calling thread, one folder at a time.
+pub fn scan_library_with_meta<K, F, D>(
     root: &Path,
+    progress: &ScanProgress,
+    known: K,
     mut on_track: F,
     mut on_dir: D,
 ) -> Result<LibraryIndex>
 where
+    K: Fn(&Path, &std::fs::Metadata) -> Option<TrackMeta> + Sync,
     F: FnMut(&Path, &str, &str, &TrackMeta, &std::fs::Metadata),
     D: FnMut(&Path, usize),
 {
@@ -190,14 +274,72 @@ where
 
     tracing::info!(root = %root.display(), "scanning library");
 
-    let mut entries_by_dir = std::collections::HashMap::new();
-    scan_dir(
-        &root,
-        &root,
-        &mut entries_by_dir,
-        &mut on_track,
-        &mut on_dir,
-    )?;
+    let mut listings = Vec::new();
+    list_dir(&root, &root, &mut listings)?;
+    progress.set_total(listings.iter().map(DirListing::file_count).sum());
+
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .clamp(1, MAX_SCAN_WORKERS);
+    let next = AtomicUsize::new(0);
+    let mut entries_by_dir = std::collections::HashMap::with_capacity(listings.len());
+    std::thread::scope(|scope| {
+        // Bounded so probed folders (cover art included) cannot pile up ahead of `on_track`.
+        let (tx, rx) = std::sync::mpsc::sync_channel(workers);
+        for _ in 0..workers {
+            let tx = tx.clone();
+            let (listings, next, known) = (&listings, &next, &known);
+            scope.spawn(move || {
+                while let Some(listing) = listings.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    progress.enter_dir(&listing.dir);
+                    if tx.send((listing, probe_dir(listing, known))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        for (listing, scanned) in rx {
+            let dir = listing.dir.as_path();
+            if !scanned.is_empty() {
+                on_dir(dir, 0);
+            }
+            let mut tracks = Vec::with_capacity(scanned.len());
+            for track in scanned {
+                on_track(
+                    &track.path,
+                    &track.file_name,
+                    &track.ext,
+                    &track.meta,
+                    &track.fs_meta,
+                );
+                let entry = LibraryEntry::Track {
+                    path: track.path.to_string_lossy().to_string(),
+                    file_name: track.file_name.clone(),
+                    ext_hint: track.ext,
+                    duration_ms: track.meta.duration_ms,
+                    sample_rate: track.meta.sample_rate,
+                    album: track.meta.album,
+                    artist: track.meta.artist,
+                    format: track.meta.format.unwrap_or_else(|| "<unknown>".into()),
+                };
+                tracks.push((track.file_name.to_lowercase(), entry));
+            }
+            tracks.sort_by(|a, b| a.0.cmp(&b.0));
+
+            let track_count = tracks.len();
+            let mut entries = Vec::with_capacity(listing.dirs.len() + tracks.len());
+            entries.extend(listing.dirs.iter().map(|(_, e)| e.clone()));
+            entries.extend(tracks.into_iter().map(|(_, e)| e));
+            entries_by_dir.insert(listing.dir.clone(), entries);
+            if track_count > 0 {
+                on_dir(dir, track_count);
+            }
+            progress.add_scanned(listing.file_count());
+        }
+    });
 
     tracing::info!(root = %root.display(), dirs = entries_by_dir.len(), "library scan complete");
     Ok(LibraryIndex {
@@ -206,21 +348,38 @@ where
     })
 }
 
-/// Recursively scan one directory and populate index maps.
-fn scan_dir<F, D>(
-    root: &Path,
-    dir: &Path,
-    entries_by_dir: &mut std::collections::HashMap<PathBuf, Vec<LibraryEntry>>,
-    on_track: &mut F,
-    on_dir: &mut D,
-) -> Result<()>
-where
-    F: FnMut(&Path, &str, &str, &TrackMeta, &std::fs::Metadata),
-    D: FnMut(&Path, usize),
-{
+/// One folder as listed by the walk that precedes probing.
+struct DirListing {
+    dir: PathBuf,
+    /// Sub-folder entries keyed by lower-cased name, sorted.
+    dirs: Vec<(String, LibraryEntry)>,
+    /// Audio files not covered by a cue sheet, as `(path, file name, extension)`.
+    files: Vec<(PathBuf, String, String)>,
+    cue_albums: Vec<CueAlbum>,
+}
+
+impl DirListing {
+    /// Audio files the folder contributes to scan progress.
+    fn file_count(&self) -> usize {
+        self.files.len() + self.cue_albums.len()
+    }
+}
+
+/// A probed track waiting for the scan callbacks.
+struct ScannedTrack {
+    path: PathBuf,
+    file_name: String,
+    ext: String,
+    meta: TrackMeta,
+    fs_meta: fs::Metadata,
+}
+
+/// Recursively list one directory and its sub-directories, parents first.
+fn list_dir(root: &Path, dir: &Path, listings: &mut Vec<DirListing>) -> Result<()> {
     let mut dirs = Vec::new();
-    let mut tracks = Vec::new();
-    let mut has_tracks = false;
+    let mut files = Vec::new();
+    let mut children = Vec::new();
+    let cue_albums = cue_albums_in_dir(dir)?;
 
     for entry in fs::read_dir(dir).with_context(|| format!("read_dir {:?}", dir))? {
         let entry = entry.context("read_dir entry")?;
@@ -239,6 +398,7 @@ where
                     name,
                 },
             ));
+            children.push(path);
             continue;
         }
         if !path.is_file() {
@@ -250,7 +410,8 @@ where
             .and_then(OsStr::to_str)
             .unwrap_or("")
             .to_ascii_lowercase();
-        if !is_supported_extension(&ext) {
+        if !is_supported_extension(&ext) || cue_albums.iter().any(|album| album.audio_path == path)
+        {
             continue;
         }
 
@@ -259,64 +420,164 @@ where
             .and_then(OsStr::to_str)
             .unwrap_or("<unknown>")
             .to_string();
+        files.push((path, file_name, ext));
+    }
+
+    dirs.sort_by(|a, b| a.0.cmp(&b.0));
+    listings.push(DirListing {
+        dir: dir.to_path_buf(),
+        dirs,
+        files,
+        cue_albums,
+    });
 
-        let meta = probe_track_meta(&path, &ext);
-        let fs_meta = match fs::metadata(&path) {
+    for path in children {
+        let canon = path
+            .canonicalize()
+            .with_context(|| format!("canonicalize {:?}", path))?;
+        if canon.starts_with(root) {
+            list_dir(root, &canon, listings)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Probe the audio files of one listed directory, reusing `known` metadata where offered.
+fn probe_dir<K>(listing: &DirListing, known: &K) -> Vec<ScannedTrack>
+where
+    K: Fn(&Path, &std::fs::Metadata) -> Option<TrackMeta>,
+{
+    let mut tracks = Vec::new();
+    for (path, file_name, ext) in &listing.files {
+        let fs_meta = match fs::metadata(path) {
             Ok(meta) => meta,
             Err(_) => continue,
         };
-        if !has_tracks {
-            has_tracks = true;
-            on_dir(dir, 0);
-        }
-        on_track(&path, &file_name, &ext, &meta, &fs_meta);
-        let entry = LibraryEntry::Track {
-            path: path.to_string_lossy().to_string(),
+        let meta = known(path, &fs_meta).unwrap_or_else(|| probe_track_meta(path, ext));
+        tracks.push(ScannedTrack {
+            path: path.clone(),
             file_name: file_name.clone(),
-            ext_hint: ext.clone(),
-            duration_ms: meta.duration_ms,
-            sample_rate: meta.sample_rate,
-            album: meta.album,
-            artist: meta.artist,
-            format: meta.format.unwrap_or_else(|| "<unknown>".into()),
-        };
-        tracks.push((file_name.to_lowercase(), entry));
+            ext: ext.clone(),
+            meta,
+            fs_meta,
+        });
     }
 
-    dirs.sort_by(|a, b| a.0.cmp(&b.0));
-    tracks.sort_by(|a, b| a.0.cmp(&b.0));
-
-    let track_count = tracks.len();
-    let mut entries = Vec::with_capacity(dirs.len() + tracks.len());
-    entries.extend(dirs.into_iter().map(|(_, e)| e));
-    entries.extend(tracks.into_iter().map(|(_, e)| e));
-
-    entries_by_dir.insert(dir.to_path_buf(), entries);
-    if has_tracks {
-        on_dir(dir, track_count);
+    for album in &listing.cue_albums {
+        let ext = album
+            .audio_path
+            .extension()
+            .and_then(OsStr::to_str)
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let audio_meta = probe_track_meta(&album.audio_path, &ext);
+        let fs_meta = match fs::metadata(&album.audio_path) {
+            Ok(meta) => meta,
+            Err(_) => continue,
+        };
+        for track in &album.sheet.tracks {
+            tracks.push(ScannedTrack {
+                path: cue_tracks::track_path(&album.cue_path, track.number),
+                file_name: cue_track_file_name(track),
+                ext: ext.clone(),
+                meta: cue_track_meta(&album.sheet, track, &audio_meta),
+                fs_meta: fs_meta.clone(),
+            });
+        }
     }
+    tracks
+}
+
+/// Cue sheet in a directory together with the single audio file it splits.
+struct CueAlbum {
+    cue_path: PathBuf,
+    audio_path: PathBuf,
+    sheet: CueSheet,
+}
 
+/// Find cue sheets in `dir` that split one supported audio file into tracks.
+///
+/// Unreadable sheets are logged and skipped so the audio file is listed as-is.
+fn cue_albums_in_dir(dir: &Path) -> Result<Vec<CueAlbum>> {
+    let mut albums = Vec::new();
     for entry in fs::read_dir(dir).with_context(|| format!("read_dir {:?}", dir))? {
-        let entry = entry.context("read_dir entry")?;
-        let path = entry.path();
-        if path.is_dir() {
-            let canon = path
-                .canonicalize()
-                .with_context(|| format!("canonicalize {:?}", path))?;
-            if canon.starts_with(root) {
-                scan_dir(root, &canon, entries_by_dir, on_track, on_dir)?;
+        let cue_path = entry.context("read_dir entry")?.path();
+        if !cue_tracks::is_cue_file(&cue_path) || !cue_path.is_file() {
+            continue;
+        }
+        let sheet = match audio_player::cue::read(&cue_path) {
+            Ok(sheet) => sheet,
+            Err(err) => {
+                tracing::warn!(path = %cue_path.display(), error = %err, "cue sheet skipped");
+                continue;
             }
+        };
+        let Some(audio_path) = cue_tracks::audio_file(&cue_path, &sheet) else {
+            continue;
+        };
+        let ext = audio_path
+            .extension()
+            .and_then(OsStr::to_str)
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        if is_supported_extension(&ext) {
+            albums.push(CueAlbum {
+                cue_path,
+                audio_path,
+                sheet,
+            });
         }
     }
+    albums.sort_by(|a, b| a.cue_path.cmp(&b.cue_path));
+    Ok(albums)
+}
 
-    Ok(())
+/// Listing name of a cue track (`03 - Title`), sorting in sheet order.
+fn cue_track_file_name(track: &CueTrack) -> String {
+    match track.title.as_deref() {
+        Some(title) => format!("{:02} - {title}", track.number),
+        None => format!("{:02}", track.number),
+    }
+}
+
+/// Metadata of one cue track: sheet fields over the audio file's tags.
+fn cue_track_meta(sheet: &CueSheet, track: &CueTrack, audio: &TrackMeta) -> TrackMeta {
+    let mut meta = audio.clone();
+    meta.duration_ms = track.range.duration_ms(audio.duration_ms);
+    meta.title = track.title.clone().or_else(|| audio.title.clone());
+    meta.artist = track.performer.clone().or_else(|| audio.artist.clone());
+    meta.album = sheet.title.clone().or_else(|| audio.album.clone());
+    if !audio.compilation {
+        meta.album_artist = sheet
+            .performer
+            .clone()
+            .or_else(|| audio.album_artist.clone());
+    }
+    meta.track_number = Some(track.number);
+    // Embedded lyrics belong to the whole file, not one cue track.
+    meta.lyrics = None;
+    meta.year = sheet.date.as_deref().and_then(parse_i32_tag).or(audio.year);
+    meta
 }
 
 /// Return whether extension is supported for audio metadata scanning.
-fn is_supported_extension(ext: &str) -> bool {
+pub(crate) fn is_supported_extension(ext: &str) -> bool {
     matches!(
         ext,
-        "flac" | "wav" | "aiff" | "aif" | "mp3" | "m4a" | "aac" | "alac" | "ogg" | "oga" | "opus"
+        "flac"
+            | "wav"
+            | "aiff"
+            | "aif"
+            | "mp3"
+            | "m4a"
+            | "aac"
+            | "alac"
+            | "ogg"
+            | "oga"
+            | "opus"
+            | "dsf"
+            | "dff"
     )
 }
 
@@ -343,8 +604,24 @@ pub struct TrackMeta {
     pub track_number: Option<u32>,
     /// Disc number.
     pub disc_number: Option<u32>,
+    /// Disc subtitle (e.g. the title of one disc in a box set).
+    pub disc_title: Option<String>,
+    /// Embedded lyrics (plain or LRC text).
+    pub lyrics: Option<String>,
     /// Release year.
     pub year: Option<i32>,
+    /// Genres in tag order, without duplicates.
+    pub genres: Vec<String>,
+    /// Composer.
+    pub composer: Option<String>,
+    /// Conductor.
+    pub conductor: Option<String>,
+    /// Work the track belongs to (e.g. a symphony).
+    pub work: Option<String>,
+    /// Movement name within the work.
+    pub movement: Option<String>,
+    /// Movement number within the work.
+    pub movement_number: Option<u32>,
     /// Container/format hint (upper-case).
     pub format: Option<String>,
     /// Embedded front cover art when available.
@@ -375,6 +652,16 @@ fn probe_track_meta(path: &Path, ext_hint: &str) -> TrackMeta {
         Ok(file) => file,
         Err(_) => return meta,
     };
+    if matches!(ext_hint, "dsf" | "dff") {
+        // Symphonia does not read DSD; take the stream properties from the header.
+        let mut file = file;
+        if let Ok(Some(dsd)) = audio_player::dsd::probe(&mut file) {
+            meta.sample_rate = Some(dsd.dsd_rate);
+            meta.bit_depth = Some(1);
+            meta.duration_ms = Some(dsd.duration_ms);
+        }
+        return meta;
+    }
     let mut hint = Hint::new();
     if !ext_hint.is_empty() {
         hint.with_extension(ext_hint);
@@ -445,6 +732,50 @@ fn probe_track_meta(path: &Path, ext_hint: &str) -> TrackMeta {
                         meta.year = parse_i32_tag(&tag.value.to_string());
                     }
                 }
+                Some(symphonia::core::meta::StandardTagKey::DiscSubtitle)
+                    if meta.disc_title.is_none() =>
+                {
+                    meta.disc_title = Some(tag.value.to_string());
+                }
+                Some(symphonia::core::meta::StandardTagKey::Lyrics) if meta.lyrics.is_none() => {
+                    meta.lyrics = Some(tag.value.to_string());
+                }
+                Some(symphonia::core::meta::StandardTagKey::Genre) => {
+                    push_genres(&mut meta.genres, &tag.value.to_string());
+                }
+                Some(symphonia::core::meta::StandardTagKey::Composer)
+                    if meta.composer.is_none() =>
+                {
+                    meta.composer = Some(tag.value.to_string());
+                }
+                Some(symphonia::core::meta::StandardTagKey::Conductor)
+                    if meta.conductor.is_none() =>
+                {
+                    meta.conductor = Some(tag.value.to_string());
+                }
+                Some(symphonia::core::meta::StandardTagKey::MovementName)
+                    if meta.movement.is_none() =>
+                {
+                    meta.movement = Some(tag.value.to_string());
+                }
+                Some(symphonia::core::meta::StandardTagKey::MovementNumber)
+                    if meta.movement_number.is_none() =>
+                {
+                    meta.movement_number = parse_u32_tag(&tag.value.to_string());
+                }
+                // Symphonia has no standard key for these Vorbis/TXXX names used by taggers.
+                None => match tag.key.to_ascii_uppercase().as_str() {
+                    "WORK" | "©WRK" if meta.work.is_none() => {
+                        meta.work = Some(tag.value.to_string());
+                    }
+                    "MOVEMENTNAME" if meta.movement.is_none() => {
+                        meta.movement = Some(tag.value.to_string());
+                    }
+                    "MOVEMENT" if meta.movement_number.is_none() => {
+                        meta.movement_number = parse_u32_tag(&tag.value.to_string());
+                    }
+                    _ => {}
+                },
                 _ => {}
             }
         }
@@ -462,8 +793,18 @@ fn probe_track_meta(path: &Path, ext_hint: &str) -> TrackMeta {
     meta
 }
 
-/// Probe and validate one supported track file.
+/// Probe and validate one supported track file (or cue track).
 pub fn probe_track(path: &Path) -> Result<TrackMeta> {
+    if let Some((cue_path, number)) = cue_tracks::split_track_path(path) {
+        let sheet = audio_player::cue::read(&cue_path)?;
+        let track = sheet
+            .track(number)
+            .ok_or_else(|| anyhow::anyhow!("cue track {number} not found"))?;
+        let audio_path = cue_tracks::audio_file(&cue_path, &sheet)
+            .ok_or_else(|| anyhow::anyhow!("cue audio file not found"))?;
+        let audio_meta = probe_track(&audio_path)?;
+        return Ok(cue_track_meta(&sheet, track, &audio_meta));
+    }
     let ext = path
         .extension()
         .and_then(OsStr::to_str)
@@ -490,6 +831,15 @@ fn parse_i32_tag(raw: &str) -> Option<i32> {
 }
 
 /// Parse boolean-ish tag values (`1`, `true`, `yes`, `y`).
+/// Append the genres of one tag value, which may hold several separated by `;` or NUL.
+fn push_genres(genres: &mut Vec<String>, raw: &str) {
+    for genre in raw.split([';', '\0']).map(str::trim) {
+        if !genre.is_empty() && !genres.iter().any(|g| g.eq_ignore_ascii_case(genre)) {
+            genres.push(genre.to_string());
+        }
+    }
+}
+
 fn parse_bool_tag(raw: &str) -> bool {
     matches!(
         raw.trim().to_ascii_lowercase().as_str(),
@@ -547,9 +897,20 @@ mod tests {
         assert!(is_supported_extension("flac"));
         assert!(is_supported_extension("mp3"));
         assert!(is_supported_extension("opus"));
+        assert!(is_supported_extension("m4a"));
+        assert!(is_supported_extension("aac"));
+        assert!(is_supported_extension("ogg"));
         assert!(!is_supported_extension("txt"));
     }
 
+    #[test]
+    fn push_genres_splits_and_dedups() {
+        let mut genres = Vec::new();
+        push_genres(&mut genres, "Jazz; Hard Bop;");
+        push_genres(&mut genres, "jazz\0Cool Jazz");
+        assert_eq!(genres, ["Jazz", "Hard Bop", "Cool Jazz"]);
+    }
+
     #[test]
     fn scan_library_lists_dirs_and_tracks() {
         let root = std::env::temp_dir().join(format!(
@@ -578,6 +939,116 @@ mod tests {
         assert!(names.contains(&"song.flac".to_string()));
     }
 
+    #[test]
+    fn scan_library_lists_cue_tracks_instead_of_rip() {
+        let root = std::env::temp_dir().join(format!(
+            "audio-hub-library-cue-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let _ = std::fs::create_dir_all(&root);
+        let _ = std::fs::write(root.join("Album.flac"), b"test");
+        let _ = std::fs::write(
+            root.join("Album.cue"),
+            "TITLE \"Album\"\nPERFORMER \"Band\"\nFILE \"Album.flac\" WAVE\n\
+             TRACK 01 AUDIO\nTITLE \"Intro\"\nINDEX 01 00:00:00\n\
+             TRACK 02 AUDIO\nTITLE \"Outro\"\nINDEX 01 02:00:00\n",
+        );
+
+        let mut seen = Vec::new();
+        let index = scan_library_with_meta(
+            &root,
+            &ScanProgress::default(),
+            |_path, _fs_meta| None,
+            |path, _file_name, ext, meta, _fs_meta| {
+                seen.push((path.to_path_buf(), ext.to_string(), meta.track_number));
+            },
+            |_dir, _count| {},
+        )
+        .expect("scan library");
+        let entries = index.list_dir(index.root()).expect("entries");
+        let names = entries
+            .iter()
+            .map(|entry| match entry {
+                LibraryEntry::Dir { name, .. } => name.clone(),
+                LibraryEntry::Track { file_name, .. } => file_name.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, vec!["01 - Intro", "02 - Outro"]);
+        let cue_path = index.root().join("Album.cue");
+        assert_eq!(
+            seen[1],
+            (
+                cue_tracks::track_path(&cue_path, 2),
+                "flac".to_string(),
+                Some(2)
+            )
+        );
+        let meta = probe_track(&seen[0].0).expect("probe cue track");
+        assert_eq!(meta.title.as_deref(), Some("Intro"));
+        assert_eq!(meta.album_artist.as_deref(), Some("Band"));
+        assert_eq!(meta.duration_ms, Some(120_000));
+    }
+
+    #[test]
+    fn scan_library_reports_progress_and_reuses_known_meta() {
+        let root = std::env::temp_dir().join(format!(
+            "audio-hub-library-progress-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        for album in ["A", "B", "B/C"] {
+            let _ = std::fs::create_dir_all(root.join(album));
+        }
+        for track in ["A/1.flac", "A/2.flac", "B/C/3.mp3", "B/notes.txt"] {
+            let _ = std::fs::write(root.join(track), b"test");
+        }
+
+        let progress = ScanProgress::default();
+        assert!(progress.begin());
+        assert!(!progress.begin());
+        let mut finished = Vec::new();
+        let index = scan_library_with_meta(
+            &root,
+            &progress,
+            |path, _fs_meta| {
+                path.ends_with("2.flac").then(|| TrackMeta {
+                    title: Some("Known".to_string()),
+                    format: Some("FLAC".to_string()),
+                    ..TrackMeta::default()
+                })
+            },
+            |path, _file_name, _ext, meta, _fs_meta| {
+                if path.ends_with("2.flac") {
+                    assert_eq!(meta.title.as_deref(), Some("Known"));
+                }
+            },
+            |dir, count| {
+                if count > 0 {
+                    finished.push((dir.file_name().unwrap().to_owned(), count));
+                }
+            },
+        )
+        .expect("scan library");
+        progress.finish(None);
+
+        finished.sort();
+        assert_eq!(finished, [("A".into(), 2), ("C".into(), 1)]);
+        let snapshot = progress.snapshot();
+        assert!(!snapshot.running);
+        assert_eq!((snapshot.files_scanned, snapshot.files_total), (3, 3));
+        assert!(snapshot.finished_at_ms.is_some());
+        let nested = index.root().join("B/C");
+        assert_eq!(index.list_dir(&nested).map(<[_]>::len), Some(1));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
     #[test]
     fn find_track_by_path_locates_track() {
         let root = std::env::temp_dir().join(format!(
@@ -0,0 +1,52 @@
+//! Background full-library scans.
+//!
+//! The startup scan and `/library/rescan` run on their own thread so neither delays serving
+//! requests. Progress is published through [`AppState::library_scan`] and streamed by
+//! `/library/scan/progress`; only one scan runs at a time.
+
+use actix_web::web;
+
+use crate::state::AppState;
+use crate::webhooks::{self, WebhookEvent};
+
+/// Start a full scan unless one is already running; returns whether it started.
+///
+/// With `prune`, tracks no longer on disk are dropped from the metadata DB (a user rescan);
+/// without it the scan only adds and refreshes tracks (startup).
+pub(crate) fn spawn_library_scan(state: web::Data<AppState>, prune: bool) -> bool {
+    if !state.library_scan.begin() {
+        return false;
+    }
+    let metadata_service = state.metadata_service();
+    std::thread::spawn(move || {
+        let result = if prune {
+            metadata_service.rescan_library(&state.library_scan, true)
+        } else {
+            metadata_service
+                .scan_library(&state.library_scan, false)
+                .inspect(|_| metadata_service.ensure_album_markers())
+        };
+        match result {
+            Ok(index) => {
+                *state.library.write().unwrap() = index;
+                state.events.library_changed();
+                state.metadata.wake.notify();
+                state.library_scan.finish(None);
+                webhooks::emit(WebhookEvent::ScanCompleted {
+                    full_rescan: prune,
+                    error: None,
+                });
+            }
+            Err(err) => {
+                tracing::warn!(error = %err, "library scan failed");
+                let error = format!("{err:#}");
+                state.library_scan.finish(Some(error.clone()));
+                webhooks::emit(WebhookEvent::ScanCompleted {
+                    full_rescan: prune,
+                    error: Some(error),
+                });
+            }
+        }
+    });
+    true
+}
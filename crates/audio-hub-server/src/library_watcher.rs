@@ -0,0 +1,293 @@
+//! Filesystem watcher for incremental library updates.
+//!
+//! Watches the media root recursively (inotify/FSEvents/ReadDirectoryChanges via `notify`)
+//! and rescans or removes only the tracks that changed, so new downloads show up without a
+//! manual `/library/rescan`. Each change emits `LibraryChanged` through the metadata service.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use actix_web::web;
+use notify::event::{AccessKind, AccessMode, ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::metadata_service::MetadataService;
+use crate::state::AppState;
+
+/// Quiet period that ends a batch of filesystem events.
+const SETTLE_DELAY: Duration = Duration::from_millis(750);
+
+/// Longest a batch collects events while files keep changing (e.g. a large copy).
+const MAX_BATCH_AGE: Duration = Duration::from_secs(5);
+
+/// What a batch does with one path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Change {
+    /// Rescan the file, or every audio file below the directory.
+    Upsert,
+    /// Drop the track, or every track below the directory.
+    Remove,
+}
+
+/// Spawn the watcher thread for the library root.
+pub(crate) fn spawn_library_watcher(state: web::Data<AppState>) {
+    let root = state.library.read().unwrap().root().to_path_buf();
+    let metadata_service = state.metadata_service();
+    std::thread::spawn(move || {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut watcher: RecommendedWatcher = match notify::recommended_watcher(tx) {
+            Ok(watcher) => watcher,
+            Err(err) => {
+                tracing::warn!(error = %err, "metadata watcher init failed");
+                return;
+            }
+        };
+        if let Err(err) = watcher.watch(&root, RecursiveMode::Recursive) {
+            tracing::warn!(error = %err, "metadata watcher setup failed");
+            return;
+        }
+        tracing::info!(root = %root.display(), "watching library for changes");
+        while let Ok(first) = rx.recv() {
+            let started = Instant::now();
+            let mut pending = BTreeMap::new();
+            let mut next = Some(first);
+            while let Some(event) = next.take() {
+                match event {
+                    Ok(event) => record_event(&root, &event, &mut pending),
+                    Err(err) => tracing::warn!(error = %err, "metadata watcher event error"),
+                }
+                if started.elapsed() < MAX_BATCH_AGE {
+                    next = rx.recv_timeout(SETTLE_DELAY).ok();
+                }
+            }
+            apply_changes(&state, &metadata_service, pending);
+        }
+    });
+}
+
+/// Fold one filesystem event into the batch; a later event for a path wins.
+fn record_event(root: &Path, event: &Event, pending: &mut BTreeMap<PathBuf, Change>) {
+    let mut record = |path: &PathBuf, change: Change| {
+        if !is_hidden(root, path) {
+            pending.insert(path.clone(), change);
+        }
+    };
+    let exists = |path: &PathBuf| {
+        if path.exists() {
+            Change::Upsert
+        } else {
+            Change::Remove
+        }
+    };
+    match event.kind {
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() >= 2 => {
+            record(&event.paths[0], Change::Remove);
+            record(&event.paths[1], Change::Upsert);
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) | EventKind::Remove(_) => {
+            event.paths.iter().for_each(|p| record(p, Change::Remove));
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) | EventKind::Create(_) => {
+            event.paths.iter().for_each(|p| record(p, Change::Upsert));
+        }
+        // Opening or reading a file (e.g. streaming it) changes nothing.
+        EventKind::Access(AccessKind::Close(AccessMode::Write))
+        | EventKind::Modify(_)
+        | EventKind::Any
+        | EventKind::Other => {
+            event.paths.iter().for_each(|p| record(p, exists(p)));
+        }
+        EventKind::Access(_) => {}
+    }
+}
+
+/// Whether `path` sits in a hidden file or directory under `root` (such as `.audio-hub`).
+fn is_hidden(root: &Path, path: &Path) -> bool {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .any(|c| {
+            c.as_os_str()
+                .to_str()
+                .is_some_and(|name| name.starts_with('.'))
+        })
+}
+
+/// Apply one batch of changes to the metadata DB and library index.
+fn apply_changes(
+    state: &AppState,
+    metadata_service: &MetadataService,
+    pending: BTreeMap<PathBuf, Change>,
+) {
+    let mut known_tracks: Option<Vec<PathBuf>> = None;
+    let (upserts, removes): (Vec<_>, Vec<_>) = pending
+        .into_iter()
+        .map(|(path, change)| {
+            if change == Change::Upsert && !path.exists() {
+                (path, Change::Remove)
+            } else {
+                (path, change)
+            }
+        })
+        .partition(|(_, change)| *change == Change::Upsert);
+    // Upserts go first so a file moved within the batch is rebound to its new path (see
+    // `MetadataService::upsert_track_record`) before its old path is dropped.
+    for (path, change) in upserts.into_iter().chain(removes) {
+        match change {
+            Change::Upsert => {
+                let mut files = Vec::new();
+                collect_audio_files(&path, &mut files);
+                for file in files {
+                    if let Err(response) = metadata_service.rescan_track(&state.library, &file) {
+                        let status = response.status();
+                        if status != actix_web::http::StatusCode::NOT_FOUND
+                            && status != actix_web::http::StatusCode::BAD_REQUEST
+                        {
+                            tracing::warn!(
+                                status = %status,
+                                path = %file.display(),
+                                "metadata watcher rescan failed"
+                            );
+                        }
+                    }
+                }
+            }
+            Change::Remove => {
+                let known = known_tracks.get_or_insert_with(|| {
+                    state
+                        .metadata
+                        .db
+                        .list_all_track_paths()
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(PathBuf::from)
+                        .collect()
+                });
+                // A removed directory takes every track below it along.
+                for track in known.iter().filter(|track| track.starts_with(&path)) {
+                    if let Err(response) =
+                        metadata_service.remove_track_by_path(&state.library, track)
+                    {
+                        let status = response.status();
+                        if status != actix_web::http::StatusCode::NOT_FOUND
+                            && status != actix_web::http::StatusCode::BAD_REQUEST
+                        {
+                            tracing::warn!(
+                                status = %status,
+                                path = %track.display(),
+                                "metadata watcher remove failed"
+                            );
+                        }
+                    }
+                }
+                known.retain(|track| !track.starts_with(&path));
+            }
+        }
+    }
+}
+
+/// Collect `path` if it is a supported audio file, or the audio files below it if a directory.
+fn collect_audio_files(path: &Path, files: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        let Ok(entries) = std::fs::read_dir(path) else {
+            return;
+        };
+        let mut children: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+        children.sort();
+        for child in children {
+            let hidden = child
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with('.'));
+            if !hidden {
+                collect_audio_files(&child, files);
+            }
+        }
+    } else if path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| crate::library::is_supported_extension(&ext.to_ascii_lowercase()))
+    {
+        files.push(path.to_path_buf());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, DataChange, RemoveKind};
+
+    fn event(kind: EventKind, paths: &[&str]) -> Event {
+        paths.iter().fold(Event::new(kind), |event, path| {
+            event.add_path(PathBuf::from(path))
+        })
+    }
+
+    #[test]
+    fn record_event_folds_renames_and_skips_reads_and_hidden_paths() {
+        let root = Path::new("/music");
+        let mut pending = BTreeMap::new();
+        let events = [
+            event(EventKind::Create(CreateKind::Folder), &["/music/New Album"]),
+            event(
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+                &["/music/old.flac", "/music/Artist/new.flac"],
+            ),
+            event(
+                EventKind::Access(AccessKind::Open(AccessMode::Read)),
+                &["/music/a.flac"],
+            ),
+            event(
+                EventKind::Modify(ModifyKind::Data(DataChange::Content)),
+                &["/music/.audio-hub/metadata.sqlite"],
+            ),
+            event(EventKind::Remove(RemoveKind::Folder), &["/music/Gone"]),
+            event(EventKind::Create(CreateKind::File), &["/music/Gone"]),
+            event(EventKind::Remove(RemoveKind::Folder), &["/music/Gone"]),
+        ];
+        for event in &events {
+            record_event(root, event, &mut pending);
+        }
+
+        let changes: Vec<_> = pending
+            .iter()
+            .map(|(path, change)| (path.to_str().unwrap(), *change))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("/music/Artist/new.flac", Change::Upsert),
+                ("/music/Gone", Change::Remove),
+                ("/music/New Album", Change::Upsert),
+                ("/music/old.flac", Change::Remove),
+            ]
+        );
+    }
+
+    #[test]
+    fn collect_audio_files_walks_directories() {
+        let root = std::env::temp_dir().join(format!(
+            "audio-hub-watch-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(root.join("Album/.hidden")).unwrap();
+        for name in [
+            "Album/01.flac",
+            "Album/cover.jpg",
+            "Album/.hidden/x.flac",
+            "b.MP3",
+        ] {
+            std::fs::write(root.join(name), b"").unwrap();
+        }
+
+        let mut files = Vec::new();
+        collect_audio_files(&root, &mut files);
+        assert_eq!(files, [root.join("Album/01.flac"), root.join("b.MP3")]);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
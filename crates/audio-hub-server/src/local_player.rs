@@ -13,7 +13,8 @@ use crossbeam_channel::{Receiver, Sender};
 use symphonia::core::probe::Hint;
 
 use audio_player::config::PlaybackConfig;
-use audio_player::{decode, device, pipeline};
+use audio_player::cue::TrackRange;
+use audio_player::{decode, device, dsd, pipeline};
 
 use crate::bridge::BridgeCommand;
 use crate::status_store::StatusStore;
@@ -190,8 +191,12 @@ fn play_one_file(
     my_id: u64,
     session_id: Arc<AtomicU64>,
 ) -> Result<()> {
+    let (file_path, range) = match crate::cue_tracks::resolve(&path)? {
+        Some(src) => (src.audio_path, src.range),
+        None => (path.clone(), TrackRange::default()),
+    };
     let mut hint = Hint::new();
-    if let Some(ext) = path.extension().and_then(|s| s.to_str()) {
+    if let Some(ext) = file_path.extension().and_then(|s| s.to_str()) {
         hint.with_extension(ext);
     }
 
@@ -202,19 +207,24 @@ fn play_one_file(
         playback_eff.chunk_frames = playback_eff.chunk_frames.min(1024);
     }
 
-    let file = File::open(&path).with_context(|| format!("open {:?}", path))?;
+    let file = File::open(&file_path).with_context(|| format!("open {:?}", file_path))?;
     let (src_spec, srcq, duration_ms, source_info) =
-        decode::start_streaming_decode_from_media_source_at(
+        decode::start_streaming_decode_range_from_media_source(
             Box::new(file),
             hint,
             playback_eff.buffer_seconds,
             seek_ms,
+            range,
+            playback_eff.decode_errors,
         )
         .context("decode local file")?;
+    let (src_spec, srcq) =
+        dsd::ensure_pcm(src_spec, srcq, &source_info, playback_eff.buffer_seconds);
 
     let selected = device_selected.lock().unwrap().clone();
     let device = device::pick_device(host, selected.as_deref())?;
-    let config = device::pick_output_config(&device, Some(src_spec.rate))?;
+    let config =
+        device::pick_source_output_config(&device, src_spec.rate, playback_eff.rate_switch)?;
     let mut stream_config: cpal::StreamConfig = config.clone().into();
     if let Some(buf) = device::pick_buffer_size(&config) {
         stream_config.buffer_size = buf;
@@ -236,7 +246,7 @@ fn play_one_file(
     let underrun_frames = Arc::new(AtomicU64::new(0));
     let underrun_events = Arc::new(AtomicU64::new(0));
     let output_sample_format = Some(format!("{:?}", config.sample_format()));
-    let container = path
+    let container = file_path
         .extension()
         .and_then(|s| s.to_str())
         .map(|s| s.to_ascii_uppercase());
@@ -257,6 +267,10 @@ fn play_one_file(
         seek_ms,
         paused_flag.load(Ordering::Relaxed),
     );
+    status.set_chapters(
+        (!source_info.chapters.is_empty())
+            .then(|| source_info.chapters.iter().map(Into::into).collect()),
+    );
 
     let result = pipeline::play_decoded_source(
         &device,
@@ -275,6 +289,10 @@ fn play_one_file(
             buffer_capacity_frames: None,
             volume_percent: None,
             muted: None,
+            levels: None,
+            bit_perfect: None,
+            hotplug: None,
+            output: None,
         },
     );
 
@@ -0,0 +1,399 @@
+//! Loudness analysis (ITU-R BS.1770 / EBU R128) and the background ReplayGain job.
+//!
+//! Each track gets an integrated loudness (LUFS) and a true peak (dBTP). Album values are
+//! measured over the gating blocks of all album tracks together, as ReplayGain's album mode
+//! expects, so the job always re-measures a whole album when any of its tracks changes.
+
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+
+use crate::metadata_db::{LoudnessCandidate, LoudnessResult, MetadataDb};
+use crate::metadata_service::MetadataService;
+use crate::state::MetadataWake;
+use crate::track_analysis::TrackReader;
+
+/// Gating blocks are 400 ms long and start every 100 ms.
+const BLOCK_STEPS: usize = 4;
+/// Blocks quieter than this never count towards integrated loudness.
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+/// Blocks this far below the ungated loudness are dropped by the relative gate.
+const RELATIVE_GATE_LU: f64 = 10.0;
+/// Taps per polyphase branch of the true-peak interpolator.
+const TRUE_PEAK_TAPS: usize = 16;
+
+/// Second-order IIR section in direct form I.
+#[derive(Clone, Copy, Default)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, input: f64) -> f64 {
+        let output = self.b[0] * input + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [input, self.x[0]];
+        self.y = [output, self.y[0]];
+        output
+    }
+}
+
+/// K-weighting filter pair (high shelf, then high pass) designed for `sample_rate`.
+///
+/// BS.1770 only lists 48 kHz coefficients; these are the analog prototypes libebur128 uses,
+/// which reproduce them at 48 kHz and work at any other rate.
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let rate = f64::from(sample_rate);
+
+    let f0 = 1681.974450955533;
+    let gain_db = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (PI * f0 / rate).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        ..Biquad::default()
+    };
+
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        ..Biquad::default()
+    };
+
+    [shelf, high_pass]
+}
+
+/// Polyphase windowed-sinc interpolator used to find inter-sample peaks.
+struct TruePeakDetector {
+    /// One coefficient set per interpolated phase.
+    phases: Vec<[f64; TRUE_PEAK_TAPS]>,
+    /// Most recent input samples per channel, newest first.
+    history: Vec<VecDeque<f64>>,
+    peak: f64,
+}
+
+impl TruePeakDetector {
+    /// Oversample 4x below 96 kHz and 2x below 192 kHz, as BS.1770 annex 2 suggests.
+    fn new(sample_rate: u32, channels: usize) -> Self {
+        let factor = match sample_rate {
+            0..96_000 => 4,
+            96_000..192_000 => 2,
+            _ => 1,
+        };
+        let delay = (TRUE_PEAK_TAPS / 2) as f64;
+        let phases = (0..factor)
+            .map(|phase| {
+                let mut taps = [0.0; TRUE_PEAK_TAPS];
+                for (k, tap) in taps.iter_mut().enumerate() {
+                    let x = k as f64 - delay + phase as f64 / factor as f64;
+                    let sinc = if x == 0.0 {
+                        1.0
+                    } else {
+                        (PI * x).sin() / (PI * x)
+                    };
+                    let window = 0.5 * (1.0 + (PI * x / (delay + 1.0)).cos());
+                    *tap = sinc * window;
+                }
+                taps
+            })
+            .collect();
+        Self {
+            phases,
+            history: vec![VecDeque::from(vec![0.0; TRUE_PEAK_TAPS]); channels],
+            peak: 0.0,
+        }
+    }
+
+    fn push(&mut self, channel: usize, sample: f64) {
+        let history = &mut self.history[channel];
+        history.pop_back();
+        history.push_front(sample);
+        self.peak = self.peak.max(sample.abs());
+        for taps in &self.phases {
+            let value: f64 = taps.iter().zip(history.iter()).map(|(t, x)| t * x).sum();
+            self.peak = self.peak.max(value.abs());
+        }
+    }
+}
+
+/// Streaming BS.1770 meter for one track.
+pub struct LoudnessMeter {
+    filters: Vec<[Biquad; 2]>,
+    true_peak: TruePeakDetector,
+    /// Frames per 100 ms step.
+    step_frames: usize,
+    frames_in_step: usize,
+    /// Summed K-weighted channel power of the current step.
+    step_power: f64,
+    /// Mean power of the last few complete steps.
+    recent_steps: VecDeque<f64>,
+    /// Mean power of every complete 400 ms gating block.
+    blocks: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    /// Meter for interleaved audio with `channels` channels at `sample_rate`.
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        Self {
+            filters: vec![k_weighting(sample_rate); channels],
+            true_peak: TruePeakDetector::new(sample_rate, channels),
+            step_frames: (sample_rate as usize / 10).max(1),
+            frames_in_step: 0,
+            step_power: 0.0,
+            recent_steps: VecDeque::with_capacity(BLOCK_STEPS),
+            blocks: Vec::new(),
+        }
+    }
+
+    /// Feed interleaved samples.
+    ///
+    /// All channels are weighted equally; surround weighting needs channel positions the
+    /// library does not track.
+    pub fn push_interleaved(&mut self, samples: &[f32]) {
+        let channels = self.filters.len();
+        for frame in samples.chunks_exact(channels) {
+            for (channel, sample) in frame.iter().enumerate() {
+                let sample = f64::from(*sample);
+                self.true_peak.push(channel, sample);
+                let [shelf, high_pass] = &mut self.filters[channel];
+                let weighted = high_pass.process(shelf.process(sample));
+                self.step_power += weighted * weighted;
+            }
+            self.frames_in_step += 1;
+            if self.frames_in_step == self.step_frames {
+                self.finish_step();
+            }
+        }
+    }
+
+    fn finish_step(&mut self) {
+        if self.recent_steps.len() == BLOCK_STEPS {
+            self.recent_steps.pop_front();
+        }
+        self.recent_steps
+            .push_back(self.step_power / self.step_frames as f64);
+        if self.recent_steps.len() == BLOCK_STEPS {
+            self.blocks
+                .push(self.recent_steps.iter().sum::<f64>() / BLOCK_STEPS as f64);
+        }
+        self.step_power = 0.0;
+        self.frames_in_step = 0;
+    }
+
+    /// Finish metering and return the gating blocks and true peak.
+    pub fn finish(self) -> Measurement {
+        Measurement {
+            blocks: self.blocks,
+            peak: self.true_peak.peak,
+        }
+    }
+}
+
+/// Raw meter output for one track.
+pub struct Measurement {
+    /// Mean K-weighted power of each 400 ms gating block.
+    pub blocks: Vec<f64>,
+    /// True peak as a linear sample value.
+    pub peak: f64,
+}
+
+impl Measurement {
+    /// Integrated loudness in LUFS; `None` when everything is below the absolute gate.
+    pub fn integrated_lufs(&self) -> Option<f64> {
+        integrated_lufs(&self.blocks)
+    }
+
+    /// True peak in dBTP; `None` for digital silence.
+    pub fn true_peak_db(&self) -> Option<f64> {
+        amplitude_db(self.peak)
+    }
+}
+
+fn block_lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+/// Gated integrated loudness over `blocks`, which may span several tracks.
+pub fn integrated_lufs(blocks: &[f64]) -> Option<f64> {
+    let mean_lufs = |blocks: &mut dyn Iterator<Item = &f64>| {
+        let (sum, count) = blocks.fold((0.0, 0usize), |(sum, count), power| {
+            (sum + power, count + 1)
+        });
+        (count > 0).then(|| block_lufs(sum / count as f64))
+    };
+    let loud_enough = |power: &&f64| **power > 0.0 && block_lufs(**power) > ABSOLUTE_GATE_LUFS;
+    let ungated = mean_lufs(&mut blocks.iter().filter(loud_enough))?;
+    let relative_gate = ungated - RELATIVE_GATE_LU;
+    mean_lufs(
+        &mut blocks
+            .iter()
+            .filter(loud_enough)
+            .filter(|power| block_lufs(**power) > relative_gate),
+    )
+}
+
+fn amplitude_db(peak: f64) -> Option<f64> {
+    (peak > 0.0).then(|| 20.0 * peak.log10())
+}
+
+/// Decode `path` (or the cue range it names) and meter it.
+pub fn measure_track(path: &Path) -> Result<Measurement> {
+    let mut reader = TrackReader::open(path)?;
+    let mut meter = LoudnessMeter::new(reader.sample_rate, reader.channels);
+    while let Some(samples) = reader.next_chunk() {
+        meter.push_interleaved(samples);
+    }
+    Ok(meter.finish())
+}
+
+/// Run the loudness job on a background thread.
+///
+/// Albums with a track that was never measured, or changed since, are measured whole so the
+/// album values stay consistent; once the library is covered the loop sleeps until `wake`
+/// fires (e.g. after a rescan).
+pub fn spawn_loudness_loop(db: MetadataDb, root: PathBuf, wake: MetadataWake) {
+    std::thread::spawn(move || {
+        let mut wake_seq = 0u64;
+        loop {
+            let candidates = match db.list_loudness_candidates(5) {
+                Ok(candidates) => candidates,
+                Err(err) => {
+                    tracing::warn!(error = %err, "loudness candidate query failed");
+                    std::thread::sleep(Duration::from_secs(10));
+                    continue;
+                }
+            };
+            if candidates.is_empty() {
+                wake.wait(&mut wake_seq);
+                continue;
+            }
+            tracing::info!(count = candidates.len(), "loudness candidates fetched");
+            for group in
+                candidates.chunk_by(|a, b| a.album_id.is_some() && a.album_id == b.album_id)
+            {
+                for (candidate, result) in group.iter().zip(measure_group(&root, group)) {
+                    if let Err(err) =
+                        db.upsert_loudness(candidate.track_id, candidate.mtime_ms, &result)
+                    {
+                        tracing::warn!(
+                            error = %err,
+                            path = %candidate.path,
+                            "loudness store failed"
+                        );
+                        std::thread::sleep(Duration::from_secs(10));
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Measure the tracks of one album (or a single loose track).
+///
+/// Failures are recorded per track so they are not retried until the file changes; album
+/// values cover the tracks that could be measured.
+fn measure_group(root: &Path, group: &[LoudnessCandidate]) -> Vec<LoudnessResult> {
+    let measurements: Vec<Result<Measurement>> = group
+        .iter()
+        .map(|candidate| {
+            let full_path = MetadataService::resolve_track_path(root, &candidate.path)
+                .map_err(|response| anyhow!("resolve path failed ({})", response.status()))?;
+            measure_track(&full_path)
+        })
+        .collect();
+    let measured = || measurements.iter().filter_map(|m| m.as_ref().ok());
+    let is_album = group.first().is_some_and(|c| c.album_id.is_some());
+    let album_blocks: Vec<f64> = measured().flat_map(|m| m.blocks.iter().copied()).collect();
+    let album_lufs = is_album.then(|| integrated_lufs(&album_blocks)).flatten();
+    let album_peak_db = is_album
+        .then(|| amplitude_db(measured().map(|m| m.peak).fold(0.0, f64::max)))
+        .flatten();
+    measurements
+        .into_iter()
+        .map(|measurement| match measurement {
+            Ok(measurement) => LoudnessResult {
+                integrated_lufs: measurement.integrated_lufs(),
+                true_peak_db: measurement.true_peak_db(),
+                album_lufs,
+                album_peak_db,
+                error: None,
+            },
+            Err(err) => LoudnessResult {
+                error: Some(err.to_string()),
+                ..LoudnessResult::default()
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(rate: u32, hz: f64, amplitude: f64, phase: f64, seconds: f64) -> Vec<f32> {
+        (0..(f64::from(rate) * seconds) as usize)
+            .map(|n| {
+                (amplitude * (2.0 * PI * hz * n as f64 / f64::from(rate) + phase).sin()) as f32
+            })
+            .collect()
+    }
+
+    #[test]
+    fn full_scale_sine_in_one_channel_reads_minus_three_lufs() {
+        for rate in [44_100, 48_000, 96_000] {
+            let mut meter = LoudnessMeter::new(rate, 1);
+            meter.push_interleaved(&sine(rate, 997.0, 1.0, 0.0, 5.0));
+            let lufs = meter.finish().integrated_lufs().expect("loudness");
+            assert!((lufs + 3.01).abs() < 0.05, "{rate} Hz: {lufs}");
+        }
+    }
+
+    #[test]
+    fn gating_ignores_silence_and_quiet_passages() {
+        let rate = 48_000;
+        let mut meter = LoudnessMeter::new(rate, 1);
+        meter.push_interleaved(&vec![0.0; rate as usize * 10]);
+        meter.push_interleaved(&sine(rate, 997.0, 0.5, 0.0, 20.0));
+        // 30 dB down: below the relative gate.
+        meter.push_interleaved(&sine(rate, 997.0, 0.5 / 31.6, 0.0, 5.0));
+        let measurement = meter.finish();
+        let lufs = measurement.integrated_lufs().expect("loudness");
+        assert!((lufs + 9.03).abs() < 0.1, "{lufs}");
+
+        let silent = LoudnessMeter::new(rate, 2).finish();
+        assert!(silent.integrated_lufs().is_none());
+        assert!(silent.true_peak_db().is_none());
+    }
+
+    #[test]
+    fn true_peak_finds_inter_sample_overs() {
+        // A quarter-rate sine sampled 45 degrees off its crests never hits a sample above 0.707.
+        let rate = 44_100;
+        let samples = sine(rate, f64::from(rate) / 4.0, 1.0, PI / 4.0, 1.0);
+        let sample_peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!(sample_peak < 0.71);
+        let mut meter = LoudnessMeter::new(rate, 1);
+        meter.push_interleaved(&samples);
+        let peak_db = meter.finish().true_peak_db().expect("peak");
+        assert!(peak_db.abs() < 0.5, "{peak_db}");
+    }
+}
@@ -0,0 +1,288 @@
+//! Track lyrics: LRC parsing, local sources, and the optional LRCLIB lookup.
+//!
+//! Scans pick up lyrics from an `.lrc` sidecar next to the audio file or from the embedded
+//! `LYRICS`/`USLT` tag. Tracks without local lyrics can be looked up on LRCLIB when
+//! `[lyrics] fetch_online` is set; the answer (including "none") is stored so it is asked once.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::clock::now_ms;
+use crate::metadata_db::TrackLyrics;
+
+const DEFAULT_PROVIDER_URL: &str = "https://lrclib.net/api";
+const USER_AGENT: &str = "audio-hub (https://github.com/dariusbakunas/audio-bridge)";
+/// Farthest a provider's track length may be from ours and still count as the same recording.
+const DURATION_TOLERANCE_MS: u64 = 3000;
+
+/// Lyrics source recorded for a sidecar `.lrc` file.
+pub const SOURCE_SIDECAR: &str = "sidecar";
+/// Lyrics source recorded for embedded tags.
+pub const SOURCE_EMBEDDED: &str = "embedded";
+/// Lyrics source recorded for LRCLIB answers.
+pub const SOURCE_LRCLIB: &str = "lrclib";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+/// One timed line of synced lyrics.
+pub struct LyricLine {
+    /// Start of the line from the beginning of the track, in milliseconds.
+    pub time_ms: u64,
+    /// Line text (empty for instrumental breaks).
+    pub text: String,
+}
+
+/// Parse LRC text into lines sorted by time, applying any `[offset:]` tag.
+///
+/// Lines without a timestamp and ID tags such as `[ar:]` are skipped; a line with several
+/// timestamps (`[00:12.00][01:40.00]chorus`) is repeated at each of them.
+pub fn parse_lrc(text: &str) -> Vec<LyricLine> {
+    let mut offset_ms = 0i64;
+    let mut lines = Vec::new();
+    for raw in text.lines() {
+        let mut rest = raw.trim();
+        let mut times = Vec::new();
+        while let Some(tag) = rest.strip_prefix('[') {
+            let Some((inner, after)) = tag.split_once(']') else {
+                break;
+            };
+            if let Some(value) = inner.strip_prefix("offset:") {
+                offset_ms = value.trim().parse().unwrap_or(0);
+            } else if let Some(time_ms) = parse_lrc_time(inner) {
+                times.push(time_ms);
+            }
+            rest = after;
+        }
+        for time_ms in times {
+            lines.push((time_ms, rest.trim().to_string()));
+        }
+    }
+    // A positive offset shows lyrics earlier.
+    let mut lines: Vec<LyricLine> = lines
+        .into_iter()
+        .map(|(time_ms, text)| LyricLine {
+            time_ms: (time_ms as i64 - offset_ms).max(0) as u64,
+            text,
+        })
+        .collect();
+    lines.sort_by_key(|line| line.time_ms);
+    lines
+}
+
+/// Parse an LRC timestamp (`mm:ss`, `mm:ss.xx` or `mm:ss.xxx`) into milliseconds.
+fn parse_lrc_time(tag: &str) -> Option<u64> {
+    let (minutes, seconds) = tag.split_once(':')?;
+    let minutes: u64 = minutes.trim().parse().ok()?;
+    let (whole, fraction) = seconds.split_once(['.', ':']).unwrap_or((seconds, ""));
+    let whole: u64 = whole.trim().parse().ok()?;
+    if whole >= 60 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let fraction_ms = match fraction.len() {
+        0 => 0,
+        1 => fraction.parse::<u64>().ok()? * 100,
+        2 => fraction.parse::<u64>().ok()? * 10,
+        _ => fraction[..3].parse::<u64>().ok()?,
+    };
+    Some((minutes * 60 + whole) * 1000 + fraction_ms)
+}
+
+/// Plain text of synced lyrics, one line per timed line.
+pub fn plain_from_lines(lines: &[LyricLine]) -> String {
+    lines
+        .iter()
+        .map(|line| line.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Split lyrics text into `(plain, synced)` depending on whether it carries LRC timestamps.
+fn classify(text: &str) -> (Option<String>, Option<String>) {
+    let text = text.trim();
+    if text.is_empty() {
+        (None, None)
+    } else if parse_lrc(text).is_empty() {
+        (Some(text.to_string()), None)
+    } else {
+        (None, Some(text.to_string()))
+    }
+}
+
+/// Lyrics found next to or inside a track file: a `.lrc` sidecar wins over the embedded tag.
+pub fn local_lyrics(path: &Path, embedded: Option<&str>) -> Option<TrackLyrics> {
+    let sidecar = path
+        .is_file()
+        .then(|| std::fs::read_to_string(path.with_extension("lrc")).ok())
+        .flatten()
+        .map(|text| (text, SOURCE_SIDECAR));
+    let (text, source) =
+        sidecar.or_else(|| embedded.map(|text| (text.to_string(), SOURCE_EMBEDDED)))?;
+    let (plain, synced) = classify(&text);
+    if plain.is_none() && synced.is_none() {
+        return None;
+    }
+    Some(TrackLyrics {
+        plain,
+        synced,
+        source: source.to_string(),
+        updated_at_ms: Some(now_ms()),
+    })
+}
+
+/// Online lyrics lookup against an LRCLIB-compatible API.
+pub struct LyricsClient {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// One LRCLIB search result.
+struct LrclibTrack {
+    #[serde(default)]
+    duration: Option<f64>,
+    #[serde(default)]
+    instrumental: bool,
+    #[serde(default)]
+    plain_lyrics: Option<String>,
+    #[serde(default)]
+    synced_lyrics: Option<String>,
+}
+
+impl LyricsClient {
+    /// Build a client for `base_url` (default: LRCLIB).
+    pub fn new(base_url: Option<&str>) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .user_agent(USER_AGENT)
+                .build()
+                .unwrap_or_default(),
+            base_url: base_url
+                .unwrap_or(DEFAULT_PROVIDER_URL)
+                .trim_end_matches('/')
+                .to_string(),
+        }
+    }
+
+    /// Look lyrics up by artist/title; `Ok(None)` means the provider has none for this track.
+    pub async fn fetch(
+        &self,
+        artist: &str,
+        title: &str,
+        album: Option<&str>,
+        duration_ms: Option<u64>,
+    ) -> Result<Option<TrackLyrics>> {
+        let mut query = vec![("artist_name", artist), ("track_name", title)];
+        if let Some(album) = album {
+            query.push(("album_name", album));
+        }
+        let resp = self
+            .client
+            .get(format!("{}/search", self.base_url))
+            .query(&query)
+            .send()
+            .await
+            .context("lyrics request failed")?;
+        if !resp.status().is_success() {
+            anyhow::bail!("lyrics request failed with status {}", resp.status());
+        }
+        let results: Vec<LrclibTrack> = resp.json().await.context("lyrics response parse")?;
+        let Some(track) = pick_match(&results, duration_ms) else {
+            return Ok(None);
+        };
+        let synced = track.synced_lyrics.clone().filter(|t| !t.trim().is_empty());
+        let plain = track.plain_lyrics.clone().filter(|t| !t.trim().is_empty());
+        Ok(Some(TrackLyrics {
+            plain,
+            synced,
+            source: SOURCE_LRCLIB.to_string(),
+            updated_at_ms: Some(now_ms()),
+        }))
+    }
+}
+
+/// Closest-length result with lyrics, ignoring ones whose length clearly differs.
+fn pick_match(results: &[LrclibTrack], duration_ms: Option<u64>) -> Option<&LrclibTrack> {
+    let distance = |track: &LrclibTrack| match (duration_ms, track.duration) {
+        (Some(ours), Some(theirs)) => ours.abs_diff((theirs * 1000.0) as u64),
+        _ => 0,
+    };
+    results
+        .iter()
+        .filter(|track| {
+            !track.instrumental && (track.plain_lyrics.is_some() || track.synced_lyrics.is_some())
+        })
+        .filter(|track| distance(track) <= DURATION_TOLERANCE_MS)
+        .min_by_key(|track| (track.synced_lyrics.is_none(), distance(track)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_lrc_handles_repeats_offsets_and_tags() {
+        let text = "[ar:Artist]\n[offset:+500]\n[00:12.30]First\n[00:05.00][01:02.5]Chorus\nno time\n[00:20]";
+        let lines: Vec<_> = parse_lrc(text)
+            .into_iter()
+            .map(|line| (line.time_ms, line.text))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (4500, "Chorus".to_string()),
+                (11800, "First".to_string()),
+                (19500, String::new()),
+                (62000, "Chorus".to_string()),
+            ]
+        );
+        assert!(parse_lrc("Just plain words\n[not a time]").is_empty());
+    }
+
+    #[test]
+    fn local_lyrics_prefers_sidecar_and_classifies_text() {
+        let dir = std::env::temp_dir().join(format!(
+            "audio-hub-lyrics-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let track = dir.join("01 Song.flac");
+        std::fs::write(&track, b"audio").unwrap();
+
+        let embedded = local_lyrics(&track, Some("la la\nla")).expect("embedded");
+        assert_eq!(embedded.source, SOURCE_EMBEDDED);
+        assert_eq!(embedded.plain.as_deref(), Some("la la\nla"));
+        assert_eq!(embedded.synced, None);
+        assert!(local_lyrics(&track, Some("  ")).is_none());
+
+        std::fs::write(dir.join("01 Song.lrc"), "[00:01.00]la").unwrap();
+        let sidecar = local_lyrics(&track, Some("la la")).expect("sidecar");
+        assert_eq!(sidecar.source, SOURCE_SIDECAR);
+        assert_eq!(sidecar.synced.as_deref(), Some("[00:01.00]la"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn pick_match_prefers_synced_results_of_matching_length() {
+        let track = |duration, plain: Option<&str>, synced: Option<&str>| LrclibTrack {
+            duration: Some(duration),
+            instrumental: false,
+            plain_lyrics: plain.map(str::to_string),
+            synced_lyrics: synced.map(str::to_string),
+        };
+        let results = [
+            track(200.0, Some("plain"), None),
+            track(320.0, Some("live"), Some("[00:01]live")),
+            track(201.0, Some("plain"), Some("[00:01]studio")),
+        ];
+        let picked = pick_match(&results, Some(200_500)).expect("match");
+        assert_eq!(picked.synced_lyrics.as_deref(), Some("[00:01]studio"));
+        assert!(pick_match(&results[1..2], Some(200_000)).is_none());
+    }
+}
@@ -2,44 +2,71 @@
 //!
 //! Scans the media library, manages output providers, and serves playback control APIs.
 
+mod acoustid;
+mod airplay;
 mod api;
+mod artist_images;
+mod audit;
+mod auth;
 mod bridge;
 mod bridge_device_streams;
 mod bridge_manager;
+mod bridge_network;
 mod bridge_transport;
 mod cast_v2;
+mod clock;
 mod config;
+mod cors;
 mod cover_art;
+mod cue_tracks;
 mod discovery;
+mod dlna;
 mod events;
+mod hls;
 mod library;
+mod library_scan;
+mod library_watcher;
 mod local_playback_sessions;
 mod local_player;
+mod loudness;
+mod lyrics;
 mod media_assets;
 mod metadata_db;
 mod metadata_service;
 mod models;
+mod mqtt;
 mod musicbrainz;
 mod openapi;
 mod output_controller;
+mod output_groups;
 mod output_providers;
+mod output_remote;
+mod pcm_track;
+mod play_history;
 mod playback_manager;
 mod playback_transport;
 mod queue_service;
+mod rate_limit;
 mod session_playback_manager;
 mod session_registry;
+mod snapcast;
 mod startup;
 mod state;
 mod status_store;
+mod stream_capture;
+mod stream_limits;
 mod stream_url;
 mod tag_writer;
+mod tempo_key;
 mod track_analysis;
+mod webhooks;
 
 use anyhow::Result;
+use audio_player::log_filter::LogFilterControl;
 use clap::Parser;
 use std::path::PathBuf;
-use tracing_subscriber::EnvFilter;
 use tracing_subscriber::prelude::*;
+use tracing_subscriber::{EnvFilter, reload};
 
 use crate::events::{LogBus, LogLayer};
 
@@ -78,16 +105,31 @@ pub(crate) struct Args {
     /// TLS private key path (PEM)
     #[arg(long)]
     tls_key: Option<PathBuf>,
+
+    /// Validate the config file, print all problems, and exit
+    #[arg(long)]
+    check_config: bool,
+
+    /// Fail startup on unknown config fields instead of warning
+    #[arg(long)]
+    strict_config: bool,
 }
 
 #[actix_web::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
+    if args.check_config {
+        let ok = startup::check_config(args.config.as_ref())?;
+        std::process::exit(if ok { 0 } else { 1 });
+    }
 
     let log_bus = std::sync::Arc::new(LogBus::new(500));
     let log_layer = LogLayer::new(log_bus.clone());
     let env_filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new("info,actix_web=info,audio_server=info"));
+    let initial_filter = env_filter.to_string();
+    let (env_filter, filter_handle) = reload::Layer::new(env_filter);
+    let log_filter = std::sync::Arc::new(LogFilterControl::new(filter_handle, initial_filter));
 
     tracing_subscriber::registry()
         .with(env_filter)
@@ -104,5 +146,5 @@ async fn main() -> Result<()> {
         "audio-hub-server starting"
     );
 
-    startup::run(args, log_bus).await
+    startup::run(args, log_bus, log_filter).await
 }
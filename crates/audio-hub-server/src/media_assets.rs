@@ -9,8 +9,11 @@ use reqwest::Client;
 use reqwest::Url;
 use tokio::net::lookup_host;
 
+use crate::clock::now_ms;
+
 const ASSETS_DIR: &str = ".audio-hub/assets";
-const MAX_IMAGE_BYTES: usize = 6_000_000;
+/// Largest image accepted for a media asset.
+pub(crate) const MAX_IMAGE_BYTES: usize = 6_000_000;
 
 /// Result of storing/fetching one remote media asset.
 pub struct StoredAsset {
@@ -78,15 +81,27 @@ impl MediaAssetStore {
             .unwrap_or("")
             .trim()
             .to_ascii_lowercase();
-        let ext = extension_for_mime(&content_type)
-            .ok_or_else(|| anyhow!("unsupported image content-type"))?;
-
         let bytes = resp.bytes().await.context("read image bytes")?;
+        self.store_image_bytes(owner_type, owner_id, kind, &content_type, &bytes, trimmed)
+    }
+
+    /// Validate and persist image bytes already fetched from `source_url`.
+    pub fn store_image_bytes(
+        &self,
+        owner_type: &str,
+        owner_id: i64,
+        kind: &str,
+        content_type: &str,
+        bytes: &[u8],
+        source_url: &str,
+    ) -> Result<StoredAsset> {
+        let ext = extension_for_mime(content_type)
+            .ok_or_else(|| anyhow!("unsupported image content-type"))?;
         if bytes.len() > MAX_IMAGE_BYTES {
             return Err(anyhow!("image exceeds {} bytes", MAX_IMAGE_BYTES));
         }
 
-        let checksum = hash_bytes(&bytes);
+        let checksum = hash_bytes(bytes);
         let relative = PathBuf::from(ASSETS_DIR)
             .join(owner_type)
             .join(owner_id.to_string())
@@ -97,14 +112,14 @@ impl MediaAssetStore {
                 .with_context(|| format!("create assets dir {:?}", parent))?;
         }
         if !full_path.exists() {
-            std::fs::write(&full_path, &bytes)
+            std::fs::write(&full_path, bytes)
                 .with_context(|| format!("write asset {:?}", full_path))?;
         }
 
         Ok(StoredAsset {
             local_path: relative.to_string_lossy().to_string(),
             checksum,
-            source_url: trimmed.to_string(),
+            source_url: source_url.to_string(),
             updated_at_ms: now_ms(),
         })
     }
@@ -279,14 +294,6 @@ fn hash_bytes(data: &[u8]) -> String {
     format!("{:016x}", hasher.finish())
 }
 
-/// Return current UNIX timestamp in milliseconds.
-fn now_ms() -> i64 {
-    std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map(|d| d.as_millis() as i64)
-        .unwrap_or(0)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,6 +2,7 @@
 //!
 //! Provides pooled connections and schema bootstrap.
 
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -10,9 +11,11 @@ use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::{Connection, OptionalExtension, params};
 
+use crate::auth::TokenRole;
+use crate::clock::now_ms;
 use crate::musicbrainz::MusicBrainzMatch;
 use uuid::Uuid;
-const SCHEMA_VERSION: i32 = 10;
+const SCHEMA_VERSION: i32 = 30;
 
 #[derive(Clone)]
 /// SQLite-backed metadata database handle with pooled connections.
@@ -42,8 +45,22 @@ pub struct TrackRecord {
     pub track_number: Option<u32>,
     /// Disc number.
     pub disc_number: Option<u32>,
+    /// Disc subtitle within a multi-disc release.
+    pub disc_title: Option<String>,
     /// Release year.
     pub year: Option<i32>,
+    /// Genres in tag order.
+    pub genres: Vec<String>,
+    /// Composer.
+    pub composer: Option<String>,
+    /// Conductor.
+    pub conductor: Option<String>,
+    /// Work the track belongs to.
+    pub work: Option<String>,
+    /// Movement name within the work.
+    pub movement: Option<String>,
+    /// Movement number within the work.
+    pub movement_number: Option<u32>,
     /// Duration in milliseconds.
     pub duration_ms: Option<u64>,
     /// Sample rate in Hz.
@@ -77,6 +94,19 @@ pub struct ArtistSummary {
     pub track_count: i64,
 }
 
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+/// Genre summary row returned by the genres endpoint.
+pub struct GenreSummary {
+    /// Genre id.
+    pub id: i64,
+    /// Genre name as first seen in tags.
+    pub name: String,
+    /// Albums with at least one track in this genre.
+    pub album_count: i64,
+    /// Tracks in this genre.
+    pub track_count: i64,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 /// Album summary row returned by list endpoints.
 pub struct AlbumSummary {
@@ -90,6 +120,8 @@ pub struct AlbumSummary {
     pub artist: Option<String>,
     /// Album artist id.
     pub artist_id: Option<i64>,
+    /// Composer shared by every track that names one, if they agree.
+    pub composer: Option<String>,
     /// Display year.
     pub year: Option<i32>,
     /// Original release year.
@@ -108,6 +140,12 @@ pub struct AlbumSummary {
     pub cover_art_url: Option<String>,
     /// True when album has at least one hi-res track.
     pub hi_res: bool,
+    /// True when the album is marked as a favorite.
+    pub favorite: bool,
+    /// User rating from 1 to 5, if rated.
+    pub rating: Option<u8>,
+    /// When the album was first added to the library (unix millis).
+    pub first_seen_at_ms: Option<i64>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
@@ -127,6 +165,18 @@ pub struct TrackSummary {
     pub track_number: Option<u32>,
     /// Disc number.
     pub disc_number: Option<u32>,
+    /// Disc subtitle within a multi-disc release.
+    pub disc_title: Option<String>,
+    /// Composer.
+    pub composer: Option<String>,
+    /// Conductor.
+    pub conductor: Option<String>,
+    /// Work the track belongs to.
+    pub work: Option<String>,
+    /// Movement name within the work.
+    pub movement: Option<String>,
+    /// Movement number within the work.
+    pub movement_number: Option<u32>,
     /// Duration in milliseconds.
     pub duration_ms: Option<u64>,
     /// Format label.
@@ -139,6 +189,814 @@ pub struct TrackSummary {
     pub mbid: Option<String>,
     /// Optional served cover URL.
     pub cover_art_url: Option<String>,
+    /// Integrated track loudness (LUFS), once measured.
+    pub loudness_lufs: Option<f64>,
+    /// Track true peak (dBTP), once measured.
+    pub true_peak_db: Option<f64>,
+    /// Integrated album loudness (LUFS), once measured.
+    pub album_loudness_lufs: Option<f64>,
+    /// Highest true peak on the album (dBTP), once measured.
+    pub album_true_peak_db: Option<f64>,
+    /// Estimated tempo in beats per minute, once analyzed.
+    pub bpm: Option<f64>,
+    /// Estimated musical key (e.g. `A minor`), once analyzed.
+    pub key: Option<String>,
+    /// True when the track is marked as a favorite.
+    pub favorite: bool,
+    /// User rating from 1 to 5, if rated.
+    pub rating: Option<u8>,
+    /// Number of counted plays (at least half the track heard).
+    pub play_count: i64,
+    /// Start of the last counted play (unix millis).
+    pub last_played_at_ms: Option<i64>,
+    /// When the track was first added to the library (unix millis).
+    pub first_seen_at_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+/// One listening-history row, newest first in `GET /history`.
+pub struct PlayHistoryEntry {
+    /// History row id.
+    pub id: i64,
+    /// Playback start (unix millis).
+    pub played_at_ms: i64,
+    /// Milliseconds heard before the track finished or was skipped.
+    pub played_ms: i64,
+    /// Share of the track heard (0-100), when its length is known.
+    pub completed_pct: Option<f64>,
+    /// Session that played the track.
+    pub session_id: String,
+    /// Output the session was bound to.
+    pub output_id: Option<String>,
+    /// The track.
+    pub track: TrackSummary,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+/// One audit-log row, newest first in `GET /audit`.
+pub struct AuditEntry {
+    /// Audit row id.
+    pub id: i64,
+    /// When the action happened (unix millis).
+    pub at_ms: i64,
+    /// API token name, or `mqtt` for MQTT commands; empty when authentication is off.
+    pub actor: Option<String>,
+    /// Client that owns the session acted on.
+    pub client_id: Option<String>,
+    /// Session acted on.
+    pub session_id: Option<String>,
+    /// Output acted on.
+    pub output_id: Option<String>,
+    /// Action name, e.g. `next`, `stop`, `output_select` or `metadata_edit`.
+    pub action: String,
+    /// HTTP method (`MQTT` for MQTT commands).
+    pub method: String,
+    /// Request path or MQTT topic.
+    pub path: String,
+    /// Response status code.
+    pub status: u16,
+}
+
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+/// Playback preferences for one output, sent to its bridge whenever it is selected.
+///
+/// Unset fields leave the bridge's own defaults in place.
+pub struct OutputPreferences {
+    /// Resampler preset: `fast`, `balanced` or `high`.
+    #[serde(default)]
+    pub resample_quality: Option<String>,
+    /// Exclusive device access; overrides `[outputs].exclusive` when set.
+    #[serde(default)]
+    pub exclusive: Option<bool>,
+    /// Preferred sample width: 16, 24 or 32 bits.
+    #[serde(default)]
+    pub bit_depth: Option<u16>,
+    /// Highest sample rate (Hz) to open the device at; faster tracks are resampled.
+    #[serde(default)]
+    pub max_sample_rate: Option<u32>,
+}
+
+impl OutputPreferences {
+    /// Whether no preference is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// A control action to store in the audit log.
+pub struct AuditRecord {
+    pub at_ms: i64,
+    pub actor: Option<String>,
+    pub client_id: Option<String>,
+    pub session_id: Option<String>,
+    pub output_id: Option<String>,
+    pub action: String,
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+}
+
+#[derive(Debug, Clone, Default)]
+/// Filters for [`MetadataDb::list_audit`]; unset fields match everything.
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub client_id: Option<String>,
+    pub session_id: Option<String>,
+    pub action: Option<String>,
+    /// Only rows at or after this time (unix millis).
+    pub since_ms: Option<i64>,
+    /// Only rows before this time (unix millis).
+    pub until_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+/// A finished play to store in the listening history.
+pub struct PlayRecord {
+    /// Track id.
+    pub track_id: i64,
+    /// Session that played the track.
+    pub session_id: String,
+    /// Output the session was bound to.
+    pub output_id: Option<String>,
+    /// Playback start (unix millis).
+    pub played_at_ms: i64,
+    /// Milliseconds heard.
+    pub played_ms: i64,
+    /// Share of the track heard (0-100), when its length is known.
+    pub completed_pct: Option<f64>,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+/// Favorite flag and star rating of a track or album.
+pub struct Rating {
+    /// True when marked as a favorite.
+    pub favorite: bool,
+    /// Rating from 1 to 5, if rated.
+    pub rating: Option<u8>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+/// Playlist summary row returned by the playlist endpoints.
+pub struct PlaylistSummary {
+    /// Playlist id.
+    pub id: i64,
+    /// Playlist name.
+    pub name: String,
+    /// Number of entries in the playlist.
+    pub track_count: i64,
+    /// Total duration in milliseconds of entries with a known duration.
+    pub duration_ms: u64,
+    /// Track whose cover was picked for the playlist, if set explicitly.
+    pub cover_track_id: Option<i64>,
+    /// Optional served cover URL (explicit cover track, else first entry with art).
+    pub cover_art_url: Option<String>,
+    /// Creation time (unix millis).
+    pub created_at_ms: i64,
+    /// Last modification time (unix millis).
+    pub updated_at_ms: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+/// Composer summary row returned by the composers endpoint.
+pub struct ComposerSummary {
+    /// Composer name as tagged.
+    pub name: String,
+    /// Albums with at least one track by this composer.
+    pub album_count: i64,
+    /// Tracks by this composer.
+    pub track_count: i64,
+    /// Distinct works tagged on those tracks.
+    pub work_count: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+/// One disc of an album, as shown in the album detail view.
+pub struct AlbumDisc {
+    /// Disc number (1 for tracks without one).
+    pub disc_number: u32,
+    /// Disc subtitle, when any track on the disc carries one.
+    pub title: Option<String>,
+    /// Tracks on this disc.
+    pub track_count: i64,
+    /// Total duration in milliseconds.
+    pub duration_ms: Option<u64>,
+}
+
+/// Current [`MetadataExport::version`].
+pub const METADATA_EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+/// User-authored metadata, portable to another hub (`GET /metadata/export`).
+pub struct MetadataExport {
+    /// Format version; imports of newer versions are rejected.
+    pub version: u32,
+    /// Export time (unix millis).
+    #[serde(default)]
+    pub exported_at_ms: i64,
+    /// Tracks with a rating, favorite, MBID or locked fields.
+    #[serde(default)]
+    pub tracks: Vec<TrackExport>,
+    /// Albums with a rating, favorite, MBID or hand-written notes.
+    #[serde(default)]
+    pub albums: Vec<AlbumExport>,
+    /// Artists with an MBID or hand-written bios.
+    #[serde(default)]
+    pub artists: Vec<ArtistExport>,
+    /// All playlists.
+    #[serde(default)]
+    pub playlists: Vec<PlaylistExport>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+/// How an exported entry finds its track: by library-relative path, then by content hash
+/// (so files that moved still match).
+pub struct TrackRef {
+    /// Path relative to the media root.
+    pub path: String,
+    /// Audio content hash, when computed.
+    #[serde(default)]
+    pub content_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+/// Exported per-track data.
+pub struct TrackExport {
+    pub track: TrackRef,
+    #[serde(default)]
+    pub favorite: bool,
+    /// Star rating (1-5).
+    #[serde(default)]
+    pub rating: Option<u8>,
+    /// MusicBrainz recording id.
+    #[serde(default)]
+    pub mbid: Option<String>,
+    /// Fields locked against album-wide tag updates.
+    #[serde(default)]
+    pub locked_fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+/// Exported per-album data.
+pub struct AlbumExport {
+    pub title: String,
+    /// Album artist name.
+    #[serde(default)]
+    pub artist: Option<String>,
+    /// One of the album's tracks; matched before title and artist.
+    #[serde(default)]
+    pub track: Option<TrackRef>,
+    #[serde(default)]
+    pub favorite: bool,
+    /// Star rating (1-5).
+    #[serde(default)]
+    pub rating: Option<u8>,
+    /// MusicBrainz release id.
+    #[serde(default)]
+    pub mbid: Option<String>,
+    /// Locked (hand-written) notes, one per language.
+    #[serde(default)]
+    pub notes: Vec<TextExport>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+/// Exported per-artist data.
+pub struct ArtistExport {
+    pub name: String,
+    /// MusicBrainz artist id.
+    #[serde(default)]
+    pub mbid: Option<String>,
+    /// Locked (hand-written) bios, one per language.
+    #[serde(default)]
+    pub bios: Vec<TextExport>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+/// Exported bio or album notes text.
+pub struct TextExport {
+    pub lang: String,
+    pub text: String,
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+/// Exported playlist with its entries in order.
+pub struct PlaylistExport {
+    pub name: String,
+    #[serde(default)]
+    pub cover: Option<TrackRef>,
+    #[serde(default)]
+    pub tracks: Vec<TrackRef>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, utoipa::ToSchema)]
+/// Outcome of merging a [`MetadataExport`].
+pub struct MetadataImportSummary {
+    /// Tracks updated.
+    pub tracks: usize,
+    /// Albums updated.
+    pub albums: usize,
+    /// Artists updated.
+    pub artists: usize,
+    /// Playlists created or replaced (same name).
+    pub playlists: usize,
+    /// Track, album and artist entries not found in this library.
+    pub unmatched: usize,
+    /// Playlist entries dropped because their track is not in this library.
+    pub unmatched_playlist_tracks: usize,
+}
+
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
+/// API token row; the secret itself is never stored, only its SHA-256 hash.
+pub struct ApiTokenInfo {
+    /// Token id.
+    pub id: i64,
+    /// Label chosen when the token was created.
+    pub name: String,
+    /// Permission level.
+    pub role: TokenRole,
+    /// Creation time (unix millis).
+    pub created_at_ms: i64,
+    /// Last successful use (unix millis), if any.
+    pub last_used_at_ms: Option<i64>,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+/// Album list ordering.
+pub enum AlbumSort {
+    /// By album artist, then year and title.
+    #[default]
+    Artist,
+    /// By the album's composer, then title; albums without one last.
+    Composer,
+    /// Highest rated first, favorites first among equal ratings, then by album artist.
+    Rating,
+    /// Most recently added first.
+    RecentlyAdded,
+    /// By title (sort title when tagged).
+    Title,
+    /// By original release year, then title; albums without a year last.
+    Year,
+    /// Shuffled on every request.
+    Random,
+}
+
+impl AlbumSort {
+    /// Direction the list runs in when none is requested.
+    pub fn default_direction(self) -> SortDirection {
+        match self {
+            Self::Rating | Self::RecentlyAdded => SortDirection::Desc,
+            _ => SortDirection::Asc,
+        }
+    }
+
+    /// ORDER BY keys, in the default direction.
+    fn order_keys(self) -> Vec<OrderKey> {
+        use OrderKey::{Asc, Desc, NullsLast};
+        let by_artist = [
+            NullsLast("ar.name"),
+            Asc("COALESCE(ar.sort_name, ar.name)"),
+            Asc("COALESCE(al.original_year, al.year, 9999)"),
+            Asc("COALESCE(al.sort_title, al.title)"),
+        ];
+        match self {
+            Self::Artist => by_artist.to_vec(),
+            Self::Composer => vec![
+                NullsLast(ALBUM_COMPOSER),
+                Asc(ALBUM_COMPOSER_NOCASE),
+                Asc("COALESCE(al.sort_title, al.title)"),
+                Asc("COALESCE(al.original_year, al.year, 9999)"),
+            ],
+            Self::Rating => [
+                Desc("COALESCE((SELECT rating FROM album_ratings WHERE album_id = al.id), 0)"),
+                Desc("COALESCE((SELECT favorite FROM album_ratings WHERE album_id = al.id), 0)"),
+            ]
+            .into_iter()
+            .chain(by_artist)
+            .collect(),
+            Self::RecentlyAdded => vec![
+                NullsLast("al.first_seen_at_ms"),
+                Desc("al.first_seen_at_ms"),
+                Desc("al.id"),
+            ],
+            Self::Title => vec![
+                Asc("COALESCE(al.sort_title, al.title) COLLATE NOCASE"),
+                Asc("al.id"),
+            ],
+            Self::Year => vec![
+                NullsLast("COALESCE(al.original_year, al.year)"),
+                Asc("COALESCE(al.original_year, al.year)"),
+                Asc("COALESCE(al.sort_title, al.title) COLLATE NOCASE"),
+                Asc("al.id"),
+            ],
+            Self::Random => vec![Asc("RANDOM()")],
+        }
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+/// Track list ordering.
+pub enum TrackSort {
+    /// By album, then disc and track number, so box sets play disc by disc.
+    #[default]
+    Album,
+    /// Grouped by composer and work, then movement number.
+    Work,
+    /// Highest rated first, favorites first among equal ratings, then by album.
+    Rating,
+    /// Most recently added first.
+    RecentlyAdded,
+    /// By title (file name when untagged).
+    Title,
+    /// By track artist, then album.
+    Artist,
+    /// By the album's original release year, then album; tracks without a year last.
+    Year,
+    /// Shuffled on every request.
+    Random,
+}
+
+impl TrackSort {
+    /// Direction the list runs in when none is requested.
+    pub fn default_direction(self) -> SortDirection {
+        match self {
+            Self::Rating | Self::RecentlyAdded => SortDirection::Desc,
+            _ => SortDirection::Asc,
+        }
+    }
+
+    /// ORDER BY keys, in the default direction.
+    fn order_keys(self) -> Vec<OrderKey> {
+        use OrderKey::{Asc, Desc, NullsLast};
+        let by_album = [
+            NullsLast("al.id"),
+            Asc("COALESCE(al.sort_title, al.title) COLLATE NOCASE"),
+            Asc("al.id"),
+            Asc("COALESCE(t.disc_number, 0)"),
+            Asc("COALESCE(t.track_number, 0)"),
+            Asc("t.file_name"),
+        ];
+        match self {
+            Self::Album => by_album.to_vec(),
+            Self::Work => vec![
+                NullsLast("t.composer"),
+                Asc("t.composer COLLATE NOCASE"),
+                NullsLast("t.work"),
+                Asc("t.work COLLATE NOCASE"),
+                Asc("COALESCE(t.movement_number, 0)"),
+                Asc("COALESCE(t.disc_number, 0)"),
+                Asc("COALESCE(t.track_number, 0)"),
+                Asc("t.file_name"),
+            ],
+            Self::Rating => [
+                Desc("COALESCE(tr.rating, 0)"),
+                Desc("COALESCE(tr.favorite, 0)"),
+            ]
+            .into_iter()
+            .chain(by_album)
+            .collect(),
+            Self::RecentlyAdded => vec![
+                NullsLast("t.first_seen_at_ms"),
+                Desc("t.first_seen_at_ms"),
+                Desc("t.id"),
+            ],
+            Self::Title => vec![
+                Asc("COALESCE(t.title, t.file_name) COLLATE NOCASE"),
+                Asc("t.id"),
+            ],
+            Self::Artist => [
+                NullsLast("ar.name"),
+                Asc("COALESCE(ar.sort_name, ar.name) COLLATE NOCASE"),
+            ]
+            .into_iter()
+            .chain(by_album)
+            .collect(),
+            Self::Year => [
+                NullsLast("COALESCE(al.original_year, al.year)"),
+                Asc("COALESCE(al.original_year, al.year)"),
+            ]
+            .into_iter()
+            .chain(by_album)
+            .collect(),
+            Self::Random => vec![Asc("RANDOM()")],
+        }
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+/// List sort direction.
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+/// One ORDER BY key. Keys are fixed SQL fragments; request input only picks among them.
+#[derive(Debug, Clone, Copy)]
+enum OrderKey {
+    Asc(&'static str),
+    Desc(&'static str),
+    /// Rows where the expression is NULL go last, whatever the direction.
+    NullsLast(&'static str),
+}
+
+/// Build an ORDER BY clause, reversing every key except null placement when
+/// `direction` differs from the sort's default.
+fn order_by_clause(keys: &[OrderKey], reverse: bool) -> String {
+    keys.iter()
+        .map(|key| match (key, reverse) {
+            (OrderKey::NullsLast(expr), _) => format!("{expr} IS NULL"),
+            (OrderKey::Asc(expr), false) | (OrderKey::Desc(expr), true) => format!("{expr} ASC"),
+            (OrderKey::Asc(expr), true) | (OrderKey::Desc(expr), false) => format!("{expr} DESC"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Single album composer, as `album_composer` in [`ALBUM_SUMMARY_COLUMNS`]. ORDER BY keys
+/// are also selected for cursors, where SQLite cannot refer to select-list aliases.
+const ALBUM_COMPOSER: &str = "CASE WHEN COUNT(DISTINCT t.composer) = 1 THEN MAX(t.composer) END";
+/// [`ALBUM_COMPOSER`], compared case-insensitively.
+const ALBUM_COMPOSER_NOCASE: &str =
+    "CASE WHEN COUNT(DISTINCT t.composer) = 1 THEN MAX(t.composer) END COLLATE NOCASE";
+
+/// Where a list page starts.
+#[derive(Debug, Clone, Copy)]
+pub enum PageStart<'a> {
+    /// Skip this many rows.
+    Offset(i64),
+    /// Continue after the row a previous page's `next_cursor` points at.
+    After(&'a ListCursor),
+}
+
+/// Position after the last row of a list page. Rows added or removed before it (by a
+/// rescan, say) do not shift the next page the way an offset would.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ListCursor {
+    /// List and ordering the cursor was issued for.
+    scope: String,
+    /// ORDER BY key values of the last row.
+    keys: Vec<serde_json::Value>,
+}
+
+/// One page of a list.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Where the next page starts; `None` on the last page and for random order.
+    pub next_cursor: Option<ListCursor>,
+}
+
+/// ORDER BY keys plus a final unique `id_column` key, so every row has a distinct position.
+fn keyset_keys(mut keys: Vec<OrderKey>, id_column: &'static str) -> Vec<OrderKey> {
+    keys.push(OrderKey::Asc(id_column));
+    keys
+}
+
+/// SQL for one key's value, as compared and selected for cursors.
+fn key_expr(key: &OrderKey) -> String {
+    match key {
+        OrderKey::Asc(expr) | OrderKey::Desc(expr) => (*expr).to_string(),
+        OrderKey::NullsLast(expr) => format!("({expr} IS NULL)"),
+    }
+}
+
+/// Extra select columns holding the key values of each row.
+fn key_columns(keys: &[OrderKey]) -> String {
+    keys.iter()
+        .map(|key| format!(", {}", key_expr(key)))
+        .collect()
+}
+
+/// Condition matching rows after the cursor row, whose key values are bound from
+/// `?first_param` on. NULL sorts first ascending, as in SQLite.
+fn after_cursor_clause(keys: &[OrderKey], reverse: bool, first_param: usize) -> String {
+    let mut alternatives = Vec::with_capacity(keys.len());
+    for (i, key) in keys.iter().enumerate() {
+        let mut terms: Vec<String> = keys[..i]
+            .iter()
+            .enumerate()
+            .map(|(j, prev)| format!("{} IS ?{}", key_expr(prev), first_param + j))
+            .collect();
+        let expr = key_expr(key);
+        let param = first_param + i;
+        let descending = matches!(
+            (key, reverse),
+            (OrderKey::Desc(_), false) | (OrderKey::Asc(_), true)
+        );
+        terms.push(if descending {
+            format!("({expr} < ?{param} OR ({expr} IS NULL AND ?{param} IS NOT NULL))")
+        } else {
+            format!("({expr} > ?{param} OR ({expr} IS NOT NULL AND ?{param} IS NULL))")
+        });
+        alternatives.push(format!("({})", terms.join(" AND ")));
+    }
+    alternatives.join(" OR ")
+}
+
+/// Turn a cursor into SQL parameters; `None` when it was issued for another list or order.
+fn cursor_params(
+    cursor: &ListCursor,
+    scope: &str,
+    key_count: usize,
+) -> Option<Vec<rusqlite::types::Value>> {
+    use rusqlite::types::Value;
+    if cursor.scope != scope || cursor.keys.len() != key_count {
+        return None;
+    }
+    cursor
+        .keys
+        .iter()
+        .map(|key| match key {
+            serde_json::Value::Null => Some(Value::Null),
+            serde_json::Value::Number(n) => n
+                .as_i64()
+                .map(Value::Integer)
+                .or_else(|| n.as_f64().map(Value::Real)),
+            serde_json::Value::String(text) => Some(Value::Text(text.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Read the key columns selected by [`key_columns`], starting at column `first`.
+fn cursor_keys(
+    row: &rusqlite::Row<'_>,
+    first: usize,
+    key_count: usize,
+) -> rusqlite::Result<Vec<serde_json::Value>> {
+    use rusqlite::types::Value;
+    (first..first + key_count)
+        .map(|idx| {
+            Ok(match row.get::<_, Value>(idx)? {
+                Value::Integer(n) => n.into(),
+                Value::Real(n) => n.into(),
+                Value::Text(text) => text.into(),
+                Value::Null | Value::Blob(_) => serde_json::Value::Null,
+            })
+        })
+        .collect()
+}
+
+/// Build a page from rows carrying their cursor keys; a full page gets a `next_cursor`.
+fn into_page<T>(
+    rows: Vec<(T, Vec<serde_json::Value>)>,
+    limit: i64,
+    scope: Option<String>,
+) -> Page<T> {
+    let full = rows.len() as i64 >= limit;
+    let mut items = Vec::with_capacity(rows.len());
+    let mut last_keys = None;
+    for (item, keys) in rows {
+        items.push(item);
+        last_keys = Some(keys);
+    }
+    let next_cursor = match (scope, last_keys) {
+        (Some(scope), Some(keys)) if full => Some(ListCursor { scope, keys }),
+        _ => None,
+    };
+    Page { items, next_cursor }
+}
+
+/// Filters and ordering for [`MetadataDb::list_albums`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AlbumFilter<'a> {
+    /// Only albums by this album artist.
+    pub artist_id: Option<i64>,
+    /// Only albums with a track in this genre.
+    pub genre_id: Option<i64>,
+    /// Only albums with a track by this composer (case-insensitive).
+    pub composer: Option<&'a str>,
+    /// Case-insensitive title substring.
+    pub search: Option<&'a str>,
+    /// Only favorite (`true`) or non-favorite (`false`) albums.
+    pub favorite: Option<bool>,
+    /// Only albums rated at least this many stars.
+    pub min_rating: Option<u8>,
+    /// Result order.
+    pub sort: AlbumSort,
+    /// Result direction; `None` uses the sort's default.
+    pub direction: Option<SortDirection>,
+}
+
+/// Filters and ordering for [`MetadataDb::list_tracks`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrackFilter<'a> {
+    /// Only tracks on this album.
+    pub album_id: Option<i64>,
+    /// Only tracks by this artist.
+    pub artist_id: Option<i64>,
+    /// Only tracks in this genre.
+    pub genre_id: Option<i64>,
+    /// Only tracks by this composer (case-insensitive).
+    pub composer: Option<&'a str>,
+    /// Case-insensitive title substring.
+    pub search: Option<&'a str>,
+    /// Only tracks analyzed at this tempo or faster.
+    pub min_bpm: Option<f64>,
+    /// Only tracks analyzed at this tempo or slower.
+    pub max_bpm: Option<f64>,
+    /// Only tracks analyzed in this key (case-insensitive, e.g. `A minor`).
+    pub key: Option<&'a str>,
+    /// Only favorite (`true`) or non-favorite (`false`) tracks.
+    pub favorite: Option<bool>,
+    /// Only tracks rated at least this many stars.
+    pub min_rating: Option<u8>,
+    /// Result order.
+    pub sort: TrackSort,
+    /// Result direction; `None` uses the sort's default.
+    pub direction: Option<SortDirection>,
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+/// Kind of library item a search hit refers to.
+pub enum SearchKind {
+    Artist,
+    Album,
+    Track,
+}
+
+impl SearchKind {
+    /// Kind code stored in the low bits of a `search_index` rowid.
+    fn code(self) -> i64 {
+        match self {
+            SearchKind::Artist => 0,
+            SearchKind::Album => 1,
+            SearchKind::Track => 2,
+        }
+    }
+
+    fn from_code(code: i64) -> Option<Self> {
+        match code {
+            0 => Some(SearchKind::Artist),
+            1 => Some(SearchKind::Album),
+            2 => Some(SearchKind::Track),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+/// One ranked full-text search hit.
+pub struct SearchHit {
+    /// Item kind; `id` is an artist, album or track id accordingly.
+    pub kind: SearchKind,
+    /// Item id.
+    pub id: i64,
+    /// Artist name, album title, or track title (file name when untitled).
+    pub name: String,
+    /// Artist of an album or track.
+    pub artist: Option<String>,
+    /// Artist id of an album or track.
+    pub artist_id: Option<i64>,
+    /// Album of a track.
+    pub album: Option<String>,
+    /// Album id of a track.
+    pub album_id: Option<i64>,
+    /// Optional served cover URL for albums and tracks.
+    pub cover_art_url: Option<String>,
+    /// Relevance; higher is better. Only comparable within one response.
+    pub score: f64,
 }
 
 #[derive(Debug, Clone)]
@@ -211,6 +1069,159 @@ pub struct MusicBrainzCandidate {
     pub no_match_key: Option<String>,
 }
 
+#[derive(Debug, Clone)]
+/// Untagged track candidate for AcoustID fingerprint matching.
+pub struct AcoustIdCandidate {
+    /// Track id.
+    pub track_id: i64,
+    /// Track path.
+    pub path: String,
+    /// Stored Chromaprint fingerprint, if computed before.
+    pub fingerprint: Option<String>,
+    /// Duration in seconds the fingerprint was computed for.
+    pub duration_s: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+/// Recording matched by AcoustID.
+pub struct AcoustIdMatch {
+    /// AcoustID track id.
+    pub acoustid_id: String,
+    /// MusicBrainz recording MBID.
+    pub recording_mbid: String,
+    /// Recording title.
+    pub title: Option<String>,
+    /// Primary artist name.
+    pub artist: Option<String>,
+    /// Primary artist MBID.
+    pub artist_mbid: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+/// Track candidate for the lossy-source check job.
+pub struct LossyCheckCandidate {
+    /// Track id.
+    pub track_id: i64,
+    /// Track path.
+    pub path: String,
+    /// File mtime (unix ms) the check applies to.
+    pub mtime_ms: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+/// Outcome of one lossy-source check.
+pub struct LossyCheckResult {
+    /// Detected spectral cutoff (Hz).
+    pub cutoff_hz: Option<f32>,
+    /// Level drop across the cutoff (dB).
+    pub cutoff_drop_db: Option<f32>,
+    /// Likelihood (0..1) the file was transcoded from a lossy source.
+    pub confidence: Option<f32>,
+    /// Analysis error, when the file could not be checked.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+/// Track candidate for the loudness job.
+pub struct LoudnessCandidate {
+    /// Track id.
+    pub track_id: i64,
+    /// Track path.
+    pub path: String,
+    /// File mtime (unix ms) the measurement applies to.
+    pub mtime_ms: i64,
+    /// Album the track belongs to; album tracks are measured together.
+    pub album_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default)]
+/// Outcome of one loudness measurement.
+pub struct LoudnessResult {
+    /// Integrated track loudness (LUFS).
+    pub integrated_lufs: Option<f64>,
+    /// Track true peak (dBTP).
+    pub true_peak_db: Option<f64>,
+    /// Integrated loudness of the whole album (LUFS).
+    pub album_lufs: Option<f64>,
+    /// Highest true peak on the album (dBTP).
+    pub album_peak_db: Option<f64>,
+    /// Analysis error, when the file could not be measured.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+/// Track candidate for the tempo/key job.
+pub struct TempoKeyCandidate {
+    /// Track id.
+    pub track_id: i64,
+    /// Track path.
+    pub path: String,
+    /// File mtime (unix ms) the analysis applies to.
+    pub mtime_ms: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+/// Outcome of one tempo/key analysis.
+pub struct TempoKeyResult {
+    /// Estimated beats per minute.
+    pub bpm: Option<f64>,
+    /// Estimated key, e.g. `A minor`.
+    pub key: Option<String>,
+    /// Analysis error, when the file could not be analyzed.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+/// Track row in the lossy-source report.
+pub struct LossyReportEntry {
+    /// Track id.
+    pub track_id: i64,
+    /// Filename for display.
+    pub file_name: String,
+    /// Track title.
+    pub title: Option<String>,
+    /// Track artist.
+    pub artist: Option<String>,
+    /// Album title.
+    pub album: Option<String>,
+    /// Sample rate in Hz.
+    pub sample_rate: Option<u32>,
+    /// Bit depth.
+    pub bit_depth: Option<u32>,
+    /// Detected spectral cutoff (Hz).
+    pub cutoff_hz: Option<f32>,
+    /// Level drop across the cutoff (dB).
+    pub cutoff_drop_db: Option<f32>,
+    /// Likelihood (0..1) the file was transcoded from a lossy source.
+    pub confidence: f32,
+    /// Check time (unix ms).
+    pub checked_at_ms: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Stored lyrics for one track.
+///
+/// A row with neither `plain` nor `synced` records that the online provider had none.
+pub struct TrackLyrics {
+    /// Unsynced lyrics text.
+    pub plain: Option<String>,
+    /// Synced lyrics in LRC format.
+    pub synced: Option<String>,
+    /// Where the lyrics came from (`sidecar`, `embedded` or `lrclib`).
+    pub source: String,
+    /// Last update time (unix ms).
+    pub updated_at_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+/// Artist candidate for image enrichment jobs.
+pub struct ArtistImageCandidate {
+    /// Artist id.
+    pub artist_id: i64,
+    /// MusicBrainz artist MBID.
+    pub mbid: String,
+}
+
 #[derive(Debug, Clone)]
 /// Album candidate for cover art enrichment jobs.
 pub struct CoverArtCandidate {
@@ -233,26 +1244,288 @@ fn map_artist_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ArtistSummary> {
     })
 }
 
-/// Map one SQL row into [`TextEntry`].
-fn map_text_entry_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<TextEntry> {
-    let locked: i64 = row.get(3)?;
-    Ok(TextEntry {
-        lang: row.get(0)?,
-        text: row.get(1)?,
-        source: row.get(2)?,
-        locked: locked != 0,
-        updated_at_ms: row.get(4)?,
+/// Playlist summary columns, in [`map_playlist_row`] order; `p` is the playlist.
+const PLAYLIST_SUMMARY_COLUMNS: &str = r#"
+    p.id, p.name,
+    (SELECT COUNT(*) FROM playlist_tracks pt WHERE pt.playlist_id = p.id),
+    (SELECT COALESCE(SUM(t.duration_ms), 0)
+     FROM playlist_tracks pt JOIN tracks t ON t.id = pt.track_id
+     WHERE pt.playlist_id = p.id),
+    p.cover_track_id,
+    (SELECT t.id
+     FROM tracks t JOIN albums al ON al.id = t.album_id
+     LEFT JOIN playlist_tracks pt ON pt.track_id = t.id AND pt.playlist_id = p.id
+     WHERE (t.id = p.cover_track_id OR (p.cover_track_id IS NULL AND pt.track_id IS NOT NULL))
+       AND TRIM(COALESCE(al.cover_art_path, '')) != ''
+     ORDER BY pt.position
+     LIMIT 1),
+    p.created_at_ms, p.updated_at_ms
+"#;
+
+/// Map one [`PLAYLIST_SUMMARY_COLUMNS`] row into [`PlaylistSummary`].
+fn map_playlist_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<PlaylistSummary> {
+    let cover_source: Option<i64> = row.get(5)?;
+    Ok(PlaylistSummary {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        track_count: row.get(2)?,
+        duration_ms: row.get::<_, i64>(3)?.max(0) as u64,
+        cover_track_id: row.get(4)?,
+        cover_art_url: cover_source.map(|track_id| format!("/tracks/{track_id}/cover")),
+        created_at_ms: row.get(6)?,
+        updated_at_ms: row.get(7)?,
     })
 }
 
-/// Map one SQL row into [`MediaAssetRecord`].
-fn map_media_asset_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<MediaAssetRecord> {
-    Ok(MediaAssetRecord {
+/// Map an `id, name, role, created_at_ms, last_used_at_ms` row to [`ApiTokenInfo`].
+fn api_token_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ApiTokenInfo> {
+    Ok(ApiTokenInfo {
         id: row.get(0)?,
-        owner_type: row.get(1)?,
-        owner_id: row.get(2)?,
-        kind: row.get(3)?,
-        local_path: row.get(4)?,
+        name: row.get(1)?,
+        role: TokenRole::from_db(&row.get::<_, String>(2)?),
+        created_at_ms: row.get(3)?,
+        last_used_at_ms: row.get(4)?,
+    })
+}
+
+/// Apply a partial favorite/rating update to `table` and return the new state.
+///
+/// Rows that end up neither favorite nor rated are removed.
+fn update_rating(
+    conn: &Connection,
+    table: &str,
+    id_column: &str,
+    id: i64,
+    favorite: Option<bool>,
+    rating: Option<Option<u8>>,
+) -> Result<Rating> {
+    conn.execute(
+        &format!(
+            r#"
+            INSERT INTO {table} ({id_column}, favorite, rating, updated_at_ms)
+            VALUES (?1, COALESCE(?2, 0), ?4, ?5)
+            ON CONFLICT({id_column}) DO UPDATE SET
+                favorite = COALESCE(?2, favorite),
+                rating = CASE WHEN ?3 THEN ?4 ELSE rating END,
+                updated_at_ms = ?5
+            "#
+        ),
+        params![id, favorite, rating.is_some(), rating.flatten(), now_ms()],
+    )
+    .with_context(|| format!("update {table}"))?;
+    let current = conn.query_row(
+        &format!("SELECT favorite, rating FROM {table} WHERE {id_column} = ?1"),
+        params![id],
+        |row| {
+            Ok(Rating {
+                favorite: row.get::<_, i64>(0)? != 0,
+                rating: row.get::<_, Option<i64>>(1)?.map(|v| v as u8),
+            })
+        },
+    )?;
+    if !current.favorite && current.rating.is_none() {
+        conn.execute(
+            &format!("DELETE FROM {table} WHERE {id_column} = ?1"),
+            params![id],
+        )?;
+    }
+    Ok(current)
+}
+
+/// Locked (hand-written) bios or notes by owner id.
+fn locked_texts(
+    conn: &Connection,
+    table: &str,
+    owner_column: &str,
+) -> Result<HashMap<i64, Vec<TextExport>>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {owner_column}, lang, text, source FROM {table} WHERE locked = 1 ORDER BY {owner_column}, lang"
+    ))?;
+    let mut texts: HashMap<i64, Vec<TextExport>> = HashMap::new();
+    for row in stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            TextExport {
+                lang: row.get(1)?,
+                text: row.get(2)?,
+                source: row.get(3)?,
+            },
+        ))
+    })? {
+        let (owner_id, text) = row?;
+        texts.entry(owner_id).or_default().push(text);
+    }
+    Ok(texts)
+}
+
+/// Apply an imported favorite/rating; entries without either leave local values alone.
+fn import_rating(
+    conn: &Connection,
+    table: &str,
+    id_column: &str,
+    id: i64,
+    favorite: bool,
+    rating: Option<u8>,
+) -> Result<()> {
+    let rating = rating.filter(|stars| (1..=5).contains(stars));
+    if favorite || rating.is_some() {
+        update_rating(conn, table, id_column, id, Some(favorite), Some(rating))?;
+    }
+    Ok(())
+}
+
+/// Write playlist entries in order, starting at position 0.
+fn insert_playlist_tracks(conn: &Connection, playlist_id: i64, track_ids: &[i64]) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO playlist_tracks (playlist_id, position, track_id) VALUES (?1, ?2, ?3)",
+    )?;
+    for (position, track_id) in track_ids.iter().enumerate() {
+        stmt.execute(params![playlist_id, position as i64, track_id])
+            .context("insert playlist track")?;
+    }
+    Ok(())
+}
+
+/// Track summary columns, in [`map_track_summary_row`] order; join with [`TRACK_SUMMARY_JOINS`].
+const TRACK_SUMMARY_COLUMNS: &str = r#"
+    t.id, t.file_name, t.title, ar.name, al.title,
+    t.track_number, t.disc_number, t.duration_ms, t.format,
+    t.sample_rate, t.bit_depth, t.mbid, al.cover_art_path,
+    t.composer, t.conductor, t.work, t.movement, t.movement_number,
+    t.disc_title, lo.integrated_lufs, lo.true_peak_db, lo.album_lufs,
+    lo.album_peak_db, tk.bpm, tk.musical_key, COALESCE(tr.favorite, 0), tr.rating,
+    COALESCE(ps.play_count, 0), ps.last_played_at_ms, t.first_seen_at_ms
+"#;
+
+/// Number of [`TRACK_SUMMARY_COLUMNS`]; extra columns selected after them start here.
+const TRACK_SUMMARY_COLUMN_COUNT: usize = 30;
+
+/// Plays count towards `play_count` once this share of the track was heard.
+const COUNTED_PLAY_MIN_PCT: f64 = 50.0;
+/// Plays of tracks with unknown length count after this many milliseconds.
+const COUNTED_PLAY_MIN_MS_UNKNOWN_LENGTH: i64 = 30_000;
+
+/// Joins required by [`TRACK_SUMMARY_COLUMNS`] on top of `tracks t`.
+const TRACK_SUMMARY_JOINS: &str = r#"
+    LEFT JOIN artists ar ON ar.id = t.artist_id
+    LEFT JOIN albums al ON al.id = t.album_id
+    LEFT JOIN track_loudness lo
+        ON lo.track_id = t.id AND lo.mtime_ms = COALESCE(t.mtime_ms, 0)
+    LEFT JOIN track_tempo_key tk
+        ON tk.track_id = t.id AND tk.mtime_ms = COALESCE(t.mtime_ms, 0)
+    LEFT JOIN track_ratings tr ON tr.track_id = t.id
+    LEFT JOIN track_play_stats ps ON ps.track_id = t.id
+"#;
+
+/// Map one [`TRACK_SUMMARY_COLUMNS`] row into [`TrackSummary`].
+fn map_track_summary_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<TrackSummary> {
+    let track_id: i64 = row.get(0)?;
+    let cover_path: Option<String> = row.get(12)?;
+    let cover_art_url = cover_path
+        .as_deref()
+        .filter(|value| !value.trim().is_empty())
+        .map(|_| format!("/tracks/{}/cover", track_id));
+    Ok(TrackSummary {
+        id: track_id,
+        file_name: row.get(1)?,
+        title: row.get(2)?,
+        artist: row.get(3)?,
+        album: row.get(4)?,
+        track_number: row.get::<_, Option<i64>>(5)?.map(|v| v as u32),
+        disc_number: row.get::<_, Option<i64>>(6)?.map(|v| v as u32),
+        disc_title: row.get(18)?,
+        composer: row.get(13)?,
+        conductor: row.get(14)?,
+        work: row.get(15)?,
+        movement: row.get(16)?,
+        movement_number: row.get::<_, Option<i64>>(17)?.map(|v| v as u32),
+        duration_ms: row.get::<_, Option<i64>>(7)?.map(|v| v as u64),
+        format: row.get(8)?,
+        sample_rate: row.get::<_, Option<i64>>(9)?.map(|v| v as u32),
+        bit_depth: row.get::<_, Option<i64>>(10)?.map(|v| v as u32),
+        mbid: row.get(11)?,
+        cover_art_url,
+        loudness_lufs: row.get(19)?,
+        true_peak_db: row.get(20)?,
+        album_loudness_lufs: row.get(21)?,
+        album_true_peak_db: row.get(22)?,
+        bpm: row.get(23)?,
+        key: row.get(24)?,
+        favorite: row.get::<_, i64>(25)? != 0,
+        rating: row.get::<_, Option<i64>>(26)?.map(|v| v as u8),
+        play_count: row.get(27)?,
+        last_played_at_ms: row.get(28)?,
+        first_seen_at_ms: row.get(29)?,
+    })
+}
+
+/// Album summary columns, in [`map_album_row`] order; `al`, `ar` and `t` must be joined.
+const ALBUM_SUMMARY_COLUMNS: &str = r#"
+    al.id, al.uuid, al.title, ar.name, al.artist_id, al.year,
+    al.original_year, al.edition_year, al.edition_label, al.mbid,
+    COUNT(t.id) AS track_count, al.cover_art_path,
+    MAX(t.bit_depth) AS max_bit_depth,
+    CASE WHEN COUNT(DISTINCT t.composer) = 1 THEN MAX(t.composer) END AS album_composer,
+    COALESCE((SELECT favorite FROM album_ratings WHERE album_id = al.id), 0) AS album_favorite,
+    (SELECT rating FROM album_ratings WHERE album_id = al.id) AS album_rating,
+    al.first_seen_at_ms
+"#;
+
+/// Number of [`ALBUM_SUMMARY_COLUMNS`]; extra columns selected after them start here.
+const ALBUM_SUMMARY_COLUMN_COUNT: usize = 17;
+
+/// Map one [`ALBUM_SUMMARY_COLUMNS`] row into [`AlbumSummary`].
+fn map_album_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<AlbumSummary> {
+    let album_id: i64 = row.get(0)?;
+    let cover_path: Option<String> = row.get(11)?;
+    let max_bit_depth: Option<i64> = row.get(12)?;
+    let hi_res = max_bit_depth.unwrap_or(0) >= 24;
+    let cover_art_url = cover_path
+        .as_deref()
+        .filter(|value| !value.trim().is_empty())
+        .map(|_| format!("/albums/{}/cover", album_id));
+    Ok(AlbumSummary {
+        id: album_id,
+        uuid: row.get(1)?,
+        title: row.get(2)?,
+        artist: row.get(3)?,
+        artist_id: row.get(4)?,
+        composer: row.get(13)?,
+        year: row.get(5)?,
+        original_year: row.get(6)?,
+        edition_year: row.get(7)?,
+        edition_label: row.get(8)?,
+        mbid: row.get(9)?,
+        track_count: row.get(10)?,
+        cover_art_path: cover_path,
+        cover_art_url,
+        hi_res,
+        favorite: row.get::<_, i64>(14)? != 0,
+        rating: row.get::<_, Option<i64>>(15)?.map(|v| v as u8),
+        first_seen_at_ms: row.get(16)?,
+    })
+}
+
+/// Map one SQL row into [`TextEntry`].
+fn map_text_entry_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<TextEntry> {
+    let locked: i64 = row.get(3)?;
+    Ok(TextEntry {
+        lang: row.get(0)?,
+        text: row.get(1)?,
+        source: row.get(2)?,
+        locked: locked != 0,
+        updated_at_ms: row.get(4)?,
+    })
+}
+
+/// Map one SQL row into [`MediaAssetRecord`].
+fn map_media_asset_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<MediaAssetRecord> {
+    Ok(MediaAssetRecord {
+        id: row.get(0)?,
+        owner_type: row.get(1)?,
+        owner_id: row.get(2)?,
+        kind: row.get(3)?,
+        local_path: row.get(4)?,
         checksum: row.get(5)?,
         source_url: row.get(6)?,
         updated_at_ms: row.get(7)?,
@@ -518,8 +1791,11 @@ impl MetadataDb {
             r#"
             INSERT INTO tracks (
                 path, file_name, title, artist_id, album_id, track_number, disc_number,
-                duration_ms, sample_rate, bit_depth, format, mtime_ms, size_bytes, mb_no_match_key
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+                duration_ms, sample_rate, bit_depth, format, mtime_ms, size_bytes, mb_no_match_key,
+                composer, conductor, work, movement, movement_number, disc_title,
+                first_seen_at_ms
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14,
+                      ?15, ?16, ?17, ?18, ?19, ?20, ?21)
             ON CONFLICT(path) DO UPDATE SET
                 file_name = excluded.file_name,
                 title = excluded.title,
@@ -531,9 +1807,22 @@ impl MetadataDb {
                 sample_rate = excluded.sample_rate,
                 bit_depth = excluded.bit_depth,
                 format = excluded.format,
+                acoustid_fingerprint = CASE
+                    WHEN mtime_ms = excluded.mtime_ms AND size_bytes = excluded.size_bytes
+                    THEN acoustid_fingerprint END,
+                acoustid_duration = CASE
+                    WHEN mtime_ms = excluded.mtime_ms AND size_bytes = excluded.size_bytes
+                    THEN acoustid_duration END,
+                acoustid_checked_at_ms = NULL,
                 mtime_ms = excluded.mtime_ms,
                 size_bytes = excluded.size_bytes,
-                mb_no_match_key = NULL
+                mb_no_match_key = NULL,
+                composer = excluded.composer,
+                conductor = excluded.conductor,
+                work = excluded.work,
+                movement = excluded.movement,
+                movement_number = excluded.movement_number,
+                disc_title = excluded.disc_title
             "#,
             params![
                 &record_path,
@@ -549,10 +1838,25 @@ impl MetadataDb {
                 record.format,
                 record.mtime_ms,
                 record.size_bytes,
-                Option::<String>::None
+                Option::<String>::None,
+                record.composer,
+                record.conductor,
+                record.work,
+                record.movement,
+                record.movement_number,
+                record.disc_title,
+                now_ms()
             ],
         )
         .context("upsert track")?;
+        let track_id: i64 = tx
+            .query_row(
+                "SELECT id FROM tracks WHERE path = ?1",
+                params![&record_path],
+                |row| row.get(0),
+            )
+            .context("lookup upserted track")?;
+        replace_track_genres(&tx, track_id, &record.genres)?;
 
         if let Some(album_id) = album_id {
             tx.execute(
@@ -618,7 +1922,13 @@ impl MetadataDb {
         if let (Some(artist_id), Some(artist_mbid)) = (artist_id, mb.artist_mbid.as_deref()) {
             if override_existing {
                 tx.execute(
-                    "UPDATE artists SET mbid = ?1 WHERE id = ?2",
+                    r#"
+                    UPDATE artists
+                    SET mbid = ?1,
+                        image_checked_at_ms = CASE WHEN mbid IS ?1 THEN image_checked_at_ms END,
+                        image_fail_count = CASE WHEN mbid IS ?1 THEN image_fail_count END
+                    WHERE id = ?2
+                    "#,
                     params![artist_mbid, artist_id],
                 )
                 .context("update artist mbid")?;
@@ -720,7 +2030,13 @@ impl MetadataDb {
         if let (Some(artist_id), Some(artist_mbid)) = (artist_id, mb.artist_mbid.as_deref()) {
             if override_existing {
                 tx.execute(
-                    "UPDATE artists SET mbid = ?1 WHERE id = ?2",
+                    r#"
+                    UPDATE artists
+                    SET mbid = ?1,
+                        image_checked_at_ms = CASE WHEN mbid IS ?1 THEN image_checked_at_ms END,
+                        image_fail_count = CASE WHEN mbid IS ?1 THEN image_fail_count END
+                    WHERE id = ?2
+                    "#,
                     params![artist_mbid, artist_id],
                 )
                 .context("update artist mbid")?;
@@ -779,7 +2095,12 @@ impl MetadataDb {
             r#"
                 SELECT t.path, t.file_name, t.title, ar.name, aa.name, al.title, al.uuid,
                        t.track_number, t.disc_number, al.year, t.duration_ms,
-                       t.sample_rate, t.bit_depth, t.format, t.mtime_ms, t.size_bytes
+                       t.sample_rate, t.bit_depth, t.format, t.mtime_ms, t.size_bytes,
+                       (SELECT group_concat(g.name, char(31) ORDER BY tg.position)
+                        FROM track_genres tg JOIN genres g ON g.id = tg.genre_id
+                        WHERE tg.track_id = t.id),
+                       t.composer, t.conductor, t.work, t.movement, t.movement_number,
+                       t.disc_title
                 FROM tracks t
                 LEFT JOIN artists ar ON ar.id = t.artist_id
                 LEFT JOIN albums al ON al.id = t.album_id
@@ -799,7 +2120,14 @@ impl MetadataDb {
                     album_uuid: row.get(6)?,
                     track_number: row.get::<_, Option<i64>>(7)?.map(|v| v as u32),
                     disc_number: row.get::<_, Option<i64>>(8)?.map(|v| v as u32),
+                    disc_title: row.get(22)?,
                     year: row.get(9)?,
+                    genres: split_genre_list(row.get(16)?),
+                    composer: row.get(17)?,
+                    conductor: row.get(18)?,
+                    work: row.get(19)?,
+                    movement: row.get(20)?,
+                    movement_number: row.get::<_, Option<i64>>(21)?.map(|v| v as u32),
                     duration_ms: row.get::<_, Option<i64>>(10)?.map(|v| v as u64),
                     sample_rate: row.get::<_, Option<i64>>(11)?.map(|v| v as u32),
                     bit_depth: row.get::<_, Option<i64>>(12)?.map(|v| v as u32),
@@ -820,7 +2148,12 @@ impl MetadataDb {
             r#"
                 SELECT t.path, t.file_name, t.title, ar.name, aa.name, al.title, al.uuid,
                        t.track_number, t.disc_number, al.year, t.duration_ms,
-                       t.sample_rate, t.bit_depth, t.format, t.mtime_ms, t.size_bytes
+                       t.sample_rate, t.bit_depth, t.format, t.mtime_ms, t.size_bytes,
+                       (SELECT group_concat(g.name, char(31) ORDER BY tg.position)
+                        FROM track_genres tg JOIN genres g ON g.id = tg.genre_id
+                        WHERE tg.track_id = t.id),
+                       t.composer, t.conductor, t.work, t.movement, t.movement_number,
+                       t.disc_title
                 FROM tracks t
                 LEFT JOIN artists ar ON ar.id = t.artist_id
                 LEFT JOIN albums al ON al.id = t.album_id
@@ -840,7 +2173,14 @@ impl MetadataDb {
                     album_uuid: row.get(6)?,
                     track_number: row.get::<_, Option<i64>>(7)?.map(|v| v as u32),
                     disc_number: row.get::<_, Option<i64>>(8)?.map(|v| v as u32),
+                    disc_title: row.get(22)?,
                     year: row.get(9)?,
+                    genres: split_genre_list(row.get(16)?),
+                    composer: row.get(17)?,
+                    conductor: row.get(18)?,
+                    work: row.get(19)?,
+                    movement: row.get(20)?,
+                    movement_number: row.get::<_, Option<i64>>(21)?.map(|v| v as u32),
                     duration_ms: row.get::<_, Option<i64>>(10)?.map(|v| v as u64),
                     sample_rate: row.get::<_, Option<i64>>(11)?.map(|v| v as u32),
                     bit_depth: row.get::<_, Option<i64>>(12)?.map(|v| v as u32),
@@ -867,6 +2207,51 @@ impl MetadataDb {
         .context("fetch track id for path")
     }
 
+    /// Tracks whose stored content hash is `hash`, as `(id, path)`.
+    pub fn tracks_with_content_hash(&self, hash: &str) -> Result<Vec<(i64, String)>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut stmt =
+            conn.prepare("SELECT id, path FROM tracks WHERE content_hash = ?1 ORDER BY id")?;
+        let rows = stmt.query_map(params![hash], |row| {
+            let path: String = row.get(1)?;
+            Ok((row.get(0)?, self.path_from_db(path)))
+        })?;
+        Ok(rows.filter_map(Result::ok).collect())
+    }
+
+    /// Paths of tracks that have no content hash yet.
+    pub fn list_track_paths_without_content_hash(&self) -> Result<Vec<String>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut stmt = conn.prepare("SELECT path FROM tracks WHERE content_hash IS NULL")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        Ok(rows
+            .filter_map(Result::ok)
+            .map(|path| self.path_from_db(path))
+            .collect())
+    }
+
+    /// Store the content hash of the track at `path`.
+    pub fn set_track_content_hash(&self, path: &str, hash: &str) -> Result<()> {
+        let conn = self.pool.get().context("open metadata db")?;
+        conn.execute(
+            "UPDATE tracks SET content_hash = ?1 WHERE path = ?2",
+            params![hash, self.path_to_db(path)],
+        )
+        .context("update track content hash")?;
+        Ok(())
+    }
+
+    /// Point an existing track row at a new path, keeping its id and everything keyed by it.
+    pub fn rebind_track_path(&self, track_id: i64, path: &str, file_name: &str) -> Result<()> {
+        let conn = self.pool.get().context("open metadata db")?;
+        conn.execute(
+            "UPDATE tracks SET path = ?1, file_name = ?2 WHERE id = ?3",
+            params![self.path_to_db(path), file_name, track_id],
+        )
+        .context("rebind track path")?;
+        Ok(())
+    }
+
     /// Resolve track path by track id.
     pub fn track_path_for_id(&self, track_id: i64) -> Result<Option<String>> {
         let conn = self.pool.get().context("open metadata db")?;
@@ -978,6 +2363,99 @@ impl MetadataDb {
         Ok(rows.filter_map(Result::ok).collect())
     }
 
+    /// List tracks without title or artist that AcoustID has not been asked about.
+    pub fn list_acoustid_candidates(&self, limit: i64) -> Result<Vec<AcoustIdCandidate>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT t.id, t.path, t.acoustid_fingerprint, t.acoustid_duration
+            FROM tracks t
+            WHERE (t.title IS NULL OR t.title = '' OR t.artist_id IS NULL)
+              AND (t.mbid IS NULL OR t.mbid = '' OR t.acoustid_id IS NOT NULL)
+              AND t.acoustid_checked_at_ms IS NULL
+            ORDER BY t.path
+            LIMIT ?1
+            "#,
+        )?;
+        let rows = stmt.query_map(params![limit], |row| {
+            let path: String = row.get(1)?;
+            Ok(AcoustIdCandidate {
+                track_id: row.get(0)?,
+                path: self.path_from_db(path),
+                fingerprint: row.get(2)?,
+                duration_s: row.get::<_, Option<i64>>(3)?.map(|v| v as u32),
+            })
+        })?;
+        Ok(rows.filter_map(Result::ok).collect())
+    }
+
+    /// Store a computed fingerprint so later lookups skip recomputing it.
+    pub fn set_acoustid_fingerprint(
+        &self,
+        track_id: i64,
+        fingerprint: &str,
+        duration_s: u32,
+    ) -> Result<()> {
+        let conn = self.pool.get().context("open metadata db")?;
+        conn.execute(
+            "UPDATE tracks SET acoustid_fingerprint = ?1, acoustid_duration = ?2 WHERE id = ?3",
+            params![fingerprint, duration_s as i64, track_id],
+        )
+        .context("store acoustid fingerprint")?;
+        Ok(())
+    }
+
+    /// Record an AcoustID lookup, filling title/artist the tags left empty on a match.
+    pub fn apply_acoustid_result(
+        &self,
+        track_id: i64,
+        result: Option<&AcoustIdMatch>,
+        checked_at_ms: i64,
+    ) -> Result<()> {
+        let mut conn = self.pool.get().context("open metadata db")?;
+        let tx = conn.transaction().context("begin acoustid tx")?;
+        tx.execute(
+            "UPDATE tracks SET acoustid_checked_at_ms = ?1 WHERE id = ?2",
+            params![checked_at_ms, track_id],
+        )
+        .context("mark acoustid checked")?;
+        if let Some(result) = result {
+            tx.execute(
+                r#"
+                UPDATE tracks
+                SET acoustid_id = ?1,
+                    mbid = CASE WHEN mbid IS NULL OR mbid = '' THEN ?2 ELSE mbid END,
+                    title = CASE WHEN title IS NULL OR title = '' THEN ?3 ELSE title END
+                WHERE id = ?4
+                "#,
+                params![
+                    result.acoustid_id,
+                    result.recording_mbid,
+                    result.title,
+                    track_id
+                ],
+            )
+            .context("apply acoustid match")?;
+            if let Some(artist) = result.artist.as_deref() {
+                let artist_id = upsert_artist(&tx, artist)?;
+                tx.execute(
+                    "UPDATE tracks SET artist_id = ?1 WHERE id = ?2 AND artist_id IS NULL",
+                    params![artist_id, track_id],
+                )
+                .context("apply acoustid artist")?;
+                if let Some(mbid) = result.artist_mbid.as_deref() {
+                    tx.execute(
+                        "UPDATE artists SET mbid = ?1 WHERE id = ?2 AND (mbid IS NULL OR mbid = '')",
+                        params![mbid, artist_id],
+                    )
+                    .context("apply acoustid artist mbid")?;
+                }
+            }
+        }
+        tx.commit().context("commit acoustid tx")?;
+        Ok(())
+    }
+
     /// Lookup album cover path by title/optional artist.
     pub fn album_cover_path(&self, album: &str, artist: Option<&str>) -> Result<Option<String>> {
         let conn = self.pool.get().context("open metadata db")?;
@@ -1092,6 +2570,41 @@ impl MetadataDb {
         Ok(updated > 0)
     }
 
+    /// Replace an album's cover, returning the previous path once no album uses it.
+    pub fn replace_album_cover(&self, album_id: i64, cover_path: &str) -> Result<Option<String>> {
+        let mut conn = self.pool.get().context("open metadata db")?;
+        let tx = conn.transaction().context("begin metadata tx")?;
+        let previous: Option<String> = tx
+            .query_row(
+                "SELECT cover_art_path FROM albums WHERE id = ?1",
+                params![album_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("fetch album cover path")?
+            .flatten();
+        tx.execute(
+            "UPDATE albums SET cover_art_path = ?1, caa_fail_count = NULL, caa_last_error = NULL WHERE id = ?2",
+            params![cover_path, album_id],
+        )
+        .context("replace album cover")?;
+        let previous = match previous.filter(|path| !path.trim().is_empty() && path != cover_path) {
+            Some(path) => {
+                let in_use: bool = tx
+                    .query_row(
+                        "SELECT EXISTS(SELECT 1 FROM albums WHERE cover_art_path = ?1)",
+                        params![&path],
+                        |row| row.get(0),
+                    )
+                    .context("check cover path in use")?;
+                (!in_use).then_some(path)
+            }
+            None => None,
+        };
+        tx.commit().context("commit metadata tx")?;
+        Ok(previous)
+    }
+
     /// List albums eligible for cover-art fetch attempts.
     pub fn list_cover_art_candidates(&self, limit: i64) -> Result<Vec<CoverArtCandidate>> {
         let conn = self.pool.get().context("open metadata db")?;
@@ -1116,6 +2629,61 @@ impl MetadataDb {
         Ok(rows.filter_map(Result::ok).collect())
     }
 
+    /// List artists with an MBID whose image has not been looked up yet.
+    pub fn list_artist_image_candidates(&self, limit: i64) -> Result<Vec<ArtistImageCandidate>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT ar.id, ar.mbid
+            FROM artists ar
+            WHERE ar.mbid IS NOT NULL
+              AND ar.mbid != ''
+              AND ar.image_checked_at_ms IS NULL
+              AND COALESCE(ar.image_fail_count, 0) < 3
+              AND NOT EXISTS (
+                  SELECT 1 FROM media_assets ma
+                  WHERE ma.owner_type = 'artist' AND ma.owner_id = ar.id AND ma.kind = 'image'
+              )
+            ORDER BY ar.id
+            LIMIT ?1
+            "#,
+        )?;
+        let rows = stmt.query_map(params![limit], |row| {
+            Ok(ArtistImageCandidate {
+                artist_id: row.get(0)?,
+                mbid: row.get(1)?,
+            })
+        })?;
+        Ok(rows.filter_map(Result::ok).collect())
+    }
+
+    /// Record that an artist's image lookup finished, with or without an image.
+    pub fn mark_artist_image_checked(&self, artist_id: i64, checked_at_ms: i64) -> Result<()> {
+        let conn = self.pool.get().context("open metadata db")?;
+        conn.execute(
+            "UPDATE artists SET image_checked_at_ms = ?1, image_last_error = NULL WHERE id = ?2",
+            params![checked_at_ms, artist_id],
+        )
+        .context("mark artist image checked")?;
+        Ok(())
+    }
+
+    /// Increment artist-image failure count and persist last error text.
+    pub fn increment_artist_image_fail(&self, artist_id: i64, error: &str) -> Result<i64> {
+        let conn = self.pool.get().context("open metadata db")?;
+        conn.execute(
+            "UPDATE artists SET image_fail_count = COALESCE(image_fail_count, 0) + 1, image_last_error = ?1 WHERE id = ?2",
+            params![error, artist_id],
+        )
+        .context("increment artist image fail count")?;
+        let count: i64 = conn.query_row(
+            "SELECT COALESCE(image_fail_count, 0) FROM artists WHERE id = ?1",
+            params![artist_id],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
     /// Increment cover-art failure count and persist last error text.
     pub fn increment_cover_art_fail(&self, album_id: i64, error: &str) -> Result<i64> {
         let conn = self.pool.get().context("open metadata db")?;
@@ -1189,157 +2757,209 @@ impl MetadataDb {
     }
 
     /// List artist summaries with optional search and paging.
+    #[cfg(test)]
     pub fn list_artists(
         &self,
         search: Option<&str>,
         limit: i64,
         offset: i64,
     ) -> Result<Vec<ArtistSummary>> {
+        Ok(self
+            .list_artists_page(search, limit, PageStart::Offset(offset))?
+            .map(|page| page.items)
+            .unwrap_or_default())
+    }
+
+    /// List artists by name, starting at `start`; `None` when the cursor is not for this list.
+    pub fn list_artists_page(
+        &self,
+        search: Option<&str>,
+        limit: i64,
+        start: PageStart<'_>,
+    ) -> Result<Option<Page<ArtistSummary>>> {
         let conn = self.pool.get().context("open metadata db")?;
         let search_like = search.map(|s| format!("%{}%", s.to_lowercase()));
-        let mut stmt = if search_like.is_some() {
-            conn.prepare(
-                r#"
+        let keys = keyset_keys(vec![OrderKey::Asc("a.name")], "a.id");
+        let scope = "artists";
+        let (offset, after) = match start {
+            PageStart::Offset(offset) => (offset, None),
+            PageStart::After(cursor) => match cursor_params(cursor, scope, keys.len()) {
+                Some(values) => (0, Some(values)),
+                None => return Ok(None),
+            },
+        };
+        let having = match after {
+            Some(_) => format!("HAVING {}", after_cursor_clause(&keys, false, 4)),
+            None => String::new(),
+        };
+        let mut stmt = conn.prepare(&format!(
+            r#"
             SELECT a.id, a.uuid, a.name, a.sort_name, a.mbid,
                    COUNT(DISTINCT al.id) AS album_count,
                    COUNT(t.id) AS track_count
+                   {key_columns}
                 FROM artists a
                 LEFT JOIN albums al ON al.artist_id = a.id
                 LEFT JOIN tracks t ON t.artist_id = a.id
-                WHERE LOWER(a.name) LIKE ?1
+                WHERE (?1 IS NULL OR LOWER(a.name) LIKE ?1)
                 GROUP BY a.id
-                ORDER BY a.name
+                {having}
+                ORDER BY {order_by}
                 LIMIT ?2 OFFSET ?3
                 "#,
-            )?
-        } else {
-            conn.prepare(
-                r#"
-            SELECT a.id, a.uuid, a.name, a.sort_name, a.mbid,
-                   COUNT(DISTINCT al.id) AS album_count,
-                   COUNT(t.id) AS track_count
-                FROM artists a
-                LEFT JOIN albums al ON al.artist_id = a.id
-                LEFT JOIN tracks t ON t.artist_id = a.id
-                GROUP BY a.id
-                ORDER BY a.name
-                LIMIT ?1 OFFSET ?2
-                "#,
-            )?
-        };
+            key_columns = key_columns(&keys),
+            order_by = order_by_clause(&keys, false),
+        ))?;
+        let mut args: Vec<&dyn rusqlite::ToSql> = vec![&search_like, &limit, &offset];
+        args.extend(after.iter().flatten().map(|v| v as &dyn rusqlite::ToSql));
+        let rows = stmt.query_map(args.as_slice(), |row| {
+            Ok((map_artist_row(row)?, cursor_keys(row, 7, keys.len())?))
+        })?;
+        let rows = rows.filter_map(Result::ok).collect();
+        Ok(Some(into_page(rows, limit, Some(scope.to_string()))))
+    }
 
-        let rows = if let Some(search_like) = search_like {
-            stmt.query_map(params![search_like, limit, offset], map_artist_row)?
-        } else {
-            stmt.query_map(params![limit, offset], map_artist_row)?
-        };
+    /// List genres with album/track counts, optional name search and paging.
+    pub fn list_genres(
+        &self,
+        search: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<GenreSummary>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let search_like = search.map(|s| format!("%{}%", s.to_lowercase()));
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT g.id, g.name,
+                   COUNT(DISTINCT t.album_id) AS album_count,
+                   COUNT(DISTINCT t.id) AS track_count
+            FROM genres g
+            JOIN track_genres tg ON tg.genre_id = g.id
+            JOIN tracks t ON t.id = tg.track_id
+            WHERE (?1 IS NULL OR LOWER(g.name) LIKE ?1)
+            GROUP BY g.id
+            ORDER BY g.name COLLATE NOCASE
+            LIMIT ?2 OFFSET ?3
+            "#,
+        )?;
+        let rows = stmt.query_map(params![search_like, limit, offset], |row| {
+            Ok(GenreSummary {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                album_count: row.get(2)?,
+                track_count: row.get(3)?,
+            })
+        })?;
 
         Ok(rows.filter_map(Result::ok).collect())
     }
 
-    /// List album summaries with optional artist/search filters and paging.
+    /// List album summaries matching `filter`, in `filter.sort` order, with paging.
+    #[cfg(test)]
     pub fn list_albums(
         &self,
-        artist_id: Option<i64>,
-        search: Option<&str>,
+        filter: &AlbumFilter<'_>,
         limit: i64,
         offset: i64,
     ) -> Result<Vec<AlbumSummary>> {
+        Ok(self
+            .list_albums_page(filter, limit, PageStart::Offset(offset))?
+            .map(|page| page.items)
+            .unwrap_or_default())
+    }
+
+    /// List albums matching `filter`, starting at `start`; `None` when the cursor was
+    /// issued for another list or order.
+    pub fn list_albums_page(
+        &self,
+        filter: &AlbumFilter<'_>,
+        limit: i64,
+        start: PageStart<'_>,
+    ) -> Result<Option<Page<AlbumSummary>>> {
         let conn = self.pool.get().context("open metadata db")?;
-        let search_like = search.map(|s| format!("%{}%", s.to_lowercase()));
-        let mut stmt = conn.prepare(
+        let search_like = filter.search.map(|s| format!("%{}%", s.to_lowercase()));
+        let direction = filter
+            .direction
+            .unwrap_or_else(|| filter.sort.default_direction());
+        let reverse = direction != filter.sort.default_direction();
+        let keys = keyset_keys(filter.sort.order_keys(), "al.id");
+        let scope = (filter.sort != AlbumSort::Random)
+            .then(|| format!("albums/{:?}/{direction:?}", filter.sort));
+        let (offset, after) = match (start, &scope) {
+            (PageStart::Offset(offset), _) => (offset, None),
+            (PageStart::After(cursor), Some(scope)) => {
+                match cursor_params(cursor, scope, keys.len()) {
+                    Some(values) => (0, Some(values)),
+                    None => return Ok(None),
+                }
+            }
+            (PageStart::After(_), None) => return Ok(None),
+        };
+        let having = match after {
+            Some(_) => format!("HAVING {}", after_cursor_clause(&keys, reverse, 9)),
+            None => String::new(),
+        };
+        let mut stmt = conn.prepare(&format!(
             r#"
-            SELECT al.id, al.uuid, al.title, ar.name, al.artist_id, al.year,
-                   al.original_year, al.edition_year, al.edition_label, al.mbid,
-                   COUNT(t.id) AS track_count, al.cover_art_path,
-                   MAX(t.bit_depth) AS max_bit_depth
+            SELECT {ALBUM_SUMMARY_COLUMNS} {key_columns}
             FROM albums al
             LEFT JOIN artists ar ON ar.id = al.artist_id
             LEFT JOIN tracks t ON t.album_id = al.id
             WHERE (?1 IS NULL OR al.artist_id = ?1)
               AND (?2 IS NULL OR LOWER(al.title) LIKE ?2)
+              AND (?5 IS NULL OR al.id IN (SELECT album_id FROM album_genres WHERE genre_id = ?5))
+              AND (?6 IS NULL OR al.id IN (
+                    SELECT album_id FROM tracks WHERE composer = ?6 COLLATE NOCASE))
+              AND (?7 IS NULL OR ?7 = (al.id IN (
+                    SELECT album_id FROM album_ratings WHERE favorite = 1)))
+              AND (?8 IS NULL OR al.id IN (
+                    SELECT album_id FROM album_ratings WHERE rating >= ?8))
               AND al.orphaned_at IS NULL
             GROUP BY al.id
-            ORDER BY
-                CASE WHEN ar.name IS NULL THEN 1 ELSE 0 END,
-                COALESCE(ar.sort_name, ar.name),
-                COALESCE(al.original_year, al.year, 9999),
-                COALESCE(al.sort_title, al.title)
+            {having}
+            ORDER BY {order_by}
             LIMIT ?3 OFFSET ?4
             "#,
-        )?;
-        let rows = stmt.query_map(params![artist_id, search_like, limit, offset], |row| {
-            let album_id: i64 = row.get(0)?;
-            let cover_path: Option<String> = row.get(11)?;
-            let max_bit_depth: Option<i64> = row.get(12)?;
-            let hi_res = max_bit_depth.unwrap_or(0) >= 24;
-            let cover_art_url = cover_path
-                .as_deref()
-                .filter(|value| !value.trim().is_empty())
-                .map(|_| format!("/albums/{}/cover", album_id));
-            Ok(AlbumSummary {
-                id: album_id,
-                uuid: row.get(1)?,
-                title: row.get(2)?,
-                artist: row.get(3)?,
-                artist_id: row.get(4)?,
-                year: row.get(5)?,
-                original_year: row.get(6)?,
-                edition_year: row.get(7)?,
-                edition_label: row.get(8)?,
-                mbid: row.get(9)?,
-                track_count: row.get(10)?,
-                cover_art_path: cover_path,
-                cover_art_url,
-                hi_res,
-            })
+            key_columns = key_columns(&keys),
+            order_by = order_by_clause(&keys, reverse),
+        ))?;
+        let mut args: Vec<&dyn rusqlite::ToSql> = vec![
+            &filter.artist_id,
+            &search_like,
+            &limit,
+            &offset,
+            &filter.genre_id,
+            &filter.composer,
+            &filter.favorite,
+            &filter.min_rating,
+        ];
+        args.extend(after.iter().flatten().map(|v| v as &dyn rusqlite::ToSql));
+        let rows = stmt.query_map(args.as_slice(), |row| {
+            Ok((
+                map_album_row(row)?,
+                cursor_keys(row, ALBUM_SUMMARY_COLUMN_COUNT, keys.len())?,
+            ))
         })?;
-
-        Ok(rows.filter_map(Result::ok).collect())
+        let rows = rows.filter_map(Result::ok).collect();
+        Ok(Some(into_page(rows, limit, scope)))
     }
 
     /// Fetch one album summary by id.
     pub fn album_summary_by_id(&self, album_id: i64) -> Result<Option<AlbumSummary>> {
         let conn = self.pool.get().context("open metadata db")?;
         conn.query_row(
-            r#"
-                SELECT al.id, al.uuid, al.title, ar.name, al.artist_id, al.year,
-                       al.original_year, al.edition_year, al.edition_label, al.mbid,
-                       COUNT(t.id) AS track_count, al.cover_art_path,
-                       MAX(t.bit_depth) AS max_bit_depth
+            &format!(
+                r#"
+                SELECT {ALBUM_SUMMARY_COLUMNS}
                 FROM albums al
                 LEFT JOIN artists ar ON ar.id = al.artist_id
                 LEFT JOIN tracks t ON t.album_id = al.id
                 WHERE al.id = ?1
                 GROUP BY al.id
-                "#,
+                "#
+            ),
             params![album_id],
-            |row| {
-                let album_id: i64 = row.get(0)?;
-                let cover_path: Option<String> = row.get(11)?;
-                let max_bit_depth: Option<i64> = row.get(12)?;
-                let hi_res = max_bit_depth.unwrap_or(0) >= 24;
-                let cover_art_url = cover_path
-                    .as_deref()
-                    .filter(|value| !value.trim().is_empty())
-                    .map(|_| format!("/albums/{}/cover", album_id));
-                Ok(AlbumSummary {
-                    id: album_id,
-                    uuid: row.get(1)?,
-                    title: row.get(2)?,
-                    artist: row.get(3)?,
-                    artist_id: row.get(4)?,
-                    year: row.get(5)?,
-                    original_year: row.get(6)?,
-                    edition_year: row.get(7)?,
-                    edition_label: row.get(8)?,
-                    mbid: row.get(9)?,
-                    track_count: row.get(10)?,
-                    cover_art_path: cover_path,
-                    cover_art_url,
-                    hi_res,
-                })
-            },
+            map_album_row,
         )
         .optional()
         .context("select album summary by id")
@@ -1626,351 +3246,1820 @@ impl MetadataDb {
         Ok(existing)
     }
 
-    /// List tracks with optional album/artist/search filters and paging.
+    /// List tracks matching `filter`, in `filter.sort` order, with paging.
+    #[cfg(test)]
     pub fn list_tracks(
         &self,
-        album_id: Option<i64>,
-        artist_id: Option<i64>,
-        search: Option<&str>,
+        filter: &TrackFilter<'_>,
         limit: i64,
         offset: i64,
     ) -> Result<Vec<TrackSummary>> {
+        Ok(self
+            .list_tracks_page(filter, limit, PageStart::Offset(offset))?
+            .map(|page| page.items)
+            .unwrap_or_default())
+    }
+
+    /// List tracks matching `filter`, starting at `start`; `None` when the cursor was
+    /// issued for another list or order.
+    pub fn list_tracks_page(
+        &self,
+        filter: &TrackFilter<'_>,
+        limit: i64,
+        start: PageStart<'_>,
+    ) -> Result<Option<Page<TrackSummary>>> {
         let conn = self.pool.get().context("open metadata db")?;
-        let search_like = search.map(|s| format!("%{}%", s.to_lowercase()));
-        let mut stmt = conn.prepare(
-            r#"
-            SELECT t.id, t.file_name, t.title, ar.name, al.title,
-                   t.track_number, t.disc_number, t.duration_ms, t.format,
-                   t.sample_rate, t.bit_depth, t.mbid, al.cover_art_path
+        let search_like = filter.search.map(|s| format!("%{}%", s.to_lowercase()));
+        let direction = filter
+            .direction
+            .unwrap_or_else(|| filter.sort.default_direction());
+        let reverse = direction != filter.sort.default_direction();
+        let keys = keyset_keys(filter.sort.order_keys(), "t.id");
+        let scope = (filter.sort != TrackSort::Random)
+            .then(|| format!("tracks/{:?}/{direction:?}", filter.sort));
+        let (offset, after) = match (start, &scope) {
+            (PageStart::Offset(offset), _) => (offset, None),
+            (PageStart::After(cursor), Some(scope)) => {
+                match cursor_params(cursor, scope, keys.len()) {
+                    Some(values) => (0, Some(values)),
+                    None => return Ok(None),
+                }
+            }
+            (PageStart::After(_), None) => return Ok(None),
+        };
+        let after_cursor = match after {
+            Some(_) => format!("AND ({})", after_cursor_clause(&keys, reverse, 13)),
+            None => String::new(),
+        };
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT {TRACK_SUMMARY_COLUMNS} {key_columns}
             FROM tracks t
-            LEFT JOIN artists ar ON ar.id = t.artist_id
-            LEFT JOIN albums al ON al.id = t.album_id
+            {TRACK_SUMMARY_JOINS}
             WHERE (?1 IS NULL OR t.album_id = ?1)
               AND (?2 IS NULL OR t.artist_id = ?2)
               AND (?3 IS NULL OR LOWER(COALESCE(t.title, t.file_name)) LIKE ?3)
-            ORDER BY COALESCE(t.disc_number, 0), COALESCE(t.track_number, 0), t.file_name
+              AND (?6 IS NULL OR t.id IN (SELECT track_id FROM track_genres WHERE genre_id = ?6))
+              AND (?7 IS NULL OR t.composer = ?7 COLLATE NOCASE)
+              AND (?8 IS NULL OR tk.bpm >= ?8)
+              AND (?9 IS NULL OR tk.bpm <= ?9)
+              AND (?10 IS NULL OR tk.musical_key = ?10 COLLATE NOCASE)
+              AND (?11 IS NULL OR COALESCE(tr.favorite, 0) = ?11)
+              AND (?12 IS NULL OR tr.rating >= ?12)
+              {after_cursor}
+            ORDER BY {order_by}
             LIMIT ?4 OFFSET ?5
             "#,
+            key_columns = key_columns(&keys),
+            order_by = order_by_clause(&keys, reverse),
+        ))?;
+        let mut args: Vec<&dyn rusqlite::ToSql> = vec![
+            &filter.album_id,
+            &filter.artist_id,
+            &search_like,
+            &limit,
+            &offset,
+            &filter.genre_id,
+            &filter.composer,
+            &filter.min_bpm,
+            &filter.max_bpm,
+            &filter.key,
+            &filter.favorite,
+            &filter.min_rating,
+        ];
+        args.extend(after.iter().flatten().map(|v| v as &dyn rusqlite::ToSql));
+        let rows = stmt.query_map(args.as_slice(), |row| {
+            Ok((
+                map_track_summary_row(row)?,
+                cursor_keys(row, TRACK_SUMMARY_COLUMN_COUNT, keys.len())?,
+            ))
+        })?;
+        let rows = rows.filter_map(Result::ok).collect();
+        Ok(Some(into_page(rows, limit, scope)))
+    }
+
+    /// Update a track's favorite flag and/or rating (`Some(None)` clears the rating).
+    pub fn set_track_rating(
+        &self,
+        track_id: i64,
+        favorite: Option<bool>,
+        rating: Option<Option<u8>>,
+    ) -> Result<Rating> {
+        let conn = self.pool.get().context("open metadata db")?;
+        update_rating(
+            &conn,
+            "track_ratings",
+            "track_id",
+            track_id,
+            favorite,
+            rating,
+        )
+    }
+
+    /// Update an album's favorite flag and/or rating (`Some(None)` clears the rating).
+    pub fn set_album_rating(
+        &self,
+        album_id: i64,
+        favorite: Option<bool>,
+        rating: Option<Option<u8>>,
+    ) -> Result<Rating> {
+        let conn = self.pool.get().context("open metadata db")?;
+        update_rating(
+            &conn,
+            "album_ratings",
+            "album_id",
+            album_id,
+            favorite,
+            rating,
+        )
+    }
+
+    /// Store a finished play and update the track's play count when it counts.
+    pub fn record_play(&self, play: &PlayRecord) -> Result<()> {
+        let mut conn = self.pool.get().context("open metadata db")?;
+        let tx = conn.transaction().context("begin play history tx")?;
+        let inserted = tx
+            .execute(
+                r#"
+                INSERT INTO play_history
+                    (track_id, session_id, output_id, played_at_ms, played_ms, completed_pct)
+                SELECT ?1, ?2, ?3, ?4, ?5, ?6
+                WHERE EXISTS (SELECT 1 FROM tracks WHERE id = ?1)
+                "#,
+                params![
+                    play.track_id,
+                    play.session_id,
+                    play.output_id,
+                    play.played_at_ms,
+                    play.played_ms,
+                    play.completed_pct
+                ],
+            )
+            .context("insert play history")?;
+        let counted = match play.completed_pct {
+            Some(pct) => pct >= COUNTED_PLAY_MIN_PCT,
+            None => play.played_ms >= COUNTED_PLAY_MIN_MS_UNKNOWN_LENGTH,
+        };
+        if inserted > 0 && counted {
+            tx.execute(
+                r#"
+                INSERT INTO track_play_stats (track_id, play_count, last_played_at_ms)
+                VALUES (?1, 1, ?2)
+                ON CONFLICT(track_id) DO UPDATE SET
+                    play_count = play_count + 1,
+                    last_played_at_ms = MAX(last_played_at_ms, excluded.last_played_at_ms)
+                "#,
+                params![play.track_id, play.played_at_ms],
+            )
+            .context("update play stats")?;
+        }
+        tx.commit().context("commit play history tx")?;
+        Ok(())
+    }
+
+    /// List listening history, newest first, optionally for one track or session.
+    pub fn list_play_history(
+        &self,
+        track_id: Option<i64>,
+        session_id: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<PlayHistoryEntry>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT {TRACK_SUMMARY_COLUMNS},
+                   ph.id, ph.played_at_ms, ph.played_ms, ph.completed_pct,
+                   ph.session_id, ph.output_id
+            FROM play_history ph
+            JOIN tracks t ON t.id = ph.track_id
+            {TRACK_SUMMARY_JOINS}
+            WHERE (?1 IS NULL OR ph.track_id = ?1)
+              AND (?2 IS NULL OR ph.session_id = ?2)
+            ORDER BY ph.played_at_ms DESC, ph.id DESC
+            LIMIT ?3 OFFSET ?4
+            "#
+        ))?;
+        let rows = stmt.query_map(params![track_id, session_id, limit, offset], |row| {
+            let base = TRACK_SUMMARY_COLUMN_COUNT;
+            Ok(PlayHistoryEntry {
+                id: row.get(base)?,
+                played_at_ms: row.get(base + 1)?,
+                played_ms: row.get(base + 2)?,
+                completed_pct: row.get(base + 3)?,
+                session_id: row.get(base + 4)?,
+                output_id: row.get(base + 5)?,
+                track: map_track_summary_row(row)?,
+            })
+        })?;
+        Ok(rows.filter_map(Result::ok).collect())
+    }
+
+    /// Store audit-log rows.
+    pub fn record_audit(&self, records: &[AuditRecord]) -> Result<()> {
+        let mut conn = self.pool.get().context("open metadata db")?;
+        let tx = conn.transaction().context("begin audit tx")?;
+        for record in records {
+            tx.execute(
+                r#"
+                INSERT INTO audit_log
+                    (at_ms, actor, client_id, session_id, output_id, action, method, path, status)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                "#,
+                params![
+                    record.at_ms,
+                    record.actor,
+                    record.client_id,
+                    record.session_id,
+                    record.output_id,
+                    record.action,
+                    record.method,
+                    record.path,
+                    record.status
+                ],
+            )
+            .context("insert audit row")?;
+        }
+        tx.commit().context("commit audit tx")?;
+        Ok(())
+    }
+
+    /// Drop all but the newest `keep` audit-log rows; returns how many were removed.
+    pub fn prune_audit(&self, keep: i64) -> Result<usize> {
+        let conn = self.pool.get().context("open metadata db")?;
+        conn.execute(
+            r#"
+            DELETE FROM audit_log
+            WHERE id <= (SELECT id FROM audit_log ORDER BY id DESC LIMIT 1 OFFSET ?1)
+            "#,
+            params![keep],
+        )
+        .context("prune audit log")
+    }
+
+    /// List audit-log rows matching `filter`, newest first.
+    pub fn list_audit(
+        &self,
+        filter: &AuditFilter,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<AuditEntry>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, at_ms, actor, client_id, session_id, output_id, action, method, path, status
+            FROM audit_log
+            WHERE (?1 IS NULL OR actor = ?1)
+              AND (?2 IS NULL OR client_id = ?2)
+              AND (?3 IS NULL OR session_id = ?3)
+              AND (?4 IS NULL OR action = ?4)
+              AND (?5 IS NULL OR at_ms >= ?5)
+              AND (?6 IS NULL OR at_ms < ?6)
+            ORDER BY at_ms DESC, id DESC
+            LIMIT ?7 OFFSET ?8
+            "#,
         )?;
         let rows = stmt.query_map(
-            params![album_id, artist_id, search_like, limit, offset],
+            params![
+                filter.actor,
+                filter.client_id,
+                filter.session_id,
+                filter.action,
+                filter.since_ms,
+                filter.until_ms,
+                limit,
+                offset
+            ],
             |row| {
-                let track_id: i64 = row.get(0)?;
-                let cover_path: Option<String> = row.get(12)?;
-                let cover_art_url = cover_path
-                    .as_deref()
-                    .filter(|value| !value.trim().is_empty())
-                    .map(|_| format!("/tracks/{}/cover", track_id));
-                Ok(TrackSummary {
-                    id: track_id,
-                    file_name: row.get(1)?,
-                    title: row.get(2)?,
-                    artist: row.get(3)?,
-                    album: row.get(4)?,
-                    track_number: row.get::<_, Option<i64>>(5)?.map(|v| v as u32),
-                    disc_number: row.get::<_, Option<i64>>(6)?.map(|v| v as u32),
-                    duration_ms: row.get::<_, Option<i64>>(7)?.map(|v| v as u64),
-                    format: row.get(8)?,
-                    sample_rate: row.get::<_, Option<i64>>(9)?.map(|v| v as u32),
-                    bit_depth: row.get::<_, Option<i64>>(10)?.map(|v| v as u32),
-                    mbid: row.get(11)?,
-                    cover_art_url,
+                Ok(AuditEntry {
+                    id: row.get(0)?,
+                    at_ms: row.get(1)?,
+                    actor: row.get(2)?,
+                    client_id: row.get(3)?,
+                    session_id: row.get(4)?,
+                    output_id: row.get(5)?,
+                    action: row.get(6)?,
+                    method: row.get(7)?,
+                    path: row.get(8)?,
+                    status: row.get(9)?,
                 })
             },
         )?;
-
         Ok(rows.filter_map(Result::ok).collect())
     }
 
-    /// List track paths belonging to an album id.
-    pub fn list_track_paths_by_album_id(&self, album_id: i64) -> Result<Vec<String>> {
+    /// Load every output's stored playback preferences.
+    pub fn list_output_preferences(&self) -> Result<HashMap<String, OutputPreferences>> {
         let conn = self.pool.get().context("open metadata db")?;
         let mut stmt = conn.prepare(
-            "SELECT path FROM tracks WHERE album_id = ?1 ORDER BY COALESCE(disc_number, 0), COALESCE(track_number, 0), file_name",
+            r#"
+            SELECT output_id, resample_quality, exclusive, bit_depth, max_sample_rate
+            FROM output_preferences
+            "#,
         )?;
-        let rows = stmt.query_map(params![album_id], |row| row.get(0))?;
-        Ok(rows
-            .filter_map(Result::ok)
-            .map(|path: String| self.path_from_db(path))
-            .collect())
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                OutputPreferences {
+                    resample_quality: row.get(1)?,
+                    exclusive: row.get(2)?,
+                    bit_depth: row.get(3)?,
+                    max_sample_rate: row.get(4)?,
+                },
+            ))
+        })?;
+        Ok(rows.filter_map(Result::ok).collect())
     }
 
-    /// List all track paths currently in DB.
-    pub fn list_all_track_paths(&self) -> Result<Vec<String>> {
+    /// Store `prefs` for `output_id`; empty preferences remove the row.
+    pub fn set_output_preferences(&self, output_id: &str, prefs: &OutputPreferences) -> Result<()> {
         let conn = self.pool.get().context("open metadata db")?;
-        let mut stmt = conn.prepare("SELECT path FROM tracks")?;
-        let rows = stmt.query_map([], |row| row.get(0))?;
-        Ok(rows
-            .filter_map(Result::ok)
-            .map(|path: String| self.path_from_db(path))
-            .collect())
+        if prefs.is_empty() {
+            conn.execute(
+                "DELETE FROM output_preferences WHERE output_id = ?1",
+                params![output_id],
+            )
+            .context("delete output preferences")?;
+            return Ok(());
+        }
+        conn.execute(
+            r#"
+            INSERT INTO output_preferences
+                (output_id, resample_quality, exclusive, bit_depth, max_sample_rate, updated_at_ms)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT(output_id) DO UPDATE SET
+                resample_quality = excluded.resample_quality,
+                exclusive = excluded.exclusive,
+                bit_depth = excluded.bit_depth,
+                max_sample_rate = excluded.max_sample_rate,
+                updated_at_ms = excluded.updated_at_ms
+            "#,
+            params![
+                output_id,
+                prefs.resample_quality,
+                prefs.exclusive,
+                prefs.bit_depth,
+                prefs.max_sample_rate,
+                now_ms()
+            ],
+        )
+        .context("store output preferences")?;
+        Ok(())
     }
 
-    /// Update album metadata, merging rows when title+artist collide.
-    pub fn update_album_metadata(
-        &self,
-        album_id: i64,
-        title: Option<&str>,
-        artist: Option<&str>,
-        year: Option<i32>,
-    ) -> Result<Option<i64>> {
-        let mut conn = self.pool.get().context("open metadata db")?;
-        let tx = conn.transaction().context("begin metadata tx")?;
-        let current: Option<(String, Option<i64>)> = tx
+    /// List all playlists ordered by name.
+    pub fn list_playlists(&self) -> Result<Vec<PlaylistSummary>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {PLAYLIST_SUMMARY_COLUMNS} FROM playlists p ORDER BY p.name COLLATE NOCASE, p.id"
+        ))?;
+        let rows = stmt.query_map([], map_playlist_row)?;
+        Ok(rows.filter_map(Result::ok).collect())
+    }
+
+    /// Fetch one playlist summary by id.
+    pub fn playlist_summary(&self, playlist_id: i64) -> Result<Option<PlaylistSummary>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        conn.query_row(
+            &format!("SELECT {PLAYLIST_SUMMARY_COLUMNS} FROM playlists p WHERE p.id = ?1"),
+            params![playlist_id],
+            map_playlist_row,
+        )
+        .optional()
+        .context("load playlist")
+    }
+
+    /// List playlist entries in playlist order.
+    pub fn playlist_tracks(&self, playlist_id: i64) -> Result<Vec<TrackSummary>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT {TRACK_SUMMARY_COLUMNS}
+            FROM playlist_tracks pt
+            JOIN tracks t ON t.id = pt.track_id
+            {TRACK_SUMMARY_JOINS}
+            WHERE pt.playlist_id = ?1
+            ORDER BY pt.position
+            "#
+        ))?;
+        let rows = stmt.query_map(params![playlist_id], map_track_summary_row)?;
+        Ok(rows.filter_map(Result::ok).collect())
+    }
+
+    /// List playlist track ids in order, or `None` when the playlist does not exist.
+    pub fn playlist_track_ids(&self, playlist_id: i64) -> Result<Option<Vec<i64>>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let exists: Option<i64> = conn
             .query_row(
-                "SELECT title, artist_id FROM albums WHERE id = ?1",
-                params![album_id],
-                |row| Ok((row.get(0)?, row.get(1)?)),
+                "SELECT id FROM playlists WHERE id = ?1",
+                params![playlist_id],
+                |row| row.get(0),
             )
-            .optional()
-            .context("lookup current album")?;
-        let Some((current_title, current_artist_id)) = current else {
+            .optional()?;
+        if exists.is_none() {
             return Ok(None);
-        };
-        let artist_id = if let Some(name) = artist {
-            Some(upsert_artist(&tx, name)?)
-        } else {
-            None
-        };
-        let desired_title = title.unwrap_or(current_title.as_str());
-        let desired_artist_id = artist_id.or(current_artist_id);
-        if let Some(existing_id) = find_album_id(&tx, desired_title, desired_artist_id)? {
-            if existing_id != album_id {
-                tx.execute(
-                    r#"
-                    UPDATE albums
-                    SET title = ?1,
-                        sort_title = LOWER(?1),
-                        artist_id = ?2,
-                        year = COALESCE(?3, year)
-                    WHERE id = ?4
-                    "#,
-                    params![desired_title, desired_artist_id, year, existing_id],
-                )
-                .context("merge album metadata")?;
-                tx.execute(
-                    "UPDATE tracks SET album_id = ?1 WHERE album_id = ?2",
-                    params![existing_id, album_id],
-                )
-                .context("reassign tracks to merged album")?;
-                tx.execute("DELETE FROM albums WHERE id = ?1", params![album_id])
-                    .context("delete merged album")?;
-                tx.commit().context("commit metadata tx")?;
-                return Ok(Some(existing_id));
+        }
+        let mut stmt = conn.prepare(
+            "SELECT track_id FROM playlist_tracks WHERE playlist_id = ?1 ORDER BY position",
+        )?;
+        let rows = stmt.query_map(params![playlist_id], |row| row.get(0))?;
+        Ok(Some(rows.filter_map(Result::ok).collect()))
+    }
+
+    /// Return the ids from `track_ids` that have no track row.
+    pub fn unknown_track_ids(&self, track_ids: &[i64]) -> Result<Vec<i64>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut stmt = conn.prepare("SELECT 1 FROM tracks WHERE id = ?1")?;
+        let mut unknown = Vec::new();
+        for track_id in track_ids {
+            if !stmt.exists(params![track_id])? && !unknown.contains(track_id) {
+                unknown.push(*track_id);
             }
         }
+        Ok(unknown)
+    }
+
+    /// Create a playlist with the given entries and return its id.
+    pub fn create_playlist(
+        &self,
+        name: &str,
+        track_ids: &[i64],
+        cover_track_id: Option<i64>,
+    ) -> Result<i64> {
+        let mut conn = self.pool.get().context("open metadata db")?;
+        let tx = conn.transaction().context("begin playlist tx")?;
+        let now_ms = now_ms();
+        tx.execute(
+            "INSERT INTO playlists (name, cover_track_id, created_at_ms, updated_at_ms) VALUES (?1, ?2, ?3, ?3)",
+            params![name, cover_track_id, now_ms],
+        )
+        .context("insert playlist")?;
+        let playlist_id = tx.last_insert_rowid();
+        insert_playlist_tracks(&tx, playlist_id, track_ids)?;
+        tx.commit().context("commit playlist tx")?;
+        Ok(playlist_id)
+    }
+
+    /// Replace a playlist's name, entries and cover; returns false if it does not exist.
+    pub fn update_playlist(
+        &self,
+        playlist_id: i64,
+        name: &str,
+        track_ids: &[i64],
+        cover_track_id: Option<i64>,
+    ) -> Result<bool> {
+        let mut conn = self.pool.get().context("open metadata db")?;
+        let tx = conn.transaction().context("begin playlist tx")?;
         let updated = tx
             .execute(
-                r#"
-                UPDATE albums
-                SET title = COALESCE(?1, title),
-                    sort_title = CASE WHEN ?1 IS NULL THEN sort_title ELSE LOWER(?1) END,
-                    artist_id = COALESCE(?2, artist_id),
-                    year = COALESCE(?3, year)
-                WHERE id = ?4
-                "#,
-                params![title, artist_id, year, album_id],
+                "UPDATE playlists SET name = ?1, cover_track_id = ?2, updated_at_ms = ?3 WHERE id = ?4",
+                params![name, cover_track_id, now_ms(), playlist_id],
             )
-            .context("update album metadata")?;
-        tx.commit().context("commit metadata tx")?;
-        if updated > 0 {
-            Ok(Some(album_id))
-        } else {
-            Ok(None)
+            .context("update playlist")?;
+        if updated == 0 {
+            return Ok(false);
         }
+        tx.execute(
+            "DELETE FROM playlist_tracks WHERE playlist_id = ?1",
+            params![playlist_id],
+        )
+        .context("clear playlist tracks")?;
+        insert_playlist_tracks(&tx, playlist_id, track_ids)?;
+        tx.commit().context("commit playlist tx")?;
+        Ok(true)
     }
 
-    /// Delete one track by path.
-    pub fn delete_track_by_path(&self, path: &str) -> Result<bool> {
+    /// Delete a playlist; returns false if it did not exist.
+    pub fn delete_playlist(&self, playlist_id: i64) -> Result<bool> {
         let conn = self.pool.get().context("open metadata db")?;
-        let db_path = self.path_to_db(path);
         let deleted = conn
-            .execute("DELETE FROM tracks WHERE path = ?1", params![db_path])
-            .context("delete track by path")?;
+            .execute("DELETE FROM playlists WHERE id = ?1", params![playlist_id])
+            .context("delete playlist")?;
         Ok(deleted > 0)
     }
 
-    /// Mark/clear orphaned albums according to current track references.
-    pub fn prune_orphaned_albums_and_artists(&self) -> Result<()> {
-        let mut conn = self.pool.get().context("open metadata db")?;
-        let tx = conn.transaction().context("begin metadata tx")?;
-        let now_ms = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map(|d| d.as_millis() as i64)
-            .unwrap_or(0);
-        tx.execute(
+    /// Collect user-authored metadata for [`MetadataExport`].
+    pub fn export_user_data(&self) -> Result<MetadataExport> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let track_ref = |row: &rusqlite::Row<'_>, idx: usize| -> rusqlite::Result<TrackRef> {
+            Ok(TrackRef {
+                path: row.get(idx)?,
+                content_hash: row.get(idx + 1)?,
+            })
+        };
+
+        let mut locks: HashMap<i64, Vec<String>> = HashMap::new();
+        let mut stmt =
+            conn.prepare("SELECT track_id, field FROM track_field_locks ORDER BY track_id, field")?;
+        for row in stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get(1)?)))? {
+            let (track_id, field) = row?;
+            locks.entry(track_id).or_default().push(field);
+        }
+        let mut stmt = conn.prepare(
             r#"
-            UPDATE albums
-            SET orphaned_at = ?1
-            WHERE orphaned_at IS NULL
-              AND id NOT IN (SELECT DISTINCT album_id FROM tracks WHERE album_id IS NOT NULL)
+            SELECT t.id, t.path, t.content_hash, COALESCE(tr.favorite, 0), tr.rating, t.mbid
+            FROM tracks t
+            LEFT JOIN track_ratings tr ON tr.track_id = t.id
+            WHERE tr.track_id IS NOT NULL
+               OR COALESCE(t.mbid, '') != ''
+               OR t.id IN (SELECT track_id FROM track_field_locks)
+            ORDER BY t.path
             "#,
-            params![now_ms],
-        )
-        .context("mark orphaned albums")?;
-        tx.execute(
+        )?;
+        let tracks = stmt
+            .query_map([], |row| {
+                let track_id: i64 = row.get(0)?;
+                Ok(TrackExport {
+                    track: track_ref(row, 1)?,
+                    favorite: row.get::<_, i64>(3)? != 0,
+                    rating: row.get(4)?,
+                    mbid: row.get::<_, Option<String>>(5)?.filter(|v| !v.is_empty()),
+                    locked_fields: locks.remove(&track_id).unwrap_or_default(),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let notes = locked_texts(&conn, "album_notes", "album_id")?;
+        let mut stmt = conn.prepare(
             r#"
-            UPDATE albums
-            SET orphaned_at = NULL
-            WHERE orphaned_at IS NOT NULL
-              AND id IN (SELECT DISTINCT album_id FROM tracks WHERE album_id IS NOT NULL)
+            SELECT al.id, al.title, ar.name, t.path, t.content_hash,
+                   COALESCE(r.favorite, 0), r.rating, al.mbid
+            FROM albums al
+            LEFT JOIN artists ar ON ar.id = al.artist_id
+            LEFT JOIN album_ratings r ON r.album_id = al.id
+            LEFT JOIN tracks t ON t.id = (SELECT MIN(id) FROM tracks WHERE album_id = al.id)
+            WHERE al.orphaned_at IS NULL
+              AND (r.album_id IS NOT NULL
+                   OR COALESCE(al.mbid, '') != ''
+                   OR al.id IN (SELECT album_id FROM album_notes WHERE locked = 1))
+            ORDER BY al.title COLLATE NOCASE, al.id
             "#,
-            [],
-        )
-        .context("clear orphaned albums")?;
-        tx.commit().context("commit metadata tx")?;
-        Ok(())
+        )?;
+        let albums = stmt
+            .query_map([], |row| {
+                let album_id: i64 = row.get(0)?;
+                let track = match row.get::<_, Option<String>>(3)? {
+                    Some(_) => Some(track_ref(row, 3)?),
+                    None => None,
+                };
+                Ok(AlbumExport {
+                    title: row.get(1)?,
+                    artist: row.get(2)?,
+                    track,
+                    favorite: row.get::<_, i64>(5)? != 0,
+                    rating: row.get(6)?,
+                    mbid: row.get::<_, Option<String>>(7)?.filter(|v| !v.is_empty()),
+                    notes: notes.get(&album_id).cloned().unwrap_or_default(),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let bios = locked_texts(&conn, "artist_bios", "artist_id")?;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, name, mbid FROM artists
+            WHERE COALESCE(mbid, '') != ''
+               OR id IN (SELECT artist_id FROM artist_bios WHERE locked = 1)
+            ORDER BY name COLLATE NOCASE, id
+            "#,
+        )?;
+        let artists = stmt
+            .query_map([], |row| {
+                let artist_id: i64 = row.get(0)?;
+                Ok(ArtistExport {
+                    name: row.get(1)?,
+                    mbid: row.get::<_, Option<String>>(2)?.filter(|v| !v.is_empty()),
+                    bios: bios.get(&artist_id).cloned().unwrap_or_default(),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut entries: HashMap<i64, Vec<TrackRef>> = HashMap::new();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT pt.playlist_id, t.path, t.content_hash
+            FROM playlist_tracks pt
+            JOIN tracks t ON t.id = pt.track_id
+            ORDER BY pt.playlist_id, pt.position
+            "#,
+        )?;
+        for row in stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, track_ref(row, 1)?)))? {
+            let (playlist_id, track) = row?;
+            entries.entry(playlist_id).or_default().push(track);
+        }
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT p.id, p.name, t.path, t.content_hash
+            FROM playlists p
+            LEFT JOIN tracks t ON t.id = p.cover_track_id
+            ORDER BY p.name COLLATE NOCASE, p.id
+            "#,
+        )?;
+        let playlists = stmt
+            .query_map([], |row| {
+                let playlist_id: i64 = row.get(0)?;
+                let cover = match row.get::<_, Option<String>>(2)? {
+                    Some(_) => Some(track_ref(row, 2)?),
+                    None => None,
+                };
+                Ok(PlaylistExport {
+                    name: row.get(1)?,
+                    cover,
+                    tracks: entries.remove(&playlist_id).unwrap_or_default(),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(MetadataExport {
+            version: METADATA_EXPORT_VERSION,
+            exported_at_ms: now_ms(),
+            tracks,
+            albums,
+            artists,
+            playlists,
+        })
     }
-}
 
-/// Compute canonical DB path under media root.
-fn db_path_for(media_root: &Path) -> PathBuf {
-    media_root.join(".audio-hub").join("metadata.sqlite")
-}
+    /// Merge an export into this library. Entries are matched against scanned tracks,
+    /// albums and artists; imported values replace local ones, and playlists with the
+    /// same name are replaced.
+    pub fn import_user_data(&self, data: &MetadataExport) -> Result<MetadataImportSummary> {
+        let mut conn = self.pool.get().context("open metadata db")?;
+        let tx = conn.transaction().context("begin import tx")?;
+        let mut summary = MetadataImportSummary::default();
+        let now_ms = now_ms();
 
-/// Best-effort canonicalized media root for path normalization.
-fn normalize_media_root(media_root: &Path) -> PathBuf {
-    media_root
-        .canonicalize()
-        .unwrap_or_else(|_| media_root.to_path_buf())
-}
+        for entry in &data.tracks {
+            let Some(track_id) = self.resolve_track_ref(&tx, &entry.track)? else {
+                summary.unmatched += 1;
+                continue;
+            };
+            import_rating(
+                &tx,
+                "track_ratings",
+                "track_id",
+                track_id,
+                entry.favorite,
+                entry.rating,
+            )?;
+            if let Some(mbid) = entry.mbid.as_deref().filter(|v| !v.is_empty()) {
+                tx.execute(
+                    "UPDATE tracks SET mbid = ?1, mb_no_match_key = NULL WHERE id = ?2",
+                    params![mbid, track_id],
+                )?;
+            }
+            for field in &entry.locked_fields {
+                tx.execute(
+                    "INSERT OR IGNORE INTO track_field_locks (track_id, field) VALUES (?1, ?2)",
+                    params![track_id, field],
+                )?;
+            }
+            summary.tracks += 1;
+        }
 
-/// Convert absolute path into media-root-relative path when possible.
-fn relative_from_absolute(path: &Path, media_root: &Path) -> Option<PathBuf> {
-    if let Ok(relative) = path.strip_prefix(media_root) {
-        return Some(relative.to_path_buf());
-    }
-    let media_name = media_root.file_name()?;
-    let mut matched_index: Option<usize> = None;
-    let components: Vec<_> = path.components().collect();
-    for (index, component) in components.iter().enumerate() {
-        if component.as_os_str() == media_name {
-            matched_index = Some(index);
+        for entry in &data.albums {
+            let by_track = match &entry.track {
+                Some(track) => self
+                    .resolve_track_ref(&tx, track)?
+                    .map(|track_id| {
+                        tx.query_row(
+                            "SELECT album_id FROM tracks WHERE id = ?1",
+                            params![track_id],
+                            |row| row.get::<_, Option<i64>>(0),
+                        )
+                    })
+                    .transpose()?
+                    .flatten(),
+                None => None,
+            };
+            let album_id = match by_track {
+                Some(album_id) => Some(album_id),
+                None => tx
+                    .query_row(
+                        r#"
+                        SELECT al.id FROM albums al
+                        LEFT JOIN artists ar ON ar.id = al.artist_id
+                        WHERE al.title = ?1 COLLATE NOCASE
+                          AND ar.name IS ?2 COLLATE NOCASE
+                          AND al.orphaned_at IS NULL
+                        ORDER BY al.id
+                        LIMIT 1
+                        "#,
+                        params![entry.title, entry.artist],
+                        |row| row.get::<_, i64>(0),
+                    )
+                    .optional()?,
+            };
+            let Some(album_id) = album_id else {
+                summary.unmatched += 1;
+                continue;
+            };
+            import_rating(
+                &tx,
+                "album_ratings",
+                "album_id",
+                album_id,
+                entry.favorite,
+                entry.rating,
+            )?;
+            if let Some(mbid) = entry.mbid.as_deref().filter(|v| !v.is_empty()) {
+                tx.execute(
+                    r#"
+                    UPDATE albums
+                    SET mbid = ?1, caa_fail_count = NULL, caa_last_error = NULL,
+                        caa_release_candidates = NULL
+                    WHERE id = ?2 AND mbid IS NOT ?1
+                    "#,
+                    params![mbid, album_id],
+                )?;
+            }
+            for note in &entry.notes {
+                tx.execute(
+                    r#"
+                    INSERT INTO album_notes (album_id, lang, text, source, locked, updated_at_ms)
+                    VALUES (?1, ?2, ?3, ?4, 1, ?5)
+                    ON CONFLICT(album_id, lang) DO UPDATE SET
+                        text = excluded.text,
+                        source = excluded.source,
+                        locked = 1,
+                        updated_at_ms = excluded.updated_at_ms
+                    "#,
+                    params![album_id, note.lang, note.text, note.source, now_ms],
+                )?;
+            }
+            summary.albums += 1;
         }
+
+        for entry in &data.artists {
+            let artist_id = tx
+                .query_row(
+                    "SELECT id FROM artists WHERE name = ?1 COLLATE NOCASE ORDER BY id LIMIT 1",
+                    params![entry.name],
+                    |row| row.get::<_, i64>(0),
+                )
+                .optional()?;
+            let Some(artist_id) = artist_id else {
+                summary.unmatched += 1;
+                continue;
+            };
+            if let Some(mbid) = entry.mbid.as_deref().filter(|v| !v.is_empty()) {
+                tx.execute(
+                    "UPDATE artists SET mbid = ?1 WHERE id = ?2",
+                    params![mbid, artist_id],
+                )?;
+            }
+            for bio in &entry.bios {
+                tx.execute(
+                    r#"
+                    INSERT INTO artist_bios (artist_id, lang, text, source, locked, updated_at_ms)
+                    VALUES (?1, ?2, ?3, ?4, 1, ?5)
+                    ON CONFLICT(artist_id, lang) DO UPDATE SET
+                        text = excluded.text,
+                        source = excluded.source,
+                        locked = 1,
+                        updated_at_ms = excluded.updated_at_ms
+                    "#,
+                    params![artist_id, bio.lang, bio.text, bio.source, now_ms],
+                )?;
+            }
+            summary.artists += 1;
+        }
+
+        for entry in &data.playlists {
+            let mut track_ids = Vec::with_capacity(entry.tracks.len());
+            for track in &entry.tracks {
+                match self.resolve_track_ref(&tx, track)? {
+                    Some(track_id) => track_ids.push(track_id),
+                    None => summary.unmatched_playlist_tracks += 1,
+                }
+            }
+            let cover_track_id = match &entry.cover {
+                Some(cover) => self.resolve_track_ref(&tx, cover)?,
+                None => None,
+            };
+            let existing = tx
+                .query_row(
+                    "SELECT id FROM playlists WHERE name = ?1 ORDER BY id LIMIT 1",
+                    params![entry.name],
+                    |row| row.get::<_, i64>(0),
+                )
+                .optional()?;
+            let playlist_id = match existing {
+                Some(playlist_id) => {
+                    tx.execute(
+                        "UPDATE playlists SET cover_track_id = ?1, updated_at_ms = ?2 WHERE id = ?3",
+                        params![cover_track_id, now_ms, playlist_id],
+                    )?;
+                    tx.execute(
+                        "DELETE FROM playlist_tracks WHERE playlist_id = ?1",
+                        params![playlist_id],
+                    )?;
+                    playlist_id
+                }
+                None => {
+                    tx.execute(
+                        "INSERT INTO playlists (name, cover_track_id, created_at_ms, updated_at_ms) VALUES (?1, ?2, ?3, ?3)",
+                        params![entry.name, cover_track_id, now_ms],
+                    )?;
+                    tx.last_insert_rowid()
+                }
+            };
+            insert_playlist_tracks(&tx, playlist_id, &track_ids)?;
+            summary.playlists += 1;
+        }
+
+        tx.commit().context("commit import tx")?;
+        Ok(summary)
     }
-    let start = matched_index?;
-    let relative = components
-        .iter()
-        .skip(start + 1)
-        .fold(PathBuf::new(), |mut acc, component| {
-            acc.push(component.as_os_str());
-            acc
-        });
-    if relative.as_os_str().is_empty() {
-        return None;
-    }
-    if media_root.join(&relative).exists() {
-        Some(relative)
-    } else {
-        None
+
+    /// Find the track an exported reference points at: by path, then by content hash.
+    fn resolve_track_ref(&self, conn: &Connection, track: &TrackRef) -> Result<Option<i64>> {
+        let by_path = conn
+            .query_row(
+                "SELECT id FROM tracks WHERE path = ?1",
+                params![self.path_to_db(&track.path)],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()?;
+        if by_path.is_some() {
+            return Ok(by_path);
+        }
+        let Some(hash) = track.content_hash.as_deref() else {
+            return Ok(None);
+        };
+        conn.query_row(
+            "SELECT id FROM tracks WHERE content_hash = ?1 ORDER BY id LIMIT 1",
+            params![hash],
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .context("resolve track by content hash")
     }
-}
 
-/// Return true when optional string is missing or whitespace-only.
-fn is_blank(value: &Option<String>) -> bool {
-    value
-        .as_deref()
-        .map(|s| s.trim().is_empty())
-        .unwrap_or(true)
-}
+    /// List API tokens, oldest first.
+    pub fn list_api_tokens(&self) -> Result<Vec<ApiTokenInfo>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, role, created_at_ms, last_used_at_ms FROM api_tokens ORDER BY id",
+        )?;
+        let rows = stmt.query_map([], api_token_from_row)?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .context("list api tokens")
+    }
 
-/// Fill missing UUID values for all rows in a table.
-fn backfill_uuids(conn: &Connection, table: &str) -> Result<()> {
-    let mut stmt = conn.prepare(&format!("SELECT id FROM {table} WHERE uuid IS NULL"))?;
-    let ids = stmt.query_map([], |row| row.get::<_, i64>(0))?;
-    for id in ids.filter_map(Result::ok) {
-        let uuid = Uuid::new_v4().to_string();
+    /// Store a new API token by the hash of its secret.
+    pub fn create_api_token(
+        &self,
+        name: &str,
+        role: TokenRole,
+        token_hash: &str,
+    ) -> Result<ApiTokenInfo> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let created_at_ms = now_ms();
         conn.execute(
-            &format!("UPDATE {table} SET uuid = ?1 WHERE id = ?2"),
-            params![uuid, id],
+            "INSERT INTO api_tokens (name, role, token_hash, created_at_ms) VALUES (?1, ?2, ?3, ?4)",
+            params![name, role.as_str(), token_hash, created_at_ms],
         )
-        .with_context(|| format!("backfill {table} uuid"))?;
+        .context("insert api token")?;
+        Ok(ApiTokenInfo {
+            id: conn.last_insert_rowid(),
+            name: name.to_string(),
+            role,
+            created_at_ms,
+            last_used_at_ms: None,
+        })
     }
-    Ok(())
-}
 
-/// Ensure UUID unique indexes exist on artists/albums.
-fn ensure_uuid_indexes(conn: &Connection) -> Result<()> {
-    conn.execute_batch(
-        r#"
-        CREATE UNIQUE INDEX IF NOT EXISTS idx_artists_uuid ON artists(uuid);
-        CREATE UNIQUE INDEX IF NOT EXISTS idx_albums_uuid ON albums(uuid);
-        "#,
-    )
-    .context("create uuid indexes")?;
-    Ok(())
-}
+    /// Delete an API token; returns false if it did not exist.
+    pub fn delete_api_token(&self, token_id: i64) -> Result<bool> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let deleted = conn
+            .execute("DELETE FROM api_tokens WHERE id = ?1", params![token_id])
+            .context("delete api token")?;
+        Ok(deleted > 0)
+    }
 
-/// Ensure one row has a non-empty UUID value.
-fn ensure_row_uuid(conn: &Connection, table: &str, id: i64) -> Result<()> {
-    let existing: Option<String> = conn
-        .query_row(
-            &format!("SELECT uuid FROM {table} WHERE id = ?1"),
-            params![id],
+    /// Number of stored API tokens, optionally only those with `role`.
+    pub fn count_api_tokens(&self, role: Option<TokenRole>) -> Result<i64> {
+        let conn = self.pool.get().context("open metadata db")?;
+        conn.query_row(
+            "SELECT COUNT(*) FROM api_tokens WHERE ?1 IS NULL OR role = ?1",
+            params![role.map(TokenRole::as_str)],
             |row| row.get(0),
         )
-        .optional()
-        .with_context(|| format!("select {table} uuid"))?;
-    if existing
-        .as_deref()
-        .map(|v| v.trim().is_empty())
-        .unwrap_or(true)
-    {
-        let uuid = Uuid::new_v4().to_string();
-        conn.execute(
-            &format!("UPDATE {table} SET uuid = ?1 WHERE id = ?2"),
-            params![uuid, id],
-        )
-        .with_context(|| format!("update {table} uuid"))?;
+        .context("count api tokens")
     }
-    Ok(())
-}
 
-/// Lookup artist id by exact artist name.
-fn find_artist_id(conn: &Connection, name: &str) -> Result<Option<i64>> {
-    let id = conn
-        .query_row(
-            "SELECT id FROM artists WHERE name = ?1",
-            params![name],
-            |row| row.get(0),
+    /// Look up a token by the hash of its secret and record the use.
+    pub fn use_api_token(&self, token_hash: &str) -> Result<Option<ApiTokenInfo>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let now_ms = now_ms();
+        conn.query_row(
+            r#"
+            UPDATE api_tokens SET last_used_at_ms = ?2
+            WHERE token_hash = ?1
+            RETURNING id, name, role, created_at_ms, last_used_at_ms
+            "#,
+            params![token_hash, now_ms],
+            api_token_from_row,
         )
         .optional()
-        .context("find artist id")?;
-    Ok(id)
-}
+        .context("use api token")
+    }
 
-/// Lookup album id by exact `(title, artist_id)` pair.
-fn find_album_id(conn: &Connection, title: &str, artist_id: Option<i64>) -> Result<Option<i64>> {
-    let id = conn
-        .query_row(
-            "SELECT id FROM albums WHERE title = ?1 AND artist_id IS ?2",
-            params![title, artist_id],
-            |row| row.get(0),
-        )
-        .optional()
-        .context("find album id")?;
-    Ok(id)
-}
+    /// List composers with album/track/work counts, optional name search and paging.
+    pub fn list_composers(
+        &self,
+        search: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ComposerSummary>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let search_like = search.map(|s| format!("%{}%", s.to_lowercase()));
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT MIN(t.composer),
+                   COUNT(DISTINCT t.album_id) AS album_count,
+                   COUNT(t.id) AS track_count,
+                   COUNT(DISTINCT LOWER(t.work)) AS work_count
+            FROM tracks t
+            WHERE t.composer IS NOT NULL
+              AND (?1 IS NULL OR LOWER(t.composer) LIKE ?1)
+            GROUP BY t.composer COLLATE NOCASE
+            ORDER BY t.composer COLLATE NOCASE
+            LIMIT ?2 OFFSET ?3
+            "#,
+        )?;
+        let rows = stmt.query_map(params![search_like, limit, offset], |row| {
+            Ok(ComposerSummary {
+                name: row.get(0)?,
+                album_count: row.get(1)?,
+                track_count: row.get(2)?,
+                work_count: row.get(3)?,
+            })
+        })?;
 
-/// Initialize/migrate metadata schema to current version.
-fn init_schema(conn: &Connection) -> Result<()> {
-    conn.execute_batch(
-        r#"
-        CREATE TABLE IF NOT EXISTS meta (
-            key TEXT PRIMARY KEY,
-            value TEXT NOT NULL
-        );
+        Ok(rows.filter_map(Result::ok).collect())
+    }
 
-        CREATE TABLE IF NOT EXISTS artists (
-            id INTEGER PRIMARY KEY,
-            uuid TEXT,
-            name TEXT NOT NULL,
-            sort_name TEXT,
-            mbid TEXT
-        );
+    /// List the discs of an album in disc order.
+    pub fn album_discs(&self, album_id: i64) -> Result<Vec<AlbumDisc>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT COALESCE(disc_number, 1) AS disc,
+                   MAX(disc_title),
+                   COUNT(id),
+                   SUM(duration_ms)
+            FROM tracks
+            WHERE album_id = ?1
+            GROUP BY disc
+            ORDER BY disc
+            "#,
+        )?;
+        let rows = stmt.query_map(params![album_id], |row| {
+            Ok(AlbumDisc {
+                disc_number: row.get::<_, i64>(0)? as u32,
+                title: row.get(1)?,
+                track_count: row.get(2)?,
+                duration_ms: row.get::<_, Option<i64>>(3)?.map(|v| v as u64),
+            })
+        })?;
 
-        CREATE TABLE IF NOT EXISTS albums (
-            id INTEGER PRIMARY KEY,
+        Ok(rows.filter_map(Result::ok).collect())
+    }
+
+    /// List track paths belonging to an album id.
+    pub fn list_track_paths_by_album_id(&self, album_id: i64) -> Result<Vec<String>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut stmt = conn.prepare(
+            "SELECT path FROM tracks WHERE album_id = ?1 ORDER BY COALESCE(disc_number, 0), COALESCE(track_number, 0), file_name",
+        )?;
+        let rows = stmt.query_map(params![album_id], |row| row.get(0))?;
+        Ok(rows
+            .filter_map(Result::ok)
+            .map(|path: String| self.path_from_db(path))
+            .collect())
+    }
+
+    /// List all track paths currently in DB.
+    pub fn list_all_track_paths(&self) -> Result<Vec<String>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut stmt = conn.prepare("SELECT path FROM tracks")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        Ok(rows
+            .filter_map(Result::ok)
+            .map(|path: String| self.path_from_db(path))
+            .collect())
+    }
+
+    /// Update album metadata, merging rows when title+artist collide.
+    pub fn update_album_metadata(
+        &self,
+        album_id: i64,
+        title: Option<&str>,
+        artist: Option<&str>,
+        year: Option<i32>,
+    ) -> Result<Option<i64>> {
+        let mut conn = self.pool.get().context("open metadata db")?;
+        let tx = conn.transaction().context("begin metadata tx")?;
+        let current: Option<(String, Option<i64>)> = tx
+            .query_row(
+                "SELECT title, artist_id FROM albums WHERE id = ?1",
+                params![album_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .context("lookup current album")?;
+        let Some((current_title, current_artist_id)) = current else {
+            return Ok(None);
+        };
+        let artist_id = if let Some(name) = artist {
+            Some(upsert_artist(&tx, name)?)
+        } else {
+            None
+        };
+        let desired_title = title.unwrap_or(current_title.as_str());
+        let desired_artist_id = artist_id.or(current_artist_id);
+        if let Some(existing_id) = find_album_id(&tx, desired_title, desired_artist_id)? {
+            if existing_id != album_id {
+                tx.execute(
+                    r#"
+                    UPDATE albums
+                    SET title = ?1,
+                        sort_title = LOWER(?1),
+                        artist_id = ?2,
+                        year = COALESCE(?3, year)
+                    WHERE id = ?4
+                    "#,
+                    params![desired_title, desired_artist_id, year, existing_id],
+                )
+                .context("merge album metadata")?;
+                tx.execute(
+                    "UPDATE tracks SET album_id = ?1 WHERE album_id = ?2",
+                    params![existing_id, album_id],
+                )
+                .context("reassign tracks to merged album")?;
+                tx.execute("DELETE FROM albums WHERE id = ?1", params![album_id])
+                    .context("delete merged album")?;
+                tx.commit().context("commit metadata tx")?;
+                return Ok(Some(existing_id));
+            }
+        }
+        let updated = tx
+            .execute(
+                r#"
+                UPDATE albums
+                SET title = COALESCE(?1, title),
+                    sort_title = CASE WHEN ?1 IS NULL THEN sort_title ELSE LOWER(?1) END,
+                    artist_id = COALESCE(?2, artist_id),
+                    year = COALESCE(?3, year)
+                WHERE id = ?4
+                "#,
+                params![title, artist_id, year, album_id],
+            )
+            .context("update album metadata")?;
+        tx.commit().context("commit metadata tx")?;
+        if updated > 0 {
+            Ok(Some(album_id))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// List FLAC tracks that were never checked for a lossy source, or changed since.
+    pub fn list_lossy_check_candidates(&self, limit: i64) -> Result<Vec<LossyCheckCandidate>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT t.id, t.path, COALESCE(t.mtime_ms, 0)
+            FROM tracks t
+            LEFT JOIN track_lossy_checks c ON c.track_id = t.id
+            WHERE UPPER(t.format) = 'FLAC'
+              AND (c.track_id IS NULL OR c.mtime_ms != COALESCE(t.mtime_ms, 0))
+            ORDER BY t.id
+            LIMIT ?1
+            "#,
+        )?;
+        let rows = stmt.query_map(params![limit], |row| {
+            let path: String = row.get(1)?;
+            Ok(LossyCheckCandidate {
+                track_id: row.get(0)?,
+                path: self.path_from_db(path),
+                mtime_ms: row.get(2)?,
+            })
+        })?;
+
+        Ok(rows.filter_map(Result::ok).collect())
+    }
+
+    /// Store the lossy-source check result for a track at `mtime_ms`.
+    pub fn upsert_lossy_check(
+        &self,
+        track_id: i64,
+        mtime_ms: i64,
+        result: &LossyCheckResult,
+    ) -> Result<()> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        conn.execute(
+            r#"
+            INSERT INTO track_lossy_checks
+                (track_id, mtime_ms, cutoff_hz, cutoff_drop_db, confidence, error, checked_at_ms)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            ON CONFLICT(track_id) DO UPDATE SET
+                mtime_ms = excluded.mtime_ms,
+                cutoff_hz = excluded.cutoff_hz,
+                cutoff_drop_db = excluded.cutoff_drop_db,
+                confidence = excluded.confidence,
+                error = excluded.error,
+                checked_at_ms = excluded.checked_at_ms
+            "#,
+            params![
+                track_id,
+                mtime_ms,
+                result.cutoff_hz,
+                result.cutoff_drop_db,
+                result.confidence,
+                result.error,
+                now_ms
+            ],
+        )
+        .context("upsert lossy check")?;
+        Ok(())
+    }
+
+    /// List tracks the loudness job should measure, grouped by album.
+    ///
+    /// Up to `limit` albums with a track never measured or modified since are returned with
+    /// all their tracks, followed by up to `limit` such tracks without an album.
+    pub fn list_loudness_candidates(&self, limit: i64) -> Result<Vec<LoudnessCandidate>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut candidates = Vec::new();
+        for sql in [
+            r#"
+            SELECT t.id, t.path, COALESCE(t.mtime_ms, 0), t.album_id
+            FROM tracks t
+            WHERE t.album_id IN (
+                SELECT DISTINCT s.album_id
+                FROM tracks s
+                LEFT JOIN track_loudness l ON l.track_id = s.id
+                WHERE s.album_id IS NOT NULL
+                  AND (l.track_id IS NULL OR l.mtime_ms != COALESCE(s.mtime_ms, 0))
+                ORDER BY s.album_id
+                LIMIT ?1
+            )
+            ORDER BY t.album_id, t.id
+            "#,
+            r#"
+            SELECT t.id, t.path, COALESCE(t.mtime_ms, 0), t.album_id
+            FROM tracks t
+            LEFT JOIN track_loudness l ON l.track_id = t.id
+            WHERE t.album_id IS NULL
+              AND (l.track_id IS NULL OR l.mtime_ms != COALESCE(t.mtime_ms, 0))
+            ORDER BY t.id
+            LIMIT ?1
+            "#,
+        ] {
+            let mut stmt = conn.prepare(sql)?;
+            let rows = stmt.query_map(params![limit], |row| {
+                let path: String = row.get(1)?;
+                Ok(LoudnessCandidate {
+                    track_id: row.get(0)?,
+                    path: self.path_from_db(path),
+                    mtime_ms: row.get(2)?,
+                    album_id: row.get(3)?,
+                })
+            })?;
+            candidates.extend(rows.filter_map(Result::ok));
+        }
+        Ok(candidates)
+    }
+
+    /// Store the loudness measurement for a track at `mtime_ms`.
+    pub fn upsert_loudness(
+        &self,
+        track_id: i64,
+        mtime_ms: i64,
+        result: &LoudnessResult,
+    ) -> Result<()> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        conn.execute(
+            r#"
+            INSERT INTO track_loudness
+                (track_id, mtime_ms, integrated_lufs, true_peak_db, album_lufs, album_peak_db,
+                 error, checked_at_ms)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT(track_id) DO UPDATE SET
+                mtime_ms = excluded.mtime_ms,
+                integrated_lufs = excluded.integrated_lufs,
+                true_peak_db = excluded.true_peak_db,
+                album_lufs = excluded.album_lufs,
+                album_peak_db = excluded.album_peak_db,
+                error = excluded.error,
+                checked_at_ms = excluded.checked_at_ms
+            "#,
+            params![
+                track_id,
+                mtime_ms,
+                result.integrated_lufs,
+                result.true_peak_db,
+                result.album_lufs,
+                result.album_peak_db,
+                result.error,
+                now_ms
+            ],
+        )
+        .context("upsert loudness")?;
+        Ok(())
+    }
+
+    /// List tracks never analyzed for tempo/key, or modified since.
+    pub fn list_tempo_key_candidates(&self, limit: i64) -> Result<Vec<TempoKeyCandidate>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT t.id, t.path, COALESCE(t.mtime_ms, 0)
+            FROM tracks t
+            LEFT JOIN track_tempo_key k ON k.track_id = t.id
+            WHERE k.track_id IS NULL OR k.mtime_ms != COALESCE(t.mtime_ms, 0)
+            ORDER BY t.id
+            LIMIT ?1
+            "#,
+        )?;
+        let rows = stmt.query_map(params![limit], |row| {
+            let path: String = row.get(1)?;
+            Ok(TempoKeyCandidate {
+                track_id: row.get(0)?,
+                path: self.path_from_db(path),
+                mtime_ms: row.get(2)?,
+            })
+        })?;
+
+        Ok(rows.filter_map(Result::ok).collect())
+    }
+
+    /// Store the tempo/key analysis for a track at `mtime_ms`.
+    pub fn upsert_tempo_key(
+        &self,
+        track_id: i64,
+        mtime_ms: i64,
+        result: &TempoKeyResult,
+    ) -> Result<()> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        conn.execute(
+            r#"
+            INSERT INTO track_tempo_key (track_id, mtime_ms, bpm, musical_key, error, checked_at_ms)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT(track_id) DO UPDATE SET
+                mtime_ms = excluded.mtime_ms,
+                bpm = excluded.bpm,
+                musical_key = excluded.musical_key,
+                error = excluded.error,
+                checked_at_ms = excluded.checked_at_ms
+            "#,
+            params![
+                track_id,
+                mtime_ms,
+                result.bpm,
+                result.key,
+                result.error,
+                now_ms
+            ],
+        )
+        .context("upsert tempo/key")?;
+        Ok(())
+    }
+
+    /// List checked tracks with a lossy-source confidence of at least `min_confidence`,
+    /// most suspicious first.
+    pub fn list_lossy_report(
+        &self,
+        min_confidence: f32,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<LossyReportEntry>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT t.id, t.file_name, t.title, ar.name, al.title, t.sample_rate, t.bit_depth,
+                   c.cutoff_hz, c.cutoff_drop_db, c.confidence, c.checked_at_ms
+            FROM track_lossy_checks c
+            JOIN tracks t ON t.id = c.track_id
+            LEFT JOIN artists ar ON ar.id = t.artist_id
+            LEFT JOIN albums al ON al.id = t.album_id
+            WHERE c.confidence IS NOT NULL AND c.confidence >= ?1
+            ORDER BY c.confidence DESC, t.id
+            LIMIT ?2 OFFSET ?3
+            "#,
+        )?;
+        let rows = stmt.query_map(params![min_confidence, limit, offset], |row| {
+            Ok(LossyReportEntry {
+                track_id: row.get(0)?,
+                file_name: row.get(1)?,
+                title: row.get(2)?,
+                artist: row.get(3)?,
+                album: row.get(4)?,
+                sample_rate: row.get::<_, Option<i64>>(5)?.map(|v| v as u32),
+                bit_depth: row.get::<_, Option<i64>>(6)?.map(|v| v as u32),
+                cutoff_hz: row.get(7)?,
+                cutoff_drop_db: row.get(8)?,
+                confidence: row.get(9)?,
+                checked_at_ms: row.get(10)?,
+            })
+        })?;
+
+        Ok(rows.filter_map(Result::ok).collect())
+    }
+
+    /// Fields of a track locked against album-wide tag updates.
+    pub fn track_field_locks(&self, track_id: i64) -> Result<HashSet<String>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut stmt = conn.prepare("SELECT field FROM track_field_locks WHERE track_id = ?1")?;
+        let rows = stmt.query_map(params![track_id], |row| row.get(0))?;
+        Ok(rows.filter_map(Result::ok).collect())
+    }
+
+    /// Lock and unlock track fields (unlocks apply after locks).
+    pub fn update_track_field_locks(
+        &self,
+        track_id: i64,
+        lock: &[String],
+        unlock: &[String],
+    ) -> Result<()> {
+        let mut conn = self.pool.get().context("open metadata db")?;
+        let tx = conn.transaction().context("begin track locks")?;
+        for field in lock {
+            tx.execute(
+                "INSERT OR IGNORE INTO track_field_locks (track_id, field) VALUES (?1, ?2)",
+                params![track_id, field],
+            )
+            .context("lock track field")?;
+        }
+        for field in unlock {
+            tx.execute(
+                "DELETE FROM track_field_locks WHERE track_id = ?1 AND field = ?2",
+                params![track_id, field],
+            )
+            .context("unlock track field")?;
+        }
+        tx.commit().context("commit track locks")?;
+        Ok(())
+    }
+
+    /// Fetch stored lyrics for a track.
+    pub fn track_lyrics(&self, track_id: i64) -> Result<Option<TrackLyrics>> {
+        let conn = self.pool.get().context("open metadata db")?;
+        conn.query_row(
+            "SELECT plain, synced, source, updated_at_ms FROM track_lyrics WHERE track_id = ?1",
+            params![track_id],
+            |row| {
+                Ok(TrackLyrics {
+                    plain: row.get(0)?,
+                    synced: row.get(1)?,
+                    source: row.get(2)?,
+                    updated_at_ms: row.get(3)?,
+                })
+            },
+        )
+        .optional()
+        .context("fetch track lyrics")
+    }
+
+    /// Insert or replace lyrics for a track.
+    pub fn store_track_lyrics(&self, track_id: i64, lyrics: &TrackLyrics) -> Result<()> {
+        let conn = self.pool.get().context("open metadata db")?;
+        conn.execute(
+            r#"
+            INSERT INTO track_lyrics (track_id, plain, synced, source, updated_at_ms)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT(track_id) DO UPDATE SET
+                plain = excluded.plain,
+                synced = excluded.synced,
+                source = excluded.source,
+                updated_at_ms = excluded.updated_at_ms
+            "#,
+            params![
+                track_id,
+                lyrics.plain,
+                lyrics.synced,
+                lyrics.source,
+                lyrics.updated_at_ms
+            ],
+        )
+        .context("store track lyrics")?;
+        Ok(())
+    }
+
+    /// Sync scanned lyrics for the track at `path`.
+    ///
+    /// Local lyrics replace whatever is stored; without them, earlier local lyrics are
+    /// dropped while fetched ones are kept.
+    pub fn sync_local_lyrics(&self, path: &str, lyrics: Option<&TrackLyrics>) -> Result<()> {
+        let Some(track_id) = self.track_id_for_path(path)? else {
+            return Ok(());
+        };
+        match lyrics {
+            Some(lyrics) => self.store_track_lyrics(track_id, lyrics),
+            None => {
+                let conn = self.pool.get().context("open metadata db")?;
+                conn.execute(
+                    "DELETE FROM track_lyrics WHERE track_id = ?1 AND source IN ('sidecar', 'embedded')",
+                    params![track_id],
+                )
+                .context("clear local track lyrics")?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Delete one track by path.
+    pub fn delete_track_by_path(&self, path: &str) -> Result<bool> {
+        let conn = self.pool.get().context("open metadata db")?;
+        let db_path = self.path_to_db(path);
+        let deleted = conn
+            .execute("DELETE FROM tracks WHERE path = ?1", params![db_path])
+            .context("delete track by path")?;
+        Ok(deleted > 0)
+    }
+
+    /// Full-text search over artist names, album titles and track titles.
+    ///
+    /// Every word of `query` must match (as a prefix) the item or its artist/album.
+    /// Hits are ranked by relevance, title matches above artist/album matches.
+    pub fn search(
+        &self,
+        query: &str,
+        kind: Option<SearchKind>,
+        limit: i64,
+    ) -> Result<Vec<SearchHit>> {
+        let Some(fts_query) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        let conn = self.pool.get().context("open metadata db")?;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT search_index.rowid % 4, search_index.rowid / 4,
+                   -bm25(search_index, 10.0, 1.0) AS score,
+                   COALESCE(ar.name, al.title, t.title, t.file_name),
+                   owner.name, owner.id, tal.title, tal.id,
+                   COALESCE(al.cover_art_path, tal.cover_art_path)
+            FROM search_index
+            LEFT JOIN artists ar ON search_index.rowid % 4 = 0 AND ar.id = search_index.rowid / 4
+            LEFT JOIN albums al ON search_index.rowid % 4 = 1 AND al.id = search_index.rowid / 4
+            LEFT JOIN tracks t ON search_index.rowid % 4 = 2 AND t.id = search_index.rowid / 4
+            LEFT JOIN albums tal ON tal.id = t.album_id
+            LEFT JOIN artists owner ON owner.id = COALESCE(al.artist_id, t.artist_id)
+            WHERE search_index MATCH ?1
+              AND (?2 IS NULL OR search_index.rowid % 4 = ?2)
+              AND (al.id IS NULL OR al.orphaned_at IS NULL)
+            ORDER BY score DESC
+            LIMIT ?3
+            "#,
+        )?;
+        let rows = stmt.query_map(
+            params![fts_query, kind.map(SearchKind::code), limit],
+            |row| {
+                let kind = SearchKind::from_code(row.get(0)?).unwrap_or(SearchKind::Track);
+                let id: i64 = row.get(1)?;
+                let cover_path: Option<String> = row.get(8)?;
+                let cover_art_url = cover_path
+                    .as_deref()
+                    .filter(|value| !value.trim().is_empty())
+                    .and_then(|_| match kind {
+                        SearchKind::Artist => None,
+                        SearchKind::Album => Some(format!("/albums/{id}/cover")),
+                        SearchKind::Track => Some(format!("/tracks/{id}/cover")),
+                    });
+                Ok(SearchHit {
+                    kind,
+                    id,
+                    score: row.get(2)?,
+                    name: row.get(3)?,
+                    artist: row.get(4)?,
+                    artist_id: row.get(5)?,
+                    album: row.get(6)?,
+                    album_id: row.get(7)?,
+                    cover_art_url,
+                })
+            },
+        )?;
+
+        Ok(rows.filter_map(Result::ok).collect())
+    }
+
+    /// Mark/clear orphaned albums according to current track references; drop unused genres.
+    pub fn prune_orphaned_albums_and_artists(&self) -> Result<()> {
+        let mut conn = self.pool.get().context("open metadata db")?;
+        let tx = conn.transaction().context("begin metadata tx")?;
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        tx.execute(
+            r#"
+            UPDATE albums
+            SET orphaned_at = ?1
+            WHERE orphaned_at IS NULL
+              AND id NOT IN (SELECT DISTINCT album_id FROM tracks WHERE album_id IS NOT NULL)
+            "#,
+            params![now_ms],
+        )
+        .context("mark orphaned albums")?;
+        tx.execute(
+            r#"
+            UPDATE albums
+            SET orphaned_at = NULL
+            WHERE orphaned_at IS NOT NULL
+              AND id IN (SELECT DISTINCT album_id FROM tracks WHERE album_id IS NOT NULL)
+            "#,
+            [],
+        )
+        .context("clear orphaned albums")?;
+        tx.execute(
+            "DELETE FROM genres WHERE id NOT IN (SELECT genre_id FROM track_genres)",
+            [],
+        )
+        .context("delete unused genres")?;
+        tx.commit().context("commit metadata tx")?;
+        Ok(())
+    }
+}
+
+/// Most words of a search query that are matched.
+const MAX_SEARCH_TERMS: usize = 16;
+
+/// Turn free text into an FTS5 query: every word, quoted, as a prefix.
+///
+/// Quoting keeps FTS5 operators and punctuation in user input from being parsed as syntax.
+fn fts_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split_whitespace()
+        .map(|word| word.replace('"', ""))
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .take(MAX_SEARCH_TERMS)
+        .map(|word| format!("\"{word}\"*"))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Full-text index over artists, albums and tracks, kept current by triggers.
+///
+/// A row's rowid is `id * 4 + kind` (see [`SearchKind::code`]); `context` holds the
+/// artist/album names an item can also be found by.
+const SEARCH_INDEX_SCHEMA: &str = r#"
+    CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5(
+        name, context, tokenize = 'unicode61 remove_diacritics 2'
+    );
+
+    CREATE VIEW IF NOT EXISTS search_source (kind, id, name, context) AS
+        SELECT 0, id, name, '' FROM artists
+        UNION ALL
+        SELECT 1, al.id, al.title, COALESCE(ar.name, '')
+        FROM albums al LEFT JOIN artists ar ON ar.id = al.artist_id
+        UNION ALL
+        SELECT 2, t.id, COALESCE(t.title, t.file_name),
+               TRIM(COALESCE(ar.name, '') || ' ' || COALESCE(al.title, ''))
+        FROM tracks t
+        LEFT JOIN artists ar ON ar.id = t.artist_id
+        LEFT JOIN albums al ON al.id = t.album_id;
+
+    CREATE TRIGGER IF NOT EXISTS search_artists_insert AFTER INSERT ON artists BEGIN
+        INSERT INTO search_index (rowid, name, context)
+            SELECT id * 4, name, context FROM search_source WHERE kind = 0 AND id = new.id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS search_artists_update AFTER UPDATE OF name ON artists BEGIN
+        DELETE FROM search_index WHERE rowid = old.id * 4;
+        INSERT INTO search_index (rowid, name, context)
+            SELECT id * 4, name, context FROM search_source WHERE kind = 0 AND id = new.id;
+        DELETE FROM search_index
+            WHERE rowid IN (SELECT id * 4 + 1 FROM albums WHERE artist_id = new.id);
+        INSERT INTO search_index (rowid, name, context)
+            SELECT id * 4 + 1, name, context FROM search_source
+            WHERE kind = 1 AND id IN (SELECT id FROM albums WHERE artist_id = new.id);
+        DELETE FROM search_index
+            WHERE rowid IN (SELECT id * 4 + 2 FROM tracks WHERE artist_id = new.id);
+        INSERT INTO search_index (rowid, name, context)
+            SELECT id * 4 + 2, name, context FROM search_source
+            WHERE kind = 2 AND id IN (SELECT id FROM tracks WHERE artist_id = new.id);
+    END;
+    CREATE TRIGGER IF NOT EXISTS search_artists_delete AFTER DELETE ON artists BEGIN
+        DELETE FROM search_index WHERE rowid = old.id * 4;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS search_albums_insert AFTER INSERT ON albums BEGIN
+        INSERT INTO search_index (rowid, name, context)
+            SELECT id * 4 + 1, name, context FROM search_source WHERE kind = 1 AND id = new.id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS search_albums_update AFTER UPDATE OF title, artist_id ON albums BEGIN
+        DELETE FROM search_index WHERE rowid = old.id * 4 + 1;
+        INSERT INTO search_index (rowid, name, context)
+            SELECT id * 4 + 1, name, context FROM search_source WHERE kind = 1 AND id = new.id;
+        DELETE FROM search_index
+            WHERE rowid IN (SELECT id * 4 + 2 FROM tracks WHERE album_id = new.id);
+        INSERT INTO search_index (rowid, name, context)
+            SELECT id * 4 + 2, name, context FROM search_source
+            WHERE kind = 2 AND id IN (SELECT id FROM tracks WHERE album_id = new.id);
+    END;
+    CREATE TRIGGER IF NOT EXISTS search_albums_delete AFTER DELETE ON albums BEGIN
+        DELETE FROM search_index WHERE rowid = old.id * 4 + 1;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS search_tracks_insert AFTER INSERT ON tracks BEGIN
+        INSERT INTO search_index (rowid, name, context)
+            SELECT id * 4 + 2, name, context FROM search_source WHERE kind = 2 AND id = new.id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS search_tracks_update
+    AFTER UPDATE OF title, file_name, artist_id, album_id ON tracks BEGIN
+        DELETE FROM search_index WHERE rowid = old.id * 4 + 2;
+        INSERT INTO search_index (rowid, name, context)
+            SELECT id * 4 + 2, name, context FROM search_source WHERE kind = 2 AND id = new.id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS search_tracks_delete AFTER DELETE ON tracks BEGIN
+        DELETE FROM search_index WHERE rowid = old.id * 4 + 2;
+    END;
+"#;
+
+/// Refill the search index from the library tables.
+fn rebuild_search_index(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        DELETE FROM search_index;
+        INSERT INTO search_index (rowid, name, context)
+            SELECT id * 4 + kind, name, context FROM search_source;
+        "#,
+    )
+    .context("rebuild search index")
+}
+
+/// Compute canonical DB path under media root.
+fn db_path_for(media_root: &Path) -> PathBuf {
+    media_root.join(".audio-hub").join("metadata.sqlite")
+}
+
+/// Best-effort canonicalized media root for path normalization.
+fn normalize_media_root(media_root: &Path) -> PathBuf {
+    media_root
+        .canonicalize()
+        .unwrap_or_else(|_| media_root.to_path_buf())
+}
+
+/// Convert absolute path into media-root-relative path when possible.
+fn relative_from_absolute(path: &Path, media_root: &Path) -> Option<PathBuf> {
+    if let Ok(relative) = path.strip_prefix(media_root) {
+        return Some(relative.to_path_buf());
+    }
+    let media_name = media_root.file_name()?;
+    let mut matched_index: Option<usize> = None;
+    let components: Vec<_> = path.components().collect();
+    for (index, component) in components.iter().enumerate() {
+        if component.as_os_str() == media_name {
+            matched_index = Some(index);
+        }
+    }
+    let start = matched_index?;
+    let relative = components
+        .iter()
+        .skip(start + 1)
+        .fold(PathBuf::new(), |mut acc, component| {
+            acc.push(component.as_os_str());
+            acc
+        });
+    if relative.as_os_str().is_empty() {
+        return None;
+    }
+    if media_root.join(&relative).exists() {
+        Some(relative)
+    } else {
+        None
+    }
+}
+
+/// Return true when optional string is missing or whitespace-only.
+fn is_blank(value: &Option<String>) -> bool {
+    value
+        .as_deref()
+        .map(|s| s.trim().is_empty())
+        .unwrap_or(true)
+}
+
+/// Fill missing UUID values for all rows in a table.
+fn backfill_uuids(conn: &Connection, table: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("SELECT id FROM {table} WHERE uuid IS NULL"))?;
+    let ids = stmt.query_map([], |row| row.get::<_, i64>(0))?;
+    for id in ids.filter_map(Result::ok) {
+        let uuid = Uuid::new_v4().to_string();
+        conn.execute(
+            &format!("UPDATE {table} SET uuid = ?1 WHERE id = ?2"),
+            params![uuid, id],
+        )
+        .with_context(|| format!("backfill {table} uuid"))?;
+    }
+    Ok(())
+}
+
+/// Ensure UUID unique indexes exist on artists/albums.
+/// Indexes backing the "recently added" lists; created once the columns exist.
+const FIRST_SEEN_INDEXES: &str = r#"
+    CREATE INDEX IF NOT EXISTS idx_tracks_first_seen ON tracks(first_seen_at_ms);
+    CREATE INDEX IF NOT EXISTS idx_albums_first_seen ON albums(first_seen_at_ms);
+"#;
+
+fn ensure_uuid_indexes(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_artists_uuid ON artists(uuid);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_albums_uuid ON albums(uuid);
+        "#,
+    )
+    .context("create uuid indexes")?;
+    Ok(())
+}
+
+/// Ensure one row has a non-empty UUID value.
+fn ensure_row_uuid(conn: &Connection, table: &str, id: i64) -> Result<()> {
+    let existing: Option<String> = conn
+        .query_row(
+            &format!("SELECT uuid FROM {table} WHERE id = ?1"),
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()
+        .with_context(|| format!("select {table} uuid"))?;
+    if existing
+        .as_deref()
+        .map(|v| v.trim().is_empty())
+        .unwrap_or(true)
+    {
+        let uuid = Uuid::new_v4().to_string();
+        conn.execute(
+            &format!("UPDATE {table} SET uuid = ?1 WHERE id = ?2"),
+            params![uuid, id],
+        )
+        .with_context(|| format!("update {table} uuid"))?;
+    }
+    Ok(())
+}
+
+/// Lookup artist id by exact artist name.
+fn find_artist_id(conn: &Connection, name: &str) -> Result<Option<i64>> {
+    let id = conn
+        .query_row(
+            "SELECT id FROM artists WHERE name = ?1",
+            params![name],
+            |row| row.get(0),
+        )
+        .optional()
+        .context("find artist id")?;
+    Ok(id)
+}
+
+/// Lookup album id by exact `(title, artist_id)` pair.
+fn find_album_id(conn: &Connection, title: &str, artist_id: Option<i64>) -> Result<Option<i64>> {
+    let id = conn
+        .query_row(
+            "SELECT id FROM albums WHERE title = ?1 AND artist_id IS ?2",
+            params![title, artist_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .context("find album id")?;
+    Ok(id)
+}
+
+/// Initialize/migrate metadata schema to current version.
+fn init_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS meta (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS artists (
+            id INTEGER PRIMARY KEY,
+            uuid TEXT,
+            name TEXT NOT NULL,
+            sort_name TEXT,
+            mbid TEXT,
+            image_checked_at_ms INTEGER,
+            image_fail_count INTEGER,
+            image_last_error TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS albums (
+            id INTEGER PRIMARY KEY,
             uuid TEXT,
             title TEXT NOT NULL,
             sort_title TEXT,
@@ -1985,122 +5074,802 @@ fn init_schema(conn: &Connection) -> Result<()> {
             caa_fail_count INTEGER,
             caa_last_error TEXT,
             caa_release_candidates TEXT,
+            first_seen_at_ms INTEGER,
             FOREIGN KEY(artist_id) REFERENCES artists(id) ON DELETE SET NULL
         );
 
-        CREATE TABLE IF NOT EXISTS tracks (
-            id INTEGER PRIMARY KEY,
-            path TEXT NOT NULL UNIQUE,
-            file_name TEXT NOT NULL,
-            title TEXT,
-            artist_id INTEGER,
-            album_id INTEGER,
-            track_number INTEGER,
-            disc_number INTEGER,
-            duration_ms INTEGER,
-            sample_rate INTEGER,
-            bit_depth INTEGER,
-            format TEXT,
-            mtime_ms INTEGER,
-            size_bytes INTEGER,
-            mbid TEXT,
-            mb_no_match_key TEXT,
-            FOREIGN KEY(artist_id) REFERENCES artists(id) ON DELETE SET NULL,
-            FOREIGN KEY(album_id) REFERENCES albums(id) ON DELETE SET NULL
-        );
+        CREATE TABLE IF NOT EXISTS tracks (
+            id INTEGER PRIMARY KEY,
+            path TEXT NOT NULL UNIQUE,
+            file_name TEXT NOT NULL,
+            title TEXT,
+            artist_id INTEGER,
+            album_id INTEGER,
+            track_number INTEGER,
+            disc_number INTEGER,
+            disc_title TEXT,
+            duration_ms INTEGER,
+            sample_rate INTEGER,
+            bit_depth INTEGER,
+            format TEXT,
+            composer TEXT,
+            conductor TEXT,
+            work TEXT,
+            movement TEXT,
+            movement_number INTEGER,
+            mtime_ms INTEGER,
+            size_bytes INTEGER,
+            mbid TEXT,
+            mb_no_match_key TEXT,
+            acoustid_fingerprint TEXT,
+            acoustid_duration INTEGER,
+            acoustid_id TEXT,
+            acoustid_checked_at_ms INTEGER,
+            content_hash TEXT,
+            first_seen_at_ms INTEGER,
+            FOREIGN KEY(artist_id) REFERENCES artists(id) ON DELETE SET NULL,
+            FOREIGN KEY(album_id) REFERENCES albums(id) ON DELETE SET NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS artist_bios (
+            artist_id INTEGER NOT NULL,
+            lang TEXT NOT NULL,
+            text TEXT NOT NULL,
+            source TEXT,
+            locked INTEGER NOT NULL DEFAULT 0,
+            updated_at_ms INTEGER,
+            PRIMARY KEY (artist_id, lang),
+            FOREIGN KEY(artist_id) REFERENCES artists(id) ON DELETE CASCADE
+        );
+
+
+        CREATE TABLE IF NOT EXISTS album_notes (
+            album_id INTEGER NOT NULL,
+            lang TEXT NOT NULL,
+            text TEXT NOT NULL,
+            source TEXT,
+            locked INTEGER NOT NULL DEFAULT 0,
+            updated_at_ms INTEGER,
+            PRIMARY KEY (album_id, lang),
+            FOREIGN KEY(album_id) REFERENCES albums(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS media_assets (
+            id INTEGER PRIMARY KEY,
+            owner_type TEXT NOT NULL,
+            owner_id INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            local_path TEXT NOT NULL,
+            checksum TEXT,
+            source_url TEXT,
+            updated_at_ms INTEGER
+        );
+
+        CREATE TABLE IF NOT EXISTS track_lossy_checks (
+            track_id INTEGER PRIMARY KEY,
+            mtime_ms INTEGER NOT NULL,
+            cutoff_hz REAL,
+            cutoff_drop_db REAL,
+            confidence REAL,
+            error TEXT,
+            checked_at_ms INTEGER NOT NULL,
+            FOREIGN KEY(track_id) REFERENCES tracks(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS genres (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE
+        );
+
+        CREATE TABLE IF NOT EXISTS track_genres (
+            track_id INTEGER NOT NULL,
+            genre_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            PRIMARY KEY(track_id, genre_id),
+            FOREIGN KEY(track_id) REFERENCES tracks(id) ON DELETE CASCADE,
+            FOREIGN KEY(genre_id) REFERENCES genres(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS track_lyrics (
+            track_id INTEGER PRIMARY KEY,
+            plain TEXT,
+            synced TEXT,
+            source TEXT NOT NULL,
+            updated_at_ms INTEGER,
+            FOREIGN KEY(track_id) REFERENCES tracks(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS track_field_locks (
+            track_id INTEGER NOT NULL,
+            field TEXT NOT NULL,
+            PRIMARY KEY(track_id, field),
+            FOREIGN KEY(track_id) REFERENCES tracks(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS track_loudness (
+            track_id INTEGER PRIMARY KEY,
+            mtime_ms INTEGER NOT NULL,
+            integrated_lufs REAL,
+            true_peak_db REAL,
+            album_lufs REAL,
+            album_peak_db REAL,
+            error TEXT,
+            checked_at_ms INTEGER NOT NULL,
+            FOREIGN KEY(track_id) REFERENCES tracks(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS track_tempo_key (
+            track_id INTEGER PRIMARY KEY,
+            mtime_ms INTEGER NOT NULL,
+            bpm REAL,
+            musical_key TEXT,
+            error TEXT,
+            checked_at_ms INTEGER NOT NULL,
+            FOREIGN KEY(track_id) REFERENCES tracks(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS playlists (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            cover_track_id INTEGER,
+            created_at_ms INTEGER NOT NULL,
+            updated_at_ms INTEGER NOT NULL,
+            FOREIGN KEY(cover_track_id) REFERENCES tracks(id) ON DELETE SET NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS playlist_tracks (
+            playlist_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            track_id INTEGER NOT NULL,
+            PRIMARY KEY(playlist_id, position),
+            FOREIGN KEY(playlist_id) REFERENCES playlists(id) ON DELETE CASCADE,
+            FOREIGN KEY(track_id) REFERENCES tracks(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_playlist_tracks_track ON playlist_tracks(track_id);
+
+        CREATE TABLE IF NOT EXISTS track_ratings (
+            track_id INTEGER PRIMARY KEY,
+            favorite INTEGER NOT NULL DEFAULT 0,
+            rating INTEGER,
+            updated_at_ms INTEGER NOT NULL,
+            FOREIGN KEY(track_id) REFERENCES tracks(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS album_ratings (
+            album_id INTEGER PRIMARY KEY,
+            favorite INTEGER NOT NULL DEFAULT 0,
+            rating INTEGER,
+            updated_at_ms INTEGER NOT NULL,
+            FOREIGN KEY(album_id) REFERENCES albums(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS play_history (
+            id INTEGER PRIMARY KEY,
+            track_id INTEGER NOT NULL,
+            session_id TEXT NOT NULL,
+            output_id TEXT,
+            played_at_ms INTEGER NOT NULL,
+            played_ms INTEGER NOT NULL,
+            completed_pct REAL,
+            FOREIGN KEY(track_id) REFERENCES tracks(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_play_history_played_at ON play_history(played_at_ms);
+        CREATE INDEX IF NOT EXISTS idx_play_history_track ON play_history(track_id);
+
+        CREATE TABLE IF NOT EXISTS track_play_stats (
+            track_id INTEGER PRIMARY KEY,
+            play_count INTEGER NOT NULL,
+            last_played_at_ms INTEGER NOT NULL,
+            FOREIGN KEY(track_id) REFERENCES tracks(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS api_tokens (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            role TEXT NOT NULL DEFAULT 'admin',
+            token_hash TEXT NOT NULL UNIQUE,
+            created_at_ms INTEGER NOT NULL,
+            last_used_at_ms INTEGER
+        );
+
+        CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY,
+            at_ms INTEGER NOT NULL,
+            actor TEXT,
+            client_id TEXT,
+            session_id TEXT,
+            output_id TEXT,
+            action TEXT NOT NULL,
+            method TEXT NOT NULL,
+            path TEXT NOT NULL,
+            status INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS output_preferences (
+            output_id TEXT PRIMARY KEY,
+            resample_quality TEXT,
+            exclusive INTEGER,
+            bit_depth INTEGER,
+            max_sample_rate INTEGER,
+            updated_at_ms INTEGER NOT NULL
+        );
+
+        CREATE VIEW IF NOT EXISTS album_genres AS
+            SELECT DISTINCT t.album_id, tg.genre_id
+            FROM track_genres tg
+            JOIN tracks t ON t.id = tg.track_id
+            WHERE t.album_id IS NOT NULL;
+
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_artists_name ON artists(name);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_albums_title_artist ON albums(title, artist_id);
+        CREATE INDEX IF NOT EXISTS idx_tracks_album_id ON tracks(album_id);
+        CREATE INDEX IF NOT EXISTS idx_tracks_artist_id ON tracks(artist_id);
+        CREATE INDEX IF NOT EXISTS idx_albums_artist_id ON albums(artist_id);
+        CREATE INDEX IF NOT EXISTS idx_media_assets_owner_kind ON media_assets(owner_type, owner_id, kind);
+        CREATE INDEX IF NOT EXISTS idx_track_genres_genre_id ON track_genres(genre_id);
+        CREATE INDEX IF NOT EXISTS idx_audit_log_at ON audit_log(at_ms);
+        CREATE INDEX IF NOT EXISTS idx_audit_log_actor ON audit_log(actor);
+        "#,
+    )
+    .context("create metadata schema")?;
+    conn.execute_batch(SEARCH_INDEX_SCHEMA)
+        .context("create search index")?;
+
+    let version_raw: Option<String> = conn
+        .query_row(
+            "SELECT value FROM meta WHERE key = 'schema_version'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let version = version_raw
+        .as_deref()
+        .and_then(|value| value.parse::<i32>().ok());
+    if version.is_none() {
+        conn.execute(
+            "INSERT INTO meta (key, value) VALUES ('schema_version', ?1)",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("insert schema version")?;
+        ensure_uuid_indexes(conn)?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_tracks_content_hash ON tracks(content_hash)",
+            [],
+        )
+        .context("create content hash index")?;
+        conn.execute_batch(FIRST_SEEN_INDEXES)
+            .context("create first seen indexes")?;
+        return Ok(());
+    }
+    let version = version.unwrap_or(1);
+    if version < 2 {
+        conn.execute("ALTER TABLE tracks ADD COLUMN mb_no_match_key TEXT", [])
+            .context("migrate tracks mb_no_match_key")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+        return Ok(());
+    }
+    if version < 3 {
+        conn.execute("ALTER TABLE albums ADD COLUMN caa_fail_count INTEGER", [])
+            .context("migrate albums caa_fail_count")?;
+        conn.execute("ALTER TABLE albums ADD COLUMN caa_last_error TEXT", [])
+            .context("migrate albums caa_last_error")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+        return Ok(());
+    }
+    if version < 4 {
+        conn.execute(
+            "ALTER TABLE albums ADD COLUMN caa_release_candidates TEXT",
+            [],
+        )
+        .context("migrate albums caa_release_candidates")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
+
+    if version < 5 {
+        conn.execute("ALTER TABLE tracks ADD COLUMN bit_depth INTEGER", [])
+            .context("migrate tracks bit_depth")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
+
+    if version < 6 {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS artist_bios (
+                artist_id INTEGER NOT NULL,
+                lang TEXT NOT NULL,
+                text TEXT NOT NULL,
+                source TEXT,
+                locked INTEGER NOT NULL DEFAULT 0,
+                updated_at_ms INTEGER,
+                PRIMARY KEY (artist_id, lang),
+                FOREIGN KEY(artist_id) REFERENCES artists(id) ON DELETE CASCADE
+            );
+
+
+            CREATE TABLE IF NOT EXISTS album_notes (
+                album_id INTEGER NOT NULL,
+                lang TEXT NOT NULL,
+                text TEXT NOT NULL,
+                source TEXT,
+                locked INTEGER NOT NULL DEFAULT 0,
+                updated_at_ms INTEGER,
+                PRIMARY KEY (album_id, lang),
+                FOREIGN KEY(album_id) REFERENCES albums(id) ON DELETE CASCADE
+            );
+            CREATE TABLE IF NOT EXISTS media_assets (
+                id INTEGER PRIMARY KEY,
+                owner_type TEXT NOT NULL,
+                owner_id INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                local_path TEXT NOT NULL,
+                checksum TEXT,
+                source_url TEXT,
+                updated_at_ms INTEGER
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_media_assets_owner_kind ON media_assets(owner_type, owner_id, kind);
+            "#,
+        )
+        .context("migrate bio/notes/assets tables")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
+
+    if version < 7 {
+        conn.execute_batch(
+            r#"
+            DROP TABLE IF EXISTS artist_histories;
+            DROP TABLE IF EXISTS album_histories;
+            "#,
+        )
+        .context("drop history tables")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
+
+    if version < 8 {
+        conn.execute_batch(
+            r#"
+            ALTER TABLE artists ADD COLUMN uuid TEXT;
+            ALTER TABLE albums ADD COLUMN uuid TEXT;
+            "#,
+        )
+        .context("add artist/album uuid columns")?;
+        ensure_uuid_indexes(conn)?;
+        backfill_uuids(conn, "artists")?;
+        backfill_uuids(conn, "albums")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
+
+    if version < 9 {
+        conn.execute_batch(
+            r#"
+            ALTER TABLE albums ADD COLUMN original_year INTEGER;
+            ALTER TABLE albums ADD COLUMN edition_year INTEGER;
+            ALTER TABLE albums ADD COLUMN edition_label TEXT;
+            "#,
+        )
+        .context("add album edition columns")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
+
+    if version < 10 {
+        conn.execute("ALTER TABLE albums ADD COLUMN orphaned_at INTEGER", [])
+            .context("add album orphaned_at")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
+
+    if version < 11 {
+        // The table itself is created by the bootstrap batch above.
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
 
-        CREATE TABLE IF NOT EXISTS artist_bios (
-            artist_id INTEGER NOT NULL,
-            lang TEXT NOT NULL,
-            text TEXT NOT NULL,
-            source TEXT,
-            locked INTEGER NOT NULL DEFAULT 0,
-            updated_at_ms INTEGER,
-            PRIMARY KEY (artist_id, lang),
-            FOREIGN KEY(artist_id) REFERENCES artists(id) ON DELETE CASCADE
-        );
+    if version < 12 {
+        // Index and triggers come from the bootstrap batch; index the existing library.
+        rebuild_search_index(conn)?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
 
+    if version < 13 {
+        // Genre tables come from the bootstrap batch. Existing tracks were stored without
+        // genres; clearing mtime makes the next scan re-read their tags.
+        conn.execute("UPDATE tracks SET mtime_ms = 0", [])
+            .context("reset track mtimes for genre backfill")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
 
-        CREATE TABLE IF NOT EXISTS album_notes (
-            album_id INTEGER NOT NULL,
-            lang TEXT NOT NULL,
-            text TEXT NOT NULL,
-            source TEXT,
-            locked INTEGER NOT NULL DEFAULT 0,
-            updated_at_ms INTEGER,
-            PRIMARY KEY (album_id, lang),
-            FOREIGN KEY(album_id) REFERENCES albums(id) ON DELETE CASCADE
-        );
+    if version < 14 {
+        conn.execute_batch(
+            r#"
+            ALTER TABLE tracks ADD COLUMN composer TEXT;
+            ALTER TABLE tracks ADD COLUMN conductor TEXT;
+            ALTER TABLE tracks ADD COLUMN work TEXT;
+            ALTER TABLE tracks ADD COLUMN movement TEXT;
+            ALTER TABLE tracks ADD COLUMN movement_number INTEGER;
+            UPDATE tracks SET mtime_ms = 0;
+            "#,
+        )
+        .context("migrate tracks classical credits")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
 
-        CREATE TABLE IF NOT EXISTS media_assets (
-            id INTEGER PRIMARY KEY,
-            owner_type TEXT NOT NULL,
-            owner_id INTEGER NOT NULL,
-            kind TEXT NOT NULL,
-            local_path TEXT NOT NULL,
-            checksum TEXT,
-            source_url TEXT,
-            updated_at_ms INTEGER
-        );
+    if version < 15 {
+        // Re-reading tags picks up disc subtitles and regroups disc folders of box sets.
+        conn.execute_batch(
+            r#"
+            ALTER TABLE tracks ADD COLUMN disc_title TEXT;
+            UPDATE tracks SET mtime_ms = 0;
+            "#,
+        )
+        .context("migrate tracks disc titles")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
 
-        CREATE UNIQUE INDEX IF NOT EXISTS idx_artists_name ON artists(name);
-        CREATE UNIQUE INDEX IF NOT EXISTS idx_albums_title_artist ON albums(title, artist_id);
-        CREATE INDEX IF NOT EXISTS idx_tracks_album_id ON tracks(album_id);
-        CREATE INDEX IF NOT EXISTS idx_tracks_artist_id ON tracks(artist_id);
-        CREATE INDEX IF NOT EXISTS idx_albums_artist_id ON albums(artist_id);
-        CREATE INDEX IF NOT EXISTS idx_media_assets_owner_kind ON media_assets(owner_type, owner_id, kind);
-        "#,
-    )
-    .context("create metadata schema")?;
+    if version < 16 {
+        conn.execute_batch(
+            r#"
+            ALTER TABLE artists ADD COLUMN image_checked_at_ms INTEGER;
+            ALTER TABLE artists ADD COLUMN image_fail_count INTEGER;
+            ALTER TABLE artists ADD COLUMN image_last_error TEXT;
+            "#,
+        )
+        .context("migrate artists image fetch state")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
 
-    let version_raw: Option<String> = conn
-        .query_row(
-            "SELECT value FROM meta WHERE key = 'schema_version'",
-            [],
-            |row| row.get(0),
+    if version < 17 {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS track_lyrics (
+                track_id INTEGER PRIMARY KEY,
+                plain TEXT,
+                synced TEXT,
+                source TEXT NOT NULL,
+                updated_at_ms INTEGER,
+                FOREIGN KEY(track_id) REFERENCES tracks(id) ON DELETE CASCADE
+            );
+            UPDATE tracks SET mtime_ms = 0;
+            "#,
         )
-        .optional()?;
-    let version = version_raw
-        .as_deref()
-        .and_then(|value| value.parse::<i32>().ok());
-    if version.is_none() {
+        .context("migrate track lyrics")?;
         conn.execute(
-            "INSERT INTO meta (key, value) VALUES ('schema_version', ?1)",
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
             params![SCHEMA_VERSION.to_string()],
         )
-        .context("insert schema version")?;
-        ensure_uuid_indexes(conn)?;
-        return Ok(());
+        .context("update schema version")?;
     }
-    let version = version.unwrap_or(1);
-    if version < 2 {
-        conn.execute("ALTER TABLE tracks ADD COLUMN mb_no_match_key TEXT", [])
-            .context("migrate tracks mb_no_match_key")?;
+
+    if version < 18 {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS track_field_locks (
+                track_id INTEGER NOT NULL,
+                field TEXT NOT NULL,
+                PRIMARY KEY(track_id, field),
+                FOREIGN KEY(track_id) REFERENCES tracks(id) ON DELETE CASCADE
+            );
+            "#,
+        )
+        .context("migrate track field locks")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
+
+    if version < 19 {
+        conn.execute_batch(
+            r#"
+            ALTER TABLE tracks ADD COLUMN acoustid_fingerprint TEXT;
+            ALTER TABLE tracks ADD COLUMN acoustid_duration INTEGER;
+            ALTER TABLE tracks ADD COLUMN acoustid_id TEXT;
+            ALTER TABLE tracks ADD COLUMN acoustid_checked_at_ms INTEGER;
+            "#,
+        )
+        .context("migrate tracks acoustid columns")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
+
+    if version < 20 {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS track_loudness (
+                track_id INTEGER PRIMARY KEY,
+                mtime_ms INTEGER NOT NULL,
+                integrated_lufs REAL,
+                true_peak_db REAL,
+                album_lufs REAL,
+                album_peak_db REAL,
+                error TEXT,
+                checked_at_ms INTEGER NOT NULL,
+                FOREIGN KEY(track_id) REFERENCES tracks(id) ON DELETE CASCADE
+            );
+            "#,
+        )
+        .context("migrate track loudness")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
+
+    if version < 21 {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS track_tempo_key (
+                track_id INTEGER PRIMARY KEY,
+                mtime_ms INTEGER NOT NULL,
+                bpm REAL,
+                musical_key TEXT,
+                error TEXT,
+                checked_at_ms INTEGER NOT NULL,
+                FOREIGN KEY(track_id) REFERENCES tracks(id) ON DELETE CASCADE
+            );
+            "#,
+        )
+        .context("migrate track tempo and key")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
+
+    if version < 22 {
+        conn.execute_batch(
+            r#"
+            ALTER TABLE tracks ADD COLUMN content_hash TEXT;
+            CREATE INDEX IF NOT EXISTS idx_tracks_content_hash ON tracks(content_hash);
+            "#,
+        )
+        .context("migrate tracks content_hash")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
+
+    if version < 23 {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS playlists (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                cover_track_id INTEGER,
+                created_at_ms INTEGER NOT NULL,
+                updated_at_ms INTEGER NOT NULL,
+                FOREIGN KEY(cover_track_id) REFERENCES tracks(id) ON DELETE SET NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS playlist_tracks (
+                playlist_id INTEGER NOT NULL,
+                position INTEGER NOT NULL,
+                track_id INTEGER NOT NULL,
+                PRIMARY KEY(playlist_id, position),
+                FOREIGN KEY(playlist_id) REFERENCES playlists(id) ON DELETE CASCADE,
+                FOREIGN KEY(track_id) REFERENCES tracks(id) ON DELETE CASCADE
+            );
+            CREATE INDEX IF NOT EXISTS idx_playlist_tracks_track ON playlist_tracks(track_id);
+            "#,
+        )
+        .context("migrate playlists")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
+
+    if version < 24 {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS track_ratings (
+                track_id INTEGER PRIMARY KEY,
+                favorite INTEGER NOT NULL DEFAULT 0,
+                rating INTEGER,
+                updated_at_ms INTEGER NOT NULL,
+                FOREIGN KEY(track_id) REFERENCES tracks(id) ON DELETE CASCADE
+            );
+
+            CREATE TABLE IF NOT EXISTS album_ratings (
+                album_id INTEGER PRIMARY KEY,
+                favorite INTEGER NOT NULL DEFAULT 0,
+                rating INTEGER,
+                updated_at_ms INTEGER NOT NULL,
+                FOREIGN KEY(album_id) REFERENCES albums(id) ON DELETE CASCADE
+            );
+            "#,
+        )
+        .context("migrate ratings")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
+
+    if version < 25 {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS play_history (
+                id INTEGER PRIMARY KEY,
+                track_id INTEGER NOT NULL,
+                session_id TEXT NOT NULL,
+                output_id TEXT,
+                played_at_ms INTEGER NOT NULL,
+                played_ms INTEGER NOT NULL,
+                completed_pct REAL,
+                FOREIGN KEY(track_id) REFERENCES tracks(id) ON DELETE CASCADE
+            );
+            CREATE INDEX IF NOT EXISTS idx_play_history_played_at ON play_history(played_at_ms);
+            CREATE INDEX IF NOT EXISTS idx_play_history_track ON play_history(track_id);
+
+            CREATE TABLE IF NOT EXISTS track_play_stats (
+                track_id INTEGER PRIMARY KEY,
+                play_count INTEGER NOT NULL,
+                last_played_at_ms INTEGER NOT NULL,
+                FOREIGN KEY(track_id) REFERENCES tracks(id) ON DELETE CASCADE
+            );
+            "#,
+        )
+        .context("migrate play history")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
+
+    if version < 26 {
+        // Existing tracks count as added when their file was last written.
+        conn.execute_batch(
+            r#"
+            ALTER TABLE tracks ADD COLUMN first_seen_at_ms INTEGER;
+            ALTER TABLE albums ADD COLUMN first_seen_at_ms INTEGER;
+            UPDATE tracks SET first_seen_at_ms = mtime_ms;
+            UPDATE albums SET first_seen_at_ms = (
+                SELECT MIN(t.first_seen_at_ms) FROM tracks t WHERE t.album_id = albums.id
+            );
+            "#,
+        )
+        .context("migrate first seen timestamps")?;
+        conn.execute_batch(FIRST_SEEN_INDEXES)
+            .context("create first seen indexes")?;
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![SCHEMA_VERSION.to_string()],
+        )
+        .context("update schema version")?;
+    }
+
+    if version < 27 {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS api_tokens (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                token_hash TEXT NOT NULL UNIQUE,
+                created_at_ms INTEGER NOT NULL,
+                last_used_at_ms INTEGER
+            );
+            "#,
+        )
+        .context("migrate api tokens")?;
         conn.execute(
             "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
             params![SCHEMA_VERSION.to_string()],
         )
         .context("update schema version")?;
-        return Ok(());
     }
-    if version < 3 {
-        conn.execute("ALTER TABLE albums ADD COLUMN caa_fail_count INTEGER", [])
-            .context("migrate albums caa_fail_count")?;
-        conn.execute("ALTER TABLE albums ADD COLUMN caa_last_error TEXT", [])
-            .context("migrate albums caa_last_error")?;
+
+    if version < 28 {
+        // Tokens created before roles existed had full access; keep it. A v26 database got the
+        // table, role included, from the schema above.
+        let has_role: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('api_tokens') WHERE name = 'role'",
+                [],
+                |row| row.get(0),
+            )
+            .context("inspect api tokens")?;
+        if has_role == 0 {
+            conn.execute_batch(
+                "ALTER TABLE api_tokens ADD COLUMN role TEXT NOT NULL DEFAULT 'admin';",
+            )
+            .context("migrate api token roles")?;
+        }
         conn.execute(
             "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
             params![SCHEMA_VERSION.to_string()],
         )
         .context("update schema version")?;
-        return Ok(());
     }
-    if version < 4 {
-        conn.execute(
-            "ALTER TABLE albums ADD COLUMN caa_release_candidates TEXT",
-            [],
+
+    if version < 29 {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY,
+                at_ms INTEGER NOT NULL,
+                actor TEXT,
+                client_id TEXT,
+                session_id TEXT,
+                output_id TEXT,
+                action TEXT NOT NULL,
+                method TEXT NOT NULL,
+                path TEXT NOT NULL,
+                status INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_audit_log_at ON audit_log(at_ms);
+            CREATE INDEX IF NOT EXISTS idx_audit_log_actor ON audit_log(actor);
+            "#,
         )
-        .context("migrate albums caa_release_candidates")?;
+        .context("migrate audit log")?;
         conn.execute(
             "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
             params![SCHEMA_VERSION.to_string()],
@@ -2108,9 +5877,20 @@ fn init_schema(conn: &Connection) -> Result<()> {
         .context("update schema version")?;
     }
 
-    if version < 5 {
-        conn.execute("ALTER TABLE tracks ADD COLUMN bit_depth INTEGER", [])
-            .context("migrate tracks bit_depth")?;
+    if version < 30 {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS output_preferences (
+                output_id TEXT PRIMARY KEY,
+                resample_quality TEXT,
+                exclusive INTEGER,
+                bit_depth INTEGER,
+                max_sample_rate INTEGER,
+                updated_at_ms INTEGER NOT NULL
+            );
+            "#,
+        )
+        .context("migrate output preferences")?;
         conn.execute(
             "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
             params![SCHEMA_VERSION.to_string()],
@@ -2118,181 +5898,1580 @@ fn init_schema(conn: &Connection) -> Result<()> {
         .context("update schema version")?;
     }
 
-    if version < 6 {
-        conn.execute_batch(
-            r#"
-            CREATE TABLE IF NOT EXISTS artist_bios (
-                artist_id INTEGER NOT NULL,
-                lang TEXT NOT NULL,
-                text TEXT NOT NULL,
-                source TEXT,
-                locked INTEGER NOT NULL DEFAULT 0,
-                updated_at_ms INTEGER,
-                PRIMARY KEY (artist_id, lang),
-                FOREIGN KEY(artist_id) REFERENCES artists(id) ON DELETE CASCADE
-            );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn db_path_for_places_db_under_audio_hub_dir() {
+        let root = Path::new("/music/library");
+        let path = db_path_for(root);
+        assert_eq!(path, root.join(".audio-hub").join("metadata.sqlite"));
+    }
+
+    #[test]
+    fn is_blank_handles_empty_and_whitespace() {
+        assert!(is_blank(&None));
+        assert!(is_blank(&Some("".to_string())));
+        assert!(is_blank(&Some("  ".to_string())));
+        assert!(!is_blank(&Some("a".to_string())));
+    }
+
+    #[test]
+    fn find_artist_id_and_album_id_work() {
+        let conn = Connection::open_in_memory().expect("open memory db");
+        conn.execute_batch(
+            r#"
+            CREATE TABLE artists (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+            CREATE TABLE albums (id INTEGER PRIMARY KEY, title TEXT NOT NULL, artist_id INTEGER);
+            "#,
+        )
+        .expect("create schema");
+        conn.execute("INSERT INTO artists (id, name) VALUES (1, 'A-Ha')", [])
+            .expect("insert artist");
+        conn.execute(
+            "INSERT INTO albums (id, title, artist_id) VALUES (10, 'Hunting High and Low', 1)",
+            [],
+        )
+        .expect("insert album");
+
+        let artist_id = find_artist_id(&conn, "A-Ha").expect("find artist");
+        assert_eq!(artist_id, Some(1));
+        let album_id = find_album_id(&conn, "Hunting High and Low", Some(1)).expect("find album");
+        assert_eq!(album_id, Some(10));
+
+        let missing_artist = find_artist_id(&conn, "Missing").expect("find missing artist");
+        assert_eq!(missing_artist, None);
+        let missing_album = find_album_id(&conn, "Missing", Some(1)).expect("find missing album");
+        assert_eq!(missing_album, None);
+    }
+
+    #[test]
+    fn relative_from_absolute_supports_root_basename_heuristic() {
+        let tmp = std::env::temp_dir().join(format!(
+            "audio-hub-path-rel-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let root = tmp.join("music");
+        let track = root.join("Artist").join("Album").join("song.flac");
+        fs::create_dir_all(track.parent().unwrap()).expect("create track dir");
+        fs::write(&track, b"audio").expect("write track file");
+
+        let legacy = Path::new("/legacy/mount/music/Artist/Album/song.flac");
+        let rel = relative_from_absolute(legacy, &root).expect("relative path");
+        assert_eq!(rel, PathBuf::from("Artist/Album/song.flac"));
+    }
+
+    #[test]
+    fn loudness_candidates_group_albums_and_follow_file_changes() {
+        let tmp = std::env::temp_dir().join(format!(
+            "audio-hub-loudness-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let db = MetadataDb::new_at_path(&tmp.join("metadata.sqlite")).expect("open db");
+        let mut record = TrackRecord {
+            path: "/music/Album/01.flac".to_string(),
+            file_name: "01.flac".to_string(),
+            title: Some("One".to_string()),
+            artist: Some("Artist".to_string()),
+            album_artist: Some("Artist".to_string()),
+            album: Some("Album".to_string()),
+            album_uuid: None,
+            track_number: Some(1),
+            disc_number: None,
+            disc_title: None,
+            year: None,
+            genres: Vec::new(),
+            composer: None,
+            conductor: None,
+            work: None,
+            movement: None,
+            movement_number: None,
+            duration_ms: None,
+            sample_rate: Some(44_100),
+            bit_depth: Some(16),
+            format: Some("FLAC".to_string()),
+            mtime_ms: 1,
+            size_bytes: 1,
+        };
+        db.upsert_track(&record).expect("upsert first");
+        record.path = "/music/Album/02.flac".to_string();
+        record.file_name = "02.flac".to_string();
+        record.track_number = Some(2);
+        db.upsert_track(&record).expect("upsert second");
+        let mut loose = record.clone();
+        loose.path = "/music/loose.flac".to_string();
+        loose.file_name = "loose.flac".to_string();
+        loose.album = None;
+        loose.album_artist = None;
+        db.upsert_track(&loose).expect("upsert loose");
+
+        let candidates = db.list_loudness_candidates(10).expect("candidates");
+        let paths: Vec<_> = candidates.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "/music/Album/01.flac",
+                "/music/Album/02.flac",
+                "/music/loose.flac"
+            ]
+        );
+        assert!(candidates[0].album_id.is_some());
+        assert_eq!(candidates[0].album_id, candidates[1].album_id);
+        assert_eq!(candidates[2].album_id, None);
+
+        for (index, candidate) in candidates.iter().enumerate() {
+            let result = LoudnessResult {
+                integrated_lufs: Some(-10.0 - index as f64),
+                true_peak_db: Some(-1.0),
+                album_lufs: candidate.album_id.map(|_| -10.5),
+                album_peak_db: candidate.album_id.map(|_| -1.0),
+                error: None,
+            };
+            db.upsert_loudness(candidate.track_id, candidate.mtime_ms, &result)
+                .expect("store loudness");
+        }
+        assert!(db.list_loudness_candidates(10).unwrap().is_empty());
+        let tracks = db
+            .list_tracks(&TrackFilter::default(), 10, 0)
+            .expect("tracks");
+        let first = tracks.iter().find(|t| t.file_name == "01.flac").unwrap();
+        assert_eq!(first.loudness_lufs, Some(-10.0));
+        assert_eq!(first.album_loudness_lufs, Some(-10.5));
+        assert_eq!(first.album_true_peak_db, Some(-1.0));
+
+        // Changing one album track re-measures the whole album and hides stale values.
+        record.mtime_ms = 2;
+        db.upsert_track(&record).expect("touch second");
+        let paths: Vec<_> = db
+            .list_loudness_candidates(10)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.path)
+            .collect();
+        assert_eq!(paths, ["/music/Album/01.flac", "/music/Album/02.flac"]);
+        let tracks = db
+            .list_tracks(&TrackFilter::default(), 10, 0)
+            .expect("tracks");
+        let second = tracks.iter().find(|t| t.file_name == "02.flac").unwrap();
+        assert_eq!(second.loudness_lufs, None);
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn recent_lists_keep_first_seen_across_rescans() {
+        let tmp = std::env::temp_dir().join(format!(
+            "audio-hub-recent-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let db = MetadataDb::new_at_path(&tmp.join("metadata.sqlite")).expect("open db");
+        let mut record = TrackRecord {
+            path: "/music/Old/old.flac".to_string(),
+            file_name: "old.flac".to_string(),
+            title: None,
+            artist: Some("Artist".to_string()),
+            album_artist: None,
+            album: Some("Old".to_string()),
+            album_uuid: None,
+            track_number: None,
+            disc_number: None,
+            disc_title: None,
+            year: None,
+            genres: Vec::new(),
+            composer: None,
+            conductor: None,
+            work: None,
+            movement: None,
+            movement_number: None,
+            duration_ms: None,
+            sample_rate: None,
+            bit_depth: None,
+            format: None,
+            mtime_ms: 1,
+            size_bytes: 1,
+        };
+        db.upsert_track(&record).expect("upsert old");
+        let first_seen = db
+            .list_tracks(&TrackFilter::default(), 10, 0)
+            .expect("tracks")[0]
+            .first_seen_at_ms
+            .expect("first seen");
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let mut new_record = record.clone();
+        new_record.path = "/music/New/new.flac".to_string();
+        new_record.file_name = "new.flac".to_string();
+        new_record.album = Some("New".to_string());
+        db.upsert_track(&new_record).expect("upsert new");
+        record.mtime_ms = 2;
+        db.upsert_track(&record).expect("rescan old");
+
+        let recent = |sort| {
+            db.list_tracks(
+                &TrackFilter {
+                    sort,
+                    ..TrackFilter::default()
+                },
+                10,
+                0,
+            )
+            .expect("tracks")
+        };
+        let tracks = recent(TrackSort::RecentlyAdded);
+        let names: Vec<&str> = tracks.iter().map(|t| t.file_name.as_str()).collect();
+        assert_eq!(names, ["new.flac", "old.flac"]);
+        assert_eq!(tracks[1].first_seen_at_ms, Some(first_seen));
+
+        let albums = db
+            .list_albums(
+                &AlbumFilter {
+                    sort: AlbumSort::RecentlyAdded,
+                    ..AlbumFilter::default()
+                },
+                10,
+                0,
+            )
+            .expect("albums");
+        let titles: Vec<&str> = albums.iter().map(|a| a.title.as_str()).collect();
+        assert_eq!(titles, ["New", "Old"]);
+        assert!(albums[0].first_seen_at_ms >= albums[1].first_seen_at_ms);
+    }
+
+    #[test]
+    fn export_import_moves_user_data_to_a_fresh_library() {
+        let tmp = std::env::temp_dir().join(format!(
+            "audio-hub-export-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let record = |path: &str| TrackRecord {
+            path: path.to_string(),
+            file_name: path.rsplit('/').next().unwrap().to_string(),
+            title: None,
+            artist: Some("Artist".to_string()),
+            album_artist: None,
+            album: Some("Album".to_string()),
+            album_uuid: None,
+            track_number: None,
+            disc_number: None,
+            disc_title: None,
+            year: None,
+            genres: Vec::new(),
+            composer: None,
+            conductor: None,
+            work: None,
+            movement: None,
+            movement_number: None,
+            duration_ms: None,
+            sample_rate: None,
+            bit_depth: None,
+            format: None,
+            mtime_ms: 1,
+            size_bytes: 1,
+        };
+
+        let old = MetadataDb::new_at_path(&tmp.join("old.sqlite")).expect("open old db");
+        for path in ["/music/a.flac", "/music/b.flac", "/music/gone.flac"] {
+            old.upsert_track(&record(path)).expect("upsert");
+        }
+        old.set_track_content_hash("/music/b.flac", "hash-b")
+            .expect("hash");
+        let a = old.track_id_for_path("/music/a.flac").unwrap().unwrap();
+        let b = old.track_id_for_path("/music/b.flac").unwrap().unwrap();
+        let gone = old.track_id_for_path("/music/gone.flac").unwrap().unwrap();
+        old.set_track_rating(a, Some(true), Some(Some(4)))
+            .expect("rate");
+        old.set_track_rating(gone, None, Some(Some(2)))
+            .expect("rate");
+        old.update_track_field_locks(b, &["title".to_string()], &[])
+            .expect("lock");
+        let album_id = old.list_albums(&AlbumFilter::default(), 1, 0).unwrap()[0].id;
+        old.set_album_rating(album_id, Some(true), None)
+            .expect("rate album");
+        let artist_id = old.list_artists(None, 1, 0).unwrap()[0].id;
+        old.upsert_artist_bio(
+            artist_id,
+            "en",
+            "Written by hand",
+            Some("manual"),
+            true,
+            None,
+        )
+        .expect("bio");
+        old.create_playlist("Mix", &[b, gone, a], Some(b))
+            .expect("playlist");
+        let export = old.export_user_data().expect("export");
+        let json = serde_json::to_string(&export).expect("serialize");
+        let export: MetadataExport = serde_json::from_str(&json).expect("parse");
+
+        // The new machine has `b.flac` under another folder and no `gone.flac`.
+        let new = MetadataDb::new_at_path(&tmp.join("new.sqlite")).expect("open new db");
+        for path in ["/music/a.flac", "/music/moved/b.flac"] {
+            new.upsert_track(&record(path)).expect("upsert");
+        }
+        new.set_track_content_hash("/music/moved/b.flac", "hash-b")
+            .expect("hash");
+        let summary = new.import_user_data(&export).expect("import");
+        assert_eq!(
+            summary,
+            MetadataImportSummary {
+                tracks: 2,
+                albums: 1,
+                artists: 1,
+                playlists: 1,
+                unmatched: 1,
+                unmatched_playlist_tracks: 1,
+            }
+        );
+
+        let a = new.track_id_for_path("/music/a.flac").unwrap().unwrap();
+        let b = new
+            .track_id_for_path("/music/moved/b.flac")
+            .unwrap()
+            .unwrap();
+        let tracks = new.list_tracks(&TrackFilter::default(), 10, 0).unwrap();
+        let rated = tracks.iter().find(|t| t.id == a).unwrap();
+        assert!(rated.favorite);
+        assert_eq!(rated.rating, Some(4));
+        assert!(new.track_field_locks(b).unwrap().contains("title"));
+        assert!(new.list_albums(&AlbumFilter::default(), 1, 0).unwrap()[0].favorite);
+        let artist_id = new.list_artists(None, 1, 0).unwrap()[0].id;
+        let bio = new.artist_bio(artist_id, "en").unwrap().expect("bio");
+        assert_eq!(bio.text, "Written by hand");
+        assert!(bio.locked);
+        let playlist = &new.list_playlists().unwrap()[0];
+        assert_eq!(playlist.name, "Mix");
+        assert_eq!(playlist.cover_track_id, Some(b));
+        assert_eq!(
+            new.playlist_track_ids(playlist.id).unwrap(),
+            Some(vec![b, a])
+        );
+
+        // Importing again replaces the playlist instead of adding a second one.
+        new.import_user_data(&export).expect("reimport");
+        assert_eq!(new.list_playlists().unwrap().len(), 1);
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn cursor_pages_continue_after_last_row_when_rows_are_added() {
+        let tmp = std::env::temp_dir().join(format!(
+            "audio-hub-cursor-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let db = MetadataDb::new_at_path(&tmp.join("metadata.sqlite")).expect("open db");
+        let upsert = |title: &str, artist: &str| {
+            db.upsert_track(&TrackRecord {
+                path: format!("/music/{artist}/{title}.flac"),
+                file_name: format!("{title}.flac"),
+                title: Some(title.to_string()),
+                artist: Some(artist.to_string()),
+                album_artist: None,
+                album: Some(format!("{artist} Album")),
+                album_uuid: None,
+                track_number: None,
+                disc_number: None,
+                disc_title: None,
+                year: None,
+                genres: Vec::new(),
+                composer: None,
+                conductor: None,
+                work: None,
+                movement: None,
+                movement_number: None,
+                duration_ms: None,
+                sample_rate: None,
+                bit_depth: None,
+                format: None,
+                mtime_ms: 1,
+                size_bytes: 1,
+            })
+            .expect("upsert");
+        };
+        // Two tracks share a title so the id tie-breaker is exercised.
+        for (title, artist) in [("b", "Bea"), ("c", "Cy"), ("c", "Dee"), ("e", "Eve")] {
+            upsert(title, artist);
+        }
+
+        let filter = TrackFilter {
+            sort: TrackSort::Title,
+            ..TrackFilter::default()
+        };
+        let first = db
+            .list_tracks_page(&filter, 2, PageStart::Offset(0))
+            .expect("page")
+            .expect("offset page");
+        let cursor = first.next_cursor.clone().expect("cursor");
+        // A rescan adds a track sorting before the cursor; the next page must not repeat rows.
+        upsert("a", "Al");
+        let second = db
+            .list_tracks_page(&filter, 2, PageStart::After(&cursor))
+            .expect("page")
+            .expect("cursor page");
+        let artists = |page: &Page<TrackSummary>| -> Vec<Option<String>> {
+            page.items.iter().map(|t| t.artist.clone()).collect()
+        };
+        let some = |names: [&str; 2]| names.map(|name| Some(name.to_string()));
+        assert_eq!(artists(&first), some(["Bea", "Cy"]));
+        assert_eq!(artists(&second), some(["Dee", "Eve"]));
+        let last = db
+            .list_tracks_page(
+                &filter,
+                2,
+                PageStart::After(second.next_cursor.as_ref().expect("cursor")),
+            )
+            .expect("page")
+            .expect("cursor page");
+        assert!(last.items.is_empty());
+        assert!(last.next_cursor.is_none());
+
+        // Cursors only work for the list and order they were issued for.
+        let reversed = TrackFilter {
+            direction: Some(SortDirection::Desc),
+            ..filter
+        };
+        assert!(
+            db.list_tracks_page(&reversed, 2, PageStart::After(&cursor))
+                .expect("page")
+                .is_none()
+        );
+
+        let mut artists = Vec::new();
+        let mut start = None;
+        loop {
+            let page = db
+                .list_artists_page(
+                    None,
+                    2,
+                    start
+                        .as_ref()
+                        .map_or(PageStart::Offset(0), PageStart::After),
+                )
+                .expect("page")
+                .expect("artists page");
+            artists.extend(page.items.into_iter().map(|a| a.name));
+            match page.next_cursor {
+                Some(cursor) => start = Some(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(artists, ["Al", "Bea", "Cy", "Dee", "Eve"]);
+
+        let albums = AlbumFilter {
+            sort: AlbumSort::Rating,
+            ..AlbumFilter::default()
+        };
+        db.set_album_rating(
+            db.list_albums(&albums, 10, 0).expect("albums")[4].id,
+            None,
+            Some(Some(5)),
+        )
+        .expect("rate");
+        let first = db
+            .list_albums_page(&albums, 3, PageStart::Offset(0))
+            .expect("page")
+            .expect("offset page");
+        let rest = db
+            .list_albums_page(
+                &albums,
+                3,
+                PageStart::After(first.next_cursor.as_ref().expect("cursor")),
+            )
+            .expect("page")
+            .expect("cursor page");
+        let titles: Vec<_> = first
+            .items
+            .iter()
+            .chain(&rest.items)
+            .map(|a| a.title.as_str())
+            .collect();
+        assert_eq!(
+            titles,
+            [
+                "Eve Album",
+                "Al Album",
+                "Bea Album",
+                "Cy Album",
+                "Dee Album"
+            ]
+        );
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn list_sorts_honor_direction_and_keep_missing_values_last() {
+        let tmp = std::env::temp_dir().join(format!(
+            "audio-hub-sort-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let db = MetadataDb::new_at_path(&tmp.join("metadata.sqlite")).expect("open db");
+        for (album, year) in [("Beta", Some(2001)), ("Alpha", Some(1999)), ("Gamma", None)] {
+            db.upsert_track(&TrackRecord {
+                path: format!("/music/{album}/01.flac"),
+                file_name: format!("{album}.flac"),
+                title: Some(album.to_string()),
+                artist: Some("Artist".to_string()),
+                album_artist: None,
+                album: Some(album.to_string()),
+                album_uuid: None,
+                track_number: Some(1),
+                disc_number: None,
+                disc_title: None,
+                year,
+                genres: Vec::new(),
+                composer: None,
+                conductor: None,
+                work: None,
+                movement: None,
+                movement_number: None,
+                duration_ms: None,
+                sample_rate: None,
+                bit_depth: None,
+                format: None,
+                mtime_ms: 1,
+                size_bytes: 1,
+            })
+            .expect("upsert");
+        }
+
+        let albums = |sort, direction| {
+            db.list_albums(
+                &AlbumFilter {
+                    sort,
+                    direction,
+                    ..AlbumFilter::default()
+                },
+                10,
+                0,
+            )
+            .expect("albums")
+            .into_iter()
+            .map(|a| a.title)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(albums(AlbumSort::Year, None), ["Alpha", "Beta", "Gamma"]);
+        assert_eq!(
+            albums(AlbumSort::Year, Some(SortDirection::Desc)),
+            ["Beta", "Alpha", "Gamma"]
+        );
+        assert_eq!(
+            albums(AlbumSort::Title, Some(SortDirection::Desc)),
+            ["Gamma", "Beta", "Alpha"]
+        );
+        assert_eq!(albums(AlbumSort::Random, None).len(), 3);
+
+        let tracks = |sort, direction| {
+            db.list_tracks(
+                &TrackFilter {
+                    sort,
+                    direction,
+                    ..TrackFilter::default()
+                },
+                10,
+                0,
+            )
+            .expect("tracks")
+            .into_iter()
+            .map(|t| t.file_name)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            tracks(TrackSort::Title, Some(SortDirection::Asc)),
+            ["Alpha.flac", "Beta.flac", "Gamma.flac"]
+        );
+        assert_eq!(
+            tracks(TrackSort::Year, Some(SortDirection::Desc)),
+            ["Beta.flac", "Alpha.flac", "Gamma.flac"]
+        );
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn output_preferences_round_trip_and_clear() {
+        let tmp = std::env::temp_dir().join(format!(
+            "audio-hub-output-prefs-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let db = MetadataDb::new_at_path(&tmp.join("metadata.sqlite")).expect("open db");
+        let prefs = OutputPreferences {
+            resample_quality: Some("high".to_string()),
+            exclusive: Some(true),
+            bit_depth: Some(24),
+            max_sample_rate: Some(96_000),
+        };
+        db.set_output_preferences("bridge:a:dac", &prefs)
+            .expect("store");
+        db.set_output_preferences("bridge:a:usb", &prefs)
+            .expect("store");
+        let updated = OutputPreferences {
+            exclusive: Some(false),
+            ..OutputPreferences::default()
+        };
+        db.set_output_preferences("bridge:a:usb", &updated)
+            .expect("update");
+
+        let stored = db.list_output_preferences().expect("list");
+        assert_eq!(stored.get("bridge:a:dac"), Some(&prefs));
+        assert_eq!(stored.get("bridge:a:usb"), Some(&updated));
+
+        db.set_output_preferences("bridge:a:dac", &OutputPreferences::default())
+            .expect("clear");
+        let stored = db.list_output_preferences().expect("list");
+        assert_eq!(stored.len(), 1);
+    }
+
+    #[test]
+    fn recorded_plays_list_newest_first_and_count_when_mostly_heard() {
+        let tmp = std::env::temp_dir().join(format!(
+            "audio-hub-play-history-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let db = MetadataDb::new_at_path(&tmp.join("metadata.sqlite")).expect("open db");
+        db.upsert_track(&TrackRecord {
+            path: "/music/a.flac".to_string(),
+            file_name: "a.flac".to_string(),
+            title: Some("A".to_string()),
+            artist: None,
+            album_artist: None,
+            album: None,
+            album_uuid: None,
+            track_number: None,
+            disc_number: None,
+            disc_title: None,
+            year: None,
+            genres: Vec::new(),
+            composer: None,
+            conductor: None,
+            work: None,
+            movement: None,
+            movement_number: None,
+            duration_ms: Some(200_000),
+            sample_rate: None,
+            bit_depth: None,
+            format: None,
+            mtime_ms: 1,
+            size_bytes: 1,
+        })
+        .expect("upsert");
+        let track_id = db.track_id_for_path("/music/a.flac").unwrap().unwrap();
+        let play = |played_at_ms: i64, completed_pct: Option<f64>| PlayRecord {
+            track_id,
+            session_id: "s1".to_string(),
+            output_id: Some("bridge:b:dev".to_string()),
+            played_at_ms,
+            played_ms: 1_000,
+            completed_pct,
+        };
+        db.record_play(&play(1_000, Some(100.0))).expect("finished");
+        db.record_play(&play(2_000, Some(10.0))).expect("skipped");
+        db.record_play(&play(3_000, Some(60.0)))
+            .expect("mostly heard");
+        db.record_play(&PlayRecord {
+            track_id: 9_999,
+            ..play(4_000, Some(100.0))
+        })
+        .expect("unknown track is ignored");
+
+        let history = db.list_play_history(None, None, 10, 0).expect("history");
+        let starts: Vec<i64> = history.iter().map(|entry| entry.played_at_ms).collect();
+        assert_eq!(starts, [3_000, 2_000, 1_000]);
+        assert_eq!(history[1].completed_pct, Some(10.0));
+        assert_eq!(history[0].track.title.as_deref(), Some("A"));
+        assert!(
+            db.list_play_history(None, Some("other"), 10, 0)
+                .expect("history")
+                .is_empty()
+        );
+
+        let tracks = db
+            .list_tracks(&TrackFilter::default(), 10, 0)
+            .expect("tracks");
+        assert_eq!(tracks[0].play_count, 2);
+        assert_eq!(tracks[0].last_played_at_ms, Some(3_000));
+    }
+
+    #[test]
+    fn audit_log_filters_and_prunes() {
+        let tmp = std::env::temp_dir().join(format!(
+            "audio-hub-audit-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let db = MetadataDb::new_at_path(&tmp.join("metadata.sqlite")).expect("open db");
+        let row = |at_ms: i64, actor: &str, action: &str| AuditRecord {
+            at_ms,
+            actor: Some(actor.to_string()),
+            session_id: Some("s1".to_string()),
+            action: action.to_string(),
+            method: "POST".to_string(),
+            path: "/sessions/s1/queue/next".to_string(),
+            status: 200,
+            ..Default::default()
+        };
+        db.record_audit(&[
+            row(1_000, "kid", "next"),
+            row(2_000, "parent", "stop"),
+            row(3_000, "kid", "next"),
+        ])
+        .expect("record");
+
+        let all = db.list_audit(&AuditFilter::default(), 10, 0).expect("list");
+        let times: Vec<i64> = all.iter().map(|entry| entry.at_ms).collect();
+        assert_eq!(times, [3_000, 2_000, 1_000]);
+        let kid = AuditFilter {
+            actor: Some("kid".to_string()),
+            since_ms: Some(2_000),
+            ..Default::default()
+        };
+        let kid = db.list_audit(&kid, 10, 0).expect("list");
+        assert_eq!(kid.len(), 1);
+        assert_eq!(kid[0].action, "next");
+
+        assert_eq!(db.prune_audit(2).expect("prune"), 1);
+        let kept = db.list_audit(&AuditFilter::default(), 10, 0).expect("list");
+        assert_eq!(kept.len(), 2);
+        assert_eq!(db.prune_audit(2).expect("prune"), 0);
+    }
+
+    #[test]
+    fn ratings_filter_and_sort_track_and_album_lists() {
+        let tmp = std::env::temp_dir().join(format!(
+            "audio-hub-ratings-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let db = MetadataDb::new_at_path(&tmp.join("metadata.sqlite")).expect("open db");
+        let mut record = TrackRecord {
+            path: "/music/A/a.flac".to_string(),
+            file_name: "a.flac".to_string(),
+            title: Some("A".to_string()),
+            artist: Some("Artist".to_string()),
+            album_artist: None,
+            album: Some("A".to_string()),
+            album_uuid: None,
+            track_number: None,
+            disc_number: None,
+            disc_title: None,
+            year: None,
+            genres: Vec::new(),
+            composer: None,
+            conductor: None,
+            work: None,
+            movement: None,
+            movement_number: None,
+            duration_ms: None,
+            sample_rate: None,
+            bit_depth: None,
+            format: None,
+            mtime_ms: 1,
+            size_bytes: 1,
+        };
+        db.upsert_track(&record).expect("upsert a");
+        record.path = "/music/B/b.flac".to_string();
+        record.file_name = "b.flac".to_string();
+        record.album = Some("B".to_string());
+        db.upsert_track(&record).expect("upsert b");
+        let a = db.track_id_for_path("/music/A/a.flac").unwrap().unwrap();
+        let b = db.track_id_for_path("/music/B/b.flac").unwrap().unwrap();
+
+        let current = db.set_track_rating(b, Some(true), None).expect("favorite");
+        assert!(current.favorite);
+        assert_eq!(current.rating, None);
+        let current = db.set_track_rating(b, None, Some(Some(4))).expect("rate");
+        assert!(current.favorite);
+        assert_eq!(current.rating, Some(4));
+        db.set_track_rating(a, None, Some(Some(5))).expect("rate a");
+
+        let names = |filter: TrackFilter<'_>| -> Vec<String> {
+            db.list_tracks(&filter, 10, 0)
+                .expect("tracks")
+                .into_iter()
+                .map(|track| track.file_name)
+                .collect()
+        };
+        assert_eq!(
+            names(TrackFilter {
+                favorite: Some(true),
+                ..TrackFilter::default()
+            }),
+            ["b.flac"]
+        );
+        assert_eq!(
+            names(TrackFilter {
+                min_rating: Some(5),
+                ..TrackFilter::default()
+            }),
+            ["a.flac"]
+        );
+        assert_eq!(
+            names(TrackFilter {
+                sort: TrackSort::Rating,
+                ..TrackFilter::default()
+            }),
+            ["a.flac", "b.flac"]
+        );
+
+        let cleared = db.set_track_rating(a, None, Some(None)).expect("clear");
+        assert_eq!(cleared, Rating::default());
+        assert_eq!(
+            names(TrackFilter {
+                favorite: Some(false),
+                ..TrackFilter::default()
+            }),
+            ["a.flac"]
+        );
+
+        let albums = db
+            .list_albums(&AlbumFilter::default(), 10, 0)
+            .expect("albums");
+        let album_b = albums.iter().find(|album| album.title == "B").unwrap().id;
+        db.set_album_rating(album_b, Some(true), Some(Some(3)))
+            .expect("rate album");
+        let albums = db
+            .list_albums(
+                &AlbumFilter {
+                    sort: AlbumSort::Rating,
+                    ..AlbumFilter::default()
+                },
+                10,
+                0,
+            )
+            .expect("albums");
+        assert_eq!(albums[0].title, "B");
+        assert!(albums[0].favorite);
+        assert_eq!(albums[0].rating, Some(3));
+        let favorites = db
+            .list_albums(
+                &AlbumFilter {
+                    favorite: Some(true),
+                    ..AlbumFilter::default()
+                },
+                10,
+                0,
+            )
+            .expect("albums");
+        assert_eq!(favorites.len(), 1);
+    }
+
+    #[test]
+    fn tempo_key_results_filter_track_lists() {
+        let tmp = std::env::temp_dir().join(format!(
+            "audio-hub-tempo-key-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let db = MetadataDb::new_at_path(&tmp.join("metadata.sqlite")).expect("open db");
+        let mut record = TrackRecord {
+            path: "/music/slow.flac".to_string(),
+            file_name: "slow.flac".to_string(),
+            title: Some("Slow".to_string()),
+            artist: None,
+            album_artist: None,
+            album: None,
+            album_uuid: None,
+            track_number: None,
+            disc_number: None,
+            disc_title: None,
+            year: None,
+            genres: Vec::new(),
+            composer: None,
+            conductor: None,
+            work: None,
+            movement: None,
+            movement_number: None,
+            duration_ms: None,
+            sample_rate: Some(44_100),
+            bit_depth: Some(16),
+            format: Some("FLAC".to_string()),
+            mtime_ms: 1,
+            size_bytes: 1,
+        };
+        db.upsert_track(&record).expect("upsert slow");
+        record.path = "/music/fast.flac".to_string();
+        record.file_name = "fast.flac".to_string();
+        db.upsert_track(&record).expect("upsert fast");
+
+        let candidates = db.list_tempo_key_candidates(10).expect("candidates");
+        assert_eq!(candidates.len(), 2);
+        for candidate in &candidates {
+            let fast = candidate.path.ends_with("fast.flac");
+            let result = TempoKeyResult {
+                bpm: Some(if fast { 174.0 } else { 80.0 }),
+                key: Some(if fast { "F# minor" } else { "C major" }.to_string()),
+                error: None,
+            };
+            db.upsert_tempo_key(candidate.track_id, candidate.mtime_ms, &result)
+                .expect("store tempo/key");
+        }
+        assert!(db.list_tempo_key_candidates(10).unwrap().is_empty());
+
+        let names = |filter: TrackFilter<'_>| -> Vec<String> {
+            db.list_tracks(&filter, 10, 0)
+                .expect("tracks")
+                .into_iter()
+                .map(|track| track.file_name)
+                .collect()
+        };
+        assert_eq!(
+            names(TrackFilter {
+                min_bpm: Some(120.0),
+                ..TrackFilter::default()
+            }),
+            ["fast.flac"]
+        );
+        assert_eq!(
+            names(TrackFilter {
+                max_bpm: Some(120.0),
+                key: Some("c MAJOR"),
+                ..TrackFilter::default()
+            }),
+            ["slow.flac"]
+        );
+        assert!(
+            names(TrackFilter {
+                key: Some("C major"),
+                min_bpm: Some(100.0),
+                ..TrackFilter::default()
+            })
+            .is_empty()
+        );
 
+        let _ = fs::remove_dir_all(&tmp);
+    }
 
-            CREATE TABLE IF NOT EXISTS album_notes (
-                album_id INTEGER NOT NULL,
-                lang TEXT NOT NULL,
-                text TEXT NOT NULL,
-                source TEXT,
-                locked INTEGER NOT NULL DEFAULT 0,
-                updated_at_ms INTEGER,
-                PRIMARY KEY (album_id, lang),
-                FOREIGN KEY(album_id) REFERENCES albums(id) ON DELETE CASCADE
-            );
-            CREATE TABLE IF NOT EXISTS media_assets (
-                id INTEGER PRIMARY KEY,
-                owner_type TEXT NOT NULL,
-                owner_id INTEGER NOT NULL,
-                kind TEXT NOT NULL,
-                local_path TEXT NOT NULL,
-                checksum TEXT,
-                source_url TEXT,
-                updated_at_ms INTEGER
-            );
+    #[test]
+    fn lossy_checks_track_candidates_and_report() {
+        let tmp = std::env::temp_dir().join(format!(
+            "audio-hub-lossy-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let db = MetadataDb::new_at_path(&tmp.join("metadata.sqlite")).expect("open db");
+        let mut record = TrackRecord {
+            path: "/music/a.flac".to_string(),
+            file_name: "a.flac".to_string(),
+            title: Some("A".to_string()),
+            artist: None,
+            album_artist: None,
+            album: None,
+            album_uuid: None,
+            track_number: None,
+            disc_number: None,
+            disc_title: None,
+            year: None,
+            genres: Vec::new(),
+            composer: None,
+            conductor: None,
+            work: None,
+            movement: None,
+            movement_number: None,
+            duration_ms: None,
+            sample_rate: Some(44_100),
+            bit_depth: Some(16),
+            format: Some("FLAC".to_string()),
+            mtime_ms: 1,
+            size_bytes: 1,
+        };
+        db.upsert_track(&record).expect("upsert flac");
+        record.path = "/music/b.mp3".to_string();
+        record.format = Some("MP3".to_string());
+        db.upsert_track(&record).expect("upsert mp3");
+
+        let candidates = db.list_lossy_check_candidates(10).expect("candidates");
+        assert_eq!(candidates.len(), 1);
+        let candidate = &candidates[0];
+        assert_eq!(candidate.path, "/music/a.flac");
+
+        let result = LossyCheckResult {
+            cutoff_hz: Some(16_000.0),
+            cutoff_drop_db: Some(80.0),
+            confidence: Some(0.9),
+            error: None,
+        };
+        db.upsert_lossy_check(candidate.track_id, candidate.mtime_ms, &result)
+            .expect("store check");
+        assert!(db.list_lossy_check_candidates(10).unwrap().is_empty());
 
-            CREATE INDEX IF NOT EXISTS idx_media_assets_owner_kind ON media_assets(owner_type, owner_id, kind);
-            "#,
-        )
-        .context("migrate bio/notes/assets tables")?;
-        conn.execute(
-            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
-            params![SCHEMA_VERSION.to_string()],
-        )
-        .context("update schema version")?;
-    }
+        let report = db.list_lossy_report(0.5, 10, 0).expect("report");
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].file_name, "a.flac");
+        assert!(db.list_lossy_report(0.95, 10, 0).unwrap().is_empty());
 
-    if version < 7 {
-        conn.execute_batch(
-            r#"
-            DROP TABLE IF EXISTS artist_histories;
-            DROP TABLE IF EXISTS album_histories;
-            "#,
-        )
-        .context("drop history tables")?;
-        conn.execute(
-            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
-            params![SCHEMA_VERSION.to_string()],
-        )
-        .context("update schema version")?;
+        let _ = fs::remove_dir_all(&tmp);
     }
 
-    if version < 8 {
-        conn.execute_batch(
-            r#"
-            ALTER TABLE artists ADD COLUMN uuid TEXT;
-            ALTER TABLE albums ADD COLUMN uuid TEXT;
-            "#,
-        )
-        .context("add artist/album uuid columns")?;
-        ensure_uuid_indexes(conn)?;
-        backfill_uuids(conn, "artists")?;
-        backfill_uuids(conn, "albums")?;
-        conn.execute(
-            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
-            params![SCHEMA_VERSION.to_string()],
-        )
-        .context("update schema version")?;
+    #[test]
+    fn genres_link_tracks_and_albums_and_filter_lists() {
+        let tmp = std::env::temp_dir().join(format!(
+            "audio-hub-genres-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let db = MetadataDb::new_at_path(&tmp.join("metadata.sqlite")).expect("open db");
+        let track = |path: &str, album: &str, genres: &[&str], mtime_ms: i64| TrackRecord {
+            path: path.to_string(),
+            file_name: path.rsplit('/').next().unwrap().to_string(),
+            title: None,
+            artist: Some("Artist".to_string()),
+            album_artist: None,
+            album: Some(album.to_string()),
+            album_uuid: None,
+            track_number: None,
+            disc_number: None,
+            disc_title: None,
+            year: None,
+            genres: genres.iter().map(|g| g.to_string()).collect(),
+            composer: None,
+            conductor: None,
+            work: None,
+            movement: None,
+            movement_number: None,
+            duration_ms: None,
+            sample_rate: None,
+            bit_depth: None,
+            format: None,
+            mtime_ms,
+            size_bytes: 1,
+        };
+        db.upsert_track(&track("/m/a1.flac", "A", &["Jazz", "Bebop"], 1))
+            .expect("upsert");
+        db.upsert_track(&track("/m/a2.flac", "A", &["jazz"], 1))
+            .expect("upsert");
+        db.upsert_track(&track("/m/b1.flac", "B", &["Rock"], 1))
+            .expect("upsert");
+
+        let genres = db.list_genres(None, 10, 0).expect("genres");
+        let counts: Vec<_> = genres
+            .iter()
+            .map(|g| (g.name.as_str(), g.album_count, g.track_count))
+            .collect();
+        assert_eq!(counts, [("Bebop", 1, 1), ("Jazz", 1, 2), ("Rock", 1, 1)]);
+        let jazz = genres[1].id;
+        let albums = db
+            .list_albums(
+                &AlbumFilter {
+                    genre_id: Some(jazz),
+                    ..AlbumFilter::default()
+                },
+                10,
+                0,
+            )
+            .expect("albums");
+        assert_eq!(albums.len(), 1);
+        assert_eq!(albums[0].title, "A");
+        let tracks = db
+            .list_tracks(
+                &TrackFilter {
+                    genre_id: Some(jazz),
+                    ..TrackFilter::default()
+                },
+                10,
+                0,
+            )
+            .expect("tracks");
+        assert_eq!(tracks.len(), 2);
+        let record = db.track_record_by_path("/m/a1.flac").expect("record");
+        assert_eq!(record.unwrap().genres, ["Jazz", "Bebop"]);
+
+        db.upsert_track(&track("/m/a1.flac", "A", &["Jazz"], 2))
+            .expect("upsert");
+        db.prune_orphaned_albums_and_artists().expect("prune");
+        let names: Vec<_> = db
+            .list_genres(None, 10, 0)
+            .expect("genres")
+            .into_iter()
+            .map(|g| g.name)
+            .collect();
+        assert_eq!(names, ["Jazz", "Rock"]);
+
+        let _ = fs::remove_dir_all(&tmp);
     }
 
-    if version < 9 {
-        conn.execute_batch(
-            r#"
-            ALTER TABLE albums ADD COLUMN original_year INTEGER;
-            ALTER TABLE albums ADD COLUMN edition_year INTEGER;
-            ALTER TABLE albums ADD COLUMN edition_label TEXT;
-            "#,
-        )
-        .context("add album edition columns")?;
-        conn.execute(
-            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
-            params![SCHEMA_VERSION.to_string()],
-        )
-        .context("update schema version")?;
-    }
+    #[test]
+    fn composers_group_classical_tracks_by_work() {
+        let tmp = std::env::temp_dir().join(format!(
+            "audio-hub-composers-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let db = MetadataDb::new_at_path(&tmp.join("metadata.sqlite")).expect("open db");
+        let track =
+            |path: &str, album: &str, composer: Option<&str>, work: &str, movement| TrackRecord {
+                path: path.to_string(),
+                file_name: path.rsplit('/').next().unwrap().to_string(),
+                title: None,
+                artist: Some("Berliner Philharmoniker".to_string()),
+                album_artist: None,
+                album: Some(album.to_string()),
+                album_uuid: None,
+                track_number: None,
+                disc_number: None,
+                disc_title: None,
+                year: None,
+                genres: Vec::new(),
+                composer: composer.map(str::to_string),
+                conductor: Some("Herbert von Karajan".to_string()),
+                work: Some(work.to_string()),
+                movement: None,
+                movement_number: Some(movement),
+                duration_ms: None,
+                sample_rate: None,
+                bit_depth: None,
+                format: None,
+                mtime_ms: 1,
+                size_bytes: 1,
+            };
+        for record in [
+            track(
+                "/m/b2.flac",
+                "Symphonies",
+                Some("Brahms"),
+                "Symphony No. 1",
+                2,
+            ),
+            track(
+                "/m/b1.flac",
+                "Symphonies",
+                Some("Brahms"),
+                "Symphony No. 1",
+                1,
+            ),
+            track(
+                "/m/a1.flac",
+                "Symphonies",
+                Some("Beethoven"),
+                "Symphony No. 5",
+                1,
+            ),
+            track("/m/c1.flac", "Overtures", Some("beethoven"), "Egmont", 1),
+            track("/m/x1.flac", "Anthology", None, "Unknown", 1),
+        ] {
+            db.upsert_track(&record).expect("upsert");
+        }
 
-    if version < 10 {
-        conn.execute("ALTER TABLE albums ADD COLUMN orphaned_at INTEGER", [])
-            .context("add album orphaned_at")?;
-        conn.execute(
-            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
-            params![SCHEMA_VERSION.to_string()],
-        )
-        .context("update schema version")?;
-    }
+        let composers: Vec<_> = db
+            .list_composers(None, 10, 0)
+            .expect("composers")
+            .into_iter()
+            .map(|c| (c.name, c.album_count, c.track_count, c.work_count))
+            .collect();
+        assert_eq!(
+            composers,
+            [
+                ("Beethoven".to_string(), 2, 2, 2),
+                ("Brahms".to_string(), 1, 2, 1)
+            ]
+        );
 
-    Ok(())
-}
+        let albums = db
+            .list_albums(
+                &AlbumFilter {
+                    sort: AlbumSort::Composer,
+                    ..AlbumFilter::default()
+                },
+                10,
+                0,
+            )
+            .expect("albums");
+        let albums: Vec<_> = albums
+            .iter()
+            .map(|a| (a.title.as_str(), a.composer.as_deref()))
+            .collect();
+        assert_eq!(
+            albums,
+            [
+                ("Overtures", Some("beethoven")),
+                ("Anthology", None),
+                ("Symphonies", None)
+            ]
+        );
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
+        let tracks = db
+            .list_tracks(
+                &TrackFilter {
+                    composer: Some("BRAHMS"),
+                    sort: TrackSort::Work,
+                    ..TrackFilter::default()
+                },
+                10,
+                0,
+            )
+            .expect("tracks");
+        let files: Vec<_> = tracks.iter().map(|t| t.file_name.as_str()).collect();
+        assert_eq!(files, ["b1.flac", "b2.flac"]);
+        assert_eq!(tracks[0].conductor.as_deref(), Some("Herbert von Karajan"));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
 
     #[test]
-    fn db_path_for_places_db_under_audio_hub_dir() {
-        let root = Path::new("/music/library");
-        let path = db_path_for(root);
-        assert_eq!(path, root.join(".audio-hub").join("metadata.sqlite"));
+    fn album_discs_and_track_order_follow_box_set_discs() {
+        let tmp = std::env::temp_dir().join(format!(
+            "audio-hub-discs-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let db = MetadataDb::new_at_path(&tmp.join("metadata.sqlite")).expect("open db");
+        let track = |path: &str, album: &str, disc: u32, number: u32, disc_title: Option<&str>| {
+            TrackRecord {
+                path: path.to_string(),
+                file_name: path.rsplit('/').next().unwrap().to_string(),
+                title: None,
+                artist: Some("Artist".to_string()),
+                album_artist: None,
+                album: Some(album.to_string()),
+                album_uuid: Some(format!("uuid-{album}")),
+                track_number: Some(number),
+                disc_number: Some(disc),
+                disc_title: disc_title.map(str::to_string),
+                year: None,
+                genres: Vec::new(),
+                composer: None,
+                conductor: None,
+                work: None,
+                movement: None,
+                movement_number: None,
+                duration_ms: Some(1000),
+                sample_rate: None,
+                bit_depth: None,
+                format: None,
+                mtime_ms: 1,
+                size_bytes: 1,
+            }
+        };
+        for record in [
+            track("/m/Box/CD2/01.flac", "Box", 2, 1, Some("Live")),
+            track("/m/Box/CD1/02.flac", "Box", 1, 2, Some("Studio")),
+            track("/m/Box/CD1/01.flac", "Box", 1, 1, Some("Studio")),
+            track("/m/Another/01.flac", "Another", 1, 1, None),
+        ] {
+            db.upsert_track(&record).expect("upsert");
+        }
+
+        let tracks = db
+            .list_tracks(&TrackFilter::default(), 10, 0)
+            .expect("tracks");
+        let order: Vec<_> = tracks
+            .iter()
+            .map(|t| (t.album.as_deref().unwrap(), t.disc_number, t.track_number))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                ("Another", Some(1), Some(1)),
+                ("Box", Some(1), Some(1)),
+                ("Box", Some(1), Some(2)),
+                ("Box", Some(2), Some(1)),
+            ]
+        );
+        assert_eq!(tracks[3].disc_title.as_deref(), Some("Live"));
+
+        let box_id = db
+            .list_albums(&AlbumFilter::default(), 10, 0)
+            .expect("albums")
+            .into_iter()
+            .find(|a| a.title == "Box")
+            .expect("box album")
+            .id;
+        let discs: Vec<_> = db
+            .album_discs(box_id)
+            .expect("discs")
+            .into_iter()
+            .map(|d| (d.disc_number, d.title, d.track_count, d.duration_ms))
+            .collect();
+        assert_eq!(
+            discs,
+            [
+                (1, Some("Studio".to_string()), 2, Some(2000)),
+                (2, Some("Live".to_string()), 1, Some(1000)),
+            ]
+        );
+
+        let _ = fs::remove_dir_all(&tmp);
     }
 
     #[test]
-    fn is_blank_handles_empty_and_whitespace() {
-        assert!(is_blank(&None));
-        assert!(is_blank(&Some("".to_string())));
-        assert!(is_blank(&Some("  ".to_string())));
-        assert!(!is_blank(&Some("a".to_string())));
+    fn acoustid_candidates_cover_untagged_tracks_and_reset_on_file_change() {
+        let tmp = std::env::temp_dir().join(format!(
+            "audio-hub-acoustid-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let db = MetadataDb::new_at_path(&tmp.join("metadata.sqlite")).expect("open db");
+        let record = |name: &str, title: Option<&str>, mtime_ms: i64| TrackRecord {
+            path: format!("/m/{name}"),
+            file_name: name.to_string(),
+            title: title.map(str::to_string),
+            artist: title.map(|_| "Artist".to_string()),
+            album_artist: None,
+            album: None,
+            album_uuid: None,
+            track_number: None,
+            disc_number: None,
+            disc_title: None,
+            year: None,
+            genres: Vec::new(),
+            composer: None,
+            conductor: None,
+            work: None,
+            movement: None,
+            movement_number: None,
+            duration_ms: None,
+            sample_rate: None,
+            bit_depth: None,
+            format: None,
+            mtime_ms,
+            size_bytes: 1,
+        };
+        db.upsert_track(&record("tagged.flac", Some("Song"), 1))
+            .expect("upsert");
+        db.upsert_track(&record("untagged.flac", None, 1))
+            .expect("upsert");
+        let candidates = db.list_acoustid_candidates(10).expect("candidates");
+        assert_eq!(candidates.len(), 1);
+        let track_id = candidates[0].track_id;
+        assert_eq!(candidates[0].fingerprint, None);
+
+        db.set_acoustid_fingerprint(track_id, "AQAD", 200)
+            .expect("fingerprint");
+        let found = AcoustIdMatch {
+            acoustid_id: "acoustid-1".to_string(),
+            recording_mbid: "rec-1".to_string(),
+            title: Some("Found".to_string()),
+            artist: Some("Found Artist".to_string()),
+            artist_mbid: Some("artist-1".to_string()),
+        };
+        db.apply_acoustid_result(track_id, Some(&found), 5)
+            .expect("apply");
+        assert!(
+            db.list_acoustid_candidates(10)
+                .expect("candidates")
+                .is_empty()
+        );
+        let stored = db
+            .track_record_by_id(track_id)
+            .expect("record")
+            .expect("track");
+        assert_eq!(stored.title.as_deref(), Some("Found"));
+        assert_eq!(stored.artist.as_deref(), Some("Found Artist"));
+
+        // A changed file drops the fingerprint and is matched again.
+        db.upsert_track(&record("untagged.flac", None, 2))
+            .expect("upsert");
+        let candidates = db.list_acoustid_candidates(10).expect("candidates");
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].fingerprint, None);
+        assert_eq!(candidates[0].duration_s, None);
+        let _ = fs::remove_dir_all(&tmp);
     }
 
     #[test]
-    fn find_artist_id_and_album_id_work() {
-        let conn = Connection::open_in_memory().expect("open memory db");
-        conn.execute_batch(
-            r#"
-            CREATE TABLE artists (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
-            CREATE TABLE albums (id INTEGER PRIMARY KEY, title TEXT NOT NULL, artist_id INTEGER);
-            "#,
-        )
-        .expect("create schema");
-        conn.execute("INSERT INTO artists (id, name) VALUES (1, 'A-Ha')", [])
-            .expect("insert artist");
+    fn artist_image_candidates_skip_checked_failed_and_imaged_artists() {
+        let tmp = std::env::temp_dir().join(format!(
+            "audio-hub-artist-images-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let db = MetadataDb::new_at_path(&tmp.join("metadata.sqlite")).expect("open db");
+        for (index, artist) in ["A", "B", "C", "D"].into_iter().enumerate() {
+            db.upsert_track(&TrackRecord {
+                path: format!("/m/{index}.flac"),
+                file_name: format!("{index}.flac"),
+                title: None,
+                artist: Some(artist.to_string()),
+                album_artist: None,
+                album: None,
+                album_uuid: None,
+                track_number: None,
+                disc_number: None,
+                disc_title: None,
+                year: None,
+                genres: Vec::new(),
+                composer: None,
+                conductor: None,
+                work: None,
+                movement: None,
+                movement_number: None,
+                duration_ms: None,
+                sample_rate: None,
+                bit_depth: None,
+                format: None,
+                mtime_ms: 1,
+                size_bytes: 1,
+            })
+            .expect("upsert");
+        }
+        let conn = db.pool.get().expect("conn");
         conn.execute(
-            "INSERT INTO albums (id, title, artist_id) VALUES (10, 'Hunting High and Low', 1)",
+            "UPDATE artists SET mbid = 'mbid-' || name WHERE name != 'D'",
             [],
         )
-        .expect("insert album");
+        .expect("set mbids");
+        let id_of = |name: &str| -> i64 {
+            conn.query_row(
+                "SELECT id FROM artists WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .expect("artist id")
+        };
+        let candidates = |db: &MetadataDb| -> Vec<String> {
+            db.list_artist_image_candidates(10)
+                .expect("candidates")
+                .into_iter()
+                .map(|c| c.mbid)
+                .collect()
+        };
+        assert_eq!(candidates(&db), ["mbid-A", "mbid-B", "mbid-C"]);
 
-        let artist_id = find_artist_id(&conn, "A-Ha").expect("find artist");
-        assert_eq!(artist_id, Some(1));
-        let album_id = find_album_id(&conn, "Hunting High and Low", Some(1)).expect("find album");
-        assert_eq!(album_id, Some(10));
+        db.mark_artist_image_checked(id_of("A"), 1).expect("mark");
+        for _ in 0..3 {
+            db.increment_artist_image_fail(id_of("B"), "timeout")
+                .expect("fail");
+        }
+        db.upsert_media_asset("artist", id_of("C"), "image", "x.jpg", None, None, None)
+            .expect("asset");
+        assert!(candidates(&db).is_empty());
 
-        let missing_artist = find_artist_id(&conn, "Missing").expect("find missing artist");
-        assert_eq!(missing_artist, None);
-        let missing_album = find_album_id(&conn, "Missing", Some(1)).expect("find missing album");
-        assert_eq!(missing_album, None);
+        let _ = fs::remove_dir_all(&tmp);
     }
 
     #[test]
-    fn relative_from_absolute_supports_root_basename_heuristic() {
+    fn search_ranks_mixed_results_and_follows_edits() {
         let tmp = std::env::temp_dir().join(format!(
-            "audio-hub-path-rel-{}",
+            "audio-hub-search-{}",
             SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_nanos()
         ));
-        let root = tmp.join("music");
-        let track = root.join("Artist").join("Album").join("song.flac");
-        fs::create_dir_all(track.parent().unwrap()).expect("create track dir");
-        fs::write(&track, b"audio").expect("write track file");
+        let db = MetadataDb::new_at_path(&tmp.join("metadata.sqlite")).expect("open db");
+        let track = |path: &str, title: &str, artist: &str, album: &str| TrackRecord {
+            path: path.to_string(),
+            file_name: path.rsplit('/').next().unwrap().to_string(),
+            title: Some(title.to_string()),
+            artist: Some(artist.to_string()),
+            album_artist: Some(artist.to_string()),
+            album: Some(album.to_string()),
+            album_uuid: None,
+            track_number: None,
+            disc_number: None,
+            disc_title: None,
+            year: None,
+            genres: Vec::new(),
+            composer: None,
+            conductor: None,
+            work: None,
+            movement: None,
+            movement_number: None,
+            duration_ms: None,
+            sample_rate: None,
+            bit_depth: None,
+            format: None,
+            mtime_ms: 1,
+            size_bytes: 1,
+        };
+        db.upsert_track(&track(
+            "/m/1.flac",
+            "Blue in Green",
+            "Miles Davis",
+            "Kind of Blue",
+        ))
+        .expect("upsert");
+        db.upsert_track(&track(
+            "/m/2.flac",
+            "So What",
+            "Miles Davis",
+            "Kind of Blue",
+        ))
+        .expect("upsert");
+        db.upsert_track(&track("/m/3.flac", "Björk Song", "Björk", "Début"))
+            .expect("upsert");
+
+        let hits = db.search("blue", None, 10).expect("search");
+        let found: Vec<_> = hits.iter().map(|h| (h.kind, h.name.as_str())).collect();
+        // Title matches outrank a track found only through its album.
+        assert_eq!(found.len(), 3);
+        assert!(found[..2].contains(&(SearchKind::Album, "Kind of Blue")));
+        assert!(found[..2].contains(&(SearchKind::Track, "Blue in Green")));
+        assert_eq!(found[2], (SearchKind::Track, "So What"));
+        let album = hits.iter().find(|h| h.kind == SearchKind::Album).unwrap();
+        assert_eq!(album.artist.as_deref(), Some("Miles Davis"));
+
+        let tracks = db
+            .search("mil so", Some(SearchKind::Track), 10)
+            .expect("search");
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].album.as_deref(), Some("Kind of Blue"));
+        // Diacritics are folded; the album and its track both match.
+        assert_eq!(db.search("bjork debut", None, 10).unwrap().len(), 2);
+        assert!(db.search("\"* OR", None, 10).unwrap().is_empty());
+
+        let retitled = TrackRecord {
+            mtime_ms: 2,
+            ..track(
+                "/m/2.flac",
+                "Freddie Freeloader",
+                "Miles Davis",
+                "Kind of Blue",
+            )
+        };
+        db.upsert_track(&retitled).expect("retitle");
+        assert!(db.search("so what", None, 10).unwrap().is_empty());
+        assert_eq!(db.search("freddie", None, 10).unwrap().len(), 1);
+        assert!(db.delete_track_by_path("/m/3.flac").unwrap());
+        assert!(
+            db.search("bjork", Some(SearchKind::Track), 10)
+                .unwrap()
+                .is_empty()
+        );
 
-        let legacy = Path::new("/legacy/mount/music/Artist/Album/song.flac");
-        let rel = relative_from_absolute(legacy, &root).expect("relative path");
-        assert_eq!(rel, PathBuf::from("Artist/Album/song.flac"));
+        let _ = fs::remove_dir_all(&tmp);
     }
 }
 
@@ -2312,6 +7491,37 @@ fn upsert_artist(conn: &Connection, name: &str) -> Result<i64> {
     Ok(id)
 }
 
+/// Replace the genre links of one track, creating genres as needed.
+fn replace_track_genres(conn: &Connection, track_id: i64, genres: &[String]) -> Result<()> {
+    conn.execute(
+        "DELETE FROM track_genres WHERE track_id = ?1",
+        params![track_id],
+    )
+    .context("clear track genres")?;
+    for (position, name) in genres.iter().enumerate() {
+        conn.execute(
+            "INSERT OR IGNORE INTO genres (name) VALUES (?1)",
+            params![name],
+        )
+        .context("upsert genre")?;
+        conn.execute(
+            r#"
+            INSERT OR IGNORE INTO track_genres (track_id, genre_id, position)
+            SELECT ?1, id, ?2 FROM genres WHERE name = ?3
+            "#,
+            params![track_id, position as i64, name],
+        )
+        .context("link track genre")?;
+    }
+    Ok(())
+}
+
+/// Split a `char(31)`-separated genre list as returned by track record queries.
+fn split_genre_list(raw: Option<String>) -> Vec<String> {
+    raw.map(|raw| raw.split('\u{1f}').map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
 /// Insert-or-fetch album id by `(title, artist_id)` and ensure UUID presence.
 fn upsert_album(
     conn: &Connection,
@@ -2320,8 +7530,15 @@ fn upsert_album(
     year: Option<i32>,
 ) -> Result<i64> {
     conn.execute(
-        "INSERT OR IGNORE INTO albums (uuid, title, artist_id, year, sort_title) VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![Uuid::new_v4().to_string(), title, artist_id, year, title.to_lowercase()],
+        "INSERT OR IGNORE INTO albums (uuid, title, artist_id, year, sort_title, first_seen_at_ms) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            Uuid::new_v4().to_string(),
+            title,
+            artist_id,
+            year,
+            title.to_lowercase(),
+            now_ms()
+        ],
     )
     .context("upsert album")?;
     let id: i64 = conn.query_row(
@@ -2370,8 +7587,8 @@ fn upsert_album_with_uuid(
         id
     } else {
         conn.execute(
-            "INSERT INTO albums (uuid, title, artist_id, year, sort_title) VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![uuid, title, artist_id, year, title.to_lowercase()],
+            "INSERT INTO albums (uuid, title, artist_id, year, sort_title, first_seen_at_ms) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![uuid, title, artist_id, year, title.to_lowercase(), now_ms()],
         )
         .context("insert album with uuid")?;
         conn.query_row(
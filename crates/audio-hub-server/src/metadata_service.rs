@@ -1,7 +1,7 @@
 //! Shared metadata operations (scan/rescan/update helpers).
 
 use std::path::{Path, PathBuf};
-use std::sync::RwLock;
+use std::sync::{Mutex, RwLock};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use actix_web::HttpResponse;
@@ -11,7 +11,7 @@ use uuid::Uuid;
 
 use crate::cover_art::CoverArtResolver;
 use crate::events::{EventBus, MetadataEvent};
-use crate::library::{LibraryIndex, TrackMeta, probe_track, scan_library_with_meta};
+use crate::library::{LibraryIndex, ScanProgress, TrackMeta, probe_track, scan_library_with_meta};
 use crate::metadata_db::{AlbumSummary, MetadataDb, TrackRecord};
 use crate::state::MetadataWake;
 
@@ -74,17 +74,19 @@ impl MetadataService {
             album_uuid,
             track_number: meta.track_number,
             disc_number,
+            disc_title: meta.disc_title.clone(),
             year: meta.year,
+            genres: meta.genres.clone(),
+            composer: meta.composer.clone(),
+            conductor: meta.conductor.clone(),
+            work: meta.work.clone(),
+            movement: meta.movement.clone(),
+            movement_number: meta.movement_number,
             duration_ms: meta.duration_ms,
             sample_rate: meta.sample_rate,
             bit_depth: meta.bit_depth,
             format: meta.format.clone(),
-            mtime_ms: fs_meta
-                .modified()
-                .ok()
-                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                .map(|d| d.as_millis() as i64)
-                .unwrap_or(0),
+            mtime_ms: file_mtime_ms(fs_meta),
             size_bytes: fs_meta.len() as i64,
         }
     }
@@ -96,22 +98,98 @@ impl MetadataService {
         meta: &TrackMeta,
         record: &TrackRecord,
     ) -> Result<(), String> {
+        let content_hash = match content_hash(path) {
+            Ok(hash) => Some(hash),
+            Err(err) => {
+                tracing::debug!(error = %err, path = %record.path, "content hash failed");
+                None
+            }
+        };
+        if let Some(hash) = &content_hash {
+            self.rebind_moved_track(record, hash);
+        }
         self.db
             .upsert_track(record)
             .map_err(|err| err.to_string())?;
+        if let Some(hash) = &content_hash
+            && let Err(err) = self.db.set_track_content_hash(&record.path, hash)
+        {
+            tracing::warn!(error = %err, path = %record.path, "content hash store failed");
+        }
         if let Err(err) = self.cover_art.apply_for_track(path, meta, record) {
             tracing::warn!(error = %err, path = %record.path, "cover art apply failed");
         }
+        let lyrics = crate::lyrics::local_lyrics(path, meta.lyrics.as_deref());
+        if let Err(err) = self.db.sync_local_lyrics(&record.path, lyrics.as_ref()) {
+            tracing::warn!(error = %err, path = %record.path, "lyrics sync failed");
+        }
         Ok(())
     }
 
+    /// Move the row of a vanished file with the same content onto `record`'s new path.
+    ///
+    /// Keeping the row keeps its id, so MBIDs and everything stored per track id survive
+    /// library reorganizations. Only rows whose file is gone qualify; a copy next to the
+    /// original gets a row of its own.
+    fn rebind_moved_track(&self, record: &TrackRecord, hash: &str) {
+        if !matches!(self.db.track_id_for_path(&record.path), Ok(None)) {
+            return;
+        }
+        let candidates = match self.db.tracks_with_content_hash(hash) {
+            Ok(candidates) => candidates,
+            Err(err) => {
+                tracing::warn!(error = %err, "content hash lookup failed");
+                return;
+            }
+        };
+        let Some((track_id, old_path)) = candidates.into_iter().find(|(_, path)| {
+            !crate::cue_tracks::source_file(Path::new(path)).is_ok_and(|file| file.exists())
+        }) else {
+            return;
+        };
+        if let Err(err) = self
+            .db
+            .rebind_track_path(track_id, &record.path, &record.file_name)
+        {
+            tracing::warn!(error = %err, path = %record.path, "track rebind failed");
+            return;
+        }
+        tracing::info!(track_id, from = %old_path, to = %record.path, "track moved");
+        self.events.metadata_event(MetadataEvent::TrackMoved {
+            track_id,
+            from: old_path,
+            to: record.path.clone(),
+        });
+    }
+
+    /// Hash tracks stored before content hashes existed, so later moves can be recognized.
+    fn backfill_content_hashes(&self) {
+        let paths = match self.db.list_track_paths_without_content_hash() {
+            Ok(paths) => paths,
+            Err(err) => {
+                tracing::warn!(error = %err, "content hash backfill query failed");
+                return;
+            }
+        };
+        for path in paths {
+            if let Ok(hash) = content_hash(Path::new(&path))
+                && let Err(err) = self.db.set_track_content_hash(&path, &hash)
+            {
+                tracing::warn!(error = %err, path = %path, "content hash store failed");
+            }
+        }
+    }
+
     /// Rescan a single track file and update DB + in-memory index.
     pub fn rescan_track(
         &self,
         library: &RwLock<LibraryIndex>,
         full_path: &Path,
     ) -> Result<(), HttpResponse> {
-        let fs_meta = match std::fs::metadata(full_path) {
+        // Cue tracks take size/mtime from their audio file.
+        let source_path = crate::cue_tracks::source_file(full_path)
+            .map_err(|_| HttpResponse::NotFound().finish())?;
+        let fs_meta = match std::fs::metadata(&source_path) {
             Ok(meta) => meta,
             Err(_) => return Err(HttpResponse::NotFound().finish()),
         };
@@ -164,7 +242,7 @@ impl MetadataService {
             .file_name()
             .and_then(|name| name.to_str())
             .unwrap_or("<unknown>");
-        let ext_hint = full_path
+        let ext_hint = source_path
             .extension()
             .and_then(|ext| ext.to_str())
             .unwrap_or("")
@@ -254,9 +332,27 @@ impl MetadataService {
             .map_err(|err| err.to_string())
     }
 
+    /// Replace an album's cover with uploaded image bytes.
+    pub fn replace_album_cover(
+        &self,
+        album_id: i64,
+        mime_type: &str,
+        data: &[u8],
+    ) -> Result<(), String> {
+        self.cover_art
+            .replace_album_cover(album_id, mime_type, data)
+            .map_err(|err| err.to_string())?;
+        self.events.library_changed();
+        Ok(())
+    }
+
     /// Full library rescan plus stale-track pruning and marker backfill.
-    pub fn rescan_library(&self, emit_events: bool) -> Result<LibraryIndex> {
-        let (index, seen_paths) = self.scan_library_with_paths(emit_events)?;
+    pub fn rescan_library(
+        &self,
+        progress: &ScanProgress,
+        emit_events: bool,
+    ) -> Result<LibraryIndex> {
+        let (index, seen_paths) = self.scan_library_with_paths(progress, emit_events)?;
         let existing = self.db.list_all_track_paths()?;
         for path in existing {
             if !seen_paths.contains(path.as_str()) {
@@ -269,15 +365,41 @@ impl MetadataService {
     }
 
     /// Internal scan returning both index and seen path set.
+    ///
+    /// Files whose size and mtime match their DB record reuse the stored metadata instead
+    /// of being probed and upserted again, so a scan cut short by a restart picks up where
+    /// it stopped.
     fn scan_library_with_paths(
         &self,
+        progress: &ScanProgress,
         emit_events: bool,
     ) -> Result<(LibraryIndex, std::collections::HashSet<String>)> {
+        self.backfill_content_hashes();
         let mut seen = std::collections::HashSet::new();
+        let unchanged = Mutex::new(std::collections::HashSet::new());
         let index = scan_library_with_meta(
             &self.root,
+            progress,
+            |path, fs_meta| {
+                let record = self
+                    .db
+                    .track_record_by_path(&path.to_string_lossy())
+                    .ok()
+                    .flatten()?;
+                if record.mtime_ms != file_mtime_ms(fs_meta)
+                    || record.size_bytes != fs_meta.len() as i64
+                    || record.duration_ms.is_none()
+                {
+                    return None;
+                }
+                unchanged.lock().unwrap().insert(path.to_path_buf());
+                Some(track_meta_from_record(record))
+            },
             |path, file_name, _ext, meta, fs_meta| {
                 seen.insert(path.to_string_lossy().to_string());
+                if unchanged.lock().unwrap().remove(path) {
+                    return;
+                }
                 let mut normalized_meta = meta.clone();
                 let original_album = normalized_meta.album.clone();
                 let (album, disc_number, source) = normalize_album_and_disc(path, &normalized_meta);
@@ -374,8 +496,8 @@ impl MetadataService {
     }
 
     /// Scan library and return fresh index (without stale-track pruning).
-    pub fn scan_library(&self, emit_events: bool) -> Result<LibraryIndex> {
-        let (index, _) = self.scan_library_with_paths(emit_events)?;
+    pub fn scan_library(&self, progress: &ScanProgress, emit_events: bool) -> Result<LibraryIndex> {
+        let (index, _) = self.scan_library_with_paths(progress, emit_events)?;
         Ok(index)
     }
 
@@ -390,7 +512,9 @@ impl MetadataService {
         };
         for candidate in candidates {
             let path = PathBuf::from(candidate.path);
-            let Some(dir) = path.parent() else { continue };
+            let Some(dir) = album_dir(&path) else {
+                continue;
+            };
             if read_album_marker(dir).is_some() {
                 continue;
             }
@@ -418,6 +542,10 @@ impl MetadataService {
         } else {
             root.join(raw_path)
         };
+        if let Some((cue_path, number)) = crate::cue_tracks::split_track_path(&candidate) {
+            let cue_path = Self::resolve_track_path(root, &cue_path.to_string_lossy())?;
+            return Ok(crate::cue_tracks::track_path(&cue_path, number));
+        }
         let full_path = match candidate.canonicalize() {
             Ok(path) => path,
             Err(_) => return Err(HttpResponse::NotFound().finish()),
@@ -441,16 +569,21 @@ impl MetadataService {
         else {
             return None;
         };
-        let dir = path.parent()?;
+        let dir = album_dir(path)?;
         if let Some(marker) = read_album_marker(dir) {
             return Some(marker.album_uuid);
         }
         let album_artist = meta.album_artist.as_deref().or(meta.artist.as_deref());
-        let existing = self
-            .db
-            .album_uuid_for_title_artist(album_title, album_artist)
-            .ok()
-            .flatten();
+        // Disc folders scanned before box sets were grouped carry their own marker.
+        let existing = disc_folder(path)
+            .and_then(|_| read_album_marker(path.parent()?))
+            .map(|marker| marker.album_uuid)
+            .or_else(|| {
+                self.db
+                    .album_uuid_for_title_artist(album_title, album_artist)
+                    .ok()
+                    .flatten()
+            });
         let album_uuid = existing.unwrap_or_else(|| Uuid::new_v4().to_string());
         let marker = AlbumFolderMarker {
             album_uuid: album_uuid.clone(),
@@ -469,13 +602,86 @@ impl MetadataService {
     }
 }
 
+/// File modification time in unix milliseconds, as stored on track records.
+fn file_mtime_ms(fs_meta: &std::fs::Metadata) -> i64 {
+    fs_meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Bytes hashed from each end of a file for its content hash.
+const CONTENT_HASH_SPAN: u64 = 64 * 1024;
+
+/// Cheap content fingerprint used to recognize moved or renamed files.
+///
+/// Combines the file size with an FNV-1a hash of the first and last 64 KiB, which differ
+/// between encodes and carry the tags. Cue tracks hash their audio file plus the track number.
+fn content_hash(path: &Path) -> std::io::Result<String> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let (file_path, suffix) = match crate::cue_tracks::split_track_path(path) {
+        Some((_, number)) => (
+            crate::cue_tracks::source_file(path).map_err(std::io::Error::other)?,
+            format!("#{number:02}"),
+        ),
+        None => (path.to_path_buf(), String::new()),
+    };
+    let mut file = std::fs::File::open(&file_path)?;
+    let size = file.metadata()?.len();
+    let mut data = Vec::with_capacity(2 * CONTENT_HASH_SPAN as usize);
+    (&mut file).take(CONTENT_HASH_SPAN).read_to_end(&mut data)?;
+    if size > CONTENT_HASH_SPAN {
+        file.seek(SeekFrom::Start(
+            size.saturating_sub(CONTENT_HASH_SPAN)
+                .max(CONTENT_HASH_SPAN),
+        ))?;
+        file.read_to_end(&mut data)?;
+    }
+    let hash = data.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    });
+    Ok(format!("{size:x}-{hash:016x}{suffix}"))
+}
+
+/// Track metadata recovered from a stored record, for files a scan does not re-probe.
+fn track_meta_from_record(record: TrackRecord) -> TrackMeta {
+    TrackMeta {
+        duration_ms: record.duration_ms,
+        sample_rate: record.sample_rate,
+        bit_depth: record.bit_depth,
+        album: record.album,
+        artist: record.artist,
+        album_artist: record.album_artist,
+        compilation: false,
+        title: record.title,
+        track_number: record.track_number,
+        disc_title: record.disc_title,
+        lyrics: None,
+        disc_number: record.disc_number,
+        year: record.year,
+        genres: record.genres,
+        composer: record.composer,
+        conductor: record.conductor,
+        work: record.work,
+        movement: record.movement,
+        movement_number: record.movement_number,
+        format: record.format,
+        cover_art: None,
+    }
+}
+
 /// Normalize album names containing disc suffixes and infer disc number.
 fn normalize_album_and_disc(
     path: &Path,
     meta: &TrackMeta,
 ) -> (Option<String>, Option<u32>, Option<&'static str>) {
     let mut album = meta.album.clone();
-    let mut disc_number = meta.disc_number;
+    let mut disc_number = meta
+        .disc_number
+        .or_else(|| disc_folder(path).map(|(_, disc)| disc));
     let Some(raw_album) = meta.album.as_deref() else {
         return (album, disc_number, None);
     };
@@ -645,6 +851,28 @@ fn disc_hint_from_path(path: &Path) -> Option<u32> {
     parse_disc_number(&name)
 }
 
+/// Release folder and disc number when `path` sits in a `CD1`/`Disc 2` folder of a box set.
+fn disc_folder(path: &Path) -> Option<(&Path, u32)> {
+    let dir = path.parent()?;
+    let name = dir.file_name()?.to_str()?.trim().to_ascii_lowercase();
+    let rest = ["disc", "disk", "cd"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))?;
+    // `Disco Hits 2` is an album folder, not a disc.
+    if rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let disc = parse_disc_number(&name)?;
+    Some((dir.parent()?, disc))
+}
+
+/// Folder holding the album marker for `path`: the release folder for disc folders.
+fn album_dir(path: &Path) -> Option<&Path> {
+    disc_folder(path)
+        .map(|(release, _)| release)
+        .or_else(|| path.parent())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -698,6 +926,75 @@ mod tests {
         assert_eq!(record.size_bytes, fs_meta.len() as i64);
     }
 
+    #[test]
+    fn moved_files_keep_their_track_row() {
+        let root = temp_root();
+        let db = MetadataDb::new_at_path(&root.join("metadata.sqlite")).expect("open db");
+        let service = MetadataService::new(
+            db.clone(),
+            root.clone(),
+            EventBus::new(),
+            MetadataWake::new(),
+        );
+        let meta = TrackMeta {
+            title: Some("Song".to_string()),
+            format: Some("FLAC".to_string()),
+            ..TrackMeta::default()
+        };
+        let upsert = |path: &Path| {
+            let fs_meta = std::fs::metadata(path).expect("metadata");
+            let file_name = path.file_name().unwrap().to_str().unwrap();
+            let record =
+                MetadataService::build_track_record(path, file_name, &meta, &fs_meta, None);
+            service
+                .upsert_track_record(path, &meta, &record)
+                .expect("upsert");
+            db.track_id_for_path(&record.path)
+                .expect("lookup")
+                .expect("track id")
+        };
+
+        let old_path = root.join("song.flac");
+        std::fs::write(&old_path, vec![7u8; 200_000]).expect("write file");
+        let track_id = upsert(&old_path);
+        let found = crate::metadata_db::AcoustIdMatch {
+            acoustid_id: "aid".to_string(),
+            recording_mbid: "mbid-1".to_string(),
+            title: None,
+            artist: None,
+            artist_mbid: None,
+        };
+        db.apply_acoustid_result(track_id, Some(&found), 1)
+            .expect("set mbid");
+
+        // A copy next to the original is a separate track.
+        let copy = root.join("copy.flac");
+        std::fs::copy(&old_path, &copy).expect("copy file");
+        assert_ne!(upsert(&copy), track_id);
+        std::fs::remove_file(&copy).expect("remove copy");
+
+        let new_dir = root.join("Artist").join("Album");
+        std::fs::create_dir_all(&new_dir).expect("create dir");
+        let new_path = new_dir.join("01 Song.flac");
+        std::fs::rename(&old_path, &new_path).expect("move file");
+        assert_eq!(upsert(&new_path), track_id);
+        assert_eq!(
+            db.track_id_for_path(&old_path.to_string_lossy()).unwrap(),
+            None
+        );
+        let tracks = db
+            .list_tracks(&crate::metadata_db::TrackFilter::default(), 10, 0)
+            .expect("tracks");
+        let moved = tracks
+            .iter()
+            .find(|t| t.id == track_id)
+            .expect("moved track");
+        assert_eq!(moved.file_name, "01 Song.flac");
+        assert_eq!(moved.mbid.as_deref(), Some("mbid-1"));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
     #[test]
     fn resolve_track_path_rejects_outside_root() {
         let root = temp_root().canonicalize().expect("canonicalize root");
@@ -778,4 +1075,29 @@ mod tests {
         assert_eq!(album.as_deref(), Some("Random Access Memories"));
         assert_eq!(disc, Some(2));
     }
+
+    #[test]
+    fn disc_folders_share_the_release_folder() {
+        let release = Path::new("/music/Box Set");
+        let disc_two = release.join("Disc 2").join("01.flac");
+        assert_eq!(
+            disc_folder(&release.join("CD1/01.flac")),
+            Some((release, 1))
+        );
+        assert_eq!(disc_folder(&disc_two), Some((release, 2)));
+        assert_eq!(album_dir(&disc_two), Some(release));
+        assert_eq!(disc_folder(Path::new("/music/Disco Hits 2/01.flac")), None);
+        assert_eq!(
+            album_dir(Path::new("/music/Album/01.flac")),
+            Some(Path::new("/music/Album"))
+        );
+
+        let meta = TrackMeta {
+            album: Some("Box Set".to_string()),
+            ..TrackMeta::default()
+        };
+        let (album, disc, _source) = normalize_album_and_disc(&disc_two, &meta);
+        assert_eq!(album.as_deref(), Some("Box Set"));
+        assert_eq!(disc, Some(2));
+    }
 }
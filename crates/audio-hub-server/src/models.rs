@@ -2,7 +2,13 @@
 //!
 //! Defines request/response structures for the hub server API.
 
-use crate::metadata_db::{AlbumSummary, ArtistSummary, TrackSummary};
+use crate::auth::TokenRole;
+use crate::lyrics::LyricLine;
+use crate::metadata_db::{
+    AlbumDisc, AlbumSummary, ApiTokenInfo, ArtistSummary, AuditEntry, ComposerSummary,
+    GenreSummary, LossyReportEntry, PlayHistoryEntry, PlaylistSummary, SearchHit, TrackSummary,
+};
+use crate::tag_writer::TagFieldChange;
 use audio_bridge_types::PlaybackStatus;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -49,6 +55,25 @@ pub struct LibraryResponse {
     pub entries: Vec<LibraryEntry>,
 }
 
+/// Progress of the running (or most recent) library scan.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct LibraryScanProgress {
+    /// True while a scan is in progress.
+    pub running: bool,
+    /// Audio files scanned so far.
+    pub files_scanned: usize,
+    /// Audio files found by the scan; zero until the folder walk finishes.
+    pub files_total: usize,
+    /// Folder most recently picked up by a scan worker.
+    pub current_dir: Option<String>,
+    /// Scan start time (unix ms).
+    pub started_at_ms: Option<i64>,
+    /// Scan end time (unix ms).
+    pub finished_at_ms: Option<i64>,
+    /// Error that stopped the last scan.
+    pub error: Option<String>,
+}
+
 /// Playback request payload for the `/play` endpoint.
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 pub struct PlayRequest {
@@ -204,6 +229,9 @@ pub struct TrackMetadataResponse {
     pub disc_number: Option<u32>,
     #[serde(default)]
     pub extra_tags: std::collections::BTreeMap<String, String>,
+    /// Fields album-wide updates leave alone on this track.
+    #[serde(default)]
+    pub locked_fields: Vec<String>,
 }
 
 /// Update request for writing tag metadata to a track file.
@@ -231,6 +259,36 @@ pub struct TrackMetadataUpdateRequest {
     pub clear_fields: Option<Vec<String>>,
     #[serde(default)]
     pub clear_extra_tags: Option<Vec<String>>,
+    /// Lock standard fields against album-wide updates.
+    #[serde(default)]
+    pub lock_fields: Option<Vec<String>>,
+    /// Remove field locks (applied after `lock_fields`).
+    #[serde(default)]
+    pub unlock_fields: Option<Vec<String>>,
+    /// Report the tag changes without writing the file or changing locks.
+    #[serde(default)]
+    pub dry_run: Option<bool>,
+}
+
+/// Tag changes planned for one track.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct TrackTagPreview {
+    /// Track id from the metadata DB.
+    pub track_id: i64,
+    /// Track path.
+    pub path: String,
+    /// Fields whose value would change.
+    pub changes: Vec<TagFieldChange>,
+    /// Locked fields the update leaves alone.
+    #[serde(default)]
+    pub skipped_locked: Vec<String>,
+}
+
+/// Dry-run result of a metadata update.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct MetadataUpdatePreviewResponse {
+    /// Planned changes per track.
+    pub tracks: Vec<TrackTagPreview>,
 }
 
 /// Supported metadata fields for a track file.
@@ -279,6 +337,15 @@ pub struct TrackAnalysisHeuristics {
     /// Estimated dynamic range in dB.
     #[serde(default)]
     pub dynamic_range_db: Option<f32>,
+    /// Highest frequency carrying meaningful energy (Hz).
+    #[serde(default)]
+    pub cutoff_hz: Option<f32>,
+    /// Level drop across the first kHz above `cutoff_hz` (dB).
+    #[serde(default)]
+    pub cutoff_drop_db: Option<f32>,
+    /// Likelihood (0..1) the track was transcoded from a lossy source.
+    #[serde(default)]
+    pub lossy_confidence: Option<f32>,
     /// Human-readable notes generated by heuristics.
     #[serde(default)]
     pub notes: Vec<String>,
@@ -312,6 +379,28 @@ pub struct AlbumMetadataResponse {
     pub album_artist: Option<String>,
     /// Release year.
     pub year: Option<i32>,
+    /// Number of discs in the release.
+    #[serde(default)]
+    pub disc_count: usize,
+    /// Per-disc sections in disc order.
+    #[serde(default)]
+    pub discs: Vec<AlbumDisc>,
+}
+
+/// Lyrics for one track.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct TrackLyricsResponse {
+    /// Track id from the metadata DB.
+    pub track_id: i64,
+    /// Where the lyrics came from (`sidecar`, `embedded` or `lrclib`).
+    pub source: String,
+    /// Whether `lines` carries timestamps.
+    pub synced: bool,
+    /// Lyrics as plain text.
+    pub plain: String,
+    /// Timed lines, empty for unsynced lyrics.
+    #[serde(default)]
+    pub lines: Vec<LyricLine>,
 }
 
 /// Update request for writing album metadata to all tracks.
@@ -327,6 +416,9 @@ pub struct AlbumMetadataUpdateRequest {
     pub year: Option<i32>,
     #[serde(default)]
     pub track_artist: Option<String>,
+    /// Report the tag changes without writing any file.
+    #[serde(default)]
+    pub dry_run: Option<bool>,
 }
 
 /// Response for album metadata updates.
@@ -334,6 +426,9 @@ pub struct AlbumMetadataUpdateRequest {
 pub struct AlbumMetadataUpdateResponse {
     /// Album id after update (may differ if merged).
     pub album_id: i64,
+    /// Tracks where locked fields were left alone.
+    #[serde(default)]
+    pub locked_tracks: usize,
 }
 
 /// Text metadata for an artist or album.
@@ -477,6 +572,17 @@ pub struct AlbumImageSetRequest {
     pub url: String,
 }
 
+/// Partial update of a track's or album's favorite flag and rating.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct RatingUpdateRequest {
+    /// Mark or unmark as favorite; omitted leaves it unchanged.
+    #[serde(default)]
+    pub favorite: Option<bool>,
+    /// Rating from 1 to 5, or 0 to clear; omitted leaves it unchanged.
+    #[serde(default)]
+    pub rating: Option<u8>,
+}
+
 /// Request to clear an album image.
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 pub struct AlbumImageClearRequest {
@@ -523,11 +629,27 @@ pub enum MusicBrainzMatchApplyRequest {
     },
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+/// Composer listing response.
+pub struct ComposerListResponse {
+    /// Composer items.
+    pub items: Vec<ComposerSummary>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+/// Genre listing response.
+pub struct GenreListResponse {
+    /// Genre items.
+    pub items: Vec<GenreSummary>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 /// Artist listing response.
 pub struct ArtistListResponse {
     /// Artist items.
     pub items: Vec<ArtistSummary>,
+    /// Pass as `cursor` to fetch the next page; `None` on the last page and for random order.
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
@@ -535,6 +657,8 @@ pub struct ArtistListResponse {
 pub struct AlbumListResponse {
     /// Album items.
     pub items: Vec<AlbumSummary>,
+    /// Pass as `cursor` to fetch the next page; `None` on the last page and for random order.
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
@@ -542,6 +666,187 @@ pub struct AlbumListResponse {
 pub struct TrackListResponse {
     /// Track items.
     pub items: Vec<TrackSummary>,
+    /// Pass as `cursor` to fetch the next page; `None` on the last page and for random order.
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+/// Library search response.
+pub struct SearchResponse {
+    /// Artists, albums and tracks, best match first.
+    pub items: Vec<SearchHit>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+/// Tracks flagged by the lossy-source check, most suspicious first.
+pub struct LossyReportResponse {
+    /// Flagged tracks.
+    pub items: Vec<LossyReportEntry>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+/// Listening history response.
+pub struct PlayHistoryResponse {
+    /// History rows, newest first.
+    pub items: Vec<PlayHistoryEntry>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+/// Audit log response.
+pub struct AuditListResponse {
+    /// Audit rows, newest first.
+    pub items: Vec<AuditEntry>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+/// Playlist listing response.
+pub struct PlaylistListResponse {
+    /// Playlist items.
+    pub items: Vec<PlaylistSummary>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+/// Playlist detail response.
+pub struct PlaylistDetailResponse {
+    /// Playlist summary.
+    pub playlist: PlaylistSummary,
+    /// Entries in playlist order.
+    pub tracks: Vec<TrackSummary>,
+}
+
+/// Payload to create a playlist or replace an existing one.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct PlaylistWriteRequest {
+    /// Playlist name.
+    pub name: String,
+    /// Track ids in playlist order; duplicates are allowed.
+    #[serde(default)]
+    pub track_ids: Vec<i64>,
+    /// Track whose cover represents the playlist; defaults to the first entry with art.
+    #[serde(default)]
+    pub cover_track_id: Option<i64>,
+}
+
+/// API token listing response.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct ApiTokenListResponse {
+    /// True when requests must present a token.
+    pub enabled: bool,
+    /// Tokens created through `/auth/tokens`.
+    pub items: Vec<ApiTokenInfo>,
+    /// Tokens defined in the `[auth]` config section.
+    pub config_tokens: Vec<ConfigTokenEntry>,
+}
+
+/// Token defined in config (its secret is never returned).
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct ConfigTokenEntry {
+    /// Token name.
+    pub name: String,
+    /// Permission level.
+    pub role: TokenRole,
+}
+
+/// Payload to create an API token.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ApiTokenCreateRequest {
+    /// Label for the token (e.g. the device using it).
+    pub name: String,
+    /// Permission level (default: `admin`).
+    #[serde(default)]
+    pub role: TokenRole,
+}
+
+/// Newly created API token.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct ApiTokenCreateResponse {
+    /// Token row.
+    pub token: ApiTokenInfo,
+    /// Secret to send as `Authorization: Bearer <secret>`; it cannot be retrieved again.
+    pub secret: String,
+}
+
+/// Home Assistant discovery info: one media player per output.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct HaDiscoveryResponse {
+    /// Hub name shown as the device manufacturer/model.
+    pub name: String,
+    /// Hub version.
+    pub version: String,
+    /// Outputs exposed as media players.
+    pub players: Vec<HaPlayerInfo>,
+}
+
+/// Output exposed as a Home Assistant media player.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct HaPlayerInfo {
+    /// Output id, stable across restarts (use as the entity unique id).
+    pub unique_id: String,
+    /// Display name.
+    pub name: String,
+    /// Output kind (bridge/local/cast).
+    pub kind: String,
+    /// Room label, for the HA area.
+    pub room: Option<String>,
+    /// Whether the output is reachable.
+    pub available: bool,
+    /// Supported features, named after `MediaPlayerEntityFeature` (`play`, `volume_set`, ...).
+    pub supported_features: Vec<String>,
+}
+
+/// Media player state shaped like Home Assistant's `media_player` attributes.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct HaPlayerState {
+    /// Output id.
+    pub unique_id: String,
+    /// `off` (unreachable), `idle`, `playing` or `paused`.
+    pub state: String,
+    /// Session controlling the output, if any.
+    pub session_id: Option<String>,
+    /// Volume 0.0-1.0 when the output supports volume.
+    pub volume_level: Option<f32>,
+    /// Mute state when the output supports volume.
+    pub is_volume_muted: Option<bool>,
+    /// Current track id.
+    pub media_content_id: Option<String>,
+    /// Always `music` while a track is loaded.
+    pub media_content_type: Option<String>,
+    pub media_title: Option<String>,
+    pub media_artist: Option<String>,
+    pub media_album_name: Option<String>,
+    /// Track length in seconds.
+    pub media_duration: Option<f64>,
+    /// Playback position in seconds.
+    pub media_position: Option<f64>,
+    /// Absolute cover art URL.
+    pub media_image_url: Option<String>,
+}
+
+/// Home Assistant media player service call.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HaCommand {
+    MediaPlay,
+    MediaPause,
+    MediaPlayPause,
+    MediaStop,
+    MediaNextTrack,
+    MediaPreviousTrack,
+    VolumeSet,
+    VolumeMute,
+    VolumeUp,
+    VolumeDown,
+}
+
+/// Command request for a Home Assistant media player.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct HaCommandRequest {
+    /// Service to run.
+    pub command: HaCommand,
+    /// Volume 0.0-1.0 for `volume_set`.
+    pub volume_level: Option<f32>,
+    /// Mute state for `volume_mute`.
+    pub is_volume_muted: Option<bool>,
 }
 
 /// Payload to add items to the queue.
@@ -551,6 +856,16 @@ pub struct QueueAddRequest {
     pub track_ids: Vec<i64>,
 }
 
+/// Payload to enqueue every entry of a playlist.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct QueuePlaylistRequest {
+    /// Playlist id.
+    pub playlist_id: i64,
+    /// Insert at the front of the queue instead of appending.
+    #[serde(default)]
+    pub next: bool,
+}
+
 /// Payload to remove a single item from the queue.
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 pub struct QueueRemoveRequest {
@@ -607,8 +922,46 @@ pub struct OutputInfo {
     pub provider_name: Option<String>,
     /// Supported sample rates if known.
     pub supported_rates: Option<SupportedRates>,
+    /// Native rates/formats/channels if the output reports them.
+    #[serde(default)]
+    pub formats: Option<OutputFormats>,
     /// Capabilities advertised by the output.
     pub capabilities: OutputCapabilities,
+    /// Room label of the output's bridge, if set.
+    #[serde(default)]
+    pub room: Option<String>,
+    /// Zone label of the output's bridge, if set.
+    #[serde(default)]
+    pub zone: Option<String>,
+}
+
+/// Native playback formats reported for an output device.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct OutputFormats {
+    /// Standard sample rates accepted without resampling (Hz), ascending.
+    pub sample_rates: Vec<u32>,
+    /// Supported sample formats (`F32`, `I32`, `I16`, ...).
+    pub sample_formats: Vec<String>,
+    /// Supported channel counts, ascending.
+    pub channels: Vec<u16>,
+}
+
+impl OutputFormats {
+    /// Build from reported lists, returning `None` when nothing was reported.
+    pub fn from_lists(
+        sample_rates: Vec<u32>,
+        sample_formats: Vec<String>,
+        channels: Vec<u16>,
+    ) -> Option<Self> {
+        if sample_rates.is_empty() && sample_formats.is_empty() && channels.is_empty() {
+            return None;
+        }
+        Some(Self {
+            sample_rates,
+            sample_formats,
+            channels,
+        })
+    }
 }
 
 /// Minimum/maximum sample rate range for a device.
@@ -654,6 +1007,86 @@ pub struct BridgeUnregisterResponse {
     pub cleared_active_output: bool,
 }
 
+/// Effective bridge health-check and discovery timing (`[discovery]` config).
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct DiscoverySettings {
+    /// Delay between health checks of one discovered bridge.
+    pub health_interval_ms: u64,
+    /// Timeout of one health check.
+    pub health_timeout_ms: u64,
+    /// Discovered bridges unseen for this long are dropped.
+    pub stale_after_ms: u64,
+    /// Random spread of check and reconnect delays, in percent.
+    pub jitter_percent: u8,
+}
+
+/// Last health check of a discovered bridge.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct BridgeProbeResult {
+    /// Whether the bridge answered.
+    pub ok: bool,
+    /// Check time (unix epoch milliseconds).
+    pub checked_at_ms: u64,
+    /// Time until the answer or failure, in milliseconds.
+    pub latency_ms: u64,
+    /// Failure reason.
+    pub error: Option<String>,
+}
+
+/// Bridge known to the hub.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct BridgeSummary {
+    /// Bridge id.
+    pub id: String,
+    /// Display name.
+    pub name: String,
+    /// Bridge HTTP address.
+    pub http_addr: String,
+    /// True when found via mDNS rather than config.
+    pub discovered: bool,
+    /// Room label (config, else advertised by the bridge).
+    pub room: Option<String>,
+    /// Zone label (config, else advertised by the bridge).
+    pub zone: Option<String>,
+    /// Device stream state: `pending`, `online` or `offline`.
+    pub state: String,
+    /// Last health check (discovered bridges only).
+    pub last_probe: Option<BridgeProbeResult>,
+}
+
+/// Response payload for bridge listings.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct BridgesResponse {
+    /// Timing in effect.
+    pub discovery: DiscoverySettings,
+    /// Configured and discovered bridges.
+    pub bridges: Vec<BridgeSummary>,
+}
+
+/// Log line captured by a bridge's in-memory log ring.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct BridgeLogEntry {
+    /// Monotonic sequence number assigned by the bridge.
+    pub seq: u64,
+    /// Log level string.
+    pub level: String,
+    /// Tracing target/module.
+    pub target: String,
+    /// Formatted message + selected fields.
+    pub message: String,
+    /// Event timestamp (unix millis).
+    pub timestamp_ms: i64,
+}
+
+/// Bridge log snapshot proxied by the hub.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct BridgeLogsResponse {
+    /// Buffered log entries matching the requested filter.
+    pub logs: Vec<BridgeLogEntry>,
+    /// Newest sequence number on the bridge (use as `after` for polling).
+    pub last_seq: u64,
+}
+
 /// Request payload for starting or refreshing a local playback session.
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 pub struct LocalPlaybackRegisterRequest {
@@ -939,6 +1372,33 @@ pub struct OutputSettings {
     /// Output ids that should use exclusive mode (bridge-only).
     #[serde(default)]
     pub exclusive: Vec<String>,
+    /// Output id -> silence (ms) played when the output's stream opens (bridge-only).
+    #[serde(default)]
+    pub preroll_silence_ms: HashMap<String, u32>,
+    /// Output id -> gain offset (dB) applied on top of the session volume.
+    #[serde(default)]
+    pub volume_trim_db: HashMap<String, f32>,
+}
+
+/// Request payload to hide or unhide one output.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct OutputHideRequest {
+    /// `true` hides the output (default), `false` shows it again.
+    #[serde(default = "default_output_hidden")]
+    pub hidden: bool,
+}
+
+impl Default for OutputHideRequest {
+    fn default() -> Self {
+        Self {
+            hidden: default_output_hidden(),
+        }
+    }
+}
+
+/// Default `hidden` value for output hide requests.
+fn default_output_hidden() -> bool {
+    true
 }
 
 /// Provider outputs bundled with provider info.
@@ -1033,6 +1493,13 @@ mod tests {
                 min_hz: 44_100,
                 max_hz: 192_000,
             }),
+            formats: OutputFormats::from_lists(
+                vec![44_100, 96_000],
+                vec!["I32".to_string()],
+                vec![2],
+            ),
+            room: None,
+            zone: None,
             capabilities: OutputCapabilities {
                 device_select: true,
                 volume: false,
@@ -1042,5 +1509,7 @@ mod tests {
         let de: OutputInfo = serde_json::from_str(&json).unwrap();
         assert_eq!(de.id, "bridge:one:device");
         assert_eq!(de.supported_rates.unwrap().max_hz, 192_000);
+        assert_eq!(de.formats.unwrap().sample_formats, vec!["I32"]);
+        assert!(OutputFormats::from_lists(Vec::new(), Vec::new(), Vec::new()).is_none());
     }
 }
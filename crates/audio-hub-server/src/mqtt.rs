@@ -0,0 +1,401 @@
+//! MQTT bridge for home-automation systems, from `[mqtt]` config.
+//!
+//! Every output held by a session publishes a retained JSON state to
+//! `<prefix>/outputs/<output>/state`. Plain-text commands on `<prefix>/outputs/<output>/command`
+//! (`play`, `pause`, `toggle`, `stop`, `next`, `previous`) and a 0-100 value on
+//! `<prefix>/outputs/<output>/volume/set` are applied to the session holding that output.
+//! `<prefix>/status` is `online` while connected (`offline` is the broker's last will).
+//! `/`, `+` and `#` in output ids are replaced with `_` in topics.
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use actix_web::web;
+use anyhow::Result;
+use rumqttc::{AsyncClient, Event, LastWill, MqttOptions, Packet, QoS};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::audit;
+use crate::config::{MqttConfig, ServerConfig};
+use crate::events::HubEvent;
+use crate::metadata_db::AuditRecord;
+use crate::output_remote::{self, OutputSnapshot, RemoteCommand as Command, RemoteError};
+use crate::state::AppState;
+
+/// Default broker port.
+const DEFAULT_PORT: u16 = 1883;
+/// Default topic prefix.
+const DEFAULT_TOPIC_PREFIX: &str = "audio-hub";
+/// State is republished at least this often so positions stay fresh.
+const STATE_INTERVAL: Duration = Duration::from_secs(10);
+/// Wait before reconnecting after a broker error.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// Requests buffered between the client and its event loop.
+const CLIENT_CAPACITY: usize = 64;
+
+/// Resolved `[mqtt]` settings.
+#[derive(Debug, Clone)]
+pub struct MqttSettings {
+    host: String,
+    port: u16,
+    client_id: String,
+    credentials: Option<(String, String)>,
+    topic_prefix: String,
+}
+
+/// Resolve `[mqtt]` from config (`None` when the section is absent).
+pub fn from_config(cfg: &ServerConfig) -> Result<Option<MqttSettings>> {
+    cfg.mqtt
+        .as_ref()
+        .map(|section| {
+            settings_from_section(section).map_err(|errors| anyhow::anyhow!(errors.join("; ")))
+        })
+        .transpose()
+}
+
+/// Resolve an `[mqtt]` section, listing every invalid field.
+pub(crate) fn settings_from_section(
+    section: &MqttConfig,
+) -> std::result::Result<MqttSettings, Vec<String>> {
+    let mut errors = Vec::new();
+    let broker = section.broker.trim();
+    let broker = broker.strip_prefix("mqtt://").unwrap_or(broker);
+    let (host, port) = match broker.rsplit_once(':') {
+        Some((host, port)) => match port.parse::<u16>() {
+            Ok(port) if port > 0 => (host, port),
+            _ => {
+                errors.push(format!("mqtt.broker: invalid port in `{}`", section.broker));
+                (host, DEFAULT_PORT)
+            }
+        },
+        None => (broker, DEFAULT_PORT),
+    };
+    if host.is_empty() || host.contains('/') {
+        errors.push(format!(
+            "mqtt.broker: expected host[:port] (got `{}`)",
+            section.broker
+        ));
+    }
+    let topic_prefix = section
+        .topic_prefix
+        .as_deref()
+        .unwrap_or(DEFAULT_TOPIC_PREFIX)
+        .trim_matches('/')
+        .to_string();
+    if topic_prefix.is_empty() || topic_prefix.contains(['+', '#']) {
+        errors.push("mqtt.topic_prefix: must be non-empty without `+` or `#`".to_string());
+    }
+    let credentials = match (section.username.as_ref(), section.password.as_ref()) {
+        (Some(username), password) => {
+            Some((username.clone(), password.cloned().unwrap_or_default()))
+        }
+        (None, Some(_)) => {
+            errors.push("mqtt.password: set without mqtt.username".to_string());
+            None
+        }
+        (None, None) => None,
+    };
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(MqttSettings {
+        host: host.to_string(),
+        port,
+        client_id: section
+            .client_id
+            .clone()
+            .unwrap_or_else(|| DEFAULT_TOPIC_PREFIX.to_string()),
+        credentials,
+        topic_prefix,
+    })
+}
+
+/// Topic segment used for `output_id`.
+fn topic_id(output_id: &str) -> String {
+    output_id.replace(['/', '+', '#'], "_")
+}
+
+/// Parse a command publish into the output topic id and command.
+fn parse_command<'a>(prefix: &str, topic: &'a str, payload: &str) -> Option<(&'a str, Command)> {
+    let rest = topic.strip_prefix(prefix)?.strip_prefix("/outputs/")?;
+    let payload = payload.trim();
+    if let Some(output) = rest.strip_suffix("/volume/set") {
+        let value = payload.parse::<f32>().ok()?;
+        return Some((
+            output,
+            Command::Volume(value.round().clamp(0.0, 100.0) as u8),
+        ));
+    }
+    let output = rest.strip_suffix("/command")?;
+    let command = match payload.to_ascii_lowercase().as_str() {
+        "play" => Command::Play,
+        "pause" => Command::Pause,
+        "toggle" | "play_pause" => Command::Toggle,
+        "stop" => Command::Stop,
+        "next" => Command::Next,
+        "previous" | "prev" => Command::Previous,
+        _ => return None,
+    };
+    Some((output, command))
+}
+
+/// Connect to the broker and keep state and commands flowing in the background.
+pub fn spawn_mqtt(settings: MqttSettings, state: web::Data<AppState>) {
+    let status_topic = format!("{}/status", settings.topic_prefix);
+    let mut options = MqttOptions::new(&settings.client_id, &settings.host, settings.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_last_will(LastWill::new(
+        &status_topic,
+        "offline",
+        QoS::AtLeastOnce,
+        true,
+    ));
+    if let Some((username, password)) = settings.credentials.as_ref() {
+        options.set_credentials(username, password);
+    }
+    let (client, mut eventloop) = AsyncClient::new(options, CLIENT_CAPACITY);
+    tracing::info!(
+        broker = %format!("{}:{}", settings.host, settings.port),
+        prefix = %settings.topic_prefix,
+        "mqtt enabled"
+    );
+
+    let prefix = settings.topic_prefix.clone();
+    let command_client = client.clone();
+    let command_state = state.clone();
+    actix_web::rt::spawn(async move {
+        let subscriptions = [
+            format!("{prefix}/outputs/+/command"),
+            format!("{prefix}/outputs/+/volume/set"),
+        ];
+        loop {
+            match eventloop.poll().await {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    tracing::info!("mqtt connected");
+                    for topic in &subscriptions {
+                        let _ = command_client.try_subscribe(topic, QoS::AtLeastOnce);
+                    }
+                    let _ =
+                        command_client.try_publish(&status_topic, QoS::AtLeastOnce, true, "online");
+                    command_state.events.status_changed();
+                }
+                Ok(Event::Incoming(Packet::Publish(publish))) => {
+                    let payload = String::from_utf8_lossy(&publish.payload);
+                    let Some((output, command)) = parse_command(&prefix, &publish.topic, &payload)
+                    else {
+                        tracing::debug!(topic = %publish.topic, "mqtt: ignored message");
+                        continue;
+                    };
+                    let output = output.to_string();
+                    let topic = publish.topic.clone();
+                    let state = command_state.clone();
+                    actix_web::rt::spawn(async move {
+                        apply_command(&state, &topic, &output, command).await;
+                    });
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    tracing::warn!(error = %err, "mqtt connection error");
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                }
+            }
+        }
+    });
+
+    actix_web::rt::spawn(publish_states(client, settings.topic_prefix, state));
+}
+
+/// Output id whose topic id is `output`, among outputs held by a session.
+fn held_output_id(output: &str) -> Option<String> {
+    let (output_locks, _) = crate::session_registry::lock_snapshot();
+    output_locks
+        .into_iter()
+        .map(|(output_id, _)| output_id)
+        .find(|output_id| topic_id(output_id) == output)
+}
+
+/// Apply `command` (received on `topic`) to the session holding `output`, logging failures.
+async fn apply_command(state: &web::Data<AppState>, topic: &str, output: &str, command: Command) {
+    let Some(output_id) = held_output_id(output) else {
+        tracing::warn!(output = %output, ?command, "mqtt command ignored: no session holds the output");
+        return;
+    };
+    match output_remote::apply(state, &output_id, command).await {
+        Ok(session_id) => audit::record(AuditRecord {
+            at_ms: crate::clock::now_ms(),
+            actor: Some("mqtt".to_string()),
+            session_id: Some(session_id),
+            output_id: Some(output_id),
+            action: command.action().to_string(),
+            method: "MQTT".to_string(),
+            path: topic.to_string(),
+            status: 200,
+            ..Default::default()
+        }),
+        Err(RemoteError::NothingToPlay) => {}
+        Err(err) => {
+            tracing::warn!(output_id = %output_id, ?command, error = ?err, "mqtt command failed");
+        }
+    }
+}
+
+/// Publish retained output states on hub events and every [`STATE_INTERVAL`].
+async fn publish_states(client: AsyncClient, prefix: String, state: web::Data<AppState>) {
+    let mut receiver = state.events.subscribe();
+    let mut published: HashMap<String, String> = HashMap::new();
+    loop {
+        let (output_locks, _) = crate::session_registry::lock_snapshot();
+        let held: HashSet<String> = output_locks.iter().map(|(id, _)| id.clone()).collect();
+        let mut current = HashMap::new();
+        for (output_id, _) in output_locks {
+            let snapshot = output_remote::snapshot(&state, &output_id).await;
+            let payload = state_payload(&output_id, &snapshot);
+            current.insert(output_id, payload.to_string());
+        }
+        for output_id in published.keys() {
+            if !current.contains_key(output_id) {
+                let payload = state_payload(output_id, &OutputSnapshot::default());
+                current.insert(output_id.clone(), payload.to_string());
+            }
+        }
+        for (output_id, payload) in &current {
+            if published.get(output_id) != Some(payload) {
+                let topic = format!("{prefix}/outputs/{}/state", topic_id(output_id));
+                if let Err(err) = client
+                    .publish(topic, QoS::AtLeastOnce, true, payload.clone())
+                    .await
+                {
+                    tracing::warn!(error = %err, "mqtt publish failed");
+                }
+            }
+        }
+        // Released outputs were published as idle above; stop tracking them.
+        current.retain(|output_id, _| held.contains(output_id));
+        published = current;
+
+        let wait = tokio::time::timeout(STATE_INTERVAL, async {
+            loop {
+                match receiver.recv().await {
+                    Ok(
+                        HubEvent::StatusChanged | HubEvent::OutputsChanged | HubEvent::QueueChanged,
+                    ) => {
+                        return true;
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => return false,
+                }
+            }
+        })
+        .await;
+        if wait == Ok(false) {
+            break;
+        }
+    }
+}
+
+/// Retained state JSON for one output.
+fn state_payload(output_id: &str, snapshot: &OutputSnapshot) -> serde_json::Value {
+    let status = snapshot.status.as_ref();
+    let volume = snapshot.volume();
+    serde_json::json!({
+        "output_id": output_id,
+        "session_id": snapshot.session_id,
+        "state": snapshot.playback_state(),
+        "track_id": status.and_then(|s| s.now_playing_track_id),
+        "title": status.and_then(|s| s.title.as_deref()),
+        "artist": status.and_then(|s| s.artist.as_deref()),
+        "album": status.and_then(|s| s.album.as_deref()),
+        "elapsed_ms": status.and_then(|s| s.elapsed_ms),
+        "duration_ms": status.and_then(|s| s.duration_ms),
+        "volume": volume.map(|v| v.value),
+        "muted": volume.map(|v| v.muted),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_command_reads_command_and_volume_topics() {
+        assert_eq!(
+            parse_command("hub", "hub/outputs/bridge:den:USB DAC/command", " Next\n"),
+            Some(("bridge:den:USB DAC", Command::Next))
+        );
+        assert_eq!(
+            parse_command("hub", "hub/outputs/local:default/volume/set", "42.6"),
+            Some(("local:default", Command::Volume(43)))
+        );
+        assert_eq!(
+            parse_command("hub", "hub/outputs/local:default/volume/set", "250"),
+            Some(("local:default", Command::Volume(100)))
+        );
+        assert_eq!(
+            parse_command("hub", "hub/outputs/x/command", "rewind"),
+            None
+        );
+        assert_eq!(
+            parse_command("hub", "other/outputs/x/command", "play"),
+            None
+        );
+        assert_eq!(topic_id("bridge:a/b:c+#"), "bridge:a_b:c__");
+    }
+
+    #[test]
+    fn settings_from_section_parses_broker_and_reports_errors() {
+        let section: MqttConfig = toml::from_str(
+            r#"
+            broker = "mqtt://192.168.1.5"
+            topic_prefix = "home/audio/"
+            username = "hub"
+            password = "secret"
+            "#,
+        )
+        .unwrap();
+        let settings = settings_from_section(&section).unwrap();
+        assert_eq!(settings.host, "192.168.1.5");
+        assert_eq!(settings.port, DEFAULT_PORT);
+        assert_eq!(settings.topic_prefix, "home/audio");
+        assert_eq!(settings.client_id, "audio-hub");
+
+        let section: MqttConfig = toml::from_str(
+            r#"
+            broker = "broker.lan:0"
+            topic_prefix = "audio/#"
+            password = "secret"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            settings_from_section(&section).unwrap_err(),
+            vec![
+                "mqtt.broker: invalid port in `broker.lan:0`",
+                "mqtt.topic_prefix: must be non-empty without `+` or `#`",
+                "mqtt.password: set without mqtt.username",
+            ]
+        );
+    }
+
+    #[test]
+    fn state_payload_reports_playback_state() {
+        let snapshot = OutputSnapshot {
+            session_id: Some("s1".to_string()),
+            status: Some(crate::models::StatusResponse {
+                now_playing_track_id: Some(3),
+                paused: true,
+                title: Some("Song".to_string()),
+                ..Default::default()
+            }),
+            volume: None,
+        };
+        let body = state_payload("local:default", &snapshot);
+        assert_eq!(body["state"], "paused");
+        assert_eq!(body["session_id"], "s1");
+        assert_eq!(body["title"], "Song");
+        assert!(body["volume"].is_null());
+        assert_eq!(
+            state_payload("local:default", &OutputSnapshot::default())["state"],
+            "idle"
+        );
+    }
+}
@@ -6,9 +6,10 @@ use std::time::{Duration, Instant};
 use anyhow::{Context, Result, bail};
 use serde::Deserialize;
 
+use crate::acoustid::{AcoustIdClient, Fingerprint};
 use crate::config::MusicBrainzConfig;
 use crate::events::{EventBus, MetadataEvent};
-use crate::metadata_db::{MetadataDb, MusicBrainzCandidate, TrackRecord};
+use crate::metadata_db::{AcoustIdCandidate, MetadataDb, MusicBrainzCandidate, TrackRecord};
 use crate::state::MetadataWake;
 
 const DEFAULT_BASE_URL: &str = "https://musicbrainz.org/ws/2";
@@ -436,9 +437,12 @@ pub enum MusicBrainzLookup {
 }
 
 /// Spawn background metadata enrichment loop driven by wake notifications.
+///
+/// With an AcoustID client, tracks lacking title or artist tags are matched by fingerprint.
 pub fn spawn_enrichment_loop(
     db: MetadataDb,
     client: std::sync::Arc<MusicBrainzClient>,
+    acoustid: Option<AcoustIdClient>,
     events: EventBus,
     wake: MetadataWake,
 ) {
@@ -453,7 +457,14 @@ pub fn spawn_enrichment_loop(
                             count: candidates.len(),
                         });
                     }
-                    if candidates.is_empty() {
+                    let fingerprint_candidates = match acoustid.as_ref() {
+                        Some(_) => db.list_acoustid_candidates(20).unwrap_or_else(|err| {
+                            tracing::warn!(error = %err, "acoustid candidate query failed");
+                            Vec::new()
+                        }),
+                        None => Vec::new(),
+                    };
+                    if candidates.is_empty() && fingerprint_candidates.is_empty() {
                         wake.wait(&mut wake_seq);
                         continue;
                     }
@@ -471,6 +482,24 @@ pub fn spawn_enrichment_loop(
                             }
                         }
                     }
+                    if let Some(acoustid) = acoustid.as_ref() {
+                        for candidate in fingerprint_candidates {
+                            match match_fingerprint(&db, acoustid, &events, &candidate) {
+                                Ok(()) => attempted += 1,
+                                Err(err) => {
+                                    tracing::warn!(
+                                        error = %err,
+                                        path = %candidate.path,
+                                        "acoustid lookup failed"
+                                    );
+                                    events.metadata_event(MetadataEvent::AcoustIdLookupFailure {
+                                        track_id: candidate.track_id,
+                                        error: err.to_string(),
+                                    });
+                                }
+                            }
+                        }
+                    }
                     if attempted == 0 {
                         wake.wait(&mut wake_seq);
                     }
@@ -516,7 +545,14 @@ fn enrich_candidate(
         album_uuid: None,
         track_number: None,
         disc_number: None,
+        disc_title: None,
         year: None,
+        genres: Vec::new(),
+        composer: None,
+        conductor: None,
+        work: None,
+        movement: None,
+        movement_number: None,
         duration_ms: None,
         sample_rate: None,
         bit_depth: None,
@@ -567,6 +603,60 @@ fn enrich_candidate(
     Ok(true)
 }
 
+/// Fingerprint one untagged track (reusing a stored fingerprint) and record the AcoustID match.
+///
+/// Errors leave the track unchecked so it is retried on the next pass.
+fn match_fingerprint(
+    db: &MetadataDb,
+    client: &AcoustIdClient,
+    events: &EventBus,
+    candidate: &AcoustIdCandidate,
+) -> Result<()> {
+    let checked_at_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    let path = std::path::Path::new(&candidate.path);
+    // A cue track is a slice of a larger file, so fingerprinting the file would not match it.
+    if crate::cue_tracks::split_track_path(path).is_some() {
+        return db.apply_acoustid_result(candidate.track_id, None, checked_at_ms);
+    }
+    let fingerprint = match (candidate.fingerprint.clone(), candidate.duration_s) {
+        (Some(fingerprint), Some(duration_s)) => Fingerprint {
+            fingerprint,
+            duration_s,
+        },
+        _ => match client.fingerprint(path)? {
+            Some(fingerprint) => {
+                db.set_acoustid_fingerprint(
+                    candidate.track_id,
+                    &fingerprint.fingerprint,
+                    fingerprint.duration_s,
+                )?;
+                fingerprint
+            }
+            None => return db.apply_acoustid_result(candidate.track_id, None, checked_at_ms),
+        },
+    };
+    match client.lookup(&fingerprint)? {
+        Some((found, score)) => {
+            db.apply_acoustid_result(candidate.track_id, Some(&found), checked_at_ms)?;
+            events.metadata_event(MetadataEvent::AcoustIdLookupSuccess {
+                track_id: candidate.track_id,
+                recording_mbid: found.recording_mbid,
+                score,
+            });
+        }
+        None => {
+            db.apply_acoustid_result(candidate.track_id, None, checked_at_ms)?;
+            events.metadata_event(MetadataEvent::AcoustIdLookupNoMatch {
+                track_id: candidate.track_id,
+            });
+        }
+    }
+    Ok(())
+}
+
 /// Stable key used to suppress repeated failed lookups for same inputs.
 fn no_match_key(title: &str, artist: &str, album: Option<&str>) -> String {
     let mut key = String::new();
@@ -866,6 +956,9 @@ mod tests {
             user_agent: Some("audio-hub-tests/0.1 (local testing)".to_string()),
             base_url: None,
             rate_limit_ms: Some(1000),
+            fanart_api_key: None,
+            acoustid_api_key: None,
+            fpcalc_path: None,
         };
         let client = MusicBrainzClient::new(&cfg)
             .expect("client init")
@@ -896,6 +989,9 @@ mod tests {
             user_agent: Some("audio-hub-tests/0.1 (local testing)".to_string()),
             base_url: None,
             rate_limit_ms: Some(1000),
+            fanart_api_key: None,
+            acoustid_api_key: None,
+            fpcalc_path: None,
         };
         let client = MusicBrainzClient::new(&cfg)
             .expect("client init")
@@ -16,20 +16,46 @@ use crate::models;
         api::library::rescan_track,
         api::library::stream_track_id,
         api::library::transcode_track_id,
+        api::library::hls_playlist,
+        api::library::hls_segment,
         api::metadata::artists_list,
+        api::metadata::genres_list,
+        api::metadata::composers_list,
         api::metadata::albums_list,
         api::metadata::tracks_list,
+        api::metadata::library_search,
         api::metadata::tracks_resolve,
         api::metadata::tracks_metadata,
         api::metadata::tracks_metadata_fields,
         api::metadata::tracks_metadata_update,
         api::metadata::tracks_analysis,
+        api::metadata::tracks_lossy_report,
+        api::metadata::albums_recent,
+        api::metadata::tracks_recent,
+        api::metadata::track_rating_set,
+        api::metadata::album_rating_set,
+        api::history::history_list,
+        api::audit::audit_list,
+        api::export::metadata_export,
+        api::export::metadata_import,
+        api::auth::auth_tokens_list,
+        api::auth::auth_tokens_create,
+        api::auth::auth_tokens_delete,
+        api::homeassistant::homeassistant_discovery,
+        api::homeassistant::homeassistant_player_state,
+        api::homeassistant::homeassistant_player_command,
+        api::playlists::playlists_list,
+        api::playlists::playlists_create,
+        api::playlists::playlists_get,
+        api::playlists::playlists_update,
+        api::playlists::playlists_delete,
         api::metadata::albums_metadata,
         api::metadata::albums_metadata_update,
         api::metadata::artist_profile,
         api::metadata::artist_profile_update,
         api::metadata::album_profile,
         api::metadata::album_profile_update,
+        api::metadata::artist_image,
         api::metadata::artist_image_set,
         api::metadata::artist_image_clear,
         api::metadata::album_image_set,
@@ -38,8 +64,12 @@ use crate::models;
         api::metadata::musicbrainz_match_search,
         api::metadata::musicbrainz_match_apply,
         api::metadata::track_cover,
+        api::metadata::track_lyrics,
         api::metadata::album_cover,
+        api::metadata::album_cover_upload,
         api::logs::logs_clear,
+        api::logs::log_level_get,
+        api::logs::log_level_set,
         api::local_playback::local_playback_register,
         api::local_playback::local_playback_play,
         api::local_playback::local_playback_sessions,
@@ -58,10 +88,12 @@ use crate::models;
         api::sessions::sessions_status_stream,
         api::sessions::sessions_pause,
         api::sessions::sessions_seek,
+        api::sessions::sessions_chapter,
         api::sessions::sessions_stop,
         api::sessions::sessions_queue_list,
         api::sessions::sessions_queue_add,
         api::sessions::sessions_queue_add_next,
+        api::sessions::sessions_queue_playlist,
         api::sessions::sessions_queue_remove,
         api::sessions::sessions_queue_play_from,
         api::sessions::sessions_queue_clear,
@@ -72,28 +104,41 @@ use crate::models;
         api::outputs::providers_list,
         api::outputs::provider_outputs_list,
         api::outputs::provider_refresh,
+        api::outputs::provider_logs,
         api::outputs::bridge_unregister,
+        api::outputs::bridges_list,
+        api::health::bridge_health,
         api::outputs::outputs_list,
         api::streams::outputs_stream,
         api::streams::metadata_stream,
         api::streams::albums_stream,
         api::streams::logs_stream,
+        api::streams::library_scan_progress_stream,
         api::outputs::outputs_select,
         api::outputs::outputs_settings,
         api::outputs::outputs_settings_update,
+        api::outputs::outputs_hide,
+        api::outputs::outputs_preferences,
+        api::outputs::outputs_preferences_update,
     ),
     components(
         schemas(
             models::LibraryEntry,
             models::LibraryResponse,
+            models::LibraryScanProgress,
             models::PlayRequest,
             models::PlayAlbumRequest,
             models::QueueMode,
             models::AlbumQueueMode,
             audio_bridge_types::PlaybackStatus,
+            audio_bridge_types::ChannelLevel,
+            audio_bridge_types::Chapter,
+            audio_bridge_types::ChapterRequest,
+            audio_bridge_types::ChapterStep,
             models::QueueItem,
             models::QueueResponse,
             models::QueueAddRequest,
+            models::QueuePlaylistRequest,
             models::QueueRemoveRequest,
             models::QueuePlayFromRequest,
             models::LocalPlaybackRegisterRequest,
@@ -124,17 +169,44 @@ use crate::models;
             models::OutputInfo,
             models::OutputCapabilities,
             models::SupportedRates,
+            models::OutputFormats,
             models::OutputSelectRequest,
             models::BridgeUnregisterRequest,
             models::BridgeUnregisterResponse,
+            models::BridgesResponse,
+            models::BridgeSummary,
+            models::BridgeProbeResult,
+            models::DiscoverySettings,
+            models::BridgeLogEntry,
+            models::BridgeLogsResponse,
             models::OutputSettings,
+            models::OutputHideRequest,
             models::OutputSettingsResponse,
             models::ProviderOutputs,
             models::ProviderInfo,
             models::ProvidersResponse,
             models::ArtistListResponse,
+            models::GenreListResponse,
+            models::ComposerListResponse,
             models::AlbumListResponse,
             models::TrackListResponse,
+            models::PlayHistoryResponse,
+            models::AuditListResponse,
+            models::PlaylistListResponse,
+            models::PlaylistDetailResponse,
+            models::PlaylistWriteRequest,
+            models::ApiTokenListResponse,
+            models::ApiTokenCreateRequest,
+            models::ApiTokenCreateResponse,
+            models::ConfigTokenEntry,
+            models::HaDiscoveryResponse,
+            models::HaPlayerInfo,
+            models::HaPlayerState,
+            models::HaCommand,
+            models::HaCommandRequest,
+            crate::auth::TokenRole,
+            models::RatingUpdateRequest,
+            models::SearchResponse,
             models::TrackResolveResponse,
             models::TrackMetadataResponse,
             models::TrackMetadataFieldsResponse,
@@ -142,9 +214,15 @@ use crate::models;
             models::TrackAnalysisRequest,
             models::TrackAnalysisResponse,
             models::TrackAnalysisHeuristics,
+            models::LossyReportResponse,
             models::AlbumMetadataResponse,
+            models::TrackLyricsResponse,
+            crate::lyrics::LyricLine,
             models::AlbumMetadataUpdateRequest,
             models::AlbumMetadataUpdateResponse,
+            models::MetadataUpdatePreviewResponse,
+            models::TrackTagPreview,
+            crate::tag_writer::TagFieldChange,
             models::TextMetadata,
             models::MediaAssetInfo,
             models::ArtistProfileResponse,
@@ -161,12 +239,39 @@ use crate::models;
             models::MusicBrainzMatchApplyRequest,
             models::MusicBrainzMatchKind,
             crate::metadata_db::ArtistSummary,
+            crate::metadata_db::GenreSummary,
+            crate::metadata_db::ComposerSummary,
+            crate::metadata_db::AlbumDisc,
+            crate::metadata_db::AlbumSort,
+            crate::metadata_db::TrackSort,
+            crate::metadata_db::SortDirection,
             crate::metadata_db::AlbumSummary,
             crate::metadata_db::TrackSummary,
+            crate::metadata_db::PlayHistoryEntry,
+            crate::metadata_db::AuditEntry,
+            crate::metadata_db::OutputPreferences,
+            crate::metadata_db::MetadataExport,
+            crate::metadata_db::TrackRef,
+            crate::metadata_db::TrackExport,
+            crate::metadata_db::AlbumExport,
+            crate::metadata_db::ArtistExport,
+            crate::metadata_db::TextExport,
+            crate::metadata_db::PlaylistExport,
+            crate::metadata_db::MetadataImportSummary,
+            crate::metadata_db::ApiTokenInfo,
+            crate::metadata_db::PlaylistSummary,
+            crate::metadata_db::Rating,
+            crate::metadata_db::SearchHit,
+            crate::metadata_db::SearchKind,
+            crate::metadata_db::LossyReportEntry,
             crate::events::MetadataEvent,
             crate::events::LogEvent,
             api::LogsClearResponse,
+            api::LogLevelRequest,
+            api::LogLevelResponse,
             api::HealthResponse,
+            api::BridgeHealthEntry,
+            api::BridgeHealthDetail,
         )
     ),
     tags(
@@ -97,19 +97,26 @@ impl OutputController {
         self.registry
             .volume_for_output(state, output_id)
             .await
+            .map(|resp| untrim_volume(state, output_id, resp))
             .map_err(|e| OutputControllerError::Http(e.into_response()))
     }
 
-    /// Set volume for a specific output id.
+    /// Set volume for a specific output id, applying the output's volume trim.
     pub(crate) async fn set_volume_for_output(
         &self,
         state: &AppState,
         output_id: &str,
         value: u8,
     ) -> Result<SessionVolumeResponse, OutputControllerError> {
+        let device_value = state
+            .output_settings
+            .lock()
+            .map(|settings| settings.trimmed_volume(output_id, value))
+            .unwrap_or(value);
         self.registry
-            .set_volume_for_output(state, output_id, value)
+            .set_volume_for_output(state, output_id, device_value)
             .await
+            .map(|resp| untrim_volume(state, output_id, resp))
             .map_err(|e| OutputControllerError::Http(e.into_response()))
     }
 
@@ -123,6 +130,7 @@ impl OutputController {
         self.registry
             .set_mute_for_output(state, output_id, muted)
             .await
+            .map(|resp| untrim_volume(state, output_id, resp))
             .map_err(|e| OutputControllerError::Http(e.into_response()))
     }
 
@@ -345,6 +353,11 @@ impl OutputController {
         } else {
             root.join(path)
         };
+        // Cue tracks are checked through their sheet; the `#NN` suffix is kept.
+        if let Some((cue_path, number)) = crate::cue_tracks::split_track_path(&candidate) {
+            let cue_path = self.canonicalize_under_root(state, &cue_path)?;
+            return Ok(crate::cue_tracks::track_path(&cue_path, number));
+        }
         let canon = candidate.canonicalize().map_err(|_| {
             OutputControllerError::Http(
                 HttpResponse::BadRequest().body(format!("path does not exist: {:?}", path)),
@@ -359,6 +372,18 @@ impl OutputController {
     }
 }
 
+/// Report a device volume as the session volume, removing the output's volume trim.
+fn untrim_volume(
+    state: &AppState,
+    output_id: &str,
+    mut resp: SessionVolumeResponse,
+) -> SessionVolumeResponse {
+    if let Ok(settings) = state.output_settings.lock() {
+        resp.value = settings.untrimmed_volume(output_id, resp.value);
+    }
+    resp
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,640 @@
+//! Output groups, from `[[output_groups]]` config.
+//!
+//! A group output (`group:<id>`) plays the same track on several bridge outputs at once. The group
+//! worker fans every command out to the member bridges, then keeps them aligned from the position
+//! each bridge reports on its status stream: a member that drifts from the leader (the first
+//! member still playing) by more than [`DRIFT_THRESHOLD_MS`] is seeked to the leader's position.
+//! The bridges' statuses are merged into one status for the group.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use audio_bridge_types::{BridgeStatus, PlaybackEndReason};
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
+use futures_util::future::join_all;
+
+use crate::bridge::BridgeCommand;
+use crate::bridge_manager::{merge_bridges, parse_output_id};
+use crate::bridge_transport::BridgeTransportClient;
+use crate::config::ServerConfig;
+use crate::events::EventBus;
+use crate::metadata_db::MetadataDb;
+use crate::playback_transport::ChannelTransport;
+use crate::queue_service::QueueService;
+use crate::state::{
+    BridgeProviderState, OutputGroupProviderState, OutputSettingsState, QueueState,
+};
+use crate::status_store::StatusStore;
+
+/// Members further apart than this are realigned.
+pub(crate) const DRIFT_THRESHOLD_MS: u64 = 250;
+/// Minimum time between two realignments of one member.
+const REALIGN_COOLDOWN: Duration = Duration::from_secs(5);
+/// Time after a play, seek or resume before drift is measured (bridges are still buffering).
+const SETTLE_TIME: Duration = Duration::from_secs(3);
+/// How often the group status is merged and drift checked.
+const TICK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// One bridge output in a group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupMember {
+    pub output_id: String,
+    pub bridge_id: String,
+    pub device_id: String,
+}
+
+/// A configured output group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputGroup {
+    pub id: String,
+    pub name: String,
+    pub members: Vec<GroupMember>,
+}
+
+/// Resolve `[[output_groups]]` from config (empty when absent).
+pub fn from_config(cfg: &ServerConfig) -> anyhow::Result<Vec<OutputGroup>> {
+    groups_from_config(cfg).map_err(|errors| anyhow::anyhow!(errors.join("; ")))
+}
+
+/// Resolve `[[output_groups]]`, listing every invalid entry.
+pub(crate) fn groups_from_config(cfg: &ServerConfig) -> Result<Vec<OutputGroup>, Vec<String>> {
+    let mut errors = Vec::new();
+    let mut groups: Vec<OutputGroup> = Vec::new();
+    for (idx, group) in cfg.output_groups.iter().flatten().enumerate() {
+        let id = group.id.trim();
+        if id.is_empty() || id.contains(':') {
+            errors.push(format!(
+                "output_groups[{idx}].id: must be non-empty and contain no `:`"
+            ));
+        } else if groups.iter().any(|g| g.id == id) {
+            errors.push(format!("output_groups[{idx}].id: duplicate id `{id}`"));
+        }
+        if group.members.len() < 2 {
+            errors.push(format!(
+                "output_groups[{idx}].members: needs at least two outputs"
+            ));
+        }
+        let mut members: Vec<GroupMember> = Vec::new();
+        for (member_idx, output_id) in group.members.iter().enumerate() {
+            let Ok((bridge_id, device_id)) = parse_output_id(output_id) else {
+                errors.push(format!(
+                    "output_groups[{idx}].members[{member_idx}]: expected a bridge:<bridge>:<device> output id (got `{output_id}`)"
+                ));
+                continue;
+            };
+            if members.iter().any(|m| m.bridge_id == bridge_id) {
+                errors.push(format!(
+                    "output_groups[{idx}].members[{member_idx}]: bridge `{bridge_id}` is already in the group (a bridge plays one output at a time)"
+                ));
+                continue;
+            }
+            members.push(GroupMember {
+                output_id: output_id.clone(),
+                bridge_id,
+                device_id,
+            });
+        }
+        groups.push(OutputGroup {
+            id: id.to_string(),
+            name: group.name.clone().unwrap_or_else(|| id.to_string()),
+            members,
+        });
+    }
+    if errors.is_empty() {
+        Ok(groups)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Merge the members' bridge statuses (in member order, `None` when offline) into one.
+///
+/// The leader is the first member still playing. The group is paused only when every playing
+/// member is, and has ended only when every online member has.
+pub(crate) fn aggregate_status(members: &[Option<BridgeStatus>]) -> Option<BridgeStatus> {
+    let online: Vec<&BridgeStatus> = members.iter().flatten().collect();
+    let leader = online
+        .iter()
+        .find(|s| s.now_playing.is_some() && s.end_reason.is_none())
+        .or_else(|| online.first())?;
+    let mut merged = (*leader).clone();
+    let playing: Vec<&&BridgeStatus> = online
+        .iter()
+        .filter(|s| s.now_playing.is_some() && s.end_reason.is_none())
+        .collect();
+    merged.paused = playing.iter().all(|s| s.paused);
+    merged.end_reason = if playing.is_empty() {
+        online
+            .iter()
+            .filter_map(|s| s.end_reason)
+            .find(|r| *r == PlaybackEndReason::Eof)
+            .or(leader.end_reason)
+    } else {
+        None
+    };
+    merged.underrun_frames = online
+        .iter()
+        .filter_map(|s| s.underrun_frames)
+        .reduce(|a, b| a + b);
+    merged.underrun_events = online
+        .iter()
+        .filter_map(|s| s.underrun_events)
+        .reduce(|a, b| a + b);
+    let devices: Vec<&str> = online.iter().filter_map(|s| s.device.as_deref()).collect();
+    if !devices.is_empty() {
+        merged.device = Some(devices.join(" + "));
+    }
+    Some(merged)
+}
+
+/// Members (by index) that drifted from the leader, with the position to seek each one to.
+pub(crate) fn drifted_members(
+    members: &[Option<BridgeStatus>],
+    threshold_ms: u64,
+) -> Vec<(usize, u64)> {
+    let playing = |s: &BridgeStatus| s.now_playing.is_some() && s.end_reason.is_none() && !s.paused;
+    let Some((leader_idx, leader)) = members
+        .iter()
+        .enumerate()
+        .find_map(|(idx, s)| s.as_ref().filter(|s| playing(s)).map(|s| (idx, s)))
+    else {
+        return Vec::new();
+    };
+    let Some(leader_ms) = leader.elapsed_ms else {
+        return Vec::new();
+    };
+    members
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| *idx != leader_idx)
+        .filter_map(|(idx, status)| {
+            let status = status.as_ref().filter(|s| playing(s))?;
+            if status.now_playing != leader.now_playing {
+                return None;
+            }
+            let elapsed = status.elapsed_ms?;
+            (elapsed.abs_diff(leader_ms) > threshold_ms).then_some((idx, leader_ms))
+        })
+        .collect()
+}
+
+/// Shared handles the group worker reports through.
+pub struct GroupWorkerContext {
+    pub provider: Arc<OutputGroupProviderState>,
+    pub bridge: Arc<BridgeProviderState>,
+    pub output_settings: Arc<Mutex<OutputSettingsState>>,
+    pub status: StatusStore,
+    pub queue: Arc<Mutex<QueueState>>,
+    pub events: EventBus,
+    pub metadata: Option<MetadataDb>,
+}
+
+/// Per-group worker state.
+struct Worker {
+    output_id: String,
+    group: OutputGroup,
+    cmd_tx: Sender<BridgeCommand>,
+    ctx: GroupWorkerContext,
+    queue_service: QueueService,
+    current_path: Option<PathBuf>,
+    /// A member has reported the current track playing; a later end is the group's end.
+    started: bool,
+    settle_until: Instant,
+    last_realign: HashMap<String, Instant>,
+    last_tick: Instant,
+    last_duration_ms: Option<u64>,
+    auto_advance_in_flight: bool,
+}
+
+/// Spawn the worker driving one output group.
+pub fn spawn_group_worker(
+    output_id: String,
+    group: OutputGroup,
+    cmd_rx: Receiver<BridgeCommand>,
+    cmd_tx: Sender<BridgeCommand>,
+    ctx: GroupWorkerContext,
+) {
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("output group runtime");
+        runtime.block_on(async move {
+            let queue_service =
+                QueueService::new(ctx.queue.clone(), ctx.status.clone(), ctx.events.clone());
+            let mut worker = Worker {
+                output_id,
+                group,
+                cmd_tx,
+                ctx,
+                queue_service,
+                current_path: None,
+                started: false,
+                settle_until: Instant::now(),
+                last_realign: HashMap::new(),
+                last_tick: Instant::now(),
+                last_duration_ms: None,
+                auto_advance_in_flight: false,
+            };
+            loop {
+                match cmd_rx.try_recv() {
+                    Ok(BridgeCommand::Quit) | Err(TryRecvError::Disconnected) => break,
+                    Ok(cmd) => worker.handle(cmd).await,
+                    Err(TryRecvError::Empty) => {
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                }
+                if worker.current_path.is_some() && worker.last_tick.elapsed() >= TICK_INTERVAL {
+                    worker.tick().await;
+                }
+            }
+            let provider = &worker.ctx.provider;
+            if let Ok(mut statuses) = provider.status_by_output.lock() {
+                statuses.remove(&worker.output_id);
+            }
+            if let Ok(mut workers) = provider.workers.lock() {
+                workers.remove(&worker.output_id);
+            }
+            tracing::info!(group_id = %worker.group.id, "output group worker stopped");
+        });
+    });
+}
+
+impl Worker {
+    /// HTTP address of a member's bridge, from configured and discovered bridges.
+    fn member_addr(&self, member: &GroupMember) -> Option<SocketAddr> {
+        let bridges = self.ctx.bridge.bridges.lock().ok()?;
+        let discovered = self.ctx.bridge.discovered_bridges.lock().ok()?;
+        merge_bridges(&bridges.bridges, &discovered)
+            .into_iter()
+            .find(|b| b.id == member.bridge_id)
+            .map(|b| b.http_addr)
+    }
+
+    /// Transport clients for reachable members.
+    fn member_clients(&self) -> Vec<(GroupMember, BridgeTransportClient)> {
+        self.group
+            .members
+            .iter()
+            .filter_map(|member| {
+                let addr = self.member_addr(member)?;
+                let client = BridgeTransportClient::new_with_base(
+                    addr,
+                    self.ctx.bridge.public_base_url.clone(),
+                    self.ctx.metadata.clone(),
+                );
+                Some((member.clone(), client))
+            })
+            .collect()
+    }
+
+    /// Latest status stream snapshot of every member, in member order.
+    fn member_statuses(&self) -> Vec<Option<BridgeStatus>> {
+        let cache = self.ctx.bridge.status_cache.lock().ok();
+        self.group
+            .members
+            .iter()
+            .map(|m| cache.as_ref().and_then(|c| c.get(&m.bridge_id).cloned()))
+            .collect()
+    }
+
+    /// Run `call` against every reachable member at once, logging failures.
+    async fn fan_out<F, Fut>(&self, what: &str, call: F)
+    where
+        F: Fn(GroupMember, BridgeTransportClient) -> Fut,
+        Fut: std::future::Future<Output = anyhow::Result<()>>,
+    {
+        let members = self.member_clients();
+        let ids: Vec<String> = members.iter().map(|(m, _)| m.output_id.clone()).collect();
+        let results = join_all(members.into_iter().map(|(m, c)| call(m, c))).await;
+        for (output_id, result) in ids.into_iter().zip(results) {
+            if let Err(err) = result {
+                tracing::warn!(
+                    group_id = %self.group.id,
+                    output_id = %output_id,
+                    error = %format!("{err:#}"),
+                    "output group: {what} failed"
+                );
+            }
+        }
+    }
+
+    /// Apply a control command to every member.
+    async fn handle(&mut self, cmd: BridgeCommand) {
+        match cmd {
+            BridgeCommand::Play {
+                path,
+                ext_hint,
+                seek_ms,
+                start_paused,
+            } => self.play(path, ext_hint, seek_ms, start_paused).await,
+            BridgeCommand::PauseToggle => {
+                if self.current_path.is_none() {
+                    return;
+                }
+                self.fan_out(
+                    "pause",
+                    |_, client| async move { client.pause_toggle().await },
+                )
+                .await;
+                self.settle_until = Instant::now() + SETTLE_TIME;
+                self.ctx.status.on_pause_toggle();
+            }
+            BridgeCommand::Seek { ms } => {
+                self.fan_out("seek", |_, client| async move { client.seek(ms).await })
+                    .await;
+                self.settle_until = Instant::now() + SETTLE_TIME;
+                self.ctx.status.mark_seek_in_flight();
+            }
+            BridgeCommand::Stop | BridgeCommand::StopSilent => {
+                self.fan_out("stop", |_, client| async move { client.stop().await })
+                    .await;
+                self.current_path = None;
+                self.publish(BridgeStatus::default(), Some(PlaybackEndReason::Stopped));
+            }
+            BridgeCommand::Quit => {}
+        }
+    }
+
+    /// Select each member's device and start the track on all of them.
+    async fn play(
+        &mut self,
+        path: PathBuf,
+        ext_hint: String,
+        seek_ms: Option<u64>,
+        start_paused: bool,
+    ) {
+        self.auto_advance_in_flight = false;
+        let options: HashMap<String, _> = self
+            .group
+            .members
+            .iter()
+            .map(|m| {
+                let options = self
+                    .ctx
+                    .output_settings
+                    .lock()
+                    .map(|s| s.select_options(&m.output_id))
+                    .unwrap_or_default();
+                (m.output_id.clone(), options)
+            })
+            .collect();
+        // Select devices first so the play requests go out back to back.
+        self.fan_out("select device", |member, client| {
+            let options = options.get(&member.output_id).cloned().unwrap_or_default();
+            async move { client.set_device_by_id(&member.device_id, options).await }
+        })
+        .await;
+        let title = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+        let ext_hint = (!ext_hint.trim().is_empty()).then_some(ext_hint);
+        let play_path = path.clone();
+        self.fan_out("play", |_, client| {
+            let (path, ext_hint, title) = (play_path.clone(), ext_hint.clone(), title.clone());
+            async move {
+                client
+                    .play_path(
+                        &path,
+                        ext_hint.as_deref(),
+                        title.as_deref(),
+                        seek_ms,
+                        start_paused,
+                    )
+                    .await
+            }
+        })
+        .await;
+        self.current_path = Some(path.clone());
+        self.started = false;
+        self.settle_until = Instant::now() + SETTLE_TIME;
+        self.last_realign.clear();
+        self.ctx.status.on_play(path, start_paused);
+    }
+
+    /// Merge member statuses, detect the end of the track and realign drifted members.
+    async fn tick(&mut self) {
+        self.last_tick = Instant::now();
+        let Some(path) = self.current_path.clone() else {
+            return;
+        };
+        let statuses = self.member_statuses();
+        let Some(mut merged) = aggregate_status(&statuses) else {
+            return;
+        };
+        if merged.end_reason.is_none() && merged.now_playing.is_some() {
+            self.started = true;
+        }
+        if self.started && merged.end_reason.is_some() {
+            self.current_path = None;
+            let reason = merged.end_reason;
+            self.publish(BridgeStatus::default(), reason);
+            return;
+        }
+        merged.now_playing = Some(path.to_string_lossy().to_string());
+        merged.end_reason = None;
+        self.publish(merged, None);
+
+        if Instant::now() < self.settle_until {
+            return;
+        }
+        for (idx, target_ms) in drifted_members(&statuses, DRIFT_THRESHOLD_MS) {
+            let member = self.group.members[idx].clone();
+            if self
+                .last_realign
+                .get(&member.output_id)
+                .is_some_and(|at| at.elapsed() < REALIGN_COOLDOWN)
+            {
+                continue;
+            }
+            let Some(addr) = self.member_addr(&member) else {
+                continue;
+            };
+            tracing::info!(
+                group_id = %self.group.id,
+                output_id = %member.output_id,
+                elapsed_ms = ?statuses[idx].as_ref().and_then(|s| s.elapsed_ms),
+                target_ms,
+                "output group: realigning member"
+            );
+            self.last_realign
+                .insert(member.output_id.clone(), Instant::now());
+            if let Err(err) = BridgeTransportClient::new(addr).seek(target_ms).await {
+                tracing::warn!(
+                    group_id = %self.group.id,
+                    output_id = %member.output_id,
+                    error = %format!("{err:#}"),
+                    "output group: realign failed"
+                );
+            }
+        }
+    }
+
+    /// Store the group status and handle the end of a track.
+    fn publish(&mut self, mut remote: BridgeStatus, end_reason: Option<PlaybackEndReason>) {
+        remote.end_reason = end_reason;
+        if end_reason.is_some() {
+            remote.paused = true;
+        }
+        if remote.device.is_none() {
+            remote.device = Some(self.group.name.clone());
+        }
+        let provider = &self.ctx.provider;
+        if let Ok(mut statuses) = provider.status_by_output.lock() {
+            statuses.insert(self.output_id.clone(), (remote.clone(), Instant::now()));
+        }
+
+        let bound_session = crate::session_registry::output_lock_owner(&self.output_id);
+        if end_reason.is_some() {
+            self.ctx.events.status_changed();
+        }
+        if end_reason == Some(PlaybackEndReason::Eof)
+            && !self.auto_advance_in_flight
+            && let Some(session_id) = bound_session.as_deref()
+        {
+            self.advance_session(session_id);
+        }
+        let is_active = self
+            .ctx
+            .bridge
+            .bridges
+            .lock()
+            .map(|b| b.active_output_id.as_deref() == Some(self.output_id.as_str()))
+            .unwrap_or(false);
+        if !is_active {
+            return;
+        }
+        let (inputs, changed) = self
+            .ctx
+            .status
+            .reduce_remote_and_inputs(&remote, self.last_duration_ms);
+        self.ctx.status.emit_if_changed(changed);
+        self.last_duration_ms = remote.duration_ms;
+        if bound_session.is_some() {
+            return;
+        }
+        let transport = ChannelTransport::new(self.cmd_tx.clone());
+        let _ = self.queue_service.maybe_auto_advance(&transport, inputs);
+    }
+
+    /// Play the next queue entry of the session holding this group.
+    fn advance_session(&mut self, session_id: &str) {
+        match crate::session_registry::queue_next_track_id(session_id) {
+            Ok(Some(track_id)) => {
+                let Some(path) = self
+                    .ctx
+                    .metadata
+                    .as_ref()
+                    .and_then(|db| db.track_path_for_id(track_id).ok().flatten())
+                    .map(PathBuf::from)
+                else {
+                    tracing::warn!(
+                        output_id = %self.output_id,
+                        session_id = %session_id,
+                        track_id,
+                        "output group session auto-advance track not found"
+                    );
+                    return;
+                };
+                self.auto_advance_in_flight = true;
+                let _ = self.cmd_tx.send(BridgeCommand::Play {
+                    path,
+                    ext_hint: String::new(),
+                    seek_ms: None,
+                    start_paused: false,
+                });
+            }
+            Ok(None) => {
+                if let Ok(true) = crate::session_registry::queue_finish_now_playing(session_id) {
+                    self.ctx.events.queue_changed();
+                    self.ctx.events.status_changed();
+                }
+            }
+            Err(()) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playing(track: &str, elapsed_ms: u64) -> Option<BridgeStatus> {
+        Some(BridgeStatus {
+            now_playing: Some(track.to_string()),
+            elapsed_ms: Some(elapsed_ms),
+            duration_ms: Some(200_000),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn groups_from_config_reports_invalid_entries() {
+        let cfg: ServerConfig = toml::from_str(
+            r#"
+            [[output_groups]]
+            id = "downstairs"
+            name = "Downstairs"
+            members = ["bridge:kitchen:hw0", "bridge:den:hw1"]
+
+            [[output_groups]]
+            id = "downstairs"
+            members = ["bridge:kitchen:hw0", "bridge:kitchen:hw1", "cast:abc"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            groups_from_config(&cfg).unwrap_err(),
+            vec![
+                "output_groups[1].id: duplicate id `downstairs`",
+                "output_groups[1].members[1]: bridge `kitchen` is already in the group (a bridge plays one output at a time)",
+                "output_groups[1].members[2]: expected a bridge:<bridge>:<device> output id (got `cast:abc`)",
+            ]
+        );
+
+        let cfg: ServerConfig = toml::from_str(
+            "[[output_groups]]\nid = \"all\"\nmembers = [\"bridge:a:x\", \"bridge:b:y\"]",
+        )
+        .unwrap();
+        let groups = groups_from_config(&cfg).unwrap();
+        assert_eq!(groups[0].name, "all");
+        assert_eq!(groups[0].members[1].bridge_id, "b");
+        assert_eq!(groups[0].members[1].device_id, "y");
+    }
+
+    #[test]
+    fn aggregate_status_follows_first_playing_member() {
+        let mut ended = playing("song", 200_000);
+        ended.as_mut().unwrap().end_reason = Some(PlaybackEndReason::Eof);
+        let mut paused = playing("song", 10_000);
+        paused.as_mut().unwrap().paused = true;
+        let merged =
+            aggregate_status(&[None, ended.clone(), paused, playing("song", 10_100)]).unwrap();
+        assert_eq!(merged.elapsed_ms, Some(10_000));
+        assert!(!merged.paused);
+        assert_eq!(merged.end_reason, None);
+
+        let merged = aggregate_status(&[ended.clone(), ended, None]).unwrap();
+        assert_eq!(merged.end_reason, Some(PlaybackEndReason::Eof));
+        assert!(aggregate_status(&[None, None]).is_none());
+    }
+
+    #[test]
+    fn drifted_members_seeks_laggards_to_the_leader() {
+        let members = [
+            None,
+            playing("song", 60_000),
+            playing("song", 60_200),
+            playing("song", 59_500),
+            playing("other", 10_000),
+        ];
+        assert_eq!(
+            drifted_members(&members, DRIFT_THRESHOLD_MS),
+            vec![(3, 60_000)]
+        );
+        assert!(drifted_members(&[None, playing("song", 1_000)], DRIFT_THRESHOLD_MS).is_empty());
+    }
+}
@@ -0,0 +1,405 @@
+//! AirPlay (RAOP) output provider.
+//!
+//! Streams hub-decoded audio to AirPlay 1 receivers found via mDNS (see [`crate::airplay`]).
+
+use async_trait::async_trait;
+use crossbeam_channel::Sender;
+
+use crate::airplay::{AirplayDeviceDescriptor, AirplayWorkerContext, spawn_airplay_worker};
+use crate::bridge::BridgeCommand;
+use crate::models::{
+    OutputCapabilities, OutputInfo, OutputsResponse, ProviderInfo, SessionVolumeResponse,
+    StatusResponse,
+};
+use crate::output_providers::cast_provider::{CastProvider, status_from_remote};
+use crate::output_providers::registry::{OutputProvider, ProviderError};
+use crate::state::AppState;
+
+/// Volume reported before any has been set (receivers start at their own level).
+const DEFAULT_VOLUME: u8 = 100;
+
+/// Output provider for AirPlay outputs (`airplay:<receiver_id>`).
+pub(crate) struct AirplayProvider;
+
+impl AirplayProvider {
+    /// Static provider id used for provider listings and routing.
+    fn provider_id() -> &'static str {
+        "airplay"
+    }
+
+    /// Build AirPlay output id from discovered receiver id.
+    fn output_id(receiver_id: &str) -> String {
+        format!("airplay:{receiver_id}")
+    }
+
+    /// Parse `airplay:<receiver_id>` and return the receiver id.
+    pub(crate) fn parse_output_id(output_id: &str) -> Option<String> {
+        let id = output_id.strip_prefix("airplay:")?;
+        (!id.is_empty()).then(|| id.to_string())
+    }
+
+    /// Ensure a streaming worker exists for the output and return its command sender.
+    pub(crate) fn ensure_worker_for_output(
+        state: &AppState,
+        output_id: &str,
+    ) -> Result<Sender<BridgeCommand>, ProviderError> {
+        let Some(receiver_id) = Self::parse_output_id(output_id) else {
+            return Err(ProviderError::BadRequest("invalid output id".to_string()));
+        };
+        let provider = &state.providers.airplay;
+        if let Some(existing) = provider
+            .workers
+            .lock()
+            .ok()
+            .and_then(|map| map.get(output_id).cloned())
+        {
+            return Ok(existing);
+        }
+        let found = provider
+            .discovered
+            .lock()
+            .ok()
+            .and_then(|map| map.get(&receiver_id).cloned());
+        let Some(found) = found else {
+            return Err(ProviderError::Unavailable(
+                "airplay receiver offline".to_string(),
+            ));
+        };
+        let (cmd_tx, cmd_rx) = crossbeam_channel::unbounded();
+        spawn_airplay_worker(
+            output_id.to_string(),
+            AirplayDeviceDescriptor {
+                id: found.id,
+                name: found.name,
+                host: found.host,
+                port: found.port,
+                codec: found.codec,
+            },
+            cmd_rx,
+            cmd_tx.clone(),
+            AirplayWorkerContext {
+                provider: provider.clone(),
+                status: state.playback.manager.status().clone(),
+                queue: state.playback.manager.queue_service().queue().clone(),
+                events: state.events.clone(),
+                metadata: Some(state.metadata.db.clone()),
+                bridge_state: state.providers.bridge.bridges.clone(),
+            },
+        );
+        if let Ok(mut workers) = provider.workers.lock() {
+            workers.insert(output_id.to_string(), cmd_tx.clone());
+        }
+        Ok(cmd_tx)
+    }
+
+    /// Return globally active output id from bridge state.
+    fn active_output_id(state: &AppState) -> Option<String> {
+        state
+            .providers
+            .bridge
+            .bridges
+            .lock()
+            .unwrap()
+            .active_output_id
+            .clone()
+    }
+
+    /// Map a discovered receiver into output listing payload.
+    fn device_output_info(
+        device: &crate::state::DiscoveredAirplay,
+        active_id: &Option<String>,
+    ) -> OutputInfo {
+        let id = Self::output_id(&device.id);
+        let state = if active_id.as_deref() == Some(&id) {
+            "active"
+        } else {
+            "online"
+        };
+        OutputInfo {
+            id,
+            kind: "airplay".to_string(),
+            name: format!("{} ({})", device.name, device.host),
+            state: state.to_string(),
+            provider_id: Some(Self::provider_id().to_string()),
+            provider_name: Some("AirPlay".to_string()),
+            supported_rates: None,
+            formats: None,
+            room: None,
+            zone: None,
+            capabilities: OutputCapabilities {
+                device_select: false,
+                volume: true,
+            },
+        }
+    }
+
+    /// Validate the output id and return the stored volume.
+    fn volume_entry(state: &AppState, output_id: &str) -> Result<(u8, bool), ProviderError> {
+        if Self::parse_output_id(output_id).is_none() {
+            return Err(ProviderError::BadRequest("invalid output id".to_string()));
+        }
+        Ok(state
+            .providers
+            .airplay
+            .volume
+            .lock()
+            .ok()
+            .and_then(|map| map.get(output_id).copied())
+            .unwrap_or((DEFAULT_VOLUME, false)))
+    }
+
+    /// Store a volume for the worker to apply and return it.
+    fn store_volume(
+        state: &AppState,
+        output_id: &str,
+        volume: (u8, bool),
+    ) -> SessionVolumeResponse {
+        if let Ok(mut map) = state.providers.airplay.volume.lock() {
+            map.insert(output_id.to_string(), volume);
+        }
+        Self::volume_response(volume)
+    }
+
+    /// Build the volume payload.
+    fn volume_response((value, muted): (u8, bool)) -> SessionVolumeResponse {
+        SessionVolumeResponse {
+            value,
+            muted,
+            source: "airplay".to_string(),
+            available: true,
+        }
+    }
+}
+
+#[async_trait]
+impl OutputProvider for AirplayProvider {
+    /// List AirPlay provider descriptor.
+    fn list_providers(&self, _state: &AppState) -> Vec<ProviderInfo> {
+        vec![ProviderInfo {
+            id: Self::provider_id().to_string(),
+            kind: "airplay".to_string(),
+            name: "AirPlay".to_string(),
+            state: "available".to_string(),
+            capabilities: OutputCapabilities {
+                device_select: false,
+                volume: true,
+            },
+        }]
+    }
+
+    async fn outputs_for_provider(
+        &self,
+        state: &AppState,
+        provider_id: &str,
+    ) -> Result<OutputsResponse, ProviderError> {
+        if provider_id != Self::provider_id() {
+            return Err(ProviderError::BadRequest("unknown provider id".to_string()));
+        }
+        let outputs = self.list_outputs(state).await;
+        let active_id = Self::active_output_id(state).filter(|id| id.starts_with("airplay:"));
+        Ok(OutputsResponse { active_id, outputs })
+    }
+
+    async fn list_outputs(&self, state: &AppState) -> Vec<OutputInfo> {
+        let active_id = Self::active_output_id(state);
+        let snapshot = state.providers.airplay.discovered.lock().ok();
+        snapshot
+            .map(|map| {
+                map.values()
+                    .map(|device| Self::device_output_info(device, &active_id))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Return whether output id belongs to the AirPlay namespace.
+    fn can_handle_output_id(&self, output_id: &str) -> bool {
+        output_id.starts_with("airplay:")
+    }
+
+    /// Return whether provider id matches the AirPlay provider id.
+    fn can_handle_provider_id(&self, _state: &AppState, provider_id: &str) -> bool {
+        provider_id == Self::provider_id()
+    }
+
+    /// AirPlay provider does not inject synthetic active outputs.
+    fn inject_active_output_if_missing(
+        &self,
+        _state: &AppState,
+        _outputs: &mut Vec<OutputInfo>,
+        _active_output_id: &str,
+    ) {
+    }
+
+    async fn ensure_active_connected(&self, state: &AppState) -> Result<(), ProviderError> {
+        let active_id = Self::active_output_id(state)
+            .ok_or_else(|| ProviderError::Unavailable("no active output selected".to_string()))?;
+        let Some(receiver_id) = Self::parse_output_id(&active_id) else {
+            return Err(ProviderError::BadRequest("invalid output id".to_string()));
+        };
+        let online = state
+            .providers
+            .airplay
+            .discovered
+            .lock()
+            .ok()
+            .is_some_and(|map| map.contains_key(&receiver_id));
+        if online {
+            Ok(())
+        } else {
+            Err(ProviderError::Unavailable(
+                "airplay receiver offline".to_string(),
+            ))
+        }
+    }
+
+    async fn select_output(&self, state: &AppState, output_id: &str) -> Result<(), ProviderError> {
+        let cmd_tx = Self::ensure_worker_for_output(state, output_id)?;
+        let has_session_owner = crate::session_registry::output_lock_owner(output_id).is_some();
+
+        {
+            let player = state.providers.bridge.player.lock().unwrap();
+            let _ = player.cmd_tx.send(BridgeCommand::Quit);
+        }
+        let resume_info = if has_session_owner {
+            None
+        } else {
+            let status = state.playback.manager.status().inner().lock().unwrap();
+            Some((status.now_playing.clone(), status.elapsed_ms, status.paused))
+        };
+        {
+            let mut player = state.providers.bridge.player.lock().unwrap();
+            player.cmd_tx = cmd_tx.clone();
+        }
+        {
+            let mut bridges = state.providers.bridge.bridges.lock().unwrap();
+            bridges.active_output_id = Some(output_id.to_string());
+            bridges.active_bridge_id = None;
+        }
+
+        if let Some((Some(path), Some(elapsed_ms), paused)) = resume_info {
+            let ext_hint = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or("")
+                .to_ascii_lowercase();
+            let _ = cmd_tx.send(BridgeCommand::Play {
+                path,
+                ext_hint,
+                seek_ms: Some(elapsed_ms),
+                start_paused: paused,
+            });
+        }
+        Ok(())
+    }
+
+    async fn status_for_output(
+        &self,
+        state: &AppState,
+        output_id: &str,
+    ) -> Result<StatusResponse, ProviderError> {
+        let Some(receiver_id) = Self::parse_output_id(output_id) else {
+            return Err(ProviderError::BadRequest("invalid output id".to_string()));
+        };
+        let provider = &state.providers.airplay;
+        let found = provider
+            .discovered
+            .lock()
+            .ok()
+            .and_then(|map| map.get(&receiver_id).cloned());
+        let Some(found) = found else {
+            return Ok(CastProvider::idle_status(output_id, None, false));
+        };
+        if let Some(mut remote) = provider
+            .status_by_output
+            .lock()
+            .ok()
+            .and_then(|map| map.get(output_id).cloned())
+        {
+            if !remote.paused
+                && let Some(base_elapsed) = remote.elapsed_ms
+                && let Some(updated_at) = provider
+                    .status_updated_at
+                    .lock()
+                    .ok()
+                    .and_then(|map| map.get(output_id).copied())
+            {
+                let advanced = base_elapsed.saturating_add(updated_at.elapsed().as_millis() as u64);
+                remote.elapsed_ms = Some(match remote.duration_ms {
+                    Some(duration) => advanced.min(duration),
+                    None => advanced,
+                });
+            }
+            return Ok(status_from_remote(state, output_id, remote));
+        }
+        Ok(CastProvider::idle_status(output_id, Some(found.name), true))
+    }
+
+    async fn stop_output(&self, state: &AppState, output_id: &str) -> Result<(), ProviderError> {
+        if Self::parse_output_id(output_id).is_none() {
+            return Err(ProviderError::BadRequest("invalid output id".to_string()));
+        }
+        if let Some(tx) = state
+            .providers
+            .airplay
+            .workers
+            .lock()
+            .ok()
+            .and_then(|map| map.get(output_id).cloned())
+        {
+            let _ = tx.send(BridgeCommand::Stop);
+            return Ok(());
+        }
+        if let Ok(player) = state.providers.bridge.player.lock() {
+            let _ = player.cmd_tx.send(BridgeCommand::Stop);
+        }
+        Ok(())
+    }
+
+    async fn volume_for_output(
+        &self,
+        state: &AppState,
+        output_id: &str,
+    ) -> Result<SessionVolumeResponse, ProviderError> {
+        Self::volume_entry(state, output_id).map(Self::volume_response)
+    }
+
+    async fn set_volume_for_output(
+        &self,
+        state: &AppState,
+        output_id: &str,
+        value: u8,
+    ) -> Result<SessionVolumeResponse, ProviderError> {
+        let (_, muted) = Self::volume_entry(state, output_id)?;
+        Ok(Self::store_volume(
+            state,
+            output_id,
+            (value.min(100), muted),
+        ))
+    }
+
+    async fn set_mute_for_output(
+        &self,
+        state: &AppState,
+        output_id: &str,
+        muted: bool,
+    ) -> Result<SessionVolumeResponse, ProviderError> {
+        let (value, _) = Self::volume_entry(state, output_id)?;
+        Ok(Self::store_volume(state, output_id, (value, muted)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_output_id_requires_airplay_prefix() {
+        assert_eq!(
+            AirplayProvider::parse_output_id("airplay:aabbccddeeff").as_deref(),
+            Some("aabbccddeeff")
+        );
+        assert_eq!(AirplayProvider::parse_output_id("airplay:"), None);
+        assert_eq!(AirplayProvider::parse_output_id("cast:abc"), None);
+    }
+}
@@ -9,8 +9,8 @@ use std::time::Duration;
 use crate::bridge_manager::{merge_bridges, parse_output_id, parse_provider_id};
 use crate::bridge_transport::{BridgeTransportClient, HttpDeviceInfo};
 use crate::models::{
-    OutputCapabilities, OutputInfo, OutputsResponse, ProviderInfo, SessionVolumeResponse,
-    StatusResponse, SupportedRates,
+    OutputCapabilities, OutputFormats, OutputInfo, OutputsResponse, ProviderInfo,
+    SessionVolumeResponse, StatusResponse, SupportedRates,
 };
 use crate::output_providers::registry::{OutputProvider, ProviderError};
 use crate::state::AppState;
@@ -352,8 +352,8 @@ impl OutputProvider for BridgeProvider {
                     return Err(ProviderError::Internal(format!("{e:#}")));
                 }
             };
-        let device_name = match resolve_device_name(&devices, &device_id) {
-            Some(name) => name,
+        let bridge_device_id = match resolve_device_id(&devices, &device_id) {
+            Some(id) => id,
             None => {
                 state
                     .providers
@@ -417,13 +417,13 @@ impl OutputProvider for BridgeProvider {
             );
             return Err(ProviderError::Internal(format!("{e:#}")));
         }
-        let exclusive = state
+        let options = state
             .output_settings
             .lock()
-            .map(|s| s.is_exclusive(output_id))
-            .unwrap_or(false);
+            .map(|s| s.select_options(output_id))
+            .unwrap_or_default();
         if let Err(e) = BridgeTransportClient::new(http_addr)
-            .set_device(&device_name, Some(exclusive))
+            .set_device_by_id(&bridge_device_id, options)
             .await
         {
             state
@@ -436,7 +436,7 @@ impl OutputProvider for BridgeProvider {
             }
             tracing::warn!(
                 bridge_id = %bridge_id,
-                device = %device_name,
+                device = %bridge_device_id,
                 error = %e,
                 "output select failed: set device"
             );
@@ -460,7 +460,7 @@ impl OutputProvider for BridgeProvider {
         Self::ensure_active_connected(state).await?;
         tracing::info!(
             bridge_id = %bridge_id,
-            device = %device_name,
+            device = %bridge_device_id,
             "output select ensured active connection"
         );
 
@@ -485,7 +485,7 @@ impl OutputProvider for BridgeProvider {
             );
             tracing::info!(
                 bridge_id = %bridge_id,
-                device = %device_name,
+                device = %bridge_device_id,
                 seek_ms = ?resume_info.1,
                 start_paused,
                 "output select resumed playback"
@@ -590,6 +590,11 @@ impl OutputProvider for BridgeProvider {
             buffered_frames: status.buffered_frames,
             buffer_capacity_frames: status.buffer_capacity_frames,
             has_previous: status.has_previous,
+            levels: None,
+            bit_perfect: None,
+            dop: None,
+            chapters: None,
+            chapter_index: None,
         };
         drop(status);
         if http_addr.is_some() {
@@ -755,8 +760,8 @@ async fn build_outputs_from_bridges_with_failures(
     }
 
     for (bridge, device) in by_bridge {
-        let mut display_name = device.name.clone();
-        if name_counts.get(&device.name).copied().unwrap_or(0) > 1 {
+        let mut display_name = device_label(&device);
+        if device.alias.is_none() && name_counts.get(&device.name).copied().unwrap_or(0) > 1 {
             let suffix = short_device_id(&device.id);
             display_name = format!("{display_name} [{}] ({suffix})", bridge.name);
         }
@@ -772,6 +777,9 @@ async fn build_outputs_from_bridges_with_failures(
             provider_id: Some(format!("bridge:{}", bridge.id)),
             provider_name: Some(bridge.name.clone()),
             supported_rates,
+            formats: device_formats(&device),
+            room: bridge.room.clone(),
+            zone: bridge.zone.clone(),
             capabilities: OutputCapabilities {
                 device_select: true,
                 volume: false,
@@ -795,8 +803,9 @@ async fn build_outputs_for_bridge(
         *name_counts.entry(device.name.clone()).or_insert(0) += 1;
     }
     for device in devices {
-        let mut name = device.name;
-        if name_counts.get(&name).copied().unwrap_or(0) > 1 {
+        let formats = device_formats(&device);
+        let mut name = device_label(&device);
+        if device.alias.is_none() && name_counts.get(&device.name).copied().unwrap_or(0) > 1 {
             let suffix = short_device_id(&device.id);
             name = format!("{name} ({suffix})");
         }
@@ -812,6 +821,9 @@ async fn build_outputs_for_bridge(
             provider_id: Some(format!("bridge:{}", bridge.id)),
             provider_name: Some(bridge.name.clone()),
             supported_rates,
+            formats,
+            room: bridge.room.clone(),
+            zone: bridge.zone.clone(),
             capabilities: OutputCapabilities {
                 device_select: true,
                 volume: false,
@@ -837,6 +849,8 @@ async fn list_devices_cached_or_fetch(
         id: bridge_id.to_string(),
         name: bridge_name.to_string(),
         http_addr,
+        room: None,
+        zone: None,
     };
     let devices = list_devices_with_retry(&bridge, 3).await?;
     if let Ok(mut cache) = state.providers.bridge.device_cache.lock() {
@@ -845,13 +859,21 @@ async fn list_devices_cached_or_fetch(
     Ok(devices)
 }
 
-/// Resolve display device name from id or fallback name lookup.
-fn resolve_device_name(devices: &[HttpDeviceInfo], device_id: &str) -> Option<String> {
+/// Resolve the bridge device id from an output's device id or fallback name lookup.
+fn resolve_device_id(devices: &[HttpDeviceInfo], device_id: &str) -> Option<String> {
     devices
         .iter()
         .find(|d| d.id == device_id)
         .or_else(|| devices.iter().find(|d| d.name == device_id))
-        .map(|d| d.name.clone())
+        .map(|d| d.id.clone())
+}
+
+/// Output name for a bridge device: its name, with the alias appended when one is set.
+fn device_label(device: &HttpDeviceInfo) -> String {
+    match device.alias.as_deref() {
+        Some(alias) => format!("{} ({alias})", device.name),
+        None => device.name.clone(),
+    }
 }
 
 /// Fetch cached bridge status snapshot for bridge id.
@@ -892,6 +914,11 @@ fn apply_remote_status(
     resp.buffer_size_frames = remote.buffer_size_frames;
     resp.buffered_frames = remote.buffered_frames;
     resp.buffer_capacity_frames = remote.buffer_capacity_frames;
+    resp.levels = remote.levels;
+    resp.bit_perfect = remote.bit_perfect;
+    resp.dop = remote.dop;
+    resp.chapters = remote.chapters;
+    resp.chapter_index = remote.chapter_index;
 }
 
 /// Fetch bridge devices with bounded retry policy.
@@ -959,6 +986,8 @@ mod tests_retry {
             id: "bridge-1".to_string(),
             name: "Bridge 1".to_string(),
             http_addr: "127.0.0.1:1".parse().unwrap(),
+            room: None,
+            zone: None,
         }
     }
 
@@ -975,9 +1004,13 @@ mod tests_retry {
                     } else {
                         Ok(vec![HttpDeviceInfo {
                             id: "dev1".to_string(),
+                            alias: None,
                             name: "Device 1".to_string(),
                             min_rate: 0,
                             max_rate: 0,
+                            sample_rates: Vec::new(),
+                            sample_formats: Vec::new(),
+                            channels: Vec::new(),
                         }])
                     }
                 })
@@ -1014,6 +1047,15 @@ fn normalize_supported_rates(min_hz: u32, max_hz: u32) -> Option<SupportedRates>
     Some(SupportedRates { min_hz, max_hz })
 }
 
+/// Native formats reported by the bridge for a device (`None` for older bridges).
+fn device_formats(device: &HttpDeviceInfo) -> Option<OutputFormats> {
+    OutputFormats::from_lists(
+        device.sample_rates.clone(),
+        device.sample_formats.clone(),
+        device.channels.clone(),
+    )
+}
+
 /// Shorten long device ids for display.
 fn short_device_id(id: &str) -> String {
     const MAX_LEN: usize = 48;
@@ -1066,6 +1108,9 @@ fn inject_active_output_for_bridge(
         provider_id: Some(format!("bridge:{}", bridge.id)),
         provider_name: Some(bridge.name.clone()),
         supported_rates,
+        formats: None,
+        room: bridge.room.clone(),
+        zone: bridge.zone.clone(),
         capabilities: OutputCapabilities {
             device_select: true,
             volume: false,
@@ -1128,6 +1173,35 @@ mod tests {
         assert_eq!(rates.max_hz, 96_000);
     }
 
+    #[test]
+    fn resolve_device_id_prefers_id_then_name() {
+        let device = |id: &str, name: &str, alias: Option<&str>| HttpDeviceInfo {
+            id: id.to_string(),
+            alias: alias.map(str::to_string),
+            name: name.to_string(),
+            min_rate: 44_100,
+            max_rate: 96_000,
+            sample_rates: Vec::new(),
+            sample_formats: Vec::new(),
+            channels: Vec::new(),
+        };
+        let devices = vec![
+            device("kitchen", "USB Audio Device", Some("kitchen")),
+            device("dev-0123456789abcdef", "Speakers", None),
+        ];
+        assert_eq!(
+            resolve_device_id(&devices, "kitchen").as_deref(),
+            Some("kitchen")
+        );
+        assert_eq!(
+            resolve_device_id(&devices, "Speakers").as_deref(),
+            Some("dev-0123456789abcdef")
+        );
+        assert!(resolve_device_id(&devices, "missing").is_none());
+        assert_eq!(device_label(&devices[0]), "USB Audio Device (kitchen)");
+        assert_eq!(device_label(&devices[1]), "Speakers");
+    }
+
     #[test]
     fn short_device_id_truncates_long_ids() {
         let long = "a".repeat(80);
@@ -1180,6 +1254,8 @@ mod tests {
             id: "bridge-1".to_string(),
             name: "Bridge".to_string(),
             http_addr: "127.0.0.1:5556".parse().unwrap(),
+            room: None,
+            zone: None,
         };
         let active_id = "bridge:bridge-1:device-1";
         let mut outputs = vec![OutputInfo {
@@ -1190,6 +1266,9 @@ mod tests {
             provider_id: Some("bridge:bridge-1".to_string()),
             provider_name: Some("Bridge".to_string()),
             supported_rates: None,
+            formats: None,
+            room: None,
+            zone: None,
             capabilities: OutputCapabilities {
                 device_select: true,
                 volume: false,
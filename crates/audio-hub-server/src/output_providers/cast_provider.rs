@@ -135,6 +135,9 @@ impl CastProvider {
             provider_id: Some(Self::provider_id().to_string()),
             provider_name: Some("Chromecast".to_string()),
             supported_rates: None,
+            formats: None,
+            room: None,
+            zone: None,
             capabilities: OutputCapabilities {
                 device_select: false,
                 volume: false,
@@ -143,7 +146,7 @@ impl CastProvider {
     }
 
     /// Build idle status payload for cast outputs without active media state.
-    fn idle_status(
+    pub(crate) fn idle_status(
         output_id: &str,
         device_name: Option<String>,
         bridge_online: bool,
@@ -178,6 +181,11 @@ impl CastProvider {
             buffered_frames: None,
             buffer_capacity_frames: None,
             has_previous: None,
+            levels: None,
+            bit_perfect: None,
+            dop: None,
+            chapters: None,
+            chapter_index: None,
         }
     }
 }
@@ -405,16 +413,16 @@ fn container_from_path(path: &std::path::PathBuf) -> Option<&'static str> {
         "flac" => Some("FLAC"),
         "mp3" => Some("MP3"),
         "aac" => Some("AAC"),
-        "m4a" => Some("MP4"),
-        "ogg" => Some("OGG"),
+        "m4a" | "alac" => Some("MP4"),
+        "ogg" | "oga" => Some("OGG"),
         "opus" => Some("OPUS"),
         "wav" => Some("WAV"),
         _ => None,
     }
 }
 
-/// Merge remote cast status with local library metadata for API response.
-fn status_from_remote(
+/// Merge remote cast (or AirPlay) status with local library metadata for API response.
+pub(crate) fn status_from_remote(
     state: &AppState,
     output_id: &str,
     remote: audio_bridge_types::BridgeStatus,
@@ -497,5 +505,10 @@ fn status_from_remote(
         buffered_frames: remote.buffered_frames,
         buffer_capacity_frames: remote.buffer_capacity_frames,
         has_previous: None,
+        levels: None,
+        bit_perfect: None,
+        dop: None,
+        chapters: None,
+        chapter_index: None,
     }
 }
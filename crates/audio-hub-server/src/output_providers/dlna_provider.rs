@@ -0,0 +1,359 @@
+//! UPnP/DLNA MediaRenderer output provider.
+//!
+//! Renderers found via SSDP play the hub's stream URLs through `AVTransport` (see [`crate::dlna`]).
+
+use actix_web::web;
+use async_trait::async_trait;
+use crossbeam_channel::Sender;
+
+use crate::bridge::BridgeCommand;
+use crate::dlna::{DlnaClient, DlnaDeviceDescriptor, DlnaWorkerContext, spawn_dlna_worker};
+use crate::models::{
+    OutputCapabilities, OutputInfo, OutputsResponse, ProviderInfo, SessionVolumeResponse,
+    StatusResponse,
+};
+use crate::output_providers::cast_provider::{CastProvider, status_from_remote};
+use crate::output_providers::registry::{OutputProvider, ProviderError};
+use crate::state::{AppState, DiscoveredDlna};
+
+/// Output provider for DLNA outputs (`dlna:<renderer_id>`).
+pub(crate) struct DlnaProvider;
+
+impl DlnaProvider {
+    /// Static provider id used for provider listings and routing.
+    fn provider_id() -> &'static str {
+        "dlna"
+    }
+
+    /// Build DLNA output id from discovered renderer id.
+    fn output_id(renderer_id: &str) -> String {
+        format!("dlna:{renderer_id}")
+    }
+
+    /// Parse `dlna:<renderer_id>` and return the renderer id.
+    pub(crate) fn parse_output_id(output_id: &str) -> Option<String> {
+        let id = output_id.strip_prefix("dlna:")?;
+        (!id.is_empty()).then(|| id.to_string())
+    }
+
+    /// Look up the discovered renderer behind an output id.
+    fn renderer(state: &AppState, output_id: &str) -> Result<DiscoveredDlna, ProviderError> {
+        let Some(renderer_id) = Self::parse_output_id(output_id) else {
+            return Err(ProviderError::BadRequest("invalid output id".to_string()));
+        };
+        state
+            .providers
+            .dlna
+            .discovered
+            .lock()
+            .ok()
+            .and_then(|map| map.get(&renderer_id).cloned())
+            .ok_or_else(|| ProviderError::Unavailable("dlna renderer offline".to_string()))
+    }
+
+    /// Control endpoints of a discovered renderer.
+    fn descriptor(found: DiscoveredDlna) -> DlnaDeviceDescriptor {
+        DlnaDeviceDescriptor {
+            id: found.id,
+            name: found.name,
+            host: found.host,
+            av_transport_url: found.av_transport_url,
+            rendering_control_url: found.rendering_control_url,
+        }
+    }
+
+    /// Ensure a control worker exists for the output and return its command sender.
+    pub(crate) fn ensure_worker_for_output(
+        state: &AppState,
+        output_id: &str,
+    ) -> Result<Sender<BridgeCommand>, ProviderError> {
+        let provider = &state.providers.dlna;
+        if let Some(existing) = provider
+            .workers
+            .lock()
+            .ok()
+            .and_then(|map| map.get(output_id).cloned())
+        {
+            return Ok(existing);
+        }
+        let found = Self::renderer(state, output_id)?;
+        let (cmd_tx, cmd_rx) = crossbeam_channel::unbounded();
+        spawn_dlna_worker(
+            output_id.to_string(),
+            Self::descriptor(found),
+            cmd_rx,
+            cmd_tx.clone(),
+            DlnaWorkerContext {
+                provider: provider.clone(),
+                status: state.playback.manager.status().clone(),
+                queue: state.playback.manager.queue_service().queue().clone(),
+                events: state.events.clone(),
+                public_base_url: state.providers.bridge.public_base_url.clone(),
+                metadata: Some(state.metadata.db.clone()),
+                bridge_state: state.providers.bridge.bridges.clone(),
+            },
+        );
+        if let Ok(mut workers) = provider.workers.lock() {
+            workers.insert(output_id.to_string(), cmd_tx.clone());
+        }
+        Ok(cmd_tx)
+    }
+
+    /// Return globally active output id from bridge state.
+    fn active_output_id(state: &AppState) -> Option<String> {
+        state
+            .providers
+            .bridge
+            .bridges
+            .lock()
+            .unwrap()
+            .active_output_id
+            .clone()
+    }
+
+    /// Map a discovered renderer into output listing payload.
+    fn device_output_info(device: &DiscoveredDlna, active_id: &Option<String>) -> OutputInfo {
+        let id = Self::output_id(&device.id);
+        let state = if active_id.as_deref() == Some(&id) {
+            "active"
+        } else {
+            "online"
+        };
+        OutputInfo {
+            id,
+            kind: "dlna".to_string(),
+            name: format!("{} ({})", device.name, device.host),
+            state: state.to_string(),
+            provider_id: Some(Self::provider_id().to_string()),
+            provider_name: Some("DLNA".to_string()),
+            supported_rates: None,
+            formats: None,
+            room: None,
+            zone: None,
+            capabilities: OutputCapabilities {
+                device_select: false,
+                volume: device.rendering_control_url.is_some(),
+            },
+        }
+    }
+
+    /// Run a blocking `RenderingControl` call for the output and return the resulting volume.
+    async fn rendering_call(
+        state: &AppState,
+        output_id: &str,
+        call: impl FnOnce(&DlnaClient) -> anyhow::Result<()> + Send + 'static,
+    ) -> Result<SessionVolumeResponse, ProviderError> {
+        let found = Self::renderer(state, output_id)?;
+        if found.rendering_control_url.is_none() {
+            return Err(ProviderError::Unavailable(
+                "volume control unavailable for this output".to_string(),
+            ));
+        }
+        let client = DlnaClient::new(Self::descriptor(found));
+        let (value, muted) = web::block(move || {
+            call(&client)?;
+            client.volume()
+        })
+        .await
+        .map_err(|e| ProviderError::Internal(e.to_string()))?
+        .map_err(|e| ProviderError::Internal(format!("{e:#}")))?;
+        Ok(SessionVolumeResponse {
+            value,
+            muted,
+            source: "dlna".to_string(),
+            available: true,
+        })
+    }
+}
+
+#[async_trait]
+impl OutputProvider for DlnaProvider {
+    /// List DLNA provider descriptor.
+    fn list_providers(&self, _state: &AppState) -> Vec<ProviderInfo> {
+        vec![ProviderInfo {
+            id: Self::provider_id().to_string(),
+            kind: "dlna".to_string(),
+            name: "DLNA".to_string(),
+            state: "available".to_string(),
+            capabilities: OutputCapabilities {
+                device_select: false,
+                volume: true,
+            },
+        }]
+    }
+
+    async fn outputs_for_provider(
+        &self,
+        state: &AppState,
+        provider_id: &str,
+    ) -> Result<OutputsResponse, ProviderError> {
+        if provider_id != Self::provider_id() {
+            return Err(ProviderError::BadRequest("unknown provider id".to_string()));
+        }
+        let outputs = self.list_outputs(state).await;
+        let active_id = Self::active_output_id(state).filter(|id| id.starts_with("dlna:"));
+        Ok(OutputsResponse { active_id, outputs })
+    }
+
+    async fn list_outputs(&self, state: &AppState) -> Vec<OutputInfo> {
+        let active_id = Self::active_output_id(state);
+        let snapshot = state.providers.dlna.discovered.lock().ok();
+        snapshot
+            .map(|map| {
+                map.values()
+                    .map(|device| Self::device_output_info(device, &active_id))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Return whether output id belongs to the DLNA namespace.
+    fn can_handle_output_id(&self, output_id: &str) -> bool {
+        output_id.starts_with("dlna:")
+    }
+
+    /// Return whether provider id matches the DLNA provider id.
+    fn can_handle_provider_id(&self, _state: &AppState, provider_id: &str) -> bool {
+        provider_id == Self::provider_id()
+    }
+
+    /// DLNA provider does not inject synthetic active outputs.
+    fn inject_active_output_if_missing(
+        &self,
+        _state: &AppState,
+        _outputs: &mut Vec<OutputInfo>,
+        _active_output_id: &str,
+    ) {
+    }
+
+    async fn ensure_active_connected(&self, state: &AppState) -> Result<(), ProviderError> {
+        let active_id = Self::active_output_id(state)
+            .ok_or_else(|| ProviderError::Unavailable("no active output selected".to_string()))?;
+        Self::renderer(state, &active_id).map(|_| ())
+    }
+
+    async fn select_output(&self, state: &AppState, output_id: &str) -> Result<(), ProviderError> {
+        let cmd_tx = Self::ensure_worker_for_output(state, output_id)?;
+        let has_session_owner = crate::session_registry::output_lock_owner(output_id).is_some();
+
+        {
+            let player = state.providers.bridge.player.lock().unwrap();
+            let _ = player.cmd_tx.send(BridgeCommand::Quit);
+        }
+        let resume_info = if has_session_owner {
+            None
+        } else {
+            let status = state.playback.manager.status().inner().lock().unwrap();
+            Some((status.now_playing.clone(), status.elapsed_ms, status.paused))
+        };
+        {
+            let mut player = state.providers.bridge.player.lock().unwrap();
+            player.cmd_tx = cmd_tx.clone();
+        }
+        {
+            let mut bridges = state.providers.bridge.bridges.lock().unwrap();
+            bridges.active_output_id = Some(output_id.to_string());
+            bridges.active_bridge_id = None;
+        }
+
+        if let Some((Some(path), Some(elapsed_ms), paused)) = resume_info {
+            let ext_hint = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or("")
+                .to_ascii_lowercase();
+            let _ = cmd_tx.send(BridgeCommand::Play {
+                path,
+                ext_hint,
+                seek_ms: Some(elapsed_ms),
+                start_paused: paused,
+            });
+        }
+        Ok(())
+    }
+
+    async fn status_for_output(
+        &self,
+        state: &AppState,
+        output_id: &str,
+    ) -> Result<StatusResponse, ProviderError> {
+        let found = match Self::renderer(state, output_id) {
+            Ok(found) => found,
+            Err(ProviderError::Unavailable(_)) => {
+                return Ok(CastProvider::idle_status(output_id, None, false));
+            }
+            Err(err) => return Err(err),
+        };
+        let provider = &state.providers.dlna;
+        if let Some(mut remote) = provider
+            .status_by_output
+            .lock()
+            .ok()
+            .and_then(|map| map.get(output_id).cloned())
+        {
+            if !remote.paused
+                && let Some(base_elapsed) = remote.elapsed_ms
+                && let Some(updated_at) = provider
+                    .status_updated_at
+                    .lock()
+                    .ok()
+                    .and_then(|map| map.get(output_id).copied())
+            {
+                let advanced = base_elapsed.saturating_add(updated_at.elapsed().as_millis() as u64);
+                remote.elapsed_ms = Some(match remote.duration_ms {
+                    Some(duration) => advanced.min(duration),
+                    None => advanced,
+                });
+            }
+            return Ok(status_from_remote(state, output_id, remote));
+        }
+        Ok(CastProvider::idle_status(output_id, Some(found.name), true))
+    }
+
+    async fn stop_output(&self, state: &AppState, output_id: &str) -> Result<(), ProviderError> {
+        if Self::parse_output_id(output_id).is_none() {
+            return Err(ProviderError::BadRequest("invalid output id".to_string()));
+        }
+        if let Some(tx) = state
+            .providers
+            .dlna
+            .workers
+            .lock()
+            .ok()
+            .and_then(|map| map.get(output_id).cloned())
+        {
+            let _ = tx.send(BridgeCommand::Stop);
+            return Ok(());
+        }
+        if let Ok(player) = state.providers.bridge.player.lock() {
+            let _ = player.cmd_tx.send(BridgeCommand::Stop);
+        }
+        Ok(())
+    }
+
+    async fn volume_for_output(
+        &self,
+        state: &AppState,
+        output_id: &str,
+    ) -> Result<SessionVolumeResponse, ProviderError> {
+        Self::rendering_call(state, output_id, |_| Ok(())).await
+    }
+
+    async fn set_volume_for_output(
+        &self,
+        state: &AppState,
+        output_id: &str,
+        value: u8,
+    ) -> Result<SessionVolumeResponse, ProviderError> {
+        Self::rendering_call(state, output_id, move |client| client.set_volume(value)).await
+    }
+
+    async fn set_mute_for_output(
+        &self,
+        state: &AppState,
+        output_id: &str,
+        muted: bool,
+    ) -> Result<SessionVolumeResponse, ProviderError> {
+        Self::rendering_call(state, output_id, move |client| client.set_mute(muted)).await
+    }
+}
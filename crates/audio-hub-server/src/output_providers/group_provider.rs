@@ -0,0 +1,425 @@
+//! Output group provider.
+//!
+//! Exposes `[[output_groups]]` as `group:<id>` outputs that play on several bridges at once
+//! (see [`crate::output_groups`]).
+
+use async_trait::async_trait;
+use crossbeam_channel::Sender;
+use futures_util::future::join_all;
+
+use crate::bridge::BridgeCommand;
+use crate::bridge_manager::merge_bridges;
+use crate::bridge_transport::{BridgeTransportClient, HttpVolumeResponse};
+use crate::models::{
+    OutputCapabilities, OutputInfo, OutputsResponse, ProviderInfo, SessionVolumeResponse,
+    StatusResponse,
+};
+use crate::output_groups::{GroupWorkerContext, OutputGroup, spawn_group_worker};
+use crate::output_providers::cast_provider::{CastProvider, status_from_remote};
+use crate::output_providers::registry::{OutputProvider, ProviderError};
+use crate::state::AppState;
+
+/// Output provider for output groups (`group:<group_id>`).
+pub(crate) struct GroupProvider;
+
+impl GroupProvider {
+    /// Static provider id used for provider listings and routing.
+    fn provider_id() -> &'static str {
+        "group"
+    }
+
+    /// Build group output id from group id.
+    fn output_id(group_id: &str) -> String {
+        format!("group:{group_id}")
+    }
+
+    /// Parse `group:<group_id>` and return the group id.
+    pub(crate) fn parse_output_id(output_id: &str) -> Option<String> {
+        let id = output_id.strip_prefix("group:")?;
+        (!id.is_empty()).then(|| id.to_string())
+    }
+
+    /// Configured groups (empty when none are configured).
+    fn groups(state: &AppState) -> &[OutputGroup] {
+        state
+            .providers
+            .groups
+            .groups
+            .get()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Look up the configured group behind an output id.
+    fn group(state: &AppState, output_id: &str) -> Result<OutputGroup, ProviderError> {
+        let Some(group_id) = Self::parse_output_id(output_id) else {
+            return Err(ProviderError::BadRequest("invalid output id".to_string()));
+        };
+        Self::groups(state)
+            .iter()
+            .find(|g| g.id == group_id)
+            .cloned()
+            .ok_or_else(|| ProviderError::BadRequest("unknown output group".to_string()))
+    }
+
+    /// Whether any member bridge currently reports status.
+    fn any_member_online(state: &AppState, group: &OutputGroup) -> bool {
+        state
+            .providers
+            .bridge
+            .status_cache
+            .lock()
+            .map(|cache| {
+                group
+                    .members
+                    .iter()
+                    .any(|m| cache.contains_key(&m.bridge_id))
+            })
+            .unwrap_or(false)
+    }
+
+    /// Transport clients for the member bridges that are known.
+    fn member_clients(state: &AppState, group: &OutputGroup) -> Vec<BridgeTransportClient> {
+        let bridges = state.providers.bridge.bridges.lock().unwrap();
+        let discovered = state.providers.bridge.discovered_bridges.lock().unwrap();
+        let merged = merge_bridges(&bridges.bridges, &discovered);
+        group
+            .members
+            .iter()
+            .filter_map(|m| merged.iter().find(|b| b.id == m.bridge_id))
+            .map(|b| BridgeTransportClient::new(b.http_addr))
+            .collect()
+    }
+
+    /// Ensure a worker exists for the group and return its command sender.
+    pub(crate) fn ensure_worker_for_output(
+        state: &AppState,
+        output_id: &str,
+    ) -> Result<Sender<BridgeCommand>, ProviderError> {
+        let provider = &state.providers.groups;
+        if let Some(existing) = provider
+            .workers
+            .lock()
+            .ok()
+            .and_then(|map| map.get(output_id).cloned())
+        {
+            return Ok(existing);
+        }
+        let group = Self::group(state, output_id)?;
+        let (cmd_tx, cmd_rx) = crossbeam_channel::unbounded();
+        spawn_group_worker(
+            output_id.to_string(),
+            group,
+            cmd_rx,
+            cmd_tx.clone(),
+            GroupWorkerContext {
+                provider: provider.clone(),
+                bridge: state.providers.bridge.clone(),
+                output_settings: state.output_settings.clone(),
+                status: state.playback.manager.status().clone(),
+                queue: state.playback.manager.queue_service().queue().clone(),
+                events: state.events.clone(),
+                metadata: Some(state.metadata.db.clone()),
+            },
+        );
+        if let Ok(mut workers) = provider.workers.lock() {
+            workers.insert(output_id.to_string(), cmd_tx.clone());
+        }
+        Ok(cmd_tx)
+    }
+
+    /// Return globally active output id from bridge state.
+    fn active_output_id(state: &AppState) -> Option<String> {
+        state
+            .providers
+            .bridge
+            .bridges
+            .lock()
+            .unwrap()
+            .active_output_id
+            .clone()
+    }
+
+    /// Map a configured group into output listing payload.
+    fn group_output_info(
+        state: &AppState,
+        group: &OutputGroup,
+        active_id: &Option<String>,
+    ) -> OutputInfo {
+        let id = Self::output_id(&group.id);
+        let output_state = if active_id.as_deref() == Some(&id) {
+            "active"
+        } else if Self::any_member_online(state, group) {
+            "online"
+        } else {
+            "offline"
+        };
+        OutputInfo {
+            id,
+            kind: "group".to_string(),
+            name: group.name.clone(),
+            state: output_state.to_string(),
+            provider_id: Some(Self::provider_id().to_string()),
+            provider_name: Some("Output groups".to_string()),
+            supported_rates: None,
+            formats: None,
+            room: None,
+            zone: None,
+            capabilities: OutputCapabilities {
+                device_select: false,
+                volume: true,
+            },
+        }
+    }
+
+    /// Run a volume call on every member and return the group's mean volume.
+    async fn volume_call<F, Fut>(
+        state: &AppState,
+        output_id: &str,
+        call: F,
+    ) -> Result<SessionVolumeResponse, ProviderError>
+    where
+        F: Fn(BridgeTransportClient) -> Fut,
+        Fut: std::future::Future<Output = anyhow::Result<HttpVolumeResponse>>,
+    {
+        let group = Self::group(state, output_id)?;
+        let clients = Self::member_clients(state, &group);
+        let volumes: Vec<HttpVolumeResponse> = join_all(clients.into_iter().map(call))
+            .await
+            .into_iter()
+            .filter_map(Result::ok)
+            .collect();
+        if volumes.is_empty() {
+            return Err(ProviderError::Unavailable(
+                "no group member reachable".to_string(),
+            ));
+        }
+        let total: u32 = volumes.iter().map(|v| u32::from(v.value.min(100))).sum();
+        Ok(SessionVolumeResponse {
+            value: (total / volumes.len() as u32) as u8,
+            muted: volumes.iter().all(|v| v.muted),
+            source: "group".to_string(),
+            available: true,
+        })
+    }
+}
+
+#[async_trait]
+impl OutputProvider for GroupProvider {
+    /// List the output group provider when groups are configured.
+    fn list_providers(&self, state: &AppState) -> Vec<ProviderInfo> {
+        if Self::groups(state).is_empty() {
+            return Vec::new();
+        }
+        vec![ProviderInfo {
+            id: Self::provider_id().to_string(),
+            kind: "group".to_string(),
+            name: "Output groups".to_string(),
+            state: "available".to_string(),
+            capabilities: OutputCapabilities {
+                device_select: false,
+                volume: true,
+            },
+        }]
+    }
+
+    async fn outputs_for_provider(
+        &self,
+        state: &AppState,
+        provider_id: &str,
+    ) -> Result<OutputsResponse, ProviderError> {
+        if provider_id != Self::provider_id() {
+            return Err(ProviderError::BadRequest("unknown provider id".to_string()));
+        }
+        let outputs = self.list_outputs(state).await;
+        let active_id = Self::active_output_id(state).filter(|id| id.starts_with("group:"));
+        Ok(OutputsResponse { active_id, outputs })
+    }
+
+    async fn list_outputs(&self, state: &AppState) -> Vec<OutputInfo> {
+        let active_id = Self::active_output_id(state);
+        Self::groups(state)
+            .iter()
+            .map(|group| Self::group_output_info(state, group, &active_id))
+            .collect()
+    }
+
+    /// Return whether output id belongs to the group namespace.
+    fn can_handle_output_id(&self, output_id: &str) -> bool {
+        output_id.starts_with("group:")
+    }
+
+    /// Return whether provider id matches the group provider id.
+    fn can_handle_provider_id(&self, _state: &AppState, provider_id: &str) -> bool {
+        provider_id == Self::provider_id()
+    }
+
+    /// Group provider does not inject synthetic active outputs.
+    fn inject_active_output_if_missing(
+        &self,
+        _state: &AppState,
+        _outputs: &mut Vec<OutputInfo>,
+        _active_output_id: &str,
+    ) {
+    }
+
+    async fn ensure_active_connected(&self, state: &AppState) -> Result<(), ProviderError> {
+        let active_id = Self::active_output_id(state)
+            .ok_or_else(|| ProviderError::Unavailable("no active output selected".to_string()))?;
+        let group = Self::group(state, &active_id)?;
+        if Self::any_member_online(state, &group) {
+            Ok(())
+        } else {
+            Err(ProviderError::Unavailable(
+                "group members offline".to_string(),
+            ))
+        }
+    }
+
+    async fn select_output(&self, state: &AppState, output_id: &str) -> Result<(), ProviderError> {
+        let cmd_tx = Self::ensure_worker_for_output(state, output_id)?;
+        let has_session_owner = crate::session_registry::output_lock_owner(output_id).is_some();
+
+        {
+            let player = state.providers.bridge.player.lock().unwrap();
+            if !player.cmd_tx.same_channel(&cmd_tx) {
+                // A bridge keeps playing after its worker quits.
+                let _ = player.cmd_tx.send(BridgeCommand::StopSilent);
+                let _ = player.cmd_tx.send(BridgeCommand::Quit);
+            }
+        }
+        let resume_info = if has_session_owner {
+            None
+        } else {
+            let status = state.playback.manager.status().inner().lock().unwrap();
+            Some((status.now_playing.clone(), status.elapsed_ms, status.paused))
+        };
+        {
+            let mut player = state.providers.bridge.player.lock().unwrap();
+            player.cmd_tx = cmd_tx.clone();
+        }
+        {
+            let mut bridges = state.providers.bridge.bridges.lock().unwrap();
+            bridges.active_output_id = Some(output_id.to_string());
+            bridges.active_bridge_id = None;
+        }
+
+        if let Some((Some(path), Some(elapsed_ms), paused)) = resume_info {
+            let ext_hint = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or("")
+                .to_ascii_lowercase();
+            let _ = cmd_tx.send(BridgeCommand::Play {
+                path,
+                ext_hint,
+                seek_ms: Some(elapsed_ms),
+                start_paused: paused,
+            });
+        }
+        Ok(())
+    }
+
+    async fn status_for_output(
+        &self,
+        state: &AppState,
+        output_id: &str,
+    ) -> Result<StatusResponse, ProviderError> {
+        let group = Self::group(state, output_id)?;
+        let stored = state
+            .providers
+            .groups
+            .status_by_output
+            .lock()
+            .ok()
+            .and_then(|map| map.get(output_id).cloned());
+        if let Some((mut remote, updated_at)) = stored {
+            if !remote.paused
+                && let Some(base_elapsed) = remote.elapsed_ms
+            {
+                let advanced = base_elapsed.saturating_add(updated_at.elapsed().as_millis() as u64);
+                remote.elapsed_ms = Some(match remote.duration_ms {
+                    Some(duration) => advanced.min(duration),
+                    None => advanced,
+                });
+            }
+            return Ok(status_from_remote(state, output_id, remote));
+        }
+        let online = Self::any_member_online(state, &group);
+        Ok(CastProvider::idle_status(
+            output_id,
+            Some(group.name),
+            online,
+        ))
+    }
+
+    async fn stop_output(&self, state: &AppState, output_id: &str) -> Result<(), ProviderError> {
+        let group = Self::group(state, output_id)?;
+        if let Some(tx) = state
+            .providers
+            .groups
+            .workers
+            .lock()
+            .ok()
+            .and_then(|map| map.get(output_id).cloned())
+        {
+            let _ = tx.send(BridgeCommand::Stop);
+            return Ok(());
+        }
+        let clients = Self::member_clients(state, &group);
+        join_all(clients.iter().map(|client| client.stop())).await;
+        Ok(())
+    }
+
+    async fn volume_for_output(
+        &self,
+        state: &AppState,
+        output_id: &str,
+    ) -> Result<SessionVolumeResponse, ProviderError> {
+        Self::volume_call(
+            state,
+            output_id,
+            |client| async move { client.volume().await },
+        )
+        .await
+    }
+
+    async fn set_volume_for_output(
+        &self,
+        state: &AppState,
+        output_id: &str,
+        value: u8,
+    ) -> Result<SessionVolumeResponse, ProviderError> {
+        Self::volume_call(state, output_id, |client| async move {
+            client.set_volume(value.min(100)).await
+        })
+        .await
+    }
+
+    async fn set_mute_for_output(
+        &self,
+        state: &AppState,
+        output_id: &str,
+        muted: bool,
+    ) -> Result<SessionVolumeResponse, ProviderError> {
+        Self::volume_call(state, output_id, |client| async move {
+            client.set_mute(muted).await
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_output_id_requires_group_prefix() {
+        assert_eq!(
+            GroupProvider::parse_output_id("group:downstairs"),
+            Some("downstairs".to_string())
+        );
+        assert_eq!(GroupProvider::parse_output_id("group:"), None);
+        assert_eq!(GroupProvider::parse_output_id("bridge:a:b"), None);
+    }
+}
@@ -7,7 +7,8 @@ use async_trait::async_trait;
 use audio_player::device;
 
 use crate::models::{
-    OutputCapabilities, OutputInfo, OutputsResponse, ProviderInfo, StatusResponse, SupportedRates,
+    OutputCapabilities, OutputFormats, OutputInfo, OutputsResponse, ProviderInfo, StatusResponse,
+    SupportedRates,
 };
 use crate::output_providers::registry::{OutputProvider, ProviderError};
 use crate::state::AppState;
@@ -145,6 +146,13 @@ impl OutputProvider for LocalProvider {
                     provider_id: Some(Self::provider_id(state)),
                     provider_name: Some(state.providers.local.name.clone()),
                     supported_rates: Some(supported_rates),
+                    formats: OutputFormats::from_lists(
+                        dev.sample_rates,
+                        dev.sample_formats,
+                        dev.channels,
+                    ),
+                    room: None,
+                    zone: None,
                     capabilities: OutputCapabilities {
                         device_select: true,
                         volume: false,
@@ -203,6 +211,9 @@ impl OutputProvider for LocalProvider {
             provider_id: Some(Self::provider_id(state)),
             provider_name: Some(state.providers.local.name.clone()),
             supported_rates,
+            formats: None,
+            room: None,
+            zone: None,
             capabilities: OutputCapabilities {
                 device_select: true,
                 volume: false,
@@ -352,6 +363,15 @@ impl OutputProvider for LocalProvider {
             buffered_frames: status.buffered_frames,
             buffer_capacity_frames: status.buffer_capacity_frames,
             has_previous: status.has_previous,
+            levels: None,
+            bit_perfect: None,
+            dop: None,
+            chapter_index: status
+                .chapters
+                .as_deref()
+                .zip(status.elapsed_ms)
+                .and_then(|(chapters, ms)| audio_bridge_types::chapter_at(chapters, ms)),
+            chapters: status.chapters.clone(),
         };
         drop(status);
         Ok(resp)
@@ -587,6 +607,9 @@ mod tests {
             provider_id: Some("local:local".to_string()),
             provider_name: Some("Local Host".to_string()),
             supported_rates: None,
+            formats: None,
+            room: None,
+            zone: None,
             capabilities: OutputCapabilities {
                 device_select: true,
                 volume: false,
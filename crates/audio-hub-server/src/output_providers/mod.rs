@@ -1,8 +1,13 @@
 //! Output provider implementations and registry wiring.
 //!
-//! Includes bridge-backed and local providers plus the shared registry.
+//! Includes bridge-backed, local, Cast, AirPlay, DLNA, Snapcast and output group providers plus the
+//! shared registry.
 
+pub(crate) mod airplay_provider;
 pub(crate) mod bridge_provider;
 pub(crate) mod cast_provider;
+pub(crate) mod dlna_provider;
+pub(crate) mod group_provider;
 pub(crate) mod local_provider;
 pub(crate) mod registry;
+pub(crate) mod snapcast_provider;
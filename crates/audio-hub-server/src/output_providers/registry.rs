@@ -8,9 +8,13 @@ use async_trait::async_trait;
 use crate::models::{
     OutputInfo, OutputsResponse, ProvidersResponse, SessionVolumeResponse, StatusResponse,
 };
+use crate::output_providers::airplay_provider::AirplayProvider;
 use crate::output_providers::bridge_provider::BridgeProvider;
 use crate::output_providers::cast_provider::CastProvider;
+use crate::output_providers::dlna_provider::DlnaProvider;
+use crate::output_providers::group_provider::GroupProvider;
 use crate::output_providers::local_provider::LocalProvider;
+use crate::output_providers::snapcast_provider::SnapcastProvider;
 use crate::state::AppState;
 use tracing::warn;
 
@@ -131,6 +135,10 @@ impl OutputRegistry {
             Box::new(BridgeProvider),
             Box::new(LocalProvider),
             Box::new(CastProvider),
+            Box::new(AirplayProvider),
+            Box::new(DlnaProvider),
+            Box::new(SnapcastProvider),
+            Box::new(GroupProvider),
         ])
     }
 
@@ -379,7 +387,7 @@ fn is_output_disabled(state: &AppState, output_id: &str) -> bool {
     state
         .output_settings
         .lock()
-        .map(|s| s.disabled.contains(output_id))
+        .map(|s| s.is_disabled(output_id))
         .unwrap_or(false)
 }
 
@@ -565,6 +573,29 @@ mod tests {
         assert!(*inject_flag.lock().unwrap());
     }
 
+    #[test]
+    fn hidden_outputs_are_not_listed_or_selectable() {
+        let active = "bridge:test:hdmi".to_string();
+        let state = make_state(Some(active.clone()));
+        state
+            .output_settings
+            .lock()
+            .unwrap()
+            .set_disabled(&active, true);
+        let registry =
+            OutputRegistry::new(vec![Box::new(MockProvider::new(&active, "bridge", true))]);
+
+        let (listed, selected) = actix_web::rt::System::new().block_on(async {
+            (
+                registry.list_outputs(&state).await,
+                registry.select_output(&state, &active).await,
+            )
+        });
+        assert!(listed.active_id.is_none());
+        assert!(listed.outputs.iter().all(|o| o.id != active));
+        assert!(matches!(selected, Err(ProviderError::BadRequest(_))));
+    }
+
     #[test]
     fn ensure_active_connected_fails_without_active() {
         let state = make_state(None);
@@ -0,0 +1,479 @@
+//! Snapcast output provider.
+//!
+//! Snapserver groups and clients are outputs; all of them play the hub's one Snapcast stream
+//! (see [`crate::snapcast`]).
+
+use actix_web::web;
+use async_trait::async_trait;
+use crossbeam_channel::Sender;
+
+use crate::bridge::BridgeCommand;
+use crate::models::{
+    OutputCapabilities, OutputInfo, OutputsResponse, ProviderInfo, SessionVolumeResponse,
+    StatusResponse,
+};
+use crate::output_providers::cast_provider::{CastProvider, status_from_remote};
+use crate::output_providers::registry::{OutputProvider, ProviderError};
+use crate::snapcast::{
+    SnapcastGroup, SnapcastRpc, SnapcastSettings, SnapcastWorkerContext, spawn_snapcast_worker,
+};
+use crate::state::AppState;
+
+/// Snapcast output target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum SnapcastTarget {
+    /// A whole group (`snapcast:group:<id>`).
+    Group(String),
+    /// One client (`snapcast:client:<id>`).
+    Client(String),
+}
+
+/// Output provider for Snapcast groups and clients.
+pub(crate) struct SnapcastProvider;
+
+impl SnapcastProvider {
+    /// Static provider id used for provider listings and routing.
+    fn provider_id() -> &'static str {
+        "snapcast"
+    }
+
+    /// Parse `snapcast:group:<id>` / `snapcast:client:<id>`.
+    pub(crate) fn parse_output_id(output_id: &str) -> Option<SnapcastTarget> {
+        let rest = output_id.strip_prefix("snapcast:")?;
+        let (kind, id) = rest.split_once(':')?;
+        if id.is_empty() {
+            return None;
+        }
+        match kind {
+            "group" => Some(SnapcastTarget::Group(id.to_string())),
+            "client" => Some(SnapcastTarget::Client(id.to_string())),
+            _ => None,
+        }
+    }
+
+    /// Configured settings, or `Unavailable` when `[snapcast]` is absent.
+    fn settings(state: &AppState) -> Result<SnapcastSettings, ProviderError> {
+        state
+            .providers
+            .snapcast
+            .settings
+            .get()
+            .cloned()
+            .ok_or_else(|| ProviderError::Unavailable("snapcast not configured".to_string()))
+    }
+
+    /// Group holding the target, from the last poll.
+    fn group_for(
+        groups: &[SnapcastGroup],
+        target: &SnapcastTarget,
+    ) -> Result<SnapcastGroup, ProviderError> {
+        groups
+            .iter()
+            .find(|group| match target {
+                SnapcastTarget::Group(id) => &group.id == id,
+                SnapcastTarget::Client(id) => group.clients.iter().any(|c| &c.id == id),
+            })
+            .cloned()
+            .ok_or_else(|| ProviderError::Unavailable("snapcast output offline".to_string()))
+    }
+
+    /// Resolve an output id to its target and current group.
+    fn resolve(
+        state: &AppState,
+        output_id: &str,
+    ) -> Result<(SnapcastTarget, SnapcastGroup), ProviderError> {
+        let Some(target) = Self::parse_output_id(output_id) else {
+            return Err(ProviderError::BadRequest("invalid output id".to_string()));
+        };
+        Self::settings(state)?;
+        let groups = state.providers.snapcast.groups.lock().unwrap();
+        let group = Self::group_for(&groups, &target)?;
+        Ok((target, group))
+    }
+
+    /// Ensure the feeder exists, point the output's group at the hub stream, and return the
+    /// feeder's command sender.
+    pub(crate) fn ensure_worker_for_output(
+        state: &AppState,
+        output_id: &str,
+    ) -> Result<Sender<BridgeCommand>, ProviderError> {
+        let (_, group) = Self::resolve(state, output_id)?;
+        let settings = Self::settings(state)?;
+        let provider = &state.providers.snapcast;
+        if let Ok(mut current) = provider.current_output.lock() {
+            *current = Some(output_id.to_string());
+        }
+        if group.stream_id != settings.stream_id {
+            let settings = settings.clone();
+            std::thread::spawn(move || {
+                let result = SnapcastRpc::connect(&settings)
+                    .and_then(|mut rpc| rpc.set_group_stream(&group.id, &settings.stream_id));
+                if let Err(err) = result {
+                    tracing::warn!(error = %format!("{err:#}"), group = %group.id, "snapcast: set group stream failed");
+                }
+            });
+        }
+        let mut worker = provider.worker.lock().unwrap();
+        if let Some(existing) = worker.as_ref() {
+            return Ok(existing.clone());
+        }
+        let (cmd_tx, cmd_rx) = crossbeam_channel::unbounded();
+        spawn_snapcast_worker(
+            cmd_rx,
+            cmd_tx.clone(),
+            SnapcastWorkerContext {
+                settings,
+                provider: provider.clone(),
+                status: state.playback.manager.status().clone(),
+                queue: state.playback.manager.queue_service().queue().clone(),
+                events: state.events.clone(),
+                metadata: Some(state.metadata.db.clone()),
+                bridge_state: state.providers.bridge.bridges.clone(),
+            },
+        );
+        *worker = Some(cmd_tx.clone());
+        Ok(cmd_tx)
+    }
+
+    /// Return globally active output id from bridge state.
+    fn active_output_id(state: &AppState) -> Option<String> {
+        state
+            .providers
+            .bridge
+            .bridges
+            .lock()
+            .unwrap()
+            .active_output_id
+            .clone()
+    }
+
+    /// Map one output into the listing payload.
+    fn output_info(
+        id: String,
+        name: String,
+        online: bool,
+        active_id: &Option<String>,
+    ) -> OutputInfo {
+        let state = if active_id.as_deref() == Some(&id) {
+            "active"
+        } else if online {
+            "online"
+        } else {
+            "offline"
+        };
+        OutputInfo {
+            id,
+            kind: "snapcast".to_string(),
+            name,
+            state: state.to_string(),
+            provider_id: Some(Self::provider_id().to_string()),
+            provider_name: Some("Snapcast".to_string()),
+            supported_rates: None,
+            formats: None,
+            room: None,
+            zone: None,
+            capabilities: OutputCapabilities {
+                device_select: false,
+                volume: true,
+            },
+        }
+    }
+
+    /// Volume of a target within its group: the client's own, or the group's mean.
+    fn volume_of(target: &SnapcastTarget, group: &SnapcastGroup) -> SessionVolumeResponse {
+        let (value, muted) = match target {
+            SnapcastTarget::Client(id) => group
+                .clients
+                .iter()
+                .find(|c| &c.id == id)
+                .map(|c| (c.volume, c.muted))
+                .unwrap_or((100, false)),
+            SnapcastTarget::Group(_) => {
+                let total: u32 = group.clients.iter().map(|c| u32::from(c.volume)).sum();
+                let count = group.clients.len().max(1) as u32;
+                ((total / count) as u8, group.muted)
+            }
+        };
+        SessionVolumeResponse {
+            value,
+            muted,
+            source: "snapcast".to_string(),
+            available: true,
+        }
+    }
+
+    /// Run a blocking control call for the output, then refresh groups and return its volume.
+    async fn control_call(
+        state: &AppState,
+        output_id: &str,
+        call: impl FnOnce(&mut SnapcastRpc, &SnapcastTarget, &SnapcastGroup) -> anyhow::Result<()>
+        + Send
+        + 'static,
+    ) -> Result<SessionVolumeResponse, ProviderError> {
+        let (target, group) = Self::resolve(state, output_id)?;
+        let settings = Self::settings(state)?;
+        let (target, groups) = web::block(move || {
+            let mut rpc = SnapcastRpc::connect(&settings)?;
+            call(&mut rpc, &target, &group)?;
+            Ok::<_, anyhow::Error>((target, rpc.groups()?))
+        })
+        .await
+        .map_err(|e| ProviderError::Internal(e.to_string()))?
+        .map_err(|e| ProviderError::Internal(format!("{e:#}")))?;
+        let group = Self::group_for(&groups, &target)?;
+        if let Ok(mut current) = state.providers.snapcast.groups.lock() {
+            *current = groups;
+        }
+        Ok(Self::volume_of(&target, &group))
+    }
+}
+
+#[async_trait]
+impl OutputProvider for SnapcastProvider {
+    /// List the Snapcast provider when configured.
+    fn list_providers(&self, state: &AppState) -> Vec<ProviderInfo> {
+        if Self::settings(state).is_err() {
+            return Vec::new();
+        }
+        vec![ProviderInfo {
+            id: Self::provider_id().to_string(),
+            kind: "snapcast".to_string(),
+            name: "Snapcast".to_string(),
+            state: "available".to_string(),
+            capabilities: OutputCapabilities {
+                device_select: false,
+                volume: true,
+            },
+        }]
+    }
+
+    async fn outputs_for_provider(
+        &self,
+        state: &AppState,
+        provider_id: &str,
+    ) -> Result<OutputsResponse, ProviderError> {
+        if provider_id != Self::provider_id() {
+            return Err(ProviderError::BadRequest("unknown provider id".to_string()));
+        }
+        let outputs = self.list_outputs(state).await;
+        let active_id = Self::active_output_id(state).filter(|id| id.starts_with("snapcast:"));
+        Ok(OutputsResponse { active_id, outputs })
+    }
+
+    async fn list_outputs(&self, state: &AppState) -> Vec<OutputInfo> {
+        if Self::settings(state).is_err() {
+            return Vec::new();
+        }
+        let active_id = Self::active_output_id(state);
+        let groups = state.providers.snapcast.groups.lock().unwrap().clone();
+        let mut outputs = Vec::new();
+        for group in groups {
+            let name = if group.name.is_empty() {
+                let names: Vec<&str> = group.clients.iter().map(|c| c.name.as_str()).collect();
+                format!("Snapcast group ({})", names.join(", "))
+            } else {
+                format!("Snapcast group {}", group.name)
+            };
+            let online = group.clients.iter().any(|c| c.connected);
+            outputs.push(Self::output_info(
+                format!("snapcast:group:{}", group.id),
+                name,
+                online,
+                &active_id,
+            ));
+            for client in &group.clients {
+                outputs.push(Self::output_info(
+                    format!("snapcast:client:{}", client.id),
+                    format!("{} (Snapcast)", client.name),
+                    client.connected,
+                    &active_id,
+                ));
+            }
+        }
+        outputs
+    }
+
+    /// Return whether output id belongs to the Snapcast namespace.
+    fn can_handle_output_id(&self, output_id: &str) -> bool {
+        output_id.starts_with("snapcast:")
+    }
+
+    /// Return whether provider id matches the Snapcast provider id.
+    fn can_handle_provider_id(&self, _state: &AppState, provider_id: &str) -> bool {
+        provider_id == Self::provider_id()
+    }
+
+    /// Snapcast provider does not inject synthetic active outputs.
+    fn inject_active_output_if_missing(
+        &self,
+        _state: &AppState,
+        _outputs: &mut Vec<OutputInfo>,
+        _active_output_id: &str,
+    ) {
+    }
+
+    async fn ensure_active_connected(&self, state: &AppState) -> Result<(), ProviderError> {
+        let active_id = Self::active_output_id(state)
+            .ok_or_else(|| ProviderError::Unavailable("no active output selected".to_string()))?;
+        Self::resolve(state, &active_id).map(|_| ())
+    }
+
+    async fn select_output(&self, state: &AppState, output_id: &str) -> Result<(), ProviderError> {
+        let cmd_tx = Self::ensure_worker_for_output(state, output_id)?;
+        let has_session_owner = crate::session_registry::output_lock_owner(output_id).is_some();
+
+        {
+            let player = state.providers.bridge.player.lock().unwrap();
+            if !player.cmd_tx.same_channel(&cmd_tx) {
+                let _ = player.cmd_tx.send(BridgeCommand::Quit);
+            }
+        }
+        let resume_info = if has_session_owner {
+            None
+        } else {
+            let status = state.playback.manager.status().inner().lock().unwrap();
+            Some((status.now_playing.clone(), status.elapsed_ms, status.paused))
+        };
+        {
+            let mut player = state.providers.bridge.player.lock().unwrap();
+            player.cmd_tx = cmd_tx.clone();
+        }
+        {
+            let mut bridges = state.providers.bridge.bridges.lock().unwrap();
+            bridges.active_output_id = Some(output_id.to_string());
+            bridges.active_bridge_id = None;
+        }
+
+        if let Some((Some(path), Some(elapsed_ms), paused)) = resume_info {
+            let ext_hint = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or("")
+                .to_ascii_lowercase();
+            let _ = cmd_tx.send(BridgeCommand::Play {
+                path,
+                ext_hint,
+                seek_ms: Some(elapsed_ms),
+                start_paused: paused,
+            });
+        }
+        Ok(())
+    }
+
+    async fn status_for_output(
+        &self,
+        state: &AppState,
+        output_id: &str,
+    ) -> Result<StatusResponse, ProviderError> {
+        let (_, group) = match Self::resolve(state, output_id) {
+            Ok(found) => found,
+            Err(ProviderError::Unavailable(_)) => {
+                return Ok(CastProvider::idle_status(output_id, None, false));
+            }
+            Err(err) => return Err(err),
+        };
+        let settings = Self::settings(state)?;
+        let provider = &state.providers.snapcast;
+        let playing_here = group.stream_id == settings.stream_id
+            || provider.current_output.lock().unwrap().as_deref() == Some(output_id);
+        let feeder_status = provider.status.lock().unwrap().clone();
+        if playing_here && let Some((mut remote, updated_at)) = feeder_status {
+            if !remote.paused
+                && let Some(base_elapsed) = remote.elapsed_ms
+            {
+                let advanced = base_elapsed.saturating_add(updated_at.elapsed().as_millis() as u64);
+                remote.elapsed_ms = Some(match remote.duration_ms {
+                    Some(duration) => advanced.min(duration),
+                    None => advanced,
+                });
+            }
+            return Ok(status_from_remote(state, output_id, remote));
+        }
+        Ok(CastProvider::idle_status(
+            output_id,
+            Some("Snapcast".to_string()),
+            true,
+        ))
+    }
+
+    async fn stop_output(&self, state: &AppState, output_id: &str) -> Result<(), ProviderError> {
+        if Self::parse_output_id(output_id).is_none() {
+            return Err(ProviderError::BadRequest("invalid output id".to_string()));
+        }
+        if let Some(tx) = state.providers.snapcast.worker.lock().unwrap().clone() {
+            let _ = tx.send(BridgeCommand::Stop);
+            return Ok(());
+        }
+        if let Ok(player) = state.providers.bridge.player.lock() {
+            let _ = player.cmd_tx.send(BridgeCommand::Stop);
+        }
+        Ok(())
+    }
+
+    async fn volume_for_output(
+        &self,
+        state: &AppState,
+        output_id: &str,
+    ) -> Result<SessionVolumeResponse, ProviderError> {
+        let (target, group) = Self::resolve(state, output_id)?;
+        Ok(Self::volume_of(&target, &group))
+    }
+
+    async fn set_volume_for_output(
+        &self,
+        state: &AppState,
+        output_id: &str,
+        value: u8,
+    ) -> Result<SessionVolumeResponse, ProviderError> {
+        Self::control_call(state, output_id, move |rpc, target, group| {
+            for client in &group.clients {
+                if matches!(target, SnapcastTarget::Client(id) if id != &client.id) {
+                    continue;
+                }
+                rpc.set_client_volume(&client.id, value, client.muted)?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn set_mute_for_output(
+        &self,
+        state: &AppState,
+        output_id: &str,
+        muted: bool,
+    ) -> Result<SessionVolumeResponse, ProviderError> {
+        Self::control_call(state, output_id, move |rpc, target, group| match target {
+            SnapcastTarget::Group(id) => rpc.set_group_mute(id, muted),
+            SnapcastTarget::Client(id) => {
+                let volume = group
+                    .clients
+                    .iter()
+                    .find(|c| &c.id == id)
+                    .map_or(100, |c| c.volume);
+                rpc.set_client_volume(id, volume, muted)
+            }
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_output_id_distinguishes_groups_and_clients() {
+        assert_eq!(
+            SnapcastProvider::parse_output_id("snapcast:group:g1"),
+            Some(SnapcastTarget::Group("g1".to_string()))
+        );
+        assert_eq!(
+            SnapcastProvider::parse_output_id("snapcast:client:aa:bb:cc"),
+            Some(SnapcastTarget::Client("aa:bb:cc".to_string()))
+        );
+        assert_eq!(SnapcastProvider::parse_output_id("snapcast:stream:x"), None);
+        assert_eq!(SnapcastProvider::parse_output_id("snapcast:group:"), None);
+        assert_eq!(SnapcastProvider::parse_output_id("dlna:group:g1"), None);
+    }
+}
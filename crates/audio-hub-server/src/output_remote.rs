@@ -0,0 +1,165 @@
+//! Remote control of outputs for integrations (MQTT, Home Assistant).
+//!
+//! Integrations address outputs rather than sessions; commands are applied to the session that
+//! currently holds the output, the same way the session endpoints would.
+
+use actix_web::web;
+
+use crate::models::{SessionVolumeResponse, StatusResponse};
+use crate::session_playback_manager::SessionPlaybackError;
+use crate::state::AppState;
+
+/// Control request addressed to an output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum RemoteCommand {
+    /// Resume when paused.
+    Play,
+    /// Pause when playing.
+    Pause,
+    Toggle,
+    Stop,
+    Next,
+    Previous,
+    /// Volume percent (0-100).
+    Volume(u8),
+    Mute(bool),
+}
+
+/// Why a remote command could not be applied.
+#[derive(Debug)]
+pub(crate) enum RemoteError {
+    /// No session holds the output.
+    NotHeld,
+    /// Next/previous on a session whose client plays the audio itself.
+    LocalSession,
+    /// The queue has no next/previous track (or its file is gone).
+    NothingToPlay,
+    Playback(SessionPlaybackError),
+}
+
+impl RemoteCommand {
+    /// Action name used in the audit log.
+    pub fn action(self) -> &'static str {
+        match self {
+            Self::Play => "play",
+            Self::Pause => "pause",
+            Self::Toggle => "pause_toggle",
+            Self::Stop => "stop",
+            Self::Next => "next",
+            Self::Previous => "previous",
+            Self::Volume(_) => "volume",
+            Self::Mute(_) => "mute",
+        }
+    }
+}
+
+impl From<SessionPlaybackError> for RemoteError {
+    fn from(err: SessionPlaybackError) -> Self {
+        Self::Playback(err)
+    }
+}
+
+/// What an output is doing right now.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OutputSnapshot {
+    /// Session holding the output.
+    pub session_id: Option<String>,
+    pub status: Option<StatusResponse>,
+    pub volume: Option<SessionVolumeResponse>,
+}
+
+impl OutputSnapshot {
+    /// `playing`, `paused` or `idle`.
+    pub fn playback_state(&self) -> &'static str {
+        match self.status.as_ref() {
+            Some(status) if status.now_playing_track_id.is_some() && status.paused => "paused",
+            Some(status) if status.now_playing_track_id.is_some() => "playing",
+            _ => "idle",
+        }
+    }
+
+    /// Volume state when the output supports volume control.
+    pub fn volume(&self) -> Option<&SessionVolumeResponse> {
+        self.volume.as_ref().filter(|volume| volume.available)
+    }
+}
+
+/// Read the status and volume of `output_id` from the session holding it.
+pub(crate) async fn snapshot(state: &web::Data<AppState>, output_id: &str) -> OutputSnapshot {
+    let Some(session_id) = crate::session_registry::output_lock_owner(output_id) else {
+        return OutputSnapshot::default();
+    };
+    let playback = &state.output.session_playback;
+    OutputSnapshot {
+        status: playback.status(state, &session_id).await.ok(),
+        volume: playback.volume(state, &session_id).await.ok(),
+        session_id: Some(session_id),
+    }
+}
+
+/// Apply `command` to the session holding `output_id`; returns that session id.
+pub(crate) async fn apply(
+    state: &web::Data<AppState>,
+    output_id: &str,
+    command: RemoteCommand,
+) -> Result<String, RemoteError> {
+    let session_id =
+        crate::session_registry::output_lock_owner(output_id).ok_or(RemoteError::NotHeld)?;
+    let playback = &state.output.session_playback;
+    match command {
+        RemoteCommand::Play | RemoteCommand::Pause => {
+            let paused = playback
+                .status(state, &session_id)
+                .await
+                .map(|status| status.paused)
+                .unwrap_or(false);
+            if paused == (command == RemoteCommand::Play) {
+                playback.pause_toggle(state, &session_id).await?;
+            }
+        }
+        RemoteCommand::Toggle => playback.pause_toggle(state, &session_id).await?,
+        RemoteCommand::Stop => playback.stop(state, &session_id).await?,
+        RemoteCommand::Volume(value) => {
+            playback
+                .set_volume(state, &session_id, value.min(100))
+                .await?;
+        }
+        RemoteCommand::Mute(muted) => {
+            playback.set_mute(state, &session_id, muted).await?;
+        }
+        RemoteCommand::Next | RemoteCommand::Previous => {
+            skip(state, &session_id, command == RemoteCommand::Next).await?;
+        }
+    }
+    state.events.status_changed();
+    tracing::info!(output_id = %output_id, session_id = %session_id, ?command, "remote command applied");
+    Ok(session_id)
+}
+
+/// Play the next or previous queue entry of a hub-driven session.
+async fn skip(
+    state: &web::Data<AppState>,
+    session_id: &str,
+    forward: bool,
+) -> Result<(), RemoteError> {
+    if crate::api::sessions::is_local_session(session_id) {
+        return Err(RemoteError::LocalSession);
+    }
+    let track_id = if forward {
+        crate::session_registry::queue_next_track_id(session_id)
+    } else {
+        crate::session_registry::queue_previous_track_id(session_id)
+    };
+    let path = track_id
+        .ok()
+        .flatten()
+        .and_then(|track_id| crate::api::sessions::canonical_track_path_by_id(state, track_id))
+        .ok_or(RemoteError::NothingToPlay)?;
+    state.events.queue_changed();
+    state
+        .output
+        .session_playback
+        .play_path(state, session_id, path)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,163 @@
+//! Hub-side decode of a library track to 16-bit stereo PCM.
+//!
+//! Outputs that take raw audio from the hub (AirPlay, Snapcast) decode with audio-player,
+//! resample to their fixed rate, and pull fixed-size chunks as the network allows.
+
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use audio_player::decode;
+use audio_player::dsd;
+use audio_player::queue::{PopStrategy, SharedAudio};
+use audio_player::resample::{self, ResampleConfig};
+use symphonia::core::probe::Hint;
+
+/// Decode and resample buffer.
+const BUFFER_SECONDS: f32 = 2.0;
+
+/// A track being decoded for a raw-PCM output.
+pub(crate) struct PcmTrack {
+    pub(crate) path: PathBuf,
+    /// Decoder output, closed on drop so the decoder thread exits.
+    decoded: Arc<SharedAudio>,
+    /// Audio at [`Self::rate`] (the decoder output when no resampling is needed).
+    queue: Arc<SharedAudio>,
+    channels: usize,
+    /// Output sample rate.
+    pub(crate) rate: u32,
+    pub(crate) duration_ms: Option<u64>,
+    pub(crate) codec: Option<String>,
+    pub(crate) source_rate: u32,
+    /// Position the decode started at.
+    pub(crate) start_ms: u64,
+    /// Frames of this track handed to the output.
+    pub(crate) frames_sent: u64,
+    /// Samples converted but not yet taken (less than a chunk).
+    pending: Vec<i16>,
+    pub(crate) paused: bool,
+    /// All audio has been taken.
+    pub(crate) drained: bool,
+}
+
+impl PcmTrack {
+    /// Open `path` at `start_ms` and convert it to `rate`.
+    pub(crate) fn open(path: PathBuf, start_ms: u64, rate: u32) -> Result<Self> {
+        let file = File::open(&path).with_context(|| format!("open {}", path.display()))?;
+        let mut hint = Hint::new();
+        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+            hint.with_extension(ext);
+        }
+        let (spec, decoded, duration_ms, info) =
+            decode::start_streaming_decode_from_media_source_at(
+                Box::new(file),
+                hint,
+                BUFFER_SECONDS,
+                (start_ms > 0).then_some(start_ms),
+            )?;
+        let (spec, decoded) = dsd::ensure_pcm(spec, decoded, &info, BUFFER_SECONDS);
+        let queue = if spec.rate == rate {
+            decoded.clone()
+        } else {
+            resample::start_resampler(
+                decoded.clone(),
+                spec,
+                rate,
+                ResampleConfig {
+                    chunk_frames: 1024,
+                    buffer_seconds: BUFFER_SECONDS,
+                    quality: Default::default(),
+                    backend: Default::default(),
+                },
+            )?
+        };
+        Ok(Self {
+            path,
+            decoded,
+            queue,
+            channels: spec.channels.count(),
+            rate,
+            duration_ms,
+            codec: info.codec,
+            source_rate: spec.rate,
+            start_ms,
+            frames_sent: 0,
+            pending: Vec::new(),
+            paused: false,
+            drained: false,
+        })
+    }
+
+    /// Position the listener hears, given how many frames the output buffers.
+    pub(crate) fn elapsed_ms(&self, latency_frames: u32) -> u64 {
+        let heard = self.frames_sent.saturating_sub(u64::from(latency_frames));
+        let elapsed = self.start_ms + heard * 1000 / u64::from(self.rate);
+        self.duration_ms.map_or(elapsed, |d| elapsed.min(d))
+    }
+
+    /// Take `frames` frames of interleaved stereo samples; `None` when the decode has nothing
+    /// ready. The last chunk of a track may be shorter.
+    pub(crate) fn next_chunk(&mut self, frames: usize) -> Option<Vec<i16>> {
+        let wanted = frames * 2;
+        let mut buf = vec![0f32; frames * self.channels.max(1)];
+        while self.pending.len() < wanted {
+            let missing = (wanted - self.pending.len()) / 2;
+            let strategy = PopStrategy::NonBlocking {
+                max_frames: missing,
+            };
+            match self
+                .queue
+                .copy_to_slice(strategy, &mut buf[..missing * self.channels])
+            {
+                Some(samples) if samples > 0 => {
+                    to_stereo_i16(&buf[..samples], self.channels, &mut self.pending);
+                }
+                _ => break,
+            }
+        }
+        if self.pending.len() >= wanted {
+            return Some(self.pending.drain(..wanted).collect());
+        }
+        if self.queue.is_done() && self.queue.len_frames() == 0 {
+            self.drained = true;
+            if !self.pending.is_empty() {
+                return Some(std::mem::take(&mut self.pending));
+            }
+        }
+        None
+    }
+}
+
+impl Drop for PcmTrack {
+    fn drop(&mut self) {
+        self.queue.close();
+        self.decoded.close();
+    }
+}
+
+/// Convert interleaved `f32` samples with `channels` channels to 16-bit stereo.
+fn to_stereo_i16(samples: &[f32], channels: usize, out: &mut Vec<i16>) {
+    let convert = |s: f32| (s.clamp(-1.0, 1.0) * f32::from(i16::MAX)).round() as i16;
+    for frame in samples.chunks_exact(channels.max(1)) {
+        let left = frame[0];
+        let right = if channels > 1 { frame[1] } else { left };
+        out.push(convert(left));
+        out.push(convert(right));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_stereo_i16_duplicates_mono_and_drops_extra_channels() {
+        let mut out = Vec::new();
+        to_stereo_i16(&[1.0, -1.0], 1, &mut out);
+        assert_eq!(out, vec![i16::MAX, i16::MAX, -i16::MAX, -i16::MAX]);
+        out.clear();
+        to_stereo_i16(&[0.5, -0.5, 0.9, 2.0, 0.0, 0.0], 3, &mut out);
+        assert_eq!(out, vec![16384, -16384, i16::MAX, 0]);
+    }
+}
@@ -0,0 +1,283 @@
+//! Listening history.
+//!
+//! Follows what each session is playing and records a history row once a track finishes,
+//! is skipped or its session goes away. The session registry reports `now_playing`
+//! changes; status reads report the playback position, which is extrapolated between
+//! reads (wall-clock time since the start is used when no position was ever seen).
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::clock::now_ms;
+use crate::metadata_db::{MetadataDb, PlayRecord};
+
+/// Finished plays kept in memory until the writer loop stores them.
+const MAX_PENDING: usize = 1000;
+/// How often the writer loop stores finished plays.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Last position reported for the current track.
+struct Position {
+    elapsed_ms: u64,
+    duration_ms: Option<u64>,
+    paused: bool,
+    seen: Instant,
+}
+
+/// Track a session is currently playing.
+struct Current {
+    track_id: i64,
+    output_id: Option<String>,
+    started_at_ms: i64,
+    started: Instant,
+    position: Option<Position>,
+}
+
+impl Current {
+    /// Estimate how much of the track has been heard by `now`.
+    fn played_ms(&self, now: Instant) -> u64 {
+        let played = match &self.position {
+            Some(position) if position.paused => position.elapsed_ms,
+            Some(position) => position
+                .elapsed_ms
+                .saturating_add(now.saturating_duration_since(position.seen).as_millis() as u64),
+            None => now.saturating_duration_since(self.started).as_millis() as u64,
+        };
+        match self.position.as_ref().and_then(|p| p.duration_ms) {
+            Some(duration_ms) => played.min(duration_ms),
+            None => played,
+        }
+    }
+}
+
+/// A play that ended and waits to be stored.
+struct FinishedPlay {
+    track_id: i64,
+    session_id: String,
+    output_id: Option<String>,
+    played_at_ms: i64,
+    played_ms: u64,
+    duration_ms: Option<u64>,
+}
+
+#[derive(Default)]
+struct Tracker {
+    by_session: HashMap<String, Current>,
+    finished: Vec<FinishedPlay>,
+}
+
+impl Tracker {
+    /// Finish the session's current play, then start following `track_id` (if any).
+    fn change(
+        &mut self,
+        session_id: &str,
+        output_id: Option<&str>,
+        track_id: Option<i64>,
+        now: Instant,
+        now_ms: i64,
+    ) {
+        self.finish(session_id, now);
+        if let Some(track_id) = track_id {
+            self.by_session.insert(
+                session_id.to_string(),
+                Current {
+                    track_id,
+                    output_id: output_id.map(str::to_string),
+                    started_at_ms: now_ms,
+                    started: now,
+                    position: None,
+                },
+            );
+        }
+    }
+
+    /// Finish the session's current play, if any.
+    fn finish(&mut self, session_id: &str, now: Instant) {
+        let Some(current) = self.by_session.remove(session_id) else {
+            return;
+        };
+        if self.finished.len() >= MAX_PENDING {
+            self.finished.remove(0);
+        }
+        self.finished.push(FinishedPlay {
+            track_id: current.track_id,
+            session_id: session_id.to_string(),
+            output_id: current.output_id.clone(),
+            played_at_ms: current.started_at_ms,
+            played_ms: current.played_ms(now),
+            duration_ms: current.position.as_ref().and_then(|p| p.duration_ms),
+        });
+    }
+
+    /// Record a position report; ignored unless it is about the track being followed.
+    fn observe(
+        &mut self,
+        session_id: &str,
+        track_id: i64,
+        elapsed_ms: u64,
+        duration_ms: Option<u64>,
+        paused: bool,
+        now: Instant,
+    ) {
+        if let Some(current) = self.by_session.get_mut(session_id)
+            && current.track_id == track_id
+        {
+            current.position = Some(Position {
+                elapsed_ms,
+                duration_ms: duration_ms.filter(|ms| *ms > 0),
+                paused,
+                seen: now,
+            });
+        }
+    }
+}
+
+/// Return the global play tracker.
+fn tracker() -> &'static Mutex<Tracker> {
+    static TRACKER: OnceLock<Mutex<Tracker>> = OnceLock::new();
+    TRACKER.get_or_init(|| Mutex::new(Tracker::default()))
+}
+
+/// Note that a session's `now_playing` changed; finishes the previous play.
+pub(crate) fn now_playing_changed(
+    session_id: &str,
+    output_id: Option<&str>,
+    track_id: Option<i64>,
+) {
+    if let Ok(mut tracker) = tracker().lock() {
+        tracker.change(session_id, output_id, track_id, Instant::now(), now_ms());
+    }
+}
+
+/// Note that a session went away; finishes its current play.
+pub(crate) fn session_ended(session_id: &str) {
+    if let Ok(mut tracker) = tracker().lock() {
+        tracker.finish(session_id, Instant::now());
+    }
+}
+
+/// Report the playback position of a session's current track.
+pub(crate) fn observe_position(
+    session_id: &str,
+    track_id: Option<i64>,
+    elapsed_ms: Option<u64>,
+    duration_ms: Option<u64>,
+    paused: bool,
+) {
+    let (Some(track_id), Some(elapsed_ms)) = (track_id, elapsed_ms) else {
+        return;
+    };
+    if let Ok(mut tracker) = tracker().lock() {
+        tracker.observe(
+            session_id,
+            track_id,
+            elapsed_ms,
+            duration_ms,
+            paused,
+            Instant::now(),
+        );
+    }
+}
+
+/// Store finished plays in the background.
+pub fn spawn_play_history_loop(db: MetadataDb) {
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(FLUSH_INTERVAL);
+            let finished = match tracker().lock() {
+                Ok(mut tracker) => std::mem::take(&mut tracker.finished),
+                Err(_) => continue,
+            };
+            for play in finished {
+                let record = play_record(&db, play);
+                if let Err(err) = db.record_play(&record) {
+                    tracing::warn!(
+                        error = %err,
+                        track_id = record.track_id,
+                        "play history store failed"
+                    );
+                }
+            }
+        }
+    });
+}
+
+/// Build the stored row, taking the track length from the DB when playback never reported it.
+fn play_record(db: &MetadataDb, play: FinishedPlay) -> PlayRecord {
+    let duration_ms = play.duration_ms.or_else(|| {
+        db.track_record_by_id(play.track_id)
+            .ok()
+            .flatten()
+            .and_then(|record| record.duration_ms)
+    });
+    PlayRecord {
+        track_id: play.track_id,
+        session_id: play.session_id,
+        output_id: play.output_id,
+        played_at_ms: play.played_at_ms,
+        played_ms: play.played_ms.min(duration_ms.unwrap_or(u64::MAX)) as i64,
+        completed_pct: completed_pct(play.played_ms, duration_ms),
+    }
+}
+
+/// Share of the track heard, in percent (0-100).
+fn completed_pct(played_ms: u64, duration_ms: Option<u64>) -> Option<f64> {
+    let duration_ms = duration_ms.filter(|ms| *ms > 0)?;
+    Some((played_ms as f64 * 100.0 / duration_ms as f64).min(100.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn change_finishes_previous_play_with_extrapolated_position() {
+        let mut tracker = Tracker::default();
+        let t0 = Instant::now();
+        tracker.change("s", Some("out"), Some(1), t0, 1_000);
+        tracker.observe("s", 1, 10_000, Some(60_000), false, t0);
+        // Reports about another track are ignored.
+        tracker.observe("s", 2, 59_000, Some(60_000), false, t0);
+        tracker.change(
+            "s",
+            Some("out"),
+            Some(2),
+            t0 + Duration::from_secs(5),
+            6_000,
+        );
+
+        assert_eq!(tracker.finished.len(), 1);
+        let play = &tracker.finished[0];
+        assert_eq!(play.track_id, 1);
+        assert_eq!(play.played_at_ms, 1_000);
+        assert_eq!(play.played_ms, 15_000);
+        assert_eq!(play.duration_ms, Some(60_000));
+        assert_eq!(play.output_id.as_deref(), Some("out"));
+        assert_eq!(tracker.by_session["s"].track_id, 2);
+    }
+
+    #[test]
+    fn paused_position_does_not_advance_and_is_capped_at_duration() {
+        let t0 = Instant::now();
+        let mut tracker = Tracker::default();
+        tracker.change("s", None, Some(1), t0, 0);
+        tracker.observe("s", 1, 20_000, Some(30_000), true, t0);
+        tracker.finish("s", t0 + Duration::from_secs(60));
+        assert_eq!(tracker.finished[0].played_ms, 20_000);
+
+        tracker.change("s", None, Some(1), t0, 0);
+        tracker.observe("s", 1, 25_000, Some(30_000), false, t0);
+        tracker.finish("s", t0 + Duration::from_secs(60));
+        assert_eq!(tracker.finished[1].played_ms, 30_000);
+        assert!(tracker.by_session.is_empty());
+    }
+
+    #[test]
+    fn completed_pct_needs_a_duration() {
+        assert_eq!(completed_pct(30_000, Some(60_000)), Some(50.0));
+        assert_eq!(completed_pct(90_000, Some(60_000)), Some(100.0));
+        assert_eq!(completed_pct(30_000, None), None);
+        assert_eq!(completed_pct(30_000, Some(0)), None);
+    }
+}
@@ -0,0 +1,394 @@
+//! Per-client request limits for expensive endpoints from `[rate_limits]` config.
+//!
+//! Library rescans, search, transcoding and MusicBrainz lookups each get a token bucket per
+//! client IP. A client over its budget gets `429 Too Many Requests` with `Retry-After`, so one
+//! misbehaving client can neither peg the CPU nor get the hub banned by MusicBrainz. Bridges,
+//! cast devices, DLNA renderers and `exempt_ips` are never limited (they fetch transcodes during
+//! playback).
+
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::{Method, header};
+use actix_web::{Error, HttpResponse, web};
+use anyhow::{Context as AnyhowContext, Result};
+use futures_util::future::{LocalBoxFuture, Ready, ok};
+
+use crate::config::ServerConfig;
+use crate::state::AppState;
+
+/// Group of endpoints sharing one budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LimitClass {
+    /// `POST /library/rescan` and `POST /library/rescan/track`.
+    Rescan,
+    /// `GET /search`.
+    Search,
+    /// `GET /stream/transcode/track/{id}`.
+    Transcode,
+    /// `POST /metadata/match/search` and `POST /metadata/match/apply`.
+    MusicBrainz,
+}
+
+impl LimitClass {
+    /// Label used in logs.
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Rescan => "rescan",
+            Self::Search => "search",
+            Self::Transcode => "transcode",
+            Self::MusicBrainz => "musicbrainz",
+        }
+    }
+}
+
+/// Requests per minute allowed for each class.
+#[derive(Debug, Clone)]
+pub struct RateLimits {
+    /// Limit library rescans.
+    pub rescan_per_minute: u32,
+    /// Limit search requests (search-as-you-type sends one per keystroke).
+    pub search_per_minute: u32,
+    /// Limit transcode streams started.
+    pub transcode_per_minute: u32,
+    /// Limit MusicBrainz-backed requests.
+    pub musicbrainz_per_minute: u32,
+    /// Extra peers never limited.
+    pub exempt_ips: HashSet<IpAddr>,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            rescan_per_minute: 10,
+            search_per_minute: 120,
+            transcode_per_minute: 30,
+            musicbrainz_per_minute: 20,
+            exempt_ips: HashSet::new(),
+        }
+    }
+}
+
+impl RateLimits {
+    /// Budget for `class`.
+    fn per_minute(&self, class: LimitClass) -> u32 {
+        match class {
+            LimitClass::Rescan => self.rescan_per_minute,
+            LimitClass::Search => self.search_per_minute,
+            LimitClass::Transcode => self.transcode_per_minute,
+            LimitClass::MusicBrainz => self.musicbrainz_per_minute,
+        }
+    }
+}
+
+/// Active limits plus one bucket per client and class.
+struct Limiter {
+    limits: RateLimits,
+    buckets: Mutex<HashMap<(IpAddr, LimitClass), Bucket>>,
+}
+
+/// Drop idle buckets once this many are tracked.
+const MAX_TRACKED_BUCKETS: usize = 4096;
+
+fn store() -> &'static Mutex<Option<Arc<Limiter>>> {
+    static STORE: OnceLock<Mutex<Option<Arc<Limiter>>>> = OnceLock::new();
+    STORE.get_or_init(|| Mutex::new(None))
+}
+
+/// Parse `[rate_limits]` (defaults when absent; `None` when `enabled = false`).
+pub fn from_config(cfg: &ServerConfig) -> Result<Option<RateLimits>> {
+    let Some(section) = cfg.rate_limits.as_ref() else {
+        return Ok(Some(RateLimits::default()));
+    };
+    if !section.enabled.unwrap_or(true) {
+        return Ok(None);
+    }
+    let defaults = RateLimits::default();
+    let per_minute = |field: &str, value: Option<u32>, default: u32| match value {
+        Some(0) => Err(anyhow::anyhow!(
+            "rate_limits.{field} must be greater than 0"
+        )),
+        Some(value) => Ok(value),
+        None => Ok(default),
+    };
+    Ok(Some(RateLimits {
+        rescan_per_minute: per_minute(
+            "rescan_per_minute",
+            section.rescan_per_minute,
+            defaults.rescan_per_minute,
+        )?,
+        search_per_minute: per_minute(
+            "search_per_minute",
+            section.search_per_minute,
+            defaults.search_per_minute,
+        )?,
+        transcode_per_minute: per_minute(
+            "transcode_per_minute",
+            section.transcode_per_minute,
+            defaults.transcode_per_minute,
+        )?,
+        musicbrainz_per_minute: per_minute(
+            "musicbrainz_per_minute",
+            section.musicbrainz_per_minute,
+            defaults.musicbrainz_per_minute,
+        )?,
+        exempt_ips: section
+            .exempt_ips
+            .iter()
+            .flatten()
+            .map(|ip| {
+                ip.parse::<IpAddr>()
+                    .with_context(|| format!("parse rate_limits exempt_ip {ip}"))
+            })
+            .collect::<Result<_>>()?,
+    }))
+}
+
+/// Replace the active limits (forgets every client's usage).
+pub fn install(limits: Option<RateLimits>) {
+    let limiter = limits.map(|limits| {
+        Arc::new(Limiter {
+            limits,
+            buckets: Mutex::new(HashMap::new()),
+        })
+    });
+    if let Ok(mut guard) = store().lock() {
+        *guard = limiter;
+    }
+}
+
+/// Endpoint group a request is charged to, if any.
+pub(crate) fn classify(method: &Method, path: &str) -> Option<LimitClass> {
+    match (method.as_str(), path) {
+        ("POST", "/library/rescan" | "/library/rescan/track") => Some(LimitClass::Rescan),
+        ("GET", "/search") => Some(LimitClass::Search),
+        ("GET" | "HEAD", _) if path.starts_with("/stream/transcode/track/") || is_hls(path) => {
+            Some(LimitClass::Transcode)
+        }
+        ("POST", "/metadata/match/search" | "/metadata/match/apply") => {
+            Some(LimitClass::MusicBrainz)
+        }
+        _ => None,
+    }
+}
+
+/// `/stream/track/{id}/hls/...`: HLS playlists and segments, packaged by ffmpeg.
+fn is_hls(path: &str) -> bool {
+    path.strip_prefix("/stream/track/")
+        .and_then(|rest| rest.split_once('/'))
+        .is_some_and(|(_, rest)| rest.starts_with("hls/"))
+}
+
+/// Charge one request of `class` to `peer`; returns the wait before the next one when over budget.
+fn charge(state: Option<&AppState>, peer: IpAddr, class: LimitClass) -> Option<Duration> {
+    let limiter = store().lock().ok().and_then(|guard| guard.clone())?;
+    if limiter.limits.exempt_ips.contains(&peer)
+        || state.is_some_and(|state| crate::stream_limits::is_realtime_peer(state, peer))
+    {
+        return None;
+    }
+    let per_minute = limiter.limits.per_minute(class);
+    let now = Instant::now();
+    let mut buckets = limiter.buckets.lock().ok()?;
+    if buckets.len() >= MAX_TRACKED_BUCKETS {
+        buckets.retain(|_, bucket| !bucket.is_full(now));
+    }
+    buckets
+        .entry((peer, class))
+        .or_insert_with(|| Bucket::new(per_minute, now))
+        .take(now)
+        .err()
+}
+
+/// Token bucket holding up to `capacity` requests, refilled evenly over a minute.
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    tokens: f64,
+    per_sec: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(per_minute: u32, now: Instant) -> Self {
+        let capacity = f64::from(per_minute.max(1));
+        Self {
+            capacity,
+            tokens: capacity,
+            per_sec: capacity / 60.0,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_sec).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// Spend one token, or return how long until one is available.
+    fn take(&mut self, now: Instant) -> std::result::Result<(), Duration> {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.per_sec))
+        }
+    }
+
+    fn is_full(&self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens + elapsed * self.per_sec >= self.capacity
+    }
+}
+
+/// Actix middleware answering `429` once a client exceeds its budget (see [`classify`]).
+pub(crate) struct RateLimit;
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RateLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    /// Build middleware instance around inner service.
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RateLimitMiddleware { service })
+    }
+}
+
+/// Service wrapper that enforces [`RateLimits`].
+pub(crate) struct RateLimitMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    /// Delegate readiness polling to wrapped service.
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    /// Reject the request with 429 when its client is over budget.
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if let Some(class) = classify(req.method(), req.path())
+            && let Some(peer) = req.peer_addr().map(|addr| addr.ip())
+            && let Some(wait) = charge(
+                req.app_data::<web::Data<AppState>>().map(|state| &***state),
+                peer,
+                class,
+            )
+        {
+            let retry_after = wait.as_secs().max(1);
+            tracing::warn!(
+                path = %req.path(),
+                peer = %peer,
+                class = class.as_str(),
+                retry_after_secs = retry_after,
+                reason = "rate_limited",
+                "http request rejected"
+            );
+            let res = HttpResponse::TooManyRequests()
+                .insert_header((header::RETRY_AFTER, retry_after.to_string()))
+                .body(format!("too many {} requests", class.as_str()));
+            return Box::pin(async move { Ok(req.into_response(res).map_into_right_body()) });
+        }
+        let fut = self.service.call(req);
+        Box::pin(async move { Ok(fut.await?.map_into_left_body()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_allows_a_burst_then_refills_evenly() {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(6, now);
+        for _ in 0..6 {
+            assert!(bucket.take(now).is_ok());
+        }
+        let wait = bucket.take(now).unwrap_err();
+        assert_eq!(wait.as_secs(), 10);
+        assert!(!bucket.is_full(now));
+        assert!(bucket.take(now + Duration::from_secs(10)).is_ok());
+        assert!(bucket.take(now + Duration::from_secs(10)).is_err());
+        assert!(bucket.is_full(now + Duration::from_secs(70)));
+    }
+
+    #[test]
+    fn classify_covers_expensive_endpoints_only() {
+        assert_eq!(
+            classify(&Method::POST, "/library/rescan"),
+            Some(LimitClass::Rescan)
+        );
+        assert_eq!(
+            classify(&Method::POST, "/library/rescan/track"),
+            Some(LimitClass::Rescan)
+        );
+        assert_eq!(classify(&Method::GET, "/search"), Some(LimitClass::Search));
+        assert_eq!(
+            classify(&Method::GET, "/stream/transcode/track/7"),
+            Some(LimitClass::Transcode)
+        );
+        assert_eq!(
+            classify(&Method::POST, "/metadata/match/search"),
+            Some(LimitClass::MusicBrainz)
+        );
+        assert_eq!(
+            classify(&Method::GET, "/stream/track/7/hls/playlist.m3u8"),
+            Some(LimitClass::Transcode)
+        );
+        assert_eq!(
+            classify(&Method::GET, "/stream/track/7/hls/seg00003.ts"),
+            Some(LimitClass::Transcode)
+        );
+        assert_eq!(classify(&Method::GET, "/stream/track/7"), None);
+        assert_eq!(classify(&Method::GET, "/library/rescan"), None);
+        assert_eq!(classify(&Method::GET, "/albums"), None);
+    }
+
+    #[test]
+    fn from_config_applies_defaults_and_can_disable() {
+        let cfg: ServerConfig = toml::from_str(
+            r#"
+            [rate_limits]
+            search_per_minute = 30
+            exempt_ips = ["192.168.1.60"]
+            "#,
+        )
+        .expect("parse config");
+        let limits = from_config(&cfg).unwrap().unwrap();
+        assert_eq!(limits.search_per_minute, 30);
+        assert_eq!(limits.rescan_per_minute, 10);
+        assert!(
+            limits
+                .exempt_ips
+                .contains(&"192.168.1.60".parse::<IpAddr>().unwrap())
+        );
+
+        let cfg: ServerConfig = toml::from_str("[rate_limits]\nenabled = false").unwrap();
+        assert!(from_config(&cfg).unwrap().is_none());
+        let cfg: ServerConfig = toml::from_str("[rate_limits]\nrescan_per_minute = 0").unwrap();
+        assert!(from_config(&cfg).is_err());
+    }
+}
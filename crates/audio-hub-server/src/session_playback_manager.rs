@@ -7,12 +7,18 @@ use std::path::{Path, PathBuf};
 use actix_web::HttpResponse;
 use crossbeam_channel::Sender;
 
+use audio_bridge_types::{ChapterRequest, chapter_seek_ms};
+
 use crate::bridge::BridgeCommand;
 use crate::bridge_manager::{merge_bridges, parse_output_id};
 use crate::bridge_transport::BridgeTransportClient;
 use crate::models::QueueMode;
 use crate::output_controller::OutputControllerError;
+use crate::output_providers::airplay_provider::AirplayProvider;
 use crate::output_providers::cast_provider::CastProvider;
+use crate::output_providers::dlna_provider::DlnaProvider;
+use crate::output_providers::group_provider::GroupProvider;
+use crate::output_providers::snapcast_provider::SnapcastProvider;
 use crate::session_registry::BoundOutputError;
 use crate::state::AppState;
 
@@ -52,6 +58,12 @@ pub enum SessionPlaybackError {
         output_id: String,
         reason: String,
     },
+    NoChapters {
+        session_id: String,
+    },
+    ChapterNotFound {
+        session_id: String,
+    },
 }
 
 impl SessionPlaybackError {
@@ -103,10 +115,20 @@ impl SessionPlaybackError {
             } => HttpResponse::ServiceUnavailable().body(format!(
                 "failed to execute session command: session_id={session_id} output_id={output_id} reason={reason}"
             )),
+            SessionPlaybackError::NoChapters { session_id } => HttpResponse::Conflict()
+                .body(format!("current track has no chapters: session_id={session_id}")),
+            SessionPlaybackError::ChapterNotFound { session_id } => HttpResponse::NotFound()
+                .body(format!("no such chapter: session_id={session_id}")),
         }
     }
 }
 
+/// Reason for a failed send to an output worker (`cast_send_failed`, `dlna_send_failed`, ...).
+fn worker_send_failed(output_id: &str, err: impl std::fmt::Display) -> String {
+    let kind = output_id.split(':').next().unwrap_or("output");
+    format!("{kind}_send_failed {err}")
+}
+
 /// Map controller-layer errors into stable reason strings for API responses/logs.
 fn controller_error_reason(err: &OutputControllerError) -> String {
     match err {
@@ -186,12 +208,24 @@ impl SessionPlaybackManager {
         })
     }
 
-    /// Resolve the cast worker sender for a cast output id.
-    fn cast_worker(&self, state: &AppState, output_id: &str) -> Option<Sender<BridgeCommand>> {
-        if !output_id.starts_with("cast:") {
-            return None;
+    /// Resolve the hub-side worker sender for a cast, AirPlay, DLNA, Snapcast or group output id.
+    fn output_worker(&self, state: &AppState, output_id: &str) -> Option<Sender<BridgeCommand>> {
+        if output_id.starts_with("cast:") {
+            return CastProvider::ensure_worker_for_output(state, output_id).ok();
+        }
+        if output_id.starts_with("airplay:") {
+            return AirplayProvider::ensure_worker_for_output(state, output_id).ok();
         }
-        CastProvider::ensure_worker_for_output(state, output_id).ok()
+        if output_id.starts_with("dlna:") {
+            return DlnaProvider::ensure_worker_for_output(state, output_id).ok();
+        }
+        if output_id.starts_with("snapcast:") {
+            return SnapcastProvider::ensure_worker_for_output(state, output_id).ok();
+        }
+        if output_id.starts_with("group:") {
+            return GroupProvider::ensure_worker_for_output(state, output_id).ok();
+        }
+        None
     }
 
     /// Dispatch a file path to a bridge output after selecting the target device.
@@ -226,13 +260,13 @@ impl SessionPlaybackManager {
                 reason: "unknown_device".to_string(),
             });
         }
-        let exclusive = state
+        let options = state
             .output_settings
             .lock()
-            .map(|settings| settings.is_exclusive(&target.output_id))
-            .unwrap_or(false);
+            .map(|settings| settings.select_options(&target.output_id))
+            .unwrap_or_default();
         client
-            .set_device_by_id(&target.device_id, Some(exclusive))
+            .set_device_by_id(&target.device_id, options)
             .await
             .map_err(|err| SessionPlaybackError::SelectFailed {
                 session_id: session_id.to_string(),
@@ -293,7 +327,7 @@ impl SessionPlaybackManager {
         start_paused: bool,
     ) -> Result<String, SessionPlaybackError> {
         let output_id = self.bound_output_id(session_id)?;
-        if let Some(tx) = self.cast_worker(state, &output_id) {
+        if let Some(tx) = self.output_worker(state, &output_id) {
             let ext_hint = path
                 .extension()
                 .and_then(|ext| ext.to_str())
@@ -308,7 +342,7 @@ impl SessionPlaybackManager {
             .map_err(|err| SessionPlaybackError::DispatchFailed {
                 session_id: session_id.to_string(),
                 output_id: output_id.clone(),
-                reason: format!("cast_send_failed {err}"),
+                reason: worker_send_failed(&output_id, err),
             })?;
             state.events.status_changed();
             return Ok(output_id);
@@ -348,10 +382,29 @@ impl SessionPlaybackManager {
     }
 
     /// Read normalized playback status for the session's selected output.
+    ///
+    /// The reported position also feeds the listening history.
     pub async fn status(
         &self,
         state: &AppState,
         session_id: &str,
+    ) -> Result<crate::models::StatusResponse, SessionPlaybackError> {
+        let status = self.read_status(state, session_id).await?;
+        crate::play_history::observe_position(
+            session_id,
+            status.now_playing_track_id,
+            status.elapsed_ms,
+            status.duration_ms,
+            status.paused,
+        );
+        Ok(status)
+    }
+
+    /// Read normalized playback status without side effects.
+    async fn read_status(
+        &self,
+        state: &AppState,
+        session_id: &str,
     ) -> Result<crate::models::StatusResponse, SessionPlaybackError> {
         let output_id = self.bound_output_id(session_id)?;
         if let Some(target) = self.bridge_target(state, &output_id) {
@@ -502,6 +555,11 @@ impl SessionPlaybackManager {
             buffered_frames: status.buffered_frames,
             buffer_capacity_frames: status.buffer_capacity_frames,
             has_previous: session_has_previous,
+            levels: status.levels,
+            bit_perfect: status.bit_perfect,
+            dop: status.dop,
+            chapters: status.chapters,
+            chapter_index: status.chapter_index,
         }
     }
 
@@ -512,12 +570,12 @@ impl SessionPlaybackManager {
         session_id: &str,
     ) -> Result<(), SessionPlaybackError> {
         let output_id = self.bound_output_id(session_id)?;
-        if let Some(tx) = self.cast_worker(state, &output_id) {
+        if let Some(tx) = self.output_worker(state, &output_id) {
             tx.send(BridgeCommand::PauseToggle).map_err(|err| {
                 SessionPlaybackError::CommandFailed {
                     session_id: session_id.to_string(),
                     output_id: output_id.clone(),
-                    reason: format!("cast_send_failed {err}"),
+                    reason: worker_send_failed(&output_id, err),
                 }
             })?;
             state.events.status_changed();
@@ -567,12 +625,12 @@ impl SessionPlaybackManager {
         ms: u64,
     ) -> Result<(), SessionPlaybackError> {
         let output_id = self.bound_output_id(session_id)?;
-        if let Some(tx) = self.cast_worker(state, &output_id) {
+        if let Some(tx) = self.output_worker(state, &output_id) {
             tx.send(BridgeCommand::Seek { ms }).map_err(|err| {
                 SessionPlaybackError::CommandFailed {
                     session_id: session_id.to_string(),
                     output_id: output_id.clone(),
-                    reason: format!("cast_send_failed {err}"),
+                    reason: worker_send_failed(&output_id, err),
                 }
             })?;
             state.events.status_changed();
@@ -614,6 +672,28 @@ impl SessionPlaybackManager {
             })
     }
 
+    /// Seek the session's output to a chapter of the current track.
+    pub async fn chapter(
+        &self,
+        state: &AppState,
+        session_id: &str,
+        req: &ChapterRequest,
+    ) -> Result<(), SessionPlaybackError> {
+        let status = self.status(state, session_id).await?;
+        let Some(chapters) = status.chapters else {
+            return Err(SessionPlaybackError::NoChapters {
+                session_id: session_id.to_string(),
+            });
+        };
+        let ms =
+            chapter_seek_ms(&chapters, status.elapsed_ms.unwrap_or(0), req).ok_or_else(|| {
+                SessionPlaybackError::ChapterNotFound {
+                    session_id: session_id.to_string(),
+                }
+            })?;
+        self.seek(state, session_id, ms).await
+    }
+
     /// Stop playback for the session's selected output.
     pub async fn stop(
         &self,
@@ -621,12 +701,12 @@ impl SessionPlaybackManager {
         session_id: &str,
     ) -> Result<(), SessionPlaybackError> {
         let output_id = self.bound_output_id(session_id)?;
-        if let Some(tx) = self.cast_worker(state, &output_id) {
+        if let Some(tx) = self.output_worker(state, &output_id) {
             tx.send(BridgeCommand::Stop)
                 .map_err(|err| SessionPlaybackError::CommandFailed {
                     session_id: session_id.to_string(),
                     output_id: output_id.clone(),
-                    reason: format!("cast_send_failed {err}"),
+                    reason: worker_send_failed(&output_id, err),
                 })?;
             state.events.status_changed();
             return Ok(());
@@ -790,6 +870,11 @@ impl SessionPlaybackManager {
             buffered_frames: None,
             buffer_capacity_frames: None,
             has_previous: Some(has_previous),
+            levels: None,
+            bit_perfect: None,
+            dop: None,
+            chapters: None,
+            chapter_index: None,
         }
     }
 
@@ -11,6 +11,7 @@ use std::time::{Duration, Instant};
 use uuid::Uuid;
 
 use crate::models::SessionMode;
+use crate::webhooks::{self, WebhookEvent};
 
 const DEFAULT_LEASE_TTL_SEC: u64 = 30;
 
@@ -353,6 +354,7 @@ pub fn queue_clear(session_id: &str, clear_queue: bool, clear_history: bool) ->
 pub fn queue_play_from(session_id: &str, track_id: i64) -> Result<bool, ()> {
     let mut store = store().lock().map_err(|_| ())?;
     let session = store.by_id.get_mut(session_id).ok_or(())?;
+    let before = session.now_playing;
     let found = if let Some(pos) = session.queue_items.iter().position(|id| *id == track_id) {
         if let Some(current) = session.now_playing.take() {
             if session
@@ -389,6 +391,7 @@ pub fn queue_play_from(session_id: &str, track_id: i64) -> Result<bool, ()> {
     if !found {
         return Ok(false);
     }
+    note_now_playing_change(session_id, session, before);
     if session.history.len() > 100 {
         let _ = session.history.pop_front();
     }
@@ -403,6 +406,7 @@ pub fn queue_play_from(session_id: &str, track_id: i64) -> Result<bool, ()> {
 pub fn queue_next_track_id(session_id: &str) -> Result<Option<i64>, ()> {
     let mut store = store().lock().map_err(|_| ())?;
     let session = store.by_id.get_mut(session_id).ok_or(())?;
+    let before = session.now_playing;
     let next = if session.queue_items.is_empty() {
         None
     } else {
@@ -418,6 +422,7 @@ pub fn queue_next_track_id(session_id: &str) -> Result<Option<i64>, ()> {
             }
         }
         session.now_playing = Some(track_id);
+        note_now_playing_change(session_id, session, before);
         if session.history.len() > 100 {
             let _ = session.history.pop_front();
         }
@@ -434,6 +439,7 @@ pub fn queue_next_track_id(session_id: &str) -> Result<Option<i64>, ()> {
 pub fn queue_previous_track_id(session_id: &str) -> Result<Option<i64>, ()> {
     let mut store = store().lock().map_err(|_| ())?;
     let session = store.by_id.get_mut(session_id).ok_or(())?;
+    let before = session.now_playing;
     while let Some(prev) = session.history.pop_back() {
         if let Some(current) = session.now_playing.take() {
             if current != prev {
@@ -441,6 +447,7 @@ pub fn queue_previous_track_id(session_id: &str) -> Result<Option<i64>, ()> {
             }
         }
         session.now_playing = Some(prev);
+        note_now_playing_change(session_id, session, before);
         session.queue_len = session.queue_items.len();
         session.last_seen = Instant::now();
         return Ok(Some(prev));
@@ -462,6 +469,7 @@ pub fn queue_finish_now_playing(session_id: &str) -> Result<bool, ()> {
         session.last_seen = Instant::now();
         return Ok(false);
     };
+    note_now_playing_change(session_id, session, Some(current));
     if session
         .history
         .back()
@@ -478,6 +486,43 @@ pub fn queue_finish_now_playing(session_id: &str) -> Result<bool, ()> {
     Ok(true)
 }
 
+/// Report a `now_playing` change to the listening history and webhooks.
+fn note_now_playing_change(session_id: &str, session: &SessionRecord, before: Option<i64>) {
+    if session.now_playing != before {
+        crate::play_history::now_playing_changed(
+            session_id,
+            session.active_output_id.as_deref(),
+            session.now_playing,
+        );
+        if let Some(track_id) = before {
+            webhooks::emit(WebhookEvent::TrackStopped {
+                session_id: session_id.to_string(),
+                output_id: session.active_output_id.clone(),
+                track_id,
+            });
+        }
+        if let Some(track_id) = session.now_playing {
+            webhooks::emit(WebhookEvent::TrackStarted {
+                session_id: session_id.to_string(),
+                output_id: session.active_output_id.clone(),
+                track_id,
+            });
+        }
+    }
+}
+
+/// Report a removed session to the listening history and webhooks.
+fn note_session_ended(session_id: &str, removed: &SessionRecord) {
+    crate::play_history::session_ended(session_id);
+    if let Some(track_id) = removed.now_playing {
+        webhooks::emit(WebhookEvent::TrackStopped {
+            session_id: session_id.to_string(),
+            output_id: removed.active_output_id.clone(),
+            track_id,
+        });
+    }
+}
+
 /// Errors validating a session's selected output lock.
 #[derive(Clone, Debug)]
 pub enum BoundOutputError {
@@ -660,6 +705,7 @@ pub fn delete_session(session_id: &str) -> Result<Option<String>, ()> {
     let Some(removed) = store.by_id.remove(session_id) else {
         return Err(());
     };
+    note_session_ended(session_id, &removed);
     let key = session_identity_key(&removed.mode, &removed.name, &removed.client_id);
     if store.by_key.get(&key).map(|id| id.as_str()) == Some(session_id) {
         store.by_key.remove(&key);
@@ -710,6 +756,7 @@ pub fn purge_expired() -> Vec<String> {
         let Some(removed) = store.by_id.remove(session_id) else {
             continue;
         };
+        note_session_ended(session_id, &removed);
         let key = session_identity_key(&removed.mode, &removed.name, &removed.client_id);
         if store.by_key.get(&key).map(|id| id.as_str()) == Some(session_id.as_str()) {
             store.by_key.remove(&key);
@@ -0,0 +1,779 @@
+//! Snapcast output, from `[snapcast]` config.
+//!
+//! The hub decodes tracks itself and writes 16-bit stereo PCM into one snapserver stream source,
+//! either a named pipe (`pipe:///tmp/snapfifo`) or a TCP source in server mode
+//! (`tcp://host:4953`). The source's sample format must match `sample_rate` (`<rate>:16:2`).
+//! Snapserver's JSON-RPC control port lists groups and clients, which become
+//! `snapcast:group:<id>` and `snapcast:client:<id>` outputs; selecting one switches its group to
+//! the hub's stream. Every Snapcast output plays the same stream, sample-synchronized by
+//! Snapcast, so all of them show the same track.
+//!
+//! Elapsed time trails what was written by the server's buffer (`buffer_ms`).
+
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_web::web;
+use anyhow::{Context, Result, anyhow};
+use audio_bridge_types::{BridgeStatus, PlaybackEndReason};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use serde_json::{Value, json};
+
+use crate::bridge::BridgeCommand;
+use crate::config::{ServerConfig, SnapcastConfig};
+use crate::events::EventBus;
+use crate::metadata_db::MetadataDb;
+use crate::pcm_track::PcmTrack;
+use crate::playback_transport::ChannelTransport;
+use crate::queue_service::QueueService;
+use crate::state::{AppState, QueueState, SnapcastProviderState};
+use crate::status_store::StatusStore;
+
+/// Default JSON-RPC control port.
+const DEFAULT_CONTROL_PORT: u16 = 1705;
+/// Default port of a snapserver TCP stream source.
+const DEFAULT_SINK_PORT: u16 = 4953;
+/// Default stream id the hub feeds.
+const DEFAULT_STREAM: &str = "audio-hub";
+/// Default sample rate (snapserver's default `48000:16:2`).
+const DEFAULT_SAMPLE_RATE: u32 = 48_000;
+/// Default snapserver buffer.
+const DEFAULT_BUFFER_MS: u32 = 1_000;
+/// How often groups and clients are refreshed.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Timeout of one control request.
+const RPC_TIMEOUT: Duration = Duration::from_secs(3);
+/// Audio written per chunk.
+const CHUNK_MS: u32 = 20;
+/// How far ahead of real time audio is written.
+const LEAD_MS: u64 = 200;
+/// How often status is published while playing.
+const STATUS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Where PCM is written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapcastSink {
+    /// Named pipe read by a `pipe://` source.
+    Pipe(PathBuf),
+    /// `host:port` of a `tcp://` source in server mode.
+    Tcp(String),
+}
+
+/// Resolved `[snapcast]` settings.
+#[derive(Debug, Clone)]
+pub struct SnapcastSettings {
+    /// `host:port` of the JSON-RPC control interface.
+    pub control_addr: String,
+    /// Stream id groups are switched to.
+    pub stream_id: String,
+    pub sink: SnapcastSink,
+    pub sample_rate: u32,
+    pub buffer_ms: u32,
+}
+
+/// Resolve `[snapcast]` from config (`None` when the section is absent).
+pub fn from_config(cfg: &ServerConfig) -> Result<Option<SnapcastSettings>> {
+    cfg.snapcast
+        .as_ref()
+        .map(|section| settings_from_section(section).map_err(|errors| anyhow!(errors.join("; "))))
+        .transpose()
+}
+
+/// Resolve a `[snapcast]` section, listing every invalid field.
+pub(crate) fn settings_from_section(
+    section: &SnapcastConfig,
+) -> std::result::Result<SnapcastSettings, Vec<String>> {
+    let mut errors = Vec::new();
+    let server = section.server.trim();
+    let (host, control_port) = match server.rsplit_once(':') {
+        Some((host, port)) => match port.parse::<u16>() {
+            Ok(port) if port > 0 => (host, port),
+            _ => {
+                errors.push(format!(
+                    "snapcast.server: invalid port in `{}`",
+                    section.server
+                ));
+                (host, DEFAULT_CONTROL_PORT)
+            }
+        },
+        None => (server, DEFAULT_CONTROL_PORT),
+    };
+    if host.is_empty() || host.contains('/') {
+        errors.push(format!(
+            "snapcast.server: expected host[:port] (got `{}`)",
+            section.server
+        ));
+    }
+    let sink = match section.sink.as_deref().map(str::trim) {
+        None => SnapcastSink::Tcp(format!("{host}:{DEFAULT_SINK_PORT}")),
+        Some(sink) => {
+            if let Some(path) = sink.strip_prefix("pipe://").filter(|p| p.starts_with('/')) {
+                SnapcastSink::Pipe(PathBuf::from(path))
+            } else if let Some(addr) = sink.strip_prefix("tcp://").filter(|addr| {
+                addr.rsplit_once(':')
+                    .is_some_and(|(_, p)| p.parse::<u16>().is_ok())
+            }) {
+                SnapcastSink::Tcp(addr.to_string())
+            } else {
+                errors.push(format!(
+                    "snapcast.sink: expected pipe:///path or tcp://host:port (got `{sink}`)"
+                ));
+                SnapcastSink::Tcp(String::new())
+            }
+        }
+    };
+    let sample_rate = section.sample_rate.unwrap_or(DEFAULT_SAMPLE_RATE);
+    if !(8_000..=192_000).contains(&sample_rate) {
+        errors.push("snapcast.sample_rate: must be 8000-192000".to_string());
+    }
+    let stream_id = section
+        .stream
+        .as_deref()
+        .unwrap_or(DEFAULT_STREAM)
+        .trim()
+        .to_string();
+    if stream_id.is_empty() {
+        errors.push("snapcast.stream: must not be empty".to_string());
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(SnapcastSettings {
+        control_addr: format!("{host}:{control_port}"),
+        stream_id,
+        sink,
+        sample_rate,
+        buffer_ms: section.buffer_ms.unwrap_or(DEFAULT_BUFFER_MS),
+    })
+}
+
+/// A snapserver group and its clients.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapcastGroup {
+    pub id: String,
+    pub name: String,
+    pub stream_id: String,
+    pub muted: bool,
+    pub clients: Vec<SnapcastClient>,
+}
+
+/// A snapclient.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapcastClient {
+    pub id: String,
+    pub name: String,
+    pub connected: bool,
+    pub volume: u8,
+    pub muted: bool,
+}
+
+/// Groups from a `Server.GetStatus` result.
+fn parse_status(result: &Value) -> Vec<SnapcastGroup> {
+    let str_at = |value: &Value, path: &[&str]| {
+        path.iter()
+            .try_fold(value, |value, key| value.get(key))
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string()
+    };
+    let groups = result
+        .pointer("/server/groups")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    groups
+        .iter()
+        .map(|group| {
+            let clients = group
+                .get("clients")
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default()
+                .iter()
+                .map(|client| {
+                    let mut name = str_at(client, &["config", "name"]);
+                    if name.is_empty() {
+                        name = str_at(client, &["host", "name"]);
+                    }
+                    SnapcastClient {
+                        id: str_at(client, &["id"]),
+                        name,
+                        connected: client
+                            .get("connected")
+                            .and_then(Value::as_bool)
+                            .unwrap_or(false),
+                        volume: client
+                            .pointer("/config/volume/percent")
+                            .and_then(Value::as_u64)
+                            .unwrap_or(100)
+                            .min(100) as u8,
+                        muted: client
+                            .pointer("/config/volume/muted")
+                            .and_then(Value::as_bool)
+                            .unwrap_or(false),
+                    }
+                })
+                .collect();
+            SnapcastGroup {
+                id: str_at(group, &["id"]),
+                name: str_at(group, &["name"]),
+                stream_id: str_at(group, &["stream_id"]),
+                muted: group.get("muted").and_then(Value::as_bool).unwrap_or(false),
+                clients,
+            }
+        })
+        .filter(|group: &SnapcastGroup| !group.id.is_empty())
+        .collect()
+}
+
+/// Connection to the snapserver JSON-RPC control interface (newline-delimited JSON).
+pub struct SnapcastRpc {
+    reader: BufReader<TcpStream>,
+    next_id: u64,
+}
+
+impl SnapcastRpc {
+    /// Connect to the control interface.
+    pub fn connect(settings: &SnapcastSettings) -> Result<Self> {
+        let addr = settings
+            .control_addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| anyhow!("no address for {}", settings.control_addr))?;
+        let stream = TcpStream::connect_timeout(&addr, RPC_TIMEOUT).context("connect")?;
+        stream.set_read_timeout(Some(RPC_TIMEOUT))?;
+        Ok(Self {
+            reader: BufReader::new(stream),
+            next_id: 1,
+        })
+    }
+
+    /// Call `method` and return its result; notifications received meanwhile are skipped.
+    pub fn call(&mut self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = json!({ "id": id, "jsonrpc": "2.0", "method": method, "params": params });
+        let mut line = request.to_string();
+        line.push_str("\r\n");
+        self.reader.get_mut().write_all(line.as_bytes())?;
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Err(anyhow!("control connection closed"));
+            }
+            let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+            if message.get("id").and_then(Value::as_u64) != Some(id) {
+                continue;
+            }
+            if let Some(error) = message.get("error") {
+                return Err(anyhow!("{method} failed: {error}"));
+            }
+            return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+        }
+    }
+
+    /// Current groups and clients.
+    pub fn groups(&mut self) -> Result<Vec<SnapcastGroup>> {
+        Ok(parse_status(&self.call("Server.GetStatus", json!({}))?))
+    }
+
+    /// Switch a group to `stream_id`.
+    pub fn set_group_stream(&mut self, group_id: &str, stream_id: &str) -> Result<()> {
+        self.call(
+            "Group.SetStream",
+            json!({ "id": group_id, "stream_id": stream_id }),
+        )
+        .map(|_| ())
+    }
+
+    /// Set one client's volume and mute.
+    pub fn set_client_volume(&mut self, client_id: &str, percent: u8, muted: bool) -> Result<()> {
+        self.call(
+            "Client.SetVolume",
+            json!({ "id": client_id, "volume": { "percent": percent.min(100), "muted": muted } }),
+        )
+        .map(|_| ())
+    }
+
+    /// Mute or unmute a whole group.
+    pub fn set_group_mute(&mut self, group_id: &str, muted: bool) -> Result<()> {
+        self.call("Group.SetMute", json!({ "id": group_id, "mute": muted }))
+            .map(|_| ())
+    }
+}
+
+/// Refresh groups and clients in the background.
+pub fn spawn_snapcast_poller(state: web::Data<AppState>) {
+    let Some(settings) = state.providers.snapcast.settings.get().cloned() else {
+        return;
+    };
+    std::thread::spawn(move || {
+        let mut rpc: Option<SnapcastRpc> = None;
+        let mut online = true;
+        loop {
+            let result = match rpc.as_mut() {
+                Some(rpc) => rpc.groups(),
+                None => SnapcastRpc::connect(&settings).and_then(|mut conn| {
+                    let groups = conn.groups();
+                    rpc = Some(conn);
+                    groups
+                }),
+            };
+            let groups = match result {
+                Ok(groups) => {
+                    if !online {
+                        tracing::info!(server = %settings.control_addr, "snapcast: server reachable");
+                    }
+                    online = true;
+                    groups
+                }
+                Err(err) => {
+                    if online {
+                        tracing::warn!(error = %format!("{err:#}"), server = %settings.control_addr, "snapcast: server unreachable");
+                    }
+                    online = false;
+                    rpc = None;
+                    Vec::new()
+                }
+            };
+            let changed = match state.providers.snapcast.groups.lock() {
+                Ok(mut current) if *current != groups => {
+                    *current = groups;
+                    true
+                }
+                _ => false,
+            };
+            if changed {
+                state.events.outputs_changed();
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+/// Open PCM writer for the stream source.
+enum SinkWriter {
+    Pipe(std::fs::File),
+    Tcp(TcpStream),
+}
+
+impl SinkWriter {
+    /// Open the configured sink.
+    fn open(sink: &SnapcastSink) -> Result<Self> {
+        match sink {
+            SnapcastSink::Pipe(path) => OpenOptions::new()
+                .write(true)
+                .open(path)
+                .map(Self::Pipe)
+                .with_context(|| format!("open {}", path.display())),
+            SnapcastSink::Tcp(addr) => {
+                let addr = addr
+                    .to_socket_addrs()?
+                    .next()
+                    .ok_or_else(|| anyhow!("no address for {addr}"))?;
+                let stream = TcpStream::connect_timeout(&addr, RPC_TIMEOUT)
+                    .with_context(|| format!("connect {addr}"))?;
+                stream.set_nodelay(true)?;
+                Ok(Self::Tcp(stream))
+            }
+        }
+    }
+
+    /// Write interleaved stereo samples as little-endian 16-bit PCM.
+    fn write(&mut self, samples: &[i16]) -> std::io::Result<()> {
+        let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        match self {
+            Self::Pipe(file) => file.write_all(&bytes),
+            Self::Tcp(stream) => stream.write_all(&bytes),
+        }
+    }
+}
+
+/// Shared handles the feeder reports through.
+pub struct SnapcastWorkerContext {
+    pub settings: SnapcastSettings,
+    pub provider: Arc<SnapcastProviderState>,
+    pub status: StatusStore,
+    pub queue: Arc<Mutex<QueueState>>,
+    pub events: EventBus,
+    pub metadata: Option<MetadataDb>,
+    pub bridge_state: Arc<Mutex<crate::state::BridgeState>>,
+}
+
+/// Feeder state.
+struct Worker {
+    cmd_tx: Sender<BridgeCommand>,
+    ctx: SnapcastWorkerContext,
+    queue_service: QueueService,
+    sink: Option<SinkWriter>,
+    track: Option<PcmTrack>,
+    anchor: Instant,
+    anchor_frames: u64,
+    stream_frames: u64,
+    last_status: Instant,
+    last_duration_ms: Option<u64>,
+    auto_advance_in_flight: bool,
+}
+
+/// Spawn the feeder writing the hub's playback into the Snapcast stream.
+pub fn spawn_snapcast_worker(
+    cmd_rx: Receiver<BridgeCommand>,
+    cmd_tx: Sender<BridgeCommand>,
+    ctx: SnapcastWorkerContext,
+) {
+    std::thread::spawn(move || {
+        let queue_service =
+            QueueService::new(ctx.queue.clone(), ctx.status.clone(), ctx.events.clone());
+        let mut worker = Worker {
+            cmd_tx,
+            ctx,
+            queue_service,
+            sink: None,
+            track: None,
+            anchor: Instant::now(),
+            anchor_frames: 0,
+            stream_frames: 0,
+            last_status: Instant::now(),
+            last_duration_ms: None,
+            auto_advance_in_flight: false,
+        };
+        loop {
+            let streaming = worker
+                .track
+                .as_ref()
+                .is_some_and(|t| !t.paused && !t.drained);
+            let tick = if streaming {
+                Duration::from_millis(u64::from(CHUNK_MS) / 2)
+            } else {
+                Duration::from_millis(250)
+            };
+            match cmd_rx.recv_timeout(tick) {
+                Ok(BridgeCommand::Quit) | Err(RecvTimeoutError::Disconnected) => break,
+                Ok(cmd) => worker.handle(cmd),
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+            worker.pump();
+            if worker.track.is_some() && worker.last_status.elapsed() >= STATUS_INTERVAL {
+                worker.publish(None);
+            }
+        }
+        let provider = &worker.ctx.provider;
+        if let Ok(mut status) = provider.status.lock() {
+            *status = None;
+        }
+        if let Ok(mut feeder) = provider.worker.lock()
+            && feeder
+                .as_ref()
+                .is_some_and(|tx| tx.same_channel(&worker.cmd_tx))
+        {
+            *feeder = None;
+        }
+        tracing::info!("snapcast feeder stopped");
+    });
+}
+
+impl Worker {
+    /// Buffered frames between writing and hearing.
+    fn latency_frames(&self) -> u32 {
+        self.ctx.settings.sample_rate / 1000 * self.ctx.settings.buffer_ms
+    }
+
+    /// Apply a control command.
+    fn handle(&mut self, cmd: BridgeCommand) {
+        match cmd {
+            BridgeCommand::Play {
+                path,
+                seek_ms,
+                start_paused,
+                ..
+            } => self.play(path, seek_ms.unwrap_or(0), start_paused),
+            BridgeCommand::PauseToggle => {
+                if let Some(track) = self.track.as_mut() {
+                    track.paused = !track.paused;
+                    self.anchor = Instant::now();
+                    self.anchor_frames = self.stream_frames;
+                    self.publish(None);
+                }
+            }
+            BridgeCommand::Seek { ms } => {
+                if let Some(track) = self.track.as_ref() {
+                    let (path, paused) = (track.path.clone(), track.paused);
+                    self.play(path, ms, paused);
+                }
+            }
+            BridgeCommand::Stop | BridgeCommand::StopSilent => {
+                self.track = None;
+                self.publish(Some(PlaybackEndReason::Stopped));
+            }
+            BridgeCommand::Quit => {}
+        }
+    }
+
+    /// Start feeding `path` from `start_ms`.
+    fn play(&mut self, path: PathBuf, start_ms: u64, start_paused: bool) {
+        self.auto_advance_in_flight = false;
+        self.track = None;
+        let mut track = match PcmTrack::open(path.clone(), start_ms, self.ctx.settings.sample_rate)
+        {
+            Ok(track) => track,
+            Err(err) => {
+                tracing::warn!(error = %format!("{err:#}"), path = %path.display(), "snapcast: open track failed");
+                self.publish(Some(PlaybackEndReason::Error));
+                return;
+            }
+        };
+        if self.sink.is_none() {
+            match SinkWriter::open(&self.ctx.settings.sink) {
+                Ok(sink) => self.sink = Some(sink),
+                Err(err) => {
+                    tracing::warn!(error = %format!("{err:#}"), "snapcast: sink unavailable");
+                    self.publish(Some(PlaybackEndReason::Error));
+                    return;
+                }
+            }
+        }
+        track.paused = start_paused;
+        self.anchor = Instant::now();
+        self.anchor_frames = self.stream_frames;
+        self.track = Some(track);
+        self.ctx.status.on_play(path, start_paused);
+        self.publish(None);
+    }
+
+    /// Write audio up to the real-time schedule; finishes the track once it is drained.
+    fn pump(&mut self) {
+        let (Some(sink), Some(track)) = (self.sink.as_mut(), self.track.as_mut()) else {
+            return;
+        };
+        if track.paused || track.drained {
+            return;
+        }
+        let rate = u64::from(self.ctx.settings.sample_rate);
+        let due = self.anchor_frames
+            + self.anchor.elapsed().as_millis() as u64 * rate / 1000
+            + LEAD_MS * rate / 1000;
+        let chunk = (self.ctx.settings.sample_rate / 1000 * CHUNK_MS) as usize;
+        while self.stream_frames < due {
+            let Some(samples) = track.next_chunk(chunk) else {
+                break;
+            };
+            if let Err(err) = sink.write(&samples) {
+                tracing::warn!(error = %err, "snapcast: sink write failed");
+                self.sink = None;
+                self.track = None;
+                self.publish(Some(PlaybackEndReason::Error));
+                return;
+            }
+            let frames = (samples.len() / 2) as u64;
+            track.frames_sent += frames;
+            self.stream_frames += frames;
+        }
+        if track.drained {
+            self.publish(Some(PlaybackEndReason::Eof));
+        }
+    }
+
+    /// Store the shared status and handle the end of a track.
+    fn publish(&mut self, end_reason: Option<PlaybackEndReason>) {
+        self.last_status = Instant::now();
+        let latency = self.latency_frames();
+        let rate = self.ctx.settings.sample_rate;
+        let mut remote = BridgeStatus {
+            device: Some("Snapcast".to_string()),
+            end_reason,
+            paused: true,
+            ..Default::default()
+        };
+        if end_reason.is_none()
+            && let Some(track) = self.track.as_ref()
+        {
+            remote.now_playing = Some(track.path.to_string_lossy().to_string());
+            remote.paused = track.paused;
+            remote.elapsed_ms = Some(track.elapsed_ms(latency));
+            remote.duration_ms = track.duration_ms;
+            remote.source_codec = track.codec.clone();
+            remote.sample_rate = Some(rate);
+            remote.channels = Some(2);
+            remote.output_sample_format = Some("S16".to_string());
+            remote.resampling = Some(track.source_rate != rate);
+            remote.resample_from_hz = Some(track.source_rate);
+            remote.resample_to_hz = Some(rate);
+        }
+        if end_reason.is_some() {
+            self.track = None;
+        }
+        let provider = &self.ctx.provider;
+        if let Ok(mut status) = provider.status.lock() {
+            *status = Some((remote.clone(), Instant::now()));
+        }
+
+        let output_id = provider
+            .current_output
+            .lock()
+            .ok()
+            .and_then(|id| id.clone());
+        let bound_session = output_id
+            .as_deref()
+            .and_then(crate::session_registry::output_lock_owner);
+        if end_reason.is_some() {
+            self.ctx.events.status_changed();
+        }
+        if end_reason == Some(PlaybackEndReason::Eof)
+            && !self.auto_advance_in_flight
+            && let Some(session_id) = bound_session.as_deref()
+        {
+            self.advance_session(session_id);
+        }
+        let is_active = self
+            .ctx
+            .bridge_state
+            .lock()
+            .map(|b| {
+                b.active_output_id
+                    .as_deref()
+                    .is_some_and(|id| id.starts_with("snapcast:"))
+            })
+            .unwrap_or(false);
+        if !is_active {
+            return;
+        }
+        let (inputs, changed) = self
+            .ctx
+            .status
+            .reduce_remote_and_inputs(&remote, self.last_duration_ms);
+        self.ctx.status.emit_if_changed(changed);
+        self.last_duration_ms = remote.duration_ms;
+        if bound_session.is_some() {
+            return;
+        }
+        let transport = ChannelTransport::new(self.cmd_tx.clone());
+        let _ = self.queue_service.maybe_auto_advance(&transport, inputs);
+    }
+
+    /// Play the next queue entry of the session holding the Snapcast output.
+    fn advance_session(&mut self, session_id: &str) {
+        match crate::session_registry::queue_next_track_id(session_id) {
+            Ok(Some(track_id)) => {
+                let Some(path) = self
+                    .ctx
+                    .metadata
+                    .as_ref()
+                    .and_then(|db| db.track_path_for_id(track_id).ok().flatten())
+                    .map(PathBuf::from)
+                else {
+                    tracing::warn!(
+                        session_id = %session_id,
+                        track_id,
+                        "snapcast session auto-advance track not found"
+                    );
+                    return;
+                };
+                self.auto_advance_in_flight = true;
+                let _ = self.cmd_tx.send(BridgeCommand::Play {
+                    path,
+                    ext_hint: String::new(),
+                    seek_ms: None,
+                    start_paused: false,
+                });
+            }
+            Ok(None) => {
+                if let Ok(true) = crate::session_registry::queue_finish_now_playing(session_id) {
+                    self.ctx.events.queue_changed();
+                    self.ctx.events.status_changed();
+                }
+            }
+            Err(()) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_from_section_resolves_defaults_and_reports_errors() {
+        let section: SnapcastConfig = toml::from_str(r#"server = "snap.lan""#).unwrap();
+        let settings = settings_from_section(&section).unwrap();
+        assert_eq!(settings.control_addr, "snap.lan:1705");
+        assert_eq!(
+            settings.sink,
+            SnapcastSink::Tcp("snap.lan:4953".to_string())
+        );
+        assert_eq!(settings.stream_id, "audio-hub");
+        assert_eq!(settings.sample_rate, 48_000);
+
+        let section: SnapcastConfig = toml::from_str(
+            r#"
+            server = "snap.lan:1780"
+            sink = "pipe:///tmp/snapfifo"
+            "#,
+        )
+        .unwrap();
+        let settings = settings_from_section(&section).unwrap();
+        assert_eq!(settings.control_addr, "snap.lan:1780");
+        assert_eq!(
+            settings.sink,
+            SnapcastSink::Pipe(PathBuf::from("/tmp/snapfifo"))
+        );
+
+        let section: SnapcastConfig = toml::from_str(
+            r#"
+            server = "snap.lan:0"
+            sink = "udp://snap.lan"
+            sample_rate = 1000
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            settings_from_section(&section).unwrap_err(),
+            vec![
+                "snapcast.server: invalid port in `snap.lan:0`",
+                "snapcast.sink: expected pipe:///path or tcp://host:port (got `udp://snap.lan`)",
+                "snapcast.sample_rate: must be 8000-192000",
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_status_reads_groups_and_clients() {
+        let result = json!({
+            "server": {
+                "groups": [{
+                    "id": "g1",
+                    "name": "",
+                    "stream_id": "default",
+                    "muted": false,
+                    "clients": [
+                        {
+                            "id": "c1",
+                            "connected": true,
+                            "host": { "name": "kitchen-pi" },
+                            "config": { "name": "", "volume": { "percent": 40, "muted": true } }
+                        },
+                        {
+                            "id": "c2",
+                            "connected": false,
+                            "host": { "name": "den" },
+                            "config": { "name": "Den", "volume": { "percent": 75, "muted": false } }
+                        }
+                    ]
+                }]
+            }
+        });
+        let groups = parse_status(&result);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].stream_id, "default");
+        assert_eq!(groups[0].clients[0].name, "kitchen-pi");
+        assert_eq!(groups[0].clients[0].volume, 40);
+        assert!(groups[0].clients[0].muted);
+        assert_eq!(groups[0].clients[1].name, "Den");
+        assert!(!groups[0].clients[1].connected);
+    }
+}
@@ -6,45 +6,73 @@ use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
 
-use actix_cors::Cors;
 use actix_files::{Files, NamedFile};
 use actix_web::Error;
 use actix_web::dev::{Service, ServiceRequest, ServiceResponse};
 use actix_web::{App, HttpServer, web};
 use anyhow::{Context as AnyhowContext, Result};
+use audio_player::log_filter::LogFilterControl;
 use crossbeam_channel::unbounded;
 use futures_util::future::{LocalBoxFuture, Ready, ok};
-use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use rustls::ServerConfig as RustlsConfig;
 use std::task::{Context, Poll};
-use std::time::Duration;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
+use crate::acoustid::AcoustIdClient;
 use crate::api;
+use crate::artist_images::ArtistImageFetcher;
+use crate::audit::{AuditLog, spawn_audit_loop};
+use crate::auth::{ApiAuth, TokenAuth};
 use crate::bridge_device_streams::{
     spawn_bridge_device_streams_for_config, spawn_bridge_status_streams_for_config,
 };
-use crate::bridge_transport::BridgeTransportClient;
+use crate::bridge_network;
 use crate::config;
+use crate::cors;
 use crate::cover_art::CoverArtFetcher;
 use crate::discovery::{
-    spawn_cast_mdns_discovery, spawn_discovered_health_watcher, spawn_mdns_discovery,
+    self, spawn_airplay_mdns_discovery, spawn_cast_mdns_discovery, spawn_discovered_health_watcher,
+    spawn_dlna_ssdp_discovery, spawn_mdns_discovery,
 };
 use crate::events::LogBus;
+use crate::hls;
+use crate::library_scan::spawn_library_scan;
+use crate::library_watcher::spawn_library_watcher;
+use crate::loudness::spawn_loudness_loop;
+use crate::lyrics::LyricsClient;
 use crate::metadata_db::MetadataDb;
-use crate::metadata_service::MetadataService;
+use crate::mqtt;
 use crate::musicbrainz::{MusicBrainzClient, spawn_enrichment_loop};
 use crate::openapi;
+use crate::output_groups;
+use crate::play_history::spawn_play_history_loop;
+use crate::rate_limit::{self, RateLimit};
+use crate::snapcast;
 use crate::state::MetadataWake;
 use crate::state::{
     AppState, BridgeProviderState, BridgeState, CastProviderState, LocalProviderState,
     PlayerStatus, QueueState,
 };
+use crate::stream_capture;
+use crate::stream_limits;
+use crate::tempo_key::spawn_tempo_key_loop;
+use crate::track_analysis::spawn_lossy_check_loop;
+use crate::webhooks::{self, spawn_webhook_loop};
 
 /// Build server state and start the Actix HTTP server.
-pub(crate) async fn run(args: crate::Args, log_bus: std::sync::Arc<LogBus>) -> Result<()> {
-    let (cfg, cfg_path) = load_config(args.config.as_ref())?;
+pub(crate) async fn run(
+    args: crate::Args,
+    log_bus: std::sync::Arc<LogBus>,
+    log_filter: std::sync::Arc<LogFilterControl>,
+) -> Result<()> {
+    let (cfg, cfg_path) = load_config(args.config.as_ref(), args.strict_config)?;
+    bridge_network::install(bridge_network::from_config(&cfg)?);
+    stream_limits::install(stream_limits::from_config(&cfg)?);
+    rate_limit::install(rate_limit::from_config(&cfg)?);
+    stream_capture::install(stream_capture::from_config(&cfg)?);
+    discovery::install(discovery::from_config(&cfg)?);
+    let cors_policy = cors::from_config(&cfg)?;
     let bind = resolve_bind(args.bind, &cfg)?;
     let tls_config = resolve_tls_config(&args, &cfg)?;
     let public_base_url = config::public_base_url_from_config(&cfg, bind, tls_config.is_some())?;
@@ -65,12 +93,7 @@ pub(crate) async fn run(args: crate::Args, log_bus: std::sync::Arc<LogBus>) -> R
     }
     let events = crate::events::EventBus::new();
     let metadata_wake = MetadataWake::new();
-    let (metadata_db, library) = init_metadata_db_and_library(
-        &media_dir,
-        metadata_db_path,
-        events.clone(),
-        metadata_wake.clone(),
-    )?;
+    let (metadata_db, library) = init_metadata_db_and_library(&media_dir, metadata_db_path)?;
     let musicbrainz = init_musicbrainz(&cfg)?;
     let bridges = config::bridges_from_config(&cfg)?;
     tracing::info!(
@@ -79,24 +102,15 @@ pub(crate) async fn run(args: crate::Args, log_bus: std::sync::Arc<LogBus>) -> R
         "loaded bridges from config"
     );
 
-    let active_bridge_id = None;
-    let active_output_id = None;
-    let active_http_addr = active_bridge_id.as_ref().and_then(|bridge_id| {
-        bridges
-            .iter()
-            .find(|b| b.id == *bridge_id)
-            .map(|b| b.http_addr)
-    });
-
-    let output_settings_state =
+    // Bridges are only contacted by the background watchers spawned below, so an unreachable
+    // bridge never delays serving; `/health` reports each one as it is probed.
+    let mut output_settings_state =
         crate::state::OutputSettingsState::from_config(cfg.outputs.as_ref());
-    let active_exclusive = active_output_id
-        .as_deref()
-        .map(|id| output_settings_state.is_exclusive(id))
-        .unwrap_or(false);
-    apply_active_bridge_device(None, active_http_addr, &public_base_url, active_exclusive).await;
-    let bridge_state =
-        build_bridge_state(bridges, active_bridge_id, active_output_id, public_base_url);
+    match metadata_db.list_output_preferences() {
+        Ok(preferences) => output_settings_state.preferences = preferences,
+        Err(err) => tracing::warn!(error = %err, "failed to load output preferences"),
+    }
+    let bridge_state = build_bridge_state(bridges, public_base_url);
     let playback_manager = build_playback_manager(bridge_state.player.clone(), events.clone());
     let (local_state, device_selection) = build_local_state(&cfg);
     let cast_state = Arc::new(CastProviderState::new());
@@ -116,11 +130,15 @@ pub(crate) async fn run(args: crate::Args, log_bus: std::sync::Arc<LogBus>) -> R
         output_settings,
         cfg_path,
     ));
+    spawn_library_scan(state.clone(), false);
     spawn_library_watcher(state.clone());
     if let Some(client) = state.metadata.musicbrainz.as_ref() {
         spawn_enrichment_loop(
             state.metadata.db.clone(),
             client.clone(),
+            cfg.musicbrainz
+                .as_ref()
+                .and_then(|mb| AcoustIdClient::new(mb, client.user_agent())),
             state.events.clone(),
             metadata_wake.clone(),
         );
@@ -132,30 +150,112 @@ pub(crate) async fn run(args: crate::Args, log_bus: std::sync::Arc<LogBus>) -> R
             metadata_wake.clone(),
         )
         .spawn();
+        ArtistImageFetcher::new(
+            state.metadata.db.clone(),
+            state.library.read().unwrap().root().to_path_buf(),
+            client.user_agent().to_string(),
+            cfg.musicbrainz
+                .as_ref()
+                .and_then(|mb| mb.fanart_api_key.clone())
+                .filter(|key| !key.trim().is_empty()),
+            state.events.clone(),
+            metadata_wake.clone(),
+        )
+        .spawn();
     }
+    if let Some(lyrics) = cfg
+        .lyrics
+        .as_ref()
+        .filter(|lyrics| lyrics.fetch_online.unwrap_or(false))
+    {
+        let _ = state
+            .metadata
+            .lyrics
+            .set(LyricsClient::new(lyrics.provider_url.as_deref()));
+    }
+    if cfg
+        .analysis
+        .as_ref()
+        .and_then(|analysis| analysis.lossy_check)
+        .unwrap_or(false)
+    {
+        spawn_lossy_check_loop(
+            state.metadata.db.clone(),
+            state.library.read().unwrap().root().to_path_buf(),
+            metadata_wake.clone(),
+        );
+    }
+    if cfg
+        .analysis
+        .as_ref()
+        .and_then(|analysis| analysis.loudness)
+        .unwrap_or(false)
+    {
+        spawn_loudness_loop(
+            state.metadata.db.clone(),
+            state.library.read().unwrap().root().to_path_buf(),
+            metadata_wake.clone(),
+        );
+    }
+    if cfg
+        .analysis
+        .as_ref()
+        .and_then(|analysis| analysis.tempo_key)
+        .unwrap_or(false)
+    {
+        spawn_tempo_key_loop(
+            state.metadata.db.clone(),
+            state.library.read().unwrap().root().to_path_buf(),
+            metadata_wake.clone(),
+        );
+    }
+    spawn_play_history_loop(state.metadata.db.clone());
+    spawn_audit_loop(state.metadata.db.clone());
+    hls::spawn_hls_eviction_loop();
+    spawn_webhook_loop(
+        webhooks::from_config(&cfg)?,
+        state.metadata.db.clone(),
+        &state.events,
+    );
+    if let Some(settings) = mqtt::from_config(&cfg)? {
+        mqtt::spawn_mqtt(settings, state.clone());
+    }
+    if let Some(settings) = snapcast::from_config(&cfg)? {
+        let _ = state.providers.snapcast.settings.set(settings);
+        snapcast::spawn_snapcast_poller(state.clone());
+    }
+    let _ = state
+        .providers
+        .groups
+        .groups
+        .set(output_groups::from_config(&cfg)?);
     setup_shutdown(state.providers.bridge.player.clone());
     spawn_mdns_discovery(state.clone());
     spawn_discovered_health_watcher(state.clone());
     spawn_cast_mdns_discovery(state.clone());
+    spawn_airplay_mdns_discovery(state.clone());
+    spawn_dlna_ssdp_discovery(state.clone());
     spawn_bridge_device_streams_for_config(state.clone());
     spawn_bridge_status_streams_for_config(state.clone());
+    let log_filter = web::Data::from(log_filter);
+    let auth = web::Data::new(ApiAuth::new(state.metadata.db.clone(), cfg.auth.as_ref())?);
+    if auth.enabled() {
+        tracing::info!("api token authentication enabled");
+    } else {
+        tracing::warn!(
+            "api token authentication disabled; create a token via POST /auth/tokens or [auth] config"
+        );
+    }
     let server = HttpServer::new(move || {
-        let cors = Cors::default()
-            .allowed_origin_fn(|origin, _req_head| {
-                let value = origin.as_bytes();
-                value == b"tauri://localhost"
-                    || value == b"http://tauri.localhost"
-                    || value.starts_with(b"http://localhost:")
-                    || value.starts_with(b"http://127.0.0.1:")
-                    || value.starts_with(b"https://localhost:")
-                    || value.starts_with(b"https://127.0.0.1:")
-            })
-            .allowed_methods(vec!["GET", "POST", "HEAD"])
-            .allowed_headers(vec![actix_web::http::header::CONTENT_TYPE])
-            .max_age(3600);
+        let cors = cors_policy.build();
 
         let mut app = App::new()
             .app_data(state.clone())
+            .app_data(log_filter.clone())
+            .app_data(auth.clone())
+            .wrap(AuditLog)
+            .wrap(RateLimit)
+            .wrap(TokenAuth)
             .wrap(cors)
             .wrap(FilteredLogger)
             .service(
@@ -167,20 +267,46 @@ pub(crate) async fn run(args: crate::Args, log_bus: std::sync::Arc<LogBus>) -> R
             .service(api::rescan_track)
             .service(api::stream_track_id)
             .service(api::transcode_track_id)
+            .service(api::hls_playlist)
+            .service(api::hls_segment)
             .service(api::artists_list)
+            .service(api::genres_list)
+            .service(api::composers_list)
             .service(api::albums_list)
             .service(api::tracks_list)
+            .service(api::albums_recent)
+            .service(api::tracks_recent)
+            .service(api::track_rating_set)
+            .service(api::album_rating_set)
+            .service(api::history_list)
+            .service(api::audit_list)
+            .service(api::metadata_export)
+            .service(api::metadata_import)
+            .service(api::auth_tokens_list)
+            .service(api::auth_tokens_create)
+            .service(api::auth_tokens_delete)
+            .service(api::homeassistant_discovery)
+            .service(api::homeassistant_player_state)
+            .service(api::homeassistant_player_command)
+            .service(api::playlists_list)
+            .service(api::playlists_create)
+            .service(api::playlists_get)
+            .service(api::playlists_update)
+            .service(api::playlists_delete)
+            .service(api::library_search)
             .service(api::tracks_resolve)
             .service(api::tracks_metadata)
             .service(api::tracks_metadata_fields)
             .service(api::tracks_metadata_update)
             .service(api::tracks_analysis)
+            .service(api::tracks_lossy_report)
             .service(api::albums_metadata)
             .service(api::albums_metadata_update)
             .service(api::artist_profile)
             .service(api::artist_profile_update)
             .service(api::album_profile)
             .service(api::album_profile_update)
+            .service(api::artist_image)
             .service(api::artist_image_set)
             .service(api::artist_image_clear)
             .service(api::album_image_set)
@@ -189,8 +315,12 @@ pub(crate) async fn run(args: crate::Args, log_bus: std::sync::Arc<LogBus>) -> R
             .service(api::musicbrainz_match_search)
             .service(api::musicbrainz_match_apply)
             .service(api::track_cover)
+            .service(api::track_lyrics)
             .service(api::album_cover)
+            .service(api::album_cover_upload)
             .service(api::logs_clear)
+            .service(api::log_level_get)
+            .service(api::log_level_set)
             .service(api::local_playback_register)
             .service(api::local_playback_play)
             .service(api::local_playback_sessions)
@@ -209,10 +339,12 @@ pub(crate) async fn run(args: crate::Args, log_bus: std::sync::Arc<LogBus>) -> R
             .service(api::sessions_status_stream)
             .service(api::sessions_pause)
             .service(api::sessions_seek)
+            .service(api::sessions_chapter)
             .service(api::sessions_stop)
             .service(api::sessions_queue_list)
             .service(api::sessions_queue_add)
             .service(api::sessions_queue_add_next)
+            .service(api::sessions_queue_playlist)
             .service(api::sessions_queue_remove)
             .service(api::sessions_queue_play_from)
             .service(api::sessions_queue_clear)
@@ -223,15 +355,22 @@ pub(crate) async fn run(args: crate::Args, log_bus: std::sync::Arc<LogBus>) -> R
             .service(api::providers_list)
             .service(api::provider_outputs_list)
             .service(api::provider_refresh)
+            .service(api::provider_logs)
             .service(api::bridge_unregister)
+            .service(api::bridges_list)
+            .service(api::health::bridge_health)
             .service(api::outputs_list)
             .service(api::outputs_stream)
             .service(api::metadata_stream)
             .service(api::albums_stream)
             .service(api::logs_stream)
+            .service(api::library_scan_progress_stream)
             .service(api::outputs_select)
             .service(api::outputs_settings)
-            .service(api::outputs_settings_update);
+            .service(api::outputs_settings_update)
+            .service(api::outputs_hide)
+            .service(api::outputs_preferences)
+            .service(api::outputs_preferences_update);
 
         if let Some(dist) = web_ui_dist.clone() {
             let assets_dir = dist.join("assets");
@@ -256,7 +395,8 @@ pub(crate) async fn run(args: crate::Args, log_bus: std::sync::Arc<LogBus>) -> R
         }
 
         app
-    });
+    })
+    .on_connect(bridge_network::mark_connection);
 
     if let Some(tls_config) = tls_config {
         server.bind_rustls_0_22(bind, tls_config)?.run().await?;
@@ -267,107 +407,6 @@ pub(crate) async fn run(args: crate::Args, log_bus: std::sync::Arc<LogBus>) -> R
     Ok(())
 }
 
-/// Spawn filesystem watcher that incrementally rescans changed/removed tracks.
-fn spawn_library_watcher(state: web::Data<AppState>) {
-    let root = state.library.read().unwrap().root().to_path_buf();
-    let metadata_service = state.metadata_service();
-    std::thread::spawn(move || {
-        let (tx, rx) = std::sync::mpsc::channel();
-        let mut watcher: RecommendedWatcher = match notify::recommended_watcher(tx) {
-            Ok(watcher) => watcher,
-            Err(err) => {
-                tracing::warn!(error = %err, "metadata watcher init failed");
-                return;
-            }
-        };
-        if let Err(err) = watcher.watch(&root, RecursiveMode::Recursive) {
-            tracing::warn!(error = %err, "metadata watcher setup failed");
-            return;
-        }
-        loop {
-            let first = match rx.recv() {
-                Ok(event) => event,
-                Err(_) => break,
-            };
-            let mut events = vec![first];
-            while let Ok(event) = rx.recv_timeout(Duration::from_millis(750)) {
-                events.push(event);
-            }
-            for event in events {
-                let event = match event {
-                    Ok(event) => event,
-                    Err(err) => {
-                        tracing::warn!(error = %err, "metadata watcher event error");
-                        continue;
-                    }
-                };
-                match event.kind {
-                    EventKind::Modify(notify::event::ModifyKind::Name(_)) => {
-                        if event.paths.len() >= 2 {
-                            let from = &event.paths[0];
-                            let to = &event.paths[1];
-                            let _ = metadata_service.remove_track_by_path(&state.library, from);
-                            if !to.is_dir() {
-                                let _ = metadata_service.rescan_track(&state.library, to);
-                            }
-                        } else {
-                            for path in event.paths {
-                                if path.is_dir() {
-                                    continue;
-                                }
-                                let _ = metadata_service.rescan_track(&state.library, &path);
-                            }
-                        }
-                    }
-                    EventKind::Create(_)
-                    | EventKind::Modify(_)
-                    | EventKind::Access(_)
-                    | EventKind::Other => {
-                        for path in event.paths {
-                            if path.is_dir() {
-                                continue;
-                            }
-                            if let Err(response) =
-                                metadata_service.rescan_track(&state.library, &path)
-                            {
-                                let status = response.status();
-                                if status != actix_web::http::StatusCode::NOT_FOUND
-                                    && status != actix_web::http::StatusCode::BAD_REQUEST
-                                {
-                                    tracing::warn!(
-                                        status = %status,
-                                        path = %path.display(),
-                                        "metadata watcher rescan failed"
-                                    );
-                                }
-                            }
-                        }
-                    }
-                    EventKind::Remove(_) => {
-                        for path in event.paths {
-                            if let Err(response) =
-                                metadata_service.remove_track_by_path(&state.library, &path)
-                            {
-                                let status = response.status();
-                                if status != actix_web::http::StatusCode::NOT_FOUND
-                                    && status != actix_web::http::StatusCode::BAD_REQUEST
-                                {
-                                    tracing::warn!(
-                                        status = %status,
-                                        path = %path.display(),
-                                        "metadata watcher remove failed"
-                                    );
-                                }
-                            }
-                        }
-                    }
-                    _ => {}
-                }
-            }
-        }
-    });
-}
-
 /// Emit a warning when request latency crosses this threshold.
 const SLOW_REQUEST_WARN_MS: u128 = 2_000;
 
@@ -479,28 +518,44 @@ where
 }
 
 /// Load server config from disk or return defaults.
-fn load_config(path: Option<&PathBuf>) -> Result<(config::ServerConfig, Option<PathBuf>)> {
-    match path {
-        Some(path) => {
-            let cfg = config::ServerConfig::load(path)?;
-            Ok((cfg, Some(path.to_path_buf())))
-        }
-        None => {
-            let auto_path = std::env::current_exe()
-                .ok()
-                .and_then(|path| path.parent().map(|dir| dir.join("config.toml")));
-            if let Some(path) = auto_path.as_ref() {
-                if path.exists() {
-                    let cfg = config::ServerConfig::load(path)?;
-                    Ok((cfg, Some(path.to_path_buf())))
-                } else {
-                    Err(anyhow::anyhow!("config file is required; use --config"))
-                }
-            } else {
-                Err(anyhow::anyhow!("config file is required; use --config"))
-            }
-        }
+fn load_config(
+    path: Option<&PathBuf>,
+    strict: bool,
+) -> Result<(config::ServerConfig, Option<PathBuf>)> {
+    let path = resolve_config_path(path)?;
+    let cfg = config::ServerConfig::load(&path, strict)?;
+    Ok((cfg, Some(path)))
+}
+
+/// Resolve the config path from `--config` or `config.toml` next to the executable.
+pub(crate) fn resolve_config_path(path: Option<&PathBuf>) -> Result<PathBuf> {
+    if let Some(path) = path {
+        return Ok(path.to_path_buf());
     }
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| path.parent().map(|dir| dir.join("config.toml")))
+        .filter(|path| path.exists())
+        .ok_or_else(|| anyhow::anyhow!("config file is required; use --config"))
+}
+
+/// Check the config file, print every problem found, and return whether it is valid.
+pub(crate) fn check_config(path: Option<&PathBuf>) -> Result<bool> {
+    let path = resolve_config_path(path)?;
+    let problems = config::check_config_file(&path);
+    if problems.is_empty() {
+        println!("config ok: {}", path.display());
+        return Ok(true);
+    }
+    println!(
+        "config {} has {} problem(s):",
+        path.display(),
+        problems.len()
+    );
+    for problem in &problems {
+        println!("  - {problem}");
+    }
+    Ok(false)
 }
 
 /// Resolve the final bind address from args + config.
@@ -531,8 +586,20 @@ fn resolve_tls_config(
         .map(|p| p.to_path_buf())
         .or_else(|| cfg.tls_key.as_ref().map(PathBuf::from));
 
-    let (Some(cert_path), Some(key_path)) = (cert_path, key_path) else {
-        return Ok(None);
+    // A lone cert or key is a typo, not a request for plain HTTP.
+    let (cert_path, key_path) = match (cert_path, key_path) {
+        (Some(cert_path), Some(key_path)) => (cert_path, key_path),
+        (None, None) => return Ok(None),
+        (Some(_), None) => {
+            return Err(anyhow::anyhow!(
+                "tls cert set without a key (--tls-key or tls_key)"
+            ));
+        }
+        (None, Some(_)) => {
+            return Err(anyhow::anyhow!(
+                "tls key set without a cert (--tls-cert or tls_cert)"
+            ));
+        }
     };
 
     let cert_file = std::fs::File::open(&cert_path)
@@ -612,56 +679,34 @@ fn init_musicbrainz(cfg: &config::ServerConfig) -> Result<Option<Arc<MusicBrainz
     Ok(musicbrainz)
 }
 
-/// Initialize metadata DB/service and perform initial library scan.
+/// Initialize the metadata DB and an empty library index.
+///
+/// The first scan runs in the background (see [`spawn_library_scan`]) so a large library
+/// does not hold up serving.
 fn init_metadata_db_and_library(
     media_dir: &PathBuf,
     metadata_db_path: Option<PathBuf>,
-    events: crate::events::EventBus,
-    metadata_wake: MetadataWake,
 ) -> Result<(MetadataDb, crate::library::LibraryIndex)> {
     let metadata_db = if let Some(path) = metadata_db_path {
         MetadataDb::new_at_path_with_media_root(&path, Some(media_dir))?
     } else {
         MetadataDb::new(media_dir)?
     };
-    let metadata_service = MetadataService::new(
-        metadata_db.clone(),
-        media_dir.clone(),
-        events,
-        metadata_wake,
-    );
-    let library = metadata_service.scan_library(false)?;
-    metadata_service.ensure_album_markers();
+    let library = crate::library::LibraryIndex::empty(media_dir)?;
     Ok((metadata_db, library))
 }
 
-/// Apply preselected bridge output device at startup when configured.
-async fn apply_active_bridge_device(
-    device_to_set: Option<String>,
-    active_http_addr: Option<std::net::SocketAddr>,
-    public_base_url: &str,
-    exclusive: bool,
-) {
-    if let (Some(device_name), Some(http_addr)) = (device_to_set, active_http_addr) {
-        let _ = BridgeTransportClient::new_with_base(http_addr, public_base_url.to_string(), None)
-            .set_device(&device_name, Some(exclusive))
-            .await;
-    }
-}
-
-/// Construct bridge provider state and command channel.
+/// Construct bridge provider state and command channel; no output is active at startup.
 fn build_bridge_state(
     bridges: Vec<crate::config::BridgeConfigResolved>,
-    active_bridge_id: Option<String>,
-    active_output_id: Option<String>,
     public_base_url: String,
 ) -> Arc<BridgeProviderState> {
     let (cmd_tx, _cmd_rx) = unbounded();
     let bridge_online = Arc::new(AtomicBool::new(false));
     let bridges_state = Arc::new(Mutex::new(BridgeState {
         bridges,
-        active_bridge_id,
-        active_output_id,
+        active_bridge_id: None,
+        active_output_id: None,
     }));
     let discovered_bridges = Arc::new(Mutex::new(std::collections::HashMap::new()));
     Arc::new(BridgeProviderState::new(
@@ -751,6 +796,24 @@ fn setup_shutdown(player: std::sync::Arc<std::sync::Mutex<crate::bridge::BridgeP
 mod tests {
     use super::*;
 
+    #[test]
+    fn tls_config_requires_cert_and_key_together() {
+        use clap::Parser;
+
+        let cfg: config::ServerConfig = toml::from_str(r#"tls_key = "/etc/hub/key.pem""#).unwrap();
+        let args = crate::Args::parse_from(["audio-hub-server"]);
+        let err = resolve_tls_config(&args, &cfg).unwrap_err();
+        assert!(err.to_string().contains("without a cert"));
+
+        let cfg: config::ServerConfig = toml::from_str("").unwrap();
+        let args = crate::Args::parse_from(["audio-hub-server", "--tls-cert", "/etc/hub/cert.pem"]);
+        let err = resolve_tls_config(&args, &cfg).unwrap_err();
+        assert!(err.to_string().contains("without a key"));
+
+        let args = crate::Args::parse_from(["audio-hub-server"]);
+        assert!(resolve_tls_config(&args, &cfg).unwrap().is_none());
+    }
+
     #[test]
     fn should_log_path_filters_noisy_paths() {
         assert!(!should_log_path("/logs/stream"));
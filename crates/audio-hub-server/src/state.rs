@@ -6,15 +6,16 @@ use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Condvar;
 use std::sync::atomic::AtomicBool;
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
 
-use audio_bridge_types::BridgeStatus;
+use audio_bridge_types::{BridgeStatus, Chapter};
 use crossbeam_channel::Sender;
 
 use crate::bridge::{BridgeCommand, BridgePlayer};
 use crate::config::BridgeConfigResolved;
 use crate::events::{EventBus, LogBus};
-use crate::library::LibraryIndex;
+use crate::library::{LibraryIndex, ScanProgress};
+use crate::lyrics::LyricsClient;
 use crate::metadata_db::MetadataDb;
 use crate::metadata_service::MetadataService;
 use crate::models::StatusResponse;
@@ -105,6 +106,8 @@ pub struct PlayerStatus {
     pub seek_in_flight: bool,
     /// Manual next is in flight (suppresses auto-advance).
     pub manual_advance_in_flight: bool,
+    /// Chapters of the current file, when it has any.
+    pub chapters: Option<Vec<Chapter>>,
 }
 
 /// Grouped metadata dependencies for handlers/services.
@@ -115,6 +118,8 @@ pub struct MetadataState {
     pub musicbrainz: Option<Arc<MusicBrainzClient>>,
     /// Wake signal for metadata background jobs.
     pub wake: MetadataWake,
+    /// Online lyrics lookup, set at startup when `[lyrics] fetch_online` is enabled.
+    pub lyrics: OnceLock<LyricsClient>,
 }
 
 /// Grouped playback dependencies.
@@ -133,6 +138,14 @@ pub struct ProviderState {
     pub local: Arc<LocalProviderState>,
     /// Cast provider state (discovered Chromecast devices).
     pub cast: Arc<CastProviderState>,
+    /// AirPlay provider state (discovered RAOP receivers).
+    pub airplay: Arc<AirplayProviderState>,
+    /// DLNA provider state (discovered UPnP MediaRenderers).
+    pub dlna: Arc<DlnaProviderState>,
+    /// Snapcast provider state (server groups/clients and the shared feeder).
+    pub snapcast: Arc<SnapcastProviderState>,
+    /// Output group provider state (configured groups and their workers).
+    pub groups: Arc<OutputGroupProviderState>,
 }
 
 /// Grouped output dependencies.
@@ -149,6 +162,8 @@ pub struct OutputState {
 pub struct AppState {
     /// Library index and root.
     pub library: RwLock<LibraryIndex>,
+    /// Progress of the running (or last) full library scan.
+    pub library_scan: ScanProgress,
     /// Grouped metadata dependencies.
     pub metadata: MetadataState,
     /// Grouped provider state.
@@ -186,15 +201,21 @@ impl AppState {
     ) -> Self {
         Self {
             library: RwLock::new(library),
+            library_scan: ScanProgress::default(),
             metadata: MetadataState {
                 db: metadata_db,
                 musicbrainz,
                 wake: metadata_wake,
+                lyrics: OnceLock::new(),
             },
             providers: ProviderState {
                 bridge,
                 local,
                 cast,
+                airplay: Arc::new(AirplayProviderState::new()),
+                dlna: Arc::new(DlnaProviderState::new()),
+                snapcast: Arc::new(SnapcastProviderState::new()),
+                groups: Arc::new(OutputGroupProviderState::new()),
             },
             playback: PlaybackState {
                 manager: playback_manager,
@@ -248,6 +269,40 @@ pub struct DiscoveredCast {
     pub last_seen: std::time::Instant,
 }
 
+/// Discovered AirPlay (RAOP) receiver from mDNS.
+#[derive(Clone, Debug)]
+pub struct DiscoveredAirplay {
+    /// Stable receiver id (lowercase hardware address).
+    pub id: String,
+    /// Display name reported by discovery.
+    pub name: String,
+    /// Hostname or IP of the RTSP service.
+    pub host: String,
+    /// RTSP port.
+    pub port: u16,
+    /// Audio encoding the receiver accepts.
+    pub codec: crate::airplay::AirplayCodec,
+    /// Last-seen timestamp used for expiry.
+    pub last_seen: std::time::Instant,
+}
+
+/// Discovered UPnP/DLNA MediaRenderer from SSDP.
+#[derive(Clone, Debug)]
+pub struct DiscoveredDlna {
+    /// Stable renderer id (UDN without `uuid:`).
+    pub id: String,
+    /// Friendly name from the device description.
+    pub name: String,
+    /// Host serving the device description.
+    pub host: String,
+    /// `AVTransport` control URL.
+    pub av_transport_url: String,
+    /// `RenderingControl` control URL, when offered.
+    pub rendering_control_url: Option<String>,
+    /// Last-seen timestamp used for expiry.
+    pub last_seen: std::time::Instant,
+}
+
 /// Queue state backing the server queue service.
 #[derive(Debug, Default)]
 pub struct QueueState {
@@ -268,6 +323,28 @@ pub struct BridgeState {
     pub active_output_id: Option<String>,
 }
 
+/// Last known reachability of one bridge.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BridgeHealth {
+    /// Whether the device stream is currently connected.
+    pub online: bool,
+    /// Why the last connection attempt failed (offline only).
+    pub last_error: Option<String>,
+}
+
+/// Outcome of one discovered-bridge health check.
+#[derive(Clone, Debug)]
+pub struct BridgeProbe {
+    /// Whether the bridge answered `/health` successfully.
+    pub ok: bool,
+    /// When the check ran.
+    pub checked_at: std::time::SystemTime,
+    /// Time until the answer (or the failure).
+    pub latency: std::time::Duration,
+    /// Failure reason.
+    pub error: Option<String>,
+}
+
 /// Shared state for the bridge output provider.
 pub struct BridgeProviderState {
     /// Command channel for the active bridge player.
@@ -288,6 +365,10 @@ pub struct BridgeProviderState {
     /// Cached status snapshots by bridge id.
     pub status_cache:
         Arc<Mutex<std::collections::HashMap<String, crate::bridge_transport::HttpStatusResponse>>>,
+    /// Reachability by bridge id, kept by the device stream watchers.
+    pub health: Arc<Mutex<std::collections::HashMap<String, BridgeHealth>>>,
+    /// Last health check of each discovered bridge.
+    pub probes: Arc<Mutex<std::collections::HashMap<String, BridgeProbe>>>,
     /// Bridges that were already reset (stop command) after hub start.
     pub stop_on_join_done: Arc<Mutex<std::collections::HashSet<String>>>,
     /// Whether the bridge worker loop is running.
@@ -318,6 +399,8 @@ impl BridgeProviderState {
             device_cache: Arc::new(Mutex::new(std::collections::HashMap::new())),
             status_streams: Arc::new(Mutex::new(std::collections::HashSet::new())),
             status_cache: Arc::new(Mutex::new(std::collections::HashMap::new())),
+            health: Arc::new(Mutex::new(std::collections::HashMap::new())),
+            probes: Arc::new(Mutex::new(std::collections::HashMap::new())),
             stop_on_join_done: Arc::new(Mutex::new(std::collections::HashSet::new())),
             worker_running: Arc::new(AtomicBool::new(false)),
             output_switch_in_flight: Arc::new(AtomicBool::new(false)),
@@ -366,6 +449,107 @@ impl CastProviderState {
     }
 }
 
+/// Shared state for the AirPlay output provider.
+#[derive(Debug)]
+pub struct AirplayProviderState {
+    /// Discovered receivers keyed by receiver id.
+    pub discovered: Arc<Mutex<HashMap<String, DiscoveredAirplay>>>,
+    /// Active streaming workers keyed by output id.
+    pub workers: Arc<Mutex<HashMap<String, Sender<BridgeCommand>>>>,
+    /// Last known status per AirPlay output id.
+    pub status_by_output: Arc<Mutex<HashMap<String, BridgeStatus>>>,
+    /// Timestamp of last status update per AirPlay output id.
+    pub status_updated_at: Arc<Mutex<HashMap<String, std::time::Instant>>>,
+    /// Requested volume (percent, muted) per AirPlay output id; applied by the worker.
+    pub volume: Arc<Mutex<HashMap<String, (u8, bool)>>>,
+}
+
+impl AirplayProviderState {
+    /// Create an empty AirPlay provider state container.
+    pub fn new() -> Self {
+        Self {
+            discovered: Arc::new(Mutex::new(HashMap::new())),
+            workers: Arc::new(Mutex::new(HashMap::new())),
+            status_by_output: Arc::new(Mutex::new(HashMap::new())),
+            status_updated_at: Arc::new(Mutex::new(HashMap::new())),
+            volume: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+/// Shared state for the DLNA output provider.
+#[derive(Debug)]
+pub struct DlnaProviderState {
+    /// Discovered renderers keyed by renderer id.
+    pub discovered: Arc<Mutex<HashMap<String, DiscoveredDlna>>>,
+    /// Active control workers keyed by output id.
+    pub workers: Arc<Mutex<HashMap<String, Sender<BridgeCommand>>>>,
+    /// Last known status per DLNA output id.
+    pub status_by_output: Arc<Mutex<HashMap<String, BridgeStatus>>>,
+    /// Timestamp of last status update per DLNA output id.
+    pub status_updated_at: Arc<Mutex<HashMap<String, std::time::Instant>>>,
+}
+
+impl DlnaProviderState {
+    /// Create an empty DLNA provider state container.
+    pub fn new() -> Self {
+        Self {
+            discovered: Arc::new(Mutex::new(HashMap::new())),
+            workers: Arc::new(Mutex::new(HashMap::new())),
+            status_by_output: Arc::new(Mutex::new(HashMap::new())),
+            status_updated_at: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+/// Snapcast provider state.
+pub struct SnapcastProviderState {
+    /// Settings from `[snapcast]`; unset when Snapcast is not configured.
+    pub settings: OnceLock<crate::snapcast::SnapcastSettings>,
+    /// Groups (with clients) last reported by the server.
+    pub groups: Mutex<Vec<crate::snapcast::SnapcastGroup>>,
+    /// Feeder writing into the hub stream (shared by every Snapcast output).
+    pub worker: Mutex<Option<Sender<BridgeCommand>>>,
+    /// Snapcast output the feeder is playing for.
+    pub current_output: Mutex<Option<String>>,
+    /// Last feeder status and when it was stored.
+    pub status: Mutex<Option<(BridgeStatus, std::time::Instant)>>,
+}
+
+impl SnapcastProviderState {
+    /// Create an empty Snapcast provider state container.
+    pub fn new() -> Self {
+        Self {
+            settings: OnceLock::new(),
+            groups: Mutex::new(Vec::new()),
+            worker: Mutex::new(None),
+            current_output: Mutex::new(None),
+            status: Mutex::new(None),
+        }
+    }
+}
+
+/// Output group provider state.
+pub struct OutputGroupProviderState {
+    /// Groups from `[[output_groups]]`; unset until startup reads the config.
+    pub groups: OnceLock<Vec<crate::output_groups::OutputGroup>>,
+    /// Active group workers keyed by output id.
+    pub workers: Mutex<HashMap<String, Sender<BridgeCommand>>>,
+    /// Last merged status per group output id, with when it was stored.
+    pub status_by_output: Mutex<HashMap<String, (BridgeStatus, std::time::Instant)>>,
+}
+
+impl OutputGroupProviderState {
+    /// Create an empty output group provider state container.
+    pub fn new() -> Self {
+        Self {
+            groups: OnceLock::new(),
+            workers: Mutex::new(HashMap::new()),
+            status_by_output: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
 /// Output settings applied to provider listings.
 #[derive(Debug, Clone, Default)]
 pub struct OutputSettingsState {
@@ -375,6 +559,12 @@ pub struct OutputSettingsState {
     pub renames: HashMap<String, String>,
     /// Output ids that should request exclusive access.
     pub exclusive: HashSet<String>,
+    /// Output id -> pre-roll silence (ms) played when the output's stream opens.
+    pub preroll_silence_ms: HashMap<String, u32>,
+    /// Output id -> gain offset (dB) applied on top of the session volume.
+    pub volume_trim_db: HashMap<String, f32>,
+    /// Output id -> playback preferences, stored in the metadata DB rather than the config.
+    pub preferences: HashMap<String, crate::metadata_db::OutputPreferences>,
 }
 
 impl OutputSettingsState {
@@ -392,6 +582,14 @@ impl OutputSettingsState {
             if let Some(exclusive) = cfg.exclusive.as_ref() {
                 out.exclusive.extend(exclusive.iter().cloned());
             }
+            if let Some(preroll) = cfg.preroll_silence_ms.as_ref() {
+                out.preroll_silence_ms
+                    .extend(preroll.iter().map(|(k, v)| (k.clone(), *v)));
+            }
+            if let Some(trims) = cfg.volume_trim_db.as_ref() {
+                out.volume_trim_db
+                    .extend(trims.iter().map(|(k, v)| (k.clone(), *v)));
+            }
         }
         out
     }
@@ -403,6 +601,20 @@ impl OutputSettingsState {
         out.renames
             .extend(settings.renames.iter().map(|(k, v)| (k.clone(), v.clone())));
         out.exclusive.extend(settings.exclusive.iter().cloned());
+        out.preroll_silence_ms.extend(
+            settings
+                .preroll_silence_ms
+                .iter()
+                .filter(|(_, ms)| **ms > 0)
+                .map(|(k, v)| (k.clone(), *v)),
+        );
+        out.volume_trim_db.extend(
+            settings
+                .volume_trim_db
+                .iter()
+                .filter(|(_, db)| **db != 0.0)
+                .map(|(k, v)| (k.clone(), *v)),
+        );
         out
     }
 
@@ -412,6 +624,8 @@ impl OutputSettingsState {
             disabled: self.disabled.iter().cloned().collect(),
             renames: self.renames.clone(),
             exclusive: self.exclusive.iter().cloned().collect(),
+            preroll_silence_ms: self.preroll_silence_ms.clone(),
+            volume_trim_db: self.volume_trim_db.clone(),
         }
     }
 
@@ -433,6 +647,30 @@ impl OutputSettingsState {
             } else {
                 Some(self.exclusive.iter().cloned().collect())
             },
+            preroll_silence_ms: if self.preroll_silence_ms.is_empty() {
+                None
+            } else {
+                Some(self.preroll_silence_ms.clone())
+            },
+            volume_trim_db: if self.volume_trim_db.is_empty() {
+                None
+            } else {
+                Some(self.volume_trim_db.clone())
+            },
+        }
+    }
+
+    /// Returns `true` if the output is hidden from listings and selection.
+    pub fn is_disabled(&self, output_id: &str) -> bool {
+        self.disabled.contains(output_id)
+    }
+
+    /// Hide or unhide an output; returns whether the setting changed.
+    pub fn set_disabled(&mut self, output_id: &str, disabled: bool) -> bool {
+        if disabled {
+            self.disabled.insert(output_id.to_string())
+        } else {
+            self.disabled.remove(output_id)
         }
     }
 
@@ -440,6 +678,45 @@ impl OutputSettingsState {
     pub fn is_exclusive(&self, output_id: &str) -> bool {
         self.exclusive.contains(output_id)
     }
+
+    /// Options to send with a bridge device selection for `output_id`.
+    pub fn select_options(&self, output_id: &str) -> crate::bridge_transport::DeviceSelectOptions {
+        let prefs = self.preferences.get(output_id).cloned().unwrap_or_default();
+        crate::bridge_transport::DeviceSelectOptions {
+            exclusive: Some(
+                prefs
+                    .exclusive
+                    .unwrap_or_else(|| self.is_exclusive(output_id)),
+            ),
+            preroll_silence_ms: self.preroll_silence_ms.get(output_id).copied(),
+            resample_quality: prefs.resample_quality,
+            bit_depth: prefs.bit_depth,
+            max_sample_rate: prefs.max_sample_rate,
+        }
+    }
+
+    /// Linear factor of the output's volume trim (1.0 without one).
+    fn trim_factor(&self, output_id: &str) -> f32 {
+        self.volume_trim_db
+            .get(output_id)
+            .map_or(1.0, |db| 10f32.powf(db / 20.0))
+    }
+
+    /// Device volume for a session volume on `output_id`, with its trim applied.
+    ///
+    /// Volume percent scales amplitude, so a trim of -6 dB halves it. Positive trims stop at 100.
+    pub fn trimmed_volume(&self, output_id: &str, value: u8) -> u8 {
+        (f32::from(value) * self.trim_factor(output_id))
+            .round()
+            .clamp(0.0, 100.0) as u8
+    }
+
+    /// Session volume for a device volume read from `output_id`, with its trim removed.
+    pub fn untrimmed_volume(&self, output_id: &str, value: u8) -> u8 {
+        (f32::from(value) / self.trim_factor(output_id))
+            .round()
+            .clamp(0.0, 100.0) as u8
+    }
 }
 
 /// Selected output devices for local and bridge providers.
@@ -450,3 +727,26 @@ pub struct DeviceSelectionState {
     /// Selected device id by bridge id.
     pub bridge: Arc<Mutex<std::collections::HashMap<String, String>>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volume_trim_scales_amplitude_and_round_trips() {
+        let mut settings = OutputSettingsState::default();
+        settings
+            .volume_trim_db
+            .insert("bridge:a:dac".to_string(), -6.0206);
+        settings
+            .volume_trim_db
+            .insert("bridge:b:amp".to_string(), 6.0206);
+
+        assert_eq!(settings.trimmed_volume("bridge:a:dac", 80), 40);
+        assert_eq!(settings.untrimmed_volume("bridge:a:dac", 40), 80);
+        assert_eq!(settings.trimmed_volume("bridge:b:amp", 30), 60);
+        assert_eq!(settings.trimmed_volume("bridge:b:amp", 80), 100);
+        assert_eq!(settings.trimmed_volume("cast:x", 55), 55);
+        assert_eq!(settings.untrimmed_volume("cast:x", 55), 55);
+    }
+}
@@ -5,7 +5,7 @@
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
-use audio_bridge_types::BridgeStatus;
+use audio_bridge_types::{BridgeStatus, Chapter};
 
 use crate::events::EventBus;
 use crate::queue_service::AutoAdvanceInputs;
@@ -141,6 +141,7 @@ impl StatusStore {
             s.auto_advance_in_flight = false;
             s.seek_in_flight = false;
             s.manual_advance_in_flight = false;
+            s.chapters = None;
         });
         self.emit_if_changed(changed);
     }
@@ -201,6 +202,14 @@ impl StatusStore {
         self.emit_if_changed(changed);
     }
 
+    /// Record the chapter list of the locally playing file.
+    pub fn set_chapters(&self, chapters: Option<Vec<Chapter>>) {
+        let changed = self.update_status(|s| {
+            s.chapters = chapters;
+        });
+        self.emit_if_changed(changed);
+    }
+
     /// Clear local now-playing fields after local playback item completes.
     pub fn on_local_playback_end(&self) {
         let changed = self.update_status(|s| {
@@ -209,6 +218,7 @@ impl StatusStore {
             s.elapsed_ms = None;
             s.duration_ms = None;
             s.manual_advance_in_flight = false;
+            s.chapters = None;
         });
         self.emit_if_changed(changed);
     }
@@ -439,6 +449,17 @@ mod tests {
             buffer_capacity_frames: None,
             end_reason: None,
             output_nominal_rate: None,
+            output_disconnected: None,
+            output_mode: None,
+            resample_quality: None,
+            levels: None,
+            bit_perfect: None,
+            dop: None,
+            live: None,
+            stream_title: None,
+            corrupt_packets: None,
+            chapters: None,
+            chapter_index: None,
         }
     }
 
@@ -0,0 +1,364 @@
+//! Debug capture of stream responses sent to bridges, from `[stream_capture]` config.
+//!
+//! Each `/stream` or `/stream/transcode` response served to a bridge becomes one capture
+//! session in the configured directory:
+//! - `<bridge>-<unix_ms>-<seq>.log`: JSON lines with the request (method, URI, headers), the
+//!   response status/headers, one `chunk` entry per body chunk as it left the hub, and a
+//!   closing `end` entry.
+//! - `<bridge>-<unix_ms>-<seq>.bin`: the body bytes, truncated at `max_bytes`.
+//!
+//! Together they let a bridge-side complaint (wrong length, stalled range request, short
+//! body) be replayed offline against a different bridge version (`bridge replay`).
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+use actix_web::HttpRequest;
+use actix_web::http::StatusCode;
+use actix_web::http::header::HeaderName;
+use actix_web::web::Bytes;
+use anyhow::{Context, Result};
+use futures_util::{Stream, StreamExt};
+use serde_json::json;
+
+use crate::clock::now_ms;
+use crate::config::ServerConfig;
+use crate::state::AppState;
+
+/// Default per-session payload cap (16 MiB).
+const DEFAULT_MAX_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Active capture settings.
+#[derive(Debug)]
+pub struct StreamCapture {
+    /// Directory receiving capture files.
+    pub dir: PathBuf,
+    /// Payload bytes kept per session; later chunks are still logged.
+    pub max_bytes: u64,
+    /// Bridge ids to capture; `None` captures every bridge.
+    pub bridges: Option<HashSet<String>>,
+    seq: AtomicU64,
+}
+
+fn store() -> &'static Mutex<Option<Arc<StreamCapture>>> {
+    static STORE: OnceLock<Mutex<Option<Arc<StreamCapture>>>> = OnceLock::new();
+    STORE.get_or_init(|| Mutex::new(None))
+}
+
+/// Parse `[stream_capture]`; `None` when the section is absent or `enabled = false`.
+pub fn from_config(cfg: &ServerConfig) -> Result<Option<StreamCapture>> {
+    let Some(section) = cfg.stream_capture.as_ref() else {
+        return Ok(None);
+    };
+    if !section.enabled.unwrap_or(true) {
+        return Ok(None);
+    }
+    if section.max_bytes == Some(0) {
+        return Err(anyhow::anyhow!(
+            "stream_capture.max_bytes must be greater than 0"
+        ));
+    }
+    let dir = PathBuf::from(&section.dir);
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("create stream_capture dir {}", dir.display()))?;
+    Ok(Some(StreamCapture {
+        dir,
+        max_bytes: section.max_bytes.unwrap_or(DEFAULT_MAX_BYTES),
+        bridges: section
+            .bridges
+            .as_ref()
+            .map(|ids| ids.iter().map(|id| id.trim().to_string()).collect()),
+        seq: AtomicU64::new(0),
+    }))
+}
+
+/// Replace the active capture settings (`None` turns capture off).
+pub fn install(capture: Option<StreamCapture>) {
+    if let Some(capture) = capture.as_ref() {
+        tracing::warn!(
+            dir = %capture.dir.display(),
+            max_bytes = capture.max_bytes,
+            bridges = ?capture.bridges,
+            "stream capture enabled"
+        );
+    }
+    if let Ok(mut guard) = store().lock() {
+        *guard = capture.map(Arc::new);
+    }
+}
+
+/// Copy `stream` into a capture session when `req` comes from a captured bridge.
+///
+/// `status` and `headers` describe the response the stream is the body of.
+pub fn tee<S, E>(
+    state: &AppState,
+    req: &HttpRequest,
+    status: StatusCode,
+    headers: &[(HeaderName, String)],
+    stream: S,
+) -> impl Stream<Item = Result<Bytes, E>> + use<S, E>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+    E: std::fmt::Display,
+{
+    let session = store()
+        .lock()
+        .ok()
+        .and_then(|guard| guard.clone())
+        .and_then(|capture| {
+            let peer = req.peer_addr()?.ip();
+            let bridge = bridge_id_for_ip(state, peer)?;
+            if let Some(ids) = capture.bridges.as_ref()
+                && !ids.contains(&bridge)
+            {
+                return None;
+            }
+            match Session::open(&capture, &bridge, peer, req, status, headers) {
+                Ok(session) => Some(session),
+                Err(err) => {
+                    tracing::warn!(bridge, error = %err, "stream capture open failed");
+                    None
+                }
+            }
+        });
+    futures_util::stream::unfold(
+        (Box::pin(stream), session),
+        |(mut stream, mut session)| async move {
+            let item = stream.next().await;
+            if let Some(session) = session.as_mut() {
+                match &item {
+                    Some(Ok(chunk)) => session.chunk(chunk),
+                    Some(Err(err)) => session.error = Some(err.to_string()),
+                    None => session.complete = true,
+                }
+            }
+            Some((item?, (stream, session)))
+        },
+    )
+}
+
+/// Id of the configured or discovered bridge at `ip`.
+fn bridge_id_for_ip(state: &AppState, ip: IpAddr) -> Option<String> {
+    let bridges = &state.providers.bridge;
+    if let Ok(guard) = bridges.bridges.lock()
+        && let Some(bridge) = guard.bridges.iter().find(|b| b.http_addr.ip() == ip)
+    {
+        return Some(bridge.id.clone());
+    }
+    bridges.discovered_bridges.lock().ok().and_then(|map| {
+        map.values()
+            .find(|d| d.bridge.http_addr.ip() == ip)
+            .map(|d| d.bridge.id.clone())
+    })
+}
+
+/// One captured response; the closing `end` entry is written on drop, so a bridge that
+/// disconnects mid-body still leaves a complete log.
+struct Session {
+    log: BufWriter<File>,
+    payload: BufWriter<File>,
+    started: Instant,
+    max_bytes: u64,
+    sent_bytes: u64,
+    captured_bytes: u64,
+    complete: bool,
+    error: Option<String>,
+}
+
+impl Session {
+    fn open(
+        capture: &StreamCapture,
+        bridge: &str,
+        peer: IpAddr,
+        req: &HttpRequest,
+        status: StatusCode,
+        response_headers: &[(HeaderName, String)],
+    ) -> Result<Self> {
+        let started_at_ms = u64::try_from(now_ms()).unwrap_or(0);
+        let seq = capture.seq.fetch_add(1, Ordering::Relaxed);
+        let stem = format!("{}-{started_at_ms}-{seq}", file_safe(bridge));
+        let log_path = capture.dir.join(format!("{stem}.log"));
+        let payload_path = capture.dir.join(format!("{stem}.bin"));
+        let mut session = Self {
+            log: BufWriter::new(create(&log_path)?),
+            payload: BufWriter::new(create(&payload_path)?),
+            started: Instant::now(),
+            max_bytes: capture.max_bytes,
+            sent_bytes: 0,
+            captured_bytes: 0,
+            complete: false,
+            error: None,
+        };
+        let headers: serde_json::Map<String, serde_json::Value> = req
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).into(),
+                )
+            })
+            .collect();
+        session.line(json!({
+            "event": "request",
+            "bridge": bridge,
+            "peer": peer.to_string(),
+            "started_at_ms": started_at_ms,
+            "method": req.method().as_str(),
+            "uri": req.uri().to_string(),
+            "headers": headers,
+            "status": status.as_u16(),
+            "response_headers": response_headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), serde_json::Value::from(value.as_str())))
+                .collect::<serde_json::Map<_, _>>(),
+            "max_bytes": capture.max_bytes,
+        }));
+        tracing::info!(bridge, log = %log_path.display(), "stream capture started");
+        Ok(session)
+    }
+
+    fn chunk(&mut self, chunk: &Bytes) {
+        let room = self.max_bytes.saturating_sub(self.captured_bytes);
+        let keep = (chunk.len() as u64).min(room) as usize;
+        if keep > 0 {
+            if let Err(err) = self.payload.write_all(&chunk[..keep]) {
+                tracing::warn!(error = %err, "stream capture payload write failed");
+                self.max_bytes = self.captured_bytes;
+            } else {
+                self.captured_bytes += keep as u64;
+            }
+        }
+        self.line(json!({
+            "event": "chunk",
+            "t_ms": self.started.elapsed().as_millis() as u64,
+            "offset": self.sent_bytes,
+            "len": chunk.len(),
+            "captured": keep,
+        }));
+        self.sent_bytes += chunk.len() as u64;
+    }
+
+    fn line(&mut self, value: serde_json::Value) {
+        if let Err(err) = writeln!(self.log, "{value}") {
+            tracing::warn!(error = %err, "stream capture log write failed");
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.line(json!({
+            "event": "end",
+            "t_ms": self.started.elapsed().as_millis() as u64,
+            "sent_bytes": self.sent_bytes,
+            "captured_bytes": self.captured_bytes,
+            "complete": self.complete,
+            "error": self.error,
+        }));
+        let _ = self.log.flush();
+        let _ = self.payload.flush();
+    }
+}
+
+fn create(path: &Path) -> Result<File> {
+    File::create(path).with_context(|| format!("create {}", path.display()))
+}
+
+/// Bridge ids are user-chosen; keep them to characters safe in any filename.
+fn file_safe(id: &str) -> String {
+    id.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capture(dir: &Path, max_bytes: u64) -> StreamCapture {
+        StreamCapture {
+            dir: dir.to_path_buf(),
+            max_bytes,
+            bridges: None,
+            seq: AtomicU64::new(0),
+        }
+    }
+
+    #[test]
+    fn session_truncates_payload_and_logs_every_chunk() {
+        let dir = std::env::temp_dir().join(format!(
+            "audio-hub-stream-capture-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        let req = actix_web::test::TestRequest::get()
+            .uri("/stream/track/7")
+            .insert_header(("range", "bytes=0-"))
+            .to_http_request();
+        let peer: IpAddr = "192.168.1.20".parse().unwrap();
+        let mut session = Session::open(
+            &capture(&dir, 5),
+            "living room",
+            peer,
+            &req,
+            StatusCode::PARTIAL_CONTENT,
+            &[(
+                actix_web::http::header::CONTENT_RANGE,
+                "bytes 0-7/8".to_string(),
+            )],
+        )
+        .expect("open session");
+        session.chunk(&Bytes::from_static(b"abcd"));
+        session.chunk(&Bytes::from_static(b"efgh"));
+        session.complete = true;
+        drop(session);
+
+        let mut files: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        files.sort();
+        assert_eq!(files.len(), 2);
+        assert!(
+            files[0]
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with("living_room-")
+        );
+        assert_eq!(std::fs::read(&files[0]).unwrap(), b"abcde");
+
+        let log = std::fs::read_to_string(&files[1]).unwrap();
+        let lines: Vec<serde_json::Value> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["event"], "request");
+        assert_eq!(lines[0]["status"], 206);
+        assert_eq!(lines[0]["headers"]["range"], "bytes=0-");
+        assert_eq!(lines[0]["response_headers"]["content-range"], "bytes 0-7/8");
+        assert_eq!(lines[2]["offset"], 4);
+        assert_eq!(lines[2]["captured"], 1);
+        assert_eq!(lines[3]["event"], "end");
+        assert_eq!(lines[3]["sent_bytes"], 8);
+        assert_eq!(lines[3]["complete"], true);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
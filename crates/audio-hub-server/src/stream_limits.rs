@@ -0,0 +1,225 @@
+//! Bandwidth limits for library stream/transcode responses from `[stream_limits]` config.
+//!
+//! Bulk clients (phones syncing playlists, downloads) are paced per connection and against a
+//! shared global budget. Bridges, cast devices, DLNA renderers, and `exempt_ips` are realtime
+//! consumers and are never throttled.
+
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use actix_web::web::Bytes;
+use anyhow::{Context, Result};
+use futures_util::{Stream, StreamExt};
+
+use crate::config::ServerConfig;
+use crate::state::AppState;
+
+/// Active limits (bytes per second) and the exempt peer list.
+#[derive(Debug, Default)]
+pub struct StreamLimits {
+    /// Cap for a single response.
+    pub per_connection_bps: Option<u64>,
+    /// Shared cap across all throttled responses.
+    pub global_bps: Option<u64>,
+    /// Extra peers never throttled (e.g. web players on the LAN).
+    pub exempt_ips: HashSet<IpAddr>,
+}
+
+struct Limiter {
+    limits: StreamLimits,
+    global: Option<Arc<Mutex<Pacer>>>,
+}
+
+fn store() -> &'static Mutex<Option<Arc<Limiter>>> {
+    static STORE: OnceLock<Mutex<Option<Arc<Limiter>>>> = OnceLock::new();
+    STORE.get_or_init(|| Mutex::new(None))
+}
+
+/// Parse `[stream_limits]` into byte rates (`kbps` are kilobits per second).
+pub fn from_config(cfg: &ServerConfig) -> Result<StreamLimits> {
+    let Some(section) = cfg.stream_limits.as_ref() else {
+        return Ok(StreamLimits::default());
+    };
+    let exempt_ips = section
+        .exempt_ips
+        .iter()
+        .flatten()
+        .map(|ip| {
+            ip.parse::<IpAddr>()
+                .with_context(|| format!("parse stream_limits exempt_ip {ip}"))
+        })
+        .collect::<Result<_>>()?;
+    Ok(StreamLimits {
+        per_connection_bps: kbps_to_bps("per_connection_kbps", section.per_connection_kbps)?,
+        global_bps: kbps_to_bps("global_kbps", section.global_kbps)?,
+        exempt_ips,
+    })
+}
+
+fn kbps_to_bps(field: &str, kbps: Option<u64>) -> Result<Option<u64>> {
+    match kbps {
+        Some(0) => Err(anyhow::anyhow!(
+            "stream_limits.{field} must be greater than 0"
+        )),
+        Some(kbps) => Ok(Some(kbps.saturating_mul(1000) / 8)),
+        None => Ok(None),
+    }
+}
+
+/// Replace the active limits (resets the global budget).
+pub fn install(limits: StreamLimits) {
+    let limiter = if limits.per_connection_bps.is_none() && limits.global_bps.is_none() {
+        None
+    } else {
+        let global = limits
+            .global_bps
+            .map(|bps| Arc::new(Mutex::new(Pacer::new(bps))));
+        Some(Arc::new(Limiter { limits, global }))
+    };
+    if let Ok(mut guard) = store().lock() {
+        *guard = limiter;
+    }
+}
+
+/// Pace `stream` for `peer` unless limits are off or the peer is a realtime consumer.
+pub fn throttle<S, E>(
+    state: &AppState,
+    peer: Option<IpAddr>,
+    stream: S,
+) -> impl Stream<Item = Result<Bytes, E>> + use<S, E>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+{
+    let limiter = store().lock().ok().and_then(|guard| guard.clone());
+    let limiter = limiter.filter(|limiter| match peer {
+        Some(ip) => !limiter.limits.exempt_ips.contains(&ip) && !is_realtime_peer(state, ip),
+        None => true,
+    });
+    let per_connection = limiter
+        .as_ref()
+        .and_then(|limiter| limiter.limits.per_connection_bps)
+        .map(Pacer::new);
+    futures_util::stream::unfold(
+        (stream, limiter, per_connection),
+        |(mut stream, limiter, mut per_connection)| async move {
+            let item = stream.next().await?;
+            if let (Ok(chunk), Some(limiter)) = (&item, limiter.as_ref()) {
+                let now = Instant::now();
+                let len = chunk.len() as u64;
+                let local = per_connection
+                    .as_mut()
+                    .map(|pacer| pacer.reserve(len, now))
+                    .unwrap_or_default();
+                let global = limiter
+                    .global
+                    .as_ref()
+                    .and_then(|pacer| pacer.lock().ok().map(|mut pacer| pacer.reserve(len, now)))
+                    .unwrap_or_default();
+                let wait = local.max(global);
+                if !wait.is_zero() {
+                    tokio::time::sleep(wait).await;
+                }
+            }
+            Some((item, (stream, limiter, per_connection)))
+        },
+    )
+}
+
+/// Whether `ip` belongs to a known bridge (configured or discovered), cast device or DLNA renderer.
+pub(crate) fn is_realtime_peer(state: &AppState, ip: IpAddr) -> bool {
+    let bridges = &state.providers.bridge;
+    if let Ok(guard) = bridges.bridges.lock()
+        && guard.bridges.iter().any(|b| b.http_addr.ip() == ip)
+    {
+        return true;
+    }
+    if let Ok(map) = bridges.discovered_bridges.lock()
+        && map.values().any(|d| d.bridge.http_addr.ip() == ip)
+    {
+        return true;
+    }
+    if let Ok(map) = state.providers.cast.discovered.lock()
+        && map
+            .values()
+            .filter_map(|cast| cast.host.as_deref())
+            .any(|host| host.parse::<IpAddr>().ok() == Some(ip))
+    {
+        return true;
+    }
+    if let Ok(map) = state.providers.dlna.discovered.lock()
+        && map
+            .values()
+            .any(|renderer| renderer.host.parse::<IpAddr>().ok() == Some(ip))
+    {
+        return true;
+    }
+    false
+}
+
+/// Virtual-clock pacer: each reservation occupies the link for `bytes / rate`.
+#[derive(Debug)]
+struct Pacer {
+    bytes_per_sec: u64,
+    next_free: Option<Instant>,
+}
+
+impl Pacer {
+    fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec: bytes_per_sec.max(1),
+            next_free: None,
+        }
+    }
+
+    /// Reserve `bytes` and return how long to wait before sending them.
+    fn reserve(&mut self, bytes: u64, now: Instant) -> Duration {
+        let start = match self.next_free {
+            Some(next) if next > now => next,
+            _ => now,
+        };
+        let cost = Duration::from_secs_f64(bytes as f64 / self.bytes_per_sec as f64);
+        self.next_free = Some(start + cost);
+        start - now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pacer_spaces_chunks_by_rate() {
+        let mut pacer = Pacer::new(1000);
+        let now = Instant::now();
+        assert_eq!(pacer.reserve(500, now), Duration::ZERO);
+        assert_eq!(pacer.reserve(500, now), Duration::from_millis(500));
+        assert_eq!(pacer.reserve(1000, now), Duration::from_secs(1));
+        // Idle time is not banked as burst credit.
+        let later = now + Duration::from_secs(10);
+        assert_eq!(pacer.reserve(1000, later), Duration::ZERO);
+        assert_eq!(pacer.reserve(1, later), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn from_config_converts_kbps_and_parses_exempt_ips() {
+        let cfg: ServerConfig = toml::from_str(
+            r#"
+            [stream_limits]
+            per_connection_kbps = 8000
+            global_kbps = 16000
+            exempt_ips = ["192.168.1.50"]
+            "#,
+        )
+        .expect("parse config");
+        let limits = from_config(&cfg).unwrap();
+        assert_eq!(limits.per_connection_bps, Some(1_000_000));
+        assert_eq!(limits.global_bps, Some(2_000_000));
+        assert!(
+            limits
+                .exempt_ips
+                .contains(&"192.168.1.50".parse::<IpAddr>().unwrap())
+        );
+    }
+}
@@ -15,8 +15,14 @@ pub fn build_stream_url_for(
         .ok_or_else(|| anyhow::anyhow!("metadata database is required to build stream url"))?
         .track_id_for_path(&path.to_string_lossy())?
         .ok_or_else(|| anyhow::anyhow!("track id not found for path {}", path.display()))?;
-    Ok(format!(
-        "{}/stream/track/{track_id}",
-        public_base_url.trim_end_matches('/')
-    ))
+    Ok(track_stream_url(public_base_url, track_id))
+}
+
+/// Build the `/stream/track/{id}` URL, carrying the stream token (see [`crate::auth`]).
+pub fn track_stream_url(public_base_url: &str, track_id: i64) -> String {
+    format!(
+        "{}/stream/track/{track_id}?access_token={}",
+        public_base_url.trim_end_matches('/'),
+        crate::auth::stream_token()
+    )
 }
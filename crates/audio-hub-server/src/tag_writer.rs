@@ -2,7 +2,12 @@ use std::collections::{BTreeMap, HashSet};
 use std::path::Path;
 
 use anyhow::{Context, Result};
-use lofty::{Accessor, AudioFile, ItemKey, ItemValue, Tag, TagType, TaggedFileExt, read_from_path};
+use lofty::{
+    Accessor, AudioFile, ItemKey, ItemValue, Tag, TagType, TaggedFile, TaggedFileExt,
+    read_from_path,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 const STANDARD_VORBIS_KEYS: &[&str] = &[
     "TITLE",
@@ -45,17 +50,116 @@ pub struct TrackTagUpdate<'a> {
     pub clear_extra_tags: Option<&'a HashSet<String>>,
 }
 
+/// One tag field a metadata update would change.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TagFieldChange {
+    /// Field name (`title`, ..., or an extra tag key).
+    pub field: String,
+    /// Value currently in the file.
+    pub current: Option<String>,
+    /// Value after the update (`None` when cleared).
+    pub new: Option<String>,
+}
+
+impl TrackTagUpdate<'_> {
+    /// Drop changes to locked standard fields, returning the locked fields it touched.
+    pub fn skip_locked(&mut self, locked: &HashSet<String>) -> Vec<String> {
+        fn skip<T>(
+            field: &str,
+            value: &mut Option<T>,
+            clear: &mut bool,
+            locked: &HashSet<String>,
+            skipped: &mut Vec<String>,
+        ) {
+            if locked.contains(field) && (value.is_some() || *clear) {
+                *value = None;
+                *clear = false;
+                skipped.push(field.to_string());
+            }
+        }
+        let mut skipped = Vec::new();
+        skip(
+            "title",
+            &mut self.title,
+            &mut self.clear_title,
+            locked,
+            &mut skipped,
+        );
+        skip(
+            "artist",
+            &mut self.artist,
+            &mut self.clear_artist,
+            locked,
+            &mut skipped,
+        );
+        skip(
+            "album",
+            &mut self.album,
+            &mut self.clear_album,
+            locked,
+            &mut skipped,
+        );
+        skip(
+            "album_artist",
+            &mut self.album_artist,
+            &mut self.clear_album_artist,
+            locked,
+            &mut skipped,
+        );
+        skip(
+            "year",
+            &mut self.year,
+            &mut self.clear_year,
+            locked,
+            &mut skipped,
+        );
+        skip(
+            "track_number",
+            &mut self.track_number,
+            &mut self.clear_track_number,
+            locked,
+            &mut skipped,
+        );
+        skip(
+            "disc_number",
+            &mut self.disc_number,
+            &mut self.clear_disc_number,
+            locked,
+            &mut skipped,
+        );
+        skipped
+    }
+
+    /// Whether the update changes nothing.
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.artist.is_none()
+            && self.album.is_none()
+            && self.album_artist.is_none()
+            && self.year.is_none()
+            && self.track_number.is_none()
+            && self.disc_number.is_none()
+            && self.extra_tags.is_none_or(|tags| tags.is_empty())
+            && !self.clear_title
+            && !self.clear_artist
+            && !self.clear_album
+            && !self.clear_album_artist
+            && !self.clear_year
+            && !self.clear_track_number
+            && !self.clear_disc_number
+            && self.clear_extra_tags.is_none_or(|keys| keys.is_empty())
+    }
+}
+
+/// Return whether a track field can be locked against album-wide updates.
+pub fn is_lockable_field(field: &str) -> bool {
+    STANDARD_TRACK_FIELDS.contains(&field)
+}
+
 /// Write selected metadata fields into track tags using lofty.
 pub fn write_track_tags(path: &Path, update: TrackTagUpdate<'_>) -> Result<()> {
     let mut tagged_file = read_from_path(path).context("read tags")?;
-    let mut tag_type = tagged_file.primary_tag_type();
-    if tagged_file.tag(tag_type).is_none() {
-        if let Some(tag) = tagged_file.first_tag() {
-            tag_type = tag.tag_type();
-        } else if let Some(default) = default_tag_type(path) {
-            tag_type = default;
-        }
-    }
+    let tag_type = writable_tag_type(&tagged_file, path);
     let tag = match tagged_file.tag_mut(tag_type) {
         Some(tag) => tag,
         None => {
@@ -65,7 +169,90 @@ pub fn write_track_tags(path: &Path, update: TrackTagUpdate<'_>) -> Result<()> {
                 .context("create tag container")?
         }
     };
+    apply_update(tag, tag_type, &update);
+    tagged_file.save_to_path(path).context("write tags")?;
+    Ok(())
+}
+
+/// List the tag changes `write_track_tags` would make, without touching the file.
+pub fn preview_track_tags(path: &Path, update: &TrackTagUpdate<'_>) -> Result<Vec<TagFieldChange>> {
+    let tagged_file = read_from_path(path).context("read tags")?;
+    let tag_type = writable_tag_type(&tagged_file, path);
+    let before = tagged_file
+        .tag(tag_type)
+        .cloned()
+        .unwrap_or_else(|| Tag::new(tag_type));
+    let mut after = before.clone();
+    apply_update(&mut after, tag_type, update);
+
+    let mut extra_keys: Vec<String> = update
+        .extra_tags
+        .into_iter()
+        .flat_map(|tags| tags.keys())
+        .chain(update.clear_extra_tags.into_iter().flatten())
+        .map(|key| key.trim().to_ascii_uppercase())
+        .filter(|key| !key.is_empty())
+        .collect();
+    extra_keys.sort();
+    extra_keys.dedup();
+    let fields = STANDARD_TRACK_FIELDS
+        .iter()
+        .map(|field| field.to_string())
+        .chain(extra_keys);
+    Ok(fields
+        .filter_map(|field| {
+            let current = field_value(&before, tag_type, &field);
+            let new = field_value(&after, tag_type, &field);
+            (current != new).then_some(TagFieldChange {
+                field,
+                current,
+                new,
+            })
+        })
+        .collect())
+}
 
+/// Tag type updates are written to: the file's primary tag, else its first, else the default.
+fn writable_tag_type(tagged_file: &TaggedFile, path: &Path) -> TagType {
+    let mut tag_type = tagged_file.primary_tag_type();
+    if tagged_file.tag(tag_type).is_none() {
+        if let Some(tag) = tagged_file.first_tag() {
+            tag_type = tag.tag_type();
+        } else if let Some(default) = default_tag_type(path) {
+            tag_type = default;
+        }
+    }
+    tag_type
+}
+
+/// Read one standard field or extra tag key as text.
+fn field_value(tag: &Tag, tag_type: TagType, field: &str) -> Option<String> {
+    let value = match field {
+        "title" => tag.title().map(|v| v.to_string()),
+        "artist" => tag.artist().map(|v| v.to_string()),
+        "album" => tag.album().map(|v| v.to_string()),
+        "album_artist" => tag.get_string(&ItemKey::AlbumArtist).map(str::to_string),
+        "year" => tag.get_string(&ItemKey::Year).map(str::to_string),
+        "track_number" => tag.track().map(|v| v.to_string()),
+        "disc_number" => tag.disk().map(|v| v.to_string()),
+        key if tag_type == TagType::VorbisComments => tag
+            .items()
+            .find(|item| {
+                item.key()
+                    .map_key(TagType::VorbisComments, true)
+                    .is_some_and(|existing| existing.eq_ignore_ascii_case(key))
+            })
+            .and_then(|item| item.value().text())
+            .map(str::to_string),
+        key => tag
+            .get_string(&ItemKey::from_key(tag_type, key))
+            .map(str::to_string),
+    };
+    value.filter(|v| !v.trim().is_empty())
+}
+
+/// Apply an update to an in-memory tag.
+fn apply_update(tag: &mut Tag, tag_type: TagType, update: &TrackTagUpdate<'_>) {
     if update.clear_title {
         tag.remove_title();
     }
@@ -147,9 +334,6 @@ pub fn write_track_tags(path: &Path, update: TrackTagUpdate<'_>) -> Result<()> {
             }
         }
     }
-
-    tagged_file.save_to_path(path).context("write tags")?;
-    Ok(())
 }
 
 /// Return default tag type inferred from file extension.
@@ -278,6 +462,20 @@ pub fn tag_type_label(tag_type: TagType) -> &'static str {
     }
 }
 
+#[cfg(test)]
+/// Minimal FLAC file: the marker, a STREAMINFO block (44.1 kHz, stereo, 16-bit) and padding.
+pub(crate) fn minimal_flac() -> Vec<u8> {
+    let mut data = b"fLaC".to_vec();
+    data.extend_from_slice(&[0x00, 0x00, 0x00, 0x22]);
+    data.extend_from_slice(&[0x10, 0x00, 0x10, 0x00, 0, 0, 0, 0, 0, 0]);
+    data.extend_from_slice(&[0x0A, 0xC4, 0x42, 0xF0, 0, 0, 0, 0]);
+    data.extend_from_slice(&[0; 16]);
+    // lofty 0.18 fails to save FLAC files without a PADDING block.
+    data.extend_from_slice(&[0x81, 0x00, 0x00, 0x40]);
+    data.extend_from_slice(&[0; 64]);
+    data
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -305,4 +503,85 @@ mod tests {
             Some(TagType::Id3v2)
         );
     }
+
+    fn empty_update<'a>() -> TrackTagUpdate<'a> {
+        TrackTagUpdate {
+            title: None,
+            artist: None,
+            album: None,
+            album_artist: None,
+            year: None,
+            track_number: None,
+            disc_number: None,
+            extra_tags: None,
+            clear_title: false,
+            clear_artist: false,
+            clear_album: false,
+            clear_album_artist: false,
+            clear_year: false,
+            clear_track_number: false,
+            clear_disc_number: false,
+            clear_extra_tags: None,
+        }
+    }
+
+    #[test]
+    fn preview_lists_changes_without_writing_and_locks_skip_fields() {
+        let path = std::env::temp_dir().join(format!(
+            "audio-hub-tag-preview-{}.flac",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        std::fs::write(&path, minimal_flac()).unwrap();
+        write_track_tags(
+            &path,
+            TrackTagUpdate {
+                title: Some("Old"),
+                album: Some("Album"),
+                ..empty_update()
+            },
+        )
+        .expect("write tags");
+
+        let extra = BTreeMap::from([("MOOD".to_string(), "calm".to_string())]);
+        let update = TrackTagUpdate {
+            title: Some("New"),
+            album: Some("Album"),
+            extra_tags: Some(&extra),
+            clear_album_artist: true,
+            ..empty_update()
+        };
+        let changes = preview_track_tags(&path, &update).expect("preview");
+        assert_eq!(
+            changes,
+            [
+                TagFieldChange {
+                    field: "title".to_string(),
+                    current: Some("Old".to_string()),
+                    new: Some("New".to_string()),
+                },
+                TagFieldChange {
+                    field: "MOOD".to_string(),
+                    current: None,
+                    new: Some("calm".to_string()),
+                },
+            ]
+        );
+        let tags = read_vorbis_comment_tags(&path).expect("read tags");
+        assert_eq!(tags.get("TITLE").map(String::as_str), Some("Old"));
+        assert!(!tags.contains_key("MOOD"));
+
+        let mut locked_update = TrackTagUpdate {
+            title: Some("New"),
+            clear_album: true,
+            ..empty_update()
+        };
+        let locked = HashSet::from(["title".to_string(), "album".to_string()]);
+        assert_eq!(locked_update.skip_locked(&locked), ["title", "album"]);
+        assert!(locked_update.is_empty());
+
+        let _ = std::fs::remove_file(&path);
+    }
 }
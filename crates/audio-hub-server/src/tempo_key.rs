@@ -0,0 +1,352 @@
+//! Tempo (BPM) and musical key estimation and the background job that stores them.
+//!
+//! Tempo comes from the autocorrelation of a spectral-flux onset envelope; key from a
+//! chromagram matched against the Krumhansl-Kessler major/minor profiles. Both look at the
+//! first couple of minutes of a track, which is plenty for steady-tempo material.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use rustfft::{FftPlanner, num_complex::Complex};
+
+use crate::metadata_db::{MetadataDb, TempoKeyCandidate, TempoKeyResult};
+use crate::metadata_service::MetadataService;
+use crate::state::MetadataWake;
+use crate::track_analysis::TrackReader;
+
+/// Seconds of audio analyzed per track.
+const MAX_SECONDS: usize = 120;
+/// Onset envelope frames per second.
+const ONSET_FPS: u32 = 100;
+/// Tempo search range in BPM.
+const MIN_BPM: f64 = 60.0;
+const MAX_BPM: f64 = 200.0;
+/// Tempo the estimate is biased towards when several multiples fit about as well.
+const PREFERRED_BPM: f64 = 120.0;
+/// Width of the tempo prior, in octaves.
+const TEMPO_PRIOR_OCTAVES: f64 = 1.0;
+/// Frequency range feeding the chromagram.
+const CHROMA_MIN_HZ: f32 = 100.0;
+const CHROMA_MAX_HZ: f32 = 5000.0;
+
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+/// Krumhansl-Kessler key profiles, starting at the tonic.
+const MAJOR_PROFILE: [f64; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+const MINOR_PROFILE: [f64; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+/// Estimated tempo and key of one track.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TempoKey {
+    /// Beats per minute, rounded to 0.1.
+    pub bpm: Option<f64>,
+    /// Key name such as `A minor` or `F# major`.
+    pub key: Option<String>,
+}
+
+/// Decode the start of `path` (or the cue range it names) and estimate tempo and key.
+pub fn analyze_tempo_key(path: &Path) -> Result<TempoKey> {
+    let mut reader = TrackReader::open(path)?;
+    let channels = reader.channels;
+    let sample_rate = reader.sample_rate;
+    let max_frames = MAX_SECONDS * sample_rate as usize;
+    let mut mono = Vec::new();
+    while mono.len() < max_frames {
+        let Some(samples) = reader.next_chunk() else {
+            break;
+        };
+        mono.extend(
+            samples
+                .chunks_exact(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+        );
+    }
+    mono.truncate(max_frames);
+    Ok(estimate(&mono, sample_rate))
+}
+
+/// Estimate tempo and key of mono audio at `sample_rate`.
+pub fn estimate(mono: &[f32], sample_rate: u32) -> TempoKey {
+    let (envelope, fps) = onset_envelope(mono, sample_rate);
+    TempoKey {
+        bpm: estimate_bpm(&envelope, fps),
+        key: estimate_key(&chromagram(mono, sample_rate)),
+    }
+}
+
+fn hann(size: usize) -> Vec<f32> {
+    (0..size)
+        .map(|i| {
+            let phase = 2.0 * std::f32::consts::PI * i as f32 / (size - 1) as f32;
+            0.5 - 0.5 * phase.cos()
+        })
+        .collect()
+}
+
+/// Magnitude spectra of Hann-windowed frames of `size` samples every `hop` samples.
+fn spectra(mono: &[f32], size: usize, hop: usize) -> impl Iterator<Item = Vec<f32>> + use<'_> {
+    let fft = FftPlanner::<f32>::new().plan_fft_forward(size);
+    let window = hann(size);
+    let mut buffer = vec![Complex::new(0.0, 0.0); size];
+    (0..mono.len().saturating_sub(size) / hop).map(move |frame| {
+        let start = frame * hop;
+        for (slot, (sample, w)) in buffer
+            .iter_mut()
+            .zip(mono[start..start + size].iter().zip(&window))
+        {
+            *slot = Complex::new(sample * w, 0.0);
+        }
+        fft.process(&mut buffer);
+        buffer[..size / 2].iter().map(|c| c.norm()).collect()
+    })
+}
+
+/// Spectral-flux onset strength, with the local mean removed, and its frame rate.
+fn onset_envelope(mono: &[f32], sample_rate: u32) -> (Vec<f32>, f64) {
+    let hop = (sample_rate / ONSET_FPS).max(1) as usize;
+    let size = ((sample_rate / 40) as usize).next_power_of_two().max(64);
+    let mut previous: Option<Vec<f32>> = None;
+    let mut flux = Vec::new();
+    for spectrum in spectra(mono, size, hop) {
+        let compressed: Vec<f32> = spectrum.iter().map(|m| (1.0 + 100.0 * m).ln()).collect();
+        if let Some(previous) = &previous {
+            flux.push(
+                compressed
+                    .iter()
+                    .zip(previous)
+                    .map(|(now, before)| (now - before).max(0.0))
+                    .sum::<f32>(),
+            );
+        }
+        previous = Some(compressed);
+    }
+
+    // Subtracting a one-second running mean keeps sustained loudness changes out of the
+    // autocorrelation.
+    let span = ONSET_FPS as usize / 2;
+    let envelope = (0..flux.len())
+        .map(|i| {
+            let window = &flux[i.saturating_sub(span)..(i + span + 1).min(flux.len())];
+            let mean = window.iter().sum::<f32>() / window.len() as f32;
+            (flux[i] - mean).max(0.0)
+        })
+        .collect();
+    (envelope, f64::from(sample_rate) / hop as f64)
+}
+
+/// Tempo whose beat period best matches the envelope's autocorrelation, weighted towards
+/// `PREFERRED_BPM` so half/double-time readings lose close calls.
+fn estimate_bpm(envelope: &[f32], fps: f64) -> Option<f64> {
+    let min_lag = (60.0 * fps / MAX_BPM).floor() as usize;
+    let max_lag = (60.0 * fps / MIN_BPM).ceil() as usize;
+    if min_lag < 2 || envelope.len() < max_lag * 2 {
+        return None;
+    }
+    let acf: Vec<f64> = (0..=max_lag + 1)
+        .map(|lag| {
+            let sum: f64 = envelope[..envelope.len() - lag]
+                .iter()
+                .zip(&envelope[lag..])
+                .map(|(a, b)| f64::from(*a) * f64::from(*b))
+                .sum();
+            sum / (envelope.len() - lag) as f64
+        })
+        .collect();
+    let weight = |lag: f64| {
+        let octaves = (60.0 * fps / lag / PREFERRED_BPM).log2() / TEMPO_PRIOR_OCTAVES;
+        (-0.5 * octaves * octaves).exp()
+    };
+    let best = (min_lag..=max_lag)
+        .max_by(|a, b| (acf[*a] * weight(*a as f64)).total_cmp(&(acf[*b] * weight(*b as f64))))?;
+    if acf[best] <= 0.0 {
+        return None;
+    }
+    // Parabolic interpolation around the peak for sub-frame precision.
+    let (left, center, right) = (acf[best - 1], acf[best], acf[best + 1]);
+    let denominator = left - 2.0 * center + right;
+    let shift = if denominator.abs() > f64::EPSILON {
+        (0.5 * (left - right) / denominator).clamp(-0.5, 0.5)
+    } else {
+        0.0
+    };
+    let bpm = 60.0 * fps / (best as f64 + shift);
+    Some((bpm * 10.0).round() / 10.0)
+}
+
+/// Summed spectral magnitude per pitch class (C = 0).
+fn chromagram(mono: &[f32], sample_rate: u32) -> [f64; 12] {
+    let size = ((sample_rate / 8) as usize).next_power_of_two().max(64);
+    let bin_hz = sample_rate as f32 / size as f32;
+    let pitch_classes: Vec<Option<usize>> = (0..size / 2)
+        .map(|bin| {
+            let hz = bin as f32 * bin_hz;
+            (CHROMA_MIN_HZ..=CHROMA_MAX_HZ).contains(&hz).then(|| {
+                let midi = 69.0 + 12.0 * (hz / 440.0).log2();
+                (midi.round() as i64).rem_euclid(12) as usize
+            })
+        })
+        .collect();
+    let mut chroma = [0.0; 12];
+    for spectrum in spectra(mono, size, size / 2) {
+        for (magnitude, pitch_class) in spectrum.iter().zip(&pitch_classes) {
+            if let Some(pitch_class) = pitch_class {
+                chroma[*pitch_class] += f64::from(*magnitude);
+            }
+        }
+    }
+    chroma
+}
+
+/// Key whose profile correlates best with `chroma`; `None` for silence.
+fn estimate_key(chroma: &[f64; 12]) -> Option<String> {
+    if chroma.iter().sum::<f64>() <= 1e-6 {
+        return None;
+    }
+    let correlation = |profile: &[f64; 12], tonic: usize| {
+        let rotated: Vec<f64> = (0..12).map(|i| chroma[(i + tonic) % 12]).collect();
+        pearson(&rotated, profile)
+    };
+    let (tonic, mode, _) = (0..12)
+        .flat_map(|tonic| {
+            [
+                (tonic, "major", correlation(&MAJOR_PROFILE, tonic)),
+                (tonic, "minor", correlation(&MINOR_PROFILE, tonic)),
+            ]
+        })
+        .max_by(|a, b| a.2.total_cmp(&b.2))?;
+    Some(format!("{} {mode}", NOTE_NAMES[tonic]))
+}
+
+fn pearson(a: &[f64], b: &[f64]) -> f64 {
+    let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        cov += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a) * (x - mean_a);
+        var_b += (y - mean_b) * (y - mean_b);
+    }
+    if var_a <= 0.0 || var_b <= 0.0 {
+        0.0
+    } else {
+        cov / (var_a * var_b).sqrt()
+    }
+}
+
+/// Run tempo/key analysis on a background thread.
+///
+/// Tracks never analyzed, or modified since, are processed in batches; once the library is
+/// covered the loop sleeps until `wake` fires (e.g. after a rescan).
+pub fn spawn_tempo_key_loop(db: MetadataDb, root: PathBuf, wake: MetadataWake) {
+    std::thread::spawn(move || {
+        let mut wake_seq = 0u64;
+        loop {
+            let candidates = match db.list_tempo_key_candidates(20) {
+                Ok(candidates) => candidates,
+                Err(err) => {
+                    tracing::warn!(error = %err, "tempo/key candidate query failed");
+                    std::thread::sleep(Duration::from_secs(10));
+                    continue;
+                }
+            };
+            if candidates.is_empty() {
+                wake.wait(&mut wake_seq);
+                continue;
+            }
+            tracing::info!(count = candidates.len(), "tempo/key candidates fetched");
+            for candidate in candidates {
+                let result = analyze_candidate(&root, &candidate);
+                if let Err(err) =
+                    db.upsert_tempo_key(candidate.track_id, candidate.mtime_ms, &result)
+                {
+                    tracing::warn!(
+                        error = %err,
+                        path = %candidate.path,
+                        "tempo/key store failed"
+                    );
+                    std::thread::sleep(Duration::from_secs(10));
+                }
+            }
+        }
+    });
+}
+
+/// Analyze one candidate; failures are recorded so the track is not retried until it changes.
+fn analyze_candidate(root: &Path, candidate: &TempoKeyCandidate) -> TempoKeyResult {
+    let analysis = MetadataService::resolve_track_path(root, &candidate.path)
+        .map_err(|response| anyhow!("resolve path failed ({})", response.status()))
+        .and_then(|path| analyze_tempo_key(&path));
+    match analysis {
+        Ok(found) => TempoKeyResult {
+            bpm: found.bpm,
+            key: found.key,
+            error: None,
+        },
+        Err(err) => TempoKeyResult {
+            error: Some(err.to_string()),
+            ..TempoKeyResult::default()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 44_100;
+
+    /// Decaying 1 kHz blips at `bpm` for `seconds`.
+    fn click_track(bpm: f64, seconds: f64) -> Vec<f32> {
+        let period = (60.0 / bpm * f64::from(RATE)) as usize;
+        (0..(seconds * f64::from(RATE)) as usize)
+            .map(|n| {
+                let t = (n % period) as f32 / RATE as f32;
+                (2.0 * std::f32::consts::PI * 1000.0 * t).sin() * (-t * 60.0).exp()
+            })
+            .collect()
+    }
+
+    fn chord(frequencies: &[f32], seconds: f32) -> Vec<f32> {
+        (0..(seconds * RATE as f32) as usize)
+            .map(|n| {
+                let t = n as f32 / RATE as f32;
+                frequencies
+                    .iter()
+                    .map(|hz| (2.0 * std::f32::consts::PI * hz * t).sin() / 3.0)
+                    .sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn estimates_bpm_of_click_tracks() {
+        for bpm in [90.0, 120.0, 150.0] {
+            let (envelope, fps) = onset_envelope(&click_track(bpm, 30.0), RATE);
+            let found = estimate_bpm(&envelope, fps).expect("tempo");
+            assert!((found - bpm).abs() < 1.0, "expected {bpm}, got {found}");
+        }
+        let (envelope, fps) = onset_envelope(&vec![0.0; RATE as usize * 30], RATE);
+        assert_eq!(estimate_bpm(&envelope, fps), None);
+    }
+
+    #[test]
+    fn estimates_key_of_triads() {
+        let a_minor = chord(&[220.0, 261.63, 329.63], 5.0);
+        assert_eq!(
+            estimate_key(&chromagram(&a_minor, RATE)).as_deref(),
+            Some("A minor")
+        );
+        let c_major = chord(&[261.63, 329.63, 392.0], 5.0);
+        assert_eq!(
+            estimate_key(&chromagram(&c_major, RATE)).as_deref(),
+            Some("C major")
+        );
+        assert_eq!(estimate_key(&[0.0; 12]), None);
+    }
+}
@@ -1,15 +1,25 @@
-//! On-demand track analysis (spectrogram + heuristics).
+//! On-demand track analysis (spectrogram + heuristics) and the background lossy-source check.
 
 use std::fs::File;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use anyhow::{Context, Result, anyhow};
 use rustfft::{FftPlanner, num_complex::Complex};
 use symphonia::core::{
-    audio::SampleBuffer, codecs::DecoderOptions, formats::FormatOptions, io::MediaSourceStream,
-    meta::MetadataOptions, probe::Hint,
+    audio::SampleBuffer,
+    codecs::{Decoder, DecoderOptions},
+    formats::{FormatOptions, FormatReader, SeekMode, SeekTo},
+    io::MediaSourceStream,
+    meta::MetadataOptions,
+    probe::Hint,
+    units::{Time, TimeBase},
 };
 
+use crate::metadata_db::{LossyCheckCandidate, LossyCheckResult, MetadataDb};
+use crate::metadata_service::MetadataService;
+use crate::state::MetadataWake;
+
 /// Parameters controlling on-demand track analysis.
 pub struct AnalysisOptions {
     /// Max analyzed duration in seconds (`<=0` means full track).
@@ -34,6 +44,12 @@ pub struct AnalysisHeuristics {
     pub upper_audible_ratio: Option<f32>,
     /// Approximate dynamic range from RMS window distribution.
     pub dynamic_range_db: Option<f32>,
+    /// Highest frequency carrying meaningful energy in the average spectrum.
+    pub cutoff_hz: Option<f32>,
+    /// Level drop (dB) across the first kHz above `cutoff_hz`; lossy encoders leave a wall.
+    pub cutoff_drop_db: Option<f32>,
+    /// Likelihood (0..1) the audio was transcoded from a lossy source.
+    pub lossy_confidence: Option<f32>,
     /// Human-readable notes inferred from heuristics.
     pub notes: Vec<String>,
 }
@@ -55,7 +71,10 @@ pub struct AnalysisResult {
 }
 
 /// Analyze one track file and return compact spectrogram + heuristics.
+///
+/// Cue tracks analyze their whole audio file.
 pub fn analyze_track(path: &Path, options: AnalysisOptions) -> Result<AnalysisResult> {
+    let path = &crate::cue_tracks::source_file(path)?;
     let file = File::open(path).with_context(|| format!("open {:?}", path))?;
     let mut hint = Hint::new();
     if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
@@ -137,6 +156,7 @@ pub fn analyze_track(path: &Path, options: AnalysisOptions) -> Result<AnalysisRe
     let mut rolloff_sum = 0.0f32;
     let mut rolloff_count = 0u32;
     let mut rms_windows: Vec<f32> = Vec::new();
+    let mut spectrum_sum: Vec<f32> = vec![0.0; window_size / 2];
     let mut frame_index = 0usize;
     let nyquist = sample_rate as f32 / 2.0;
     let half = window_size / 2;
@@ -242,6 +262,10 @@ pub fn analyze_track(path: &Path, options: AnalysisOptions) -> Result<AnalysisRe
                     rolloff_count += 1;
                 }
 
+                for (sum, mag) in spectrum_sum.iter_mut().zip(&magnitudes) {
+                    *sum += *mag;
+                }
+
                 let rms = (magnitudes.iter().sum::<f32>() / magnitudes.len() as f32).sqrt();
                 rms_windows.push(rms);
 
@@ -314,7 +338,18 @@ pub fn analyze_track(path: &Path, options: AnalysisOptions) -> Result<AnalysisRe
         None
     };
 
+    let cutoff = spectral_cutoff(&spectrum_sum, nyquist);
+    let lossy_confidence = cutoff.as_ref().map(|c| lossy_confidence(c, nyquist));
+
     let mut notes = Vec::new();
+    if let (Some(cutoff), Some(confidence)) = (cutoff.as_ref(), lossy_confidence)
+        && confidence >= LOSSY_NOTE_CONFIDENCE
+    {
+        notes.push(format!(
+            "Sharp spectral cutoff at {:.1} kHz; likely transcoded from a lossy source.",
+            cutoff.hz / 1000.0
+        ));
+    }
     if let (Some(ratio), true) = (ultrasonic_ratio, sample_rate >= 88_200) {
         if ratio < 0.005 {
             notes.push(
@@ -345,11 +380,280 @@ pub fn analyze_track(path: &Path, options: AnalysisOptions) -> Result<AnalysisRe
             ultrasonic_ratio,
             upper_audible_ratio,
             dynamic_range_db,
+            cutoff_hz: cutoff.as_ref().map(|c| c.hz),
+            cutoff_drop_db: cutoff.as_ref().map(|c| c.drop_db),
+            lossy_confidence,
             notes,
         },
     })
 }
 
+/// Decoded interleaved audio of one track, read a packet at a time.
+///
+/// Cue tracks are limited to their range of the backing file.
+pub(crate) struct TrackReader {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    time_base: Option<TimeBase>,
+    /// Source sample rate in Hz.
+    pub sample_rate: u32,
+    /// Interleaved channel count.
+    pub channels: usize,
+    start_frame: u64,
+    end_frame: Option<u64>,
+    samples: Vec<f32>,
+}
+
+impl TrackReader {
+    /// Open `path` (or the cue range it names) for decoding.
+    pub(crate) fn open(path: &Path) -> Result<Self> {
+        let cue = crate::cue_tracks::resolve(path)?;
+        let path = cue
+            .as_ref()
+            .map_or(path, |source| source.audio_path.as_path());
+        let file = File::open(path).with_context(|| format!("open {:?}", path))?;
+        let mut hint = Hint::new();
+        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+            hint.with_extension(ext);
+        }
+        let mss = MediaSourceStream::new(Box::new(file), Default::default());
+        let probed = symphonia::default::get_probe().format(
+            &hint,
+            mss,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )?;
+        let mut format = probed.format;
+        let track = format
+            .default_track()
+            .ok_or_else(|| anyhow!("No default audio track"))?;
+        let track_id = track.id;
+        let sample_rate = track
+            .codec_params
+            .sample_rate
+            .ok_or_else(|| anyhow!("Unknown sample rate"))?;
+        let channels = track
+            .codec_params
+            .channels
+            .ok_or_else(|| anyhow!("Unknown channels"))?
+            .count();
+        let time_base = track.codec_params.time_base;
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())?;
+
+        let range = cue.map(|source| source.range).unwrap_or_default();
+        let ms_to_frames = |ms: u64| ms * u64::from(sample_rate) / 1000;
+        if range.start_ms > 0 {
+            format.seek(
+                SeekMode::Accurate,
+                SeekTo::Time {
+                    time: Time::new(
+                        range.start_ms / 1000,
+                        (range.start_ms % 1000) as f64 / 1000.0,
+                    ),
+                    track_id: Some(track_id),
+                },
+            )?;
+        }
+        Ok(Self {
+            format,
+            decoder,
+            track_id,
+            time_base,
+            sample_rate,
+            channels,
+            start_frame: ms_to_frames(range.start_ms),
+            end_frame: range.end_ms.map(ms_to_frames),
+            samples: Vec::new(),
+        })
+    }
+
+    /// Next run of interleaved samples; `None` at the end of the track.
+    pub(crate) fn next_chunk(&mut self) -> Option<&[f32]> {
+        loop {
+            let packet = self.format.next_packet().ok()?;
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+            let packet_frame = match self.time_base {
+                Some(tb) => {
+                    let time = tb.calc_time(packet.ts());
+                    time.seconds * u64::from(self.sample_rate)
+                        + (time.frac * f64::from(self.sample_rate)) as u64
+                }
+                None => packet.ts(),
+            };
+            if self.end_frame.is_some_and(|end| packet_frame >= end) {
+                return None;
+            }
+            let Ok(decoded) = self.decoder.decode(&packet) else {
+                continue;
+            };
+            let mut sample_buf = SampleBuffer::<f32>::new(decoded.frames() as u64, *decoded.spec());
+            sample_buf.copy_interleaved_ref(decoded);
+            let frames = (sample_buf.samples().len() / self.channels) as u64;
+            let skip = self.start_frame.saturating_sub(packet_frame).min(frames);
+            let take = self
+                .end_frame
+                .map_or(frames, |end| (end - packet_frame).min(frames));
+            if take > skip {
+                self.samples.clear();
+                self.samples.extend_from_slice(
+                    &sample_buf.samples()
+                        [skip as usize * self.channels..take as usize * self.channels],
+                );
+                return Some(&self.samples);
+            }
+        }
+    }
+}
+
+/// Run the lossy-source check over FLAC tracks on a background thread.
+///
+/// Tracks never checked, or modified since their last check, are analyzed in batches; once
+/// the library is covered the loop sleeps until `wake` fires (e.g. after a rescan).
+pub fn spawn_lossy_check_loop(db: MetadataDb, root: PathBuf, wake: MetadataWake) {
+    std::thread::spawn(move || {
+        let mut wake_seq = 0u64;
+        loop {
+            let candidates = match db.list_lossy_check_candidates(20) {
+                Ok(candidates) => candidates,
+                Err(err) => {
+                    tracing::warn!(error = %err, "lossy check candidate query failed");
+                    std::thread::sleep(Duration::from_secs(10));
+                    continue;
+                }
+            };
+            if candidates.is_empty() {
+                wake.wait(&mut wake_seq);
+                continue;
+            }
+            tracing::info!(count = candidates.len(), "lossy check candidates fetched");
+            for candidate in candidates {
+                let result = check_lossy_source(&root, &candidate);
+                if let Err(err) =
+                    db.upsert_lossy_check(candidate.track_id, candidate.mtime_ms, &result)
+                {
+                    tracing::warn!(
+                        error = %err,
+                        path = %candidate.path,
+                        "lossy check store failed"
+                    );
+                    std::thread::sleep(Duration::from_secs(10));
+                }
+            }
+        }
+    });
+}
+
+/// Analyze one candidate; failures are recorded so the track is not retried until it changes.
+fn check_lossy_source(root: &Path, candidate: &LossyCheckCandidate) -> LossyCheckResult {
+    let full_path = match MetadataService::resolve_track_path(root, &candidate.path) {
+        Ok(path) => path,
+        Err(response) => {
+            return LossyCheckResult {
+                error: Some(format!("resolve path failed ({})", response.status())),
+                ..LossyCheckResult::default()
+            };
+        }
+    };
+    let options = AnalysisOptions {
+        max_seconds: 0.0,
+        width: 120,
+        height: 64,
+        window_size: 4096,
+        high_cutoff_hz: None,
+    };
+    match analyze_track(&full_path, options) {
+        Ok(analysis) => {
+            if let Some(confidence) = analysis.heuristics.lossy_confidence
+                && confidence >= LOSSY_NOTE_CONFIDENCE
+            {
+                tracing::info!(
+                    path = %candidate.path,
+                    confidence,
+                    cutoff_hz = ?analysis.heuristics.cutoff_hz,
+                    "possible lossy source"
+                );
+            }
+            LossyCheckResult {
+                cutoff_hz: analysis.heuristics.cutoff_hz,
+                cutoff_drop_db: analysis.heuristics.cutoff_drop_db,
+                confidence: analysis.heuristics.lossy_confidence,
+                error: None,
+            }
+        }
+        Err(err) => LossyCheckResult {
+            error: Some(err.to_string()),
+            ..LossyCheckResult::default()
+        },
+    }
+}
+
+/// Width of the bands the average spectrum is smoothed into before cutoff detection.
+const CUTOFF_BAND_HZ: f32 = 250.0;
+/// Bands this far below the loudest band count as empty.
+const CUTOFF_FLOOR_DB: f32 = 60.0;
+/// Lossy confidence at which a note is added to the heuristics.
+pub const LOSSY_NOTE_CONFIDENCE: f32 = 0.5;
+
+/// High-frequency edge of the average spectrum.
+struct SpectralCutoff {
+    /// Upper edge of the last band above the floor.
+    hz: f32,
+    /// Level difference between the bands just below and the first kHz above the edge.
+    drop_db: f32,
+}
+
+/// Locate where the summed power spectrum (`bins` up to `nyquist`) falls to the floor.
+fn spectral_cutoff(power: &[f32], nyquist: f32) -> Option<SpectralCutoff> {
+    if power.len() < 16 || nyquist <= 0.0 {
+        return None;
+    }
+    let bin_hz = nyquist / power.len() as f32;
+    let band_bins = ((CUTOFF_BAND_HZ / bin_hz).round() as usize).max(1);
+    let levels: Vec<f32> = power
+        .chunks(band_bins)
+        .map(|band| 10.0 * (band.iter().sum::<f32>() / band.len() as f32 + 1e-20).log10())
+        .collect();
+    let band_hz = band_bins as f32 * bin_hz;
+
+    let peak = levels.iter().copied().fold(f32::MIN, f32::max);
+    let floor = peak - CUTOFF_FLOOR_DB;
+    let edge = levels.iter().rposition(|level| *level > floor)?;
+    let hz = ((edge + 1) as f32 * band_hz).min(nyquist);
+
+    let span = ((1000.0 / band_hz).round() as usize).max(1);
+    let above = &levels[(edge + 1).min(levels.len())..(edge + 1 + span).min(levels.len())];
+    if above.is_empty() {
+        return Some(SpectralCutoff { hz, drop_db: 0.0 });
+    }
+    let below = &levels[(edge + 1).saturating_sub(span)..=edge];
+    let mean = |values: &[f32]| values.iter().sum::<f32>() / values.len() as f32;
+    Some(SpectralCutoff {
+        hz,
+        drop_db: mean(below) - mean(above),
+    })
+}
+
+/// Score how strongly a cutoff looks like a lossy encoder's lowpass.
+///
+/// Lossy encoders cut between ~16 and ~20 kHz with a near-vertical wall, while
+/// natural recordings either extend towards Nyquist or roll off gradually.
+fn lossy_confidence(cutoff: &SpectralCutoff, nyquist: f32) -> f32 {
+    if cutoff.hz >= 21_000.0 || cutoff.hz >= nyquist - 500.0 {
+        return 0.0;
+    }
+    let steepness = ((cutoff.drop_db - 15.0) / 25.0).clamp(0.0, 1.0);
+    let position = if cutoff.hz <= 17_000.0 {
+        1.0
+    } else {
+        1.0 - 0.5 * (cutoff.hz - 17_000.0) / 4_000.0
+    };
+    steepness * position
+}
+
 /// Downsample FFT bins into fixed output height using mean pooling.
 fn downsample_bins(input: &[f32], height: usize) -> Vec<f32> {
     if height == 0 {
@@ -389,3 +693,48 @@ fn median(values: &mut [f32]) -> Option<f32> {
         Some((lo + hi) / 2.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Flat power spectrum (22.05 kHz Nyquist) that falls to `tail` above `edge_hz`.
+    fn spectrum(edge_hz: f32, tail: impl Fn(f32) -> f32) -> Vec<f32> {
+        let bins = 2048;
+        let bin_hz = 22_050.0 / bins as f32;
+        (0..bins)
+            .map(|bin| {
+                let hz = bin as f32 * bin_hz;
+                if hz < edge_hz {
+                    1.0
+                } else {
+                    tail(hz - edge_hz)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn brick_wall_lowpass_scores_as_lossy() {
+        let power = spectrum(16_000.0, |_| 1e-12);
+        let cutoff = spectral_cutoff(&power, 22_050.0).unwrap();
+        assert!((cutoff.hz - 16_000.0).abs() < 300.0, "cutoff {}", cutoff.hz);
+        assert!(cutoff.drop_db > 60.0);
+        assert!(lossy_confidence(&cutoff, 22_050.0) > 0.9);
+    }
+
+    #[test]
+    fn full_band_spectrum_is_not_flagged() {
+        let power = vec![1.0; 2048];
+        let cutoff = spectral_cutoff(&power, 22_050.0).unwrap();
+        assert_eq!(lossy_confidence(&cutoff, 22_050.0), 0.0);
+    }
+
+    #[test]
+    fn gradual_rolloff_scores_low() {
+        // -12 dB per kHz above 15 kHz.
+        let power = spectrum(15_000.0, |hz| 10f32.powf(-1.2 * hz / 1000.0));
+        let cutoff = spectral_cutoff(&power, 22_050.0).unwrap();
+        assert!(lossy_confidence(&cutoff, 22_050.0) < LOSSY_NOTE_CONFIDENCE);
+    }
+}
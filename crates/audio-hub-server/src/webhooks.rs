@@ -0,0 +1,395 @@
+//! Outgoing webhooks from `[[webhooks]]` config.
+//!
+//! Each target gets a JSON `POST` when a track starts or stops, a queue changes or a library
+//! scan completes, so integrations (notifications, logging) don't need to hold an SSE
+//! connection open. Deliveries run on one background thread; a failed delivery is retried
+//! once, then logged and dropped.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crossbeam_channel::{Receiver, Sender};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::clock::now_ms;
+use crate::config::ServerConfig;
+use crate::events::{EventBus, HubEvent};
+use crate::metadata_db::{MetadataDb, TrackRecord};
+
+/// Events waiting for delivery before new ones are dropped.
+const QUEUE_CAPACITY: usize = 256;
+/// Per-request timeout for webhook targets.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Pause before the single retry of a failed delivery.
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+/// Queue changes arriving within this window are sent as one event.
+const QUEUE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Event names accepted in `events` and sent in the `event` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookEventKind {
+    TrackStarted,
+    TrackStopped,
+    QueueChanged,
+    ScanCompleted,
+}
+
+impl WebhookEventKind {
+    const ALL: [Self; 4] = [
+        Self::TrackStarted,
+        Self::TrackStopped,
+        Self::QueueChanged,
+        Self::ScanCompleted,
+    ];
+
+    /// Wire name of the event.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::TrackStarted => "track_started",
+            Self::TrackStopped => "track_stopped",
+            Self::QueueChanged => "queue_changed",
+            Self::ScanCompleted => "scan_completed",
+        }
+    }
+
+    /// Parse a wire name.
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == name)
+    }
+}
+
+/// Something that happened in the hub, reported by core services.
+#[derive(Debug, Clone)]
+pub(crate) enum WebhookEvent {
+    TrackStarted {
+        session_id: String,
+        output_id: Option<String>,
+        track_id: i64,
+    },
+    TrackStopped {
+        session_id: String,
+        output_id: Option<String>,
+        track_id: i64,
+    },
+    QueueChanged,
+    ScanCompleted {
+        full_rescan: bool,
+        error: Option<String>,
+    },
+}
+
+impl WebhookEvent {
+    fn kind(&self) -> WebhookEventKind {
+        match self {
+            Self::TrackStarted { .. } => WebhookEventKind::TrackStarted,
+            Self::TrackStopped { .. } => WebhookEventKind::TrackStopped,
+            Self::QueueChanged => WebhookEventKind::QueueChanged,
+            Self::ScanCompleted { .. } => WebhookEventKind::ScanCompleted,
+        }
+    }
+
+    fn track_id(&self) -> Option<i64> {
+        match self {
+            Self::TrackStarted { track_id, .. } | Self::TrackStopped { track_id, .. } => {
+                Some(*track_id)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// One configured webhook target.
+#[derive(Debug, Clone)]
+pub struct WebhookTarget {
+    url: String,
+    events: Vec<WebhookEventKind>,
+    secret: Option<String>,
+}
+
+impl WebhookTarget {
+    fn wants(&self, kind: WebhookEventKind) -> bool {
+        self.events.contains(&kind)
+    }
+}
+
+/// Resolve `[[webhooks]]` from config (empty when absent).
+pub fn from_config(cfg: &ServerConfig) -> anyhow::Result<Vec<WebhookTarget>> {
+    targets_from_config(cfg).map_err(|errors| anyhow::anyhow!(errors.join("; ")))
+}
+
+/// Resolve `[[webhooks]]`, listing every invalid field.
+pub(crate) fn targets_from_config(cfg: &ServerConfig) -> Result<Vec<WebhookTarget>, Vec<String>> {
+    let mut errors = Vec::new();
+    let mut targets = Vec::new();
+    for (idx, hook) in cfg.webhooks.iter().flatten().enumerate() {
+        let url = hook.url.trim();
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            errors.push(format!(
+                "webhooks[{idx}].url: must start with http:// or https:// (got `{url}`)"
+            ));
+        }
+        let events = match hook.events.as_ref() {
+            None => WebhookEventKind::ALL.to_vec(),
+            Some(names) => {
+                if names.is_empty() {
+                    errors.push(format!("webhooks[{idx}].events: must not be empty"));
+                }
+                let mut events = Vec::new();
+                for (event_idx, name) in names.iter().enumerate() {
+                    match WebhookEventKind::from_name(name.trim()) {
+                        Some(kind) => events.push(kind),
+                        None => errors.push(format!(
+                            "webhooks[{idx}].events[{event_idx}]: unknown event `{name}` (expected one of {})",
+                            WebhookEventKind::ALL.map(WebhookEventKind::as_str).join(", ")
+                        )),
+                    }
+                }
+                events
+            }
+        };
+        if hook
+            .secret
+            .as_deref()
+            .is_some_and(|secret| secret.is_empty())
+        {
+            errors.push(format!("webhooks[{idx}].secret: must not be empty"));
+        }
+        targets.push(WebhookTarget {
+            url: url.to_string(),
+            events,
+            secret: hook.secret.clone(),
+        });
+    }
+    if errors.is_empty() {
+        Ok(targets)
+    } else {
+        Err(errors)
+    }
+}
+
+fn sender() -> &'static OnceLock<Sender<WebhookEvent>> {
+    static SENDER: OnceLock<Sender<WebhookEvent>> = OnceLock::new();
+    &SENDER
+}
+
+/// Queue an event for delivery (no-op when no webhooks are configured).
+pub(crate) fn emit(event: WebhookEvent) {
+    if let Some(tx) = sender().get()
+        && tx.try_send(event).is_err()
+    {
+        tracing::warn!("webhook queue full; event dropped");
+    }
+}
+
+/// Start delivering events to `targets` in the background.
+pub fn spawn_webhook_loop(targets: Vec<WebhookTarget>, db: MetadataDb, events: &EventBus) {
+    if targets.is_empty() {
+        return;
+    }
+    let (tx, rx) = crossbeam_channel::bounded(QUEUE_CAPACITY);
+    if sender().set(tx).is_err() {
+        return;
+    }
+    if targets
+        .iter()
+        .any(|target| target.wants(WebhookEventKind::QueueChanged))
+    {
+        let receiver = events.subscribe();
+        std::thread::spawn(move || forward_queue_changes(receiver));
+    }
+    tracing::info!(targets = targets.len(), "webhooks enabled");
+    std::thread::spawn(move || deliver_loop(rx, targets, db));
+}
+
+/// Turn bursts of queue-change bus events into single webhook events.
+fn forward_queue_changes(mut receiver: tokio::sync::broadcast::Receiver<HubEvent>) {
+    loop {
+        match receiver.blocking_recv() {
+            Ok(HubEvent::QueueChanged) => {
+                std::thread::sleep(QUEUE_DEBOUNCE);
+                while receiver.try_recv().is_ok() {}
+                emit(WebhookEvent::QueueChanged);
+            }
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Post each queued event to every target that subscribed to it.
+fn deliver_loop(rx: Receiver<WebhookEvent>, targets: Vec<WebhookTarget>, db: MetadataDb) {
+    let config = ureq::Agent::config_builder()
+        .user_agent(concat!("audio-hub/", env!("CARGO_PKG_VERSION")))
+        .timeout_global(Some(DELIVERY_TIMEOUT))
+        .build();
+    let agent = ureq::Agent::new_with_config(config);
+    for event in rx {
+        let kind = event.kind();
+        if !targets.iter().any(|target| target.wants(kind)) {
+            continue;
+        }
+        let track = event
+            .track_id()
+            .and_then(|track_id| db.track_record_by_id(track_id).ok().flatten());
+        let body = payload(&event, track.as_ref(), now_ms()).to_string();
+        for target in targets.iter().filter(|target| target.wants(kind)) {
+            let result = deliver(&agent, target, kind, &body).or_else(|_| {
+                std::thread::sleep(RETRY_DELAY);
+                deliver(&agent, target, kind, &body)
+            });
+            if let Err(err) = result {
+                tracing::warn!(
+                    url = %target.url,
+                    event = kind.as_str(),
+                    error = %err,
+                    "webhook delivery failed"
+                );
+            }
+        }
+    }
+}
+
+/// Send one request; non-2xx responses are errors.
+fn deliver(
+    agent: &ureq::Agent,
+    target: &WebhookTarget,
+    kind: WebhookEventKind,
+    body: &str,
+) -> Result<(), ureq::Error> {
+    let mut request = agent
+        .post(&target.url)
+        .header("Content-Type", "application/json")
+        .header("X-Audio-Hub-Event", kind.as_str());
+    if let Some(secret) = target.secret.as_deref() {
+        request = request.header(
+            "X-Audio-Hub-Signature",
+            format!("sha256={}", sign(secret, body)),
+        );
+    }
+    request.send(body).map(|_| ())
+}
+
+/// Hex HMAC-SHA256 of `body` keyed by the target's secret.
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(body.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// JSON body for `event`, with track details when the track is known.
+fn payload(
+    event: &WebhookEvent,
+    track: Option<&TrackRecord>,
+    timestamp_ms: i64,
+) -> serde_json::Value {
+    let mut body = serde_json::json!({
+        "event": event.kind().as_str(),
+        "timestamp_ms": timestamp_ms,
+    });
+    let fields = match event {
+        WebhookEvent::TrackStarted {
+            session_id,
+            output_id,
+            track_id,
+        }
+        | WebhookEvent::TrackStopped {
+            session_id,
+            output_id,
+            track_id,
+        } => serde_json::json!({
+            "session_id": session_id,
+            "output_id": output_id,
+            "track": {
+                "id": track_id,
+                "title": track.and_then(|t| t.title.as_deref()),
+                "artist": track.and_then(|t| t.artist.as_deref()),
+                "album": track.and_then(|t| t.album.as_deref()),
+                "duration_ms": track.and_then(|t| t.duration_ms),
+            },
+        }),
+        WebhookEvent::QueueChanged => serde_json::json!({}),
+        WebhookEvent::ScanCompleted { full_rescan, error } => serde_json::json!({
+            "full_rescan": full_rescan,
+            "ok": error.is_none(),
+            "error": error,
+        }),
+    };
+    if let (Some(body), serde_json::Value::Object(fields)) = (body.as_object_mut(), fields) {
+        body.extend(fields);
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets_from_config_defaults_to_all_events_and_reports_errors() {
+        let cfg: ServerConfig = toml::from_str(
+            r#"
+            [[webhooks]]
+            url = "https://hooks.example.com/audio"
+
+            [[webhooks]]
+            url = "ftp://hooks.example.com"
+            events = ["track_started", "track_paused"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            targets_from_config(&cfg).unwrap_err(),
+            vec![
+                "webhooks[1].url: must start with http:// or https:// (got `ftp://hooks.example.com`)",
+                "webhooks[1].events[1]: unknown event `track_paused` (expected one of track_started, track_stopped, queue_changed, scan_completed)",
+            ]
+        );
+
+        let cfg: ServerConfig = toml::from_str(
+            "[[webhooks]]\nurl = \"http://10.0.0.5/hook\"\nevents = [\"scan_completed\"]",
+        )
+        .unwrap();
+        let targets = targets_from_config(&cfg).unwrap();
+        assert!(targets[0].wants(WebhookEventKind::ScanCompleted));
+        assert!(!targets[0].wants(WebhookEventKind::TrackStarted));
+    }
+
+    #[test]
+    fn payload_flattens_event_fields() {
+        let event = WebhookEvent::TrackStopped {
+            session_id: "s1".to_string(),
+            output_id: Some("local:default".to_string()),
+            track_id: 7,
+        };
+        let body = payload(&event, None, 1_000);
+        assert_eq!(body["event"], "track_stopped");
+        assert_eq!(body["timestamp_ms"], 1_000);
+        assert_eq!(body["session_id"], "s1");
+        assert_eq!(body["track"]["id"], 7);
+        assert!(body["track"]["title"].is_null());
+
+        let event = WebhookEvent::ScanCompleted {
+            full_rescan: true,
+            error: None,
+        };
+        let body = payload(&event, None, 1_000);
+        assert_eq!(body["ok"], true);
+        assert_eq!(body["full_rescan"], true);
+    }
+
+    #[test]
+    fn sign_matches_hmac_sha256() {
+        // RFC 4231 test case 2.
+        assert_eq!(
+            sign("Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
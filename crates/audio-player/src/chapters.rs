@@ -0,0 +1,691 @@
+//! Chapter lists of long-form files (audiobooks, concert recordings).
+//!
+//! Symphonia reports no chapters for these containers, so [`read`] parses them itself:
+//! - MP4/M4B: a QuickTime chapter track (a text track referenced through `tref/chap`, as
+//!   written by Apple tools) or, failing that, a Nero `moov/udta/chpl` box;
+//! - Matroska/WebM: the first visible edition of the `Chapters` element (nested chapters are
+//!   ignored).
+//!
+//! Chapter positions are milliseconds from the start of the file, so a chapter's
+//! [`TrackRange`] seeks and plays like a cue sheet track.
+
+use std::io::{Read, SeekFrom};
+
+use anyhow::{Result, anyhow, bail};
+use symphonia::core::io::MediaSource;
+
+use crate::cue::TrackRange;
+
+/// Most chapters kept from one file.
+const MAX_CHAPTERS: usize = 1000;
+
+/// Largest `moov` box, `Chapters` element or chapter title read into memory.
+const MAX_METADATA_BYTES: u64 = 64 << 20;
+
+/// One chapter of a file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Chapter {
+    /// Chapter title, when the file names it.
+    pub title: Option<String>,
+    /// Span of the file the chapter covers; the last chapter runs to the end of the file.
+    pub range: TrackRange,
+}
+
+impl From<&Chapter> for audio_bridge_types::Chapter {
+    fn from(chapter: &Chapter) -> Self {
+        Self {
+            title: chapter.title.clone(),
+            start_ms: chapter.range.start_ms,
+            end_ms: chapter.range.end_ms,
+        }
+    }
+}
+
+/// Read the chapter list of `source`; the source is rewound either way.
+///
+/// Files in other containers, or without chapters, yield an empty list.
+pub fn read(source: &mut dyn MediaSource) -> Result<Vec<Chapter>> {
+    if !source.is_seekable() {
+        return Ok(Vec::new());
+    }
+    let mut magic = [0u8; 8];
+    let read = crate::dsd::read_up_to(source, &mut magic)?;
+    source.seek(SeekFrom::Start(0))?;
+    let chapters = if read == 8 && &magic[4..] == b"ftyp" {
+        read_mp4(source)
+    } else if read >= 4 && magic[..4] == EBML_HEADER.to_be_bytes() {
+        read_mkv(source)
+    } else {
+        Ok(Vec::new())
+    };
+    source.seek(SeekFrom::Start(0))?;
+    Ok(finish(chapters?))
+}
+
+/// Order chapters, drop duplicates and close each open range at the next chapter.
+fn finish(mut chapters: Vec<Chapter>) -> Vec<Chapter> {
+    chapters.sort_by_key(|c| c.range.start_ms);
+    chapters.dedup_by_key(|c| c.range.start_ms);
+    chapters.truncate(MAX_CHAPTERS);
+    let starts: Vec<u64> = chapters.iter().skip(1).map(|c| c.range.start_ms).collect();
+    for (chapter, next) in chapters.iter_mut().zip(starts) {
+        chapter.range.end_ms = Some(chapter.range.end_ms.map_or(next, |end| end.min(next)));
+    }
+    chapters
+}
+
+/// Read exactly `len` bytes of metadata, refusing oversized boxes/elements.
+fn read_body(source: &mut dyn MediaSource, len: u64) -> Result<Vec<u8>> {
+    if len > MAX_METADATA_BYTES {
+        bail!("chapter metadata too large ({len} bytes)");
+    }
+    let mut body = vec![0u8; len as usize];
+    source.read_exact(&mut body)?;
+    Ok(body)
+}
+
+fn be_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn be_u64(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(bytes.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// Non-empty, trimmed chapter title.
+fn title(text: String) -> Option<String> {
+    Some(text.trim().to_string()).filter(|t| !t.is_empty())
+}
+
+// --- MP4 ---
+
+/// Chapters of an MP4 file: the QuickTime chapter track, else Nero `chpl`.
+fn read_mp4(source: &mut dyn MediaSource) -> Result<Vec<Chapter>> {
+    let Some(moov) = find_top_level_box(source, b"moov")? else {
+        return Ok(Vec::new());
+    };
+    if let Some(chapters) = mp4_chapter_track(source, &moov)? {
+        return Ok(chapters);
+    }
+    Ok(mp4_nero_chapters(&moov).unwrap_or_default())
+}
+
+/// Walk the top-level boxes of `source` and return the body of the first `kind`.
+fn find_top_level_box(source: &mut dyn MediaSource, kind: &[u8; 4]) -> Result<Option<Vec<u8>>> {
+    loop {
+        let start = source.stream_position()?;
+        let mut header = [0u8; 8];
+        if crate::dsd::read_up_to(source, &mut header)? < 8 {
+            return Ok(None);
+        }
+        let (size, header_len) = match u32::from_be_bytes(header[..4].try_into()?) {
+            1 => {
+                let mut large = [0u8; 8];
+                source.read_exact(&mut large)?;
+                (u64::from_be_bytes(large), 16)
+            }
+            size => (u64::from(size), 8),
+        };
+        if &header[4..] == kind {
+            let len = match size {
+                0 => source.seek(SeekFrom::End(0))? - start - header_len,
+                size => size
+                    .checked_sub(header_len)
+                    .ok_or_else(|| anyhow!("malformed MP4 box"))?,
+            };
+            source.seek(SeekFrom::Start(start + header_len))?;
+            return read_body(source, len).map(Some);
+        }
+        if size < header_len {
+            // Size 0 runs to the end of the file; anything else is malformed.
+            return Ok(None);
+        }
+        source.seek(SeekFrom::Start(start + size))?;
+    }
+}
+
+/// Child boxes of an MP4 box body, as `(type, body)`.
+fn mp4_boxes(mut data: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    std::iter::from_fn(move || {
+        let kind: [u8; 4] = data.get(4..8)?.try_into().ok()?;
+        let (size, header_len) = match be_u32(data, 0)? {
+            0 => (data.len(), 8),
+            1 => (usize::try_from(be_u64(data, 8)?).ok()?, 16),
+            size => (size as usize, 8),
+        };
+        if size < header_len || size > data.len() {
+            return None;
+        }
+        let body = &data[header_len..size];
+        data = &data[size..];
+        Some((kind, body))
+    })
+}
+
+/// Body of the first child box of type `kind`.
+fn mp4_child<'a>(data: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+    mp4_boxes(data)
+        .find(|(k, _)| k == kind)
+        .map(|(_, body)| body)
+}
+
+/// Version byte and payload of a full box (skipping its flags).
+fn full_box(body: &[u8]) -> Option<(u8, &[u8])> {
+    Some((*body.first()?, body.get(4..)?))
+}
+
+/// Chapters from the text track that another track names in its `tref/chap` box.
+fn mp4_chapter_track(source: &mut dyn MediaSource, moov: &[u8]) -> Result<Option<Vec<Chapter>>> {
+    let traks: Vec<&[u8]> = mp4_boxes(moov)
+        .filter(|(kind, _)| kind == b"trak")
+        .map(|(_, body)| body)
+        .collect();
+    let chapter_ids: Vec<u32> = traks
+        .iter()
+        .filter_map(|trak| mp4_child(trak, b"tref"))
+        .flat_map(|tref| mp4_boxes(tref).filter(|(kind, _)| kind == b"chap"))
+        .flat_map(|(_, ids)| ids.chunks_exact(4).filter_map(|id| be_u32(id, 0)))
+        .collect();
+    let Some(trak) = traks
+        .iter()
+        .find(|trak| mp4_track_id(trak).is_some_and(|id| chapter_ids.contains(&id)))
+    else {
+        return Ok(None);
+    };
+    let Some(samples) = mp4_text_samples(trak) else {
+        return Ok(None);
+    };
+    let mut chapters = Vec::with_capacity(samples.len());
+    for (start_ms, offset, size) in samples {
+        let text = if size >= 2 {
+            source.seek(SeekFrom::Start(offset))?;
+            mp4_text(&read_body(source, u64::from(size))?)
+        } else {
+            None
+        };
+        chapters.push(Chapter {
+            title: text,
+            range: TrackRange {
+                start_ms,
+                end_ms: None,
+            },
+        });
+    }
+    Ok(Some(chapters))
+}
+
+/// `track_ID` from a `trak`'s `tkhd`.
+fn mp4_track_id(trak: &[u8]) -> Option<u32> {
+    let (version, tkhd) = full_box(mp4_child(trak, b"tkhd")?)?;
+    be_u32(tkhd, if version == 1 { 16 } else { 8 })
+}
+
+/// `(start_ms, file offset, size)` of every sample of a track, from its sample tables.
+fn mp4_text_samples(trak: &[u8]) -> Option<Vec<(u64, u64, u32)>> {
+    let mdia = mp4_child(trak, b"mdia")?;
+    let (version, mdhd) = full_box(mp4_child(mdia, b"mdhd")?)?;
+    let timescale = u64::from(be_u32(mdhd, if version == 1 { 16 } else { 8 })?).max(1);
+    let stbl = mp4_child(mp4_child(mdia, b"minf")?, b"stbl")?;
+
+    let mut starts = Vec::new();
+    let (_, stts) = full_box(mp4_child(stbl, b"stts")?)?;
+    let mut ts = 0u64;
+    'stts: for entry in 0..be_u32(stts, 0)? as usize {
+        let count = be_u32(stts, 4 + entry * 8)?;
+        let delta = u64::from(be_u32(stts, 8 + entry * 8)?);
+        for _ in 0..count {
+            if starts.len() == MAX_CHAPTERS {
+                break 'stts;
+            }
+            starts.push(ts * 1000 / timescale);
+            ts += delta;
+        }
+    }
+
+    let (_, stsz) = full_box(mp4_child(stbl, b"stsz")?)?;
+    let fixed = be_u32(stsz, 0)?;
+    let count = (be_u32(stsz, 4)? as usize).min(starts.len());
+    let sizes: Vec<u32> = (0..count)
+        .map(|i| match fixed {
+            0 => be_u32(stsz, 8 + i * 4),
+            size => Some(size),
+        })
+        .collect::<Option<_>>()?;
+
+    let chunk_offsets: Vec<u64> = if let Some(stco) = mp4_child(stbl, b"stco") {
+        let (_, stco) = full_box(stco)?;
+        (0..be_u32(stco, 0)? as usize)
+            .map(|i| be_u32(stco, 4 + i * 4).map(u64::from))
+            .collect::<Option<_>>()?
+    } else {
+        let (_, co64) = full_box(mp4_child(stbl, b"co64")?)?;
+        (0..be_u32(co64, 0)? as usize)
+            .map(|i| be_u64(co64, 4 + i * 8))
+            .collect::<Option<_>>()?
+    };
+    let (_, stsc) = full_box(mp4_child(stbl, b"stsc")?)?;
+    let runs: Vec<(u32, u32)> = (0..be_u32(stsc, 0)? as usize)
+        .map(|i| Some((be_u32(stsc, 4 + i * 12)?, be_u32(stsc, 8 + i * 12)?)))
+        .collect::<Option<_>>()?;
+
+    let mut samples = Vec::with_capacity(sizes.len());
+    'chunks: for (chunk, chunk_offset) in chunk_offsets.iter().enumerate() {
+        let per_chunk = runs
+            .iter()
+            .rev()
+            .find(|(first, _)| *first as usize <= chunk + 1)
+            .map_or(0, |(_, n)| *n);
+        let mut offset = *chunk_offset;
+        for _ in 0..per_chunk {
+            let index = samples.len();
+            if index == sizes.len() {
+                break 'chunks;
+            }
+            samples.push((starts[index], offset, sizes[index]));
+            offset += u64::from(sizes[index]);
+        }
+    }
+    Some(samples)
+}
+
+/// Decode a QuickTime text sample: a 16-bit length, then UTF-8 (or BOM-marked UTF-16) text.
+fn mp4_text(sample: &[u8]) -> Option<String> {
+    let len = usize::from(u16::from_be_bytes(sample.get(..2)?.try_into().ok()?));
+    let text = sample.get(2..2 + len)?;
+    let text = match text.strip_prefix(&[0xFE, 0xFF]) {
+        Some(utf16) => String::from_utf16_lossy(
+            &utf16
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect::<Vec<_>>(),
+        ),
+        None => String::from_utf8_lossy(text).into_owned(),
+    };
+    title(text)
+}
+
+/// Chapters from a Nero `moov/udta/chpl` box (start times in 100 ns units).
+fn mp4_nero_chapters(moov: &[u8]) -> Option<Vec<Chapter>> {
+    let (version, mut rest) = full_box(mp4_child(mp4_child(moov, b"udta")?, b"chpl")?)?;
+    if version != 0 {
+        rest = rest.get(4..)?;
+    }
+    let count = *rest.first()?;
+    rest = &rest[1..];
+    let mut chapters = Vec::with_capacity(usize::from(count));
+    for _ in 0..count {
+        let start = be_u64(rest, 0)?;
+        let len = usize::from(*rest.get(8)?);
+        let text = rest.get(9..9 + len)?;
+        chapters.push(Chapter {
+            title: title(String::from_utf8_lossy(text).into_owned()),
+            range: TrackRange {
+                start_ms: start / 10_000,
+                end_ms: None,
+            },
+        });
+        rest = &rest[9 + len..];
+    }
+    Some(chapters)
+}
+
+// --- Matroska ---
+
+const EBML_HEADER: u32 = 0x1A45_DFA3;
+const MKV_SEGMENT: u32 = 0x1853_8067;
+const MKV_SEEK_HEAD: u32 = 0x114D_9B74;
+const MKV_SEEK: u32 = 0x4DBB;
+const MKV_SEEK_ID: u32 = 0x53AB;
+const MKV_SEEK_POSITION: u32 = 0x53AC;
+const MKV_CLUSTER: u32 = 0x1F43_B675;
+const MKV_CHAPTERS: u32 = 0x1043_A770;
+const MKV_EDITION_ENTRY: u32 = 0x45B9;
+const MKV_EDITION_FLAG_HIDDEN: u32 = 0x45BD;
+const MKV_EDITION_FLAG_DEFAULT: u32 = 0x45DB;
+const MKV_CHAPTER_ATOM: u32 = 0xB6;
+const MKV_CHAPTER_TIME_START: u32 = 0x91;
+const MKV_CHAPTER_TIME_END: u32 = 0x92;
+const MKV_CHAPTER_FLAG_HIDDEN: u32 = 0x98;
+const MKV_CHAPTER_FLAG_ENABLED: u32 = 0x4598;
+const MKV_CHAPTER_DISPLAY: u32 = 0x80;
+const MKV_CHAP_STRING: u32 = 0x85;
+
+/// Chapters of a Matroska file.
+///
+/// Stops scanning at the first cluster: later `Chapters` are only found through the seek head.
+fn read_mkv(source: &mut dyn MediaSource) -> Result<Vec<Chapter>> {
+    let Some((EBML_HEADER, Some(size))) = read_element_header(source)? else {
+        bail!("missing EBML header");
+    };
+    source.seek(SeekFrom::Current(i64::try_from(size)?))?;
+    let Some((MKV_SEGMENT, segment_size)) = read_element_header(source)? else {
+        bail!("missing Matroska segment");
+    };
+    let segment_start = source.stream_position()?;
+    let segment_end = segment_size.map(|size| segment_start.saturating_add(size));
+
+    let mut chapters_at = None;
+    loop {
+        if segment_end.is_some_and(|end| source.stream_position().is_ok_and(|pos| pos >= end)) {
+            break;
+        }
+        let Some((id, size)) = read_element_header(source)? else {
+            break;
+        };
+        match (id, size) {
+            (MKV_CHAPTERS, Some(size)) => return Ok(mkv_chapters(&read_body(source, size)?)),
+            (MKV_SEEK_HEAD, Some(size)) => {
+                chapters_at = mkv_seek_position(&read_body(source, size)?, MKV_CHAPTERS)
+                    .map(|pos| segment_start.saturating_add(pos));
+            }
+            (MKV_CLUSTER, _) | (_, None) => break,
+            (_, Some(size)) => {
+                source.seek(SeekFrom::Current(i64::try_from(size)?))?;
+            }
+        }
+    }
+    if let Some(pos) = chapters_at {
+        source.seek(SeekFrom::Start(pos))?;
+        if let Some((MKV_CHAPTERS, Some(size))) = read_element_header(source)? {
+            return Ok(mkv_chapters(&read_body(source, size)?));
+        }
+    }
+    Ok(Vec::new())
+}
+
+/// Read an EBML variable-length integer, returning `(value, all value bits set)`.
+///
+/// IDs keep their length marker (`keep_marker`); sizes drop it. `None` at end of input.
+fn read_vint(input: &mut (impl Read + ?Sized), keep_marker: bool) -> Result<Option<(u64, bool)>> {
+    let mut first = [0u8; 1];
+    if input.read(&mut first)? == 0 {
+        return Ok(None);
+    }
+    let len = first[0].leading_zeros() as usize + 1;
+    if len > 8 {
+        bail!("invalid EBML length");
+    }
+    let mask = (0xFFu32 >> len) as u8;
+    let mut value = u64::from(if keep_marker {
+        first[0]
+    } else {
+        first[0] & mask
+    });
+    let mut all_ones = first[0] & mask == mask;
+    let mut rest = [0u8; 7];
+    input.read_exact(&mut rest[..len - 1])?;
+    for byte in &rest[..len - 1] {
+        value = value << 8 | u64::from(*byte);
+        all_ones &= *byte == 0xFF;
+    }
+    Ok(Some((value, all_ones)))
+}
+
+/// Read an element ID and size (`None` size: unknown length). `None` at end of input.
+fn read_element_header(input: &mut (impl Read + ?Sized)) -> Result<Option<(u32, Option<u64>)>> {
+    let Some((id, _)) = read_vint(input, true)? else {
+        return Ok(None);
+    };
+    let Some((size, unknown)) = read_vint(input, false)? else {
+        return Ok(None);
+    };
+    Ok(Some((u32::try_from(id)?, (!unknown).then_some(size))))
+}
+
+/// Child elements of an in-memory EBML element body, as `(id, body)`.
+fn ebml_elements(mut data: &[u8]) -> impl Iterator<Item = (u32, &[u8])> {
+    std::iter::from_fn(move || {
+        let (id, size) = read_element_header(&mut data).ok()??;
+        let size = match size {
+            Some(size) => usize::try_from(size).ok().filter(|s| *s <= data.len())?,
+            None => data.len(),
+        };
+        let (body, rest) = data.split_at(size);
+        data = rest;
+        Some((id, body))
+    })
+}
+
+/// Big-endian unsigned integer element value.
+fn ebml_uint(body: &[u8]) -> u64 {
+    body.iter()
+        .fold(0, |value, byte| value << 8 | u64::from(*byte))
+}
+
+/// First child `id` of an element body, as an unsigned integer.
+fn ebml_child_uint(body: &[u8], id: u32) -> Option<u64> {
+    ebml_elements(body)
+        .find(|(child, _)| *child == id)
+        .map(|(_, value)| ebml_uint(value))
+}
+
+/// Position (relative to the segment data) a seek head gives for element `target`.
+fn mkv_seek_position(seek_head: &[u8], target: u32) -> Option<u64> {
+    ebml_elements(seek_head)
+        .filter(|(id, _)| *id == MKV_SEEK)
+        .find_map(|(_, seek)| {
+            let id = ebml_elements(seek).find(|(id, _)| *id == MKV_SEEK_ID)?.1;
+            (ebml_uint(id) == u64::from(target))
+                .then(|| ebml_child_uint(seek, MKV_SEEK_POSITION))?
+        })
+}
+
+/// Chapters of the default (else first) visible edition of a `Chapters` element.
+fn mkv_chapters(body: &[u8]) -> Vec<Chapter> {
+    let editions: Vec<&[u8]> = ebml_elements(body)
+        .filter(|(id, _)| *id == MKV_EDITION_ENTRY)
+        .map(|(_, edition)| edition)
+        .filter(|edition| ebml_child_uint(edition, MKV_EDITION_FLAG_HIDDEN) != Some(1))
+        .collect();
+    let Some(edition) = editions
+        .iter()
+        .find(|edition| ebml_child_uint(edition, MKV_EDITION_FLAG_DEFAULT) == Some(1))
+        .or(editions.first())
+    else {
+        return Vec::new();
+    };
+    ebml_elements(edition)
+        .filter(|(id, _)| *id == MKV_CHAPTER_ATOM)
+        .filter(|(_, atom)| {
+            ebml_child_uint(atom, MKV_CHAPTER_FLAG_HIDDEN) != Some(1)
+                && ebml_child_uint(atom, MKV_CHAPTER_FLAG_ENABLED) != Some(0)
+        })
+        .filter_map(|(_, atom)| {
+            let start_ns = ebml_child_uint(atom, MKV_CHAPTER_TIME_START)?;
+            let end_ns = ebml_child_uint(atom, MKV_CHAPTER_TIME_END);
+            let text = ebml_elements(atom)
+                .filter(|(id, _)| *id == MKV_CHAPTER_DISPLAY)
+                .find_map(|(_, display)| {
+                    ebml_elements(display).find(|(id, _)| *id == MKV_CHAP_STRING)
+                })
+                .and_then(|(_, text)| title(String::from_utf8_lossy(text).into_owned()));
+            Some(Chapter {
+                title: text,
+                range: TrackRange {
+                    start_ms: start_ns / 1_000_000,
+                    end_ms: end_ns.map(|ns| ns / 1_000_000),
+                },
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn mp4_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut out = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn full(version: u8, body: &[u8]) -> Vec<u8> {
+        let mut out = vec![version, 0, 0, 0];
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn u32s(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    fn chapters_of(bytes: Vec<u8>) -> Vec<(Option<String>, u64, Option<u64>)> {
+        let mut source = Cursor::new(bytes);
+        read(&mut source)
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.title, c.range.start_ms, c.range.end_ms))
+            .collect()
+    }
+
+    #[test]
+    fn reads_nero_chapters_from_mp4() {
+        let mut chpl = vec![2u8];
+        for (start, name) in [(0u64, "Intro"), (65_000_0000, "Part One")] {
+            chpl.extend(start.to_be_bytes());
+            chpl.push(name.len() as u8);
+            chpl.extend(name.as_bytes());
+        }
+        let mut file = mp4_box(b"ftyp", b"M4B \0\0\0\0");
+        file.extend(mp4_box(b"mdat", &[0u8; 16]));
+        file.extend(mp4_box(
+            b"moov",
+            &mp4_box(b"udta", &mp4_box(b"chpl", &full(0, &chpl))),
+        ));
+
+        assert_eq!(
+            chapters_of(file),
+            [
+                (Some("Intro".to_string()), 0, Some(65_000)),
+                (Some("Part One".to_string()), 65_000, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn prefers_quicktime_chapter_track() {
+        let mut file = mp4_box(b"ftyp", b"M4A \0\0\0\0");
+        // Two text samples in one chunk right after the `mdat` header.
+        let mut samples = Vec::new();
+        for text in ["One", "Two"] {
+            samples.extend((text.len() as u16).to_be_bytes());
+            samples.extend(text.as_bytes());
+        }
+        let chunk_offset = file.len() as u32 + 8;
+        file.extend(mp4_box(b"mdat", &samples));
+
+        let tkhd = |id: u32| mp4_box(b"tkhd", &full(0, &u32s(&[0, 0, id, 0])));
+        let audio = [tkhd(1), mp4_box(b"tref", &mp4_box(b"chap", &u32s(&[2])))].concat();
+        let stbl = [
+            mp4_box(b"stts", &full(0, &u32s(&[1, 2, 90_000]))),
+            mp4_box(b"stsz", &full(0, &u32s(&[0, 2, 5, 5]))),
+            mp4_box(b"stsc", &full(0, &u32s(&[1, 1, 2, 1]))),
+            mp4_box(b"stco", &full(0, &u32s(&[1, chunk_offset]))),
+        ]
+        .concat();
+        let mdia = [
+            mp4_box(b"mdhd", &full(0, &u32s(&[0, 0, 1000, 180_000]))),
+            mp4_box(b"minf", &mp4_box(b"stbl", &stbl)),
+        ]
+        .concat();
+        let text = [tkhd(2), mp4_box(b"mdia", &mdia)].concat();
+        let mut chpl = full(0, &[1u8]);
+        chpl.extend(0u64.to_be_bytes());
+        chpl.extend([4u8]);
+        chpl.extend(b"Nero");
+        let moov = [
+            mp4_box(b"trak", &audio),
+            mp4_box(b"trak", &text),
+            mp4_box(b"udta", &mp4_box(b"chpl", &chpl)),
+        ]
+        .concat();
+        file.extend(mp4_box(b"moov", &moov));
+
+        assert_eq!(
+            chapters_of(file),
+            [
+                (Some("One".to_string()), 0, Some(90_000)),
+                (Some("Two".to_string()), 90_000, None),
+            ]
+        );
+    }
+
+    fn element(id: u32, body: &[u8]) -> Vec<u8> {
+        let mut out: Vec<u8> = id
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|b| *b == 0)
+            .collect();
+        // 8-byte size so any test body fits.
+        out.push(0x01);
+        out.extend(&(body.len() as u64).to_be_bytes()[1..]);
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn atom(start_ms: u64, text: Option<&str>, hidden: bool) -> Vec<u8> {
+        let mut body = element(
+            MKV_CHAPTER_TIME_START,
+            &(start_ms * 1_000_000).to_be_bytes(),
+        );
+        if hidden {
+            body.extend(element(MKV_CHAPTER_FLAG_HIDDEN, &[1]));
+        }
+        if let Some(text) = text {
+            body.extend(element(
+                MKV_CHAPTER_DISPLAY,
+                &element(MKV_CHAP_STRING, text.as_bytes()),
+            ));
+        }
+        element(MKV_CHAPTER_ATOM, &body)
+    }
+
+    #[test]
+    fn reads_matroska_chapters_through_seek_head() {
+        let chapters = element(
+            MKV_CHAPTERS,
+            &element(
+                MKV_EDITION_ENTRY,
+                &[
+                    atom(120_000, Some("Encore"), false),
+                    atom(0, Some("Opening"), false),
+                    atom(60_000, None, true),
+                ]
+                .concat(),
+            ),
+        );
+        let cluster = element(MKV_CLUSTER, &[0u8; 32]);
+        // The seek head points past the cluster to the chapters; positions are 4 bytes wide.
+        let seek_head = |position: u64| {
+            let seek = [
+                element(MKV_SEEK_ID, &MKV_CHAPTERS.to_be_bytes()),
+                element(MKV_SEEK_POSITION, &position.to_be_bytes()[4..]),
+            ]
+            .concat();
+            element(MKV_SEEK_HEAD, &element(MKV_SEEK, &seek))
+        };
+        let seek_head = seek_head((seek_head(0).len() + cluster.len()) as u64);
+
+        let mut file = element(EBML_HEADER, &[0u8; 4]);
+        file.extend(element(
+            MKV_SEGMENT,
+            &[seek_head, cluster, chapters].concat(),
+        ));
+
+        assert_eq!(
+            chapters_of(file),
+            [
+                (Some("Opening".to_string()), 0, Some(120_000)),
+                (Some("Encore".to_string()), 120_000, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn other_containers_have_no_chapters() {
+        assert!(chapters_of(b"RIFF\0\0\0\0WAVE".to_vec()).is_empty());
+    }
+}
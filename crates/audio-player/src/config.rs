@@ -1,3 +1,10 @@
+use std::sync::Arc;
+
+use crate::mirror::MirrorTarget;
+use crate::mix::ChannelMixConfig;
+use crate::record::Recorder;
+use crate::resample::{ResampleBackend, ResampleQuality};
+
 /// Playback tuning parameters shared by decode/resample/playback stages.
 #[derive(Clone, Debug)]
 pub struct PlaybackConfig {
@@ -7,6 +14,179 @@ pub struct PlaybackConfig {
     pub refill_max_frames: usize,
     /// Target buffer duration for queue sizing.
     pub buffer_seconds: f32,
+    /// Optional recorder that captures the output sample stream (`--record`).
+    pub record: Option<Arc<Recorder>>,
+    /// Optional second device every session is mirrored to (`--mirror-device`).
+    pub mirror: Option<Arc<MirrorTarget>>,
+    /// Whether sessions switch the device to the source rate or resample to its current rate.
+    pub rate_switch: RateSwitch,
+    /// Sinc filter preset used when a session resamples.
+    pub resample_quality: ResampleQuality,
+    /// Converter used when a session resamples.
+    pub resample_backend: ResampleBackend,
+    /// Gain (dB) applied to the decoded signal before volume, e.g. per-track normalization.
+    pub pre_gain_db: f32,
+    /// Downmix/upmix tuning when the source and device channel counts differ.
+    pub channel_mix: ChannelMixConfig,
+    /// What the output writes while its queue is empty.
+    pub underrun_concealment: UnderrunConcealment,
+    /// Silence (ms) played each time an output stream opens, before the first samples.
+    pub preroll_silence_ms: u32,
+    /// Audio (ms) the output queue must hold before the output starts (`0`: start at once).
+    pub min_prebuffer_ms: u32,
+    /// Skip digital silence at the start/end of each session's source (`None`: off).
+    pub silence_trim: Option<SilenceTrim>,
+    /// What the decoder does with packets it cannot decode.
+    pub decode_errors: DecodeErrorPolicy,
+    /// How DSD sources reach the output.
+    pub dsd_output: DsdOutput,
+    /// The session queue carries DSD-over-PCM frames (see [`crate::dsd`]) for a DAC that
+    /// decodes them: samples must reach the device untouched, so gain, volume, recording,
+    /// mirroring and metering are bypassed and gaps play DoP idle frames.
+    pub dop: bool,
+}
+
+/// Leading/trailing silence trimming (see [`crate::source::TrimSilence`]).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SilenceTrim {
+    /// Level (dBFS) at or below which every channel of a frame must sit for it to count as
+    /// silent.
+    pub threshold_db: f32,
+    /// Most silence removed at each end, in milliseconds.
+    pub max_ms: u32,
+    /// Trim the start; off for sessions that begin mid-track (e.g. after a seek).
+    pub leading: bool,
+    /// Trim the end.
+    pub trailing: bool,
+}
+
+impl SilenceTrim {
+    /// Default detection threshold: well below any dithered or recorded noise floor.
+    pub const DEFAULT_THRESHOLD_DB: f32 = -70.0;
+    /// Default cap on the silence removed at each end.
+    pub const DEFAULT_MAX_MS: u32 = 3000;
+}
+
+impl Default for SilenceTrim {
+    fn default() -> Self {
+        Self {
+            threshold_db: Self::DEFAULT_THRESHOLD_DB,
+            max_ms: Self::DEFAULT_MAX_MS,
+            leading: true,
+            trailing: true,
+        }
+    }
+}
+
+/// Handling of corrupt packets while decoding (see [`crate::decode::DecodeErrorStats`]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeErrorPolicy {
+    /// What replaces a corrupt packet.
+    pub action: DecodeErrorAction,
+    /// Stop decoding after this many corrupt packets in a row (at least `1`).
+    pub max_consecutive: u32,
+}
+
+impl DecodeErrorPolicy {
+    /// Default run of corrupt packets tolerated before a decode gives up.
+    pub const DEFAULT_MAX_CONSECUTIVE: u32 = 50;
+}
+
+impl Default for DecodeErrorPolicy {
+    fn default() -> Self {
+        Self {
+            action: DecodeErrorAction::default(),
+            max_consecutive: Self::DEFAULT_MAX_CONSECUTIVE,
+        }
+    }
+}
+
+/// What a decode does with one corrupt packet.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DecodeErrorAction {
+    /// Drop the packet; the audio around it is joined directly.
+    #[default]
+    Skip,
+    /// Play silence for the packet's duration, keeping the timeline intact.
+    Silence,
+    /// End the decode at the first corrupt packet.
+    Abort,
+}
+
+impl DecodeErrorAction {
+    /// Stable name used on the CLI.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DecodeErrorAction::Skip => "skip",
+            DecodeErrorAction::Silence => "silence",
+            DecodeErrorAction::Abort => "abort",
+        }
+    }
+}
+
+/// How DSD sources are played.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DsdOutput {
+    /// DSD-over-PCM when the output accepts integer PCM at the DoP rate, PCM otherwise.
+    #[default]
+    Auto,
+    /// Always convert to PCM.
+    Pcm,
+}
+
+impl DsdOutput {
+    /// Stable name used on the CLI.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DsdOutput::Auto => "auto",
+            DsdOutput::Pcm => "pcm",
+        }
+    }
+}
+
+/// How the output callback fills an underrun.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnderrunConcealment {
+    /// Write zeros; the step to silence clicks on some DACs.
+    #[default]
+    Zeros,
+    /// Hold the last frame and fade it to silence over a few milliseconds, then fade the
+    /// returning audio back in.
+    Fade,
+}
+
+impl UnderrunConcealment {
+    /// Fade length used by [`UnderrunConcealment::Fade`].
+    const FADE_SECS: f32 = 0.005;
+
+    /// Stable name used on the CLI.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UnderrunConcealment::Zeros => "zeros",
+            UnderrunConcealment::Fade => "fade",
+        }
+    }
+
+    /// Fade length in frames at `sample_rate` (`0` for [`UnderrunConcealment::Zeros`]).
+    pub fn fade_frames(self, sample_rate: u32) -> usize {
+        match self {
+            UnderrunConcealment::Zeros => 0,
+            UnderrunConcealment::Fade => {
+                ((sample_rate as f32 * Self::FADE_SECS).round() as usize).max(1)
+            }
+        }
+    }
+}
+
+/// How a session's output rate is chosen when the source rate differs from the device's.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RateSwitch {
+    /// Run the device at the source rate when it supports it (bit-perfect; the device may
+    /// click or mute briefly on each switch).
+    #[default]
+    PreferSwitch,
+    /// Keep the device at its current rate and resample (no reclocking between tracks).
+    PreferResample,
 }
 
 impl Default for PlaybackConfig {
@@ -16,6 +196,20 @@ impl Default for PlaybackConfig {
             chunk_frames: 1024,
             refill_max_frames: 4096,
             buffer_seconds: 2.0,
+            record: None,
+            mirror: None,
+            rate_switch: RateSwitch::default(),
+            resample_quality: ResampleQuality::default(),
+            resample_backend: ResampleBackend::default(),
+            pre_gain_db: 0.0,
+            channel_mix: ChannelMixConfig::default(),
+            underrun_concealment: UnderrunConcealment::default(),
+            preroll_silence_ms: 0,
+            min_prebuffer_ms: 0,
+            silence_trim: None,
+            decode_errors: DecodeErrorPolicy::default(),
+            dsd_output: DsdOutput::default(),
+            dop: false,
         }
     }
 }
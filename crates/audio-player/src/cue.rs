@@ -0,0 +1,305 @@
+//! CUE sheet parsing.
+//!
+//! A cue sheet splits one audio file (typically a whole-album rip) into tracks. [`parse`]
+//! reads the subset players care about: album/track `TITLE` and `PERFORMER`, `REM DATE`,
+//! `FILE`, `TRACK` and `INDEX 01`. Each track's [`TrackRange`] runs from its `INDEX 01` to
+//! the next track's `INDEX 01` in the same file (pregaps stay with the previous track), and
+//! to the end of the file for the last one.
+
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+
+/// Cue sheet timestamps count 75 frames per second (CD sectors).
+const FRAMES_PER_SECOND: u64 = 75;
+
+/// Span of a source file to play, in milliseconds from the start of the file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TrackRange {
+    /// Where playback starts.
+    pub start_ms: u64,
+    /// Where playback stops; `None` plays to the end of the file.
+    pub end_ms: Option<u64>,
+}
+
+impl TrackRange {
+    /// Whether the range covers the whole file.
+    pub fn is_full(&self) -> bool {
+        self.start_ms == 0 && self.end_ms.is_none()
+    }
+
+    /// Length of the range given the file's duration (needed for open-ended ranges).
+    pub fn duration_ms(&self, file_duration_ms: Option<u64>) -> Option<u64> {
+        let end = match (self.end_ms, file_duration_ms) {
+            (Some(end), Some(total)) => end.min(total),
+            (Some(end), None) => end,
+            (None, total) => total?,
+        };
+        Some(end.saturating_sub(self.start_ms))
+    }
+}
+
+/// Parsed cue sheet.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CueSheet {
+    /// Album title.
+    pub title: Option<String>,
+    /// Album performer.
+    pub performer: Option<String>,
+    /// `REM DATE` value (usually a year).
+    pub date: Option<String>,
+    /// Tracks in sheet order.
+    pub tracks: Vec<CueTrack>,
+}
+
+/// One track of a cue sheet.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CueTrack {
+    /// `TRACK` number.
+    pub number: u32,
+    /// `FILE` the track lives in, as written in the sheet (relative to the sheet).
+    pub file: String,
+    /// Track title.
+    pub title: Option<String>,
+    /// Track performer (falls back to the album performer when absent).
+    pub performer: Option<String>,
+    /// Span of `file` holding the track.
+    pub range: TrackRange,
+}
+
+impl CueSheet {
+    /// The audio file shared by every track, when the sheet describes a single-file rip.
+    pub fn single_file(&self) -> Option<&str> {
+        let first = self.tracks.first()?;
+        self.tracks
+            .iter()
+            .all(|track| track.file == first.file)
+            .then_some(first.file.as_str())
+    }
+
+    /// Look up a track by its `TRACK` number.
+    pub fn track(&self, number: u32) -> Option<&CueTrack> {
+        self.tracks.iter().find(|track| track.number == number)
+    }
+}
+
+/// Read and parse a cue sheet file.
+///
+/// Sheets are usually UTF-8 (with or without BOM), but older rippers write Latin-1; bytes
+/// that are not valid UTF-8 are decoded as Latin-1.
+pub fn read(path: &Path) -> Result<CueSheet> {
+    let bytes = std::fs::read(path).with_context(|| format!("read {:?}", path))?;
+    let text = match std::str::from_utf8(&bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes.iter().map(|&b| b as char).collect(),
+    };
+    parse(&text).with_context(|| format!("parse {:?}", path))
+}
+
+/// Parse cue sheet text.
+pub fn parse(text: &str) -> Result<CueSheet> {
+    let mut sheet = CueSheet::default();
+    let mut file: Option<String> = None;
+    let mut current: Option<CueTrack> = None;
+
+    for (line_no, line) in text.trim_start_matches('\u{feff}').lines().enumerate() {
+        let fields = split_fields(line);
+        let Some((command, args)) = fields.split_first() else {
+            continue;
+        };
+        let at = || format!("line {}", line_no + 1);
+        match command.to_ascii_uppercase().as_str() {
+            "FILE" => {
+                let name = args
+                    .first()
+                    .ok_or_else(|| anyhow!("{}: FILE without name", at()))?;
+                file = Some(name.clone());
+            }
+            "TRACK" => {
+                if let Some(track) = current.take() {
+                    sheet.tracks.push(track);
+                }
+                let number = args
+                    .first()
+                    .and_then(|n| n.parse::<u32>().ok())
+                    .ok_or_else(|| anyhow!("{}: invalid TRACK number", at()))?;
+                let file = file
+                    .clone()
+                    .ok_or_else(|| anyhow!("{}: TRACK before FILE", at()))?;
+                // Non-audio tracks (data sessions) carry no playable index.
+                let audio = args
+                    .get(1)
+                    .is_none_or(|kind| kind.eq_ignore_ascii_case("AUDIO"));
+                current = audio.then(|| CueTrack {
+                    number,
+                    file,
+                    ..CueTrack::default()
+                });
+            }
+            "INDEX" => {
+                let (Some(index), Some(time)) = (args.first(), args.get(1)) else {
+                    return Err(anyhow!("{}: INDEX needs a number and a time", at()));
+                };
+                if index.parse::<u32>().ok() == Some(1)
+                    && let Some(track) = current.as_mut()
+                {
+                    track.range.start_ms =
+                        parse_time(time).ok_or_else(|| anyhow!("{}: invalid time {time}", at()))?;
+                }
+            }
+            "TITLE" | "PERFORMER" => {
+                let value = args.first().cloned();
+                let slot = match (current.as_mut(), command.eq_ignore_ascii_case("TITLE")) {
+                    (Some(track), true) => &mut track.title,
+                    (Some(track), false) => &mut track.performer,
+                    (None, true) => &mut sheet.title,
+                    (None, false) => &mut sheet.performer,
+                };
+                *slot = value.filter(|v| !v.is_empty());
+            }
+            "REM"
+                if args
+                    .first()
+                    .is_some_and(|key| key.eq_ignore_ascii_case("DATE")) =>
+            {
+                sheet.date = args.get(1).cloned();
+            }
+            _ => {}
+        }
+    }
+    if let Some(track) = current.take() {
+        sheet.tracks.push(track);
+    }
+    if sheet.tracks.is_empty() {
+        return Err(anyhow!("no audio tracks"));
+    }
+
+    // Each track ends where the next one in the same file starts.
+    for i in 0..sheet.tracks.len() {
+        let next = sheet
+            .tracks
+            .get(i + 1)
+            .filter(|next| next.file == sheet.tracks[i].file)
+            .map(|next| next.range.start_ms);
+        let track = &mut sheet.tracks[i];
+        track.range.end_ms = next.filter(|end| *end > track.range.start_ms);
+        if track.performer.is_none() {
+            track.performer = sheet.performer.clone();
+        }
+    }
+    Ok(sheet)
+}
+
+/// Parse an `mm:ss:ff` timestamp into milliseconds.
+fn parse_time(raw: &str) -> Option<u64> {
+    let mut parts = raw.split(':').map(|p| p.parse::<u64>().ok());
+    let (minutes, seconds, frames) = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() || seconds >= 60 || frames >= FRAMES_PER_SECOND {
+        return None;
+    }
+    Some((minutes * 60 + seconds) * 1000 + frames * 1000 / FRAMES_PER_SECOND)
+}
+
+/// Split a line into whitespace-separated fields, keeping double-quoted strings together.
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut chars = line.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            fields.push(chars.by_ref().take_while(|&c| c != '"').collect());
+        } else {
+            let mut field = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                field.push(c);
+                chars.next();
+            }
+            fields.push(field);
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHEET: &str = "\u{feff}REM GENRE Rock
+REM DATE 1999
+PERFORMER \"The Band\"
+TITLE \"Live Album\"
+FILE \"Live Album.flac\" WAVE
+  TRACK 01 AUDIO
+    TITLE \"Intro\"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE \"Second Song\"
+    PERFORMER \"Guest\"
+    INDEX 00 03:58:50
+    INDEX 01 04:00:15
+  TRACK 03 AUDIO
+    TITLE \"Closer\"
+    INDEX 01 09:12:74
+";
+
+    #[test]
+    fn parse_reads_tracks_and_ranges() {
+        let sheet = parse(SHEET).unwrap();
+        assert_eq!(sheet.title.as_deref(), Some("Live Album"));
+        assert_eq!(sheet.performer.as_deref(), Some("The Band"));
+        assert_eq!(sheet.date.as_deref(), Some("1999"));
+        assert_eq!(sheet.single_file(), Some("Live Album.flac"));
+        assert_eq!(sheet.tracks.len(), 3);
+
+        let second = sheet.track(2).unwrap();
+        assert_eq!(second.title.as_deref(), Some("Second Song"));
+        assert_eq!(second.performer.as_deref(), Some("Guest"));
+        assert_eq!(second.range.start_ms, 240_200);
+        assert_eq!(second.range.end_ms, Some(552_986));
+
+        let first = sheet.track(1).unwrap();
+        assert_eq!(first.performer.as_deref(), Some("The Band"));
+        assert_eq!(first.range.end_ms, Some(240_200));
+        assert!(sheet.track(3).unwrap().range.end_ms.is_none());
+    }
+
+    #[test]
+    fn parse_rejects_sheets_without_tracks() {
+        assert!(parse("TITLE \"Empty\"\n").is_err());
+        assert!(parse("TRACK 01 AUDIO\n").is_err());
+        assert!(parse("FILE a.wav WAVE\nTRACK 01 AUDIO\nINDEX 01 00:61:00\n").is_err());
+    }
+
+    #[test]
+    fn single_file_is_none_for_per_track_files() {
+        let sheet = parse(
+            "FILE \"01.flac\" WAVE\nTRACK 01 AUDIO\nINDEX 01 00:00:00\n\
+             FILE \"02.flac\" WAVE\nTRACK 02 AUDIO\nINDEX 01 00:00:00\n",
+        )
+        .unwrap();
+        assert!(sheet.single_file().is_none());
+        assert!(sheet.tracks.iter().all(|t| t.range.end_ms.is_none()));
+    }
+
+    #[test]
+    fn track_range_duration_handles_open_end() {
+        let range = TrackRange {
+            start_ms: 1_000,
+            end_ms: None,
+        };
+        assert_eq!(range.duration_ms(Some(5_000)), Some(4_000));
+        assert_eq!(range.duration_ms(None), None);
+        let range = TrackRange {
+            start_ms: 1_000,
+            end_ms: Some(3_000),
+        };
+        assert_eq!(range.duration_ms(None), Some(2_000));
+        assert!(!range.is_full());
+        assert!(TrackRange::default().is_full());
+    }
+}
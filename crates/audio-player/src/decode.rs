@@ -4,17 +4,35 @@
 //! - probe the input container/codec
 //! - decode packets into interleaved `f32` samples
 //! - push samples into a bounded [`SharedAudio`] queue from a background thread
+//!
+//! [`preroll_decode_from_media_source`] opens the next track early for gapless playback;
+//! [`crate::source::Sequence`] splices it onto the current one.
+//!
+//! [`start_streaming_decode_range_from_media_source`] plays only a [`TrackRange`] of the
+//! source (e.g. one track of a cue sheet); its duration, seeks and elapsed time are then
+//! relative to the start of the range.
 
 use std::fs::File;
+use std::io;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
+use std::time::Duration;
 
+use crate::chapters::{self, Chapter};
+use crate::config::{DecodeErrorAction, DecodeErrorPolicy};
+use crate::cue::TrackRange;
+use crate::dsd;
 use crate::queue::{SharedAudio, calc_max_buffered_samples};
+use crate::source::QueueSource;
 use anyhow::{Context, Result, anyhow};
-use symphonia::core::audio::SampleBuffer;
-use symphonia::core::codecs::CodecParameters;
+use symphonia::core::audio::{Channels, SampleBuffer};
+use symphonia::core::codecs::{CodecParameters, Decoder};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatReader, Packet, SeekMode, SeekTo};
 use symphonia::core::io::MediaSource;
+use symphonia::core::units::{Time, TimeBase};
 use symphonia::core::{
     audio::SignalSpec, codecs::DecoderOptions, formats::FormatOptions, io::MediaSourceStream,
     meta::MetadataOptions, probe::Hint,
@@ -29,6 +47,156 @@ pub struct SourceInfo {
     pub bit_depth: Option<u16>,
     /// Container/extension hint (best-effort).
     pub container: Option<String>,
+    /// DSD bit rate per channel for DSD sources, whose queue carries DoP frames
+    /// (see [`crate::dsd`]).
+    pub dsd_rate: Option<u32>,
+    /// Corrupt-packet counters, updated while the decode runs.
+    pub decode_errors: Arc<DecodeErrorStats>,
+    /// Chapters of the file (see [`chapters`]); empty for ranged decodes.
+    pub chapters: Vec<Chapter>,
+}
+
+/// Corrupt packets met by a decode, handled per its [`DecodeErrorPolicy`].
+#[derive(Debug, Default)]
+pub struct DecodeErrorStats {
+    /// Packets the container or codec rejected.
+    pub corrupt_packets: AtomicU64,
+    /// Set when the policy ended the decode early.
+    pub aborted: AtomicBool,
+}
+
+/// How often a waiting seek re-flushes the decode queue.
+const SEEK_REFLUSH_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Longest a pre-roll waits for its decoder to produce the first samples.
+const PREROLL_PRIME_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Stream spec, queue, optional duration, source metadata, and seek handle of a decode.
+pub type SeekableDecode = (
+    SignalSpec,
+    Arc<SharedAudio>,
+    Option<u64>,
+    SourceInfo,
+    DecodeSeek,
+);
+
+/// Handle for repositioning a running streaming decode without re-opening the source.
+///
+/// Returned by the `start_seekable_*` entry points; pass it to [`seek_streaming_decode`].
+#[derive(Clone)]
+pub struct DecodeSeek {
+    queue: Arc<SharedAudio>,
+    shared: Arc<SeekShared>,
+}
+
+struct SeekShared {
+    state: Mutex<SeekState>,
+    cv: Condvar,
+    played_frames: Mutex<Option<(Arc<AtomicU64>, u32)>>,
+}
+
+#[derive(Default)]
+struct SeekState {
+    /// Target position waiting for the decoder thread.
+    pending_ms: Option<u64>,
+    /// Error from the last serviced seek.
+    failed: Option<String>,
+    /// Set once the decoder thread has exited; no further seeks are possible.
+    finished: bool,
+}
+
+impl DecodeSeek {
+    pub(crate) fn new(queue: Arc<SharedAudio>) -> Self {
+        Self {
+            queue,
+            shared: Arc::new(SeekShared {
+                state: Mutex::new(SeekState::default()),
+                cv: Condvar::new(),
+                played_frames: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Reset `played_frames` (counted at `rate_hz`) to the new position on every seek, so
+    /// elapsed time derived from it follows the seek.
+    pub fn track_played_frames(&self, played_frames: Arc<AtomicU64>, rate_hz: u32) {
+        *self.shared.played_frames.lock().unwrap() = Some((played_frames, rate_hz));
+    }
+
+    /// Target of a seek requested but not yet serviced by the decoder thread.
+    pub(crate) fn pending(&self) -> Option<u64> {
+        self.shared.state.lock().unwrap().pending_ms
+    }
+
+    /// Record the outcome of servicing a seek to `position_ms` and wake the requester.
+    pub(crate) fn complete(&self, position_ms: u64, result: Result<()>) {
+        let mut state = self.shared.state.lock().unwrap();
+        // A newer request may have replaced this one while the reader was seeking.
+        if state.pending_ms == Some(position_ms) {
+            state.pending_ms = None;
+            state.failed = result.err().map(|e| format!("{e:#}"));
+        }
+        drop(state);
+        self.shared.cv.notify_all();
+    }
+
+    /// Mark the decoder thread as gone and release any waiting requester.
+    pub(crate) fn finish(&self) {
+        self.shared.state.lock().unwrap().finished = true;
+        self.shared.cv.notify_all();
+    }
+}
+
+/// Reposition a running decode to `position_ms`.
+///
+/// Buffered audio is flushed from the decode queue right away; the call then waits for the
+/// decoder thread to seek the format reader (`SeekMode::Accurate`) and reset the codec, and
+/// resets any counter registered with [`DecodeSeek::track_played_frames`]. Audio already
+/// handed to later stages (e.g. a resampler queue) is not flushed.
+///
+/// Fails when the decode has already finished or the source cannot seek; in the latter case
+/// playback continues from where it was, minus the flushed audio.
+pub fn seek_streaming_decode(seek: &DecodeSeek, position_ms: u64) -> Result<()> {
+    let mut state = seek.shared.state.lock().unwrap();
+    if state.finished {
+        return Err(anyhow!("decode already finished"));
+    }
+    state.pending_ms = Some(position_ms);
+    state.failed = None;
+    drop(state);
+    seek.queue.flush();
+
+    let mut state = seek.shared.state.lock().unwrap();
+    while state.pending_ms.is_some() && !state.finished {
+        let (next, timeout) = seek
+            .shared
+            .cv
+            .wait_timeout(state, SEEK_REFLUSH_INTERVAL)
+            .unwrap();
+        state = next;
+        if timeout.timed_out() && state.pending_ms.is_some() {
+            // A push that started after the flush above can block on a full queue before
+            // the decoder thread gets back to the seek; flushing again releases it.
+            drop(state);
+            seek.queue.flush();
+            state = seek.shared.state.lock().unwrap();
+        }
+    }
+    if state.pending_ms.take().is_some() {
+        return Err(anyhow!("decode finished before seeking"));
+    }
+    if let Some(err) = state.failed.take() {
+        return Err(anyhow!("seek to {position_ms}ms failed: {err}"));
+    }
+    drop(state);
+
+    if let Some((played_frames, rate_hz)) = seek.shared.played_frames.lock().unwrap().as_ref() {
+        played_frames.store(
+            position_ms.saturating_mul(*rate_hz as u64) / 1000,
+            Ordering::Relaxed,
+        );
+    }
+    Ok(())
 }
 
 /// Start decoding from an arbitrary Symphonia [`MediaSource`] (seekable or not).
@@ -55,6 +223,73 @@ pub fn start_streaming_decode_from_media_source_at(
     buffer_seconds: f32,
     seek_ms: Option<u64>,
 ) -> Result<(SignalSpec, Arc<SharedAudio>, Option<u64>, SourceInfo)> {
+    start_streaming_decode_range_from_media_source(
+        source,
+        hint,
+        buffer_seconds,
+        seek_ms,
+        TrackRange::default(),
+        DecodeErrorPolicy::default(),
+    )
+}
+
+/// Start decoding `range` of a [`MediaSource`], at `seek_ms` into the range.
+///
+/// Decoding stops at the end of the range; the returned duration is the range length. Corrupt
+/// packets are handled per `on_error`.
+pub fn start_streaming_decode_range_from_media_source(
+    source: Box<dyn MediaSource>,
+    hint: Hint,
+    buffer_seconds: f32,
+    seek_ms: Option<u64>,
+    range: TrackRange,
+    on_error: DecodeErrorPolicy,
+) -> Result<(SignalSpec, Arc<SharedAudio>, Option<u64>, SourceInfo)> {
+    let (spec, shared, duration_ms, source_info, _seek) =
+        spawn_decode(source, hint, buffer_seconds, seek_ms, range, on_error)?;
+    Ok((spec, shared, duration_ms, source_info))
+}
+
+/// Like [`start_streaming_decode_from_media_source`], also returning a [`DecodeSeek`] handle.
+pub fn start_seekable_decode_from_media_source(
+    source: Box<dyn MediaSource>,
+    hint: Hint,
+    buffer_seconds: f32,
+) -> Result<SeekableDecode> {
+    spawn_decode(
+        source,
+        hint,
+        buffer_seconds,
+        None,
+        TrackRange::default(),
+        DecodeErrorPolicy::default(),
+    )
+}
+
+/// Probe `source`, optionally seek to `seek_ms` into `range`, and spawn the decoder thread.
+///
+/// DSF/DSDIFF sources decode to DoP frames (see [`crate::dsd`]).
+fn spawn_decode(
+    mut source: Box<dyn MediaSource>,
+    hint: Hint,
+    buffer_seconds: f32,
+    seek_ms: Option<u64>,
+    range: TrackRange,
+    on_error: DecodeErrorPolicy,
+) -> Result<SeekableDecode> {
+    if dsd::is_dsd(source.as_mut())? {
+        return dsd::spawn_decode(source, buffer_seconds, seek_ms, range);
+    }
+    // A cue sheet track is already a slice of the file; its chapters would not line up.
+    let chapters = if range.is_full() {
+        chapters::read(source.as_mut()).unwrap_or_else(|e| {
+            tracing::debug!(error = %e, "chapter list unreadable");
+            Vec::new()
+        })
+    } else {
+        Vec::new()
+    };
+
     // Probe once to get spec.
     let mss = MediaSourceStream::new(source, Default::default());
 
@@ -66,40 +301,39 @@ pub fn start_streaming_decode_from_media_source_at(
     )?;
 
     let mut format = probed.format;
-    if let Some(ms) = seek_ms {
-        if ms > 0 {
-            let secs = ms / 1000;
-            let frac = (ms % 1000) as f64 / 1000.0;
-            let time = symphonia::core::units::Time::new(secs, frac);
-            let _ = format.seek(
-                symphonia::core::formats::SeekMode::Accurate,
-                symphonia::core::formats::SeekTo::Time {
-                    time,
-                    track_id: None,
-                },
-            );
-        }
-    }
+    let skip_to_ts = match range.start_ms + seek_ms.unwrap_or(0) {
+        0 => None,
+        ms => seek_format(format.as_mut(), ms).ok(),
+    };
 
     let track = format
         .default_track()
         .ok_or_else(|| anyhow!("No default audio track"))?;
 
-    let channels = track
-        .codec_params
-        .channels
-        .ok_or_else(|| anyhow!("Unknown channels"))?
-        .count();
+    let layout = match track.codec_params.channels {
+        Some(layout) => layout,
+        None => channel_layout(
+            alac_cookie_channels(&track.codec_params).ok_or_else(|| anyhow!("Unknown channels"))?,
+        )?,
+    };
+    let channels = layout.count();
 
     let rate = track
         .codec_params
         .sample_rate
         .ok_or_else(|| anyhow!("Unknown sample rate"))?;
 
-    let spec = SignalSpec::new(rate, track.codec_params.channels.unwrap());
+    let spec = SignalSpec::new(rate, layout);
 
     let codec_params: CodecParameters = track.codec_params.clone();
-    let duration_ms = duration_ms_from_codec_params(&codec_params);
+    let duration_ms = if range.is_full() {
+        duration_ms_from_codec_params(&codec_params)
+    } else {
+        range.duration_ms(duration_ms_from_codec_params(&codec_params))
+    };
+    let end_ts = range
+        .end_ms
+        .map(|ms| ms_to_ts(ms, codec_params.time_base, rate));
     let source_info = SourceInfo {
         codec: codec_name_from_params(&codec_params),
         bit_depth: codec_params
@@ -107,21 +341,55 @@ pub fn start_streaming_decode_from_media_source_at(
             .or(codec_params.bits_per_coded_sample)
             .and_then(|v| u16::try_from(v).ok()),
         container: None,
+        dsd_rate: None,
+        decode_errors: Default::default(),
+        chapters,
     };
 
+    // Create the decoder up front so an unsupported codec fails the request, not the thread.
+    let decoder = symphonia::default::get_codecs()
+        .make(&codec_params, &DecoderOptions::default())
+        .with_context(|| {
+            format!(
+                "no decoder for {} audio",
+                source_info.codec.as_deref().unwrap_or("this")
+            )
+        })?;
+
     let max_buffered_samples = calc_max_buffered_samples(rate, channels, buffer_seconds);
     let shared = Arc::new(SharedAudio::new(channels, max_buffered_samples));
 
     let shared_for_thread = shared.clone();
+    let seek = DecodeSeek::new(shared.clone());
+    let seek_for_thread = seek.clone();
+    let mut errors = PacketErrors {
+        policy: on_error,
+        stats: source_info.decode_errors.clone(),
+        consecutive: 0,
+        rate,
+        time_base: codec_params.time_base,
+    };
 
     thread::spawn(move || {
-        if let Err(e) = decode_format_loop(format, codec_params, &shared_for_thread) {
+        if let Err(e) = decode_format_loop(
+            format,
+            decoder,
+            &shared_for_thread,
+            &seek_for_thread,
+            skip_to_ts,
+            DecodeBounds {
+                start_ms: range.start_ms,
+                end_ts,
+            },
+            &mut errors,
+        ) {
             tracing::error!("decoder thread error: {e:#}");
         }
+        seek_for_thread.finish();
         shared_for_thread.close();
     });
 
-    Ok((spec, shared, duration_ms, source_info))
+    Ok((spec, shared, duration_ms, source_info, seek))
 }
 
 /// Start a background decoder thread that streams interleaved `f32` samples from `path`.
@@ -141,32 +409,301 @@ pub fn start_streaming_decode(
     start_streaming_decode_from_media_source(Box::new(file), hint, buffer_seconds)
 }
 
+/// Like [`start_streaming_decode`], also returning a [`DecodeSeek`] handle.
+pub fn start_seekable_streaming_decode(
+    path: &PathBuf,
+    buffer_seconds: f32,
+) -> Result<SeekableDecode> {
+    let file = File::open(path).with_context(|| format!("open {:?}", path))?;
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    start_seekable_decode_from_media_source(Box::new(file), hint, buffer_seconds)
+}
+
+/// A decode opened ahead of time so it can follow the current track without a gap.
+///
+/// The decoder thread is already running: by the time this is returned its queue holds the
+/// first decoded audio and keeps filling up to its buffer limit.
+pub struct PrerolledDecode {
+    pub spec: SignalSpec,
+    pub queue: Arc<SharedAudio>,
+    pub duration_ms: Option<u64>,
+    pub source_info: SourceInfo,
+}
+
+impl PrerolledDecode {
+    /// Whether this track can be spliced onto a stream with `spec` (same rate and layout).
+    pub fn continues(&self, spec: &SignalSpec) -> bool {
+        self.spec == *spec
+    }
+
+    /// Read the pre-rolled queue as a [`Source`].
+    pub fn into_source(self) -> QueueSource {
+        let frames = self
+            .duration_ms
+            .map(|ms| ms * u64::from(self.spec.rate) / 1000);
+        QueueSource::new(self.queue, self.spec).with_frames(frames)
+    }
+}
+
+/// Open the next track from a [`MediaSource`] and prime its decoder while the current one
+/// drains.
+///
+/// Waits up to `PREROLL_PRIME_TIMEOUT` for the first decoded samples; a slow source still
+/// returns and keeps filling in the background.
+pub fn preroll_decode_from_media_source(
+    source: Box<dyn MediaSource>,
+    hint: Hint,
+    buffer_seconds: f32,
+) -> Result<PrerolledDecode> {
+    let (spec, queue, duration_ms, source_info, _seek) = spawn_decode(
+        source,
+        hint,
+        buffer_seconds,
+        None,
+        TrackRange::default(),
+        DecodeErrorPolicy::default(),
+    )?;
+    if !queue.wait_for_any(PREROLL_PRIME_TIMEOUT) {
+        tracing::debug!("pre-rolled decode not primed yet");
+    }
+    Ok(PrerolledDecode {
+        spec,
+        queue,
+        duration_ms,
+        source_info,
+    })
+}
+
+/// Like [`preroll_decode_from_media_source`], for a local file.
+pub fn preroll_streaming_decode(path: &PathBuf, buffer_seconds: f32) -> Result<PrerolledDecode> {
+    let file = File::open(path).with_context(|| format!("open {:?}", path))?;
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    preroll_decode_from_media_source(Box::new(file), hint, buffer_seconds)
+}
+
+/// Seek `format` to `ms` on its default track.
+///
+/// Returns the requested timestamp: the reader lands on the packet containing it, so decoded
+/// frames before it still have to be dropped.
+fn seek_format(format: &mut dyn FormatReader, ms: u64) -> Result<u64> {
+    let time = Time::new(ms / 1000, (ms % 1000) as f64 / 1000.0);
+    let seeked = format.seek(
+        SeekMode::Accurate,
+        SeekTo::Time {
+            time,
+            track_id: None,
+        },
+    )?;
+    Ok(seeked.required_ts)
+}
+
+/// Convert a position in milliseconds to a timestamp in the track's time base.
+fn ms_to_ts(ms: u64, time_base: Option<TimeBase>, rate: u32) -> u64 {
+    match time_base {
+        Some(tb) => tb.calc_timestamp(Time::new(ms / 1000, (ms % 1000) as f64 / 1000.0)),
+        None => ms * u64::from(rate) / 1000,
+    }
+}
+
+/// Part of the source a decode loop plays.
+#[derive(Clone, Copy, Debug, Default)]
+struct DecodeBounds {
+    /// Offset added to seek targets, which are relative to the start of the range.
+    start_ms: u64,
+    /// Timestamp where decoding stops.
+    end_ts: Option<u64>,
+}
+
+/// Applies a [`DecodeErrorPolicy`] to the corrupt packets of one decode.
+struct PacketErrors {
+    policy: DecodeErrorPolicy,
+    stats: Arc<DecodeErrorStats>,
+    /// Corrupt packets since the last good one.
+    consecutive: u32,
+    rate: u32,
+    time_base: Option<TimeBase>,
+}
+
+impl PacketErrors {
+    /// A packet decoded cleanly.
+    fn recovered(&mut self) {
+        self.consecutive = 0;
+    }
+
+    /// Count a corrupt `packet` (`None` when the container could not read it).
+    ///
+    /// Returns the frames of silence to play in its place, or an error when the policy ends
+    /// the decode.
+    fn corrupt(&mut self, packet: Option<&Packet>, err: &SymphoniaError) -> Result<usize> {
+        let total = self.stats.corrupt_packets.fetch_add(1, Ordering::Relaxed) + 1;
+        self.consecutive += 1;
+        let ts = packet.map(Packet::ts);
+        let give_up = match self.policy.action {
+            DecodeErrorAction::Abort => true,
+            _ => self.consecutive >= self.policy.max_consecutive.max(1),
+        };
+        if give_up {
+            self.stats.aborted.store(true, Ordering::Relaxed);
+            return Err(anyhow!(
+                "corrupt packet at ts {ts:?} ({} in a row, {total} total): {err}",
+                self.consecutive
+            ));
+        }
+        tracing::warn!(
+            ts = ?ts,
+            consecutive = self.consecutive,
+            total,
+            action = self.policy.action.as_str(),
+            error = %err,
+            "corrupt packet"
+        );
+        Ok(match (self.policy.action, packet) {
+            (DecodeErrorAction::Silence, Some(packet)) => self.packet_frames(packet),
+            _ => 0,
+        })
+    }
+
+    /// Duration of `packet` in frames.
+    fn packet_frames(&self, packet: &Packet) -> usize {
+        match self.time_base {
+            Some(tb) => {
+                let time = tb.calc_time(packet.dur());
+                ((time.seconds as f64 + time.frac) * f64::from(self.rate)).round() as usize
+            }
+            None => packet.dur() as usize,
+        }
+    }
+}
+
+/// Service a pending seek: reposition the reader, reset the codec, and drop queued audio.
+///
+/// Returns the timestamp decoding must skip to when the seek succeeded.
+fn service_seek(
+    format: &mut dyn FormatReader,
+    decoder: &mut dyn Decoder,
+    shared: &SharedAudio,
+    seek: &DecodeSeek,
+    start_ms: u64,
+) -> Option<u64> {
+    let ms = seek.pending()?;
+    let result = seek_format(format, start_ms + ms);
+    match &result {
+        Ok(_) => {
+            decoder.reset();
+            tracing::info!(position_ms = ms, "decode seek");
+        }
+        Err(e) => tracing::warn!(position_ms = ms, error = %e, "decode seek failed"),
+    }
+    // Anything pushed between the request and now predates the seek.
+    shared.flush();
+    let skip_to_ts = result.as_ref().ok().copied();
+    seek.complete(ms, result.map(|_| ()));
+    skip_to_ts
+}
+
 /// Decode packets from a probed `FormatReader` and push interleaved `f32` into `shared`.
 ///
 /// This runs in the background thread spawned by `start_streaming_decode_from_media_source`.
+/// Frames before `skip_to_ts` (an initial seek target) are dropped, and decoding ends at
+/// `bounds.end_ts`. Corrupt packets are handled by `errors`.
 fn decode_format_loop(
-    mut format: Box<dyn symphonia::core::formats::FormatReader>,
-    codec_params: CodecParameters,
+    mut format: Box<dyn FormatReader>,
+    mut decoder: Box<dyn Decoder>,
     shared: &Arc<SharedAudio>,
+    seek: &DecodeSeek,
+    mut skip_to_ts: Option<u64>,
+    bounds: DecodeBounds,
+    errors: &mut PacketErrors,
 ) -> Result<()> {
-    let mut decoder =
-        symphonia::default::get_codecs().make(&codec_params, &DecoderOptions::default())?;
+    // Reused across packets; regrown only when a packet outgrows it.
+    let mut sample_buf: Option<SampleBuffer<f32>> = None;
 
     loop {
+        if let Some(ts) = service_seek(
+            format.as_mut(),
+            decoder.as_mut(),
+            shared,
+            seek,
+            bounds.start_ms,
+        ) {
+            skip_to_ts = Some(ts);
+        }
+
         let packet = match format.next_packet() {
             Ok(p) => p,
-            Err(_) => break, // EOF
+            // EOF, or a transport failure the source reports itself.
+            Err(SymphoniaError::IoError(e)) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(SymphoniaError::IoError(e)) => {
+                tracing::warn!(error = %e, "decode read failed");
+                break;
+            }
+            Err(e @ SymphoniaError::DecodeError(_)) => {
+                errors.corrupt(None, &e)?;
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "decode stopped");
+                break;
+            }
         };
+        if bounds.end_ts.is_some_and(|end| packet.ts() >= end) {
+            break;
+        }
 
         let decoded = match decoder.decode(&packet) {
             Ok(d) => d,
-            Err(_) => continue,
+            Err(SymphoniaError::ResetRequired) => {
+                decoder.reset();
+                continue;
+            }
+            Err(e) => {
+                let silence = errors.corrupt(Some(&packet), &e)?;
+                if silence > 0 && skip_to_ts.is_none() {
+                    let frames = bounds.end_ts.map_or(silence, |end| {
+                        (end.saturating_sub(packet.ts()) as usize).min(silence)
+                    });
+                    shared.push_interleaved_blocking(&vec![0.0; frames * shared.channels()]);
+                }
+                continue;
+            }
         };
+        errors.recovered();
 
-        let mut sample_buf = SampleBuffer::<f32>::new(decoded.frames() as u64, *decoded.spec());
+        // Drop the part of the first packets after a seek that precedes the target.
+        let frames = decoded.frames();
+        let skip_frames = skip_to_ts.map_or(0, |ts| ts.saturating_sub(packet.ts()) as usize);
+        if skip_frames >= frames {
+            continue;
+        }
+        skip_to_ts = None;
+        // Cut the last packet of a range at its end.
+        let keep_frames = bounds.end_ts.map_or(frames, |end| {
+            (end.saturating_sub(packet.ts()) as usize).min(frames)
+        });
+
+        let channels = decoded.spec().channels.count();
+        let sample_buf = match &mut sample_buf {
+            Some(buf) if buf.capacity() >= frames * channels => buf,
+            slot => slot.insert(SampleBuffer::<f32>::new(
+                decoded.capacity() as u64,
+                *decoded.spec(),
+            )),
+        };
         sample_buf.copy_interleaved_ref(decoded);
 
-        shared.push_interleaved_blocking(sample_buf.samples());
+        shared.push_interleaved_blocking(
+            &sample_buf.samples()[skip_frames * channels..keep_frames.max(skip_frames) * channels],
+        );
     }
 
     Ok(())
@@ -184,6 +721,31 @@ fn duration_ms_from_codec_params(codec_params: &CodecParameters) -> Option<u64>
     Some(frames.saturating_mul(1000) / rate)
 }
 
+/// Channel layout for `count` channels when the source does not name its speakers.
+pub(crate) fn channel_layout(count: usize) -> Result<Channels> {
+    let layout = match count {
+        1 => Channels::FRONT_CENTRE,
+        2 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+        _ => (0..count).try_fold(Channels::empty(), |acc, ch| {
+            Channels::from_bits(1 << ch)
+                .map(|bit| acc | bit)
+                .ok_or_else(|| anyhow!("too many channels ({count})"))
+        })?,
+    };
+    Ok(layout)
+}
+
+/// Channel count from an ALAC magic cookie.
+///
+/// MP4 demuxing leaves the channels of ALAC tracks unset; only the cookie carries them.
+fn alac_cookie_channels(params: &CodecParameters) -> Option<usize> {
+    if params.codec != symphonia::core::codecs::CODEC_TYPE_ALAC {
+        return None;
+    }
+    let channels = *params.extra_data.as_deref()?.get(9)?;
+    (channels > 0).then_some(usize::from(channels))
+}
+
 /// Best-effort codec label used for status payloads.
 fn codec_name_from_params(params: &CodecParameters) -> Option<String> {
     use symphonia::core::codecs::*;
@@ -239,4 +801,205 @@ mod tests {
         let params = CodecParameters::new();
         assert!(codec_name_from_params(&params).is_none());
     }
+
+    fn packet_errors(action: DecodeErrorAction, max_consecutive: u32) -> PacketErrors {
+        PacketErrors {
+            policy: DecodeErrorPolicy {
+                action,
+                max_consecutive,
+            },
+            stats: Default::default(),
+            consecutive: 0,
+            rate: 48_000,
+            time_base: Some(TimeBase::new(1, 48_000)),
+        }
+    }
+
+    #[test]
+    fn packet_errors_apply_policy_and_count() {
+        let err = SymphoniaError::DecodeError("bad frame");
+        let packet = Packet::new_from_slice(0, 0, 1152, &[]);
+
+        let mut silence = packet_errors(DecodeErrorAction::Silence, 3);
+        assert_eq!(silence.corrupt(Some(&packet), &err).unwrap(), 1152);
+        assert_eq!(silence.corrupt(None, &err).unwrap(), 0);
+        silence.recovered();
+        assert_eq!(silence.corrupt(Some(&packet), &err).unwrap(), 1152);
+        assert_eq!(silence.stats.corrupt_packets.load(Ordering::Relaxed), 3);
+        assert!(!silence.stats.aborted.load(Ordering::Relaxed));
+
+        let mut skip = packet_errors(DecodeErrorAction::Skip, 2);
+        assert_eq!(skip.corrupt(Some(&packet), &err).unwrap(), 0);
+        assert!(skip.corrupt(Some(&packet), &err).is_err());
+        assert!(skip.stats.aborted.load(Ordering::Relaxed));
+
+        let mut abort = packet_errors(DecodeErrorAction::Abort, 50);
+        assert!(abort.corrupt(Some(&packet), &err).is_err());
+        assert_eq!(abort.stats.corrupt_packets.load(Ordering::Relaxed), 1);
+    }
+
+    /// Mono 16-bit PCM WAV whose sample `i` has the value `i`.
+    fn ramp_wav(rate: u32, frames: u32) -> Vec<u8> {
+        let data_len = frames * 2;
+        let mut out = Vec::with_capacity(44 + data_len as usize);
+        out.extend_from_slice(b"RIFF");
+        out.extend_from_slice(&(36 + data_len).to_le_bytes());
+        out.extend_from_slice(b"WAVEfmt ");
+        out.extend_from_slice(&16u32.to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&rate.to_le_bytes());
+        out.extend_from_slice(&(rate * 2).to_le_bytes());
+        out.extend_from_slice(&2u16.to_le_bytes());
+        out.extend_from_slice(&16u16.to_le_bytes());
+        out.extend_from_slice(b"data");
+        out.extend_from_slice(&data_len.to_le_bytes());
+        for i in 0..frames {
+            out.extend_from_slice(&(i as i16).to_le_bytes());
+        }
+        out
+    }
+
+    #[test]
+    fn seek_streaming_decode_repositions_running_decode() {
+        let mut hint = Hint::new();
+        hint.with_extension("wav");
+        let (_spec, queue, _duration_ms, _info, seek) = start_seekable_decode_from_media_source(
+            Box::new(std::io::Cursor::new(ramp_wav(8_000, 16_000))),
+            hint,
+            0.05,
+        )
+        .unwrap();
+        let played = Arc::new(AtomicU64::new(0));
+        seek.track_played_frames(played.clone(), 8_000);
+
+        seek_streaming_decode(&seek, 1_000).unwrap();
+        let first = queue
+            .pop(crate::queue::PopStrategy::BlockingExact { frames: 1 })
+            .unwrap();
+        assert_eq!((first[0] * 32_768.0).round() as i32, 8_000);
+        assert_eq!(played.load(Ordering::Relaxed), 8_000);
+
+        while queue
+            .pop(crate::queue::PopStrategy::BlockingUpTo { max_frames: 4096 })
+            .is_some()
+        {}
+        assert!(seek_streaming_decode(&seek, 0).is_err());
+    }
+
+    #[test]
+    fn range_decode_plays_only_the_range() {
+        let mut hint = Hint::new();
+        hint.with_extension("wav");
+        let range = TrackRange {
+            start_ms: 500,
+            end_ms: Some(1_500),
+        };
+        let (_spec, queue, duration_ms, _info) = start_streaming_decode_range_from_media_source(
+            Box::new(std::io::Cursor::new(ramp_wav(8_000, 16_000))),
+            hint,
+            1.0,
+            Some(250),
+            range,
+            DecodeErrorPolicy::default(),
+        )
+        .unwrap();
+        assert_eq!(duration_ms, Some(1_000));
+
+        let mut samples = Vec::new();
+        while let Some(chunk) =
+            queue.pop(crate::queue::PopStrategy::BlockingUpTo { max_frames: 4096 })
+        {
+            samples.extend(chunk);
+        }
+        let values: Vec<i32> = samples
+            .iter()
+            .map(|s| (s * 32_768.0).round() as i32)
+            .collect();
+        assert_eq!(values.first(), Some(&6_000));
+        assert_eq!(values.last(), Some(&11_999));
+        assert_eq!(values.len(), 6_000);
+    }
+
+    #[test]
+    fn preroll_decode_primes_queue_and_reports_frames() {
+        let mut hint = Hint::new();
+        hint.with_extension("wav");
+        let next = preroll_decode_from_media_source(
+            Box::new(std::io::Cursor::new(ramp_wav(8_000, 4_000))),
+            hint,
+            1.0,
+        )
+        .unwrap();
+        assert!(next.queue.len_frames() > 0);
+        let spec = next.spec;
+        assert!(next.continues(&spec));
+        assert!(!next.continues(&SignalSpec::new(44_100, next.spec.channels)));
+        assert_eq!(
+            crate::source::Source::frames(&next.into_source()),
+            Some(4_000)
+        );
+    }
+
+    fn decode_all(bytes: Vec<u8>, extension: &str) -> Result<(SignalSpec, SourceInfo, Vec<f32>)> {
+        let mut hint = Hint::new();
+        hint.with_extension(extension);
+        let (spec, queue, _duration_ms, info) = start_streaming_decode_from_media_source(
+            Box::new(std::io::Cursor::new(bytes)),
+            hint,
+            1.0,
+        )?;
+        let mut samples = Vec::new();
+        while let Some(chunk) =
+            queue.pop(crate::queue::PopStrategy::BlockingUpTo { max_frames: 4096 })
+        {
+            samples.extend(chunk);
+        }
+        Ok((spec, info, samples))
+    }
+
+    #[test]
+    fn decodes_aac_adts() {
+        let (spec, info, samples) =
+            decode_all(crate::test_media::aac_adts_silence(8), "aac").unwrap();
+        assert_eq!((spec.rate, spec.channels.count()), (44_100, 1));
+        assert_eq!(info.codec.as_deref(), Some("AAC"));
+        assert!(!samples.is_empty());
+        assert!(samples.iter().all(|s| s.abs() < 1e-6));
+    }
+
+    #[test]
+    fn decodes_alac_in_m4a() {
+        let ramp: Vec<i16> = (0..2 * crate::test_media::ALAC_FRAME_LEN as i16)
+            .map(|i| i * 8 - 4_096)
+            .collect();
+        let (spec, info, samples) =
+            decode_all(crate::test_media::alac_m4a(48_000, &ramp), "m4a").unwrap();
+        assert_eq!((spec.rate, spec.channels.count()), (48_000, 1));
+        assert_eq!(info.codec.as_deref(), Some("ALAC"));
+        let values: Vec<i16> = samples
+            .iter()
+            .map(|s| (s * 32_768.0).round() as i16)
+            .collect();
+        assert_eq!(values, ramp);
+    }
+
+    #[test]
+    fn decodes_ogg_vorbis() {
+        let (spec, info, samples) =
+            decode_all(crate::test_media::ogg_vorbis_silence(44_100, 2, 9), "ogg").unwrap();
+        assert_eq!((spec.rate, spec.channels.count()), (44_100, 2));
+        assert_eq!(info.codec.as_deref(), Some("VORBIS"));
+        assert_eq!(samples.len(), 8 * 128 * 2);
+        assert!(samples.iter().all(|s| s.abs() < 1e-6));
+    }
+
+    #[test]
+    fn unsupported_opus_fails_before_playback() {
+        let err = decode_all(crate::test_media::ogg_opus(2), "opus").unwrap_err();
+        assert!(
+            format!("{err:#}").contains("no decoder for OPUS audio"),
+            "{err:#}"
+        );
+    }
 }
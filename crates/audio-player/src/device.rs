@@ -2,30 +2,239 @@
 //!
 //! Thin wrappers around CPAL for:
 //! - listing available output devices
-//! - selecting either the default device or a device by substring match
+//! - selecting either the default device, a device by platform id or stable id, or by substring
+//!   match
+//! - following the OS default output (`default-follow`)
+//! - opening raw ALSA PCMs (`hw:`/`plughw:`) directly, bypassing PulseAudio/PipeWire
+//! - routing output through a JACK server instead of the platform host (`jack` feature)
+//! - a hardware-free null output for headless testing
 
 use anyhow::{Context, Result, anyhow};
 use cpal::traits::{DeviceTrait, HostTrait};
 use std::collections::HashSet;
+use std::str::FromStr;
 use std::sync::{Mutex, OnceLock};
 
+use crate::config::RateSwitch;
+use crate::null_output::NullPace;
+
+/// Audio backend that output hosts are created from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum OutputBackend {
+    /// Platform default host (ALSA, CoreAudio, WASAPI).
+    #[default]
+    System,
+    /// JACK server, with output ports wired per [`JackPorts`] (requires the `jack` feature).
+    Jack(JackPorts),
+    /// No hardware: a single [`null_output`](crate::null_output) device that discards samples.
+    Null(NullPace),
+}
+
+/// Where JACK output ports are connected once a stream opens.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum JackPorts {
+    /// Physical playback ports (`system:playback_*`).
+    #[default]
+    System,
+    /// Leave ports unconnected (route them with a patchbay).
+    None,
+    /// Destination port names or regexes, assigned to output channels in order.
+    Ports(Vec<String>),
+}
+
+impl FromStr for JackPorts {
+    type Err = anyhow::Error;
+
+    /// Parse `system`, `none`, or a comma-separated list of destination ports.
+    fn from_str(value: &str) -> Result<Self> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("system") {
+            return Ok(Self::System);
+        }
+        if value.eq_ignore_ascii_case("none") {
+            return Ok(Self::None);
+        }
+        let ports: Vec<String> = value
+            .split(',')
+            .map(str::trim)
+            .filter(|port| !port.is_empty())
+            .map(str::to_string)
+            .collect();
+        if ports.is_empty() {
+            return Err(anyhow!(
+                "expected `system`, `none`, or a comma-separated list of JACK ports"
+            ));
+        }
+        Ok(Self::Ports(ports))
+    }
+}
+
+/// Whether this build can open the JACK backend.
+pub fn jack_supported() -> bool {
+    cfg!(feature = "jack")
+}
+
+fn backend_store() -> &'static Mutex<OutputBackend> {
+    static BACKEND: OnceLock<Mutex<OutputBackend>> = OnceLock::new();
+    BACKEND.get_or_init(|| Mutex::new(OutputBackend::default()))
+}
+
+/// Select the backend used by [`output_host`] for the rest of the process.
+pub fn set_output_backend(backend: OutputBackend) {
+    if let Ok(mut guard) = backend_store().lock() {
+        *guard = backend;
+    }
+}
+
+/// Currently selected output backend.
+pub fn output_backend() -> OutputBackend {
+    backend_store()
+        .lock()
+        .map(|guard| guard.clone())
+        .unwrap_or_default()
+}
+
+/// Create the CPAL host for the selected [`OutputBackend`].
+pub fn output_host() -> Result<cpal::Host> {
+    match output_backend() {
+        OutputBackend::System => Ok(cpal::default_host()),
+        #[cfg(feature = "jack")]
+        OutputBackend::Jack(_) => {
+            cpal::host_from_id(cpal::HostId::Jack).context("JACK host unavailable")
+        }
+        #[cfg(not(feature = "jack"))]
+        OutputBackend::Jack(_) => Err(anyhow!(
+            "JACK backend not available (build with the `jack` feature)"
+        )),
+        OutputBackend::Null(pace) => Ok(crate::null_output::host(pace)),
+    }
+}
+
+/// Rewire a freshly opened JACK stream's ports per the selected [`JackPorts`].
+///
+/// CPAL connects JACK outputs to `system:playback_*`; other hosts are left untouched.
+pub(crate) fn wire_output_ports(device: &cpal::Device, channels: u16) {
+    #[cfg(feature = "jack")]
+    if let OutputBackend::Jack(ports) = output_backend()
+        && let Ok(cpal::DeviceId(cpal::HostId::Jack, client_name)) = device.id()
+        && let Err(err) = crate::jack_ports::apply(&client_name, channels, &ports)
+    {
+        tracing::warn!(client = %client_name, error = %err, "jack: failed to connect output ports");
+    }
+    #[cfg(not(feature = "jack"))]
+    let _ = (device, channels);
+}
+
+/// Device selector that tracks the OS default output instead of pinning one device.
+pub const DEFAULT_FOLLOW: &str = "default-follow";
+
+/// Whether a device selector asks to follow the OS default output.
+pub fn is_default_follow(needle: Option<&str>) -> bool {
+    needle
+        .map(|n| n.trim().eq_ignore_ascii_case(DEFAULT_FOLLOW))
+        .unwrap_or(false)
+}
+
+/// Whether a device selector is a stable platform device id (`<host>:<uid>`).
+///
+/// Ids stay unique when several interfaces share a display name.
+pub fn is_device_id(selector: &str) -> bool {
+    selector.trim().parse::<cpal::DeviceId>().is_ok()
+}
+
+/// Prefix of [stable device ids](stable_device_id).
+const STABLE_ID_PREFIX: &str = "dev-";
+
+/// Whether a device selector is a [stable device id](stable_device_id) (`dev-<hash>`).
+pub fn is_stable_id(selector: &str) -> bool {
+    selector
+        .trim()
+        .strip_prefix(STABLE_ID_PREFIX)
+        .is_some_and(|hash| hash.len() == 16 && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// Whether a selector names a raw ALSA PCM (`hw:0,0`, `plughw:CARD=PCH,DEV=0`).
+///
+/// `hw:` opens the card directly (bit-perfect, single client); `plughw:` adds ALSA's own
+/// format/rate conversion but still bypasses the sound server.
+pub fn is_alsa_direct(selector: &str) -> bool {
+    normalize_alsa_pcm(selector).is_some()
+}
+
+/// Normalize ALSA shorthand (`hw:1`, `hw:1,0`) to CPAL's `hw:CARD=1,DEV=0` PCM id.
+fn normalize_alsa_pcm(selector: &str) -> Option<String> {
+    let selector = selector.trim();
+    let (prefix, rest) = selector.split_once(':')?;
+    let prefix = prefix.to_ascii_lowercase();
+    if prefix != "hw" && prefix != "plughw" {
+        return None;
+    }
+    if rest.is_empty() {
+        return None;
+    }
+    if rest.contains('=') {
+        return Some(format!("{prefix}:{rest}"));
+    }
+    let (card, dev) = rest.split_once(',').unwrap_or((rest, "0"));
+    let dev: u32 = dev.trim().parse().ok()?;
+    Some(format!("{prefix}:CARD={},DEV={dev}", card.trim()))
+}
+
 /// Pick a CPAL output device.
 ///
-/// - If `needle` is `Some`, chooses the first output device whose name contains the substring
-///   (case-insensitive).
-/// - Otherwise, returns the host default output device.
+/// - If `needle` is a platform device id (see [`is_device_id`]) or a stable id (see
+///   [`is_stable_id`]), chooses that exact device.
+/// - If `needle` is an ALSA PCM (see [`is_alsa_direct`]), chooses that PCM on the ALSA host.
+/// - Otherwise, if `needle` is `Some`, chooses the first output device whose name contains the
+///   substring (case-insensitive).
+/// - Otherwise (or when `needle` is [`DEFAULT_FOLLOW`]), returns the host default output device.
 ///
 /// Returns an error if no matching device exists or if the host reports no output devices.
 /// Pick the first output device matching `needle` (case-insensitive), or the default device.
 ///
 /// Returns an error if no suitable device is found.
 pub fn pick_device(host: &cpal::Host, needle: Option<&str>) -> Result<cpal::Device> {
+    if is_default_follow(needle) {
+        return host
+            .default_output_device()
+            .ok_or_else(|| anyhow!("No default output device"));
+    }
+
     let mut devices: Vec<cpal::Device> = host
         .output_devices()
         .context("No output devices")?
         .collect();
 
     if let Some(needle) = needle {
+        if let Ok(wanted) = needle.trim().parse::<cpal::DeviceId>()
+            && let Some(idx) = devices
+                .iter()
+                .position(|d| d.id().map(|id| id == wanted).unwrap_or(false))
+        {
+            return Ok(devices.swap_remove(idx));
+        }
+        if is_stable_id(needle) {
+            let idx = stable_device_ids(host.id(), &devices)
+                .iter()
+                .position(|id| id.eq_ignore_ascii_case(needle.trim()))
+                .ok_or_else(|| anyhow!("No output device with id {}", needle.trim()))?;
+            return Ok(devices.swap_remove(idx));
+        }
+        if let Some(pcm) = normalize_alsa_pcm(needle) {
+            let wanted = format!("alsa:{pcm}");
+            return devices
+                .into_iter()
+                .find(|d| {
+                    d.id()
+                        .map(|id| id.to_string().eq_ignore_ascii_case(&wanted))
+                        .unwrap_or(false)
+                })
+                .ok_or_else(|| {
+                    anyhow!(
+                        "ALSA device {pcm} not found (direct hw access needs the ALSA host; see --list-devices)"
+                    )
+                });
+        }
         if let Some(d) = devices.drain(..).find(|d| {
             d.description()
                 .ok()
@@ -41,6 +250,40 @@ pub fn pick_device(host: &cpal::Host, needle: Option<&str>) -> Result<cpal::Devi
         .ok_or_else(|| anyhow!("No default output device"))
 }
 
+/// Error for an output device that refused to open, with a hint for exclusive ALSA PCMs.
+pub fn unavailable_error(device: &cpal::Device) -> anyhow::Error {
+    let name = device
+        .description()
+        .map(|d| d.name().to_string())
+        .unwrap_or_else(|_| "output device".to_string());
+    let direct = device
+        .id()
+        .ok()
+        .map(|id| id.to_string())
+        .and_then(|id| id.strip_prefix("alsa:").map(str::to_string))
+        .filter(|pcm| pcm.starts_with("hw:"));
+    match direct {
+        Some(pcm) => anyhow!(
+            "ALSA device {pcm} ({name}) is busy: direct hw access allows one client; \
+             stop PulseAudio/PipeWire or other players using it, or select plughw:/default"
+        ),
+        None => anyhow!("output device {name} is busy or no longer available"),
+    }
+}
+
+/// Whether two handles refer to the same output device.
+///
+/// Compares CPAL device ids, falling back to device names when ids are unavailable.
+pub fn same_device(a: &cpal::Device, b: &cpal::Device) -> bool {
+    match (a.id(), b.id()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => match (a.description(), b.description()) {
+            (Ok(a), Ok(b)) => a.name() == b.name(),
+            _ => false,
+        },
+    }
+}
+
 /// Pick the best supported output config for the device.
 ///
 /// If `target_rate` is `Some`, prefer the highest supported sample rate that is
@@ -53,8 +296,22 @@ pub fn pick_output_config(
     device: &cpal::Device,
     target_rate: Option<u32>,
 ) -> Result<cpal::SupportedStreamConfig> {
-    let ranges: Vec<cpal::SupportedStreamConfigRange> =
-        device.supported_output_configs()?.collect();
+    pick_output_config_ranked(device, target_rate, sample_format_rank)
+}
+
+/// [`pick_output_config`] with `format_rank` ordering sample formats (lower is better).
+fn pick_output_config_ranked(
+    device: &cpal::Device,
+    target_rate: Option<u32>,
+    format_rank: impl Fn(cpal::SampleFormat) -> u8,
+) -> Result<cpal::SupportedStreamConfig> {
+    let ranges: Vec<cpal::SupportedStreamConfigRange> = match device.supported_output_configs() {
+        Ok(ranges) => ranges.collect(),
+        Err(cpal::SupportedStreamConfigsError::DeviceNotAvailable) => {
+            return Err(unavailable_error(device));
+        }
+        Err(err) => return Err(err.into()),
+    };
     if ranges.is_empty() {
         return Err(anyhow!("No supported output configs"));
     }
@@ -66,7 +323,7 @@ pub fn pick_output_config(
         let max = range.max_sample_rate();
         let rate = pick_rate_for_range(min, max, target_rate);
         let below = target_rate.map(|t| rate <= t).unwrap_or(true);
-        let format_rank = sample_format_rank(range.sample_format());
+        let format_rank = format_rank(range.sample_format());
         let cfg = range.with_sample_rate(rate);
         let candidate = (below, rate, format_rank, cfg);
         let replace = match &best {
@@ -83,6 +340,91 @@ pub fn pick_output_config(
     Ok(best.unwrap().3)
 }
 
+/// Pick the output config for a source at `source_rate` under the `rate_switch` preference.
+///
+/// [`RateSwitch::PreferSwitch`] asks for the source rate, which CPAL applies by reconfiguring
+/// the device (CoreAudio nominal rate, ALSA hw params) when it is supported.
+/// [`RateSwitch::PreferResample`] asks for the device's current default rate instead, leaving
+/// conversion to the resampler stage.
+pub fn pick_source_output_config(
+    device: &cpal::Device,
+    source_rate: u32,
+    rate_switch: RateSwitch,
+) -> Result<cpal::SupportedStreamConfig> {
+    let target = match rate_switch {
+        RateSwitch::PreferSwitch => source_rate,
+        RateSwitch::PreferResample => device
+            .default_output_config()
+            .map(|config| config.sample_rate())
+            .unwrap_or(source_rate),
+    };
+    pick_output_config(device, Some(target))
+}
+
+/// Per-output limits on the stream format opened for a session.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OutputFormatLimits {
+    /// Highest sample rate (Hz) to open the device at; faster sources are resampled.
+    pub max_rate: Option<u32>,
+    /// Preferred sample width (16, 24 or 32 bits) when the device offers several formats.
+    pub bit_depth: Option<u16>,
+}
+
+/// [`pick_source_output_config`] within `limits`.
+///
+/// The output writes `I16`, `I32` and `F32` streams, so a 16-bit preference picks `I16` and
+/// 24 or 32 bits pick `I32`; a device without that format keeps the usual preference order.
+pub fn pick_limited_output_config(
+    device: &cpal::Device,
+    source_rate: u32,
+    rate_switch: RateSwitch,
+    limits: OutputFormatLimits,
+) -> Result<cpal::SupportedStreamConfig> {
+    if limits == OutputFormatLimits::default() {
+        return pick_source_output_config(device, source_rate, rate_switch);
+    }
+    let target = match rate_switch {
+        RateSwitch::PreferSwitch => source_rate,
+        RateSwitch::PreferResample => device
+            .default_output_config()
+            .map(|config| config.sample_rate())
+            .unwrap_or(source_rate),
+    };
+    let target = limits.max_rate.map_or(target, |max| target.min(max));
+    let preferred = match limits.bit_depth {
+        Some(16) => Some(cpal::SampleFormat::I16),
+        Some(24 | 32) => Some(cpal::SampleFormat::I32),
+        _ => None,
+    };
+    pick_output_config_ranked(device, Some(target), |format| {
+        if Some(format) == preferred {
+            0
+        } else {
+            sample_format_rank(format).saturating_add(1)
+        }
+    })
+}
+
+/// Pick an output config able to carry DSD-over-PCM at `rate` with `channels` channels.
+///
+/// DoP needs the exact rate and channel count and a 32-bit integer format (the marker and
+/// DSD bits occupy the top 24 bits); `None` when the device offers no such config.
+pub fn pick_dop_output_config(
+    device: &cpal::Device,
+    rate: u32,
+    channels: u16,
+) -> Option<cpal::SupportedStreamConfig> {
+    device
+        .supported_output_configs()
+        .ok()?
+        .find(|range| {
+            range.sample_format() == cpal::SampleFormat::I32
+                && range.channels() == channels
+                && (range.min_sample_rate()..=range.max_sample_rate()).contains(&rate)
+        })
+        .map(|range| range.with_sample_rate(rate))
+}
+
 /// Pick a stream buffer size, preferring larger values to reduce underruns.
 ///
 /// If the device reports a range, choose the max. If `Unknown`, return `None`
@@ -155,9 +497,16 @@ fn is_better_candidate(
 /// This is intended for CLI UX (`--list-devices`) rather than structured output.
 /// Log available output devices for the current host.
 pub fn list_devices(host: &cpal::Host) -> Result<()> {
-    let devices = host.output_devices().context("No output devices")?;
-    for (i, d) in devices.enumerate() {
-        println!("#{i}: {}", d.description()?);
+    let devices: Vec<cpal::Device> = host
+        .output_devices()
+        .context("No output devices")?
+        .collect();
+    let stable_ids = stable_device_ids(host.id(), &devices);
+    for (i, (d, stable_id)) in devices.iter().zip(stable_ids).enumerate() {
+        match d.id() {
+            Ok(id) => println!("#{i}: {} [{id}] ({stable_id})", d.description()?),
+            Err(_) => println!("#{i}: {} ({stable_id})", d.description()?),
+        }
     }
     Ok(())
 }
@@ -165,30 +514,54 @@ pub fn list_devices(host: &cpal::Host) -> Result<()> {
 #[derive(Clone, Debug)]
 /// Lightweight output device metadata for UI/device selection.
 pub struct DeviceInfo {
-    /// Stable device identifier.
+    /// Platform device identifier (or a name/rate hash when the platform has none).
     pub id: String,
+    /// Identifier that survives reboots and re-enumeration (see [`stable_device_id`]).
+    pub stable_id: String,
     /// Human-readable device name/description.
     pub name: String,
     /// Minimum supported sample rate in Hz.
     pub min_rate: u32,
     /// Maximum supported sample rate in Hz.
     pub max_rate: u32,
+    /// Standard sample rates (Hz) the device accepts natively, ascending.
+    pub sample_rates: Vec<u32>,
+    /// Supported sample formats (`F32`, `I32`, `I16`, ...).
+    pub sample_formats: Vec<String>,
+    /// Supported channel counts, ascending.
+    pub channels: Vec<u16>,
 }
 
+/// Sample rates probed against each supported config range.
+const STANDARD_RATES: &[u32] = &[
+    32_000, 44_100, 48_000, 88_200, 96_000, 176_400, 192_000, 352_800, 384_000,
+];
+
 /// Return device metadata for output selection UIs.
 pub fn list_device_infos(host: &cpal::Host) -> Result<Vec<DeviceInfo>> {
-    let devices = host.output_devices().context("No output devices")?;
+    let devices: Vec<cpal::Device> = host
+        .output_devices()
+        .context("No output devices")?
+        .collect();
+    let stable_ids = stable_device_ids(host.id(), &devices);
     let mut out = Vec::new();
-    for d in devices {
+    for (d, stable_id) in devices.into_iter().zip(stable_ids) {
         let name = d.description()?.to_string();
         let cache_key = device_cache_key(&d, &name);
         let mut min_rate = u32::MAX;
         let mut max_rate = 0u32;
+        let mut caps = Capabilities::default();
         match d.supported_output_configs() {
             Ok(ranges) => {
                 for r in ranges {
                     min_rate = min_rate.min(r.min_sample_rate());
                     max_rate = max_rate.max(r.max_sample_rate());
+                    caps.add_range(
+                        r.min_sample_rate(),
+                        r.max_sample_rate(),
+                        r.sample_format(),
+                        r.channels(),
+                    );
                 }
                 if min_rate == u32::MAX {
                     min_rate = 0;
@@ -202,6 +575,7 @@ pub fn list_device_infos(host: &cpal::Host) -> Result<Vec<DeviceInfo>> {
                 let sr = default_cfg.sample_rate();
                 min_rate = sr;
                 max_rate = sr;
+                caps.add_range(sr, sr, default_cfg.sample_format(), default_cfg.channels());
             }
         }
 
@@ -230,16 +604,59 @@ pub fn list_device_infos(host: &cpal::Host) -> Result<Vec<DeviceInfo>> {
 
         update_cached_rates(&cache_key, min_rate, max_rate);
         let id = device_id_for(&d, &name, min_rate, max_rate);
+        let Capabilities {
+            sample_rates,
+            sample_formats,
+            channels,
+        } = caps;
         out.push(DeviceInfo {
             id,
+            stable_id,
             name,
             min_rate,
             max_rate,
+            sample_rates,
+            sample_formats,
+            channels,
         });
     }
     Ok(out)
 }
 
+/// Rates/formats/channels accumulated across a device's supported config ranges.
+#[derive(Default)]
+struct Capabilities {
+    sample_rates: Vec<u32>,
+    sample_formats: Vec<String>,
+    channels: Vec<u16>,
+}
+
+impl Capabilities {
+    /// Merge one supported config range, keeping each list sorted and deduplicated.
+    fn add_range(&mut self, min: u32, max: u32, format: cpal::SampleFormat, channels: u16) {
+        let mut rates: Vec<u32> = STANDARD_RATES
+            .iter()
+            .copied()
+            .filter(|r| (min..=max).contains(r))
+            .collect();
+        if rates.is_empty() && min == max && min > 0 {
+            rates.push(min);
+        }
+        for rate in rates {
+            if let Err(idx) = self.sample_rates.binary_search(&rate) {
+                self.sample_rates.insert(idx, rate);
+            }
+        }
+        let format = format!("{format:?}");
+        if !self.sample_formats.contains(&format) {
+            self.sample_formats.push(format);
+        }
+        if let Err(idx) = self.channels.binary_search(&channels) {
+            self.channels.insert(idx, channels);
+        }
+    }
+}
+
 /// Return stable device id or deterministic fallback hash when CPAL id is unavailable.
 fn device_id_for(device: &cpal::Device, name: &str, min_rate: u32, max_rate: u32) -> String {
     if let Ok(id) = device.id() {
@@ -248,6 +665,94 @@ fn device_id_for(device: &cpal::Device, name: &str, min_rate: u32, max_rate: u32
     hash_device_id(name, min_rate, max_rate)
 }
 
+/// Identifier for `device` that survives reboots and re-enumeration: `dev-<hash>`.
+///
+/// The hash covers the host and the device's hardware identity. For ALSA cards on USB that is
+/// the vendor/product id and serial number instead of the card index/name, which the kernel
+/// hands out in plug order ("USB Audio Device (2)"). Other platform ids (CoreAudio UIDs, WASAPI
+/// endpoint ids, ALSA names of built-in cards) are already persistent and are hashed as-is;
+/// devices without an id fall back to their name.
+pub fn stable_device_id(host: cpal::HostId, device: &cpal::Device) -> String {
+    let key = match device.id() {
+        Ok(id) => hardware_key(&id.to_string()),
+        Err(_) => device
+            .description()
+            .map(|d| d.name().to_string())
+            .unwrap_or_default(),
+    };
+    format!(
+        "{STABLE_ID_PREFIX}{:016x}",
+        fnv1a(&format!("{}|{key}", host.name()))
+    )
+}
+
+/// [`stable_device_id`] for each of `devices`, kept unique.
+///
+/// Identical hardware without serial numbers hashes alike; repeats fall back to a hash of the
+/// platform id, which is only as stable as the platform's own naming.
+fn stable_device_ids(host: cpal::HostId, devices: &[cpal::Device]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    devices
+        .iter()
+        .map(|device| {
+            let id = stable_device_id(host, device);
+            if seen.insert(id.clone()) {
+                return id;
+            }
+            let platform = device.id().map(|id| id.to_string()).unwrap_or_default();
+            format!(
+                "{STABLE_ID_PREFIX}{:016x}",
+                fnv1a(&format!("{}|{platform}", host.name()))
+            )
+        })
+        .collect()
+}
+
+/// Hardware identity of a platform device id, with ALSA `CARD=` replaced by its USB identity.
+fn hardware_key(platform_id: &str) -> String {
+    let Some((head, rest)) = platform_id.split_once("CARD=") else {
+        return platform_id.to_string();
+    };
+    let (card, tail) = rest.split_at(rest.find(',').unwrap_or(rest.len()));
+    match usb_identity(card) {
+        Some(usb) => format!("{head}CARD=usb-{usb}{tail}"),
+        None => platform_id.to_string(),
+    }
+}
+
+/// `vendor:product[:serial]` of a USB sound card, from procfs/sysfs.
+#[cfg(target_os = "linux")]
+fn usb_identity(card: &str) -> Option<String> {
+    // `/proc/asound/<id>` links to `cardN`; numeric cards are `cardN` directly.
+    let dir = if card.bytes().all(|b| b.is_ascii_digit()) {
+        std::path::PathBuf::from(format!("/proc/asound/card{card}"))
+    } else {
+        std::fs::canonicalize(format!("/proc/asound/{card}")).ok()?
+    };
+    let usbid = std::fs::read_to_string(dir.join("usbid")).ok()?;
+    let usbid = usbid.trim();
+    if usbid.is_empty() {
+        return None;
+    }
+    // The card's device is the USB interface; the serial lives on its parent USB device.
+    let serial = dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(|n| std::fs::canonicalize(format!("/sys/class/sound/{n}/device")).ok())
+        .and_then(|iface| std::fs::read_to_string(iface.parent()?.join("serial")).ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    Some(match serial {
+        Some(serial) => format!("{usbid}:{serial}"),
+        None => usbid.to_string(),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn usb_identity(_card: &str) -> Option<String> {
+    None
+}
+
 /// Return cache key for per-device sample-rate cache entries.
 fn device_cache_key(device: &cpal::Device, name: &str) -> String {
     if let Ok(id) = device.id() {
@@ -290,18 +795,17 @@ fn should_warn_invalid_device(key: &str) -> bool {
 
 /// Build deterministic fallback device id from name and supported-rate range.
 fn hash_device_id(name: &str, min_rate: u32, max_rate: u32) -> String {
+    format!("{:016x}", fnv1a(&format!("{name}|{min_rate}|{max_rate}")))
+}
+
+/// 64-bit FNV-1a hash of `input`.
+fn fnv1a(input: &str) -> u64 {
     let mut hash: u64 = 0xcbf29ce484222325;
-    let mut input = String::new();
-    input.push_str(name);
-    input.push('|');
-    input.push_str(&min_rate.to_string());
-    input.push('|');
-    input.push_str(&max_rate.to_string());
     for b in input.as_bytes() {
         hash ^= u64::from(*b);
         hash = hash.wrapping_mul(0x100000001b3);
     }
-    format!("{hash:016x}")
+    hash
 }
 
 /// Case-insensitive substring match for device name selection.
@@ -317,6 +821,32 @@ fn matches_device_name(name: &str, needle: &str) -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn pick_source_output_config_honours_rate_switch() {
+        let device = crate::null_output::device(NullPace::Fast);
+        let switched =
+            pick_source_output_config(&device, 44_100, RateSwitch::PreferSwitch).unwrap();
+        assert_eq!(switched.sample_rate(), 44_100);
+        let resampled =
+            pick_source_output_config(&device, 44_100, RateSwitch::PreferResample).unwrap();
+        assert_eq!(resampled.sample_rate(), 48_000);
+    }
+
+    #[test]
+    fn pick_limited_output_config_caps_rate() {
+        let device = crate::null_output::device(NullPace::Fast);
+        let limits = OutputFormatLimits {
+            max_rate: Some(48_000),
+            bit_depth: Some(24),
+        };
+        let config =
+            pick_limited_output_config(&device, 96_000, RateSwitch::PreferSwitch, limits).unwrap();
+        assert_eq!(config.sample_rate(), 48_000);
+        let config =
+            pick_limited_output_config(&device, 44_100, RateSwitch::PreferSwitch, limits).unwrap();
+        assert_eq!(config.sample_rate(), 44_100);
+    }
+
     #[test]
     fn hash_device_id_is_deterministic() {
         let first = hash_device_id("Device", 44_100, 96_000);
@@ -331,6 +861,29 @@ mod tests {
         assert_ne!(base, other);
     }
 
+    #[test]
+    fn stable_ids_are_recognized_and_resolved() {
+        let host = crate::null_output::host(NullPace::Fast);
+        let device = host.default_output_device().unwrap();
+        let id = stable_device_id(host.id(), &device);
+        assert!(is_stable_id(&id));
+        assert_eq!(id, stable_device_id(host.id(), &device));
+        assert!(!is_stable_id("dev-xyz"));
+        assert!(!is_stable_id("USB DAC"));
+        let picked = pick_device(&host, Some(&id)).unwrap();
+        assert!(same_device(&picked, &device));
+        assert!(pick_device(&host, Some("dev-0000000000000000")).is_err());
+    }
+
+    #[test]
+    fn hardware_key_keeps_ids_without_alsa_card() {
+        assert_eq!(
+            hardware_key("coreaudio:BuiltInSpeaker"),
+            "coreaudio:BuiltInSpeaker"
+        );
+        assert_eq!(hardware_key("alsa:default"), "alsa:default");
+    }
+
     #[test]
     fn cached_rates_roundtrip() {
         let key = "device-key";
@@ -339,6 +892,71 @@ mod tests {
         assert_eq!(cached, (48_000, 96_000));
     }
 
+    #[test]
+    fn capabilities_collect_standard_rates_formats_and_channels() {
+        let mut caps = Capabilities::default();
+        caps.add_range(44_100, 96_000, cpal::SampleFormat::I32, 2);
+        caps.add_range(44_100, 48_000, cpal::SampleFormat::F32, 8);
+        caps.add_range(22_050, 22_050, cpal::SampleFormat::F32, 2);
+        assert_eq!(
+            caps.sample_rates,
+            vec![22_050, 44_100, 48_000, 88_200, 96_000]
+        );
+        assert_eq!(caps.sample_formats, vec!["I32", "F32"]);
+        assert_eq!(caps.channels, vec![2, 8]);
+    }
+
+    #[test]
+    fn is_device_id_rejects_plain_names() {
+        assert!(!is_device_id("USB DAC"));
+        assert!(!is_device_id(""));
+        assert!(!is_device_id("dummy:fixed-48k"));
+    }
+
+    #[test]
+    fn normalize_alsa_pcm_expands_shorthand() {
+        assert_eq!(
+            normalize_alsa_pcm("hw:1").as_deref(),
+            Some("hw:CARD=1,DEV=0")
+        );
+        assert_eq!(
+            normalize_alsa_pcm("HW:0,3").as_deref(),
+            Some("hw:CARD=0,DEV=3")
+        );
+        assert_eq!(
+            normalize_alsa_pcm("plughw:CARD=PCH,DEV=0").as_deref(),
+            Some("plughw:CARD=PCH,DEV=0")
+        );
+        assert!(normalize_alsa_pcm("hw:").is_none());
+        assert!(normalize_alsa_pcm("hw:0,x").is_none());
+        assert!(!is_alsa_direct("pulse"));
+        assert!(!is_alsa_direct("alsa:hw:CARD=0,DEV=0"));
+    }
+
+    #[test]
+    fn jack_ports_parse_keywords_and_port_lists() {
+        assert_eq!("system".parse::<JackPorts>().unwrap(), JackPorts::System);
+        assert_eq!(" None ".parse::<JackPorts>().unwrap(), JackPorts::None);
+        assert_eq!(
+            "Carla:audio-in1, Carla:audio-in2"
+                .parse::<JackPorts>()
+                .unwrap(),
+            JackPorts::Ports(vec![
+                "Carla:audio-in1".to_string(),
+                "Carla:audio-in2".to_string()
+            ])
+        );
+        assert!(" , ".parse::<JackPorts>().is_err());
+    }
+
+    #[test]
+    fn is_default_follow_matches_selector() {
+        assert!(is_default_follow(Some("default-follow")));
+        assert!(is_default_follow(Some(" Default-Follow ")));
+        assert!(!is_default_follow(Some("default")));
+        assert!(!is_default_follow(None));
+    }
+
     #[test]
     fn matches_device_name_is_case_insensitive() {
         assert!(matches_device_name("USB DAC", "dac"));
@@ -0,0 +1,811 @@
+//! DSD sources (DSF and DSDIFF) and DSD-over-PCM.
+//!
+//! Symphonia has no DSD support, so [`crate::decode`] hands `.dsf`/`.dff` sources to this
+//! module. The decode thread packs the 1-bit stream as DSD-over-PCM (DoP) frames into the
+//! regular `f32` queue: each 24-bit sample carries 16 DSD bits of one channel below a marker
+//! byte that alternates `0x05`/`0xFA` per frame, at 1/16 of the DSD rate (176.4 kHz for
+//! DSD64). 24-bit values survive the queue exactly, so a DoP-capable DAC fed the frames
+//! untouched (integer output at that rate, no mixing, gain or volume) plays native DSD.
+//!
+//! Outputs that cannot take DoP get PCM from [`dop_to_pcm`], which low-pass filters and
+//! decimates the bit stream to 88.2 kHz (96 kHz for the 48 kHz family). The conversion maps
+//! full modulation to 0 dBFS, so the SACD reference level (50% modulation) plays at -6 dBFS.
+
+use std::io::{Seek, SeekFrom};
+use std::sync::Arc;
+use std::thread;
+
+use anyhow::{Context, Result, anyhow};
+use symphonia::core::audio::SignalSpec;
+use symphonia::core::io::MediaSource;
+
+use crate::cue::TrackRange;
+use crate::decode::{DecodeSeek, SeekableDecode, SourceInfo, channel_layout};
+use crate::queue::{PopStrategy, SharedAudio, calc_max_buffered_samples};
+
+/// Codec label reported for DSD sources.
+pub const CODEC_NAME: &str = "dsd";
+
+/// DoP marker bytes; consecutive frames alternate between them.
+const DOP_MARKERS: [u8; 2] = [0x05, 0xFA];
+/// DSD idle pattern (equal ones and zeros, i.e. silence).
+const DSD_IDLE_BYTE: u8 = 0x69;
+/// DSD bits carried per channel by one DoP frame.
+const BITS_PER_DOP_FRAME: u32 = 16;
+/// DoP frames read per decode chunk.
+const CHUNK_FRAMES: usize = 2048;
+/// PCM rate of converted 44.1 kHz-family DSD (DSD64 = 2.8224 MHz).
+const PCM_RATE_44K: u32 = 88_200;
+/// PCM rate of converted 48 kHz-family DSD (3.072 MHz).
+const PCM_RATE_48K: u32 = 96_000;
+/// Pass band edge of the conversion filter.
+const PCM_CUTOFF_HZ: f64 = 30_000.0;
+/// Conversion filter length, in DSD bits per output decimation step.
+const FILTER_TAPS_PER_DECIMATION: usize = 32;
+
+/// Container of a DSD source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Container {
+    /// Sony DSF: little-endian, channel blocks, LSB-first bytes.
+    Dsf { block_size: usize },
+    /// Philips DSDIFF: big-endian, byte-interleaved channels, MSB-first bytes.
+    Dff,
+}
+
+/// Layout of the DSD stream inside its container.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct DsdFormat {
+    container: Container,
+    channels: usize,
+    /// Bit rate per channel (Hz).
+    dsd_rate: u32,
+    /// File offset of the first sample byte.
+    data_offset: u64,
+    /// Sample bytes per channel.
+    bytes_per_channel: u64,
+}
+
+impl DsdFormat {
+    /// DoP frame rate (one frame per 16 DSD bits).
+    fn dop_rate(&self) -> u32 {
+        self.dsd_rate / BITS_PER_DOP_FRAME
+    }
+
+    /// Per-channel byte offset of `ms`, rounded down to a whole DoP frame.
+    fn byte_at(&self, ms: u64) -> u64 {
+        let bytes = ms.saturating_mul(u64::from(self.dsd_rate)) / 8 / 1000;
+        (bytes & !1).min(self.bytes_per_channel & !1)
+    }
+
+    fn duration_ms(&self) -> u64 {
+        self.bytes_per_channel * 8 * 1000 / u64::from(self.dsd_rate)
+    }
+}
+
+/// Stream properties of a DSD file, for library scanning.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DsdProbe {
+    /// DSD bit rate per channel (Hz).
+    pub dsd_rate: u32,
+    pub channels: usize,
+    pub duration_ms: u64,
+}
+
+/// Read the header of a DSF or DSDIFF `source`; `None` for other files.
+pub fn probe(source: &mut dyn MediaSource) -> Result<Option<DsdProbe>> {
+    if !is_dsd(source)? {
+        return Ok(None);
+    }
+    let format = read_format(source)?;
+    Ok(Some(DsdProbe {
+        dsd_rate: format.dsd_rate,
+        channels: format.channels,
+        duration_ms: format.duration_ms(),
+    }))
+}
+
+/// Whether `source` starts with a DSF or DSDIFF header; the source is rewound either way.
+pub(crate) fn is_dsd(source: &mut dyn MediaSource) -> Result<bool> {
+    if !source.is_seekable() {
+        return Ok(false);
+    }
+    let mut magic = [0u8; 4];
+    let read = read_up_to(source, &mut magic)?;
+    source.seek(SeekFrom::Start(0))?;
+    Ok(read == 4 && (&magic == b"DSD " || &magic == b"FRM8"))
+}
+
+/// Parse the header of a DSD `source` and spawn a thread pushing DoP frames of `range`,
+/// starting `seek_ms` into it.
+pub(crate) fn spawn_decode(
+    mut source: Box<dyn MediaSource>,
+    buffer_seconds: f32,
+    seek_ms: Option<u64>,
+    range: TrackRange,
+) -> Result<SeekableDecode> {
+    let format = read_format(source.as_mut())?;
+    let start = format.byte_at(range.start_ms);
+    let end = range
+        .end_ms
+        .map_or(format.bytes_per_channel & !1, |ms| format.byte_at(ms))
+        .max(start);
+    let duration_ms = if range.is_full() {
+        Some(format.duration_ms())
+    } else {
+        range.duration_ms(Some(format.duration_ms()))
+    };
+    let spec = SignalSpec::new(format.dop_rate(), channel_layout(format.channels)?);
+    let source_info = SourceInfo {
+        codec: Some(CODEC_NAME.to_string()),
+        bit_depth: Some(1),
+        container: Some(
+            match format.container {
+                Container::Dsf { .. } => "DSF",
+                Container::Dff => "DFF",
+            }
+            .to_string(),
+        ),
+        dsd_rate: Some(format.dsd_rate),
+        decode_errors: Default::default(),
+        chapters: Vec::new(),
+    };
+
+    let max_buffered_samples =
+        calc_max_buffered_samples(spec.rate, format.channels, buffer_seconds);
+    let shared = Arc::new(SharedAudio::new(format.channels, max_buffered_samples));
+    let seek = DecodeSeek::new(shared.clone());
+
+    let mut reader = DsdReader {
+        source,
+        format,
+        pos: start,
+        end,
+        file_pos: None,
+        block: Vec::new(),
+    };
+    reader.pos = (start + seek_ms.map_or(0, |ms| format.byte_at(ms))).min(end);
+    let shared_for_thread = shared.clone();
+    let seek_for_thread = seek.clone();
+    thread::spawn(move || {
+        if let Err(e) = decode_loop(&mut reader, start, &shared_for_thread, &seek_for_thread) {
+            tracing::error!("dsd decoder thread error: {e:#}");
+        }
+        seek_for_thread.finish();
+        shared_for_thread.close();
+    });
+
+    Ok((spec, shared, duration_ms, source_info, seek))
+}
+
+/// Read DSD bytes and push them as DoP frames until the end of the range.
+fn decode_loop(
+    reader: &mut DsdReader,
+    start: u64,
+    shared: &SharedAudio,
+    seek: &DecodeSeek,
+) -> Result<()> {
+    let channels = reader.format.channels;
+    let mut bytes: Vec<Vec<u8>> = vec![Vec::with_capacity(CHUNK_FRAMES * 2); channels];
+    let mut frames = Vec::with_capacity(CHUNK_FRAMES * channels);
+    let mut marker = 0usize;
+    loop {
+        if let Some(ms) = seek.pending() {
+            reader.pos = (start + reader.format.byte_at(ms)).min(reader.end);
+            shared.flush();
+            tracing::info!(position_ms = ms, "dsd decode seek");
+            seek.complete(ms, Ok(()));
+        }
+        let read = reader.read(&mut bytes, CHUNK_FRAMES * 2)?;
+        if read == 0 {
+            break;
+        }
+        frames.clear();
+        for i in (0..read).step_by(2) {
+            for ch in &bytes {
+                frames.push(dop_sample(DOP_MARKERS[marker], ch[i], ch[i + 1]));
+            }
+            marker ^= 1;
+        }
+        shared.push_interleaved_blocking(&frames);
+    }
+    Ok(())
+}
+
+/// Sequential reader over the sample bytes of a DSD file.
+struct DsdReader {
+    source: Box<dyn MediaSource>,
+    format: DsdFormat,
+    /// Next per-channel byte to read.
+    pos: u64,
+    /// Per-channel byte where reading stops.
+    end: u64,
+    /// Position of `source` when known, to skip redundant seeks.
+    file_pos: Option<u64>,
+    /// Scratch buffer for one container block.
+    block: Vec<u8>,
+}
+
+impl DsdReader {
+    /// Read up to `max` bytes per channel (an even count) into `out`, MSB first in time.
+    ///
+    /// Returns the number of bytes per channel read; `0` at the end of the range.
+    fn read(&mut self, out: &mut [Vec<u8>], max: usize) -> Result<usize> {
+        let channels = self.format.channels;
+        let want = (self.end.saturating_sub(self.pos) as usize).min(max) & !1;
+        if want == 0 {
+            return Ok(0);
+        }
+        for ch in out.iter_mut() {
+            ch.clear();
+        }
+        match self.format.container {
+            Container::Dsf { block_size } => {
+                // Channel blocks: [ch0 block][ch1 block]... per block group.
+                let group = self.pos / block_size as u64;
+                let offset = (self.pos % block_size as u64) as usize;
+                let take = want.min(block_size - offset);
+                self.block.resize(block_size * channels, 0);
+                let group_offset = group * (block_size * channels) as u64;
+                self.read_at(self.format.data_offset + group_offset)?;
+                for (ch, dst) in out.iter_mut().enumerate() {
+                    let block = &self.block[ch * block_size..(ch + 1) * block_size];
+                    dst.extend(
+                        block[offset..offset + take]
+                            .iter()
+                            .map(|b| b.reverse_bits()),
+                    );
+                }
+                self.pos += take as u64;
+                Ok(take)
+            }
+            Container::Dff => {
+                self.block.resize(want * channels, 0);
+                self.read_at(self.format.data_offset + self.pos * channels as u64)?;
+                for (i, byte) in self.block.iter().enumerate() {
+                    out[i % channels].push(*byte);
+                }
+                self.pos += want as u64;
+                Ok(want)
+            }
+        }
+    }
+
+    /// Fill `self.block` from file offset `offset`.
+    fn read_at(&mut self, offset: u64) -> Result<()> {
+        if self.file_pos != Some(offset) {
+            self.source.seek(SeekFrom::Start(offset))?;
+        }
+        let read = read_up_to(self.source.as_mut(), &mut self.block)?;
+        // The last DSF block group is zero-padded and may be cut short by a truncated file.
+        self.block[read..].fill(0);
+        self.file_pos = Some(offset + read as u64);
+        Ok(())
+    }
+}
+
+/// Parse a DSF or DSDIFF header; leaves `source` positioned anywhere.
+fn read_format(source: &mut dyn MediaSource) -> Result<DsdFormat> {
+    source.seek(SeekFrom::Start(0))?;
+    let mut magic = [0u8; 4];
+    source.read_exact(&mut magic).context("read dsd header")?;
+    let format = match &magic {
+        b"DSD " => read_dsf(source)?,
+        b"FRM8" => read_dff(source)?,
+        _ => return Err(anyhow!("not a DSF or DSDIFF file")),
+    };
+    if format.channels == 0 {
+        return Err(anyhow!("dsd: no channels"));
+    }
+    if format.dsd_rate == 0
+        || !(format.dsd_rate.is_multiple_of(PCM_RATE_44K * 32)
+            || format.dsd_rate.is_multiple_of(PCM_RATE_48K * 32))
+    {
+        return Err(anyhow!("dsd: unsupported rate {} Hz", format.dsd_rate));
+    }
+    Ok(format)
+}
+
+/// Parse the rest of a DSF header after the `DSD ` magic.
+fn read_dsf(source: &mut dyn MediaSource) -> Result<DsdFormat> {
+    // `DSD ` chunk: size, total file size, metadata offset.
+    let header_size = read_u64_le(source)?;
+    source.seek(SeekFrom::Start(header_size))?;
+    let mut id = [0u8; 4];
+    source.read_exact(&mut id)?;
+    if &id != b"fmt " {
+        return Err(anyhow!("dsf: missing fmt chunk"));
+    }
+    let fmt_size = read_u64_le(source)?;
+    let _version = read_u32_le(source)?;
+    let format_id = read_u32_le(source)?;
+    let _channel_type = read_u32_le(source)?;
+    let channels = read_u32_le(source)? as usize;
+    let dsd_rate = read_u32_le(source)?;
+    let bits_per_sample = read_u32_le(source)?;
+    let sample_count = read_u64_le(source)?;
+    let block_size = read_u32_le(source)? as usize;
+    if format_id != 0 {
+        return Err(anyhow!("dsf: unsupported format id {format_id}"));
+    }
+    if bits_per_sample != 1 {
+        return Err(anyhow!(
+            "dsf: unsupported bit order ({bits_per_sample} bits per sample)"
+        ));
+    }
+    if block_size == 0 || !block_size.is_multiple_of(2) {
+        return Err(anyhow!("dsf: invalid block size {block_size}"));
+    }
+    source.seek(SeekFrom::Start(header_size + fmt_size))?;
+    source.read_exact(&mut id)?;
+    if &id != b"data" {
+        return Err(anyhow!("dsf: missing data chunk"));
+    }
+    let _data_size = read_u64_le(source)?;
+    Ok(DsdFormat {
+        container: Container::Dsf { block_size },
+        channels,
+        dsd_rate,
+        data_offset: header_size + fmt_size + 12,
+        bytes_per_channel: sample_count / 8,
+    })
+}
+
+/// Parse the rest of a DSDIFF header after the `FRM8` magic.
+fn read_dff(source: &mut dyn MediaSource) -> Result<DsdFormat> {
+    let _form_size = read_u64_be(source)?;
+    let mut form_type = [0u8; 4];
+    source.read_exact(&mut form_type)?;
+    if &form_type != b"DSD " {
+        return Err(anyhow!("dff: not a DSD form"));
+    }
+    let mut channels = None;
+    let mut dsd_rate = None;
+    let mut offset = 16u64;
+    loop {
+        source.seek(SeekFrom::Start(offset))?;
+        let mut id = [0u8; 4];
+        source
+            .read_exact(&mut id)
+            .context("dff: no DSD sound data chunk")?;
+        let size = read_u64_be(source)?;
+        let body = offset + 12;
+        match &id {
+            b"PROP" => {
+                let mut prop_type = [0u8; 4];
+                source.read_exact(&mut prop_type)?;
+                let mut sub = body + 4;
+                while sub < body + size {
+                    source.seek(SeekFrom::Start(sub))?;
+                    source.read_exact(&mut id)?;
+                    let sub_size = read_u64_be(source)?;
+                    match &id {
+                        b"FS  " => dsd_rate = Some(read_u32_be(source)?),
+                        b"CHNL" => channels = Some(usize::from(read_u16_be(source)?)),
+                        b"CMPR" => {
+                            let mut compression = [0u8; 4];
+                            source.read_exact(&mut compression)?;
+                            if &compression != b"DSD " {
+                                return Err(anyhow!(
+                                    "dff: compressed (DST) audio is not supported"
+                                ));
+                            }
+                        }
+                        _ => {}
+                    }
+                    sub += 12 + sub_size + (sub_size & 1);
+                }
+            }
+            b"DSD " => {
+                let channels = channels.ok_or_else(|| anyhow!("dff: missing CHNL chunk"))?;
+                let dsd_rate = dsd_rate.ok_or_else(|| anyhow!("dff: missing FS chunk"))?;
+                if channels == 0 {
+                    return Err(anyhow!("dsd: no channels"));
+                }
+                return Ok(DsdFormat {
+                    container: Container::Dff,
+                    channels,
+                    dsd_rate,
+                    data_offset: body,
+                    bytes_per_channel: size / channels as u64,
+                });
+            }
+            b"DST " => return Err(anyhow!("dff: compressed (DST) audio is not supported")),
+            _ => {}
+        }
+        // Chunks are padded to an even size.
+        offset = body + size + (size & 1);
+    }
+}
+
+/// One DoP sample: `marker`, then two DSD bytes (earliest first), as a 24-bit `f32`.
+fn dop_sample(marker: u8, first: u8, second: u8) -> f32 {
+    let raw = (u32::from(marker) << 16) | (u32::from(first) << 8) | u32::from(second);
+    // Sign-extend the 24-bit word; every 24-bit value is exact in `f32`.
+    (((raw << 8) as i32) >> 8) as f32 / 8_388_608.0
+}
+
+/// The two DSD bytes carried by a DoP sample.
+fn dop_bytes(sample: f32) -> [u8; 2] {
+    let raw = (sample * 8_388_608.0).round() as i32;
+    [(raw >> 8) as u8, raw as u8]
+}
+
+/// DoP idle frames for output gaps, continuing the marker sequence across calls.
+///
+/// Plain zeros would make a DoP DAC drop out of DSD mode (and click) on every pause or
+/// underrun; idle frames keep it locked.
+#[derive(Debug, Default)]
+pub struct DopIdle {
+    marker: usize,
+}
+
+impl DopIdle {
+    /// Write idle frames of `channels` interleaved channels into `out`.
+    pub fn fill<T>(&mut self, out: &mut [T], channels: usize)
+    where
+        T: cpal::Sample + cpal::FromSample<f32>,
+    {
+        for frame in out.chunks_mut(channels.max(1)) {
+            let sample = dop_sample(DOP_MARKERS[self.marker], DSD_IDLE_BYTE, DSD_IDLE_BYTE);
+            frame.fill(<T as cpal::Sample>::from_sample::<f32>(sample));
+            self.marker ^= 1;
+        }
+    }
+}
+
+/// Convert a queue of DoP frames (`spec` at the DoP rate) to PCM on a background thread.
+///
+/// Returns the PCM spec and queue; the output rate is 88.2 kHz or 96 kHz depending on the
+/// DSD rate family.
+pub fn dop_to_pcm(
+    spec: SignalSpec,
+    srcq: Arc<SharedAudio>,
+    buffer_seconds: f32,
+) -> (SignalSpec, Arc<SharedAudio>) {
+    let channels = srcq.channels();
+    let dsd_rate = spec.rate * BITS_PER_DOP_FRAME;
+    let pcm_rate = if dsd_rate.is_multiple_of(PCM_RATE_44K) {
+        PCM_RATE_44K
+    } else {
+        PCM_RATE_48K
+    };
+    let mut converter = DsdToPcm::new(channels, dsd_rate, pcm_rate);
+    let out_spec = SignalSpec::new(pcm_rate, spec.channels);
+    let dstq = Arc::new(SharedAudio::new(
+        channels,
+        calc_max_buffered_samples(pcm_rate, channels, buffer_seconds),
+    ));
+    let dstq_for_thread = dstq.clone();
+    thread::spawn(move || {
+        let mut out = Vec::new();
+        while let Some(frames) = srcq.pop(PopStrategy::BlockingUpTo {
+            max_frames: CHUNK_FRAMES,
+        }) {
+            out.clear();
+            converter.process(&frames, &mut out);
+            if !out.is_empty() {
+                dstq_for_thread.push_interleaved_blocking(&out);
+            }
+        }
+        dstq_for_thread.close();
+    });
+    tracing::info!(
+        dsd_rate_hz = dsd_rate,
+        pcm_rate_hz = pcm_rate,
+        "dsd: converting to pcm"
+    );
+    (out_spec, dstq)
+}
+
+/// PCM view of a decode: [`dop_to_pcm`] for DSD sources, the decode itself otherwise.
+///
+/// For callers that play through gain, mixing or resampling stages, which would corrupt DoP.
+pub fn ensure_pcm(
+    spec: SignalSpec,
+    queue: Arc<SharedAudio>,
+    source_info: &SourceInfo,
+    buffer_seconds: f32,
+) -> (SignalSpec, Arc<SharedAudio>) {
+    if source_info.dsd_rate.is_some() {
+        dop_to_pcm(spec, queue, buffer_seconds)
+    } else {
+        (spec, queue)
+    }
+}
+
+/// Low-pass FIR decimator from DSD bytes to PCM.
+///
+/// The filter is evaluated a byte at a time: `table[g * 256 + b]` holds the contribution of
+/// byte `b` at byte position `g` of the filter window.
+struct DsdToPcm {
+    channels: usize,
+    /// DSD bytes per channel consumed per output sample.
+    decimation_bytes: usize,
+    /// Filter window in bytes.
+    window_bytes: usize,
+    table: Vec<f32>,
+    /// Per-channel window (oldest byte first) followed by bytes not yet consumed.
+    history: Vec<Vec<u8>>,
+}
+
+impl DsdToPcm {
+    fn new(channels: usize, dsd_rate: u32, pcm_rate: u32) -> Self {
+        let decimation = (dsd_rate / pcm_rate) as usize;
+        let taps = decimation * FILTER_TAPS_PER_DECIMATION;
+        let coeffs = lowpass(taps, PCM_CUTOFF_HZ / f64::from(dsd_rate));
+        let window_bytes = taps / 8;
+        let mut table = vec![0.0f32; window_bytes * 256];
+        for (group, taps) in coeffs.chunks(8).enumerate() {
+            for byte in 0..256usize {
+                let sum: f64 = taps
+                    .iter()
+                    .enumerate()
+                    .map(|(bit, h)| if byte & (0x80 >> bit) != 0 { *h } else { -*h })
+                    .sum();
+                table[group * 256 + byte] = sum as f32;
+            }
+        }
+        Self {
+            channels,
+            decimation_bytes: decimation / 8,
+            window_bytes,
+            table,
+            // Start from digital silence rather than a run of zero bits (full negative).
+            history: vec![vec![DSD_IDLE_BYTE; window_bytes]; channels],
+        }
+    }
+
+    /// Convert interleaved DoP samples, appending interleaved PCM to `out`.
+    fn process(&mut self, dop: &[f32], out: &mut Vec<f32>) {
+        for frame in dop.chunks_exact(self.channels) {
+            for (sample, history) in frame.iter().zip(self.history.iter_mut()) {
+                history.extend_from_slice(&dop_bytes(*sample));
+            }
+        }
+        let window = self.window_bytes;
+        let step = self.decimation_bytes;
+        let available = self.history[0].len() - window;
+        let outputs = available / step;
+        for n in 0..outputs {
+            for history in &self.history {
+                let start = (n + 1) * step;
+                let sum: f32 = history[start..start + window]
+                    .iter()
+                    .enumerate()
+                    .map(|(group, byte)| self.table[group * 256 + usize::from(*byte)])
+                    .sum();
+                out.push(sum);
+            }
+        }
+        for history in &mut self.history {
+            history.drain(..outputs * step);
+        }
+    }
+}
+
+/// Blackman-windowed sinc low-pass with `taps` coefficients, unity DC gain; `cutoff` is a
+/// fraction of the input rate.
+fn lowpass(taps: usize, cutoff: f64) -> Vec<f64> {
+    let center = (taps - 1) as f64 / 2.0;
+    let mut coeffs: Vec<f64> = (0..taps)
+        .map(|n| {
+            let x = n as f64 - center;
+            let sinc = if x == 0.0 {
+                2.0 * cutoff
+            } else {
+                (2.0 * std::f64::consts::PI * cutoff * x).sin() / (std::f64::consts::PI * x)
+            };
+            let phase = 2.0 * std::f64::consts::PI * n as f64 / (taps - 1) as f64;
+            sinc * (0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos())
+        })
+        .collect();
+    let sum: f64 = coeffs.iter().sum();
+    for c in &mut coeffs {
+        *c /= sum;
+    }
+    coeffs
+}
+
+/// Read until `buf` is full or the source ends; returns the bytes read.
+pub(crate) fn read_up_to(source: &mut dyn MediaSource, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match source.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(filled)
+}
+
+fn read_u16_be(source: &mut dyn MediaSource) -> Result<u16> {
+    let mut buf = [0u8; 2];
+    source.read_exact(&mut buf)?;
+    Ok(u16::from_be_bytes(buf))
+}
+
+fn read_u32_be(source: &mut dyn MediaSource) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    source.read_exact(&mut buf)?;
+    Ok(u32::from_be_bytes(buf))
+}
+
+fn read_u64_be(source: &mut dyn MediaSource) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    source.read_exact(&mut buf)?;
+    Ok(u64::from_be_bytes(buf))
+}
+
+fn read_u32_le(source: &mut dyn MediaSource) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    source.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64_le(source: &mut dyn MediaSource) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    source.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const DSD64: u32 = 2_822_400;
+
+    /// Stereo DSF with `bytes` sample bytes per channel: left all `left`, right all `right`.
+    fn dsf(bytes: usize, left: u8, right: u8) -> Vec<u8> {
+        let block = 4096usize;
+        let groups = bytes.div_ceil(block);
+        let data_len = groups * block * 2;
+        let mut out = Vec::new();
+        out.extend_from_slice(b"DSD ");
+        out.extend_from_slice(&28u64.to_le_bytes());
+        out.extend_from_slice(&((28 + 52 + 12 + data_len) as u64).to_le_bytes());
+        out.extend_from_slice(&0u64.to_le_bytes());
+        out.extend_from_slice(b"fmt ");
+        out.extend_from_slice(&52u64.to_le_bytes());
+        for v in [1u32, 0, 2, 2, DSD64, 1] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        out.extend_from_slice(&((bytes * 8) as u64).to_le_bytes());
+        out.extend_from_slice(&(block as u32).to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(b"data");
+        out.extend_from_slice(&((12 + data_len) as u64).to_le_bytes());
+        for group in 0..groups {
+            for value in [left, right] {
+                let valid = (bytes - group * block).min(block);
+                out.extend(std::iter::repeat_n(value, valid));
+                out.extend(std::iter::repeat_n(0, block - valid));
+            }
+        }
+        out
+    }
+
+    fn drain(queue: &SharedAudio) -> Vec<f32> {
+        let mut out = Vec::new();
+        while let Some(chunk) = queue.pop(PopStrategy::BlockingUpTo { max_frames: 4096 }) {
+            out.extend(chunk);
+        }
+        out
+    }
+
+    #[test]
+    fn dsf_decodes_to_dop_frames() {
+        // 0x01 LSB-first is 0x80 in time order.
+        let mut source: Box<dyn MediaSource> = Box::new(Cursor::new(dsf(5000, 0x01, 0xFF)));
+        assert!(is_dsd(source.as_mut()).unwrap());
+        let (spec, queue, duration_ms, info, _seek) =
+            spawn_decode(source, 1.0, None, TrackRange::default()).unwrap();
+        assert_eq!(spec.rate, DSD64 / 16);
+        assert_eq!(spec.channels.count(), 2);
+        assert_eq!(duration_ms, Some(5000 * 8 * 1000 / u64::from(DSD64)));
+        assert_eq!(info.codec.as_deref(), Some(CODEC_NAME));
+        assert_eq!(info.dsd_rate, Some(DSD64));
+        let mut file = Cursor::new(dsf(5000, 0x01, 0xFF));
+        assert_eq!(
+            probe(&mut file).unwrap().map(|p| (p.dsd_rate, p.channels)),
+            Some((DSD64, 2))
+        );
+
+        let samples = drain(&queue);
+        assert_eq!(samples.len(), 2500 * 2);
+        let word = |s: f32| ((s * 8_388_608.0).round() as i32) & 0xFF_FFFF;
+        assert_eq!(word(samples[0]), 0x05_8080);
+        assert_eq!(word(samples[1]), 0x05_FFFF);
+        assert_eq!(word(samples[2]), 0xFA_8080);
+        assert_eq!(word(samples[4]), 0x05_8080);
+    }
+
+    #[test]
+    fn dff_decodes_interleaved_bytes() {
+        let bytes_per_channel = 8u64;
+        let mut out = Vec::new();
+        let chunk = |out: &mut Vec<u8>, id: &[u8; 4], body: &[u8]| {
+            out.extend_from_slice(id);
+            out.extend_from_slice(&(body.len() as u64).to_be_bytes());
+            out.extend_from_slice(body);
+        };
+        let mut prop = b"SND ".to_vec();
+        chunk(&mut prop, b"FS  ", &DSD64.to_be_bytes());
+        chunk(
+            &mut prop,
+            b"CHNL",
+            &[0, 2, b'S', b'L', b'F', b'T', b'S', b'R', b'G', b'T'],
+        );
+        chunk(&mut prop, b"CMPR", b"DSD \x0enot compressed\x00");
+        let mut body = b"DSD ".to_vec();
+        chunk(&mut body, b"FVER", &0x0105_0000u32.to_be_bytes());
+        chunk(&mut body, b"PROP", &prop);
+        let samples: Vec<u8> = (0..bytes_per_channel * 2).map(|i| i as u8).collect();
+        chunk(&mut body, b"DSD ", &samples);
+        chunk(&mut out, b"FRM8", &body);
+
+        let (_spec, queue, _duration, info, _seek) =
+            spawn_decode(Box::new(Cursor::new(out)), 1.0, None, TrackRange::default()).unwrap();
+        assert_eq!(info.container.as_deref(), Some("DFF"));
+        let words: Vec<i32> = drain(&queue)
+            .iter()
+            .map(|s| ((s * 8_388_608.0).round() as i32) & 0xFFFF)
+            .collect();
+        // Left takes even bytes, right odd bytes, two per frame.
+        assert_eq!(words[..4], [0x0002, 0x0103, 0x0406, 0x0507]);
+    }
+
+    #[test]
+    fn dsf_seek_and_range_are_frame_aligned() {
+        let range = TrackRange {
+            start_ms: 1,
+            end_ms: Some(3),
+        };
+        let (_spec, queue, duration_ms, _info, _seek) = spawn_decode(
+            Box::new(Cursor::new(dsf(4096 * 3, 0x00, 0x00))),
+            1.0,
+            Some(1),
+            range,
+        )
+        .unwrap();
+        assert_eq!(duration_ms, Some(2));
+        // 1 ms of DSD64 is 352.8 bytes per channel: the range spans bytes 352..1058 and the
+        // seek starts at byte 704, two bytes per DoP frame.
+        let frames = drain(&queue).len() / 2;
+        assert_eq!(frames, (1058 - 704) / 2);
+    }
+
+    #[test]
+    fn pcm_conversion_maps_idle_to_silence_and_ones_to_full_scale() {
+        let spec = SignalSpec::new(DSD64 / 16, channel_layout(2).unwrap());
+        let srcq = Arc::new(SharedAudio::new(2, 1 << 20));
+        let frames = 8192;
+        let mut dop = Vec::new();
+        for i in 0..frames {
+            let marker = DOP_MARKERS[i % 2];
+            dop.push(dop_sample(marker, DSD_IDLE_BYTE, DSD_IDLE_BYTE));
+            dop.push(dop_sample(marker, 0xFF, 0xFF));
+        }
+        srcq.push_interleaved_blocking(&dop);
+        srcq.close();
+        let (pcm_spec, pcmq) = dop_to_pcm(spec, srcq, 1.0);
+        assert_eq!(pcm_spec.rate, PCM_RATE_44K);
+        let pcm = drain(&pcmq);
+        let tail = &pcm[pcm.len() - 2..];
+        assert!(tail[0].abs() < 1e-3, "idle -> {}", tail[0]);
+        assert!((tail[1] - 1.0).abs() < 1e-3, "ones -> {}", tail[1]);
+        // Two DoP frames per PCM frame once the window is primed.
+        assert_eq!(pcm.len() / 2, frames / 2);
+    }
+
+    #[test]
+    fn non_dsd_sources_are_rewound() {
+        let mut source: Box<dyn MediaSource> = Box::new(Cursor::new(b"RIFF....".to_vec()));
+        assert!(!is_dsd(source.as_mut()).unwrap());
+        let mut head = [0u8; 4];
+        source.read_exact(&mut head).unwrap();
+        assert_eq!(&head, b"RIFF");
+    }
+}
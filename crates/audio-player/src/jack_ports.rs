@@ -0,0 +1,65 @@
+//! JACK port wiring for output streams opened on CPAL's JACK host.
+//!
+//! CPAL names output ports `<client>:out_<n>` and connects them to the physical playback
+//! ports. A short-lived helper client disconnects those links and connects the configured
+//! destinations instead.
+
+use anyhow::{Result, anyhow};
+
+use crate::device::JackPorts;
+
+/// Apply `ports` to the output ports of the JACK client `client_name`.
+pub(crate) fn apply(client_name: &str, channels: u16, ports: &JackPorts) -> Result<()> {
+    if *ports == JackPorts::System {
+        return Ok(());
+    }
+    let (client, _status) = jack::Client::new(
+        &format!("{client_name}_wiring"),
+        jack::ClientOptions::NO_START_SERVER,
+    )
+    .map_err(|err| anyhow!("open JACK wiring client: {err}"))?;
+
+    let sources: Vec<String> = (0..channels)
+        .map(|idx| format!("{client_name}:out_{idx}"))
+        .collect();
+    for source in &sources {
+        let Some(port) = client.port_by_name(source) else {
+            continue;
+        };
+        for dest in port.get_connections() {
+            if let Err(err) = client.disconnect_ports_by_name(source, &dest) {
+                tracing::warn!(source = %source, dest = %dest, error = %err, "jack: disconnect failed");
+            }
+        }
+    }
+
+    let JackPorts::Ports(patterns) = ports else {
+        return Ok(());
+    };
+    let mut dests = Vec::new();
+    for pattern in patterns {
+        if client.port_by_name(pattern).is_some() {
+            dests.push(pattern.clone());
+        } else {
+            dests.extend(client.ports(
+                Some(pattern.as_str()),
+                Some("audio"),
+                jack::PortFlags::IS_INPUT,
+            ));
+        }
+    }
+    if dests.len() < sources.len() {
+        tracing::warn!(
+            channels,
+            matched = dests.len(),
+            "jack: fewer destination ports than output channels"
+        );
+    }
+    for (source, dest) in sources.iter().zip(&dests) {
+        client
+            .connect_ports_by_name(source, dest)
+            .map_err(|err| anyhow!("connect {source} -> {dest}: {err}"))?;
+        tracing::info!(source = %source, dest = %dest, "jack: connected output port");
+    }
+    Ok(())
+}
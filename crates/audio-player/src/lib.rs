@@ -32,17 +32,37 @@
 //!         buffer_capacity_frames: None,
 //!         volume_percent: None,
 //!         muted: None,
+//!         levels: None,
+//!         bit_perfect: None,
+//!         hotplug: None,
+//!         output: None,
 //!     },
 //! ).expect("playback");
 //! ```
 
+/// Chapter lists of long-form files.
+pub mod chapters;
 /// Shared playback tuning parameters.
 pub mod config;
+pub mod cue;
 pub mod decode;
 pub mod device;
+pub mod dsd;
+#[cfg(feature = "jack")]
+mod jack_ports;
+/// Runtime-adjustable tracing filter (`/admin/log-level`).
+pub mod log_filter;
+pub mod meter;
+pub mod mirror;
+pub mod mix;
+pub mod null_output;
 pub mod pipeline;
 pub mod playback;
 pub mod queue;
+pub mod record;
 pub mod resample;
+pub mod source;
 /// Playback status snapshot helpers shared with API layers.
 pub mod status;
+#[cfg(test)]
+mod test_media;
@@ -0,0 +1,218 @@
+//! Runtime-adjustable tracing filter.
+//!
+//! Wraps a `tracing_subscriber::reload` handle so `EnvFilter` directives can be changed
+//! over HTTP (`POST /admin/log-level`) without restarting the bridge or hub.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::reload;
+
+type ApplyFn = Box<dyn Fn(EnvFilter) -> Result<(), String> + Send + Sync>;
+
+/// Live filter directives plus the hook that swaps the subscriber's `EnvFilter`.
+pub struct LogFilterControl {
+    initial: String,
+    current: Mutex<String>,
+    apply: ApplyFn,
+}
+
+impl std::fmt::Debug for LogFilterControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogFilterControl")
+            .field("initial", &self.initial)
+            .field("current", &self.current())
+            .finish()
+    }
+}
+
+impl LogFilterControl {
+    /// Wrap the reload handle of the subscriber's `EnvFilter` layer.
+    pub fn new<S: 'static>(
+        handle: reload::Handle<EnvFilter, S>,
+        initial: impl Into<String>,
+    ) -> Self {
+        let initial = initial.into();
+        Self {
+            current: Mutex::new(initial.clone()),
+            initial,
+            apply: Box::new(move |filter| handle.reload(filter).map_err(|e| e.to_string())),
+        }
+    }
+
+    /// Track directives without a live subscriber (tests, embedded runtimes).
+    pub fn detached(initial: impl Into<String>) -> Self {
+        let initial = initial.into();
+        Self {
+            current: Mutex::new(initial.clone()),
+            initial,
+            apply: Box::new(|_| Ok(())),
+        }
+    }
+
+    /// Current filter directives.
+    pub fn current(&self) -> String {
+        self.current.lock().map(|g| g.clone()).unwrap_or_default()
+    }
+
+    /// Replace the filter with a full directive string (e.g. `info,audio_player::decode=debug`).
+    pub fn set(&self, directives: &str) -> Result<String, String> {
+        let directives = directives.trim();
+        let filter = EnvFilter::try_new(directives).map_err(|e| e.to_string())?;
+        (self.apply)(filter)?;
+        if let Ok(mut g) = self.current.lock() {
+            *g = directives.to_string();
+        }
+        Ok(directives.to_string())
+    }
+
+    /// Change the global level and/or per-target levels on top of the current directives.
+    ///
+    /// A `None` target value drops that target's override.
+    pub fn update(
+        &self,
+        level: Option<&str>,
+        targets: &BTreeMap<String, Option<String>>,
+    ) -> Result<String, String> {
+        for value in level
+            .into_iter()
+            .chain(targets.values().flatten().map(String::as_str))
+        {
+            parse_level_filter(value).ok_or_else(|| format!("invalid log level: {value}"))?;
+        }
+        let merged = merge_directives(&self.current(), level, targets);
+        self.set(&merged)
+    }
+
+    /// Restore the directives the process started with.
+    pub fn reset(&self) -> Result<String, String> {
+        let initial = self.initial.clone();
+        self.set(&initial)
+    }
+}
+
+/// Parse a level name accepted by `EnvFilter` (`off`, `error`, ..., `trace`).
+fn parse_level_filter(value: &str) -> Option<LevelFilter> {
+    value.trim().parse::<LevelFilter>().ok()
+}
+
+/// Merge a global level and per-target overrides into an existing directive string.
+fn merge_directives(
+    current: &str,
+    level: Option<&str>,
+    targets: &BTreeMap<String, Option<String>>,
+) -> String {
+    let mut global: Option<String> = None;
+    let mut per_target: Vec<(String, String)> = Vec::new();
+    for directive in split_directives(current) {
+        match split_level(directive) {
+            Some((target, lvl)) => per_target.push((target.to_string(), lvl.to_string())),
+            None if parse_level_filter(directive).is_some() => global = Some(directive.to_string()),
+            None => per_target.push((directive.to_string(), "trace".to_string())),
+        }
+    }
+    if let Some(level) = level {
+        global = Some(level.trim().to_ascii_lowercase());
+    }
+    for (target, lvl) in targets {
+        let target = target.trim();
+        per_target.retain(|(t, _)| t != target);
+        if let Some(lvl) = lvl {
+            per_target.push((target.to_string(), lvl.trim().to_ascii_lowercase()));
+        }
+    }
+    global
+        .into_iter()
+        .chain(per_target.into_iter().map(|(t, l)| format!("{t}={l}")))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Split a directive string on commas that are not inside `[...]` span filters.
+fn split_directives(directives: &str) -> impl Iterator<Item = &str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (idx, ch) in directives.char_indices() {
+        match ch {
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(&directives[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&directives[start..]);
+    parts.into_iter().map(str::trim).filter(|d| !d.is_empty())
+}
+
+/// Split `target[span{field=value}]=level` at the last `=` outside `[...]`.
+fn split_level(directive: &str) -> Option<(&str, &str)> {
+    let mut depth = 0usize;
+    let mut split = None;
+    for (idx, ch) in directive.char_indices() {
+        match ch {
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth = depth.saturating_sub(1),
+            '=' if depth == 0 => split = Some(idx),
+            _ => {}
+        }
+    }
+    split.map(|idx| (&directive[..idx], &directive[idx + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_replaces_global_and_target_levels() {
+        let control = LogFilterControl::detached("info,bridge=info");
+        let mut targets = BTreeMap::new();
+        targets.insert(
+            "audio_player::decode".to_string(),
+            Some("DEBUG".to_string()),
+        );
+        targets.insert("bridge".to_string(), None);
+        let filter = control.update(Some("warn"), &targets).unwrap();
+        assert_eq!(filter, "warn,audio_player::decode=debug");
+        assert_eq!(control.current(), filter);
+    }
+
+    #[test]
+    fn update_rejects_unknown_level() {
+        let control = LogFilterControl::detached("info");
+        assert!(control.update(Some("loud"), &BTreeMap::new()).is_err());
+        assert_eq!(control.current(), "info");
+    }
+
+    #[test]
+    fn reset_restores_initial_directives() {
+        let control = LogFilterControl::detached("info");
+        control.set("debug,actix_web=warn").unwrap();
+        assert_eq!(control.reset().unwrap(), "info");
+    }
+
+    #[test]
+    fn update_keeps_span_field_directives_intact() {
+        let control = LogFilterControl::detached(
+            "info,bridge[stream{id=7}],audio_player[decode{kind=\"flac\"}]=debug",
+        );
+        let mut targets = BTreeMap::new();
+        targets.insert("bridge[stream{id=7}]".to_string(), Some("warn".to_string()));
+        let filter = control.update(None, &targets).unwrap();
+        assert_eq!(
+            filter,
+            "info,audio_player[decode{kind=\"flac\"}]=debug,bridge[stream{id=7}]=warn"
+        );
+        assert_eq!(
+            split_level("bridge[stream{id=7}]"),
+            None,
+            "a span field is not a level"
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Per-channel peak/RMS metering for the output callback.
+//!
+//! The callback accumulates each buffer into a [`LevelBlock`] (plain `f32` math, no
+//! allocation) and publishes it with [`LevelMeter::publish`]; readers such as status snapshots
+//! call [`LevelMeter::levels`] from any thread. Values travel as `f32` bits in atomics so
+//! neither side takes a lock.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use audio_bridge_types::ChannelLevel;
+
+/// Time for a held peak to fall by a factor of `e` once the signal drops.
+const PEAK_RELEASE_SECS: f32 = 0.5;
+/// Averaging window of the RMS level (VU-style ballistics).
+const RMS_WINDOW_SECS: f32 = 0.3;
+
+/// Shared level meter for one output stream.
+#[derive(Debug)]
+pub struct LevelMeter {
+    sample_rate: u32,
+    /// Linear peak per channel.
+    peak: Box<[AtomicU32]>,
+    /// Mean square per channel.
+    mean_square: Box<[AtomicU32]>,
+}
+
+/// Per-callback accumulator handed to [`LevelMeter::publish`].
+#[derive(Debug)]
+pub struct LevelBlock {
+    peak: Vec<f32>,
+    sum_square: Vec<f32>,
+}
+
+impl LevelBlock {
+    /// Accumulator for `channels` interleaved channels.
+    pub fn new(channels: usize) -> Self {
+        Self {
+            peak: vec![0.0; channels],
+            sum_square: vec![0.0; channels],
+        }
+    }
+
+    /// Account for one output sample on `ch`; channels beyond the meter are ignored.
+    #[inline]
+    pub fn add(&mut self, ch: usize, sample: f32) {
+        if let (Some(peak), Some(sum)) = (self.peak.get_mut(ch), self.sum_square.get_mut(ch)) {
+            *peak = peak.max(sample.abs());
+            *sum += sample * sample;
+        }
+    }
+
+    fn clear(&mut self) {
+        self.peak.fill(0.0);
+        self.sum_square.fill(0.0);
+    }
+}
+
+impl LevelMeter {
+    /// Meter for `channels` channels at `sample_rate` Hz.
+    pub fn new(channels: usize, sample_rate: u32) -> Self {
+        let zeros = || (0..channels.max(1)).map(|_| AtomicU32::new(0)).collect();
+        Self {
+            sample_rate: sample_rate.max(1),
+            peak: zeros(),
+            mean_square: zeros(),
+        }
+    }
+
+    /// Number of metered channels.
+    pub fn channels(&self) -> usize {
+        self.peak.len()
+    }
+
+    /// Fresh accumulator sized for this meter.
+    pub fn block(&self) -> LevelBlock {
+        LevelBlock::new(self.channels())
+    }
+
+    /// Fold `frames` frames of output into the levels and clear `block`.
+    ///
+    /// Samples not added to `block` (silence, pause) count as zero, so the levels fall off
+    /// while nothing plays. Called from the output callback.
+    pub fn publish(&self, block: &mut LevelBlock, frames: usize) {
+        if frames == 0 {
+            return;
+        }
+        let elapsed = frames as f32 / self.sample_rate as f32;
+        let peak_decay = (-elapsed / PEAK_RELEASE_SECS).exp();
+        let rms_keep = (-elapsed / RMS_WINDOW_SECS).exp();
+        for ch in 0..self.channels() {
+            let prev_peak = f32::from_bits(self.peak[ch].load(Ordering::Relaxed));
+            let peak = block.peak[ch].max(prev_peak * peak_decay);
+            self.peak[ch].store(peak.to_bits(), Ordering::Relaxed);
+
+            let prev_ms = f32::from_bits(self.mean_square[ch].load(Ordering::Relaxed));
+            let block_ms = block.sum_square[ch] / frames as f32;
+            let ms = prev_ms * rms_keep + block_ms * (1.0 - rms_keep);
+            self.mean_square[ch].store(ms.to_bits(), Ordering::Relaxed);
+        }
+        block.clear();
+    }
+
+    /// Current levels, one entry per channel.
+    pub fn levels(&self) -> Vec<ChannelLevel> {
+        (0..self.channels())
+            .map(|ch| {
+                let peak = f32::from_bits(self.peak[ch].load(Ordering::Relaxed));
+                let ms = f32::from_bits(self.mean_square[ch].load(Ordering::Relaxed));
+                ChannelLevel {
+                    peak_dbfs: to_dbfs(peak),
+                    rms_dbfs: to_dbfs(ms.sqrt()),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Linear amplitude to dBFS, floored at [`ChannelLevel::FLOOR_DBFS`].
+fn to_dbfs(amplitude: f32) -> f32 {
+    if amplitude > 0.0 {
+        (20.0 * amplitude.log10()).max(ChannelLevel::FLOOR_DBFS)
+    } else {
+        ChannelLevel::FLOOR_DBFS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_scale_square_wave_settles_at_zero_dbfs() {
+        let meter = LevelMeter::new(2, 48_000);
+        let mut block = meter.block();
+        for _ in 0..20 {
+            for i in 0..4_800 {
+                let s = if i % 2 == 0 { 1.0 } else { -1.0 };
+                block.add(0, s);
+                block.add(1, s * 0.5);
+            }
+            meter.publish(&mut block, 4_800);
+        }
+        let levels = meter.levels();
+        assert!(levels[0].peak_dbfs.abs() < 0.01);
+        assert!(levels[0].rms_dbfs.abs() < 0.1);
+        assert!((levels[1].peak_dbfs + 6.02).abs() < 0.01);
+    }
+
+    #[test]
+    fn levels_fall_back_to_floor_during_silence() {
+        let meter = LevelMeter::new(1, 48_000);
+        let mut block = meter.block();
+        block.add(0, 1.0);
+        meter.publish(&mut block, 1);
+        assert_eq!(meter.levels()[0].peak_dbfs, 0.0);
+
+        meter.publish(&mut block, 4_800);
+        let held = meter.levels()[0].peak_dbfs;
+        assert!(held < 0.0 && held > -2.0, "peak released too fast: {held}");
+
+        for _ in 0..100 {
+            meter.publish(&mut block, 48_000);
+        }
+        assert_eq!(meter.levels()[0].peak_dbfs, ChannelLevel::FLOOR_DBFS);
+        assert_eq!(meter.levels()[0].rms_dbfs, ChannelLevel::FLOOR_DBFS);
+    }
+}
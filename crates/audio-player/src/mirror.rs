@@ -0,0 +1,284 @@
+//! Mirror output: plays the primary stream on a second device (e.g. main DAC + kitchen speaker).
+//!
+//! The primary output callback is the clock master. Each callback hands the frames it just
+//! played (channel-mapped, before volume) to a [`MirrorTap`] without blocking; the mirror
+//! device drains them through its own [`OutputFiller`](crate::playback::OutputFiller) with an
+//! independent volume and mute. Clock drift between the two devices is absorbed by the short
+//! mirror queue: when the mirror runs slow, frames that no longer fit are dropped, and when it
+//! runs fast it plays silence until the primary catches up.
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering};
+
+use anyhow::{Result, anyhow};
+use cpal::traits::{DeviceTrait, StreamTrait};
+use symphonia::core::audio::{Channels, SignalSpec};
+
+use crate::device;
+use crate::playback;
+use crate::queue::{SharedAudio, calc_max_buffered_samples};
+use crate::resample;
+
+/// Mirror queue depth; bounds how far the mirror can lag the primary output.
+const MIRROR_BUFFER_SECONDS: f32 = 0.5;
+
+/// Secondary output that every playback session is mirrored to.
+#[derive(Debug)]
+pub struct MirrorTarget {
+    selector: String,
+    volume_percent: Arc<AtomicU8>,
+    muted: Arc<AtomicBool>,
+}
+
+impl MirrorTarget {
+    /// Mirror to the device matching `selector` (same forms as the primary device selector),
+    /// starting at full volume.
+    pub fn new(selector: impl Into<String>) -> Self {
+        Self {
+            selector: selector.into(),
+            volume_percent: Arc::new(AtomicU8::new(100)),
+            muted: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Device selector the mirror resolves per session.
+    pub fn selector(&self) -> &str {
+        &self.selector
+    }
+
+    /// Read current `(volume, muted)` state.
+    pub fn snapshot(&self) -> (u8, bool) {
+        (
+            self.volume_percent.load(Ordering::Relaxed),
+            self.muted.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Set mirror volume (clamped to 0..=100); applies to the running session immediately.
+    pub fn set_volume(&self, value: u8) {
+        self.volume_percent.store(value.min(100), Ordering::Relaxed);
+    }
+
+    /// Set mirror mute flag; applies to the running session immediately.
+    pub fn set_muted(&self, muted: bool) {
+        self.muted.store(muted, Ordering::Relaxed);
+    }
+}
+
+/// Non-blocking handle the primary output callback feeds.
+#[derive(Clone)]
+pub struct MirrorTap {
+    queue: Arc<SharedAudio>,
+    dropped_frames: Arc<AtomicU64>,
+}
+
+impl fmt::Debug for MirrorTap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MirrorTap")
+            .field("channels", &self.queue.channels())
+            .field(
+                "dropped_frames",
+                &self.dropped_frames.load(Ordering::Relaxed),
+            )
+            .finish()
+    }
+}
+
+impl MirrorTap {
+    /// Queue interleaved frames for the mirror; frames that do not fit are dropped and counted.
+    pub fn push(&self, samples: &[f32]) {
+        let frames = samples.len() / self.queue.channels();
+        let queued = self.queue.push_interleaved_nonblocking(samples);
+        if queued < frames && !self.queue.is_done() {
+            self.dropped_frames
+                .fetch_add((frames - queued) as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Running mirror stream for one playback session; stops when dropped.
+pub(crate) struct MirrorOutput {
+    tap: MirrorTap,
+    resampled: Option<Arc<SharedAudio>>,
+    _stream: cpal::Stream,
+}
+
+impl MirrorOutput {
+    /// Tap to hand to the primary output's [`PlaybackConfig`](playback::PlaybackConfig).
+    pub(crate) fn tap(&self) -> MirrorTap {
+        self.tap.clone()
+    }
+}
+
+impl Drop for MirrorOutput {
+    fn drop(&mut self) {
+        self.tap.queue.close();
+        if let Some(queue) = &self.resampled {
+            queue.close();
+        }
+        let dropped = self.tap.dropped_frames.load(Ordering::Relaxed);
+        if dropped > 0 {
+            tracing::info!(
+                dropped_frames = dropped,
+                "mirror output dropped frames (clock drift)"
+            );
+        }
+    }
+}
+
+/// Resolve `target` on the current output host and start mirroring a `rate`/`channels` stream.
+///
+/// Fails when the mirror resolves to the primary `device` itself.
+pub(crate) fn open(
+    target: &MirrorTarget,
+    primary: &cpal::Device,
+    rate: u32,
+    channels: u16,
+    chunk_frames: usize,
+    quality: resample::ResampleQuality,
+    backend: resample::ResampleBackend,
+) -> Result<MirrorOutput> {
+    let host = device::output_host()?;
+    let mirror = device::pick_device(&host, Some(target.selector()))?;
+    if device::same_device(&mirror, primary) {
+        return Err(anyhow!("mirror device is the primary output"));
+    }
+    open_on(
+        &mirror,
+        target,
+        rate,
+        channels,
+        chunk_frames,
+        quality,
+        backend,
+    )
+}
+
+/// Start mirroring a `rate`/`channels` stream on `mirror`, resampling if it runs at another rate.
+fn open_on(
+    mirror: &cpal::Device,
+    target: &MirrorTarget,
+    rate: u32,
+    channels: u16,
+    chunk_frames: usize,
+    quality: resample::ResampleQuality,
+    backend: resample::ResampleBackend,
+) -> Result<MirrorOutput> {
+    let config = device::pick_output_config(mirror, Some(rate))?;
+    // Device-default buffering: a large fixed buffer would outgrow the short mirror queue.
+    let stream_config: cpal::StreamConfig = config.clone().into();
+    let channels = usize::from(channels.max(1));
+    let tap = MirrorTap {
+        queue: Arc::new(SharedAudio::new(
+            channels,
+            calc_max_buffered_samples(rate, channels, MIRROR_BUFFER_SECONDS),
+        )),
+        dropped_frames: Arc::new(AtomicU64::new(0)),
+    };
+    let resampled = if stream_config.sample_rate == rate {
+        None
+    } else {
+        let layout = Channels::from_bits_truncate(((1u64 << channels) - 1) as u32);
+        Some(resample::start_resampler(
+            tap.queue.clone(),
+            SignalSpec::new(rate, layout),
+            stream_config.sample_rate,
+            resample::ResampleConfig {
+                chunk_frames,
+                buffer_seconds: MIRROR_BUFFER_SECONDS,
+                quality,
+                backend,
+            },
+        )?)
+    };
+    let source = resampled.as_ref().unwrap_or(&tap.queue);
+    let stream = playback::build_output_stream(
+        mirror,
+        &stream_config,
+        config.sample_format(),
+        source,
+        playback::PlaybackConfig {
+            refill_max_frames: chunk_frames,
+            paused: None,
+            played_frames: None,
+            underrun_frames: None,
+            underrun_events: None,
+            buffered_frames: None,
+            cancel_on_error: None,
+            device_lost: None,
+            volume_percent: Some(target.volume_percent.clone()),
+            muted: Some(target.muted.clone()),
+            record: None,
+            mirror: None,
+            // The primary output applies pre-gain before the tap.
+            pre_gain: 1.0,
+            channel_mix: Default::default(),
+            meter: None,
+            underrun_fade_frames: 0,
+            bit_perfect: None,
+            preroll_frames: 0,
+            dop_idle: false,
+            drained: None,
+        },
+    )?;
+    stream.play()?;
+    tracing::info!(
+        device = %mirror
+            .description()
+            .map(|d| d.to_string())
+            .unwrap_or_else(|_| "<unknown>".to_string()),
+        source_rate_hz = rate,
+        stream_rate_hz = stream_config.sample_rate,
+        "mirror output started"
+    );
+    Ok(MirrorOutput {
+        tap,
+        resampled,
+        _stream: stream,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::null_output::{self, NullPace};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn mirror_target_clamps_volume() {
+        let target = MirrorTarget::new("kitchen");
+        assert_eq!(target.snapshot(), (100, false));
+        target.set_volume(150);
+        target.set_muted(true);
+        assert_eq!(target.snapshot(), (100, true));
+        target.set_volume(40);
+        assert_eq!(target.snapshot(), (40, true));
+    }
+
+    #[test]
+    fn mirror_drains_tap_and_drops_overflow() {
+        let target = MirrorTarget::new("null");
+        let output = open_on(
+            &null_output::device(NullPace::Realtime),
+            &target,
+            48_000,
+            2,
+            1024,
+            resample::ResampleQuality::default(),
+            resample::ResampleBackend::default(),
+        )
+        .unwrap();
+        let tap = output.tap();
+        // One second of audio against a 0.5s queue: the excess is dropped, not blocked on.
+        tap.push(&vec![0.1; 2 * 48_000]);
+        assert!(tap.dropped_frames.load(Ordering::Relaxed) >= 24_000);
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while tap.queue.len_frames() > 20_000 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert!(tap.queue.len_frames() <= 20_000);
+        drop(output);
+        assert!(tap.queue.is_done());
+    }
+}
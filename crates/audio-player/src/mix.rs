@@ -0,0 +1,288 @@
+//! Channel mixing between the decoded layout and the output device.
+//!
+//! Layouts are identified by channel count using the WAVE/FLAC channel order:
+//! - 1: mono
+//! - 2: FL FR
+//! - 4: FL FR BL BR
+//! - 6 (5.1): FL FR FC LFE BL BR
+//! - 8 (7.1): FL FR FC LFE BL BR SL SR
+//!
+//! Other counts keep the legacy mapping: channel `n` plays source channel `n`, clamped to the
+//! last source channel.
+
+/// Attenuation used when folding a channel into two others (-3 dB).
+const FOLD_GAIN: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// Tuning for [`MixMatrix`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChannelMixConfig {
+    /// Level (dB) at which LFE is folded into the front channels when the output has no LFE
+    /// channel; `None` drops it.
+    pub lfe_db: Option<f32>,
+    /// When upmixing, copy the front pair onto rear/side speakers instead of leaving them silent.
+    pub duplicate_surround: bool,
+}
+
+impl Default for ChannelMixConfig {
+    fn default() -> Self {
+        Self {
+            lfe_db: None,
+            duplicate_surround: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Role {
+    FrontLeft,
+    FrontRight,
+    FrontCentre,
+    Lfe,
+    BackLeft,
+    BackRight,
+    SideLeft,
+    SideRight,
+}
+
+use Role::*;
+
+/// Speaker roles for a known multichannel layout.
+fn layout(channels: usize) -> Option<&'static [Role]> {
+    match channels {
+        2 => Some(&[FrontLeft, FrontRight]),
+        4 => Some(&[FrontLeft, FrontRight, BackLeft, BackRight]),
+        6 => Some(&[FrontLeft, FrontRight, FrontCentre, Lfe, BackLeft, BackRight]),
+        8 => Some(&[
+            FrontLeft,
+            FrontRight,
+            FrontCentre,
+            Lfe,
+            BackLeft,
+            BackRight,
+            SideLeft,
+            SideRight,
+        ]),
+        _ => None,
+    }
+}
+
+/// Whether `role` is on the left (`Some(true)`), right (`Some(false)`) or centre (`None`).
+fn side(role: Role) -> Option<bool> {
+    match role {
+        FrontLeft | BackLeft | SideLeft => Some(true),
+        FrontRight | BackRight | SideRight => Some(false),
+        FrontCentre | Lfe => None,
+    }
+}
+
+/// Dense `dst x src` gain matrix applied to each interleaved frame.
+#[derive(Clone, Debug)]
+pub struct MixMatrix {
+    src_channels: usize,
+    dst_channels: usize,
+    /// Row-major: `coeffs[dst * src_channels + src]`.
+    coeffs: Vec<f32>,
+}
+
+impl MixMatrix {
+    /// Build the matrix mapping `src_channels` onto `dst_channels`.
+    ///
+    /// Downmix rows whose gains sum above unity are scaled down so a full-scale source cannot
+    /// clip.
+    pub fn new(src_channels: usize, dst_channels: usize, cfg: &ChannelMixConfig) -> Self {
+        let src_channels = src_channels.max(1);
+        let dst_channels = dst_channels.max(1);
+        let mut m = Self {
+            src_channels,
+            dst_channels,
+            coeffs: vec![0.0; src_channels * dst_channels],
+        };
+
+        if src_channels == dst_channels {
+            for ch in 0..src_channels {
+                m.set(ch, ch, 1.0);
+            }
+        } else if dst_channels == 1 {
+            // Mono output: average of the stereo fold.
+            let stereo = Self::new(src_channels, 2, cfg);
+            for s in 0..src_channels {
+                m.set(0, s, 0.5 * (stereo.get(0, s) + stereo.get(1, s)));
+            }
+        } else if src_channels == 1 {
+            let dst = layout(dst_channels);
+            for d in 0..dst_channels {
+                let feed = match dst.map(|roles| roles[d]) {
+                    Some(FrontLeft | FrontRight) => true,
+                    Some(FrontCentre | Lfe) => false,
+                    Some(_) => cfg.duplicate_surround,
+                    None => true,
+                };
+                if feed {
+                    m.set(d, 0, 1.0);
+                }
+            }
+        } else if let (Some(src), Some(dst)) = (layout(src_channels), layout(dst_channels)) {
+            m.fill_between(src, dst, cfg);
+        } else {
+            for d in 0..dst_channels {
+                m.set(d, d.min(src_channels - 1), 1.0);
+            }
+        }
+
+        m.normalize_rows();
+        m
+    }
+
+    /// Source channel count this matrix expects per frame.
+    pub fn src_channels(&self) -> usize {
+        self.src_channels
+    }
+
+    /// Output sample for `dst_ch` from one interleaved source `frame`.
+    ///
+    /// Missing trailing source samples are treated as silence.
+    pub fn sample(&self, frame: &[f32], dst_ch: usize) -> f32 {
+        let row = &self.coeffs[dst_ch * self.src_channels..(dst_ch + 1) * self.src_channels];
+        row.iter().zip(frame).map(|(c, s)| c * s).sum()
+    }
+
+    fn get(&self, dst: usize, src: usize) -> f32 {
+        self.coeffs[dst * self.src_channels + src]
+    }
+
+    fn set(&mut self, dst: usize, src: usize, gain: f32) {
+        self.coeffs[dst * self.src_channels + src] = gain;
+    }
+
+    fn add(&mut self, dst: usize, src: usize, gain: f32) {
+        self.coeffs[dst * self.src_channels + src] += gain;
+    }
+
+    /// Map between two known layouts: matching roles pass through, missing ones fold into
+    /// their nearest neighbours.
+    fn fill_between(&mut self, src: &[Role], dst: &[Role], cfg: &ChannelMixConfig) {
+        let find = |role: Role| dst.iter().position(|r| *r == role);
+        let front = |left: bool| find(if left { FrontLeft } else { FrontRight });
+
+        for (s, &role) in src.iter().enumerate() {
+            if let Some(d) = find(role) {
+                self.set(d, s, 1.0);
+                continue;
+            }
+            match role {
+                FrontCentre => {
+                    for d in [find(FrontLeft), find(FrontRight)].into_iter().flatten() {
+                        self.add(d, s, FOLD_GAIN);
+                    }
+                }
+                Lfe => {
+                    if let Some(db) = cfg.lfe_db {
+                        let gain = 10f32.powf(db / 20.0);
+                        for d in [find(FrontLeft), find(FrontRight)].into_iter().flatten() {
+                            self.add(d, s, gain);
+                        }
+                    }
+                }
+                BackLeft | BackRight | SideLeft | SideRight => {
+                    let left = side(role) == Some(true);
+                    let sibling = match role {
+                        BackLeft => SideLeft,
+                        BackRight => SideRight,
+                        SideLeft => BackLeft,
+                        _ => BackRight,
+                    };
+                    if let Some(d) = find(sibling) {
+                        self.add(d, s, 1.0);
+                    } else if let Some(d) = front(left) {
+                        self.add(d, s, FOLD_GAIN);
+                    }
+                }
+                FrontLeft | FrontRight => {}
+            }
+        }
+
+        if cfg.duplicate_surround {
+            // Upmix: surround speakers with no source of their own repeat the front pair.
+            for (d, &role) in dst.iter().enumerate() {
+                let Some(left) = side(role) else {
+                    continue;
+                };
+                let silent = (0..src.len()).all(|s| self.get(d, s) == 0.0);
+                if silent && let Some(s) = src.iter().position(|r| side(*r) == Some(left)) {
+                    self.set(d, s, 1.0);
+                }
+            }
+        }
+    }
+
+    /// Scale rows summing above unity back to unity gain.
+    fn normalize_rows(&mut self) {
+        for d in 0..self.dst_channels {
+            let row = &mut self.coeffs[d * self.src_channels..(d + 1) * self.src_channels];
+            let sum: f32 = row.iter().map(|c| c.abs()).sum();
+            if sum > 1.0 {
+                for c in row.iter_mut() {
+                    *c /= sum;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn surround_to_stereo_folds_centre_and_rears() {
+        let m = MixMatrix::new(6, 2, &ChannelMixConfig::default());
+        // FL FR FC LFE BL BR, only the centre is active.
+        let left = m.sample(&[0.0, 0.0, 1.0, 0.0, 0.0, 0.0], 0);
+        let norm = 1.0 + 2.0 * FOLD_GAIN;
+        assert!(approx(left, FOLD_GAIN / norm));
+        // LFE is dropped by default.
+        assert_eq!(m.sample(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0], 0), 0.0);
+        // A full-scale frame cannot exceed unity.
+        assert!(m.sample(&[1.0; 6], 0) <= 1.0 + 1e-6);
+    }
+
+    #[test]
+    fn lfe_is_folded_when_configured() {
+        let cfg = ChannelMixConfig {
+            lfe_db: Some(0.0),
+            ..ChannelMixConfig::default()
+        };
+        let m = MixMatrix::new(6, 2, &cfg);
+        assert!(m.sample(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0], 1) > 0.0);
+    }
+
+    #[test]
+    fn seven_one_to_five_one_merges_sides_into_rears() {
+        let m = MixMatrix::new(8, 6, &ChannelMixConfig::default());
+        let frame = [0.0, 0.0, 0.0, 0.0, 0.2, 0.0, 0.2, 0.0];
+        assert!(approx(m.sample(&frame, 4), 0.2));
+        assert_eq!(m.sample(&frame, 0), 0.0);
+    }
+
+    #[test]
+    fn stereo_upmix_duplicates_front_pair() {
+        let m = MixMatrix::new(2, 8, &ChannelMixConfig::default());
+        let frame = [0.3, -0.3];
+        assert_eq!(m.sample(&frame, 0), 0.3);
+        assert_eq!(m.sample(&frame, 2), 0.0);
+        assert_eq!(m.sample(&frame, 3), 0.0);
+        assert_eq!(m.sample(&frame, 5), -0.3);
+        assert_eq!(m.sample(&frame, 6), 0.3);
+
+        let front_only = ChannelMixConfig {
+            duplicate_surround: false,
+            ..ChannelMixConfig::default()
+        };
+        let m = MixMatrix::new(2, 6, &front_only);
+        assert_eq!(m.sample(&frame, 4), 0.0);
+    }
+}
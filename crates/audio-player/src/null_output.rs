@@ -0,0 +1,344 @@
+//! Null output backend: a CPAL device with no hardware behind it.
+//!
+//! Streams run the regular output callback on a worker thread and discard the samples, paced
+//! either at the stream's sample rate or as fast as the queue can be drained. This lets the
+//! playback pipeline run in CI and on headless hosts without an audio device.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Result, anyhow};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+/// Display name of the null output device.
+pub const DEVICE_NAME: &str = "Null Output";
+
+/// Period used when the stream config leaves the buffer size to the device.
+const DEFAULT_PERIOD_FRAMES: u32 = 1024;
+/// Poll interval while a stream is paused.
+const PAUSED_POLL: Duration = Duration::from_millis(5);
+
+/// How fast null streams consume samples.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NullPace {
+    /// One period per period duration, like a real device.
+    #[default]
+    Realtime,
+    /// Back-to-back callbacks; underrun counters are meaningless in this mode.
+    Fast,
+}
+
+impl FromStr for NullPace {
+    type Err = anyhow::Error;
+
+    /// Parse `realtime` or `fast`.
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "realtime" => Ok(Self::Realtime),
+            "fast" => Ok(Self::Fast),
+            other => Err(anyhow!("expected `realtime` or `fast` (got `{other}`)")),
+        }
+    }
+}
+
+/// CPAL host exposing a single null output device.
+pub fn host(pace: NullPace) -> cpal::Host {
+    cpal::Host::from(cpal::platform::CustomHost::from_host(NullHost { pace }))
+}
+
+/// The null output device on its own (without going through [`host`]).
+pub fn device(pace: NullPace) -> cpal::Device {
+    cpal::Device::from(cpal::platform::CustomDevice::from_device(NullDevice {
+        pace,
+    }))
+}
+
+#[derive(Clone)]
+struct NullHost {
+    pace: NullPace,
+}
+
+impl HostTrait for NullHost {
+    type Device = NullDevice;
+    type Devices = std::iter::Once<NullDevice>;
+
+    fn is_available() -> bool {
+        true
+    }
+
+    fn devices(&self) -> Result<Self::Devices, cpal::DevicesError> {
+        Ok(std::iter::once(NullDevice { pace: self.pace }))
+    }
+
+    fn default_input_device(&self) -> Option<Self::Device> {
+        None
+    }
+
+    fn default_output_device(&self) -> Option<Self::Device> {
+        Some(NullDevice { pace: self.pace })
+    }
+}
+
+#[derive(Clone)]
+struct NullDevice {
+    pace: NullPace,
+}
+
+/// Channel counts offered, stereo first so it wins config selection.
+const CHANNELS: [u16; 8] = [2, 1, 3, 4, 5, 6, 7, 8];
+const MIN_RATE_HZ: u32 = 8_000;
+const MAX_RATE_HZ: u32 = 768_000;
+
+impl DeviceTrait for NullDevice {
+    type SupportedInputConfigs = std::iter::Empty<cpal::SupportedStreamConfigRange>;
+    type SupportedOutputConfigs = std::vec::IntoIter<cpal::SupportedStreamConfigRange>;
+    type Stream = NullStream;
+
+    fn description(&self) -> Result<cpal::DeviceDescription, cpal::DeviceNameError> {
+        Ok(cpal::DeviceDescriptionBuilder::new(DEVICE_NAME.to_string()).build())
+    }
+
+    fn id(&self) -> Result<cpal::DeviceId, cpal::DeviceIdError> {
+        Ok(cpal::DeviceId(cpal::HostId::Custom, "null".to_string()))
+    }
+
+    fn supported_input_configs(
+        &self,
+    ) -> Result<Self::SupportedInputConfigs, cpal::SupportedStreamConfigsError> {
+        Ok(std::iter::empty())
+    }
+
+    fn supported_output_configs(
+        &self,
+    ) -> Result<Self::SupportedOutputConfigs, cpal::SupportedStreamConfigsError> {
+        Ok(CHANNELS
+            .iter()
+            .map(|&channels| {
+                cpal::SupportedStreamConfigRange::new(
+                    channels,
+                    MIN_RATE_HZ,
+                    MAX_RATE_HZ,
+                    cpal::SupportedBufferSize::Unknown,
+                    cpal::SampleFormat::F32,
+                )
+            })
+            .collect::<Vec<_>>()
+            .into_iter())
+    }
+
+    fn default_input_config(
+        &self,
+    ) -> Result<cpal::SupportedStreamConfig, cpal::DefaultStreamConfigError> {
+        Err(cpal::DefaultStreamConfigError::StreamTypeNotSupported)
+    }
+
+    fn default_output_config(
+        &self,
+    ) -> Result<cpal::SupportedStreamConfig, cpal::DefaultStreamConfigError> {
+        Ok(cpal::SupportedStreamConfig::new(
+            2,
+            48_000,
+            cpal::SupportedBufferSize::Unknown,
+            cpal::SampleFormat::F32,
+        ))
+    }
+
+    fn build_input_stream_raw<D, E>(
+        &self,
+        _config: &cpal::StreamConfig,
+        _sample_format: cpal::SampleFormat,
+        _data_callback: D,
+        _error_callback: E,
+        _timeout: Option<Duration>,
+    ) -> Result<Self::Stream, cpal::BuildStreamError>
+    where
+        D: FnMut(&cpal::Data, &cpal::InputCallbackInfo) + Send + 'static,
+        E: FnMut(cpal::StreamError) + Send + 'static,
+    {
+        Err(cpal::BuildStreamError::StreamConfigNotSupported)
+    }
+
+    fn build_output_stream_raw<D, E>(
+        &self,
+        config: &cpal::StreamConfig,
+        sample_format: cpal::SampleFormat,
+        mut data_callback: D,
+        _error_callback: E,
+        _timeout: Option<Duration>,
+    ) -> Result<Self::Stream, cpal::BuildStreamError>
+    where
+        D: FnMut(&mut cpal::Data, &cpal::OutputCallbackInfo) + Send + 'static,
+        E: FnMut(cpal::StreamError) + Send + 'static,
+    {
+        if sample_format != cpal::SampleFormat::F32
+            || config.channels == 0
+            || !(MIN_RATE_HZ..=MAX_RATE_HZ).contains(&config.sample_rate)
+        {
+            return Err(cpal::BuildStreamError::StreamConfigNotSupported);
+        }
+        let period_frames = match config.buffer_size {
+            cpal::BufferSize::Fixed(frames) if frames > 0 => frames,
+            _ => DEFAULT_PERIOD_FRAMES,
+        };
+        let period = Duration::from_secs_f64(period_frames as f64 / config.sample_rate as f64);
+        let samples = period_frames as usize * config.channels as usize;
+        let pace = self.pace;
+        let controls = Arc::new(StreamControls {
+            exit: AtomicBool::new(false),
+            playing: AtomicBool::new(false),
+        });
+        let thread_controls = controls.clone();
+        let handle = thread::Builder::new()
+            .name("null-output".to_string())
+            .spawn(move || {
+                let started = Instant::now();
+                let mut buffer = vec![0.0f32; samples];
+                let mut deadline: Option<Instant> = None;
+                while !thread_controls.exit.load(Ordering::Relaxed) {
+                    if !thread_controls.playing.load(Ordering::Relaxed) {
+                        deadline = None;
+                        thread::sleep(PAUSED_POLL);
+                        continue;
+                    }
+                    let elapsed = started.elapsed();
+                    let instant =
+                        cpal::StreamInstant::new(elapsed.as_secs() as i64, elapsed.subsec_nanos());
+                    let info = cpal::OutputCallbackInfo::new(cpal::OutputStreamTimestamp {
+                        callback: instant,
+                        playback: instant,
+                    });
+                    // SAFETY: `buffer` is a live, exclusively borrowed `f32` buffer of
+                    // `buffer.len()` samples for the duration of the callback.
+                    let mut data = unsafe {
+                        cpal::Data::from_parts(
+                            buffer.as_mut_ptr().cast(),
+                            buffer.len(),
+                            cpal::SampleFormat::F32,
+                        )
+                    };
+                    data_callback(&mut data, &info);
+                    match pace {
+                        NullPace::Realtime => {
+                            let next = deadline.unwrap_or_else(Instant::now) + period;
+                            if let Some(wait) = next.checked_duration_since(Instant::now()) {
+                                thread::sleep(wait);
+                            }
+                            deadline = Some(next);
+                        }
+                        NullPace::Fast => thread::yield_now(),
+                    }
+                }
+            })
+            .map_err(|err| cpal::BuildStreamError::BackendSpecific {
+                err: cpal::BackendSpecificError {
+                    description: format!("spawn null output thread: {err}"),
+                },
+            })?;
+        Ok(NullStream {
+            controls,
+            handle: Some(handle),
+        })
+    }
+}
+
+struct StreamControls {
+    exit: AtomicBool,
+    playing: AtomicBool,
+}
+
+/// Worker-thread stream; starts paused and stops when dropped.
+struct NullStream {
+    controls: Arc<StreamControls>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl StreamTrait for NullStream {
+    fn play(&self) -> Result<(), cpal::PlayStreamError> {
+        self.controls.playing.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn pause(&self) -> Result<(), cpal::PauseStreamError> {
+        self.controls.playing.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+impl Drop for NullStream {
+    fn drop(&mut self) {
+        self.controls.exit.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PlaybackConfig;
+    use crate::device;
+    use crate::pipeline::{self, PlaybackSessionOptions};
+    use crate::queue::SharedAudio;
+    use std::sync::atomic::AtomicU64;
+    use symphonia::core::audio::{Channels, SignalSpec};
+
+    #[test]
+    fn null_pace_parses() {
+        assert_eq!("realtime".parse::<NullPace>().unwrap(), NullPace::Realtime);
+        assert_eq!(" FAST ".parse::<NullPace>().unwrap(), NullPace::Fast);
+        assert!("slow".parse::<NullPace>().is_err());
+    }
+
+    #[test]
+    fn host_lists_null_device_with_matching_config() {
+        let host = host(NullPace::Fast);
+        let device = device::pick_device(&host, Some("null")).unwrap();
+        let config = device::pick_output_config(&device, Some(44_100)).unwrap();
+        assert_eq!(config.sample_rate(), 44_100);
+        assert_eq!(config.channels(), 2);
+        assert_eq!(config.sample_format(), cpal::SampleFormat::F32);
+    }
+
+    #[test]
+    fn play_decoded_source_drains_queue_on_null_device() {
+        let device = device(NullPace::Fast);
+        let config = device::pick_output_config(&device, Some(48_000)).unwrap();
+        let stream_config: cpal::StreamConfig = config.clone().into();
+        let srcq = Arc::new(SharedAudio::new(2, 48_000));
+        srcq.push_interleaved_blocking(&[0.25; 2 * 10_000]);
+        srcq.close();
+        let played = Arc::new(AtomicU64::new(0));
+        pipeline::play_decoded_source(
+            &device,
+            &config,
+            &stream_config,
+            &PlaybackConfig::default(),
+            SignalSpec::new(48_000, Channels::FRONT_LEFT | Channels::FRONT_RIGHT),
+            srcq,
+            PlaybackSessionOptions {
+                paused: None,
+                cancel: None,
+                played_frames: Some(played.clone()),
+                underrun_frames: None,
+                underrun_events: None,
+                buffered_frames: None,
+                buffer_capacity_frames: None,
+                volume_percent: None,
+                muted: None,
+                levels: None,
+                bit_perfect: None,
+                hotplug: None,
+                output: None,
+            },
+        )
+        .unwrap();
+        // The session ends once the queue is drained; the filler's last refill may not be
+        // fully played out by then.
+        let played = played.load(Ordering::Relaxed);
+        assert!(played > 0 && played <= 10_000, "played {played} frames");
+    }
+}
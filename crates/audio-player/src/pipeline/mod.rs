@@ -1,15 +1,17 @@
 //! Playback pipeline wiring: resample + playback + optional reporting/cancel.
 
 use std::sync::Arc;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
-use cpal::traits::StreamTrait;
+use anyhow::{Result, anyhow};
+use cpal::traits::{DeviceTrait, StreamTrait};
 
 use crate::config::PlaybackConfig;
-use crate::{playback, queue, resample};
+use crate::meter::LevelMeter;
+use crate::source::{self, QueueSource, TrimSilence};
+use crate::{device as output_device, mirror, playback, queue, resample};
 /// Optional knobs for a single playback session (network sessions use these).
 ///
 /// This lets the pipeline wire in:
@@ -34,6 +36,36 @@ pub struct PlaybackSessionOptions {
     pub volume_percent: Option<Arc<std::sync::atomic::AtomicU8>>,
     /// Optional mute flag.
     pub muted: Option<Arc<std::sync::atomic::AtomicBool>>,
+    /// Optional level meter updated by the output callback.
+    pub levels: Option<Arc<LevelMeter>>,
+    /// Optional bit-perfect flag updated by the output callback; pass it only when the source
+    /// format reaches the device exactly (see [`playback::bit_exact_format`]).
+    pub bit_perfect: Option<Arc<AtomicBool>>,
+    /// Optional device hotplug handling (hold buffered audio and reopen on reconnect).
+    pub hotplug: Option<HotplugOptions>,
+    /// Optional non-CPAL output (e.g. WASAPI exclusive) used instead of a CPAL stream.
+    ///
+    /// `stream_config` must already describe the rate/channels this output was negotiated for.
+    pub output: Option<playback::OutputOpener>,
+}
+
+/// Device hotplug handling for a playback session.
+///
+/// When the output device disappears, the stream is dropped while the decode/resample
+/// queues keep their buffered audio. `reopen` is polled until it yields a device again,
+/// at which point a new stream is built on it and playback continues where it stopped.
+pub struct HotplugOptions {
+    /// Resolve the output device again; return `None` while it is still missing.
+    pub reopen: Box<dyn Fn() -> Option<cpal::Device> + Send>,
+    /// Delay between reopen attempts.
+    pub poll_interval: Duration,
+    /// Give up and end the session with an error after this long (`None` waits forever).
+    pub timeout: Option<Duration>,
+    /// Optional flag set to `true` while the device is disconnected.
+    pub disconnected: Option<Arc<AtomicBool>>,
+    /// Also poll `reopen` while playing and migrate the stream when it yields a different
+    /// device (used to follow the OS default output).
+    pub follow_default: bool,
 }
 
 struct PlaybackState {
@@ -46,6 +78,16 @@ struct PlaybackState {
     buffer_capacity_frames: Option<Arc<AtomicU64>>,
     volume_percent: Option<Arc<std::sync::atomic::AtomicU8>>,
     muted: Option<Arc<std::sync::atomic::AtomicBool>>,
+    levels: Option<Arc<LevelMeter>>,
+    bit_perfect: Option<Arc<AtomicBool>>,
+    hotplug: Option<HotplugOptions>,
+    output: Option<playback::OutputOpener>,
+}
+
+/// Output kept alive for the duration of one stream attempt.
+enum ActiveOutput {
+    Cpal(cpal::Stream),
+    Custom { _handle: Box<dyn Send> },
 }
 
 impl PlaybackState {
@@ -61,6 +103,10 @@ impl PlaybackState {
             buffer_capacity_frames: opts.buffer_capacity_frames,
             volume_percent: opts.volume_percent,
             muted: opts.muted,
+            levels: opts.levels,
+            bit_perfect: opts.bit_perfect,
+            hotplug: opts.hotplug,
+            output: opts.output,
         }
     }
 
@@ -68,6 +114,9 @@ impl PlaybackState {
     fn stop_reporter(self) {}
 }
 
+/// Longest a finished session keeps its output open waiting for the tail to render.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
 /// Wire up the resampler + output stream and block until playback ends or is cancelled.
 ///
 /// This function owns the stage wiring but delegates decoding to `decode::*`.
@@ -83,9 +132,41 @@ pub fn play_decoded_source(
     opts: PlaybackSessionOptions,
 ) -> Result<()> {
     let srcq_for_cancel = srcq.clone();
-    let state = PlaybackState::new(opts);
+    let mut state = PlaybackState::new(opts);
 
     let dst_rate = stream_config.sample_rate;
+    if playback.dop {
+        // DoP frames must reach the DAC bit-exact: no resampling, gain, volume or taps.
+        if src_spec.rate != dst_rate || src_spec.channels.count() != stream_config.channels as usize
+        {
+            return Err(anyhow!(
+                "DoP output needs {} Hz / {} channels, device runs {} Hz / {} channels",
+                src_spec.rate,
+                src_spec.channels.count(),
+                dst_rate,
+                stream_config.channels
+            ));
+        }
+        state.volume_percent = None;
+        state.muted = None;
+        state.levels = None;
+    }
+    let srcq = match playback.silence_trim.filter(|_| !playback.dop) {
+        Some(trim) => {
+            let channels = src_spec.channels.count();
+            let trimmed = Arc::new(queue::SharedAudio::new(
+                channels,
+                queue::calc_max_buffered_samples(src_spec.rate, channels, playback.buffer_seconds),
+            ));
+            source::spawn_pump(
+                TrimSilence::new(QueueSource::new(srcq, src_spec), trim),
+                trimmed.clone(),
+                playback.chunk_frames,
+            );
+            trimmed
+        }
+        None => srcq,
+    };
     let dstq = if src_spec.rate == dst_rate {
         tracing::info!(rate_hz = dst_rate, "resample skipped");
         srcq.clone()
@@ -97,21 +178,78 @@ pub fn play_decoded_source(
             resample::ResampleConfig {
                 chunk_frames: playback.chunk_frames,
                 buffer_seconds: playback.buffer_seconds,
+                quality: playback.resample_quality,
+                backend: playback.resample_backend,
             },
         )?;
-        tracing::info!(rate_hz = dst_rate, "resampling");
+        tracing::info!(
+            rate_hz = dst_rate,
+            resampler = playback.resample_backend.as_str(),
+            quality = playback.resample_quality.as_str(),
+            "resampling"
+        );
         out
     };
     if let Some(cap) = &state.buffer_capacity_frames {
         cap.store(dstq.max_frames() as u64, Ordering::Relaxed);
     }
 
-    let stream = playback::build_output_stream(
-        device,
-        stream_config,
-        config.sample_format(),
-        &dstq,
-        playback::PlaybackConfig {
+    // Start the output only once enough audio is queued to ride out a slow first fill.
+    if playback.min_prebuffer_ms > 0 {
+        let frames = (u64::from(playback.min_prebuffer_ms) * u64::from(dst_rate) / 1000) as usize;
+        let started = Instant::now();
+        let cancel = state.cancel.clone();
+        let ready = queue::wait_until_buffered_or(&dstq, frames, || {
+            cancel
+                .as_ref()
+                .map(|c| c.load(Ordering::Relaxed))
+                .unwrap_or(false)
+        });
+        if !ready {
+            srcq_for_cancel.close();
+            dstq.close();
+            state.stop_reporter();
+            return Ok(());
+        }
+        tracing::debug!(
+            buffered_frames = dstq.len_frames(),
+            target_frames = frames,
+            waited_ms = started.elapsed().as_millis() as u64,
+            "prebuffer ready"
+        );
+    }
+
+    // Opened once per session: the mirror keeps playing across primary stream reopens.
+    let mirror = playback
+        .mirror
+        .as_ref()
+        .filter(|_| !playback.dop)
+        .and_then(|target| {
+            mirror::open(
+            target,
+            device,
+            stream_config.sample_rate,
+            stream_config.channels,
+            playback.chunk_frames,
+            playback.resample_quality,
+            playback.resample_backend,
+        )
+        .map_err(
+            |e| tracing::warn!(device = target.selector(), error = %e, "mirror output unavailable"),
+        )
+        .ok()
+        });
+
+    let device_lost = Arc::new(AtomicBool::new(false));
+    let drained = Arc::new(playback::DrainSignal::new(dst_rate));
+    let mut device = device.clone();
+    let mut previous_device: Option<cpal::Device> = None;
+    let mut rejected_device: Option<cpal::Device> = None;
+    let mut outcome = Ok(());
+    loop {
+        device_lost.store(false, Ordering::Relaxed);
+        drained.reset();
+        let output_cfg = playback::PlaybackConfig {
             refill_max_frames: playback.refill_max_frames,
             paused: state.paused.clone(),
             played_frames: state.played_frames.clone(),
@@ -121,27 +259,207 @@ pub fn play_decoded_source(
             cancel_on_error: state.cancel.clone(),
             volume_percent: state.volume_percent.clone(),
             muted: state.muted.clone(),
-        },
-    )?;
-    stream.play()?;
+            device_lost: state.hotplug.as_ref().map(|_| device_lost.clone()),
+            record: playback
+                .record
+                .as_ref()
+                .filter(|_| !playback.dop)
+                .and_then(|recorder| {
+                    recorder
+                        .tap(stream_config.sample_rate, stream_config.channels)
+                        .map_err(|e| tracing::warn!(error = %e, "recording unavailable"))
+                        .ok()
+                }),
+            mirror: mirror.as_ref().map(|m| m.tap()),
+            pre_gain: if playback.dop {
+                1.0
+            } else {
+                playback::db_to_gain(playback.pre_gain_db)
+            },
+            channel_mix: playback.channel_mix,
+            meter: state.levels.clone(),
+            underrun_fade_frames: if playback.dop {
+                0
+            } else {
+                playback
+                    .underrun_concealment
+                    .fade_frames(stream_config.sample_rate)
+            },
+            // Resampled audio is never bit-perfect.
+            bit_perfect: state
+                .bit_perfect
+                .clone()
+                .filter(|_| src_spec.rate == stream_config.sample_rate),
+            preroll_frames: (u64::from(playback.preroll_silence_ms)
+                * u64::from(stream_config.sample_rate)
+                / 1000) as usize,
+            dop_idle: playback.dop,
+            drained: Some(drained.clone()),
+        };
+        let built = match &state.output {
+            Some(open) => {
+                open(&device, &dstq, output_cfg).map(|h| ActiveOutput::Custom { _handle: h })
+            }
+            None => playback::build_output_stream(
+                &device,
+                stream_config,
+                config.sample_format(),
+                &dstq,
+                output_cfg,
+            )
+            .map(ActiveOutput::Cpal),
+        };
+        let stream = match built {
+            Ok(stream) => stream,
+            Err(e) => {
+                // A migrated-to device may not accept the current stream config; stay put.
+                let Some(prev) = previous_device.take() else {
+                    return Err(e);
+                };
+                tracing::warn!(error = %e, "output migration failed; keeping previous device");
+                rejected_device = Some(std::mem::replace(&mut device, prev));
+                continue;
+            }
+        };
+        previous_device = None;
+        if let ActiveOutput::Cpal(stream) = &stream {
+            stream.play()?;
+        }
 
-    if let Some(cancel) = &state.cancel {
-        let finished_normally = queue::wait_until_done_and_empty_or_cancel(&dstq, cancel);
+        let cancelled = || {
+            state
+                .cancel
+                .as_ref()
+                .map(|c| c.load(Ordering::Relaxed))
+                .unwrap_or(false)
+        };
+        let follow = state.hotplug.as_ref().filter(|h| h.follow_default);
+        let mut last_follow_check = Instant::now();
+        let mut migrate_to: Option<cpal::Device> = None;
+        let finished_normally = queue::wait_until_done_and_empty_or(&dstq, || {
+            if cancelled() || device_lost.load(Ordering::Relaxed) {
+                return true;
+            }
+            let Some(hotplug) = follow else {
+                return false;
+            };
+            if last_follow_check.elapsed() < hotplug.poll_interval {
+                return false;
+            }
+            last_follow_check = Instant::now();
+            match (hotplug.reopen)() {
+                Some(candidate)
+                    if !output_device::same_device(&candidate, &device)
+                        && !rejected_device
+                            .as_ref()
+                            .is_some_and(|r| output_device::same_device(&candidate, r)) =>
+                {
+                    migrate_to = Some(candidate);
+                    true
+                }
+                _ => false,
+            }
+        });
+        if finished_normally {
+            // The queue is empty but the device still holds its tail; keep the output open
+            // until the last frame has played.
+            let rendered = drained.wait_rendered(DRAIN_TIMEOUT, || {
+                cancelled() || device_lost.load(Ordering::Relaxed)
+            });
+            if !rendered {
+                tracing::debug!("output tail not confirmed rendered");
+            }
+            break;
+        }
 
-        if !finished_normally {
-            if let Some(paused) = &state.paused {
-                paused.store(true, Ordering::Relaxed);
+        if let Some(next) = migrate_to.take()
+            && !cancelled()
+            && !device_lost.load(Ordering::Relaxed)
+        {
+            drop(stream);
+            tracing::info!(
+                device = %next
+                    .description()
+                    .map(|d| d.to_string())
+                    .unwrap_or_else(|_| "<unknown>".to_string()),
+                "default output changed; migrating stream"
+            );
+            previous_device = Some(std::mem::replace(&mut device, next));
+            rejected_device = None;
+            continue;
+        }
+
+        if device_lost.load(Ordering::Relaxed)
+            && !cancelled()
+            && let Some(hotplug) = state.hotplug.as_ref()
+        {
+            drop(stream);
+            tracing::warn!(
+                buffered_frames = dstq.len_frames(),
+                "output device disconnected; holding buffered audio"
+            );
+            if let Some(dev) = wait_for_device(hotplug, state.cancel.as_ref()) {
+                tracing::info!(
+                    device = %dev
+                        .description()
+                        .map(|d| d.to_string())
+                        .unwrap_or_else(|_| "<unknown>".to_string()),
+                    "output device reconnected; reopening stream"
+                );
+                device = dev;
+                continue;
+            }
+            if !cancelled() {
+                outcome = Err(anyhow!("output device did not reconnect"));
             }
-            srcq_for_cancel.close();
-            dstq.close();
         }
-    } else {
-        queue::wait_until_done_and_empty(&dstq);
+
+        if let Some(paused) = &state.paused {
+            paused.store(true, Ordering::Relaxed);
+        }
+        srcq_for_cancel.close();
+        dstq.close();
+        break;
     }
 
     // Stop reporter regardless of normal finish vs cancel, then join it.
     state.stop_reporter();
+    drop(mirror);
 
-    thread::sleep(Duration::from_millis(100));
-    Ok(())
+    outcome
+}
+
+/// Poll `hotplug.reopen` until a device is available, the session is cancelled, or it times out.
+fn wait_for_device(
+    hotplug: &HotplugOptions,
+    cancel: Option<&Arc<AtomicBool>>,
+) -> Option<cpal::Device> {
+    let started = Instant::now();
+    if let Some(flag) = &hotplug.disconnected {
+        flag.store(true, Ordering::Relaxed);
+    }
+    let result = loop {
+        if cancel.map(|c| c.load(Ordering::Relaxed)).unwrap_or(false) {
+            break None;
+        }
+        if let Some(device) = (hotplug.reopen)() {
+            break Some(device);
+        }
+        if hotplug
+            .timeout
+            .map(|limit| started.elapsed() >= limit)
+            .unwrap_or(false)
+        {
+            tracing::warn!(
+                waited_ms = started.elapsed().as_millis() as u64,
+                "output device reconnect timed out"
+            );
+            break None;
+        }
+        thread::sleep(hotplug.poll_interval);
+    };
+    if let Some(flag) = &hotplug.disconnected {
+        flag.store(false, Ordering::Relaxed);
+    }
+    result
 }
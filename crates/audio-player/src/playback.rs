@@ -1,17 +1,28 @@
 //! Playback stage (CPAL output stream).
 //!
-//! Builds the CPAL output stream and provides the real-time audio callback.
+//! Builds the CPAL output stream and provides the real-time audio callback ([`OutputFiller`],
+//! also usable by non-CPAL outputs via [`OutputOpener`]).
 //! The callback:
 //! - refills a small local buffer from the shared queue without blocking
 //! - applies basic channel mapping (mono↔stereo, best-effort otherwise)
 //! - converts `f32` samples to the device sample format
+//!
+//! Integer PCM up to 24 bits is carried exactly by the `f32` queue (decode and device
+//! conversion both scale by powers of two), so when nothing alters the signal the callback
+//! copies source samples straight to the device and the output is bit-perfect.
 
 use anyhow::{Result, anyhow};
 use cpal::traits::DeviceTrait;
 use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use crate::dsd::DopIdle;
+use crate::meter::{LevelBlock, LevelMeter};
+use crate::mirror::MirrorTap;
+use crate::mix::{ChannelMixConfig, MixMatrix};
 use crate::queue::{PopStrategy, SharedAudio};
+use crate::record::RecordTap;
 
 /// Configuration for the playback stage (CPAL output callback).
 #[derive(Clone, Debug)]
@@ -40,10 +51,146 @@ pub struct PlaybackConfig {
     pub buffered_frames: Option<Arc<AtomicU64>>,
     /// When set, stream errors will flip this cancel flag.
     pub cancel_on_error: Option<Arc<AtomicBool>>,
+    /// When set, device disconnect errors flip this flag instead of `cancel_on_error`.
+    ///
+    /// This lets the caller keep the queue alive and reopen the stream once the device returns.
+    pub device_lost: Option<Arc<AtomicBool>>,
     /// Optional user-facing volume percent (0..100).
     pub volume_percent: Option<Arc<AtomicU8>>,
     /// Optional mute flag.
     pub muted: Option<Arc<AtomicBool>>,
+    /// When set, produced samples (post channel mapping and volume) are teed to a recording.
+    pub record: Option<RecordTap>,
+    /// When set, produced frames (post channel mapping, pre-volume) are fed to a mirror output.
+    pub mirror: Option<MirrorTap>,
+    /// Linear gain applied to every sample before volume and the mirror tap (`1.0` = unity).
+    pub pre_gain: f32,
+    /// Downmix/upmix tuning when the source and device channel counts differ.
+    pub channel_mix: ChannelMixConfig,
+    /// When set, the callback publishes per-channel output levels (post volume) here.
+    pub meter: Option<Arc<LevelMeter>>,
+    /// Length of the fade used to conceal underruns, in output frames (`0` = write zeros).
+    ///
+    /// Through a gap the last output frame is held and faded to silence over this many frames;
+    /// audio returning after a gap fades back in over the same length.
+    pub underrun_fade_frames: usize,
+    /// When set, the callback stores whether the last buffer took the passthrough path (no
+    /// channel mixing, gain, volume or fade). Only set this when the source reaches the device
+    /// exactly otherwise (see [`bit_exact_format`]); it then reports bit-perfect output.
+    pub bit_perfect: Option<Arc<AtomicBool>>,
+    /// Frames of silence played when the stream opens, before the queue is drained (for
+    /// outputs that swallow the start of a stream).
+    pub preroll_frames: usize,
+    /// Write DSD-over-PCM idle frames instead of zeros for pre-roll, pause and underruns, so a
+    /// DoP DAC stays in DSD mode (see [`crate::dsd`]).
+    pub dop_idle: bool,
+    /// When set, the output marks it once the queue is closed and its last frame has been
+    /// handed to the device.
+    pub drained: Option<Arc<DrainSignal>>,
+}
+
+/// Tells the session when an output has finished rendering its queue.
+///
+/// The output marks it from the callback that writes the final queued frame, predicting when
+/// that buffer leaves the device; [`wait_rendered`](Self::wait_rendered) blocks until then.
+/// The deadline is a single atomic so the realtime callback never takes a lock.
+#[derive(Debug)]
+pub struct DrainSignal {
+    sample_rate: u32,
+    /// Origin of `rendered_at_ns`.
+    base: Instant,
+    /// Nanoseconds after `base` at which the final frames have played ([`UNMARKED`] until set).
+    rendered_at_ns: AtomicU64,
+}
+
+/// `rendered_at_ns` value before the output marks the signal.
+const UNMARKED: u64 = u64::MAX;
+
+/// Longest sleep between checks in [`DrainSignal::wait_rendered`].
+const DRAIN_POLL: Duration = Duration::from_millis(50);
+
+impl DrainSignal {
+    /// Create an unmarked signal for an output running at `sample_rate`.
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate: sample_rate.max(1),
+            base: Instant::now(),
+            rendered_at_ns: AtomicU64::new(UNMARKED),
+        }
+    }
+
+    /// Record that the final frames sit in a `buffer_frames` device buffer that starts playing
+    /// after `latency`.
+    ///
+    /// Called once per stream from the output callback; only stores an atomic.
+    fn mark(&self, buffer_frames: usize, latency: Duration) {
+        let buffer = Duration::from_secs_f64(buffer_frames as f64 / f64::from(self.sample_rate));
+        let at = self.base.elapsed() + latency + buffer;
+        let at_ns = u64::try_from(at.as_nanos()).unwrap_or(UNMARKED - 1);
+        self.rendered_at_ns
+            .store(at_ns.min(UNMARKED - 1), Ordering::Release);
+    }
+
+    /// Forget a previous mark before a new stream starts rendering.
+    pub fn reset(&self) {
+        self.rendered_at_ns.store(UNMARKED, Ordering::Release);
+    }
+
+    /// Block until the final frames have been rendered, `stop` returns true, or `timeout`
+    /// passes without the output marking the signal.
+    ///
+    /// `stop` and the mark are polled at least every 50ms. Returns `true` once the audio has
+    /// been rendered.
+    pub fn wait_rendered<F>(&self, timeout: Duration, mut stop: F) -> bool
+    where
+        F: FnMut() -> bool,
+    {
+        let deadline = self.base.elapsed() + timeout;
+        loop {
+            if stop() {
+                return false;
+            }
+            let now = self.base.elapsed();
+            let until = match self.rendered_at_ns.load(Ordering::Acquire) {
+                UNMARKED if now >= deadline => return false,
+                UNMARKED => deadline,
+                at_ns => {
+                    let at = Duration::from_nanos(at_ns);
+                    if now >= at {
+                        return true;
+                    }
+                    at
+                }
+            };
+            std::thread::sleep((until - now).min(DRAIN_POLL));
+        }
+    }
+}
+
+/// Largest integer PCM width the `f32` queue carries exactly.
+const EXACT_QUEUE_BITS: u16 = 24;
+
+/// Whether `source_bits`-bit integer PCM passes through the `f32` queue and the conversion to
+/// the output sample `format` (a name such as `I16`, `I24` or `F32`) unchanged.
+///
+/// `None` (lossy or float sources) is never exact.
+pub fn bit_exact_format(source_bits: Option<u16>, format: &str) -> bool {
+    let Some(bits) = source_bits else {
+        return false;
+    };
+    let format_bits = match format.to_ascii_uppercase().as_str() {
+        "I8" | "U8" => 8,
+        "I16" | "U16" => 16,
+        "I24" => 24,
+        "I32" | "U32" | "I64" | "U64" | "F32" | "F64" => EXACT_QUEUE_BITS,
+        _ => 0,
+    };
+    bits > 0 && bits <= format_bits.min(EXACT_QUEUE_BITS)
+}
+
+/// Convert a gain in dB to a linear sample multiplier.
+pub fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
 }
 
 /// Build a CPAL output stream that plays audio from `dstq`.
@@ -56,7 +203,7 @@ pub struct PlaybackConfig {
 ///
 /// ## Real-time constraints
 /// The callback never blocks on locks longer than necessary and never waits on a condition variable.
-/// Underruns are filled with zeros (silence).
+/// Underruns are filled with zeros (silence), or faded per `cfg.underrun_fade_frames`.
 ///
 /// The returned stream is **not** started; call `stream.play()` to begin playback.
 pub fn build_output_stream(
@@ -75,6 +222,11 @@ pub fn build_output_stream(
     }
 }
 
+/// Opens a non-CPAL output on `device` that drains the queue through an [`OutputFiller`]
+/// (e.g. a WASAPI exclusive-mode renderer). The returned handle keeps it running until dropped.
+pub type OutputOpener =
+    Box<dyn Fn(&cpal::Device, &Arc<SharedAudio>, PlaybackConfig) -> Result<Box<dyn Send>> + Send>;
+
 /// Type-specialized stream builder for CPAL sample formats.
 ///
 /// This sets up a callback that drains `dstq` in bursts (up to `refill_max_frames`) and writes
@@ -88,119 +240,330 @@ fn build_stream<T>(
 where
     T: cpal::Sample + cpal::SizedSample + cpal::FromSample<f32>,
 {
-    let channels_out = config.channels as usize;
-
-    let refill_max_frames = cfg.refill_max_frames.max(1);
-    let state = Arc::new(Mutex::new(PlaybackState {
-        pos: 0,
-        src_channels: dstq.channels(),
-        src: Vec::new(),
-    }));
-    let dstq_cb = dstq.clone();
-    let paused_flag = cfg.paused.clone();
-    let played_frames = cfg.played_frames.clone();
-    let underrun_frames = cfg.underrun_frames.clone();
-    let underrun_events = cfg.underrun_events.clone();
-    let buffered_frames = cfg.buffered_frames.clone();
-    let volume_percent = cfg.volume_percent.clone();
-    let muted = cfg.muted.clone();
-
     let cancel_on_error = cfg.cancel_on_error.clone();
-    let err_fn = move |err| {
+    let device_lost = cfg.device_lost.clone();
+    let underrun_events = cfg.underrun_events.clone();
+    let err_fn = move |err: cpal::StreamError| {
+        // Backend-reported xruns (ALSA, JACK) count as underruns and keep the stream going.
+        if matches!(err, cpal::StreamError::BufferUnderrun) {
+            tracing::debug!("stream xrun: {err}");
+            if let Some(events) = &underrun_events {
+                events.fetch_add(1, Ordering::Relaxed);
+            }
+            return;
+        }
         tracing::warn!("stream error: {err}");
+        if is_device_lost_error(&err)
+            && let Some(flag) = &device_lost
+        {
+            flag.store(true, Ordering::Relaxed);
+            return;
+        }
         if let Some(flag) = &cancel_on_error {
             flag.store(true, Ordering::Relaxed);
         }
     };
 
-    let state_cb = state.clone();
+    let mut filler = OutputFiller::new(dstq, config.channels as usize, &cfg);
     let stream = device.build_output_stream(
         config,
-        move |data: &mut [T], _| {
-            if let Some(p) = &paused_flag {
-                if p.load(Ordering::Relaxed) {
-                    if let Some(counter) = &buffered_frames {
-                        counter.store(dstq_cb.len_frames() as u64, Ordering::Relaxed);
-                    }
-                    data.fill(<T as cpal::Sample>::from_sample::<f32>(0.0));
-                    return;
+        move |data: &mut [T], info: &cpal::OutputCallbackInfo| {
+            let ts = info.timestamp();
+            filler.set_latency(ts.playback.duration_since(&ts.callback).unwrap_or_default());
+            filler.fill(data)
+        },
+        err_fn,
+        None,
+    );
+
+    match stream {
+        Ok(stream) => {
+            crate::device::wire_output_ports(device, config.channels);
+            Ok(stream)
+        }
+        Err(cpal::BuildStreamError::DeviceNotAvailable) => {
+            Err(crate::device::unavailable_error(device))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Real-time buffer filler shared by the CPAL callback and custom outputs.
+///
+/// Each [`fill`](Self::fill) call drains `dstq` in bursts (up to `refill_max_frames`), applies
+/// channel mapping, volume/mute and pause, and updates the playback counters.
+pub struct OutputFiller {
+    channels_out: usize,
+    refill_max_frames: usize,
+    state: PlaybackState,
+    levels: Option<LevelBlock>,
+    conceal: Option<Concealer>,
+    /// Pre-roll silence still to play.
+    preroll_left: usize,
+    /// DoP idle generator used for silence when `cfg.dop_idle` is set.
+    idle: Option<DopIdle>,
+    /// Delay until the buffer being filled reaches the device (see [`Self::set_latency`]).
+    latency: Duration,
+    /// Whether `cfg.drained` has been marked for this stream.
+    drain_marked: bool,
+    dstq: Arc<SharedAudio>,
+    cfg: PlaybackConfig,
+}
+
+impl OutputFiller {
+    /// Create a filler writing `channels_out` interleaved channels from `dstq`.
+    pub fn new(dstq: &Arc<SharedAudio>, channels_out: usize, cfg: &PlaybackConfig) -> Self {
+        let refill_max_frames = cfg.refill_max_frames.max(1);
+        Self {
+            channels_out: channels_out.max(1),
+            refill_max_frames,
+            state: PlaybackState {
+                pos: 0,
+                src_channels: dstq.channels(),
+                // Allocated up front so the output callback never allocates.
+                src: Vec::with_capacity(refill_max_frames * dstq.channels()),
+                mix: MixMatrix::new(dstq.channels(), channels_out.max(1), &cfg.channel_mix),
+            },
+            levels: cfg.meter.as_ref().map(|meter| meter.block()),
+            conceal: Concealer::new(channels_out.max(1), cfg.underrun_fade_frames),
+            preroll_left: cfg.preroll_frames,
+            idle: cfg.dop_idle.then(DopIdle::default),
+            latency: Duration::ZERO,
+            drain_marked: false,
+            dstq: dstq.clone(),
+            cfg: cfg.clone(),
+        }
+    }
+
+    /// Delay between the next [`fill`](Self::fill) and its buffer reaching the device, as
+    /// reported by the backend (counted when marking [`PlaybackConfig::drained`]).
+    pub fn set_latency(&mut self, latency: Duration) {
+        self.latency = latency;
+    }
+
+    /// Fill an interleaved device buffer; gaps are written as silence.
+    pub fn fill<T>(&mut self, data: &mut [T])
+    where
+        T: cpal::Sample + cpal::FromSample<f32>,
+    {
+        let channels_out = self.channels_out;
+        let frames = data.len() / channels_out;
+        if self.preroll_left > 0 {
+            let silent = self.preroll_left.min(frames);
+            self.preroll_left -= silent;
+            let (head, rest) = data.split_at_mut(silent * channels_out);
+            write_silence(&mut self.idle, head, channels_out);
+            if !rest.is_empty() {
+                self.fill(rest);
+            }
+            return;
+        }
+        let cfg = &self.cfg;
+        let st = &mut self.state;
+        if let Some(p) = &cfg.paused {
+            if p.load(Ordering::Relaxed) {
+                if let Some(counter) = &cfg.buffered_frames {
+                    counter.store(self.dstq.len_frames() as u64, Ordering::Relaxed);
                 }
+                if let (Some(meter), Some(block)) = (&cfg.meter, self.levels.as_mut()) {
+                    meter.publish(block, frames);
+                }
+                write_silence(&mut self.idle, data, channels_out);
+                return;
             }
-
-            let mut st = state_cb.lock().unwrap();
-            let muted_now = muted
+        }
+        let muted_now = cfg
+            .muted
+            .as_ref()
+            .map(|flag| flag.load(Ordering::Relaxed))
+            .unwrap_or(false);
+        let gain = if muted_now {
+            0.0
+        } else {
+            (cfg.volume_percent
                 .as_ref()
-                .map(|flag| flag.load(Ordering::Relaxed))
-                .unwrap_or(false);
-            let gain = if muted_now {
-                0.0
-            } else {
-                (volume_percent
-                    .as_ref()
-                    .map(|v| v.load(Ordering::Relaxed))
-                    .unwrap_or(100) as f32
-                    / 100.0)
-                    .clamp(0.0, 1.0)
-            };
+                .map(|v| v.load(Ordering::Relaxed))
+                .unwrap_or(100) as f32
+                / 100.0)
+                .clamp(0.0, 1.0)
+        };
+
+        // Nothing alters the signal: copy source samples as-is (bit-perfect for integer PCM).
+        let passthrough = gain == 1.0
+            && cfg.pre_gain == 1.0
+            && st.src_channels == channels_out
+            && self.conceal.as_ref().is_none_or(|c| c.level == 1.0);
 
-            let frames = data.len() / channels_out;
-            let mut filled_frames = 0usize;
+        let mut filled_frames = 0usize;
+        let mut recorded = cfg.record.as_ref().map(|_| Vec::with_capacity(data.len()));
+        let mut mirrored = cfg.mirror.as_ref().map(|_| Vec::with_capacity(data.len()));
 
-            for frame in 0..frames {
-                if st.pos >= st.src.len() {
-                    st.pos = 0;
+        for frame in 0..frames {
+            if st.pos >= st.src.len() {
+                st.pos = 0;
+                // Reuse the refill buffer: its capacity survives `truncate`/`clear`.
+                st.src.resize(self.refill_max_frames * st.src_channels, 0.0);
+                if let Some(samples) = self.dstq.copy_to_slice(
+                    PopStrategy::NonBlocking {
+                        max_frames: self.refill_max_frames,
+                    },
+                    &mut st.src,
+                ) {
+                    st.src.truncate(samples);
+                } else {
                     st.src.clear();
-                    if let Some(v) = dstq_cb.pop(PopStrategy::NonBlocking {
-                        max_frames: refill_max_frames,
-                    }) {
-                        st.src = v;
-                    } else {
-                        // No more audio ready; fill the rest with silence.
-                        if let Some(events) = &underrun_events {
-                            let prev = events.fetch_add(1, Ordering::Relaxed);
-                            if prev == 0 {
-                                let frames = dstq_cb.len_frames();
-                                let done = dstq_cb.is_done();
-                                tracing::warn!(
-                                    queued_frames = frames,
-                                    done,
-                                    "audio underrun: queue empty in output callback"
-                                );
-                            }
-                        }
-                        if let Some(frames_counter) = &underrun_frames {
-                            let remaining = frames.saturating_sub(frame);
-                            frames_counter.fetch_add(remaining as u64, Ordering::Relaxed);
+                    // No more audio ready; fill the rest with silence.
+                    if let Some(events) = &cfg.underrun_events {
+                        let prev = events.fetch_add(1, Ordering::Relaxed);
+                        if prev == 0 {
+                            let frames = self.dstq.len_frames();
+                            let done = self.dstq.is_done();
+                            tracing::warn!(
+                                queued_frames = frames,
+                                done,
+                                "audio underrun: queue empty in output callback"
+                            );
                         }
-                        for idx in (frame * channels_out)..data.len() {
-                            data[idx] = <T as cpal::Sample>::from_sample::<f32>(0.0);
+                    }
+                    if let Some(frames_counter) = &cfg.underrun_frames {
+                        let remaining = frames.saturating_sub(frame);
+                        frames_counter.fetch_add(remaining as u64, Ordering::Relaxed);
+                    }
+                    let gap = &mut data[frame * channels_out..];
+                    match self.conceal.as_mut() {
+                        Some(conceal) => {
+                            for out in gap.chunks_mut(channels_out) {
+                                let hold = conceal.gap_frame();
+                                for (sample, last) in out.iter_mut().zip(&conceal.last) {
+                                    *sample = <T as cpal::Sample>::from_sample::<f32>(last * hold);
+                                }
+                            }
                         }
-                        break;
+                        None => write_silence(&mut self.idle, gap, channels_out),
                     }
+                    break;
                 }
-                for ch in 0..channels_out {
-                    let sample_f32 = next_sample_mapped_from_vec(&mut *st, channels_out, ch) * gain;
-                    data[frame * channels_out + ch] =
-                        <T as cpal::Sample>::from_sample::<f32>(sample_f32);
-                }
-                filled_frames += 1;
             }
-
-            if let Some(counter) = &played_frames {
-                if filled_frames > 0 {
-                    counter.fetch_add(filled_frames as u64, Ordering::Relaxed);
+            let fade_in = self.conceal.as_mut().map_or(1.0, Concealer::audio_frame);
+            for ch in 0..channels_out {
+                let (mapped, sample_f32) = if passthrough {
+                    let sample = next_sample_direct(st, ch);
+                    (sample, sample)
+                } else {
+                    let mapped = next_sample_mapped_from_vec(st, channels_out, ch) * cfg.pre_gain;
+                    (mapped, mapped * gain)
+                };
+                if let Some(buf) = mirrored.as_mut() {
+                    buf.push(mapped);
+                }
+                let out = sample_f32 * fade_in;
+                data[frame * channels_out + ch] = <T as cpal::Sample>::from_sample::<f32>(out);
+                if let Some(conceal) = self.conceal.as_mut() {
+                    conceal.last[ch] = out;
+                }
+                if let Some(buf) = recorded.as_mut() {
+                    buf.push(sample_f32);
+                }
+                if let Some(block) = self.levels.as_mut() {
+                    block.add(ch, out);
                 }
             }
+            filled_frames += 1;
+        }
 
-            if let Some(counter) = &buffered_frames {
-                counter.store(dstq_cb.len_frames() as u64, Ordering::Relaxed);
+        if let (Some(meter), Some(block)) = (&cfg.meter, self.levels.as_mut()) {
+            meter.publish(block, frames);
+        }
+        if let (Some(tap), Some(buf)) = (&cfg.record, recorded)
+            && !buf.is_empty()
+        {
+            tap.push(buf);
+        }
+        if let (Some(tap), Some(buf)) = (&cfg.mirror, mirrored)
+            && !buf.is_empty()
+        {
+            tap.push(&buf);
+        }
+
+        if let Some(counter) = &cfg.played_frames {
+            if filled_frames > 0 {
+                counter.fetch_add(filled_frames as u64, Ordering::Relaxed);
             }
-        },
-        err_fn,
-        None,
-    )?;
+        }
+        if let Some(flag) = &cfg.bit_perfect
+            && filled_frames > 0
+        {
+            flag.store(passthrough, Ordering::Relaxed);
+        }
+
+        if let Some(counter) = &cfg.buffered_frames {
+            counter.store(self.dstq.len_frames() as u64, Ordering::Relaxed);
+        }
+
+        // The last queued frame is in this buffer (or an earlier one): the tail renders once
+        // this buffer has played.
+        if let Some(drained) = &cfg.drained
+            && !self.drain_marked
+            && st.pos >= st.src.len()
+            && self.dstq.is_done()
+            && self.dstq.len_frames() == 0
+        {
+            self.drain_marked = true;
+            drained.mark(frames, self.latency);
+        }
+    }
+}
+
+/// Fill `out` with silence: DoP idle frames when `idle` is set, zeros otherwise.
+fn write_silence<T>(idle: &mut Option<DopIdle>, out: &mut [T], channels: usize)
+where
+    T: cpal::Sample + cpal::FromSample<f32>,
+{
+    match idle {
+        Some(idle) => idle.fill(out, channels),
+        None => out.fill(<T as cpal::Sample>::from_sample::<f32>(0.0)),
+    }
+}
 
-    Ok(stream)
+/// Whether a stream error means the output device went away (unplugged/invalidated).
+fn is_device_lost_error(err: &cpal::StreamError) -> bool {
+    matches!(
+        err,
+        cpal::StreamError::DeviceNotAvailable | cpal::StreamError::StreamInvalidated
+    )
+}
+
+/// Underrun fade state for [`OutputFiller`] (see [`PlaybackConfig::underrun_fade_frames`]).
+struct Concealer {
+    /// Gain change per frame.
+    step: f32,
+    /// Current fade gain: falls towards `0` through a gap and climbs back to `1` once audio
+    /// returns, so a gap shorter than the fade never steps.
+    level: f32,
+    /// Last frame written to the device, held through a gap.
+    last: Vec<f32>,
+}
+
+impl Concealer {
+    fn new(channels: usize, fade_frames: usize) -> Option<Self> {
+        (fade_frames > 0).then(|| Self {
+            step: 1.0 / fade_frames as f32,
+            level: 1.0,
+            last: vec![0.0; channels],
+        })
+    }
+
+    /// Gain applied to the held frame for the next frame of a gap.
+    fn gap_frame(&mut self) -> f32 {
+        self.level = (self.level - self.step).max(0.0);
+        self.level
+    }
+
+    /// Gain applied to the next frame of real audio.
+    fn audio_frame(&mut self) -> f32 {
+        self.level = (self.level + self.step).min(1.0);
+        self.level
+    }
 }
 
 /// Local playback buffer state for the CPAL callback.
@@ -211,16 +574,10 @@ struct PlaybackState {
     pos: usize,
     src_channels: usize,
     src: Vec<f32>,
+    mix: MixMatrix,
 }
 
-/// Fetch the next output sample after applying basic channel mapping.
-///
-/// Mapping rules:
-/// - mono → stereo: duplicate channel 0
-/// - stereo → mono: average L/R
-/// - stereo → stereo: pass-through
-/// - other layouts: best-effort “clamp to available channels”
-/// Read one output sample for `dst_ch`, applying a simple channel mapping.
+/// Read one output sample for `dst_ch`, mixing the current source frame through `st.mix`.
 ///
 /// `st.pos` advances once per destination frame (after the last channel).
 fn next_sample_mapped_from_vec(st: &mut PlaybackState, dst_channels: usize, dst_ch: usize) -> f32 {
@@ -228,22 +585,8 @@ fn next_sample_mapped_from_vec(st: &mut PlaybackState, dst_channels: usize, dst_
         return 0.0;
     }
 
-    let frame_start = st.pos;
-    let get_src = |ch: usize, st: &PlaybackState| -> f32 {
-        if ch < st.src_channels && frame_start + ch < st.src.len() {
-            st.src[frame_start + ch]
-        } else {
-            0.0
-        }
-    };
-
-    let out = match (st.src_channels, dst_channels) {
-        (1, 1) => get_src(0, st),
-        (2, 2) => get_src(dst_ch.min(1), st),
-        (2, 1) => 0.5 * (get_src(0, st) + get_src(1, st)),
-        (1, 2) => get_src(0, st),
-        _ => get_src(dst_ch.min(st.src_channels.saturating_sub(1)), st),
-    };
+    let frame_end = (st.pos + st.src_channels).min(st.src.len());
+    let out = st.mix.sample(&st.src[st.pos..frame_end], dst_ch);
 
     if dst_ch + 1 == dst_channels {
         st.pos += st.src_channels;
@@ -251,17 +594,198 @@ fn next_sample_mapped_from_vec(st: &mut PlaybackState, dst_channels: usize, dst_
     out
 }
 
+/// Read one output sample for `dst_ch` straight from the current source frame (source and
+/// output channel counts match).
+fn next_sample_direct(st: &mut PlaybackState, dst_ch: usize) -> f32 {
+    let sample = st.src.get(st.pos + dst_ch).copied().unwrap_or(0.0);
+    if dst_ch + 1 == st.src_channels {
+        st.pos += st.src_channels;
+    }
+    sample
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn test_state(src_channels: usize, dst_channels: usize, src: Vec<f32>) -> PlaybackState {
+        PlaybackState {
+            pos: 0,
+            src_channels,
+            src,
+            mix: MixMatrix::new(src_channels, dst_channels, &ChannelMixConfig::default()),
+        }
+    }
+
+    fn filler_cfg(underrun_fade_frames: usize) -> PlaybackConfig {
+        PlaybackConfig {
+            refill_max_frames: 64,
+            paused: None,
+            played_frames: None,
+            underrun_frames: None,
+            underrun_events: None,
+            buffered_frames: None,
+            cancel_on_error: None,
+            device_lost: None,
+            volume_percent: None,
+            muted: None,
+            record: None,
+            mirror: None,
+            pre_gain: 1.0,
+            channel_mix: ChannelMixConfig::default(),
+            meter: None,
+            underrun_fade_frames,
+            bit_perfect: None,
+            preroll_frames: 0,
+            dop_idle: false,
+            drained: None,
+        }
+    }
+
+    #[test]
+    fn preroll_plays_silence_before_draining_queue() {
+        let dstq = Arc::new(SharedAudio::new(1, 64));
+        let mut cfg = filler_cfg(0);
+        cfg.preroll_frames = 3;
+        let played = Arc::new(AtomicU64::new(0));
+        cfg.played_frames = Some(played.clone());
+        let mut filler = OutputFiller::new(&dstq, 1, &cfg);
+        dstq.push_interleaved_blocking(&[0.5; 8]);
+
+        let mut out = [1.0f32; 2];
+        filler.fill(&mut out);
+        assert_eq!(out, [0.0, 0.0]);
+        assert_eq!(dstq.len_frames(), 8);
+        filler.fill(&mut out);
+        assert_eq!(out, [0.0, 0.5]);
+        assert_eq!(played.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn filler_marks_drained_once_last_frame_is_written() {
+        let dstq = Arc::new(SharedAudio::new(1, 64));
+        let drained = Arc::new(DrainSignal::new(1000));
+        let mut cfg = filler_cfg(0);
+        cfg.drained = Some(drained.clone());
+        let mut filler = OutputFiller::new(&dstq, 1, &cfg);
+        dstq.push_interleaved_blocking(&[0.5; 6]);
+
+        let mut out = [0.0f32; 4];
+        filler.fill(&mut out);
+        dstq.close();
+        assert!(!drained.wait_rendered(Duration::ZERO, || false));
+        filler.set_latency(Duration::from_millis(20));
+        filler.fill(&mut out);
+        assert_eq!(out, [0.5, 0.5, 0.0, 0.0]);
+
+        let started = Instant::now();
+        assert!(drained.wait_rendered(Duration::from_secs(1), || false));
+        // 4 frames at 1 kHz plus the reported latency.
+        assert!(started.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn drain_wait_stops_on_predicate_and_resets() {
+        let drained = DrainSignal::new(48_000);
+        drained.mark(0, Duration::from_secs(10));
+        assert!(!drained.wait_rendered(Duration::from_secs(1), || true));
+        drained.reset();
+        assert!(!drained.wait_rendered(Duration::from_millis(10), || false));
+        drained.mark(0, Duration::ZERO);
+        assert!(drained.wait_rendered(Duration::from_millis(10), || false));
+    }
+
+    #[test]
+    fn dop_idle_fills_preroll_and_underruns_with_alternating_markers() {
+        let dstq = Arc::new(SharedAudio::new(2, 64));
+        let mut cfg = filler_cfg(0);
+        cfg.preroll_frames = 1;
+        cfg.dop_idle = true;
+        let mut filler = OutputFiller::new(&dstq, 2, &cfg);
+
+        let mut out = [0i32; 6];
+        filler.fill(&mut out);
+        let markers: Vec<i32> = out.iter().map(|s| (s >> 24) & 0xFF).collect();
+        assert_eq!(markers, [0x05, 0x05, 0xFA, 0xFA, 0x05, 0x05]);
+        assert!(out.iter().all(|s| (s >> 8) & 0xFFFF == 0x6969));
+    }
+
+    #[test]
+    fn bit_exact_format_limits_to_queue_and_device_width() {
+        assert!(bit_exact_format(Some(16), "I16"));
+        assert!(bit_exact_format(Some(24), "I32"));
+        assert!(bit_exact_format(Some(24), "F32"));
+        assert!(!bit_exact_format(Some(24), "I16"));
+        assert!(!bit_exact_format(Some(32), "I32"));
+        assert!(!bit_exact_format(None, "F32"));
+    }
+
+    #[test]
+    fn passthrough_reports_bit_perfect_until_volume_changes() {
+        let dstq = Arc::new(SharedAudio::new(2, 64));
+        let volume = Arc::new(AtomicU8::new(100));
+        let flag = Arc::new(AtomicBool::new(false));
+        let mut cfg = filler_cfg(0);
+        cfg.volume_percent = Some(volume.clone());
+        cfg.bit_perfect = Some(flag.clone());
+        let mut filler = OutputFiller::new(&dstq, 2, &cfg);
+
+        // 24-bit extremes survive the trip to an i32 device untouched.
+        let max = 8_388_607i32;
+        let src = [max as f32 / 8_388_608.0, -1.0];
+        dstq.push_interleaved_blocking(&src);
+        let mut out = [0i32; 2];
+        filler.fill(&mut out);
+        assert_eq!(out, [max << 8, i32::MIN]);
+        assert!(flag.load(Ordering::Relaxed));
+
+        volume.store(50, Ordering::Relaxed);
+        dstq.push_interleaved_blocking(&src);
+        filler.fill(&mut out);
+        assert!(!flag.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn underrun_fades_held_frame_and_fades_back_in() {
+        let dstq = Arc::new(SharedAudio::new(1, 64));
+        let mut filler = OutputFiller::new(&dstq, 1, &filler_cfg(4));
+        let mut out = [0.0f32; 8];
+
+        dstq.push_interleaved_blocking(&[0.8, 0.8]);
+        filler.fill(&mut out);
+        let expected = [0.8, 0.8, 0.6, 0.4, 0.2, 0.0, 0.0, 0.0];
+        for (got, want) in out.iter().zip(expected) {
+            assert!((got - want).abs() < 1e-6, "{out:?}");
+        }
+
+        dstq.push_interleaved_blocking(&[0.8; 8]);
+        filler.fill(&mut out);
+        let expected = [0.2, 0.4, 0.6, 0.8, 0.8, 0.8, 0.8, 0.8];
+        for (got, want) in out.iter().zip(expected) {
+            assert!((got - want).abs() < 1e-6, "{out:?}");
+        }
+    }
+
+    #[test]
+    fn underrun_without_fade_writes_zeros() {
+        let dstq = Arc::new(SharedAudio::new(2, 64));
+        let mut filler = OutputFiller::new(&dstq, 2, &filler_cfg(0));
+        let mut out = [1.0f32; 8];
+        dstq.push_interleaved_blocking(&[0.5, -0.5]);
+        filler.fill(&mut out);
+        assert_eq!(out, [0.5, -0.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn db_to_gain_matches_reference_points() {
+        assert_eq!(db_to_gain(0.0), 1.0);
+        assert!((db_to_gain(-6.0) - 0.501).abs() < 1e-3);
+        assert!((db_to_gain(20.0) - 10.0).abs() < 1e-4);
+    }
+
     #[test]
     fn next_sample_mapped_from_vec_mono_to_stereo() {
-        let mut st = PlaybackState {
-            pos: 0,
-            src_channels: 1,
-            src: vec![0.25],
-        };
+        let mut st = test_state(1, 2, vec![0.25]);
         let left = next_sample_mapped_from_vec(&mut st, 2, 0);
         let right = next_sample_mapped_from_vec(&mut st, 2, 1);
         assert_eq!(left, 0.25);
@@ -271,11 +795,7 @@ mod tests {
 
     #[test]
     fn next_sample_mapped_from_vec_stereo_to_mono() {
-        let mut st = PlaybackState {
-            pos: 0,
-            src_channels: 2,
-            src: vec![0.5, -0.5],
-        };
+        let mut st = test_state(2, 1, vec![0.5, -0.5]);
         let mono = next_sample_mapped_from_vec(&mut st, 1, 0);
         assert_eq!(mono, 0.0);
         assert_eq!(st.pos, 2);
@@ -283,11 +803,7 @@ mod tests {
 
     #[test]
     fn next_sample_mapped_from_vec_passthrough() {
-        let mut st = PlaybackState {
-            pos: 0,
-            src_channels: 2,
-            src: vec![0.1, 0.2],
-        };
+        let mut st = test_state(2, 2, vec![0.1, 0.2]);
         let left = next_sample_mapped_from_vec(&mut st, 2, 0);
         let right = next_sample_mapped_from_vec(&mut st, 2, 1);
         assert_eq!(left, 0.1);
@@ -297,23 +813,22 @@ mod tests {
 
     #[test]
     fn next_sample_mapped_from_vec_clamps_missing_channels() {
-        let mut st = PlaybackState {
-            pos: 0,
-            src_channels: 3,
-            src: vec![0.1, 0.2, 0.3],
-        };
+        let mut st = test_state(3, 5, vec![0.1, 0.2, 0.3]);
         let sample = next_sample_mapped_from_vec(&mut st, 5, 4);
         assert_eq!(sample, 0.3);
         assert_eq!(st.pos, 3);
     }
 
+    #[test]
+    fn is_device_lost_error_matches_disconnects_only() {
+        assert!(is_device_lost_error(&cpal::StreamError::DeviceNotAvailable));
+        assert!(is_device_lost_error(&cpal::StreamError::StreamInvalidated));
+        assert!(!is_device_lost_error(&cpal::StreamError::BufferUnderrun));
+    }
+
     #[test]
     fn next_sample_mapped_from_vec_returns_zero_when_empty() {
-        let mut st = PlaybackState {
-            pos: 0,
-            src_channels: 2,
-            src: Vec::new(),
-        };
+        let mut st = test_state(2, 2, Vec::new());
         let sample = next_sample_mapped_from_vec(&mut st, 2, 0);
         assert_eq!(sample, 0.0);
         assert_eq!(st.pos, 0);
@@ -6,20 +6,31 @@
 //! - CPAL callback drains queue (non-blocking)
 //!
 //! The API is designed to make shutdown deterministic (`close()` + draining semantics)
-//! while keeping the playback callback real-time friendly.
+//! while keeping the playback callback real-time friendly: the non-blocking paths never wait
+//! on a lock another thread can hold across a blocking operation.
 
-use std::collections::VecDeque;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Condvar, Mutex};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::cell::UnsafeCell;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering, fence};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Longest a parked thread sleeps before re-checking the queue.
+///
+/// The output callback wakes parked threads without taking the park lock, so a wakeup can
+/// slip in between a waiter's check and its wait; this bounds the resulting delay.
+const PARK_TIMEOUT: Duration = Duration::from_millis(5);
 
 /// Thread-safe bounded queue for interleaved `f32` audio samples.
 ///
 /// ## Design
-/// - **Multiple producers / multiple consumers**: safe to call from many threads.
+/// - Samples live in a lock-free single-producer/single-consumer ring; the output callback
+///   reads it without blocking.
+/// - **Multiple producers / multiple consumers** remain safe: producers are serialized by one
+///   mutex and consumers (and [`flush`](Self::flush)) by another. The non-blocking paths only `try_lock` theirs and treat
+///   contention as "nothing available" / "no room".
 /// - **Bounded** by `max_buffered_samples` to cap memory and latency.
-/// - Uses a single [`Condvar`] as a general “state changed” signal.
-/// - A `done` flag is stored *under the same mutex* as the queue to avoid races.
+/// - Blocking calls park on a [`Condvar`] that serves as a general “state changed” signal.
 ///
 /// ## Data model
 /// Samples are stored **interleaved**:
@@ -28,15 +39,101 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 /// The `channels` count is fixed for the lifetime of the queue.
 pub struct SharedAudio {
     channels: usize,
-    inner: Mutex<SharedInner>,
+    ring: Ring,
+    /// Serializes producers; held for the duration of a push.
+    producer: Mutex<()>,
+    /// Serializes consumers; never held while parked.
+    consumer: Mutex<()>,
+    done: AtomicBool,
+    /// Bumped by [`SharedAudio::flush`] so in-flight blocking pushes can tell they are stale.
+    flushes: AtomicU64,
+    park: Mutex<()>,
     cv: Condvar,
+    /// Threads currently parked on `cv`; wakers skip the notify when zero.
+    parked: AtomicUsize,
     max_buffered_samples: usize,
-    low_watermark_ms: std::sync::atomic::AtomicU64,
+    low_watermark_ms: AtomicU64,
+}
+
+/// Fixed-capacity single-producer/single-consumer sample ring.
+///
+/// `head` and `tail` are monotonically increasing sample counts: the producer only advances
+/// `tail` and the consumer only advances `head`, so neither side needs a lock. Methods take
+/// the caller's producer/consumer guard to document which side they run on.
+struct Ring {
+    buf: Box<[UnsafeCell<f32>]>,
+    head: AtomicUsize,
+    tail: AtomicUsize,
 }
 
-struct SharedInner {
-    queue: VecDeque<f32>,
-    done: bool,
+// SAFETY: the consumer side only reads slots in `head..tail` and the producer only writes
+// slots outside it; `SharedAudio`'s producer/consumer locks guarantee at most one of each.
+unsafe impl Sync for Ring {}
+
+impl Ring {
+    fn new(capacity: usize) -> Self {
+        Self {
+            buf: (0..capacity).map(|_| UnsafeCell::new(0.0)).collect(),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Buffered samples (best-effort snapshot from any thread).
+    fn len(&self) -> usize {
+        // `head` first: `tail` only grows, so the difference cannot underflow.
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        tail.wrapping_sub(head).min(self.capacity())
+    }
+
+    fn base(&self) -> *mut f32 {
+        UnsafeCell::raw_get(self.buf.as_ptr())
+    }
+
+    /// Copy as many of `samples` as fit; returns the number written.
+    fn push(&self, _producer: &MutexGuard<'_, ()>, samples: &[f32]) -> usize {
+        let cap = self.capacity();
+        let tail = self.tail.load(Ordering::Relaxed);
+        let head = self.head.load(Ordering::Acquire);
+        let n = samples.len().min(cap - tail.wrapping_sub(head));
+        let start = tail % cap;
+        let first = n.min(cap - start);
+        // SAFETY: the `n` slots after `tail` are free (outside `head..tail`), so the consumer
+        // does not read them until `tail` is published below.
+        unsafe {
+            ptr::copy_nonoverlapping(samples.as_ptr(), self.base().add(start), first);
+            ptr::copy_nonoverlapping(samples.as_ptr().add(first), self.base(), n - first);
+        }
+        self.tail.store(tail.wrapping_add(n), Ordering::Release);
+        n
+    }
+
+    /// Move the oldest `out.len()` buffered samples (at most `len`) into `out`.
+    fn copy_out(&self, _consumer: &MutexGuard<'_, ()>, out: &mut [f32]) {
+        let cap = self.capacity();
+        let n = out.len();
+        let head = self.head.load(Ordering::Relaxed);
+        let start = head % cap;
+        let first = n.min(cap - start);
+        // SAFETY: the `n` slots after `head` are published (inside `head..tail`), and the
+        // producer does not overwrite them until `head` is advanced below.
+        unsafe {
+            ptr::copy_nonoverlapping(self.base().add(start), out.as_mut_ptr(), first);
+            ptr::copy_nonoverlapping(self.base(), out.as_mut_ptr().add(first), n - first);
+        }
+        self.head.store(head.wrapping_add(n), Ordering::Release);
+    }
+
+    /// Drop everything published so far (consumer side).
+    fn clear(&self, _consumer: &MutexGuard<'_, ()>) {
+        self.head
+            .store(self.tail.load(Ordering::Acquire), Ordering::Release);
+    }
 }
 
 /// Strategy for popping interleaved frames from the queue.
@@ -83,13 +180,16 @@ impl SharedAudio {
     pub fn new(channels: usize, max_buffered_samples: usize) -> Self {
         Self {
             channels,
-            inner: Mutex::new(SharedInner {
-                queue: VecDeque::new(),
-                done: false,
-            }),
+            ring: Ring::new(max_buffered_samples.max(channels).max(1)),
+            producer: Mutex::new(()),
+            consumer: Mutex::new(()),
+            done: AtomicBool::new(false),
+            flushes: AtomicU64::new(0),
+            park: Mutex::new(()),
             cv: Condvar::new(),
+            parked: AtomicUsize::new(0),
             max_buffered_samples,
-            low_watermark_ms: std::sync::atomic::AtomicU64::new(0),
+            low_watermark_ms: AtomicU64::new(0),
         }
     }
 
@@ -107,16 +207,14 @@ impl SharedAudio {
     ///
     /// This value can change immediately after the call returns.
     pub fn len_frames(&self) -> usize {
-        let g = self.inner.lock().unwrap();
-        g.queue.len() / self.channels
+        self.ring.len() / self.channels
     }
 
     /// Whether the queue has been closed by its producer.
     ///
     /// Closed queues may still contain buffered samples until drained.
     pub fn is_done(&self) -> bool {
-        let g = self.inner.lock().unwrap();
-        g.done
+        self.done.load(Ordering::Acquire)
     }
 
     /// Mark the queue as finished and wake all waiters.
@@ -127,128 +225,215 @@ impl SharedAudio {
     ///
     /// This is idempotent and safe to call multiple times.
     pub fn close(&self) {
-        let mut g = self.inner.lock().unwrap();
-        g.done = true;
-        drop(g);
-        self.cv.notify_all();
+        self.done.store(true, Ordering::Release);
+        self.wake();
+    }
+
+    /// Discard all buffered samples (e.g. after a seek) and wake all waiters.
+    ///
+    /// A blocking push in progress returns without queueing the rest of its samples; only a
+    /// chunk it is copying at that very moment can still land behind the flush.
+    pub fn flush(&self) {
+        // Bump first so a producer woken by the freed space sees it is stale.
+        self.flushes.fetch_add(1, Ordering::AcqRel);
+        let consumer = self.consumer.lock().unwrap();
+        self.ring.clear(&consumer);
+        drop(consumer);
+        self.wake();
     }
 
     /// Push interleaved samples into the queue, blocking when the queue is full.
     ///
     /// - Blocks until enough capacity is available, unless the queue is closed.
-    /// - If the queue is closed while waiting, this returns early and drops remaining samples.
+    /// - If the queue is closed or flushed while waiting, this returns early and drops
+    ///   remaining samples.
     ///
     /// Callers should push whole frames when possible, but this method accepts any slice length.
     pub fn push_interleaved_blocking(&self, samples: &[f32]) {
+        let producer = self.producer.lock().unwrap();
+        let flushes = self.flushes.load(Ordering::Acquire);
+        let stale = || self.is_done() || self.flushes.load(Ordering::Acquire) != flushes;
         let mut offset = 0;
 
         while offset < samples.len() {
-            let mut g = self.inner.lock().unwrap();
-
-            while g.queue.len() >= self.max_buffered_samples && !g.done {
-                g = self.cv.wait(g).unwrap();
-            }
-            if g.done {
+            if stale() {
                 return;
             }
-
-            let mut pushed_any = false;
-            while offset < samples.len() && g.queue.len() < self.max_buffered_samples {
-                g.queue.push_back(samples[offset]);
-                offset += 1;
-                pushed_any = true;
+            let pushed = self.ring.push(&producer, &samples[offset..]);
+            if pushed == 0 {
+                self.park(PARK_TIMEOUT, || {
+                    self.ring.len() < self.ring.capacity() || stale()
+                });
+                continue;
             }
+            offset += pushed;
+            self.wake();
+        }
+    }
 
-            drop(g);
-            if pushed_any {
-                self.cv.notify_all();
-            }
+    /// Push whole interleaved frames without waiting; frames that do not fit are dropped.
+    ///
+    /// Returns the number of frames queued (`0` once the queue is closed, or while another
+    /// producer is pushing). Safe to call from the output callback.
+    pub fn push_interleaved_nonblocking(&self, samples: &[f32]) -> usize {
+        let Ok(producer) = self.producer.try_lock() else {
+            return 0;
+        };
+        if self.is_done() {
+            return 0;
         }
+        let free_frames = (self.ring.capacity() - self.ring.len()) / self.channels;
+        let frames = (samples.len() / self.channels).min(free_frames);
+        self.ring
+            .push(&producer, &samples[..frames * self.channels]);
+        drop(producer);
+        if frames > 0 {
+            self.wake_from_callback();
+        }
+        frames
     }
 
     /// Pop interleaved frames using the requested strategy.
     ///
     /// Returns `None` when the queue is closed and no data can satisfy the request.
+    /// [`PopStrategy::NonBlocking`] also returns `None` while another consumer is popping.
     pub fn pop(&self, strategy: PopStrategy) -> Option<Vec<f32>> {
+        self.pop_with(strategy, usize::MAX, |consumer, samples| {
+            let mut out = vec![0.0; samples];
+            self.ring.copy_out(consumer, &mut out);
+            out
+        })
+    }
+
+    /// Like [`pop`](Self::pop), but copies into `out` instead of allocating; returns the number
+    /// of samples written to the front of `out`.
+    ///
+    /// At most `out.len()` samples (rounded down to whole frames) are taken. For
+    /// [`PopStrategy::BlockingExact`], `out` must hold the requested frames.
+    pub fn copy_to_slice(&self, strategy: PopStrategy, out: &mut [f32]) -> Option<usize> {
+        if let PopStrategy::BlockingExact { frames } = strategy {
+            assert!(
+                frames * self.channels <= out.len(),
+                "copy_to_slice: buffer too small for {frames} frames"
+            );
+        }
+        let max_samples = out.len() / self.channels * self.channels;
+        self.pop_with(strategy, max_samples, |consumer, samples| {
+            self.ring.copy_out(consumer, &mut out[..samples]);
+            samples
+        })
+    }
+
+    /// Shared pop logic: wait per `strategy`, then hand the number of samples to take (capped
+    /// at `max_samples`) to `take` while the consumer lock is held.
+    fn pop_with<R, F>(&self, strategy: PopStrategy, max_samples: usize, take: F) -> Option<R>
+    where
+        F: FnOnce(&MutexGuard<'_, ()>, usize) -> R,
+    {
+        let max_frames_fit = max_samples / self.channels;
         match strategy {
             PopStrategy::BlockingExact { frames } => {
                 let want = frames * self.channels;
-                let mut g = self.inner.lock().unwrap();
-
-                while g.queue.len() < want && !g.done {
-                    g = self.cv.wait(g).unwrap();
-                }
-
-                if g.queue.len() < want {
-                    return None;
+                loop {
+                    let consumer = self.consumer.lock().unwrap();
+                    // `done` before `len`: a producer closes only after its last push.
+                    let done = self.is_done();
+                    if self.ring.len() >= want {
+                        let out = take(&consumer, want);
+                        drop(consumer);
+                        self.wake();
+                        self.log_low_watermark();
+                        return Some(out);
+                    }
+                    drop(consumer);
+                    if done {
+                        return None;
+                    }
+                    self.park(PARK_TIMEOUT, || self.ring.len() >= want || self.is_done());
                 }
-
-                let mut out = Vec::with_capacity(want);
-                for _ in 0..want {
-                    out.push(g.queue.pop_front().unwrap_or(0.0));
-                }
-
-                drop(g);
-                self.cv.notify_all();
-                self.log_low_watermark();
-                Some(out)
             }
-            PopStrategy::BlockingUpTo { max_frames } => {
-                let mut g = self.inner.lock().unwrap();
-
-                while g.queue.is_empty() && !g.done {
-                    g = self.cv.wait(g).unwrap();
+            PopStrategy::BlockingUpTo { max_frames } => loop {
+                let consumer = self.consumer.lock().unwrap();
+                let done = self.is_done();
+                let len = self.ring.len();
+                if len > 0 {
+                    let take_frames = (len / self.channels).min(max_frames).min(max_frames_fit);
+                    let out = take(&consumer, take_frames * self.channels);
+                    drop(consumer);
+                    self.wake();
+                    self.log_low_watermark();
+                    return Some(out);
                 }
-
-                if g.queue.is_empty() && g.done {
+                drop(consumer);
+                if done {
                     return None;
                 }
-
-                let available_frames = g.queue.len() / self.channels;
-                let take_frames = available_frames.min(max_frames);
-                let take_samples = take_frames * self.channels;
-
-                let mut out = Vec::with_capacity(take_samples);
-                for _ in 0..take_samples {
-                    out.push(g.queue.pop_front().unwrap_or(0.0));
-                }
-
-                drop(g);
-                self.cv.notify_all();
-                self.log_low_watermark();
-                Some(out)
-            }
+                self.park(PARK_TIMEOUT, || self.ring.len() > 0 || self.is_done());
+            },
             PopStrategy::NonBlocking { max_frames } => {
-                let mut g = self.inner.lock().unwrap();
+                let Ok(consumer) = self.consumer.try_lock() else {
+                    return None;
+                };
 
-                let available_frames = g.queue.len() / self.channels;
-                let take_frames = available_frames.min(max_frames);
+                let available_frames = self.ring.len() / self.channels;
+                let take_frames = available_frames.min(max_frames).min(max_frames_fit);
                 let take_samples = take_frames * self.channels;
 
                 if take_samples == 0 {
                     return None;
                 }
 
-                let mut out = Vec::with_capacity(take_samples);
-                for _ in 0..take_samples {
-                    out.push(g.queue.pop_front().unwrap_or(0.0));
-                }
-
-                drop(g);
-                self.cv.notify_all();
+                let out = take(&consumer, take_samples);
+                drop(consumer);
+                self.wake_from_callback();
                 self.log_low_watermark();
                 Some(out)
             }
         }
     }
 
+    /// Park the calling thread until woken or `timeout` (at most [`PARK_TIMEOUT`]) elapses,
+    /// unless `ready` already holds.
+    fn park<F>(&self, timeout: Duration, ready: F)
+    where
+        F: Fn() -> bool,
+    {
+        let guard = self.park.lock().unwrap();
+        self.parked.fetch_add(1, Ordering::SeqCst);
+        // Pairs with the fence in `wake*`: either the waker sees us parked or we see its update.
+        fence(Ordering::SeqCst);
+        if !ready() {
+            let _ = self
+                .cv
+                .wait_timeout(guard, timeout.min(PARK_TIMEOUT))
+                .unwrap();
+        }
+        self.parked.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Wake parked threads after a state change.
+    fn wake(&self) {
+        fence(Ordering::SeqCst);
+        if self.parked.load(Ordering::SeqCst) > 0 {
+            // Taking the park lock orders us after any waiter's readiness check.
+            drop(self.park.lock().unwrap());
+            self.cv.notify_all();
+        }
+    }
+
+    /// Lock-free [`wake`](Self::wake) for the output callback; a missed wakeup costs the
+    /// waiter at most [`PARK_TIMEOUT`].
+    fn wake_from_callback(&self) {
+        fence(Ordering::SeqCst);
+        if self.parked.load(Ordering::SeqCst) > 0 {
+            self.cv.notify_all();
+        }
+    }
+
     /// Emit low-buffer diagnostics at most once per second.
     fn log_low_watermark(&self) {
         let threshold = (self.max_buffered_samples / 8).max(self.channels * 16);
-        let queued = {
-            let g = self.inner.lock().unwrap();
-            g.queue.len()
-        };
+        let queued = self.ring.len();
         if queued > 0 && queued < threshold {
             let now = SystemTime::now()
                 .duration_since(UNIX_EPOCH)
@@ -270,13 +455,22 @@ impl SharedAudio {
     ///
     /// Returns `true` if data becomes available before `timeout`.
     pub fn wait_for_any(&self, timeout: Duration) -> bool {
-        let mut g = self.inner.lock().unwrap();
-        if !g.queue.is_empty() {
-            return true;
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.ring.len() > 0 {
+                return true;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            self.park(deadline - now, || self.ring.len() > 0);
         }
-        let (g2, _timeout) = self.cv.wait_timeout(g, timeout).unwrap();
-        g = g2;
-        !g.queue.is_empty()
+    }
+
+    /// Whether the queue is closed and fully drained.
+    fn is_drained(&self) -> bool {
+        self.is_done() && self.ring.len() == 0
     }
 }
 
@@ -285,9 +479,8 @@ impl SharedAudio {
 /// This is typically used by `main` to wait for background decode/resample stages to finish
 /// before exiting the process.
 pub fn wait_until_done_and_empty(q: &Arc<SharedAudio>) {
-    let mut g = q.inner.lock().unwrap();
-    while !(g.done && g.queue.is_empty()) {
-        g = q.cv.wait(g).unwrap();
+    while !q.is_drained() {
+        q.park(PARK_TIMEOUT, || q.is_drained());
     }
 }
 
@@ -295,18 +488,50 @@ pub fn wait_until_done_and_empty(q: &Arc<SharedAudio>) {
 ///
 /// Returns `true` if queue drained normally, `false` if cancelled.
 pub fn wait_until_done_and_empty_or_cancel(q: &Arc<SharedAudio>, cancel: &Arc<AtomicBool>) -> bool {
-    let mut g = q.inner.lock().unwrap();
+    wait_until_done_and_empty_or(q, || cancel.load(Ordering::Relaxed))
+}
+
+/// Block until `q` is closed+empty OR `stop` returns true.
+///
+/// `stop` is polled at least every 50ms. Returns `true` if queue drained normally,
+/// `false` if `stop` fired first.
+pub fn wait_until_done_and_empty_or<F>(q: &Arc<SharedAudio>, mut stop: F) -> bool
+where
+    F: FnMut() -> bool,
+{
     loop {
-        if cancel.load(Ordering::Relaxed) {
+        if stop() {
             return false;
         }
 
-        if g.done && g.queue.is_empty() {
+        if q.is_drained() {
             return true;
         }
 
-        let (ng, _timeout) = q.cv.wait_timeout(g, Duration::from_millis(50)).unwrap();
-        g = ng;
+        q.park(Duration::from_millis(50), || q.is_drained());
+    }
+}
+
+/// Block until `q` holds at least `frames` frames (capped at its capacity), is closed, or `stop`
+/// returns true.
+///
+/// `stop` is polled at least every 50ms. Returns `false` if `stop` fired first.
+pub fn wait_until_buffered_or<F>(q: &Arc<SharedAudio>, frames: usize, mut stop: F) -> bool
+where
+    F: FnMut() -> bool,
+{
+    let target = frames.min(q.max_frames());
+    let ready = || q.is_done() || q.len_frames() >= target;
+    loop {
+        if stop() {
+            return false;
+        }
+
+        if ready() {
+            return true;
+        }
+
+        q.park(Duration::from_millis(50), ready);
     }
 }
 
@@ -385,6 +610,85 @@ mod tests {
         assert_eq!(out, vec![1.0, 2.0, 3.0, 4.0]);
     }
 
+    #[test]
+    fn push_nonblocking_drops_frames_that_do_not_fit() {
+        let q = SharedAudio::new(2, 6);
+        assert_eq!(q.push_interleaved_nonblocking(&[1.0, 2.0, 3.0, 4.0]), 2);
+        assert_eq!(q.push_interleaved_nonblocking(&[5.0, 6.0, 7.0, 8.0]), 1);
+        assert_eq!(q.len_frames(), 3);
+        q.close();
+        assert_eq!(q.push_interleaved_nonblocking(&[9.0, 10.0]), 0);
+    }
+
+    #[test]
+    fn ring_preserves_order_across_wraparound() {
+        let q = SharedAudio::new(2, 6);
+        let mut expected = Vec::new();
+        let mut got = Vec::new();
+        for i in 0..10 {
+            let frame = [i as f32, -(i as f32)];
+            q.push_interleaved_blocking(&frame);
+            expected.extend_from_slice(&frame);
+            if i % 2 == 1 {
+                got.extend(q.pop(PopStrategy::NonBlocking { max_frames: 2 }).unwrap());
+            }
+        }
+        assert_eq!(got, expected);
+        assert_eq!(q.len_frames(), 0);
+    }
+
+    #[test]
+    fn pop_nonblocking_does_not_wait_for_busy_consumer() {
+        let q = SharedAudio::new(2, 64);
+        q.push_interleaved_blocking(&[1.0, 2.0]);
+        let busy = q.consumer.lock().unwrap();
+        assert!(q.pop(PopStrategy::NonBlocking { max_frames: 4 }).is_none());
+        drop(busy);
+        assert_eq!(
+            q.pop(PopStrategy::NonBlocking { max_frames: 4 }).unwrap(),
+            vec![1.0, 2.0]
+        );
+    }
+
+    #[test]
+    fn copy_to_slice_fills_caller_buffer() {
+        let q = SharedAudio::new(2, 64);
+        q.push_interleaved_blocking(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let mut buf = [0.0; 5];
+        let n = q
+            .copy_to_slice(PopStrategy::NonBlocking { max_frames: 8 }, &mut buf)
+            .unwrap();
+        // Capped at whole frames that fit in `buf`.
+        assert_eq!(&buf[..n], &[1.0, 2.0, 3.0, 4.0]);
+        let n = q
+            .copy_to_slice(PopStrategy::BlockingExact { frames: 1 }, &mut buf)
+            .unwrap();
+        assert_eq!(&buf[..n], &[5.0, 6.0]);
+        q.close();
+        assert!(
+            q.copy_to_slice(PopStrategy::BlockingUpTo { max_frames: 2 }, &mut buf)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn flush_drops_buffered_and_aborts_blocked_push() {
+        let q = Arc::new(SharedAudio::new(2, 4));
+        let q_push = q.clone();
+        let handle = thread::spawn(move || {
+            // Fills the queue, then blocks on the remaining frames until the flush.
+            q_push.push_interleaved_blocking(&[0.5; 12]);
+        });
+        while q.len_frames() < 2 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        q.flush();
+        handle.join().unwrap();
+        assert_eq!(q.len_frames(), 0);
+        q.push_interleaved_blocking(&[0.1, 0.2]);
+        assert_eq!(q.len_frames(), 1);
+    }
+
     #[test]
     fn pop_blocking_exact_returns_none_when_closed() {
         let q = SharedAudio::new(2, 64);
@@ -432,6 +736,19 @@ mod tests {
         assert!(drained);
     }
 
+    #[test]
+    fn wait_until_done_and_empty_or_stops_on_predicate() {
+        let q = Arc::new(SharedAudio::new(2, 64));
+        q.push_interleaved_blocking(&[1.0, 2.0]);
+        let mut polls = 0;
+        let drained = wait_until_done_and_empty_or(&q, || {
+            polls += 1;
+            polls > 2
+        });
+        assert!(!drained);
+        assert_eq!(polls, 3);
+    }
+
     #[test]
     fn wait_until_done_and_empty_or_cancel_respects_cancel() {
         let q = Arc::new(SharedAudio::new(2, 64));
@@ -440,4 +757,30 @@ mod tests {
         let drained = wait_until_done_and_empty_or_cancel(&q, &cancel);
         assert!(!drained);
     }
+
+    #[test]
+    fn wait_until_buffered_or_returns_once_threshold_is_queued() {
+        let q = Arc::new(SharedAudio::new(2, 64));
+        let q_push = q.clone();
+        let handle = thread::spawn(move || {
+            for _ in 0..4 {
+                q_push.push_interleaved_blocking(&[0.0; 4]);
+            }
+        });
+        assert!(wait_until_buffered_or(&q, 8, || false));
+        assert!(q.len_frames() >= 8);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn wait_until_buffered_or_caps_at_capacity_and_honors_close_and_stop() {
+        let q = Arc::new(SharedAudio::new(2, 8));
+        q.push_interleaved_blocking(&[0.0; 8]);
+        assert!(wait_until_buffered_or(&q, 1000, || false));
+
+        let q = Arc::new(SharedAudio::new(2, 64));
+        assert!(!wait_until_buffered_or(&q, 8, || true));
+        q.close();
+        assert!(wait_until_buffered_or(&q, 8, || false));
+    }
 }
@@ -0,0 +1,580 @@
+//! Output recording (`--record`).
+//!
+//! Tees the post-DSP sample stream (after channel mapping and volume, before conversion to the
+//! device format) to a 24-bit WAV or FLAC file:
+//! - the output callback hands samples to a [`RecordTap`] without blocking; if the writer falls
+//!   behind, the batch is dropped and counted instead of stalling playback
+//! - a writer thread encodes and re-syncs the file headers about once a second, so the file
+//!   stays playable if the process exits without a clean shutdown
+//! - only real audio is recorded; pause and underrun silence is skipped
+//!
+//! FLAC output uses verbatim (uncompressed) subframes; it exists for tools that expect FLAC,
+//! not to save space.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow};
+
+/// Output callback batches buffered ahead of the writer thread.
+const TAP_CAPACITY: usize = 256;
+/// How often the writer flushes and patches the file headers.
+const SYNC_INTERVAL: Duration = Duration::from_secs(1);
+/// Bits per recorded sample.
+const BITS_PER_SAMPLE: u32 = 24;
+/// Largest FLAC block written (frames per channel).
+const FLAC_MAX_BLOCK: usize = 4096;
+/// Smallest FLAC block written, except for the final one.
+const FLAC_MIN_BLOCK: usize = 16;
+/// FLAC's independent channel assignment covers at most 8 channels.
+const FLAC_MAX_CHANNELS: u16 = 8;
+
+/// Container written by a [`Recorder`], chosen from the path extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordFormat {
+    /// RIFF/WAVE, 24-bit PCM.
+    Wav,
+    /// FLAC, 24-bit, verbatim subframes.
+    Flac,
+}
+
+impl RecordFormat {
+    /// Format for a `.wav`/`.flac` path (case-insensitive).
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            Some("wav") => Ok(Self::Wav),
+            Some("flac") => Ok(Self::Flac),
+            _ => Err(anyhow!(
+                "record path must end in .wav or .flac: {}",
+                path.display()
+            )),
+        }
+    }
+}
+
+/// Records every playback session of a process into WAV/FLAC files.
+///
+/// Sessions with the same rate and channel count append to the current file; a format change
+/// starts a new file named `<stem>-<n>.<ext>` next to the first one.
+pub struct Recorder {
+    path: PathBuf,
+    format: RecordFormat,
+    state: Mutex<RecorderState>,
+}
+
+#[derive(Default)]
+struct RecorderState {
+    current: Option<(u32, u16, RecordTap)>,
+    files: u32,
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder")
+            .field("path", &self.path)
+            .field("format", &self.format)
+            .finish()
+    }
+}
+
+impl Recorder {
+    /// Create a recorder writing to `path` (`.wav` or `.flac`); no file is opened yet.
+    pub fn new(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let format = RecordFormat::from_path(&path)?;
+        Ok(Self {
+            path,
+            format,
+            state: Mutex::new(RecorderState::default()),
+        })
+    }
+
+    /// Tap for a session producing `channels` interleaved channels at `sample_rate`.
+    pub fn tap(&self, sample_rate: u32, channels: u16) -> Result<RecordTap> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| anyhow!("recorder state poisoned"))?;
+        if let Some((rate, ch, tap)) = &state.current
+            && *rate == sample_rate
+            && *ch == channels
+        {
+            return Ok(tap.clone());
+        }
+        // Dropping the previous tap lets its writer finish once the old stream is gone.
+        state.current = None;
+        let path = numbered_path(&self.path, state.files);
+        let encoder = Encoder::create(&path, self.format, sample_rate, channels)?;
+        state.files += 1;
+        tracing::info!(
+            path = %path.display(),
+            sample_rate,
+            channels,
+            "recording output"
+        );
+        let (tx, rx) = mpsc::sync_channel(TAP_CAPACITY);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let writer_dropped = dropped.clone();
+        std::thread::Builder::new()
+            .name("audio-record".to_string())
+            .spawn(move || run_writer(rx, encoder, path, writer_dropped))
+            .context("spawn record writer")?;
+        let tap = RecordTap { tx, dropped };
+        state.current = Some((sample_rate, channels, tap.clone()));
+        Ok(tap)
+    }
+}
+
+/// `path` for the first file, `<stem>-<n+1>.<ext>` afterwards.
+fn numbered_path(path: &Path, index: u32) -> PathBuf {
+    if index == 0 {
+        return path.to_path_buf();
+    }
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut name = format!("{stem}-{}", index + 1);
+    if let Some(ext) = path.extension() {
+        name.push('.');
+        name.push_str(&ext.to_string_lossy());
+    }
+    path.with_file_name(name)
+}
+
+/// Real-time side of a recording: hands interleaved `f32` batches to the writer thread.
+#[derive(Clone)]
+pub struct RecordTap {
+    tx: SyncSender<Vec<f32>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl fmt::Debug for RecordTap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordTap")
+            .field("dropped", &self.dropped.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl RecordTap {
+    /// Queue a batch without blocking; drops it when the writer is behind or gone.
+    pub fn push(&self, samples: Vec<f32>) {
+        match self.tx.try_send(samples) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Writer thread: encode batches until every tap is dropped, syncing headers periodically.
+fn run_writer(
+    rx: Receiver<Vec<f32>>,
+    mut encoder: Encoder,
+    path: PathBuf,
+    dropped: Arc<AtomicU64>,
+) {
+    let mut ints = Vec::new();
+    let mut last_sync = Instant::now();
+    let result = loop {
+        match rx.recv_timeout(SYNC_INTERVAL) {
+            Ok(batch) => {
+                ints.clear();
+                ints.extend(batch.iter().map(|&s| to_i24(s)));
+                if let Err(e) = encoder.write(&ints) {
+                    break Err(e);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break encoder.finish(),
+        }
+        if last_sync.elapsed() >= SYNC_INTERVAL {
+            last_sync = Instant::now();
+            if let Err(e) = encoder.sync() {
+                break Err(e);
+            }
+        }
+    };
+    let dropped = dropped.load(Ordering::Relaxed);
+    match result {
+        Ok(()) => tracing::info!(path = %path.display(), dropped, "recording closed"),
+        Err(e) => tracing::warn!(path = %path.display(), error = %e, "recording failed"),
+    }
+}
+
+/// Quantize a `[-1, 1]` sample to signed 24-bit.
+fn to_i24(sample: f32) -> i32 {
+    const SCALE: f32 = (1 << 23) as f32;
+    ((sample * SCALE).round() as i32).clamp(-(1 << 23), (1 << 23) - 1)
+}
+
+enum Encoder {
+    Wav(WavEncoder),
+    Flac(FlacEncoder),
+}
+
+impl Encoder {
+    fn create(path: &Path, format: RecordFormat, sample_rate: u32, channels: u16) -> Result<Self> {
+        if channels == 0 {
+            return Err(anyhow!("cannot record zero channels"));
+        }
+        if format == RecordFormat::Flac && channels > FLAC_MAX_CHANNELS {
+            return Err(anyhow!(
+                "FLAC recording supports at most {FLAC_MAX_CHANNELS} channels (got {channels})"
+            ));
+        }
+        let file =
+            File::create(path).with_context(|| format!("create recording {}", path.display()))?;
+        let out = BufWriter::new(file);
+        let encoder = match format {
+            RecordFormat::Wav => Self::Wav(WavEncoder::new(out, sample_rate, channels)?),
+            RecordFormat::Flac => Self::Flac(FlacEncoder::new(out, sample_rate, channels)?),
+        };
+        Ok(encoder)
+    }
+
+    fn write(&mut self, samples: &[i32]) -> io::Result<()> {
+        match self {
+            Self::Wav(enc) => enc.write(samples),
+            Self::Flac(enc) => enc.write(samples),
+        }
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        match self {
+            Self::Wav(enc) => enc.sync(),
+            Self::Flac(enc) => enc.sync(),
+        }
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        if let Self::Flac(enc) = &mut self {
+            enc.flush_pending(1)?;
+        }
+        self.sync()
+    }
+}
+
+/// RIFF/WAVE writer with a canonical 44-byte header.
+struct WavEncoder {
+    out: BufWriter<File>,
+    data_bytes: u64,
+}
+
+impl WavEncoder {
+    fn new(mut out: BufWriter<File>, sample_rate: u32, channels: u16) -> io::Result<Self> {
+        let block_align = u32::from(channels) * BITS_PER_SAMPLE / 8;
+        out.write_all(b"RIFF")?;
+        out.write_all(&36u32.to_le_bytes())?;
+        out.write_all(b"WAVEfmt ")?;
+        out.write_all(&16u32.to_le_bytes())?;
+        out.write_all(&1u16.to_le_bytes())?;
+        out.write_all(&channels.to_le_bytes())?;
+        out.write_all(&sample_rate.to_le_bytes())?;
+        out.write_all(&(sample_rate * block_align).to_le_bytes())?;
+        out.write_all(&(block_align as u16).to_le_bytes())?;
+        out.write_all(&(BITS_PER_SAMPLE as u16).to_le_bytes())?;
+        out.write_all(b"data")?;
+        out.write_all(&0u32.to_le_bytes())?;
+        Ok(Self { out, data_bytes: 0 })
+    }
+
+    fn write(&mut self, samples: &[i32]) -> io::Result<()> {
+        for sample in samples {
+            self.out.write_all(&sample.to_le_bytes()[..3])?;
+        }
+        self.data_bytes += samples.len() as u64 * 3;
+        Ok(())
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        // RIFF sizes are 32-bit; past 4 GiB the header saturates.
+        let data = self.data_bytes.min(u64::from(u32::MAX - 36)) as u32;
+        self.out.seek(SeekFrom::Start(4))?;
+        self.out.write_all(&(36 + data).to_le_bytes())?;
+        self.out.seek(SeekFrom::Start(40))?;
+        self.out.write_all(&data.to_le_bytes())?;
+        self.out.seek(SeekFrom::End(0))?;
+        self.out.flush()
+    }
+}
+
+/// FLAC writer using variable-blocksize frames with verbatim subframes.
+struct FlacEncoder {
+    out: BufWriter<File>,
+    sample_rate: u32,
+    channels: usize,
+    /// Interleaved samples not yet written as a frame.
+    pending: Vec<i32>,
+    /// Frames (per channel) written so far; also the next frame's sample number.
+    written_frames: u64,
+    frame: Vec<u8>,
+}
+
+/// Byte offset of the STREAMINFO rate/channels/bps/total-samples word.
+const FLAC_STREAMINFO_TOTALS_OFFSET: u64 = 4 + 4 + 10;
+
+impl FlacEncoder {
+    fn new(out: BufWriter<File>, sample_rate: u32, channels: u16) -> io::Result<Self> {
+        let mut enc = Self {
+            out,
+            sample_rate,
+            channels: usize::from(channels),
+            pending: Vec::new(),
+            written_frames: 0,
+            frame: Vec::new(),
+        };
+        enc.out.write_all(b"fLaC")?;
+        // Last metadata block, type 0 (STREAMINFO), 34 bytes.
+        enc.out.write_all(&[0x80, 0, 0, 34])?;
+        enc.out.write_all(&(FLAC_MIN_BLOCK as u16).to_be_bytes())?;
+        enc.out.write_all(&(FLAC_MAX_BLOCK as u16).to_be_bytes())?;
+        // Min/max frame size unknown.
+        enc.out.write_all(&[0; 6])?;
+        enc.out.write_all(&enc.streaminfo_totals().to_be_bytes())?;
+        // MD5 unknown.
+        enc.out.write_all(&[0; 16])?;
+        Ok(enc)
+    }
+
+    /// Packed sample rate (20 bits), channels-1 (3), bps-1 (5), total samples (36).
+    fn streaminfo_totals(&self) -> u64 {
+        (u64::from(self.sample_rate) << 44)
+            | ((self.channels as u64 - 1) << 41)
+            | (u64::from(BITS_PER_SAMPLE - 1) << 36)
+            | (self.written_frames & ((1 << 36) - 1))
+    }
+
+    fn write(&mut self, samples: &[i32]) -> io::Result<()> {
+        self.pending.extend_from_slice(samples);
+        self.flush_pending(FLAC_MAX_BLOCK)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.flush_pending(FLAC_MIN_BLOCK)?;
+        self.out
+            .seek(SeekFrom::Start(FLAC_STREAMINFO_TOTALS_OFFSET))?;
+        self.out
+            .write_all(&self.streaminfo_totals().to_be_bytes())?;
+        self.out.seek(SeekFrom::End(0))?;
+        self.out.flush()
+    }
+
+    /// Write pending audio as frames of at most `FLAC_MAX_BLOCK`, while at least `min_frames`
+    /// are buffered.
+    fn flush_pending(&mut self, min_frames: usize) -> io::Result<()> {
+        let min_frames = min_frames.max(1);
+        let mut start = 0;
+        loop {
+            let available = (self.pending.len() - start) / self.channels;
+            if available < min_frames {
+                break;
+            }
+            let block = available.min(FLAC_MAX_BLOCK);
+            let end = start + block * self.channels;
+            self.encode_frame(start, end)?;
+            start = end;
+        }
+        self.pending.drain(..start);
+        Ok(())
+    }
+
+    fn encode_frame(&mut self, start: usize, end: usize) -> io::Result<()> {
+        let block = (end - start) / self.channels;
+        let frame = &mut self.frame;
+        frame.clear();
+        // Sync code, variable blocksize; 16-bit blocksize follows, rate from STREAMINFO.
+        frame.extend_from_slice(&[0xFF, 0xF9, 0x70]);
+        // Independent channels, 24 bits per sample.
+        frame.push((((self.channels - 1) as u8) << 4) | (0b110 << 1));
+        push_utf8_number(frame, self.written_frames);
+        frame.extend_from_slice(&((block - 1) as u16).to_be_bytes());
+        frame.push(crc8(frame));
+        for ch in 0..self.channels {
+            // Verbatim subframe, no wasted bits.
+            frame.push(0x02);
+            for sample in self.pending[start..end]
+                .iter()
+                .skip(ch)
+                .step_by(self.channels)
+            {
+                frame.extend_from_slice(&sample.to_be_bytes()[1..]);
+            }
+        }
+        let crc = crc16(frame);
+        frame.extend_from_slice(&crc.to_be_bytes());
+        self.out.write_all(frame)?;
+        self.written_frames += block as u64;
+        Ok(())
+    }
+}
+
+/// FLAC's UTF-8-like coding of frame/sample numbers (up to 36 bits).
+fn push_utf8_number(out: &mut Vec<u8>, value: u64) {
+    if value < 0x80 {
+        out.push(value as u8);
+        return;
+    }
+    let continuation = match value {
+        v if v < 0x800 => 1,
+        v if v < 0x1_0000 => 2,
+        v if v < 0x20_0000 => 3,
+        v if v < 0x400_0000 => 4,
+        v if v < 0x8000_0000 => 5,
+        _ => 6,
+    };
+    let prefix = !(0xFFu8 >> (continuation + 1));
+    out.push(prefix | (value >> (6 * continuation)) as u8);
+    for idx in (0..continuation).rev() {
+        out.push(0x80 | ((value >> (6 * idx)) & 0x3F) as u8);
+    }
+}
+
+/// CRC-8 (poly 0x07) over a FLAC frame header.
+fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |mut crc, byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// CRC-16 (poly 0x8005) over a whole FLAC frame.
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |mut crc, byte| {
+        crc ^= u16::from(*byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::probe::Hint;
+
+    fn temp_path(ext: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "audio-player-record-{}.{ext}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ))
+    }
+
+    /// Decode a file with symphonia into interleaved f32 samples.
+    fn decode(path: &Path) -> (u32, usize, Vec<f32>) {
+        let file = File::open(path).expect("open recording");
+        let mss = MediaSourceStream::new(Box::new(file), Default::default());
+        let mut hint = Hint::new();
+        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+            hint.with_extension(ext);
+        }
+        let probed = symphonia::default::get_probe()
+            .format(&hint, mss, &Default::default(), &Default::default())
+            .expect("probe recording");
+        let mut format = probed.format;
+        let track = format.default_track().expect("track").clone();
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &Default::default())
+            .expect("decoder");
+        let mut samples = Vec::new();
+        while let Ok(packet) = format.next_packet() {
+            let decoded = decoder.decode(&packet).expect("decode packet");
+            let mut buf = SampleBuffer::<f32>::new(decoded.capacity() as u64, *decoded.spec());
+            buf.copy_interleaved_ref(decoded);
+            samples.extend_from_slice(buf.samples());
+        }
+        let rate = track.codec_params.sample_rate.expect("rate");
+        let channels = track.codec_params.channels.expect("channels").count();
+        (rate, channels, samples)
+    }
+
+    fn roundtrip(ext: &str) {
+        let path = temp_path(ext);
+        let source: Vec<f32> = (0..2 * 5000)
+            .map(|i| ((i as f32) * 0.013).sin() * 0.8)
+            .collect();
+        let mut encoder =
+            Encoder::create(&path, RecordFormat::from_path(&path).unwrap(), 48_000, 2).unwrap();
+        let ints: Vec<i32> = source.iter().map(|&s| to_i24(s)).collect();
+        // Uneven batches exercise partial FLAC blocks.
+        for chunk in ints.chunks(2 * 1500) {
+            encoder.write(chunk).unwrap();
+            encoder.sync().unwrap();
+        }
+        encoder.finish().unwrap();
+
+        let (rate, channels, decoded) = decode(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(rate, 48_000);
+        assert_eq!(channels, 2);
+        let expected: Vec<f32> = ints.iter().map(|&s| s as f32 / (1 << 23) as f32).collect();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn wav_recording_decodes_bit_exact() {
+        roundtrip("wav");
+    }
+
+    #[test]
+    fn flac_recording_decodes_bit_exact() {
+        roundtrip("flac");
+    }
+
+    #[test]
+    fn utf8_numbers_match_flac_coding() {
+        let encode = |value| {
+            let mut out = Vec::new();
+            push_utf8_number(&mut out, value);
+            out
+        };
+        assert_eq!(encode(0x7F), vec![0x7F]);
+        assert_eq!(encode(0x80), vec![0xC2, 0x80]);
+        assert_eq!(encode(0x1000), vec![0xE1, 0x80, 0x80]);
+        assert_eq!(encode(1 << 35).len(), 7);
+        assert_eq!(encode(1 << 35)[0], 0xFE);
+    }
+
+    #[test]
+    fn numbered_path_keeps_first_name() {
+        let base = Path::new("/tmp/capture.flac");
+        assert_eq!(numbered_path(base, 0), base);
+        assert_eq!(numbered_path(base, 1), Path::new("/tmp/capture-2.flac"));
+        assert!(RecordFormat::from_path(Path::new("capture.mp3")).is_err());
+        assert_eq!(
+            RecordFormat::from_path(Path::new("CAPTURE.WAV")).unwrap(),
+            RecordFormat::Wav
+        );
+    }
+}
@@ -0,0 +1,476 @@
+//! Pull-based audio sources.
+//!
+//! A [`Source`] yields interleaved `f32` frames on demand, so processing stages (resampling,
+//! gain, future DSP) compose by wrapping one another instead of each owning a thread and a
+//! queue. [`SharedAudio`] stays the hand-off between threads:
+//! - [`QueueSource`] reads a queue as a source (e.g. the decode thread's output).
+//! - [`spawn_pump`] drains a source chain into a queue on a background thread.
+//!
+//! [`Sequence`] splices the next track's source onto the current one for gapless playback.
+//! [`TrimSilence`] drops digital silence at the start and end of a source.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use symphonia::core::audio::SignalSpec;
+
+use crate::config::SilenceTrim;
+use crate::queue::{PopStrategy, SharedAudio};
+
+/// A pull-based stream of interleaved `f32` audio.
+pub trait Source: Send {
+    /// Rate and channel layout of the samples returned by [`read`](Self::read).
+    fn spec(&self) -> SignalSpec;
+
+    /// Total frames this source will produce, when known.
+    fn frames(&self) -> Option<u64>;
+
+    /// Fill the front of `out` with whole interleaved frames, blocking until at least one
+    /// frame is available.
+    ///
+    /// Returns the number of samples written; `0` means the source is exhausted. `out` must
+    /// hold at least one frame.
+    fn read(&mut self, out: &mut [f32]) -> usize;
+
+    /// Read until `out` is full or the source ends; returns the number of samples written.
+    fn read_full(&mut self, out: &mut [f32]) -> usize {
+        let channels = self.spec().channels.count();
+        let want = out.len() / channels * channels;
+        let mut filled = 0;
+        while filled < want {
+            let n = self.read(&mut out[filled..want]);
+            if n == 0 {
+                break;
+            }
+            filled += n;
+        }
+        filled
+    }
+}
+
+impl<S: Source + ?Sized> Source for Box<S> {
+    fn spec(&self) -> SignalSpec {
+        (**self).spec()
+    }
+
+    fn frames(&self) -> Option<u64> {
+        (**self).frames()
+    }
+
+    fn read(&mut self, out: &mut [f32]) -> usize {
+        (**self).read(out)
+    }
+}
+
+/// Adapter reading a [`SharedAudio`] queue as a [`Source`].
+///
+/// The source ends once the queue is closed and drained.
+pub struct QueueSource {
+    queue: Arc<SharedAudio>,
+    spec: SignalSpec,
+    frames: Option<u64>,
+}
+
+impl QueueSource {
+    /// Wrap `queue`, whose samples are laid out as described by `spec`.
+    pub fn new(queue: Arc<SharedAudio>, spec: SignalSpec) -> Self {
+        Self {
+            queue,
+            spec,
+            frames: None,
+        }
+    }
+
+    /// Report `frames` as the total length of the stream.
+    pub fn with_frames(mut self, frames: Option<u64>) -> Self {
+        self.frames = frames;
+        self
+    }
+}
+
+impl Source for QueueSource {
+    fn spec(&self) -> SignalSpec {
+        self.spec
+    }
+
+    fn frames(&self) -> Option<u64> {
+        self.frames
+    }
+
+    fn read(&mut self, out: &mut [f32]) -> usize {
+        let max_frames = out.len() / self.queue.channels();
+        self.queue
+            .copy_to_slice(PopStrategy::BlockingUpTo { max_frames }, out)
+            .unwrap_or(0)
+    }
+}
+
+/// Fixed linear gain stage.
+pub struct Gain<S> {
+    inner: S,
+    gain: f32,
+}
+
+impl<S: Source> Gain<S> {
+    /// Scale every sample of `inner` by `gain` (`1.0` = unity).
+    pub fn new(inner: S, gain: f32) -> Self {
+        Self { inner, gain }
+    }
+}
+
+impl<S: Source> Source for Gain<S> {
+    fn spec(&self) -> SignalSpec {
+        self.inner.spec()
+    }
+
+    fn frames(&self) -> Option<u64> {
+        self.inner.frames()
+    }
+
+    fn read(&mut self, out: &mut [f32]) -> usize {
+        let n = self.inner.read(out);
+        if self.gain != 1.0 {
+            for sample in &mut out[..n] {
+                *sample *= self.gain;
+            }
+        }
+        n
+    }
+}
+
+/// Drops silent frames at the start and end of `inner`, up to a cap at each end.
+///
+/// Trailing silence cannot be told from a pause in the music until the source ends, so
+/// silent frames are held back (at most the cap) and released as soon as audio follows.
+pub struct TrimSilence<S> {
+    inner: S,
+    channels: usize,
+    threshold: f32,
+    /// Leading silent frames still allowed to be dropped (`0` once audio started).
+    lead_left: usize,
+    max_tail_frames: usize,
+    /// Silent frames held back as possible trailing silence.
+    tail: VecDeque<f32>,
+    /// Samples ready to hand out, from `ready_pos` on.
+    ready: Vec<f32>,
+    ready_pos: usize,
+    buf: Vec<f32>,
+    lead_trimmed: u64,
+    done: bool,
+}
+
+impl<S: Source> TrimSilence<S> {
+    /// Wrap `inner` with the thresholds and caps of `trim`.
+    pub fn new(inner: S, trim: SilenceTrim) -> Self {
+        let spec = inner.spec();
+        let max_frames = (u64::from(spec.rate) * u64::from(trim.max_ms) / 1000) as usize;
+        Self {
+            channels: spec.channels.count(),
+            threshold: 10f32.powf(trim.threshold_db / 20.0),
+            lead_left: if trim.leading { max_frames } else { 0 },
+            max_tail_frames: if trim.trailing { max_frames } else { 0 },
+            inner,
+            tail: VecDeque::new(),
+            ready: Vec::new(),
+            ready_pos: 0,
+            buf: Vec::new(),
+            lead_trimmed: 0,
+            done: false,
+        }
+    }
+
+    /// Sort one chunk of frames from `inner` into dropped, held and ready samples.
+    fn process(&mut self, samples: usize) {
+        for frame in self.buf[..samples].chunks_exact(self.channels) {
+            let silent = frame.iter().all(|s| s.abs() <= self.threshold);
+            if self.lead_left > 0 {
+                if silent {
+                    self.lead_left -= 1;
+                    self.lead_trimmed += 1;
+                    continue;
+                }
+                self.lead_left = 0;
+                if self.lead_trimmed > 0 {
+                    tracing::debug!(frames = self.lead_trimmed, "trimmed leading silence");
+                }
+            }
+            if self.max_tail_frames == 0 {
+                self.ready.extend_from_slice(frame);
+            } else if silent {
+                self.tail.extend(frame);
+                if self.tail.len() > self.max_tail_frames * self.channels {
+                    self.ready.extend(self.tail.drain(..self.channels));
+                }
+            } else {
+                self.ready.extend(self.tail.drain(..));
+                self.ready.extend_from_slice(frame);
+            }
+        }
+    }
+}
+
+impl<S: Source> Source for TrimSilence<S> {
+    fn spec(&self) -> SignalSpec {
+        self.inner.spec()
+    }
+
+    fn frames(&self) -> Option<u64> {
+        None
+    }
+
+    fn read(&mut self, out: &mut [f32]) -> usize {
+        loop {
+            if self.ready_pos < self.ready.len() {
+                let available = self.ready.len() - self.ready_pos;
+                let n = available.min(out.len() / self.channels * self.channels);
+                out[..n].copy_from_slice(&self.ready[self.ready_pos..self.ready_pos + n]);
+                self.ready_pos += n;
+                if self.ready_pos == self.ready.len() {
+                    self.ready.clear();
+                    self.ready_pos = 0;
+                }
+                return n;
+            }
+            if self.done {
+                return 0;
+            }
+            self.buf.resize(out.len(), 0.0);
+            let n = self.inner.read(&mut self.buf);
+            if n == 0 {
+                self.done = true;
+                if !self.tail.is_empty() {
+                    tracing::debug!(
+                        frames = self.tail.len() / self.channels,
+                        "trimmed trailing silence"
+                    );
+                    self.tail.clear();
+                }
+                continue;
+            }
+            self.process(n);
+        }
+    }
+}
+
+/// Slot holding the source queued to follow the current one.
+type NextSlot = Arc<Mutex<Option<Box<dyn Source>>>>;
+
+/// Plays sources back to back: when the current source ends, the one queued through its
+/// [`SequenceHandle`] takes over with no gap in the output.
+pub struct Sequence {
+    current: Box<dyn Source>,
+    spec: SignalSpec,
+    next: NextSlot,
+    splices: Arc<AtomicU64>,
+}
+
+/// Control handle for a [`Sequence`].
+#[derive(Clone)]
+pub struct SequenceHandle {
+    spec: SignalSpec,
+    next: NextSlot,
+    splices: Arc<AtomicU64>,
+}
+
+impl Sequence {
+    /// Start a sequence with `first`; its spec is fixed for the whole sequence.
+    pub fn new(first: Box<dyn Source>) -> (Self, SequenceHandle) {
+        let spec = first.spec();
+        let next: NextSlot = Arc::new(Mutex::new(None));
+        let splices = Arc::new(AtomicU64::new(0));
+        let handle = SequenceHandle {
+            spec,
+            next: next.clone(),
+            splices: splices.clone(),
+        };
+        (
+            Self {
+                current: first,
+                spec,
+                next,
+                splices,
+            },
+            handle,
+        )
+    }
+}
+
+impl SequenceHandle {
+    /// Queue `source` to follow the current one, replacing any previously queued source.
+    ///
+    /// A source whose spec differs from the sequence is handed back: it needs a new output
+    /// session rather than a splice.
+    pub fn set_next(&self, source: Box<dyn Source>) -> Result<(), Box<dyn Source>> {
+        if source.spec() != self.spec {
+            return Err(source);
+        }
+        *self.next.lock().unwrap() = Some(source);
+        Ok(())
+    }
+
+    /// Remove the queued source, if it has not been spliced in yet.
+    pub fn clear_next(&self) -> Option<Box<dyn Source>> {
+        self.next.lock().unwrap().take()
+    }
+
+    /// Number of times a queued source has taken over.
+    pub fn splices(&self) -> u64 {
+        self.splices.load(Ordering::Acquire)
+    }
+}
+
+impl Source for Sequence {
+    fn spec(&self) -> SignalSpec {
+        self.spec
+    }
+
+    fn frames(&self) -> Option<u64> {
+        None
+    }
+
+    fn read(&mut self, out: &mut [f32]) -> usize {
+        loop {
+            let n = self.current.read(out);
+            if n > 0 {
+                return n;
+            }
+            let Some(next) = self.next.lock().unwrap().take() else {
+                return 0;
+            };
+            self.current = next;
+            self.splices.fetch_add(1, Ordering::AcqRel);
+        }
+    }
+}
+
+/// Drain `source` into `dstq` on a background thread, closing `dstq` once the source ends.
+///
+/// `chunk_frames` sizes each read.
+pub fn spawn_pump<S>(mut source: S, dstq: Arc<SharedAudio>, chunk_frames: usize)
+where
+    S: Source + 'static,
+{
+    let channels = source.spec().channels.count();
+    thread::spawn(move || {
+        let mut buf = vec![0.0f32; chunk_frames.max(1) * channels];
+        loop {
+            let n = source.read(&mut buf);
+            if n == 0 {
+                break;
+            }
+            dstq.push_interleaved_blocking(&buf[..n]);
+        }
+        dstq.close();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia::core::audio::Channels;
+
+    fn stereo(rate: u32) -> SignalSpec {
+        SignalSpec::new(rate, Channels::FRONT_LEFT | Channels::FRONT_RIGHT)
+    }
+
+    fn closed_queue(samples: &[f32]) -> Arc<SharedAudio> {
+        let q = Arc::new(SharedAudio::new(2, samples.len().max(2)));
+        q.push_interleaved_blocking(samples);
+        q.close();
+        q
+    }
+
+    #[test]
+    fn trim_silence_caps_leading_and_trailing_but_keeps_gaps() {
+        // At 1 kHz one frame is 1 ms, so `max_ms: 3` trims up to three frames per end.
+        let (s, a) = ([0.0, 0.0], [0.5, -0.5]);
+        let frames = [s, s, s, s, s, a, s, s, a, s, s, s, s];
+        let samples: Vec<f32> = frames.concat();
+        let trim = SilenceTrim {
+            max_ms: 3,
+            ..SilenceTrim::default()
+        };
+        let mut src =
+            TrimSilence::new(QueueSource::new(closed_queue(&samples), stereo(1000)), trim);
+        let mut out = Vec::new();
+        let mut buf = [0.0f32; 4];
+        loop {
+            let n = src.read(&mut buf);
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(out, [s, s, a, s, s, a, s].concat());
+    }
+
+    #[test]
+    fn trim_silence_leading_off_keeps_start() {
+        let samples = [[0.0, 0.0], [0.5, 0.5]].concat();
+        let trim = SilenceTrim {
+            leading: false,
+            ..SilenceTrim::default()
+        };
+        let mut src =
+            TrimSilence::new(QueueSource::new(closed_queue(&samples), stereo(1000)), trim);
+        let mut out = [0.0f32; 8];
+        assert_eq!(src.read_full(&mut out), 4);
+        assert_eq!(out[..4], samples[..]);
+    }
+
+    #[test]
+    fn queue_source_reads_until_closed() {
+        let mut source = QueueSource::new(closed_queue(&[1.0, 2.0, 3.0, 4.0]), stereo(48_000));
+        let mut out = [0.0f32; 8];
+        assert_eq!(source.read_full(&mut out), 4);
+        assert_eq!(&out[..4], &[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(source.read(&mut out), 0);
+    }
+
+    #[test]
+    fn gain_scales_inner_samples() {
+        let inner = QueueSource::new(closed_queue(&[0.5, -0.5]), stereo(48_000));
+        let mut source = Gain::new(inner, 0.5);
+        let mut out = [0.0f32; 2];
+        assert_eq!(source.read(&mut out), 2);
+        assert_eq!(out, [0.25, -0.25]);
+    }
+
+    #[test]
+    fn sequence_splices_next_source_without_gap() {
+        let first = QueueSource::new(closed_queue(&[1.0, 1.0, 2.0, 2.0]), stereo(48_000));
+        let second = QueueSource::new(closed_queue(&[3.0, 3.0]), stereo(48_000));
+        let (mut seq, handle) = Sequence::new(Box::new(first));
+        assert!(handle.set_next(Box::new(second)).is_ok());
+
+        let mut out = [0.0f32; 8];
+        assert_eq!(seq.read_full(&mut out), 6);
+        assert_eq!(&out[..6], &[1.0, 1.0, 2.0, 2.0, 3.0, 3.0]);
+        assert_eq!(handle.splices(), 1);
+        assert_eq!(seq.read(&mut out), 0);
+    }
+
+    #[test]
+    fn sequence_rejects_mismatched_spec() {
+        let first = QueueSource::new(closed_queue(&[0.0, 0.0]), stereo(48_000));
+        let other = QueueSource::new(closed_queue(&[0.0, 0.0]), stereo(44_100));
+        let (_seq, handle) = Sequence::new(Box::new(first));
+        assert!(handle.set_next(Box::new(other)).is_err());
+        assert!(handle.clear_next().is_none());
+    }
+
+    #[test]
+    fn spawn_pump_forwards_source_and_closes() {
+        let samples: Vec<f32> = (0..64).map(|i| i as f32).collect();
+        let source = QueueSource::new(closed_queue(&samples), stereo(48_000));
+        let dstq = Arc::new(SharedAudio::new(2, 16));
+        spawn_pump(source, dstq.clone(), 5);
+        let mut got = Vec::new();
+        while let Some(chunk) = dstq.pop(PopStrategy::BlockingUpTo { max_frames: 16 }) {
+            got.extend(chunk);
+        }
+        assert_eq!(got, samples);
+    }
+}
@@ -1,7 +1,10 @@
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 
-use audio_bridge_types::{BridgeStatus as BridgeStatusSnapshot, PlaybackEndReason};
+use audio_bridge_types::{BridgeStatus as BridgeStatusSnapshot, Chapter, PlaybackEndReason};
+
+use crate::decode::DecodeErrorStats;
+use crate::meter::LevelMeter;
 
 /// Shared playback status state updated by the player pipeline.
 #[derive(Debug, Default)]
@@ -48,6 +51,27 @@ pub struct PlayerStatusState {
     pub buffer_capacity_frames: Option<Arc<AtomicU64>>,
     /// Terminal playback reason from the current run.
     pub end_reason: Option<PlaybackEndReason>,
+    /// Set while the output device is disconnected and playback waits for it to return.
+    pub output_disconnected: Option<Arc<AtomicBool>>,
+    /// Device access mode in use (`exclusive` or `shared`).
+    pub output_mode: Option<String>,
+    /// Resampler label (see [`ResampleBackend::label`](crate::resample::ResampleBackend::label))
+    /// while resampling.
+    pub resample_quality: Option<String>,
+    /// Output level meter fed by the playback callback.
+    pub levels: Option<Arc<LevelMeter>>,
+    /// Whether the output callback is passing source samples through untouched.
+    pub bit_perfect: Option<Arc<AtomicBool>>,
+    /// For DSD sources, whether the session plays DSD-over-PCM (`false`: converted to PCM).
+    pub dop: Option<bool>,
+    /// Set for endless live streams (internet radio).
+    pub live: Option<bool>,
+    /// Now-playing title published by a live stream's metadata.
+    pub stream_title: Option<Arc<Mutex<Option<String>>>>,
+    /// Corrupt-packet counters of the running decode.
+    pub decode_errors: Option<Arc<DecodeErrorStats>>,
+    /// Chapters of the current file, when it has any.
+    pub chapters: Option<Vec<Chapter>>,
 }
 
 /// Snapshot type returned to bridge HTTP/API layers.
@@ -107,6 +131,30 @@ impl PlayerStatusState {
                 .as_ref()
                 .map(|v| v.load(Ordering::Relaxed)),
             end_reason: self.end_reason,
+            output_disconnected: self
+                .output_disconnected
+                .as_ref()
+                .map(|v| v.load(Ordering::Relaxed)),
+            output_mode: self.output_mode.clone(),
+            resample_quality: self.resample_quality.clone(),
+            levels: self.levels.as_ref().map(|meter| meter.levels()),
+            bit_perfect: self.bit_perfect.as_ref().map(|v| v.load(Ordering::Relaxed)),
+            dop: self.dop,
+            live: self.live,
+            stream_title: self
+                .stream_title
+                .as_ref()
+                .and_then(|t| t.lock().ok().and_then(|t| t.clone())),
+            corrupt_packets: self
+                .decode_errors
+                .as_ref()
+                .map(|e| e.corrupt_packets.load(Ordering::Relaxed)),
+            chapter_index: self
+                .chapters
+                .as_deref()
+                .zip(elapsed_ms)
+                .and_then(|(chapters, ms)| audio_bridge_types::chapter_at(chapters, ms)),
+            chapters: self.chapters.clone(),
         }
     }
 
@@ -131,6 +179,16 @@ impl PlayerStatusState {
         self.buffer_size_frames = None;
         self.buffered_frames = None;
         self.buffer_capacity_frames = None;
+        self.output_disconnected = None;
+        self.output_mode = None;
+        self.resample_quality = None;
+        self.levels = None;
+        self.bit_perfect = None;
+        self.dop = None;
+        self.live = None;
+        self.stream_title = None;
+        self.decode_errors = None;
+        self.chapters = None;
     }
 }
 
@@ -148,6 +206,18 @@ mod tests {
         let snap = state.snapshot();
         assert_eq!(snap.elapsed_ms, Some(2000));
         assert!(snap.paused);
+        assert_eq!(snap.chapter_index, None);
+
+        state.chapters = Some(
+            [0, 1500, 3000]
+                .into_iter()
+                .map(|start_ms| Chapter {
+                    start_ms,
+                    ..Default::default()
+                })
+                .collect(),
+        );
+        assert_eq!(state.snapshot().chapter_index, Some(1));
     }
 
     #[test]
@@ -158,6 +228,9 @@ mod tests {
         state.buffer_capacity_frames = Some(Arc::new(AtomicU64::new(4096)));
         state.underrun_frames = Some(Arc::new(AtomicU64::new(12)));
         state.underrun_events = Some(Arc::new(AtomicU64::new(3)));
+        let decode_errors = Arc::new(DecodeErrorStats::default());
+        decode_errors.corrupt_packets.store(2, Ordering::Relaxed);
+        state.decode_errors = Some(decode_errors);
 
         let snap = state.snapshot();
         assert_eq!(snap.buffer_size_frames, Some(512));
@@ -165,6 +238,7 @@ mod tests {
         assert_eq!(snap.buffer_capacity_frames, Some(4096));
         assert_eq!(snap.underrun_frames, Some(12));
         assert_eq!(snap.underrun_events, Some(3));
+        assert_eq!(snap.corrupt_packets, Some(2));
     }
 
     #[test]
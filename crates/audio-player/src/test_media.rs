@@ -0,0 +1,379 @@
+//! Tiny in-memory media files for decoder tests.
+//!
+//! No encoders are available at test time, so each builder writes the smallest bitstream its
+//! codec accepts: AAC and Vorbis frames that decode to silence, ALAC frames in the codec's
+//! uncompressed escape mode (bit-exact samples), and Ogg Opus headers.
+
+/// Bit writer, most significant bit first (AAC, ALAC).
+#[derive(Default)]
+struct MsbBits {
+    bytes: Vec<u8>,
+    used: u32,
+}
+
+impl MsbBits {
+    fn put(&mut self, value: u32, bits: u32) {
+        for i in (0..bits).rev() {
+            if self.used % 8 == 0 {
+                self.bytes.push(0);
+            }
+            let bit = ((value >> i) & 1) as u8;
+            *self.bytes.last_mut().unwrap() |= bit << (7 - self.used % 8);
+            self.used += 1;
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// Bit writer, least significant bit first (Vorbis).
+#[derive(Default)]
+struct LsbBits {
+    bytes: Vec<u8>,
+    used: u32,
+}
+
+impl LsbBits {
+    fn put(&mut self, value: u32, bits: u32) {
+        for i in 0..bits {
+            if self.used % 8 == 0 {
+                self.bytes.push(0);
+            }
+            let bit = ((value >> i) & 1) as u8;
+            *self.bytes.last_mut().unwrap() |= bit << (self.used % 8);
+            self.used += 1;
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// ADTS AAC-LC stream of `frames` silent mono frames (1024 samples each) at 44.1 kHz.
+pub(crate) fn aac_adts_silence(frames: usize) -> Vec<u8> {
+    // Single channel element with no scale factor bands, then the end element.
+    let mut block = MsbBits::default();
+    block.put(0, 3); // ID_SCE
+    block.put(0, 4); // element instance tag
+    block.put(100, 8); // global gain
+    block.put(0, 1); // ics reserved bit
+    block.put(0, 2); // ONLY_LONG_SEQUENCE
+    block.put(0, 1); // window shape
+    block.put(0, 6); // max_sfb
+    block.put(0, 1); // predictor data present
+    block.put(0, 3); // pulse, tns, gain control data present
+    block.put(7, 3); // ID_END
+    let block = block.finish();
+
+    let frame_len = 7 + block.len() as u32;
+    let mut header = MsbBits::default();
+    header.put(0xfff, 12); // sync word
+    header.put(0, 1); // MPEG-4
+    header.put(0, 2); // layer
+    header.put(1, 1); // no CRC
+    header.put(1, 2); // AAC LC (object type 2, minus one)
+    header.put(4, 4); // 44100 Hz
+    header.put(0, 1); // private bit
+    header.put(1, 3); // mono
+    header.put(0, 4); // original, home, copyright bits
+    header.put(frame_len, 13);
+    header.put(0x7ff, 11); // buffer fullness (VBR)
+    header.put(0, 2); // one raw data block
+    let header = header.finish();
+
+    let mut out = Vec::with_capacity(frames * frame_len as usize);
+    for _ in 0..frames {
+        out.extend_from_slice(&header);
+        out.extend_from_slice(&block);
+    }
+    out
+}
+
+/// Samples per ALAC packet in [`alac_m4a`].
+pub(crate) const ALAC_FRAME_LEN: usize = 1024;
+
+/// M4A file holding mono 16-bit ALAC at `rate`; `samples.len()` must be a multiple of
+/// [`ALAC_FRAME_LEN`].
+pub(crate) fn alac_m4a(rate: u32, samples: &[i16]) -> Vec<u8> {
+    assert!(samples.len().is_multiple_of(ALAC_FRAME_LEN));
+    let packets: Vec<Vec<u8>> = samples
+        .chunks(ALAC_FRAME_LEN)
+        .map(|chunk| {
+            let mut bits = MsbBits::default();
+            bits.put(0, 3); // single channel element
+            bits.put(0, 4); // element instance tag
+            bits.put(0, 12); // unused
+            bits.put(0, 1); // full frame
+            bits.put(0, 2); // no shift
+            bits.put(1, 1); // uncompressed
+            for &sample in chunk {
+                bits.put(sample as u16 as u32, 16);
+            }
+            bits.put(7, 3); // end element
+            bits.finish()
+        })
+        .collect();
+    let total = samples.len() as u32;
+
+    let mut cookie = Vec::new();
+    cookie.extend_from_slice(&(ALAC_FRAME_LEN as u32).to_be_bytes());
+    cookie.extend_from_slice(&[0, 16, 40, 10, 14, 1]); // version, depth, pb, mb, kb, channels
+    cookie.extend_from_slice(&255u16.to_be_bytes()); // max run
+    let max_packet = packets.iter().map(Vec::len).max().unwrap_or(0) as u32;
+    cookie.extend_from_slice(&max_packet.to_be_bytes());
+    cookie.extend_from_slice(&0u32.to_be_bytes()); // average bit rate
+    cookie.extend_from_slice(&rate.to_be_bytes());
+
+    let mut entry = Vec::new();
+    entry.extend_from_slice(&[0; 6]);
+    entry.extend_from_slice(&1u16.to_be_bytes()); // data reference index
+    entry.extend_from_slice(&[0; 8]); // version, revision, vendor
+    entry.extend_from_slice(&1u16.to_be_bytes()); // channels
+    entry.extend_from_slice(&16u16.to_be_bytes()); // sample size
+    entry.extend_from_slice(&[0; 4]); // compression id, packet size
+    entry.extend_from_slice(&(rate << 16).to_be_bytes());
+    entry.extend(full_atom(b"alac", 0, &cookie));
+    let stsd = full_atom(
+        b"stsd",
+        0,
+        &[&1u32.to_be_bytes()[..], &atom(b"alac", &entry)].concat(),
+    );
+
+    let stts = full_atom(
+        b"stts",
+        0,
+        &[1, packets.len() as u32, ALAC_FRAME_LEN as u32]
+            .iter()
+            .flat_map(|v| v.to_be_bytes())
+            .collect::<Vec<_>>(),
+    );
+    let stsc = full_atom(
+        b"stsc",
+        0,
+        &[1u32, 1, packets.len() as u32, 1]
+            .iter()
+            .flat_map(|v| v.to_be_bytes())
+            .collect::<Vec<_>>(),
+    );
+    let mut stsz_body = vec![0; 4];
+    stsz_body.extend_from_slice(&(packets.len() as u32).to_be_bytes());
+    for packet in &packets {
+        stsz_body.extend_from_slice(&(packet.len() as u32).to_be_bytes());
+    }
+    let stsz = full_atom(b"stsz", 0, &stsz_body);
+
+    let ftyp = atom(b"ftyp", b"M4A \0\0\0\0M4A mp42isom");
+    // `stco` is patched once the `moov` size (and so the `mdat` offset) is known.
+    let build_moov = |chunk_offset: u32| {
+        let stco = full_atom(
+            b"stco",
+            0,
+            &[1u32, chunk_offset]
+                .iter()
+                .flat_map(|v| v.to_be_bytes())
+                .collect::<Vec<_>>(),
+        );
+        let stbl = atom(b"stbl", &[&stsd[..], &stts, &stsc, &stsz, &stco].concat());
+        let dref = full_atom(
+            b"dref",
+            0,
+            &[&1u32.to_be_bytes()[..], &full_atom(b"url ", 1, &[])].concat(),
+        );
+        let minf = atom(
+            b"minf",
+            &[full_atom(b"smhd", 0, &[0; 4]), atom(b"dinf", &dref), stbl].concat(),
+        );
+        let mut mdhd = Vec::new();
+        mdhd.extend_from_slice(&[0; 8]);
+        mdhd.extend_from_slice(&rate.to_be_bytes());
+        mdhd.extend_from_slice(&total.to_be_bytes());
+        mdhd.extend_from_slice(&[0x55, 0xc4, 0, 0]);
+        let mut hdlr = vec![0; 4];
+        hdlr.extend_from_slice(b"soun");
+        hdlr.extend_from_slice(&[0; 13]);
+        let mdia = atom(
+            b"mdia",
+            &[
+                full_atom(b"mdhd", 0, &mdhd),
+                full_atom(b"hdlr", 0, &hdlr),
+                minf,
+            ]
+            .concat(),
+        );
+        let mut tkhd = vec![0; 8];
+        tkhd.extend_from_slice(&1u32.to_be_bytes()); // track id
+        tkhd.extend_from_slice(&[0; 4]);
+        tkhd.extend_from_slice(&total.to_be_bytes());
+        tkhd.extend_from_slice(&[0; 8]);
+        tkhd.extend_from_slice(&[0, 0, 0, 0, 1, 0, 0, 0]); // layer, group, volume
+        tkhd.extend(identity_matrix());
+        tkhd.extend_from_slice(&[0; 8]);
+        let trak = atom(b"trak", &[full_atom(b"tkhd", 7, &tkhd), mdia].concat());
+        let mut mvhd = vec![0; 8];
+        mvhd.extend_from_slice(&rate.to_be_bytes());
+        mvhd.extend_from_slice(&total.to_be_bytes());
+        mvhd.extend_from_slice(&0x0001_0000u32.to_be_bytes()); // rate 1.0
+        mvhd.extend_from_slice(&[1, 0]); // volume 1.0
+        mvhd.extend_from_slice(&[0; 10]);
+        mvhd.extend(identity_matrix());
+        mvhd.extend_from_slice(&[0; 24]);
+        mvhd.extend_from_slice(&2u32.to_be_bytes()); // next track id
+        atom(b"moov", &[full_atom(b"mvhd", 0, &mvhd), trak].concat())
+    };
+    let moov_len = build_moov(0).len();
+    let moov = build_moov((ftyp.len() + moov_len + 8) as u32);
+    let mdat = atom(b"mdat", &packets.concat());
+    [ftyp, moov, mdat].concat()
+}
+
+/// Ogg Vorbis stream at `rate` with `channels` channels and `packets` silent short blocks.
+///
+/// The first block only primes the overlap, so the stream decodes to `(packets - 1) * 128`
+/// frames.
+pub(crate) fn ogg_vorbis_silence(rate: u32, channels: u8, packets: usize) -> Vec<u8> {
+    let mut ident = vec![1];
+    ident.extend_from_slice(b"vorbis");
+    ident.extend_from_slice(&0u32.to_le_bytes());
+    ident.push(channels);
+    ident.extend_from_slice(&rate.to_le_bytes());
+    ident.extend_from_slice(&[0; 12]); // bitrates
+    ident.push(0xb8); // block sizes 256 and 2048
+    ident.push(1);
+
+    let mut comment = vec![3];
+    comment.extend_from_slice(b"vorbis");
+    comment.extend_from_slice(&4u32.to_le_bytes());
+    comment.extend_from_slice(b"test");
+    comment.extend_from_slice(&0u32.to_le_bytes());
+    comment.push(1);
+
+    let mut bits = LsbBits::default();
+    bits.put(0, 8); // one codebook: 1 dimension, two 1-bit entries, no lookup
+    bits.put(0x564342, 24);
+    bits.put(1, 16);
+    bits.put(2, 24);
+    bits.put(0, 2); // not ordered, not sparse
+    bits.put(0, 5);
+    bits.put(0, 5);
+    bits.put(0, 4);
+    bits.put(0, 6); // one time-domain placeholder
+    bits.put(0, 16);
+    bits.put(0, 6); // one floor 1 without partitions
+    bits.put(1, 16);
+    bits.put(0, 5);
+    bits.put(0, 2);
+    bits.put(8, 4);
+    bits.put(0, 6); // one empty residue 0
+    bits.put(0, 16);
+    bits.put(0, 24);
+    bits.put(0, 24);
+    bits.put(0, 24);
+    bits.put(0, 6);
+    bits.put(0, 8);
+    bits.put(0, 4);
+    bits.put(0, 6); // one mapping: one submap, no coupling
+    bits.put(0, 16);
+    bits.put(0, 4);
+    bits.put(0, 24);
+    bits.put(0, 6); // one short-block mode
+    bits.put(0, 1);
+    bits.put(0, 32);
+    bits.put(0, 8);
+    bits.put(1, 1); // framing
+    let mut setup = vec![5];
+    setup.extend_from_slice(b"vorbis");
+    setup.extend(bits.finish());
+
+    // Audio packet: packet type, then an unused floor per channel.
+    let audio = vec![vec![0u8]; packets];
+    let granule = (packets.saturating_sub(1) * 128) as i64;
+    let mut out = ogg_page(0x02, 0, 0, &[ident]);
+    out.extend(ogg_page(0, 0, 1, &[comment, setup]));
+    out.extend(ogg_page(0x04, granule, 2, &audio));
+    out
+}
+
+/// Ogg Opus stream (`OpusHead`, `OpusTags` and one audio packet).
+pub(crate) fn ogg_opus(channels: u8) -> Vec<u8> {
+    let mut head = b"OpusHead".to_vec();
+    head.push(1);
+    head.push(channels);
+    head.extend_from_slice(&312u16.to_le_bytes()); // pre-skip
+    head.extend_from_slice(&48_000u32.to_le_bytes());
+    head.extend_from_slice(&0i16.to_le_bytes());
+    head.push(0);
+    let mut tags = b"OpusTags".to_vec();
+    tags.extend_from_slice(&4u32.to_le_bytes());
+    tags.extend_from_slice(b"test");
+    tags.extend_from_slice(&0u32.to_le_bytes());
+    let mut out = ogg_page(0x02, 0, 0, &[head]);
+    out.extend(ogg_page(0, 0, 1, &[tags]));
+    out.extend(ogg_page(0x04, 960, 2, &[vec![0xf8, 0xff, 0xfe]]));
+    out
+}
+
+/// One Ogg page carrying `packets` whole.
+fn ogg_page(flags: u8, granule: i64, sequence: u32, packets: &[Vec<u8>]) -> Vec<u8> {
+    let mut lacing = Vec::new();
+    for packet in packets {
+        lacing.extend(std::iter::repeat_n(255u8, packet.len() / 255));
+        lacing.push((packet.len() % 255) as u8);
+    }
+    let mut page = b"OggS".to_vec();
+    page.push(0);
+    page.push(flags);
+    page.extend_from_slice(&granule.to_le_bytes());
+    page.extend_from_slice(&0x5eed_u32.to_le_bytes()); // serial
+    page.extend_from_slice(&sequence.to_le_bytes());
+    page.extend_from_slice(&[0; 4]); // checksum
+    page.push(lacing.len() as u8);
+    page.extend(lacing);
+    for packet in packets {
+        page.extend_from_slice(packet);
+    }
+    let crc = ogg_crc(&page);
+    page[22..26].copy_from_slice(&crc.to_le_bytes());
+    page
+}
+
+/// Ogg page checksum (CRC-32, polynomial 0x04c11db7, unreflected, zero init).
+fn ogg_crc(data: &[u8]) -> u32 {
+    data.iter().fold(0u32, |mut crc, &byte| {
+        crc ^= u32::from(byte) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04c1_1db7
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// ISO BMFF box.
+fn atom(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut out = ((8 + body.len()) as u32).to_be_bytes().to_vec();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(body);
+    out
+}
+
+/// ISO BMFF full box (version 0).
+fn full_atom(kind: &[u8; 4], flags: u32, body: &[u8]) -> Vec<u8> {
+    atom(
+        kind,
+        &[&(flags & 0x00ff_ffff).to_be_bytes()[..], body].concat(),
+    )
+}
+
+/// Unity transformation matrix for `mvhd`/`tkhd`.
+fn identity_matrix() -> Vec<u8> {
+    [0x0001_0000u32, 0, 0, 0, 0x0001_0000, 0, 0, 0, 0x4000_0000]
+        .iter()
+        .flat_map(|v| v.to_be_bytes())
+        .collect()
+}
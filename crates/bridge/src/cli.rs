@@ -1,7 +1,13 @@
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use audio_player::config::{
+    DecodeErrorAction, DecodeErrorPolicy, DsdOutput, RateSwitch, SilenceTrim, UnderrunConcealment,
+};
+use audio_player::device::{JackPorts, OutputBackend};
+use audio_player::null_output::NullPace;
+use audio_player::resample::{ResampleBackend, ResampleQuality};
+use clap::{Parser, Subcommand, ValueEnum};
 
 const VERSION: &str = concat!(
     env!("CARGO_PKG_VERSION"),
@@ -24,10 +30,111 @@ pub struct Args {
     #[arg(long)]
     pub list_devices: bool,
 
-    /// Use a specific output device by substring match
+    /// Output device: stable id (`dev-…`) or alias, platform id, ALSA PCM (`hw:0,0`, bypasses
+    /// the sound server), name substring, or `default-follow` (tracks the OS default)
     #[arg(long)]
     pub device: Option<String>,
 
+    /// JSON file holding device aliases set through `/devices/alias` (default:
+    /// `audio-bridge/device-aliases.json` in the user config directory)
+    #[arg(long)]
+    pub device_aliases: Option<PathBuf>,
+
+    /// Hide an output device (same selector forms as `--device`, or its exact name) from
+    /// `/devices` and selection; repeat to hide several
+    #[arg(long = "hide-device", value_name = "SELECTOR")]
+    pub hide_devices: Vec<String>,
+
+    /// Device to use while the selected one is unavailable (same selector forms as `--device`);
+    /// repeat in priority order
+    #[arg(long = "fallback-device", value_name = "SELECTOR")]
+    pub fallback_devices: Vec<String>,
+
+    /// When playback leaves a fallback device: `track` (back to the selected device at the next
+    /// track once it reappears) or `never` (stay until the fallback goes away)
+    #[arg(long, value_enum, default_value_t = FallbackReturnArg::Track)]
+    pub fallback_return: FallbackReturnArg,
+
+    /// Mirror playback to a second output device (same selector forms as `--device`), with its
+    /// own volume via `/mirror`
+    #[arg(long)]
+    pub mirror_device: Option<String>,
+
+    /// Record the output sample stream (post-volume, pre-device) to a 24-bit `.wav` or `.flac`
+    /// file; a rate/channel change starts `<name>-2.<ext>` and so on
+    #[arg(long)]
+    pub record: Option<PathBuf>,
+
+    /// Audio backend for output devices (`jack` routes into a running JACK server, `null`
+    /// discards audio without hardware)
+    #[arg(long, value_enum, default_value_t = Backend::System)]
+    pub backend: Backend,
+
+    /// JACK port wiring: `system` (physical playback ports), `none`, or a comma-separated list
+    /// of destination ports/regexes assigned to output channels in order
+    #[arg(long)]
+    pub jack_connect: Option<String>,
+
+    /// Null backend pacing: `realtime` (like a device) or `fast` (drain as fast as decoded)
+    #[arg(long, value_enum)]
+    pub null_pace: Option<NullPaceArg>,
+
+    /// When the source rate differs from the device's: `prefer-switch` reconfigures the device to
+    /// the source rate if supported, `prefer-resample` keeps the device rate and resamples
+    #[arg(long, value_enum, default_value_t = RateSwitchArg::PreferSwitch)]
+    pub rate_switch: RateSwitchArg,
+
+    /// Resampler quality preset: `fast` (low CPU, e.g. Raspberry Pi), `balanced`, or `high`
+    #[arg(long, value_enum, default_value_t = ResampleQualityArg::Balanced)]
+    pub resample_quality: ResampleQualityArg,
+
+    /// Resampler implementation: `sinc` (default), `linear`/`cubic` (cheapest, for weak ARM
+    /// hosts; may alias when downsampling), or `soxr` (libsoxr, `soxr` feature builds only)
+    #[arg(long, value_enum, default_value_t = ResamplerArg::Sinc)]
+    pub resampler: ResamplerArg,
+
+    /// What the output plays when its buffer runs dry: `zeros` (default) or `fade` (hold the
+    /// last sample and fade out/in over a few ms, for DACs that click on hard gaps)
+    #[arg(long, value_enum, default_value_t = UnderrunConcealmentArg::Zeros)]
+    pub underrun_concealment: UnderrunConcealmentArg,
+
+    /// Silence (ms) played each time the output stream opens, for HDMI/AVR outputs that clip
+    /// the start of a stream; the hub can override it per output
+    #[arg(long, default_value_t = 0)]
+    pub preroll_silence_ms: u32,
+
+    /// Audio (ms) to buffer before the output starts, for sources slow to deliver their first
+    /// seconds; capped by `--buffer-seconds`
+    #[arg(long, default_value_t = 0)]
+    pub min_prebuffer_ms: u32,
+
+    /// Skip digital silence at the start and end of each track (up to `--trim-silence-max-ms`
+    /// at each end); not applied to live streams or after seeks
+    #[arg(long)]
+    pub trim_silence: bool,
+
+    /// Level (dBFS) below which a frame counts as silence for `--trim-silence`
+    #[arg(long, default_value_t = SilenceTrim::DEFAULT_THRESHOLD_DB, allow_negative_numbers = true)]
+    pub trim_silence_threshold_db: f32,
+
+    /// Most silence `--trim-silence` removes at each end of a track (ms)
+    #[arg(long, default_value_t = SilenceTrim::DEFAULT_MAX_MS)]
+    pub trim_silence_max_ms: u32,
+
+    /// What replaces a corrupt packet: `skip` it, play `silence` for its duration, or `abort`
+    /// the track
+    #[arg(long, value_enum, default_value_t = DecodeErrorArg::Skip)]
+    pub decode_errors: DecodeErrorArg,
+
+    /// Corrupt packets in a row after which a track is abandoned
+    #[arg(long, default_value_t = DecodeErrorPolicy::DEFAULT_MAX_CONSECUTIVE)]
+    pub decode_error_limit: u32,
+
+    /// How DSD (.dsf/.dff) files play: `auto` (DSD-over-PCM when the DAC accepts 32-bit PCM at
+    /// the DoP rate, PCM conversion otherwise) or `pcm` (always convert)
+    #[arg(long, value_enum, default_value_t = DsdArg::Auto)]
+    pub dsd: DsdArg,
+
     /// Resampler input chunk size in frames (higher => more latency, lower => more overhead)
     #[arg(long, default_value_t = 1024)]
     pub chunk_frames: usize,
@@ -40,10 +147,18 @@ pub struct Args {
     #[arg(long, default_value_t = 2.0)]
     pub buffer_seconds: f32,
 
-    /// HTTP API bind address, e.g. 0.0.0.0:5556
+    /// HTTP API bind address, e.g. 0.0.0.0:5556 (use an interface IP to listen on one interface only)
     #[arg(long, default_value = "0.0.0.0:5556")]
     pub http_bind: SocketAddr,
 
+    /// Local IP for outgoing hub connections (audio streams, unregister) on multi-homed hosts
+    #[arg(long)]
+    pub source_ip: Option<IpAddr>,
+
+    /// DSCP class marked on outgoing hub connections: EF, AF11-AF43, CS0-CS7, or 0-63
+    #[arg(long)]
+    pub dscp: Option<String>,
+
     /// Allow insecure TLS when streaming from the hub.
     #[arg(long, default_value_t = false)]
     pub tls_insecure: bool,
@@ -52,9 +167,547 @@ pub struct Args {
     #[arg(long)]
     pub hub_url: Option<String>,
 
+    /// Room label advertised over mDNS so the hub can group this bridge's outputs
+    #[arg(long)]
+    pub room: Option<String>,
+
+    /// Zone label (e.g. a floor or area spanning several rooms) advertised over mDNS
+    #[arg(long)]
+    pub zone: Option<String>,
+
     /// Expose synthetic dummy outputs for end-to-end testing.
     #[arg(long, default_value_t = false)]
     pub enable_dummy_outputs: bool,
+
+    /// Validate the configuration, print all problems, and exit
+    #[arg(long)]
+    pub check_config: bool,
+}
+
+impl Args {
+    /// Range/path problems in the parsed arguments (empty when valid).
+    ///
+    /// Device availability is checked separately since it needs the audio host.
+    pub fn config_problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !(MIN_FRAMES..=MAX_FRAMES).contains(&self.chunk_frames) {
+            problems.push(format!(
+                "--chunk-frames: must be between {MIN_FRAMES} and {MAX_FRAMES} (got {})",
+                self.chunk_frames
+            ));
+        }
+        if !(MIN_FRAMES..=MAX_FRAMES).contains(&self.refill_max_frames) {
+            problems.push(format!(
+                "--refill-max-frames: must be between {MIN_FRAMES} and {MAX_FRAMES} (got {})",
+                self.refill_max_frames
+            ));
+        }
+        if !(MIN_BUFFER_SECONDS..=MAX_BUFFER_SECONDS).contains(&self.buffer_seconds) {
+            problems.push(format!(
+                "--buffer-seconds: must be between {MIN_BUFFER_SECONDS} and {MAX_BUFFER_SECONDS} (got {})",
+                self.buffer_seconds
+            ));
+        }
+        if self.preroll_silence_ms > MAX_PREROLL_SILENCE_MS {
+            problems.push(format!(
+                "--preroll-silence-ms: must be at most {MAX_PREROLL_SILENCE_MS} (got {})",
+                self.preroll_silence_ms
+            ));
+        }
+        if self.min_prebuffer_ms > MAX_MIN_PREBUFFER_MS {
+            problems.push(format!(
+                "--min-prebuffer-ms: must be at most {MAX_MIN_PREBUFFER_MS} (got {})",
+                self.min_prebuffer_ms
+            ));
+        }
+        if !(1..=MAX_DECODE_ERROR_LIMIT).contains(&self.decode_error_limit) {
+            problems.push(format!(
+                "--decode-error-limit: must be between 1 and {MAX_DECODE_ERROR_LIMIT} (got {})",
+                self.decode_error_limit
+            ));
+        }
+        if !(MIN_TRIM_THRESHOLD_DB..=MAX_TRIM_THRESHOLD_DB)
+            .contains(&self.trim_silence_threshold_db)
+        {
+            problems.push(format!(
+                "--trim-silence-threshold-db: must be between {MIN_TRIM_THRESHOLD_DB} and {MAX_TRIM_THRESHOLD_DB} (got {})",
+                self.trim_silence_threshold_db
+            ));
+        }
+        if self.trim_silence_max_ms > MAX_TRIM_SILENCE_MS {
+            problems.push(format!(
+                "--trim-silence-max-ms: must be at most {MAX_TRIM_SILENCE_MS} (got {})",
+                self.trim_silence_max_ms
+            ));
+        }
+        if self.backend == Backend::Jack && !audio_player::device::jack_supported() {
+            problems.push(
+                "--backend: jack is not available in this build (enable the `jack` feature)"
+                    .to_string(),
+            );
+        }
+        if let Some(spec) = self.jack_connect.as_deref() {
+            if self.backend != Backend::Jack {
+                problems.push("--jack-connect: requires --backend jack".to_string());
+            } else if let Err(e) = spec.parse::<JackPorts>() {
+                problems.push(format!("--jack-connect: {e}"));
+            }
+        }
+        if self.null_pace.is_some() && self.backend != Backend::Null {
+            problems.push("--null-pace: requires --backend null".to_string());
+        }
+        if self.http_bind.port() == 0 {
+            problems.push("--http-bind: port must be between 1 and 65535".to_string());
+        }
+        if let Some(dscp) = self.dscp.as_deref()
+            && audio_bridge_types::parse_dscp(dscp).is_none()
+        {
+            problems.push(format!(
+                "--dscp: expected EF, AF11-AF43, CS0-CS7, or 0-63 (got `{dscp}`)"
+            ));
+        }
+        if let Some(url) = self.hub_url.as_deref()
+            && !(url.starts_with("http://") || url.starts_with("https://"))
+        {
+            problems.push(format!(
+                "--hub-url: must start with http:// or https:// (got `{url}`)"
+            ));
+        }
+        if let Some(path) = self.record.as_deref() {
+            if let Err(e) = audio_player::record::RecordFormat::from_path(path) {
+                problems.push(format!("--record: {e}"));
+            } else if let Some(dir) = path.parent()
+                && !dir.as_os_str().is_empty()
+                && !dir.is_dir()
+            {
+                problems.push(format!(
+                    "--record: directory does not exist: {}",
+                    dir.display()
+                ));
+            }
+        }
+        if let Command::Play { path } = &self.cmd
+            && !path.is_file()
+        {
+            problems.push(format!("play: file does not exist: {}", path.display()));
+        }
+        problems
+    }
+
+    /// Global options to persist in a service registration, in CLI form.
+    pub fn service_args(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        if let Some(device) = self.device.as_deref() {
+            out.extend(["--device".to_string(), device.to_string()]);
+        }
+        if let Some(device) = self.mirror_device.as_deref() {
+            out.extend(["--mirror-device".to_string(), device.to_string()]);
+        }
+        if let Some(path) = self.device_aliases.as_deref() {
+            out.extend(["--device-aliases".to_string(), path.display().to_string()]);
+        }
+        for selector in &self.hide_devices {
+            out.extend(["--hide-device".to_string(), selector.clone()]);
+        }
+        for selector in &self.fallback_devices {
+            out.extend(["--fallback-device".to_string(), selector.clone()]);
+        }
+        if self.fallback_return != FallbackReturnArg::Track {
+            out.extend([
+                "--fallback-return".to_string(),
+                self.fallback_return.as_str().to_string(),
+            ]);
+        }
+        if let Some(path) = self.record.as_deref() {
+            out.extend(["--record".to_string(), path.display().to_string()]);
+        }
+        if self.backend != Backend::System {
+            out.extend(["--backend".to_string(), self.backend.as_str().to_string()]);
+        }
+        if let Some(spec) = self.jack_connect.as_deref() {
+            out.extend(["--jack-connect".to_string(), spec.to_string()]);
+        }
+        if self.rate_switch != RateSwitchArg::PreferSwitch {
+            out.extend([
+                "--rate-switch".to_string(),
+                self.rate_switch.as_str().to_string(),
+            ]);
+        }
+        if self.resample_quality != ResampleQualityArg::Balanced {
+            out.extend([
+                "--resample-quality".to_string(),
+                self.resample_quality.as_str().to_string(),
+            ]);
+        }
+        if self.resampler != ResamplerArg::Sinc {
+            out.extend([
+                "--resampler".to_string(),
+                self.resampler.as_str().to_string(),
+            ]);
+        }
+        if self.underrun_concealment != UnderrunConcealmentArg::Zeros {
+            out.extend([
+                "--underrun-concealment".to_string(),
+                self.underrun_concealment.as_str().to_string(),
+            ]);
+        }
+        if self.preroll_silence_ms > 0 {
+            out.extend([
+                "--preroll-silence-ms".to_string(),
+                self.preroll_silence_ms.to_string(),
+            ]);
+        }
+        if self.min_prebuffer_ms > 0 {
+            out.extend([
+                "--min-prebuffer-ms".to_string(),
+                self.min_prebuffer_ms.to_string(),
+            ]);
+        }
+        if self.trim_silence {
+            out.extend([
+                "--trim-silence".to_string(),
+                "--trim-silence-threshold-db".to_string(),
+                self.trim_silence_threshold_db.to_string(),
+                "--trim-silence-max-ms".to_string(),
+                self.trim_silence_max_ms.to_string(),
+            ]);
+        }
+        if self.decode_errors != DecodeErrorArg::Skip {
+            out.extend([
+                "--decode-errors".to_string(),
+                self.decode_errors.as_str().to_string(),
+            ]);
+        }
+        if self.decode_error_limit != DecodeErrorPolicy::DEFAULT_MAX_CONSECUTIVE {
+            out.extend([
+                "--decode-error-limit".to_string(),
+                self.decode_error_limit.to_string(),
+            ]);
+        }
+        if self.dsd != DsdArg::Auto {
+            out.extend(["--dsd".to_string(), self.dsd.as_str().to_string()]);
+        }
+        if let Some(pace) = self.null_pace {
+            out.extend(["--null-pace".to_string(), pace.as_str().to_string()]);
+        }
+        out.extend([
+            "--chunk-frames".to_string(),
+            self.chunk_frames.to_string(),
+            "--refill-max-frames".to_string(),
+            self.refill_max_frames.to_string(),
+            "--buffer-seconds".to_string(),
+            self.buffer_seconds.to_string(),
+            "--http-bind".to_string(),
+            self.http_bind.to_string(),
+        ]);
+        if self.tls_insecure {
+            out.push("--tls-insecure".to_string());
+        }
+        if let Some(ip) = self.source_ip {
+            out.extend(["--source-ip".to_string(), ip.to_string()]);
+        }
+        if let Some(dscp) = self.dscp.as_deref() {
+            out.extend(["--dscp".to_string(), dscp.to_string()]);
+        }
+        if let Some(url) = self.hub_url.as_deref() {
+            out.extend(["--hub-url".to_string(), url.to_string()]);
+        }
+        if let Some(room) = self.room.as_deref() {
+            out.extend(["--room".to_string(), room.to_string()]);
+        }
+        if let Some(zone) = self.zone.as_deref() {
+            out.extend(["--zone".to_string(), zone.to_string()]);
+        }
+        if self.enable_dummy_outputs {
+            out.push("--enable-dummy-outputs".to_string());
+        }
+        out
+    }
+
+    /// Alias file from `--device-aliases`, or the default location.
+    pub fn device_aliases_path(&self) -> Option<PathBuf> {
+        self.device_aliases
+            .clone()
+            .or_else(crate::device_aliases::default_path)
+    }
+
+    /// Output backend selected by `--backend`/`--jack-connect`/`--null-pace`.
+    ///
+    /// An unparsable `--jack-connect` falls back to the physical ports; `config_problems`
+    /// reports it.
+    pub fn output_backend(&self) -> OutputBackend {
+        match self.backend {
+            Backend::System => OutputBackend::System,
+            Backend::Jack => OutputBackend::Jack(
+                self.jack_connect
+                    .as_deref()
+                    .and_then(|spec| spec.parse().ok())
+                    .unwrap_or_default(),
+            ),
+            Backend::Null => OutputBackend::Null(match self.null_pace {
+                Some(NullPaceArg::Fast) => NullPace::Fast,
+                Some(NullPaceArg::Realtime) | None => NullPace::Realtime,
+            }),
+        }
+    }
+}
+
+/// Audio backend choices for `--backend`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// Platform default (ALSA, CoreAudio, WASAPI)
+    System,
+    /// JACK Audio Connection Kit
+    Jack,
+    /// No audio hardware (headless testing)
+    Null,
+}
+
+impl Backend {
+    /// CLI spelling of the backend.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Backend::System => "system",
+            Backend::Jack => "jack",
+            Backend::Null => "null",
+        }
+    }
+}
+
+/// Pacing choices for `--null-pace`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NullPaceArg {
+    /// Consume one period per period duration
+    Realtime,
+    /// Consume as fast as audio is decoded
+    Fast,
+}
+
+impl NullPaceArg {
+    /// CLI spelling of the pace.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NullPaceArg::Realtime => "realtime",
+            NullPaceArg::Fast => "fast",
+        }
+    }
+}
+
+/// Output rate preference choices for `--rate-switch`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RateSwitchArg {
+    /// Switch the device to the source rate when supported
+    PreferSwitch,
+    /// Keep the device rate and resample
+    PreferResample,
+}
+
+impl RateSwitchArg {
+    /// CLI spelling of the preference.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RateSwitchArg::PreferSwitch => "prefer-switch",
+            RateSwitchArg::PreferResample => "prefer-resample",
+        }
+    }
+}
+
+impl From<RateSwitchArg> for RateSwitch {
+    fn from(arg: RateSwitchArg) -> Self {
+        match arg {
+            RateSwitchArg::PreferSwitch => RateSwitch::PreferSwitch,
+            RateSwitchArg::PreferResample => RateSwitch::PreferResample,
+        }
+    }
+}
+
+/// Resampler preset choices for `--resample-quality`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResampleQualityArg {
+    /// Short sinc, linear interpolation
+    Fast,
+    /// Default sinc length and oversampling
+    Balanced,
+    /// Long sinc, heavy oversampling
+    High,
+}
+
+impl ResampleQualityArg {
+    /// CLI spelling of the preset.
+    pub fn as_str(self) -> &'static str {
+        ResampleQuality::from(self).as_str()
+    }
+}
+
+impl From<ResampleQualityArg> for ResampleQuality {
+    fn from(arg: ResampleQualityArg) -> Self {
+        match arg {
+            ResampleQualityArg::Fast => ResampleQuality::Fast,
+            ResampleQualityArg::Balanced => ResampleQuality::Balanced,
+            ResampleQualityArg::High => ResampleQuality::High,
+        }
+    }
+}
+
+/// Resampler choices for `--resampler`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResamplerArg {
+    /// Rubato windowed sinc
+    Sinc,
+    /// Linear interpolation
+    Linear,
+    /// Cubic (Catmull-Rom) interpolation
+    Cubic,
+    /// libsoxr
+    #[cfg(feature = "soxr")]
+    Soxr,
+}
+
+impl ResamplerArg {
+    /// CLI spelling of the resampler.
+    pub fn as_str(self) -> &'static str {
+        ResampleBackend::from(self).as_str()
+    }
+}
+
+impl From<ResamplerArg> for ResampleBackend {
+    fn from(arg: ResamplerArg) -> Self {
+        match arg {
+            ResamplerArg::Sinc => ResampleBackend::Sinc,
+            ResamplerArg::Linear => ResampleBackend::Linear,
+            ResamplerArg::Cubic => ResampleBackend::Cubic,
+            #[cfg(feature = "soxr")]
+            ResamplerArg::Soxr => ResampleBackend::Soxr,
+        }
+    }
+}
+
+/// Underrun fill choices for `--underrun-concealment`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnderrunConcealmentArg {
+    /// Hard zeros
+    Zeros,
+    /// Fade the held sample out and the returning audio in
+    Fade,
+}
+
+impl UnderrunConcealmentArg {
+    /// CLI spelling of the strategy.
+    pub fn as_str(self) -> &'static str {
+        UnderrunConcealment::from(self).as_str()
+    }
+}
+
+impl From<UnderrunConcealmentArg> for UnderrunConcealment {
+    fn from(arg: UnderrunConcealmentArg) -> Self {
+        match arg {
+            UnderrunConcealmentArg::Zeros => UnderrunConcealment::Zeros,
+            UnderrunConcealmentArg::Fade => UnderrunConcealment::Fade,
+        }
+    }
+}
+
+/// Corrupt packet handling for `--decode-errors`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeErrorArg {
+    /// Drop the packet
+    Skip,
+    /// Play silence in its place
+    Silence,
+    /// Stop the track
+    Abort,
+}
+
+impl DecodeErrorArg {
+    /// CLI spelling of the action.
+    pub fn as_str(self) -> &'static str {
+        DecodeErrorAction::from(self).as_str()
+    }
+}
+
+impl From<DecodeErrorArg> for DecodeErrorAction {
+    fn from(arg: DecodeErrorArg) -> Self {
+        match arg {
+            DecodeErrorArg::Skip => DecodeErrorAction::Skip,
+            DecodeErrorArg::Silence => DecodeErrorAction::Silence,
+            DecodeErrorArg::Abort => DecodeErrorAction::Abort,
+        }
+    }
+}
+
+/// Return policy choices for `--fallback-return`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FallbackReturnArg {
+    /// Return to the selected device at the next track boundary
+    Track,
+    /// Stay on the fallback device while it is available
+    Never,
+}
+
+impl FallbackReturnArg {
+    /// CLI spelling of the policy.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FallbackReturnArg::Track => "track",
+            FallbackReturnArg::Never => "never",
+        }
+    }
+}
+
+/// DSD playback choices for `--dsd`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DsdArg {
+    /// DSD-over-PCM when the output supports it
+    Auto,
+    /// Always convert to PCM
+    Pcm,
+}
+
+impl DsdArg {
+    /// CLI spelling of the mode.
+    pub fn as_str(self) -> &'static str {
+        DsdOutput::from(self).as_str()
+    }
+}
+
+impl From<DsdArg> for DsdOutput {
+    fn from(arg: DsdArg) -> Self {
+        match arg {
+            DsdArg::Auto => DsdOutput::Auto,
+            DsdArg::Pcm => DsdOutput::Pcm,
+        }
+    }
+}
+
+const MIN_FRAMES: usize = 16;
+const MAX_FRAMES: usize = 65_536;
+const MIN_BUFFER_SECONDS: f32 = 0.1;
+const MAX_BUFFER_SECONDS: f32 = 60.0;
+/// Upper bound for pre-roll silence, from the CLI or the hub.
+pub(crate) const MAX_PREROLL_SILENCE_MS: u32 = 5_000;
+const MAX_MIN_PREBUFFER_MS: u32 = 10_000;
+const MIN_TRIM_THRESHOLD_DB: f32 = -120.0;
+const MAX_TRIM_THRESHOLD_DB: f32 = -30.0;
+const MAX_TRIM_SILENCE_MS: u32 = 30_000;
+const MAX_DECODE_ERROR_LIMIT: u32 = 10_000;
+
+impl Args {
+    /// Silence trimming selected by `--trim-silence*` (`None` when off).
+    pub fn silence_trim(&self) -> Option<SilenceTrim> {
+        self.trim_silence.then(|| SilenceTrim {
+            threshold_db: self.trim_silence_threshold_db,
+            max_ms: self.trim_silence_max_ms,
+            ..SilenceTrim::default()
+        })
+    }
+
+    /// Corrupt packet handling selected by `--decode-errors` and `--decode-error-limit`.
+    pub fn decode_errors(&self) -> DecodeErrorPolicy {
+        DecodeErrorPolicy {
+            action: self.decode_errors.into(),
+            max_consecutive: self.decode_error_limit,
+        }
+    }
 }
 
 /// Bridge subcommands.
@@ -68,4 +721,251 @@ pub enum Command {
 
     /// Run the bridge HTTP API for remote playback control
     Listen,
+
+    /// Replay a hub stream capture (`[stream_capture]`) through the local playback path
+    Replay {
+        /// Capture `.log` file, or a directory of captures from one session
+        capture: PathBuf,
+        /// Pacing relative to the original send times (`2` = twice as fast, `0` = unpaced)
+        #[arg(long, default_value_t = 1.0)]
+        speed: f64,
+        /// Only serve the capture over HTTP; point a running bridge's `/play` at the printed URL
+        #[arg(long)]
+        serve_only: bool,
+        /// Address of the replay HTTP listener
+        #[arg(long, default_value = "127.0.0.1:0")]
+        bind: std::net::SocketAddr,
+    },
+
+    /// Register, remove, or host the bridge as an OS service (launchd agent / Windows service)
+    Service {
+        /// Service action.
+        #[command(subcommand)]
+        action: ServiceAction,
+    },
+}
+
+/// Default service name (Windows service name / launchd label suffix).
+pub const DEFAULT_SERVICE_NAME: &str = "audio-bridge";
+
+/// `bridge service` actions.
+#[derive(Subcommand, Debug)]
+pub enum ServiceAction {
+    /// Register the bridge with the current global options and start it
+    Install {
+        /// Service name
+        #[arg(long, default_value = DEFAULT_SERVICE_NAME)]
+        name: String,
+    },
+    /// Stop and remove a previously installed service
+    Uninstall {
+        /// Service name
+        #[arg(long, default_value = DEFAULT_SERVICE_NAME)]
+        name: String,
+    },
+    /// Service entry point invoked by the service manager; stop requests drain playback
+    Run {
+        /// Service name
+        #[arg(long, default_value = DEFAULT_SERVICE_NAME)]
+        name: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_problems_empty_for_defaults() {
+        let args = Args::parse_from(["bridge", "listen"]);
+        assert!(args.config_problems().is_empty());
+    }
+
+    #[test]
+    fn config_problems_reports_every_bad_value() {
+        let args = Args::parse_from([
+            "bridge",
+            "--chunk-frames",
+            "0",
+            "--buffer-seconds",
+            "500",
+            "--http-bind",
+            "0.0.0.0:0",
+            "--dscp",
+            "AF51",
+            "--hub-url",
+            "hub.local:8080",
+            "--record",
+            "capture.mp3",
+            "listen",
+        ]);
+        let problems = args.config_problems();
+        assert_eq!(problems.len(), 6);
+        assert!(problems[0].starts_with("--chunk-frames"));
+        assert!(problems[3].starts_with("--dscp"));
+        assert!(problems[4].starts_with("--hub-url"));
+        assert!(problems[5].starts_with("--record"));
+    }
+
+    #[test]
+    fn jack_connect_requires_jack_backend() {
+        let args = Args::parse_from(["bridge", "--jack-connect", "none", "listen"]);
+        assert_eq!(
+            args.config_problems(),
+            vec!["--jack-connect: requires --backend jack".to_string()]
+        );
+        assert_eq!(args.output_backend(), OutputBackend::System);
+
+        let args = Args::parse_from([
+            "bridge",
+            "--backend",
+            "jack",
+            "--jack-connect",
+            "ardour:in_1,ardour:in_2",
+            "listen",
+        ]);
+        assert_eq!(
+            args.output_backend(),
+            OutputBackend::Jack(JackPorts::Ports(vec![
+                "ardour:in_1".to_string(),
+                "ardour:in_2".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn null_pace_requires_null_backend() {
+        let args = Args::parse_from(["bridge", "--null-pace", "fast", "listen"]);
+        assert_eq!(
+            args.config_problems(),
+            vec!["--null-pace: requires --backend null".to_string()]
+        );
+
+        let args = Args::parse_from(["bridge", "--backend", "null", "listen"]);
+        assert!(args.config_problems().is_empty());
+        assert_eq!(
+            args.output_backend(),
+            OutputBackend::Null(NullPace::Realtime)
+        );
+
+        let args = Args::parse_from([
+            "bridge",
+            "--backend",
+            "null",
+            "--null-pace",
+            "fast",
+            "listen",
+        ]);
+        let mut forwarded = vec!["bridge".to_string()];
+        forwarded.extend(args.service_args());
+        forwarded.push("listen".to_string());
+        assert_eq!(
+            Args::parse_from(forwarded).output_backend(),
+            OutputBackend::Null(NullPace::Fast)
+        );
+    }
+
+    #[test]
+    fn service_args_round_trip_through_parser() {
+        let args = Args::parse_from([
+            "bridge",
+            "--device",
+            "USB DAC",
+            "--mirror-device",
+            "Kitchen",
+            "--hide-device",
+            "HDMI 0",
+            "--hide-device",
+            "tv",
+            "--fallback-device",
+            "USB Speakers",
+            "--fallback-return",
+            "never",
+            "--rate-switch",
+            "prefer-resample",
+            "--resample-quality",
+            "fast",
+            "--buffer-seconds",
+            "3.5",
+            "--hub-url",
+            "http://hub.local:8080",
+            "--source-ip",
+            "192.168.10.5",
+            "--room",
+            "Living Room",
+            "--zone",
+            "Downstairs",
+            "--dscp",
+            "EF",
+            "--resampler",
+            "cubic",
+            "--underrun-concealment",
+            "fade",
+            "--preroll-silence-ms",
+            "500",
+            "--min-prebuffer-ms",
+            "750",
+            "--trim-silence",
+            "--trim-silence-threshold-db",
+            "-60",
+            "--decode-errors",
+            "silence",
+            "--decode-error-limit",
+            "8",
+            "--dsd",
+            "pcm",
+            "--backend",
+            "jack",
+            "--jack-connect",
+            "none",
+            "service",
+            "install",
+        ]);
+        let mut forwarded = vec!["bridge".to_string()];
+        forwarded.extend(args.service_args());
+        forwarded.extend(["service".to_string(), "run".to_string()]);
+        let parsed = Args::parse_from(forwarded);
+        assert_eq!(parsed.device.as_deref(), Some("USB DAC"));
+        assert_eq!(parsed.mirror_device.as_deref(), Some("Kitchen"));
+        assert_eq!(parsed.hide_devices, ["HDMI 0", "tv"]);
+        assert_eq!(parsed.fallback_devices, ["USB Speakers"]);
+        assert_eq!(parsed.fallback_return, FallbackReturnArg::Never);
+        assert_eq!(parsed.rate_switch, RateSwitchArg::PreferResample);
+        assert_eq!(parsed.resample_quality, ResampleQualityArg::Fast);
+        assert_eq!(parsed.resampler, ResamplerArg::Cubic);
+        assert_eq!(parsed.underrun_concealment, UnderrunConcealmentArg::Fade);
+        assert_eq!(parsed.preroll_silence_ms, 500);
+        assert_eq!(parsed.min_prebuffer_ms, 750);
+        assert_eq!(
+            parsed.silence_trim(),
+            Some(SilenceTrim {
+                threshold_db: -60.0,
+                ..SilenceTrim::default()
+            })
+        );
+        assert_eq!(
+            parsed.decode_errors(),
+            DecodeErrorPolicy {
+                action: DecodeErrorAction::Silence,
+                max_consecutive: 8,
+            }
+        );
+        assert_eq!(parsed.dsd, DsdArg::Pcm);
+        assert_eq!(parsed.buffer_seconds, 3.5);
+        assert_eq!(parsed.hub_url.as_deref(), Some("http://hub.local:8080"));
+        assert_eq!(parsed.source_ip, Some("192.168.10.5".parse().unwrap()));
+        assert_eq!(parsed.room.as_deref(), Some("Living Room"));
+        assert_eq!(parsed.zone.as_deref(), Some("Downstairs"));
+        assert_eq!(parsed.dscp.as_deref(), Some("EF"));
+        assert_eq!(
+            parsed.output_backend(),
+            OutputBackend::Jack(JackPorts::None)
+        );
+        assert!(matches!(
+            parsed.cmd,
+            Command::Service {
+                action: ServiceAction::Run { .. }
+            }
+        ));
+    }
 }
@@ -1,5 +1,10 @@
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
+use std::sync::Arc;
+
+use audio_player::log_filter::LogFilterControl;
+
+use crate::logs::LogBuffer;
 
 /// Playback configuration shared with the audio-player crate.
 pub use audio_player::config::PlaybackConfig;
@@ -11,14 +16,58 @@ pub struct BridgeListenConfig {
     pub http_bind: SocketAddr,
     /// Optional output device name.
     pub device: Option<String>,
+    /// File persisting device aliases (`None` keeps them in memory only).
+    pub device_aliases: Option<PathBuf>,
+    /// Selectors of output devices hidden from listing and selection.
+    pub hidden_devices: Vec<String>,
+    /// Devices to use, in order, while the selected device is unavailable.
+    pub fallback_devices: Vec<String>,
+    /// Move back to the selected device at the next track once it reappears.
+    pub fallback_return_to_preferred: bool,
     /// Playback tuning options.
     pub playback: PlaybackConfig,
     /// Allow insecure TLS when streaming from the hub.
     pub tls_insecure: bool,
+    /// Local IP that outgoing hub connections bind to (multi-homed hosts).
+    pub source_ip: Option<IpAddr>,
+    /// DSCP code point marked on outgoing hub connections.
+    pub dscp: Option<u8>,
     /// Optional hub URL used for graceful unregister notifications.
     pub hub_url: Option<String>,
+    /// Room/zone labels advertised over mDNS.
+    pub location: BridgeLocation,
     /// Expose synthetic dummy outputs for testing.
     pub enable_dummy_outputs: bool,
+    /// Shared log ring served by `/logs` and `/logs/stream`.
+    pub log_buffer: Arc<LogBuffer>,
+    /// Live tracing filter adjusted by `/admin/log-level`.
+    pub log_filter: Arc<LogFilterControl>,
+}
+
+/// Room/zone labels a bridge advertises for grouping its outputs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BridgeLocation {
+    /// Room the bridge's outputs are in.
+    pub room: Option<String>,
+    /// Zone (floor or area) the room belongs to.
+    pub zone: Option<String>,
+}
+
+/// Configuration for replaying a hub stream capture.
+#[derive(Clone, Debug)]
+pub struct BridgeReplayConfig {
+    /// Capture `.log` file or directory of captures.
+    pub capture: PathBuf,
+    /// Pacing multiplier (`0` = unpaced).
+    pub speed: f64,
+    /// Serve the capture without playing it.
+    pub serve_only: bool,
+    /// Replay listener address.
+    pub bind: SocketAddr,
+    /// Optional output device name.
+    pub device: Option<String>,
+    /// Playback tuning options.
+    pub playback: PlaybackConfig,
 }
 
 /// Configuration for playing a local file once.
@@ -0,0 +1,180 @@
+//! User-assigned output device aliases.
+//!
+//! An alias is a short name (`living-room-dac`) for a [stable device
+//! id](audio_player::device::stable_device_id). The device list reports it in place of the id,
+//! so hub output ids read `bridge:<bridge>:living-room-dac`, and `--device` or `/devices/select`
+//! accept it. Aliases persist as a JSON object (`{"dev-…": "alias"}`) in the alias file.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use audio_player::device;
+
+/// Longest accepted alias.
+pub(crate) const MAX_ALIAS_LEN: usize = 64;
+
+/// Alias table, optionally backed by a file.
+#[derive(Debug, Default)]
+pub(crate) struct DeviceAliases {
+    path: Option<PathBuf>,
+    by_id: BTreeMap<String, String>,
+}
+
+impl DeviceAliases {
+    /// Load aliases from `path`; a missing or unreadable file starts an empty table.
+    ///
+    /// Without a path aliases still work but are lost on restart.
+    pub(crate) fn load(path: Option<PathBuf>) -> Self {
+        let by_id = path
+            .as_deref()
+            .filter(|path| path.exists())
+            .and_then(|path| match read_file(path) {
+                Ok(by_id) => Some(by_id),
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = %e, "device aliases: load failed");
+                    None
+                }
+            })
+            .unwrap_or_default();
+        Self { path, by_id }
+    }
+
+    /// Alias assigned to `stable_id`.
+    pub(crate) fn alias(&self, stable_id: &str) -> Option<&str> {
+        self.by_id.get(stable_id).map(String::as_str)
+    }
+
+    /// Stable id a selector refers to when it is an alias; other selectors pass through.
+    pub(crate) fn resolve(&self, selector: &str) -> String {
+        let selector = selector.trim();
+        self.by_id
+            .iter()
+            .find(|(_, alias)| alias.eq_ignore_ascii_case(selector))
+            .map(|(id, _)| id.clone())
+            .unwrap_or_else(|| selector.to_string())
+    }
+
+    /// Assign (`Some`) or clear (`None`) the alias of `stable_id` and save the table.
+    ///
+    /// Fails when the alias is malformed or already names another device.
+    pub(crate) fn set(&mut self, stable_id: &str, alias: Option<&str>) -> Result<()> {
+        match alias.map(str::trim).filter(|alias| !alias.is_empty()) {
+            Some(alias) => {
+                validate_alias(alias)?;
+                if let Some((other, _)) = self
+                    .by_id
+                    .iter()
+                    .find(|(id, taken)| *id != stable_id && taken.eq_ignore_ascii_case(alias))
+                {
+                    return Err(anyhow!("alias `{alias}` is already used by {other}"));
+                }
+                self.by_id.insert(stable_id.to_string(), alias.to_string());
+            }
+            None => {
+                self.by_id.remove(stable_id);
+            }
+        }
+        self.save()
+    }
+
+    /// Write the table to the alias file (no-op without one).
+    fn save(&self) -> Result<()> {
+        let Some(path) = self.path.as_deref() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent()
+            && !dir.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+        }
+        let json = serde_json::to_string_pretty(&self.by_id)?;
+        std::fs::write(path, json).with_context(|| format!("write {}", path.display()))
+    }
+}
+
+/// Check that `alias` can stand in for a device id: 1-64 ASCII letters, digits, `-`, `_` or
+/// `.`, and not itself shaped like a stable id or a reserved selector.
+pub(crate) fn validate_alias(alias: &str) -> Result<()> {
+    if alias.is_empty() || alias.len() > MAX_ALIAS_LEN {
+        return Err(anyhow!("alias must be 1-{MAX_ALIAS_LEN} characters"));
+    }
+    if !alias
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+    {
+        return Err(anyhow!(
+            "alias may only contain letters, digits, `-`, `_` and `.`"
+        ));
+    }
+    if device::is_stable_id(alias) || device::is_default_follow(Some(alias)) {
+        return Err(anyhow!("alias `{alias}` is reserved"));
+    }
+    Ok(())
+}
+
+/// Default alias file: `audio-bridge/device-aliases.json` under the user config directory.
+pub fn default_path() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.map(|dir| dir.join("audio-bridge").join("device-aliases.json"))
+}
+
+/// Parse an alias file, dropping entries with malformed aliases.
+fn read_file(path: &Path) -> Result<BTreeMap<String, String>> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+    let mut by_id: BTreeMap<String, String> =
+        serde_json::from_str(&text).with_context(|| format!("parse {}", path.display()))?;
+    by_id.retain(|id, alias| match validate_alias(alias) {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!(id = %id, error = %e, "device aliases: ignoring entry");
+            false
+        }
+    });
+    Ok(by_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "dev-0123456789abcdef";
+    const OTHER: &str = "dev-fedcba9876543210";
+
+    #[test]
+    fn aliases_resolve_and_persist() {
+        let path = std::env::temp_dir()
+            .join(format!("bridge-aliases-{}", std::process::id()))
+            .join("device-aliases.json");
+        let mut aliases = DeviceAliases::load(Some(path.clone()));
+        aliases.set(ID, Some("living-room")).unwrap();
+        assert_eq!(aliases.alias(ID), Some("living-room"));
+        assert_eq!(aliases.resolve("Living-Room"), ID);
+        assert_eq!(aliases.resolve("USB DAC"), "USB DAC");
+        assert!(aliases.set(OTHER, Some("living-room")).is_err());
+
+        let reloaded = DeviceAliases::load(Some(path.clone()));
+        assert_eq!(reloaded.alias(ID), Some("living-room"));
+
+        aliases.set(ID, None).unwrap();
+        assert!(DeviceAliases::load(Some(path)).alias(ID).is_none());
+    }
+
+    #[test]
+    fn validate_alias_rejects_ids_and_separators() {
+        assert!(validate_alias("kitchen_2.dac").is_ok());
+        assert!(validate_alias("").is_err());
+        assert!(validate_alias("a:b").is_err());
+        assert!(validate_alias("with space").is_err());
+        assert!(validate_alias(ID).is_err());
+        assert!(validate_alias("default-follow").is_err());
+        assert!(validate_alias(&"a".repeat(MAX_ALIAS_LEN + 1)).is_err());
+    }
+}
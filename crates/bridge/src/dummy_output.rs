@@ -1,7 +1,8 @@
 //! Synthetic output devices for bridge testing without physical hardware.
 //!
 //! Dummy outputs are exposed in `/devices` and can be selected like normal devices.
-//! Playback uses a simulated sink that drains decoded audio at real-time speed.
+//! Playback runs the regular pipeline on audio-player's null output, which drains decoded
+//! audio at real-time speed.
 
 /// Metadata for a synthetic output device.
 #[derive(Clone, Debug)]
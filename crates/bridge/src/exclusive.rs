@@ -1,4 +1,104 @@
-//! Exclusive (hog) mode helpers for macOS.
+//! Exclusive device access helpers.
+//!
+//! - macOS: CoreAudio hog mode, nominal-rate switching and integer mode around the CPAL stream;
+//!   previous device settings are restored when the session ends.
+//! - Windows: WASAPI exclusive, event-driven renderer used instead of CPAL's shared stream.
+//!
+//! When exclusive access is unavailable playback falls back to shared mode with a warning.
+
+/// `BridgeStatus.output_mode` value when the device is held exclusively.
+pub const OUTPUT_MODE_EXCLUSIVE: &str = "exclusive";
+/// `BridgeStatus.output_mode` value for shared (mixer) access.
+pub const OUTPUT_MODE_SHARED: &str = "shared";
+
+/// Output negotiated by a platform exclusive-mode backend, replacing the CPAL stream.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct ExclusiveOutput {
+    /// Negotiated stream rate in Hz.
+    pub sample_rate: u32,
+    /// Negotiated channel count.
+    pub channels: u16,
+    /// Negotiated sample format label (`I16`, `I24`, `I32`, `F32`).
+    pub sample_format: &'static str,
+    /// Opens the renderer for each stream attempt (initial open and hotplug reopen).
+    pub open: audio_player::playback::OutputOpener,
+}
+
+/// Sample layouts tried for exclusive-mode negotiation, best first.
+#[cfg(any(windows, test))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SampleKind {
+    I32,
+    /// 24 valid bits, left-justified in a 32-bit container.
+    I24In32,
+    F32,
+    I16,
+}
+
+#[cfg(any(windows, test))]
+impl SampleKind {
+    const ALL: [SampleKind; 4] = [
+        SampleKind::I32,
+        SampleKind::I24In32,
+        SampleKind::F32,
+        SampleKind::I16,
+    ];
+
+    #[cfg_attr(test, allow(dead_code))]
+    fn container_bits(self) -> u16 {
+        match self {
+            SampleKind::I16 => 16,
+            _ => 32,
+        }
+    }
+
+    #[cfg_attr(test, allow(dead_code))]
+    fn valid_bits(self) -> u16 {
+        match self {
+            SampleKind::I16 => 16,
+            SampleKind::I24In32 => 24,
+            _ => 32,
+        }
+    }
+
+    #[cfg_attr(test, allow(dead_code))]
+    fn label(self) -> &'static str {
+        match self {
+            SampleKind::I32 => "I32",
+            SampleKind::I24In32 => "I24",
+            SampleKind::F32 => "F32",
+            SampleKind::I16 => "I16",
+        }
+    }
+}
+
+/// Exclusive-mode formats to try, closest to the source first.
+///
+/// Rates are ordered by distance from `source_rate` (the source rate itself first, ties prefer
+/// the higher rate); channels try the source layout, then stereo.
+#[cfg(any(windows, test))]
+fn exclusive_candidates(source_rate: u32, source_channels: u16) -> Vec<(u32, u16, SampleKind)> {
+    const RATES: [u32; 8] = [
+        44_100, 48_000, 88_200, 96_000, 176_400, 192_000, 352_800, 384_000,
+    ];
+    let mut rates: Vec<u32> = std::iter::once(source_rate)
+        .chain(RATES.into_iter().filter(|r| *r != source_rate))
+        .collect();
+    rates[1..].sort_by_key(|r| (r.abs_diff(source_rate), std::cmp::Reverse(*r)));
+    let mut channels = vec![source_channels.max(1)];
+    if channels[0] != 2 {
+        channels.push(2);
+    }
+    let mut out = Vec::new();
+    for rate in rates {
+        for ch in &channels {
+            for kind in SampleKind::ALL {
+                out.push((rate, *ch, kind));
+            }
+        }
+    }
+    out
+}
 
 #[cfg(target_os = "macos")]
 mod macos {
@@ -8,33 +108,76 @@ mod macos {
     use cpal::traits::DeviceTrait;
     use objc2_core_audio::AudioDeviceID;
     use objc2_core_audio::AudioObjectGetPropertyData;
+    use objc2_core_audio::AudioObjectGetPropertyDataSize;
+    use objc2_core_audio::AudioObjectID;
     use objc2_core_audio::AudioObjectPropertyAddress;
+    use objc2_core_audio::AudioObjectPropertyScope;
     use objc2_core_audio::AudioObjectPropertySelector;
+    use objc2_core_audio::AudioObjectSetPropertyData;
+    use objc2_core_audio::AudioStreamRangedDescription;
     use objc2_core_audio::kAudioDevicePropertyNominalSampleRate;
+    use objc2_core_audio::kAudioDevicePropertyStreams;
     use objc2_core_audio::kAudioObjectPropertyElementMaster;
     use objc2_core_audio::kAudioObjectPropertyScopeGlobal;
+    use objc2_core_audio::kAudioObjectPropertyScopeOutput;
+    use objc2_core_audio::kAudioStreamPropertyAvailablePhysicalFormats;
+    use objc2_core_audio::kAudioStreamPropertyPhysicalFormat;
+    use objc2_core_audio_types::{
+        AudioStreamBasicDescription, kAudioFormatFlagIsFloat, kAudioFormatFlagIsNonMixable,
+        kAudioFormatFlagIsSignedInteger, kAudioFormatLinearPCM,
+    };
     use std::ptr::NonNull;
 
     /// RAII guard that keeps a CoreAudio device in hog (exclusive) mode.
+    ///
+    /// Dropping it restores the stream formats and nominal rate it changed, then releases hog mode.
     pub struct ExclusiveGuard {
         device_id: AudioDeviceID,
         owned: bool,
+        /// Nominal rate before playback switched it.
+        previous_rate: Option<f64>,
+        /// Physical formats replaced by integer mode, per output stream.
+        previous_formats: Vec<(AudioObjectID, AudioStreamBasicDescription)>,
     }
 
     impl Drop for ExclusiveGuard {
-        /// Release hog mode when this process still owns it.
+        /// Restore device settings and release hog mode when this process still owns it.
         fn drop(&mut self) {
             if !self.owned {
                 return;
             }
             let pid = get_hogging_pid(self.device_id).unwrap_or(0);
-            if pid == std::process::id() as i32 {
-                let _ = toggle_hog_mode(self.device_id);
+            if pid != std::process::id() as i32 {
+                return;
             }
+            for (stream_id, format) in self.previous_formats.drain(..) {
+                if let Err(status) = set_property(
+                    stream_id,
+                    kAudioStreamPropertyPhysicalFormat,
+                    kAudioObjectPropertyScopeGlobal,
+                    &format,
+                ) {
+                    tracing::warn!(
+                        stream_id,
+                        status,
+                        "exclusive mode: failed to restore stream format"
+                    );
+                }
+            }
+            if let Some(rate) = self.previous_rate
+                && let Err(err) = set_device_sample_rate(self.device_id, rate)
+            {
+                tracing::warn!(
+                    rate_hz = rate,
+                    error = ?err,
+                    "exclusive mode: failed to restore device sample rate"
+                );
+            }
+            let _ = toggle_hog_mode(self.device_id);
         }
     }
 
-    /// Try to acquire exclusive access and set the device sample rate.
+    /// Try to acquire exclusive access, set the device sample rate and enable integer mode.
     pub fn maybe_acquire(
         device: &cpal::Device,
         sample_rate: u32,
@@ -84,6 +227,11 @@ mod macos {
             }
         }
 
+        let previous_rate = get_property::<f64>(
+            device_id,
+            kAudioDevicePropertyNominalSampleRate,
+            kAudioObjectPropertyScopeGlobal,
+        );
         if let Err(err) = set_device_sample_rate(device_id, sample_rate as f64) {
             tracing::warn!(
                 device = %name,
@@ -99,9 +247,22 @@ mod macos {
             );
         }
 
+        let previous_formats = enable_integer_mode(device_id, sample_rate);
+        if previous_formats.is_empty() {
+            tracing::info!(device = %name, "exclusive mode: integer mode not available");
+        } else {
+            tracing::info!(
+                device = %name,
+                streams = previous_formats.len(),
+                "exclusive mode: integer mode enabled"
+            );
+        }
+
         Some(ExclusiveGuard {
             device_id,
             owned: true,
+            previous_rate: previous_rate.filter(|rate| rate.round() as u32 != sample_rate),
+            previous_formats,
         })
     }
 
@@ -109,33 +270,586 @@ mod macos {
     pub fn current_nominal_rate(device: &cpal::Device) -> Option<u32> {
         let name = device.name().ok()?;
         let device_id = get_device_id_from_name(&name, false)?;
-        let property_address = AudioObjectPropertyAddress {
-            mSelector: kAudioDevicePropertyNominalSampleRate as AudioObjectPropertySelector,
-            mScope: kAudioObjectPropertyScopeGlobal,
+        let rate = get_property::<f64>(
+            device_id,
+            kAudioDevicePropertyNominalSampleRate,
+            kAudioObjectPropertyScopeGlobal,
+        )?;
+        if rate <= 0.0 {
+            return None;
+        }
+        Some(rate.round() as u32)
+    }
+
+    /// Switch every output stream to a non-mixable signed-integer physical format at `sample_rate`.
+    ///
+    /// Returns the formats that were replaced; empty when the device offers no integer format.
+    fn enable_integer_mode(
+        device_id: AudioDeviceID,
+        sample_rate: u32,
+    ) -> Vec<(AudioObjectID, AudioStreamBasicDescription)> {
+        let streams = get_property_array::<AudioObjectID>(
+            device_id,
+            kAudioDevicePropertyStreams,
+            kAudioObjectPropertyScopeOutput,
+        );
+        let mut replaced = Vec::new();
+        for stream_id in streams {
+            let Some(current) = get_property::<AudioStreamBasicDescription>(
+                stream_id,
+                kAudioStreamPropertyPhysicalFormat,
+                kAudioObjectPropertyScopeGlobal,
+            ) else {
+                continue;
+            };
+            let available = get_property_array::<AudioStreamRangedDescription>(
+                stream_id,
+                kAudioStreamPropertyAvailablePhysicalFormats,
+                kAudioObjectPropertyScopeGlobal,
+            );
+            let Some(mut format) = pick_integer_format(&available, &current, sample_rate) else {
+                continue;
+            };
+            format.mSampleRate = sample_rate as f64;
+            if is_same_format(&format, &current) {
+                continue;
+            }
+            match set_property(
+                stream_id,
+                kAudioStreamPropertyPhysicalFormat,
+                kAudioObjectPropertyScopeGlobal,
+                &format,
+            ) {
+                Ok(()) => replaced.push((stream_id, current)),
+                Err(status) => tracing::warn!(
+                    stream_id,
+                    status,
+                    bits = format.mBitsPerChannel,
+                    "exclusive mode: failed to enable integer mode"
+                ),
+            }
+        }
+        replaced
+    }
+
+    /// Deepest non-mixable integer PCM format matching the stream's channels at `sample_rate`.
+    fn pick_integer_format(
+        available: &[AudioStreamRangedDescription],
+        current: &AudioStreamBasicDescription,
+        sample_rate: u32,
+    ) -> Option<AudioStreamBasicDescription> {
+        let rate = sample_rate as f64;
+        available
+            .iter()
+            .filter(|ranged| {
+                let f = &ranged.mFormat;
+                f.mFormatID == kAudioFormatLinearPCM
+                    && f.mFormatFlags & kAudioFormatFlagIsSignedInteger != 0
+                    && f.mFormatFlags & kAudioFormatFlagIsNonMixable != 0
+                    && f.mFormatFlags & kAudioFormatFlagIsFloat == 0
+                    && f.mChannelsPerFrame == current.mChannelsPerFrame
+                    && ranged.mSampleRateRange.mMinimum <= rate
+                    && rate <= ranged.mSampleRateRange.mMaximum
+            })
+            .max_by_key(|ranged| ranged.mFormat.mBitsPerChannel)
+            .map(|ranged| ranged.mFormat)
+    }
+
+    fn is_same_format(a: &AudioStreamBasicDescription, b: &AudioStreamBasicDescription) -> bool {
+        a.mSampleRate == b.mSampleRate
+            && a.mFormatID == b.mFormatID
+            && a.mFormatFlags == b.mFormatFlags
+            && a.mBitsPerChannel == b.mBitsPerChannel
+            && a.mChannelsPerFrame == b.mChannelsPerFrame
+    }
+
+    fn address(
+        selector: AudioObjectPropertySelector,
+        scope: AudioObjectPropertyScope,
+    ) -> AudioObjectPropertyAddress {
+        AudioObjectPropertyAddress {
+            mSelector: selector,
+            mScope: scope,
             mElement: kAudioObjectPropertyElementMaster,
+        }
+    }
+
+    /// Read a fixed-size CoreAudio property.
+    fn get_property<T: Copy>(
+        object_id: AudioObjectID,
+        selector: AudioObjectPropertySelector,
+        scope: AudioObjectPropertyScope,
+    ) -> Option<T> {
+        let address = address(selector, scope);
+        let mut value = std::mem::MaybeUninit::<T>::uninit();
+        let mut data_size = std::mem::size_of::<T>() as u32;
+        let status = unsafe {
+            AudioObjectGetPropertyData(
+                object_id,
+                NonNull::from(&address),
+                0,
+                std::ptr::null(),
+                NonNull::from(&mut data_size),
+                NonNull::from(&mut value).cast(),
+            )
         };
-        let mut rate: f64 = 0.0;
-        let data_size = std::mem::size_of::<f64>() as u32;
+        if status != 0 || data_size as usize != std::mem::size_of::<T>() {
+            return None;
+        }
+        Some(unsafe { value.assume_init() })
+    }
+
+    /// Read a variable-length CoreAudio array property; empty on error.
+    fn get_property_array<T: Copy>(
+        object_id: AudioObjectID,
+        selector: AudioObjectPropertySelector,
+        scope: AudioObjectPropertyScope,
+    ) -> Vec<T> {
+        let address = address(selector, scope);
+        let mut data_size = 0u32;
+        let status = unsafe {
+            AudioObjectGetPropertyDataSize(
+                object_id,
+                NonNull::from(&address),
+                0,
+                std::ptr::null(),
+                NonNull::from(&mut data_size),
+            )
+        };
+        let count = data_size as usize / std::mem::size_of::<T>();
+        if status != 0 || count == 0 {
+            return Vec::new();
+        }
+        let mut values = Vec::<T>::with_capacity(count);
         let status = unsafe {
             AudioObjectGetPropertyData(
-                device_id,
-                NonNull::from(&property_address),
+                object_id,
+                NonNull::from(&address),
                 0,
                 std::ptr::null(),
-                NonNull::from(&data_size),
-                NonNull::from(&mut rate).cast(),
+                NonNull::from(&mut data_size),
+                NonNull::new_unchecked(values.as_mut_ptr()).cast(),
             )
         };
         if status != 0 {
+            return Vec::new();
+        }
+        unsafe { values.set_len((data_size as usize / std::mem::size_of::<T>()).min(count)) };
+        values
+    }
+
+    /// Write a fixed-size CoreAudio property, returning the `OSStatus` on failure.
+    fn set_property<T: Copy>(
+        object_id: AudioObjectID,
+        selector: AudioObjectPropertySelector,
+        scope: AudioObjectPropertyScope,
+        value: &T,
+    ) -> Result<(), i32> {
+        let address = address(selector, scope);
+        let status = unsafe {
+            AudioObjectSetPropertyData(
+                object_id,
+                NonNull::from(&address),
+                0,
+                std::ptr::null(),
+                std::mem::size_of::<T>() as u32,
+                NonNull::from(value).cast(),
+            )
+        };
+        if status == 0 { Ok(()) } else { Err(status) }
+    }
+}
+
+#[cfg(windows)]
+mod wasapi {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread::JoinHandle;
+
+    use anyhow::{Result, anyhow};
+    use audio_player::playback::{OutputFiller, PlaybackConfig};
+    use audio_player::queue::SharedAudio;
+    use cpal::traits::DeviceTrait;
+    use windows::Win32::Foundation::{CloseHandle, ERROR_TIMEOUT, HANDLE, S_OK, WAIT_OBJECT_0};
+    use windows::Win32::Media::Audio::{
+        AUDCLNT_BUFFERFLAGS_SILENT, AUDCLNT_E_BUFFER_SIZE_NOT_ALIGNED, AUDCLNT_E_DEVICE_IN_USE,
+        AUDCLNT_E_DEVICE_INVALIDATED, AUDCLNT_E_EXCLUSIVE_MODE_NOT_ALLOWED,
+        AUDCLNT_SHAREMODE_EXCLUSIVE, AUDCLNT_STREAMFLAGS_EVENTCALLBACK, IAudioClient,
+        IAudioRenderClient, IMMDeviceEnumerator, MMDeviceEnumerator, WAVEFORMATEX,
+        WAVEFORMATEXTENSIBLE, WAVEFORMATEXTENSIBLE_0,
+    };
+    use windows::Win32::Media::KernelStreaming::{
+        KSDATAFORMAT_SUBTYPE_PCM, WAVE_FORMAT_EXTENSIBLE,
+    };
+    use windows::Win32::Media::Multimedia::KSDATAFORMAT_SUBTYPE_IEEE_FLOAT;
+    use windows::Win32::System::Com::{
+        CLSCTX_ALL, COINIT_MULTITHREADED, CoCreateInstance, CoInitializeEx,
+    };
+    use windows::Win32::System::Threading::{
+        AvSetMmThreadCharacteristicsW, CreateEventW, WaitForSingleObject,
+    };
+    use windows::core::{HSTRING, PCWSTR, w};
+
+    use super::{ExclusiveOutput, SampleKind, exclusive_candidates};
+
+    /// Longest wait for a buffer event before the endpoint is treated as gone.
+    const EVENT_TIMEOUT_MS: u32 = 2_000;
+
+    /// Negotiate a WASAPI exclusive format for `device`, or `None` to stay in shared mode.
+    pub fn open_exclusive_output(
+        device: &cpal::Device,
+        source_rate: u32,
+        source_channels: u16,
+        enabled: bool,
+    ) -> Option<ExclusiveOutput> {
+        if !enabled {
             return None;
         }
-        if rate <= 0.0 {
+        let name = device
+            .description()
+            .map(|d| d.name().to_string())
+            .unwrap_or_else(|_| "<unknown>".to_string());
+        let Some(endpoint) = endpoint_id(device) else {
+            tracing::warn!(device = %name, "exclusive mode: not a WASAPI endpoint; using shared mode");
             return None;
+        };
+        match negotiate(&endpoint, source_rate, source_channels) {
+            Ok((sample_rate, channels, kind)) => {
+                tracing::info!(
+                    device = %name,
+                    rate_hz = sample_rate,
+                    channels,
+                    format = kind.label(),
+                    "exclusive mode: WASAPI format negotiated"
+                );
+                Some(ExclusiveOutput {
+                    sample_rate,
+                    channels,
+                    sample_format: kind.label(),
+                    open: Box::new(move |device, dstq, cfg| {
+                        let endpoint = endpoint_id(device)
+                            .ok_or_else(|| anyhow!("exclusive mode: device has no WASAPI id"))?;
+                        let stream = ExclusiveStream::start(
+                            endpoint,
+                            sample_rate,
+                            channels,
+                            kind,
+                            dstq,
+                            cfg,
+                        )?;
+                        Ok(Box::new(stream) as Box<dyn Send>)
+                    }),
+                })
+            }
+            Err(err) => {
+                tracing::warn!(
+                    device = %name,
+                    error = %err,
+                    "exclusive mode unavailable; falling back to shared mode"
+                );
+                None
+            }
+        }
+    }
+
+    /// WASAPI endpoint id from a CPAL device id (`wasapi:<endpoint>`).
+    fn endpoint_id(device: &cpal::Device) -> Option<String> {
+        let id = device.id().ok()?.to_string();
+        id.strip_prefix("wasapi:").map(str::to_string)
+    }
+
+    /// Pick the first candidate the endpoint accepts, then trial-open it exclusively.
+    fn negotiate(
+        endpoint: &str,
+        source_rate: u32,
+        source_channels: u16,
+    ) -> Result<(u32, u16, SampleKind)> {
+        let client = activate(endpoint).map_err(describe)?;
+        let chosen = exclusive_candidates(source_rate, source_channels)
+            .into_iter()
+            .find(|&(rate, channels, kind)| {
+                let format = wave_format(rate, channels, kind);
+                let hr = unsafe {
+                    client.IsFormatSupported(AUDCLNT_SHAREMODE_EXCLUSIVE, format_ptr(&format), None)
+                };
+                hr == S_OK
+            })
+            .ok_or_else(|| anyhow!("device accepts none of the exclusive-mode formats tried"))?;
+        drop(client);
+        // Catches endpoints held by another exclusive client or with exclusive mode disabled.
+        let (rate, channels, kind) = chosen;
+        initialize(endpoint, &wave_format(rate, channels, kind)).map_err(describe)?;
+        Ok(chosen)
+    }
+
+    fn activate(endpoint: &str) -> windows::core::Result<IAudioClient> {
+        unsafe {
+            // COM may already be initialized on this thread (possibly as STA); either is fine.
+            let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+            let enumerator: IMMDeviceEnumerator =
+                CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+            let device = enumerator.GetDevice(&HSTRING::from(endpoint))?;
+            device.Activate::<IAudioClient>(CLSCTX_ALL, None)
+        }
+    }
+
+    /// Initialize an exclusive, event-driven client at the device's default period.
+    fn initialize(
+        endpoint: &str,
+        format: &WAVEFORMATEXTENSIBLE,
+    ) -> windows::core::Result<IAudioClient> {
+        let client = activate(endpoint)?;
+        let mut period = 0i64;
+        unsafe {
+            client.GetDevicePeriod(Some(&raw mut period), None)?;
+            let first = client.Initialize(
+                AUDCLNT_SHAREMODE_EXCLUSIVE,
+                AUDCLNT_STREAMFLAGS_EVENTCALLBACK,
+                period,
+                period,
+                format_ptr(format),
+                None,
+            );
+            match first {
+                Ok(()) => Ok(client),
+                Err(err) if err.code() == AUDCLNT_E_BUFFER_SIZE_NOT_ALIGNED => {
+                    // Retry with a period matching the aligned buffer the driver reported.
+                    let frames = client.GetBufferSize()?;
+                    let rate = f64::from(format.Format.nSamplesPerSec);
+                    let aligned = (10_000_000.0 * f64::from(frames) / rate).round() as i64;
+                    let client = activate(endpoint)?;
+                    client.Initialize(
+                        AUDCLNT_SHAREMODE_EXCLUSIVE,
+                        AUDCLNT_STREAMFLAGS_EVENTCALLBACK,
+                        aligned,
+                        aligned,
+                        format_ptr(format),
+                        None,
+                    )?;
+                    Ok(client)
+                }
+                Err(err) => Err(err),
+            }
+        }
+    }
+
+    fn describe(err: windows::core::Error) -> anyhow::Error {
+        let code = err.code();
+        if code == AUDCLNT_E_DEVICE_IN_USE {
+            anyhow!("device is in use by another exclusive-mode application")
+        } else if code == AUDCLNT_E_EXCLUSIVE_MODE_NOT_ALLOWED {
+            anyhow!("exclusive mode is disabled for this device in Windows sound settings")
+        } else if code == AUDCLNT_E_DEVICE_INVALIDATED {
+            anyhow!("device is no longer available")
+        } else {
+            anyhow!(err)
+        }
+    }
+
+    fn format_ptr(format: &WAVEFORMATEXTENSIBLE) -> *const WAVEFORMATEX {
+        format as *const WAVEFORMATEXTENSIBLE as *const WAVEFORMATEX
+    }
+
+    fn wave_format(rate: u32, channels: u16, kind: SampleKind) -> WAVEFORMATEXTENSIBLE {
+        let container = kind.container_bits();
+        let block_align = channels * container / 8;
+        WAVEFORMATEXTENSIBLE {
+            Format: WAVEFORMATEX {
+                wFormatTag: WAVE_FORMAT_EXTENSIBLE as u16,
+                nChannels: channels,
+                nSamplesPerSec: rate,
+                nAvgBytesPerSec: rate * u32::from(block_align),
+                nBlockAlign: block_align,
+                wBitsPerSample: container,
+                cbSize: (std::mem::size_of::<WAVEFORMATEXTENSIBLE>()
+                    - std::mem::size_of::<WAVEFORMATEX>()) as u16,
+            },
+            Samples: WAVEFORMATEXTENSIBLE_0 {
+                wValidBitsPerSample: kind.valid_bits(),
+            },
+            dwChannelMask: match channels {
+                1 => 0x4, // SPEAKER_FRONT_CENTER
+                2 => 0x3, // SPEAKER_FRONT_LEFT | SPEAKER_FRONT_RIGHT
+                _ => 0,
+            },
+            SubFormat: match kind {
+                SampleKind::F32 => KSDATAFORMAT_SUBTYPE_IEEE_FLOAT,
+                _ => KSDATAFORMAT_SUBTYPE_PCM,
+            },
+        }
+    }
+
+    /// Running exclusive renderer; dropping it stops the render thread and releases the device.
+    struct ExclusiveStream {
+        stop: Arc<AtomicBool>,
+        join: Option<JoinHandle<()>>,
+    }
+
+    impl ExclusiveStream {
+        fn start(
+            endpoint: String,
+            rate: u32,
+            channels: u16,
+            kind: SampleKind,
+            dstq: &Arc<SharedAudio>,
+            cfg: PlaybackConfig,
+        ) -> Result<Self> {
+            let stop = Arc::new(AtomicBool::new(false));
+            let (ready_tx, ready_rx) = crossbeam_channel::bounded::<Result<(), String>>(1);
+            let stop_for_thread = stop.clone();
+            let dstq = dstq.clone();
+            let join = std::thread::Builder::new()
+                .name("wasapi-exclusive".to_string())
+                .spawn(move || {
+                    render_thread(
+                        &endpoint,
+                        rate,
+                        channels,
+                        kind,
+                        &dstq,
+                        cfg,
+                        &stop_for_thread,
+                        &ready_tx,
+                    )
+                })?;
+            match ready_rx.recv() {
+                Ok(Ok(())) => Ok(Self {
+                    stop,
+                    join: Some(join),
+                }),
+                Ok(Err(err)) => {
+                    let _ = join.join();
+                    Err(anyhow!("exclusive mode: {err}"))
+                }
+                Err(_) => {
+                    let _ = join.join();
+                    Err(anyhow!("exclusive mode: renderer exited during startup"))
+                }
+            }
+        }
+    }
+
+    impl Drop for ExclusiveStream {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::Relaxed);
+            if let Some(join) = self.join.take() {
+                let _ = join.join();
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_thread(
+        endpoint: &str,
+        rate: u32,
+        channels: u16,
+        kind: SampleKind,
+        dstq: &Arc<SharedAudio>,
+        cfg: PlaybackConfig,
+        stop: &AtomicBool,
+        ready_tx: &crossbeam_channel::Sender<Result<(), String>>,
+    ) {
+        let mut task_index = 0u32;
+        let _ = unsafe { AvSetMmThreadCharacteristicsW(w!("Pro Audio"), &mut task_index) };
+        let format = wave_format(rate, channels, kind);
+        let client = match initialize(endpoint, &format) {
+            Ok(client) => client,
+            Err(err) => {
+                let _ = ready_tx.send(Err(describe(err).to_string()));
+                return;
+            }
+        };
+        let event = match unsafe { CreateEventW(None, false, false, PCWSTR::null()) } {
+            Ok(event) => event,
+            Err(err) => {
+                let _ = ready_tx.send(Err(err.to_string()));
+                return;
+            }
+        };
+        let mut filler = OutputFiller::new(dstq, channels as usize, &cfg);
+        let result = render_loop(&client, event, channels, kind, &mut filler, stop, ready_tx);
+        unsafe {
+            let _ = client.Stop();
+            let _ = CloseHandle(event);
+        }
+        let Err(err) = result else {
+            return;
+        };
+        // Startup failures were already reported through `ready_tx`.
+        let _ = ready_tx.try_send(Err(err.to_string()));
+        tracing::warn!(error = %err, "exclusive mode: render loop stopped");
+        let lost =
+            err.code() == AUDCLNT_E_DEVICE_INVALIDATED || err.code() == ERROR_TIMEOUT.to_hresult();
+        match (&cfg.device_lost, &cfg.cancel_on_error) {
+            (Some(flag), _) if lost => flag.store(true, Ordering::Relaxed),
+            (_, Some(flag)) => flag.store(true, Ordering::Relaxed),
+            _ => {}
+        }
+    }
+
+    /// Fill the whole device buffer on every event until `stop` is set.
+    ///
+    /// A missing buffer event is reported as `ERROR_TIMEOUT` (treated as device loss).
+    fn render_loop(
+        client: &IAudioClient,
+        event: HANDLE,
+        channels: u16,
+        kind: SampleKind,
+        filler: &mut OutputFiller,
+        stop: &AtomicBool,
+        ready_tx: &crossbeam_channel::Sender<Result<(), String>>,
+    ) -> windows::core::Result<()> {
+        unsafe {
+            client.SetEventHandle(event)?;
+            let buffer_frames = client.GetBufferSize()?;
+            let render: IAudioRenderClient = client.GetService()?;
+            // Pre-roll one buffer of silence so the first event already has data queued.
+            render.GetBuffer(buffer_frames)?;
+            render.ReleaseBuffer(buffer_frames, AUDCLNT_BUFFERFLAGS_SILENT.0 as u32)?;
+            client.Start()?;
+            let _ = ready_tx.send(Ok(()));
+
+            let samples = buffer_frames as usize * channels as usize;
+            while !stop.load(Ordering::Relaxed) {
+                if WaitForSingleObject(event, EVENT_TIMEOUT_MS) != WAIT_OBJECT_0 {
+                    return Err(windows::core::Error::new(
+                        ERROR_TIMEOUT.to_hresult(),
+                        "no buffer event from the endpoint",
+                    ));
+                }
+                let data = render.GetBuffer(buffer_frames)?;
+                match kind {
+                    SampleKind::I16 => {
+                        filler.fill(std::slice::from_raw_parts_mut(data as *mut i16, samples))
+                    }
+                    SampleKind::I32 | SampleKind::I24In32 => {
+                        filler.fill(std::slice::from_raw_parts_mut(data as *mut i32, samples))
+                    }
+                    SampleKind::F32 => {
+                        filler.fill(std::slice::from_raw_parts_mut(data as *mut f32, samples))
+                    }
+                }
+                render.ReleaseBuffer(buffer_frames, 0)?;
+            }
+            Ok(())
         }
-        Some(rate.round() as u32)
     }
 }
 
+#[cfg(windows)]
+pub use wasapi::open_exclusive_output;
+
+#[cfg(not(windows))]
+/// WASAPI exclusive mode is Windows-only; other platforms keep the CPAL stream.
+pub fn open_exclusive_output(
+    _device: &cpal::Device,
+    _source_rate: u32,
+    _source_channels: u16,
+    _enabled: bool,
+) -> Option<ExclusiveOutput> {
+    None
+}
+
 #[cfg(target_os = "macos")]
 pub use macos::{ExclusiveGuard, current_nominal_rate, maybe_acquire};
 
@@ -158,3 +872,25 @@ pub fn maybe_acquire(
 pub fn current_nominal_rate(_device: &cpal::Device) -> Option<u32> {
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exclusive_candidates_start_with_source_format() {
+        let candidates = exclusive_candidates(44_100, 2);
+        assert_eq!(candidates[0], (44_100, 2, SampleKind::I32));
+        assert_eq!(candidates[3], (44_100, 2, SampleKind::I16));
+        // Closest rate after the source rate is 48 kHz.
+        assert_eq!(candidates[4].0, 48_000);
+    }
+
+    #[test]
+    fn exclusive_candidates_fall_back_to_stereo() {
+        let candidates = exclusive_candidates(96_000, 1);
+        assert_eq!(candidates[0], (96_000, 1, SampleKind::I32));
+        assert_eq!(candidates[4], (96_000, 2, SampleKind::I32));
+        assert!(candidates.iter().all(|(_, ch, _)| *ch == 1 || *ch == 2));
+    }
+}
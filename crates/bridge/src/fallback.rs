@@ -0,0 +1,178 @@
+//! Output fallback chain (`--fallback-device`).
+//!
+//! When the selected device is missing, playback uses the first available device from a
+//! priority list instead of failing or waiting for the device to come back. Each new track
+//! tries the selected device first again, so playback returns to it at the next track boundary
+//! once it reappears; with `--fallback-return never` it stays on the fallback until that device
+//! goes away or another device is selected.
+
+use std::sync::Mutex;
+
+use anyhow::Result;
+use audio_player::device;
+
+/// Priority list of devices to use while the selected device is unavailable.
+#[derive(Debug, Default)]
+pub(crate) struct FallbackChain {
+    devices: Vec<String>,
+    return_to_preferred: bool,
+    sticky: Mutex<Option<StickyFallback>>,
+}
+
+/// Fallback kept in use (`--fallback-return never`) for one selected device.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct StickyFallback {
+    preferred: Option<String>,
+    fallback: String,
+}
+
+impl FallbackChain {
+    /// Build from `--fallback-device` selectors (already alias-resolved), dropping blanks.
+    pub(crate) fn new(devices: Vec<String>, return_to_preferred: bool) -> Self {
+        Self {
+            devices: devices
+                .into_iter()
+                .map(|d| d.trim().to_string())
+                .filter(|d| !d.is_empty())
+                .collect(),
+            return_to_preferred,
+            sticky: Mutex::new(None),
+        }
+    }
+
+    /// Selectors to try, in order, for a session on `preferred` (`None` = system default).
+    fn candidates(&self, preferred: Option<&str>) -> Vec<Option<String>> {
+        let mut out = Vec::with_capacity(self.devices.len() + 2);
+        if !self.return_to_preferred
+            && let Ok(sticky) = self.sticky.lock()
+            && let Some(sticky) = sticky.as_ref()
+            && sticky.preferred.as_deref() == preferred
+        {
+            out.push(Some(sticky.fallback.clone()));
+        }
+        out.push(preferred.map(str::to_string));
+        for dev in &self.devices {
+            if preferred != Some(dev.as_str()) && !out.contains(&Some(dev.clone())) {
+                out.push(Some(dev.clone()));
+            }
+        }
+        out
+    }
+
+    /// Resolve the device for a new track: the selected device, else the first available
+    /// fallback. Fails with the selected device's error when nothing in the chain is present.
+    pub(crate) fn pick(&self, host: &cpal::Host, preferred: Option<&str>) -> Result<cpal::Device> {
+        self.pick_with(preferred, |selector| device::pick_device(host, selector))
+    }
+
+    /// Resolve a device after the current one disconnected; `None` while nothing is present.
+    pub(crate) fn reopen(&self, preferred: Option<&str>) -> Option<cpal::Device> {
+        let host = device::output_host().ok()?;
+        self.pick(&host, preferred).ok()
+    }
+
+    /// [`pick`](Self::pick) over an arbitrary resolver, recording which entry was used.
+    fn pick_with<T>(
+        &self,
+        preferred: Option<&str>,
+        resolve: impl Fn(Option<&str>) -> Result<T>,
+    ) -> Result<T> {
+        let mut first_err = None;
+        for selector in self.candidates(preferred) {
+            match resolve(selector.as_deref()) {
+                Ok(found) => {
+                    self.record(preferred, selector);
+                    return Ok(found);
+                }
+                Err(e) => {
+                    if first_err.is_none() && selector.as_deref() == preferred {
+                        first_err = Some(e);
+                    }
+                }
+            }
+        }
+        Err(first_err.unwrap_or_else(|| anyhow::anyhow!("no output device available")))
+    }
+
+    /// Remember the fallback in use (or that the preferred device is back).
+    fn record(&self, preferred: Option<&str>, used: Option<String>) {
+        let Ok(mut sticky) = self.sticky.lock() else {
+            return;
+        };
+        let next = used
+            .filter(|used| Some(used.as_str()) != preferred)
+            .map(|fallback| StickyFallback {
+                preferred: preferred.map(str::to_string),
+                fallback,
+            });
+        if *sticky != next {
+            match next.as_ref() {
+                Some(next) => tracing::warn!(
+                    preferred = preferred.unwrap_or("<default>"),
+                    fallback = %next.fallback,
+                    "selected output unavailable; using fallback device"
+                ),
+                None if sticky.is_some() => tracing::info!(
+                    preferred = preferred.unwrap_or("<default>"),
+                    "selected output available again"
+                ),
+                None => {}
+            }
+            *sticky = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolver(present: &'static [&'static str]) -> impl Fn(Option<&str>) -> Result<String> {
+        move |selector| {
+            let selector = selector.unwrap_or("default");
+            if present.contains(&selector) {
+                Ok(selector.to_string())
+            } else {
+                Err(anyhow::anyhow!("missing {selector}"))
+            }
+        }
+    }
+
+    #[test]
+    fn falls_back_in_order_and_returns_at_next_pick() {
+        let chain = FallbackChain::new(vec!["usb".into(), " ".into(), "hdmi".into()], true);
+        let picked = chain.pick_with(Some("dac"), resolver(&["hdmi", "usb"]));
+        assert_eq!(picked.unwrap(), "usb");
+        let picked = chain.pick_with(Some("dac"), resolver(&["dac", "usb"]));
+        assert_eq!(picked.unwrap(), "dac");
+        let err = chain.pick_with(Some("dac"), resolver(&[])).unwrap_err();
+        assert_eq!(err.to_string(), "missing dac");
+    }
+
+    #[test]
+    fn never_return_keeps_fallback_until_it_disappears() {
+        let chain = FallbackChain::new(vec!["usb".into(), "hdmi".into()], false);
+        assert_eq!(
+            chain.pick_with(Some("dac"), resolver(&["usb"])).unwrap(),
+            "usb"
+        );
+        assert_eq!(
+            chain
+                .pick_with(Some("dac"), resolver(&["dac", "usb"]))
+                .unwrap(),
+            "usb"
+        );
+        assert_eq!(
+            chain.pick_with(Some("dac"), resolver(&["dac"])).unwrap(),
+            "dac"
+        );
+        // A different selection ignores the fallback kept for the old one.
+        chain.pick_with(Some("dac"), resolver(&["hdmi"])).unwrap();
+        assert_eq!(
+            chain
+                .pick_with(Some("other"), resolver(&["other", "hdmi"]))
+                .unwrap(),
+            "other"
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! Output devices hidden with `--hide-device`.
+//!
+//! Hidden devices are left out of `/devices` (and so never become hub outputs), cannot be
+//! selected through the API and are skipped when the system default would be picked. Selectors
+//! take the `--device` forms: stable id, alias, platform id or exact name (case-insensitive).
+
+use crate::device_aliases::DeviceAliases;
+
+/// Set of selectors naming hidden devices.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct HiddenDevices {
+    selectors: Vec<String>,
+}
+
+impl HiddenDevices {
+    /// Build from raw `--hide-device` values, dropping blanks.
+    pub(crate) fn new(selectors: &[String]) -> Self {
+        Self {
+            selectors: selectors
+                .iter()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Returns `true` when no device is hidden.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.selectors.is_empty()
+    }
+
+    /// Whether the device with these identifiers is hidden.
+    ///
+    /// Aliases are resolved on every call so a selector follows alias changes.
+    pub(crate) fn hides(
+        &self,
+        aliases: &DeviceAliases,
+        stable_id: &str,
+        platform_id: &str,
+        name: &str,
+    ) -> bool {
+        self.selectors.iter().any(|selector| {
+            let resolved = aliases.resolve(selector);
+            resolved == stable_id || resolved == platform_id || resolved.eq_ignore_ascii_case(name)
+        })
+    }
+
+    /// Whether a selection request names a hidden device by one of the hide selectors.
+    pub(crate) fn hides_selector(&self, aliases: &DeviceAliases, selector: &str) -> bool {
+        let selector = aliases.resolve(selector);
+        self.selectors
+            .iter()
+            .any(|hidden| aliases.resolve(hidden).eq_ignore_ascii_case(&selector))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "dev-0123456789abcdef";
+
+    #[test]
+    fn hides_by_stable_id_alias_or_name() {
+        let path = std::env::temp_dir()
+            .join(format!("bridge-hidden-{}", std::process::id()))
+            .join("device-aliases.json");
+        let mut aliases = DeviceAliases::load(Some(path));
+        aliases.set(ID, Some("tv")).unwrap();
+
+        let by_alias = HiddenDevices::new(&["TV".to_string(), " ".to_string()]);
+        assert!(!by_alias.is_empty());
+        assert!(by_alias.hides(&aliases, ID, "hw:1,3", "HDMI 0"));
+        assert!(!by_alias.hides(&aliases, "dev-fedcba9876543210", "hw:0,0", "USB DAC"));
+        assert!(by_alias.hides_selector(&aliases, ID));
+
+        let by_name = HiddenDevices::new(&["hdmi 0".to_string()]);
+        assert!(by_name.hides(&aliases, ID, "hw:1,3", "HDMI 0"));
+        assert!(by_name.hides_selector(&aliases, "HDMI 0"));
+        assert!(!by_name.hides_selector(&aliases, "USB DAC"));
+
+        assert!(HiddenDevices::new(&[]).is_empty());
+    }
+}
@@ -14,16 +14,30 @@ use actix_web::{App, Error, HttpResponse, HttpServer, http::StatusCode, middlewa
 use crossbeam_channel::Sender;
 use futures_util::{Stream, stream::unfold};
 
+use crate::device_aliases::DeviceAliases;
 use crate::dummy_output;
-use crate::player::{BridgeVolumeState, PlayerCommand};
+use crate::hidden_devices::HiddenDevices;
+use crate::logs::{self, LogBuffer, LogEntry};
+use crate::player::{BridgeVolumeState, OutputOptions, PlayerCommand};
 use crate::status::{BridgeStatusState, StatusSnapshot};
-use audio_player::device;
+use audio_bridge_types::{ChapterRequest, chapter_seek_ms};
+use audio_player::cue::TrackRange;
+use audio_player::device::{self, OutputFormatLimits};
+use audio_player::log_filter::LogFilterControl;
+use audio_player::mirror::MirrorTarget;
+use audio_player::resample::ResampleQuality;
 
 /// Health check response payload.
 #[derive(serde::Serialize)]
 struct HealthResponse {
     status: &'static str,
     version: &'static str,
+    /// Bridge HTTP API version ([`audio_bridge_types::BRIDGE_PROTOCOL_VERSION`]).
+    protocol_version: u32,
+    /// Time since the HTTP API started.
+    uptime_ms: u64,
+    /// Newest error logged since startup.
+    last_error: Option<LogEntry>,
 }
 
 /// Device listing response payload.
@@ -37,10 +51,38 @@ struct DevicesResponse {
 /// Device metadata sent to clients.
 #[derive(serde::Serialize, Clone, PartialEq, Eq)]
 struct DeviceInfo {
+    /// Alias when one is assigned, otherwise `stable_id`.
     id: String,
+    /// Identifier that survives reboots (`dev-…`; the dummy id for dummy outputs).
+    stable_id: String,
+    /// Platform device id (`None` for dummy outputs).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    platform_id: Option<String>,
+    /// User-assigned alias.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alias: Option<String>,
     name: String,
     min_rate: u32,
     max_rate: u32,
+    /// Standard sample rates accepted natively (Hz).
+    sample_rates: Vec<u32>,
+    /// Supported sample formats (`F32`, `I32`, ...).
+    sample_formats: Vec<String>,
+    /// Supported channel counts.
+    channels: Vec<u16>,
+}
+
+impl DeviceInfo {
+    /// Whether `id` names this device by any of its ids or its alias.
+    fn matches_id(&self, id: &str) -> bool {
+        self.id == id
+            || self.stable_id == id
+            || self.platform_id.as_deref() == Some(id)
+            || self
+                .alias
+                .as_deref()
+                .is_some_and(|alias| alias.eq_ignore_ascii_case(id))
+    }
 }
 
 /// Request body for selecting a device.
@@ -52,6 +94,28 @@ struct DeviceSelectRequest {
     name: Option<String>,
     #[serde(default)]
     exclusive: Option<bool>,
+    /// Pre-roll silence for this output; absent falls back to `--preroll-silence-ms`.
+    #[serde(default)]
+    preroll_silence_ms: Option<u32>,
+    /// Resampler preset (`fast`, `balanced`, `high`); absent falls back to `--resample-quality`.
+    #[serde(default)]
+    resample_quality: Option<String>,
+    /// Preferred sample width (16, 24 or 32 bits); absent keeps the usual format order.
+    #[serde(default)]
+    bit_depth: Option<u16>,
+    /// Highest sample rate (Hz) to open the device at; absent leaves it uncapped.
+    #[serde(default)]
+    max_sample_rate: Option<u32>,
+}
+
+/// Request body for assigning a device alias.
+#[derive(serde::Deserialize)]
+struct DeviceAliasRequest {
+    /// Device id, stable id, platform id, or current alias.
+    id: String,
+    /// New alias; absent or empty clears it.
+    #[serde(default)]
+    alias: Option<String>,
 }
 
 /// Request body for playback.
@@ -64,8 +128,23 @@ struct PlayRequest {
     title: Option<String>,
     #[serde(default)]
     seek_ms: Option<u64>,
+    /// Pre-computed normalization gain (dB) applied ahead of volume.
+    #[serde(default)]
+    gain_db: Option<f32>,
+    /// Play only the part of the source from here (e.g. one track of a cue sheet).
+    #[serde(default)]
+    start_ms: Option<u64>,
+    /// Stop the source here instead of at its end.
+    #[serde(default)]
+    end_ms: Option<u64>,
+    /// Endless live stream (internet radio): played without seeking or an end of track.
+    #[serde(default)]
+    live: bool,
 }
 
+/// Largest normalization gain (dB, either direction) accepted in a play request.
+const MAX_PLAY_GAIN_DB: f32 = 24.0;
+
 /// Request body for seeking.
 #[derive(serde::Deserialize)]
 struct SeekRequest {
@@ -85,14 +164,66 @@ struct VolumeSetRequest {
     value: u8,
 }
 
+/// Mirror output snapshot payload.
+#[derive(serde::Serialize)]
+struct MirrorResponse {
+    device: String,
+    value: u8,
+    muted: bool,
+}
+
 /// Request body for setting mute state.
 #[derive(serde::Deserialize)]
 struct MuteRequest {
     muted: bool,
 }
 
+/// Query parameters for log endpoints.
+#[derive(serde::Deserialize)]
+struct LogsQuery {
+    /// Minimum level to include (`error`, `warn`, `info`, `debug`, `trace`).
+    #[serde(default)]
+    level: Option<String>,
+    /// Only return entries with a sequence number greater than this.
+    #[serde(default)]
+    after: Option<u64>,
+}
+
+/// Request body for adjusting the tracing filter.
+///
+/// `reset` wins over `filter`, which wins over `level`/`targets`.
+#[derive(serde::Deserialize)]
+struct LogLevelRequest {
+    /// Full `EnvFilter` directive string replacing the current one.
+    #[serde(default)]
+    filter: Option<String>,
+    /// Global level (`off`, `error`, `warn`, `info`, `debug`, `trace`).
+    #[serde(default)]
+    level: Option<String>,
+    /// Per-target levels; `null` removes a target override.
+    #[serde(default)]
+    targets: std::collections::BTreeMap<String, Option<String>>,
+    /// Restore the filter the bridge started with.
+    #[serde(default)]
+    reset: bool,
+}
+
+/// Active tracing filter payload.
+#[derive(serde::Serialize)]
+struct LogLevelResponse {
+    filter: String,
+}
+
+/// Log snapshot response payload.
+#[derive(serde::Serialize)]
+struct LogsResponse {
+    logs: Vec<LogEntry>,
+    last_seq: u64,
+}
+
 const DEVICES_STREAM_INTERVAL: Duration = Duration::from_secs(2);
 const STATUS_STREAM_INTERVAL: Duration = Duration::from_secs(1);
+const LOGS_STREAM_INTERVAL: Duration = Duration::from_millis(500);
 const PING_INTERVAL: Duration = Duration::from_secs(15);
 
 #[derive(Clone)]
@@ -100,10 +231,17 @@ struct AppState {
     status: Arc<Mutex<BridgeStatusState>>,
     volume: Arc<BridgeVolumeState>,
     device_selected: Arc<Mutex<Option<String>>>,
-    exclusive_selected: Arc<Mutex<bool>>,
+    device_aliases: Arc<Mutex<DeviceAliases>>,
+    hidden_devices: HiddenDevices,
+    output_options: Arc<Mutex<OutputOptions>>,
     enable_dummy_outputs: bool,
     player_tx: Sender<PlayerCommand>,
     known_hub_origins: Arc<Mutex<HashSet<String>>>,
+    log_buffer: Arc<LogBuffer>,
+    log_filter: Arc<LogFilterControl>,
+    mirror: Option<Arc<MirrorTarget>>,
+    /// When the HTTP API started, for `/health` uptime.
+    started_at: Instant,
 }
 
 /// Spawn the HTTP API server on the given bind address.
@@ -112,39 +250,64 @@ pub(crate) fn spawn_http_server(
     status: Arc<Mutex<BridgeStatusState>>,
     volume: Arc<BridgeVolumeState>,
     device_selected: Arc<Mutex<Option<String>>>,
-    exclusive_selected: Arc<Mutex<bool>>,
+    device_aliases: Arc<Mutex<DeviceAliases>>,
+    hidden_devices: HiddenDevices,
+    output_options: Arc<Mutex<OutputOptions>>,
     enable_dummy_outputs: bool,
     player_tx: Sender<PlayerCommand>,
     known_hub_origins: Arc<Mutex<HashSet<String>>>,
+    log_buffer: Arc<LogBuffer>,
+    log_filter: Arc<LogFilterControl>,
+    mirror: Option<Arc<MirrorTarget>>,
 ) -> std::thread::JoinHandle<()> {
     std::thread::spawn(move || {
         let state = AppState {
             status,
             volume,
             device_selected,
-            exclusive_selected,
+            device_aliases,
+            hidden_devices,
+            output_options,
             enable_dummy_outputs,
             player_tx,
             known_hub_origins,
+            log_buffer,
+            log_filter,
+            mirror,
+            started_at: Instant::now(),
         };
         let runner = match HttpServer::new(move || {
             App::new()
                 .app_data(web::Data::new(state.clone()))
-                .wrap(Logger::new("http request method=%m path=%U status=%s").exclude("/health"))
+                .wrap(
+                    Logger::new("http request method=%m path=%U status=%s")
+                        .exclude("/health")
+                        .exclude_regex("^/logs"),
+                )
                 .route("/health", web::get().to(health))
                 .route("/devices", web::get().to(list_devices))
                 .route("/devices/stream", web::get().to(devices_stream))
                 .route("/devices/select", web::post().to(select_device))
+                .route("/devices/alias", web::post().to(set_device_alias))
                 .route("/status", web::get().to(status_snapshot))
                 .route("/status/stream", web::get().to(status_stream))
                 .route("/volume", web::get().to(volume_snapshot))
                 .route("/volume", web::post().to(set_volume))
                 .route("/mute", web::post().to(set_mute))
+                .route("/mirror", web::get().to(mirror_snapshot))
+                .route("/mirror/volume", web::post().to(set_mirror_volume))
+                .route("/mirror/mute", web::post().to(set_mirror_mute))
                 .route("/play", web::post().to(play))
                 .route("/pause", web::post().to(pause))
                 .route("/resume", web::post().to(resume))
                 .route("/stop", web::post().to(stop))
                 .route("/seek", web::post().to(seek))
+                .route("/chapter", web::post().to(chapter))
+                .route("/logs", web::get().to(logs_snapshot))
+                .route("/logs/stream", web::get().to(logs_stream))
+                .route("/logs/clear", web::post().to(logs_clear))
+                .route("/admin/log-level", web::get().to(log_level_snapshot))
+                .route("/admin/log-level", web::post().to(set_log_level))
         })
         .bind(bind)
         {
@@ -161,10 +324,13 @@ pub(crate) fn spawn_http_server(
 }
 
 /// Return API health/version snapshot.
-async fn health() -> HttpResponse {
+async fn health(state: web::Data<AppState>) -> HttpResponse {
     HttpResponse::Ok().json(HealthResponse {
         status: "ok",
         version: env!("CARGO_PKG_VERSION"),
+        protocol_version: audio_bridge_types::BRIDGE_PROTOCOL_VERSION,
+        uptime_ms: state.started_at.elapsed().as_millis() as u64,
+        last_error: state.log_buffer.last_error(),
     })
 }
 
@@ -225,19 +391,48 @@ async fn devices_stream(state: web::Data<AppState>) -> HttpResponse {
 }
 
 /// Select active output device by id or name.
+///
+/// Selection by id accepts the reported id, stable id, platform id or alias and is stored as
+/// the stable id, so devices sharing a display name stay distinguishable and the selection
+/// survives re-enumeration; dummy outputs map to their name. Names that match an alias select
+/// the aliased device.
 async fn select_device(state: web::Data<AppState>, body: web::Bytes) -> HttpResponse {
     let req: DeviceSelectRequest = match parse_json(&body) {
         Ok(req) => req,
         Err(resp) => return resp,
     };
+    let resample_quality = match req.resample_quality.as_deref() {
+        None => None,
+        Some(name) => match ResampleQuality::from_name(name) {
+            Some(quality) => Some(quality),
+            None => {
+                return error_response(
+                    StatusCode::BAD_REQUEST,
+                    &format!("unknown resample_quality: {name}"),
+                );
+            }
+        },
+    };
+    if let Some(bits) = req.bit_depth.filter(|bits| ![16, 24, 32].contains(bits)) {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            &format!("bit_depth must be 16, 24 or 32 (got {bits})"),
+        );
+    }
 
     let mut error: Option<HttpResponse> = None;
     let selected_name = if let Some(id) = req.id {
-        match list_available_devices(state.enable_dummy_outputs) {
+        match list_available_devices(&state) {
             Ok(devices) => devices
                 .into_iter()
-                .find(|dev| dev.id == id)
-                .map(|dev| dev.name),
+                .find(|dev| dev.matches_id(&id))
+                .map(|dev| {
+                    if dev.platform_id.is_some() {
+                        dev.stable_id
+                    } else {
+                        dev.name
+                    }
+                }),
             Err(e) => {
                 error = Some(error_response(
                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -247,7 +442,15 @@ async fn select_device(state: web::Data<AppState>, body: web::Bytes) -> HttpResp
             }
         }
     } else {
-        req.name
+        req.name.map(|name| match state.device_aliases.lock() {
+            Ok(aliases) => {
+                if state.hidden_devices.hides_selector(&aliases, &name) {
+                    error = Some(error_response(StatusCode::BAD_REQUEST, "device is hidden"));
+                }
+                aliases.resolve(&name)
+            }
+            Err(_) => name,
+        })
     };
 
     if let Some(resp) = error {
@@ -261,17 +464,67 @@ async fn select_device(state: web::Data<AppState>, body: web::Bytes) -> HttpResp
             }
         }
         if let Some(exclusive) = req.exclusive {
-            if let Ok(mut g) = state.exclusive_selected.lock() {
-                *g = exclusive;
+            if let Ok(mut g) = state.output_options.lock() {
+                g.exclusive = exclusive;
             }
             tracing::info!(exclusive, "bridge device select updated exclusive mode");
         }
+        // Each selection carries the output's full pre-roll and format settings, so absent
+        // clears them.
+        let preroll = req
+            .preroll_silence_ms
+            .map(|ms| ms.min(crate::cli::MAX_PREROLL_SILENCE_MS));
+        if let Ok(mut g) = state.output_options.lock() {
+            g.preroll_silence_ms = preroll;
+            g.resample_quality = resample_quality;
+            g.format_limits = OutputFormatLimits {
+                max_rate: req.max_sample_rate.filter(|rate| *rate > 0),
+                bit_depth: req.bit_depth,
+            };
+        }
         HttpResponse::NoContent().finish()
     } else {
         error_response(StatusCode::BAD_REQUEST, "unknown device")
     }
 }
 
+/// Assign or clear the alias of a physical output device.
+///
+/// The alias replaces the device's id in `/devices` (and so in hub output ids); selecting the
+/// device is unaffected since selections are stored by stable id.
+async fn set_device_alias(state: web::Data<AppState>, body: web::Bytes) -> HttpResponse {
+    let req: DeviceAliasRequest = match parse_json(&body) {
+        Ok(req) => req,
+        Err(resp) => return resp,
+    };
+    let devices = match list_available_devices(&state) {
+        Ok(devices) => devices,
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, &e),
+    };
+    let Some(dev) = devices.into_iter().find(|dev| dev.matches_id(&req.id)) else {
+        return error_response(StatusCode::NOT_FOUND, "unknown device");
+    };
+    if dev.platform_id.is_none() {
+        return error_response(StatusCode::BAD_REQUEST, "dummy outputs cannot be aliased");
+    }
+    let result = match state.device_aliases.lock() {
+        Ok(mut aliases) => aliases.set(&dev.stable_id, req.alias.as_deref()),
+        Err(_) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, "alias table poisoned"),
+    };
+    match result {
+        Ok(()) => {
+            tracing::info!(
+                device = %dev.name,
+                stable_id = %dev.stable_id,
+                alias = ?req.alias,
+                "device alias updated"
+            );
+            HttpResponse::NoContent().finish()
+        }
+        Err(e) => error_response(StatusCode::BAD_REQUEST, &format!("{e:#}")),
+    }
+}
+
 /// Return current playback status snapshot.
 async fn status_snapshot(state: web::Data<AppState>) -> HttpResponse {
     HttpResponse::Ok().json(build_status_snapshot(&state))
@@ -324,7 +577,28 @@ async fn play(state: web::Data<AppState>, body: web::Bytes) -> HttpResponse {
     if req.url.trim().is_empty() {
         return error_response(StatusCode::BAD_REQUEST, "url is required");
     }
-    remember_hub_origin(&state, &req.url);
+    if let Some(gain_db) = req.gain_db
+        && !(gain_db.is_finite() && gain_db.abs() <= MAX_PLAY_GAIN_DB)
+    {
+        return error_response(StatusCode::BAD_REQUEST, "gain_db out of range");
+    }
+    let range = TrackRange {
+        start_ms: req.start_ms.unwrap_or(0),
+        end_ms: req.end_ms,
+    };
+    if range.end_ms.is_some_and(|end| end <= range.start_ms) {
+        return error_response(StatusCode::BAD_REQUEST, "end_ms must be after start_ms");
+    }
+    if req.live && (req.seek_ms.is_some() || range != TrackRange::default()) {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "live streams cannot take seek_ms, start_ms or end_ms",
+        );
+    }
+    // Radio URLs point at the station, not at the hub to unregister from.
+    if !req.live {
+        remember_hub_origin(&state, &req.url);
+    }
 
     if state
         .player_tx
@@ -333,6 +607,9 @@ async fn play(state: web::Data<AppState>, body: web::Bytes) -> HttpResponse {
             ext_hint: req.ext_hint,
             title: req.title,
             seek_ms: req.seek_ms,
+            gain_db: req.gain_db,
+            range,
+            live: req.live,
         })
         .is_err()
     {
@@ -393,6 +670,14 @@ async fn seek(state: web::Data<AppState>, body: web::Bytes) -> HttpResponse {
         Ok(req) => req,
         Err(resp) => return resp,
     };
+    let live = state
+        .status
+        .lock()
+        .map(|s| s.live == Some(true))
+        .unwrap_or(false);
+    if live {
+        return error_response(StatusCode::CONFLICT, "live streams cannot seek");
+    }
 
     if state
         .player_tx
@@ -405,6 +690,30 @@ async fn seek(state: web::Data<AppState>, body: web::Bytes) -> HttpResponse {
     }
 }
 
+/// Jump to a chapter of the current file, by index or relative to the current chapter.
+async fn chapter(state: web::Data<AppState>, body: web::Bytes) -> HttpResponse {
+    let req: ChapterRequest = match parse_json(&body) {
+        Ok(req) => req,
+        Err(resp) => return resp,
+    };
+    let (chapters, elapsed_ms) = match state.status.lock() {
+        Ok(s) => (s.chapters.clone(), s.snapshot().elapsed_ms),
+        Err(_) => (None, None),
+    };
+    let Some(chapters) = chapters else {
+        return error_response(StatusCode::CONFLICT, "current track has no chapters");
+    };
+    let Some(ms) = chapter_seek_ms(&chapters, elapsed_ms.unwrap_or(0), &req) else {
+        return error_response(StatusCode::NOT_FOUND, "no such chapter");
+    };
+
+    if state.player_tx.send(PlayerCommand::Seek { ms }).is_err() {
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "player offline")
+    } else {
+        HttpResponse::NoContent().finish()
+    }
+}
+
 /// Return current volume/mute snapshot.
 async fn volume_snapshot(state: web::Data<AppState>) -> HttpResponse {
     let (value, muted) = state.volume.snapshot();
@@ -450,6 +759,157 @@ async fn set_mute(state: web::Data<AppState>, body: web::Bytes) -> HttpResponse
     HttpResponse::Ok().json(VolumeResponse { value, muted })
 }
 
+/// Return the mirror output's device and volume/mute state.
+async fn mirror_snapshot(state: web::Data<AppState>) -> HttpResponse {
+    match state.mirror.as_deref() {
+        Some(mirror) => mirror_response(mirror),
+        None => error_response(StatusCode::NOT_FOUND, "no mirror device configured"),
+    }
+}
+
+/// Set the mirror output volume; the running session picks it up immediately.
+async fn set_mirror_volume(state: web::Data<AppState>, body: web::Bytes) -> HttpResponse {
+    let Some(mirror) = state.mirror.as_deref() else {
+        return error_response(StatusCode::NOT_FOUND, "no mirror device configured");
+    };
+    let req: VolumeSetRequest = match parse_json(&body) {
+        Ok(req) => req,
+        Err(resp) => return resp,
+    };
+    mirror.set_volume(req.value);
+    mirror_response(mirror)
+}
+
+/// Set the mirror output mute flag.
+async fn set_mirror_mute(state: web::Data<AppState>, body: web::Bytes) -> HttpResponse {
+    let Some(mirror) = state.mirror.as_deref() else {
+        return error_response(StatusCode::NOT_FOUND, "no mirror device configured");
+    };
+    let req: MuteRequest = match parse_json(&body) {
+        Ok(req) => req,
+        Err(resp) => return resp,
+    };
+    mirror.set_muted(req.muted);
+    mirror_response(mirror)
+}
+
+/// Build the `/mirror` response payload.
+fn mirror_response(mirror: &MirrorTarget) -> HttpResponse {
+    let (value, muted) = mirror.snapshot();
+    HttpResponse::Ok().json(MirrorResponse {
+        device: mirror.selector().to_string(),
+        value,
+        muted,
+    })
+}
+
+/// Return buffered log entries filtered by level and sequence.
+async fn logs_snapshot(state: web::Data<AppState>, query: web::Query<LogsQuery>) -> HttpResponse {
+    let min_level = match parse_logs_level(query.level.as_deref()) {
+        Ok(level) => level,
+        Err(resp) => return resp,
+    };
+    let (logs, last_seq) = state
+        .log_buffer
+        .snapshot_with_seq(min_level, query.after.unwrap_or(0));
+    HttpResponse::Ok().json(LogsResponse { logs, last_seq })
+}
+
+/// Stream buffered and new log entries via SSE.
+async fn logs_stream(state: web::Data<AppState>, query: web::Query<LogsQuery>) -> HttpResponse {
+    let min_level = match parse_logs_level(query.level.as_deref()) {
+        Ok(level) => level,
+        Err(resp) => return resp,
+    };
+    let after = query.after.unwrap_or(0);
+    let (initial, last_seq) = state.log_buffer.snapshot_with_seq(min_level, after);
+    let initial_json = serde_json::to_string(&initial).unwrap_or_else(|_| "[]".to_string());
+    let mut pending = VecDeque::new();
+    pending.push_back(sse_event("logs", &initial_json));
+
+    let stream = unfold(
+        LogsStreamState {
+            state,
+            interval: actix_web::rt::time::interval(LOGS_STREAM_INTERVAL),
+            pending,
+            min_level,
+            last_seq: last_seq.max(after),
+            last_ping: Instant::now(),
+        },
+        |mut ctx| async move {
+            loop {
+                if let Some(chunk) = ctx.pending.pop_front() {
+                    return Some((Ok(chunk), ctx));
+                }
+
+                ctx.interval.tick().await;
+                push_ping_if_needed(&mut ctx.pending, &mut ctx.last_ping);
+
+                let (entries, seq_now) = ctx
+                    .state
+                    .log_buffer
+                    .snapshot_with_seq(ctx.min_level, ctx.last_seq);
+                for entry in entries {
+                    if let Ok(json) = serde_json::to_string(&entry) {
+                        ctx.pending.push_back(sse_event("log", &json));
+                    }
+                }
+                ctx.last_seq = ctx.last_seq.max(seq_now);
+            }
+        },
+    );
+
+    sse_response(stream)
+}
+
+/// Clear the in-memory log ring.
+async fn logs_clear(state: web::Data<AppState>) -> HttpResponse {
+    state.log_buffer.clear();
+    HttpResponse::NoContent().finish()
+}
+
+/// Return the active tracing filter directives.
+async fn log_level_snapshot(state: web::Data<AppState>) -> HttpResponse {
+    HttpResponse::Ok().json(LogLevelResponse {
+        filter: state.log_filter.current(),
+    })
+}
+
+/// Adjust the tracing filter at runtime (global and per-target).
+async fn set_log_level(state: web::Data<AppState>, body: web::Bytes) -> HttpResponse {
+    let req: LogLevelRequest = match parse_json(&body) {
+        Ok(req) => req,
+        Err(resp) => return resp,
+    };
+    let result = if req.reset {
+        state.log_filter.reset()
+    } else if let Some(filter) = req.filter.as_deref() {
+        state.log_filter.set(filter)
+    } else {
+        state.log_filter.update(req.level.as_deref(), &req.targets)
+    };
+    match result {
+        Ok(filter) => {
+            tracing::info!(filter = %filter, "log filter updated");
+            HttpResponse::Ok().json(LogLevelResponse { filter })
+        }
+        Err(e) => error_response(StatusCode::BAD_REQUEST, &e),
+    }
+}
+
+/// Parse an optional `level` query value, defaulting to all levels.
+fn parse_logs_level(value: Option<&str>) -> Result<tracing::Level, HttpResponse> {
+    match value.map(str::trim).filter(|v| !v.is_empty()) {
+        None => Ok(tracing::Level::TRACE),
+        Some(raw) => logs::parse_level(raw).ok_or_else(|| {
+            error_response(
+                StatusCode::BAD_REQUEST,
+                &format!("invalid log level: {raw}"),
+            )
+        }),
+    }
+}
+
 /// Parse request JSON body into the target type or return HTTP 400.
 fn parse_json<T: serde::de::DeserializeOwned>(body: &web::Bytes) -> Result<T, HttpResponse> {
     serde_json::from_slice(body)
@@ -458,27 +918,24 @@ fn parse_json<T: serde::de::DeserializeOwned>(body: &web::Bytes) -> Result<T, Ht
 
 /// Build a normalized, deduplicated device list response.
 fn build_devices_response(state: &AppState) -> Result<DevicesResponse, String> {
-    let devices = list_available_devices(state.enable_dummy_outputs)?;
+    let devices = list_available_devices(state)?;
     let mut seen = std::collections::HashSet::new();
     let mut deduped = Vec::new();
     for dev in devices {
         if seen.insert(dev.id.clone()) {
-            deduped.push(DeviceInfo {
-                id: dev.id,
-                name: dev.name,
-                min_rate: dev.min_rate,
-                max_rate: dev.max_rate,
-            });
+            deduped.push(dev);
         }
     }
     deduped.sort_by(|a, b| a.name.cmp(&b.name));
-    let selected = state.device_selected.lock().ok().and_then(|g| g.clone());
-    let selected_id = selected.as_ref().and_then(|name| {
+    let selector = state.device_selected.lock().ok().and_then(|g| g.clone());
+    let selected_dev = selector.as_ref().and_then(|sel| {
         deduped
             .iter()
-            .find(|dev| dev.name == *name)
-            .map(|dev| dev.id.clone())
+            .find(|dev| dev.matches_id(sel))
+            .or_else(|| deduped.iter().find(|dev| dev.name == *sel))
     });
+    let selected_id = selected_dev.map(|dev| dev.id.clone());
+    let selected = selected_dev.map(|dev| dev.name.clone()).or(selector);
     Ok(DevicesResponse {
         devices: deduped,
         selected,
@@ -487,25 +944,54 @@ fn build_devices_response(state: &AppState) -> Result<DevicesResponse, String> {
 }
 
 /// Collect physical + synthetic output devices for API selection/listing.
-fn list_available_devices(enable_dummy_outputs: bool) -> Result<Vec<DeviceInfo>, String> {
-    let host = cpal::default_host();
-    let mut devices: Vec<DeviceInfo> = device::list_device_infos(&host)
-        .map_err(|e| format!("{e:#}"))?
+///
+/// Physical devices are reported under their alias, or their stable id when unaliased.
+fn list_available_devices(state: &AppState) -> Result<Vec<DeviceInfo>, String> {
+    let host = device::output_host().map_err(|e| format!("{e:#}"))?;
+    let infos = device::list_device_infos(&host).map_err(|e| format!("{e:#}"))?;
+    let aliases = state
+        .device_aliases
+        .lock()
+        .map_err(|_| "alias table poisoned".to_string())?;
+    let mut devices: Vec<DeviceInfo> = infos
         .into_iter()
-        .map(|dev| DeviceInfo {
-            id: dev.id,
-            name: dev.name,
-            min_rate: dev.min_rate,
-            max_rate: dev.max_rate,
+        .filter(|dev| {
+            !state
+                .hidden_devices
+                .hides(&aliases, &dev.stable_id, &dev.id, &dev.name)
+        })
+        .map(|dev| {
+            let alias = aliases.alias(&dev.stable_id).map(str::to_string);
+            DeviceInfo {
+                id: alias.clone().unwrap_or_else(|| dev.stable_id.clone()),
+                stable_id: dev.stable_id,
+                platform_id: Some(dev.id),
+                alias,
+                name: dev.name,
+                min_rate: dev.min_rate,
+                max_rate: dev.max_rate,
+                sample_rates: dev.sample_rates,
+                sample_formats: dev.sample_formats,
+                channels: dev.channels,
+            }
         })
         .collect();
-    if enable_dummy_outputs {
+    drop(aliases);
+    if state.enable_dummy_outputs {
         for dev in dummy_output::list_devices() {
+            let mut sample_rates = vec![dev.normal_rate_hz, dev.exclusive_rate_hz];
+            sample_rates.dedup();
             devices.push(DeviceInfo {
                 id: dev.id.to_string(),
+                stable_id: dev.id.to_string(),
+                platform_id: None,
+                alias: None,
                 name: dev.name.to_string(),
                 min_rate: dev.min_rate_hz,
                 max_rate: dev.max_rate_hz,
+                sample_rates,
+                sample_formats: vec!["F32".to_string()],
+                channels: vec![2],
             });
         }
     }
@@ -532,6 +1018,9 @@ fn build_status_snapshot(state: &AppState) -> StatusSnapshot {
             resample_to_hz: None,
             sample_rate: None,
             output_nominal_rate: None,
+            output_disconnected: None,
+            output_mode: None,
+            resample_quality: None,
             channels: None,
             device: None,
             underrun_frames: None,
@@ -540,6 +1029,14 @@ fn build_status_snapshot(state: &AppState) -> StatusSnapshot {
             buffered_frames: None,
             buffer_capacity_frames: None,
             end_reason: None,
+            levels: None,
+            bit_perfect: None,
+            dop: None,
+            live: None,
+            stream_title: None,
+            corrupt_packets: None,
+            chapters: None,
+            chapter_index: None,
         })
 }
 
@@ -601,6 +1098,16 @@ struct StatusStreamState {
     last_ping: Instant,
 }
 
+/// Internal mutable state for the logs SSE loop.
+struct LogsStreamState {
+    state: web::Data<AppState>,
+    interval: actix_web::rt::time::Interval,
+    pending: VecDeque<Bytes>,
+    min_level: tracing::Level,
+    last_seq: u64,
+    last_ping: Instant,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -629,6 +1136,16 @@ mod tests {
         assert!(req.ext_hint.is_none());
         assert!(req.title.is_none());
         assert!(req.seek_ms.is_none());
+        assert!(req.gain_db.is_none());
+        assert!(req.start_ms.is_none());
+        assert!(req.end_ms.is_none());
+    }
+
+    #[test]
+    fn play_request_parses_gain_db() {
+        let req: PlayRequest =
+            serde_json::from_str(r#"{"url":"http://host/track.flac","gain_db":-6.5}"#).unwrap();
+        assert_eq!(req.gain_db, Some(-6.5));
     }
 
     #[test]
@@ -649,6 +1166,35 @@ mod tests {
         assert!(req.muted);
     }
 
+    #[test]
+    fn logs_query_defaults_to_none() {
+        let req: LogsQuery = serde_json::from_str("{}").unwrap();
+        assert!(req.level.is_none());
+        assert!(req.after.is_none());
+    }
+
+    #[test]
+    fn parse_logs_level_defaults_to_trace_and_rejects_unknown() {
+        assert_eq!(parse_logs_level(None).ok(), Some(tracing::Level::TRACE));
+        assert_eq!(
+            parse_logs_level(Some("warn")).ok(),
+            Some(tracing::Level::WARN)
+        );
+        assert!(parse_logs_level(Some("loud")).is_err());
+    }
+
+    #[test]
+    fn log_level_request_parses_targets() {
+        let req: LogLevelRequest = serde_json::from_str(
+            r#"{"level":"debug","targets":{"audio_player::decode":"trace","bridge":null}}"#,
+        )
+        .unwrap();
+        assert_eq!(req.level.as_deref(), Some("debug"));
+        assert_eq!(req.targets.len(), 2);
+        assert_eq!(req.targets["bridge"], None);
+        assert!(!req.reset);
+    }
+
     #[test]
     fn extract_origin_parses_http_origin() {
         assert_eq!(
@@ -9,6 +9,8 @@ use std::time::Duration;
 
 use symphonia::core::io::MediaSource;
 
+use crate::net::HubConnectOptions;
+
 /// Configuration for HTTP range fetching.
 #[derive(Clone, Debug)]
 pub(crate) struct HttpRangeConfig {
@@ -20,8 +22,8 @@ pub(crate) struct HttpRangeConfig {
     pub(crate) retry_attempts: usize,
     /// Base backoff for retries (multiplied by attempt number).
     pub(crate) retry_backoff: Duration,
-    /// Allow insecure TLS (self-signed certs).
-    pub(crate) tls_insecure: bool,
+    /// TLS, source-address and DSCP settings for the hub connection.
+    pub(crate) connect: HubConnectOptions,
 }
 
 impl Default for HttpRangeConfig {
@@ -32,7 +34,7 @@ impl Default for HttpRangeConfig {
             timeout: Duration::from_secs(10),
             retry_attempts: 5,
             retry_backoff: Duration::from_millis(200),
-            tls_insecure: false,
+            connect: HubConnectOptions::default(),
         }
     }
 }
@@ -40,6 +42,8 @@ impl Default for HttpRangeConfig {
 /// A simple HTTP range reader with a small in-memory block cache.
 pub(crate) struct HttpRangeSource {
     url: String,
+    /// `url` with the access token redacted, for logs and errors.
+    log_url: String,
     config: HttpRangeConfig,
     agent: ureq::Agent,
     pos: u64,
@@ -58,8 +62,9 @@ impl HttpRangeSource {
         cancel: Option<Arc<AtomicBool>>,
         error_flag: Option<Arc<AtomicBool>>,
     ) -> Self {
-        let agent = build_agent(config.tls_insecure);
+        let agent = crate::net::hub_agent(&config.connect, None);
         Self {
+            log_url: crate::net::redact_url(&url),
             url,
             config,
             agent,
@@ -108,7 +113,7 @@ impl HttpRangeSource {
         for attempt in 1..=attempts {
             let started = std::time::Instant::now();
             tracing::debug!(
-                url = %self.url,
+                url = %self.log_url,
                 range = %range,
                 attempt,
                 "http range request"
@@ -132,7 +137,7 @@ impl HttpRangeSource {
                     if attempt < attempts {
                         let backoff = self.config.retry_backoff.saturating_mul(attempt as u32);
                         tracing::warn!(
-                            url = %self.url,
+                            url = %self.log_url,
                             range = %range,
                             attempt,
                             backoff_ms = backoff.as_millis(),
@@ -143,7 +148,7 @@ impl HttpRangeSource {
                     }
                     self.mark_error();
                     tracing::error!(
-                        url = %self.url,
+                        url = %self.log_url,
                         range = %range,
                         "http range request failed: {e}"
                     );
@@ -170,7 +175,7 @@ impl HttpRangeSource {
                 .map(|s| s.to_string());
             tracing::debug!(
                 status = ?status,
-                url = %self.url,
+                url = %self.log_url,
                 range = %range,
                 content_length = ?content_length,
                 content_range = ?content_range,
@@ -188,7 +193,7 @@ impl HttpRangeSource {
                 if attempt < attempts {
                     let backoff = self.config.retry_backoff.saturating_mul(attempt as u32);
                     tracing::warn!(
-                        url = %self.url,
+                        url = %self.log_url,
                         range = %range,
                         attempt,
                         backoff_ms = backoff.as_millis(),
@@ -202,7 +207,7 @@ impl HttpRangeSource {
             }
             tracing::debug!(
                 status = ?status,
-                url = %self.url,
+                url = %self.log_url,
                 range = %range,
                 bytes = buf.len(),
                 content_length = ?content_length,
@@ -224,13 +229,13 @@ impl HttpRangeSource {
                     io::ErrorKind::Other,
                     format!(
                         "http range status={status} url={} range={} content_length={:?} content_range={:?} content_type={:?}{}",
-                        self.url, range, content_length, content_range, content_type, detail
+                        self.log_url, range, content_length, content_range, content_type, detail
                     ),
                 ));
                 if attempt < attempts && status.is_server_error() {
                     let backoff = self.config.retry_backoff.saturating_mul(attempt as u32);
                     tracing::warn!(
-                        url = %self.url,
+                        url = %self.log_url,
                         range = %range,
                         status = ?status,
                         attempt,
@@ -248,13 +253,13 @@ impl HttpRangeSource {
                     io::ErrorKind::Other,
                     format!(
                         "http range empty body status={status} url={} range={} content_length={:?} content_range={:?} content_type={:?}",
-                        self.url, range, content_length, content_range, content_type
+                        self.log_url, range, content_length, content_range, content_type
                     ),
                 ));
                 if attempt < attempts {
                     let backoff = self.config.retry_backoff.saturating_mul(attempt as u32);
                     tracing::warn!(
-                        url = %self.url,
+                        url = %self.log_url,
                         range = %range,
                         attempt,
                         backoff_ms = backoff.as_millis(),
@@ -315,7 +320,7 @@ impl HttpRangeSource {
 
         let (buf, len) = self.fetch_range(start, end)?;
         tracing::debug!(
-            url = %self.url,
+            url = %self.log_url,
             start = start,
             end = end,
             bytes = buf.len(),
@@ -331,21 +336,6 @@ impl HttpRangeSource {
     }
 }
 
-/// Build the HTTP client used for range requests.
-fn build_agent(tls_insecure: bool) -> ureq::Agent {
-    let mut tls_builder = ureq::tls::TlsConfig::builder()
-        .provider(ureq::tls::TlsProvider::Rustls)
-        .root_certs(ureq::tls::RootCerts::PlatformVerifier);
-    if tls_insecure {
-        tls_builder = tls_builder.disable_verification(true);
-    }
-    let tls = tls_builder.build();
-    ureq::Agent::config_builder()
-        .tls_config(tls)
-        .build()
-        .new_agent()
-}
-
 impl Read for HttpRangeSource {
     /// Read audio bytes at the current cursor, refilling from HTTP ranges as needed.
     fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
@@ -358,7 +348,7 @@ impl Read for HttpRangeSource {
         if let Some(len) = self.len {
             if self.pos >= len {
                 tracing::debug!(
-                    url = %self.url,
+                    url = %self.log_url,
                     pos = self.pos,
                     len = len,
                     "http read reached end"
@@ -0,0 +1,341 @@
+//! Endless internet radio streams (Shoutcast/Icecast).
+//!
+//! A live source is a single long-running GET sent with `Icy-MetaData: 1`. The server then
+//! interleaves a metadata block after every `icy-metaint` audio bytes; those blocks are stripped
+//! before decoding and their `StreamTitle` is published as the current now-playing title. When
+//! the connection drops the source reconnects, so decoding only ends on stop or after repeated
+//! failures.
+
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use symphonia::core::io::MediaSource;
+
+use crate::http_stream::HttpRangeConfig;
+
+/// Now-playing title shared between a live source and the status endpoint.
+pub(crate) type StreamTitle = Arc<Mutex<Option<String>>>;
+
+/// Strips ICY metadata blocks from an audio byte stream.
+pub(crate) struct IcyReader<R> {
+    inner: R,
+    metaint: Option<usize>,
+    until_meta: usize,
+    title: StreamTitle,
+}
+
+impl<R: Read> IcyReader<R> {
+    /// Wrap `inner`; `metaint` is the `icy-metaint` response header (`None`: no metadata).
+    pub(crate) fn new(inner: R, metaint: Option<usize>, title: StreamTitle) -> Self {
+        let metaint = metaint.filter(|n| *n > 0);
+        Self {
+            inner,
+            metaint,
+            until_meta: metaint.unwrap_or(0),
+            title,
+        }
+    }
+
+    /// Consume one metadata block and publish its title.
+    fn read_metadata(&mut self) -> io::Result<()> {
+        let mut len = [0u8; 1];
+        self.inner.read_exact(&mut len)?;
+        let len = usize::from(len[0]) * 16;
+        if len == 0 {
+            return Ok(());
+        }
+        let mut block = vec![0u8; len];
+        self.inner.read_exact(&mut block)?;
+        if let Some(title) = parse_stream_title(&block) {
+            let title = Some(title).filter(|t| !t.is_empty());
+            let mut current = self.title.lock().unwrap_or_else(PoisonError::into_inner);
+            if *current != title {
+                tracing::info!(title = title.as_deref().unwrap_or(""), "live stream title");
+                *current = title;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for IcyReader<R> {
+    /// Read audio bytes, skipping over any metadata block in the way.
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let Some(metaint) = self.metaint else {
+            return self.inner.read(out);
+        };
+        if out.is_empty() {
+            return Ok(0);
+        }
+        if self.until_meta == 0 {
+            self.read_metadata()?;
+            self.until_meta = metaint;
+        }
+        let want = out.len().min(self.until_meta);
+        let read = self.inner.read(&mut out[..want])?;
+        self.until_meta -= read;
+        Ok(read)
+    }
+}
+
+/// Extract `StreamTitle` from an ICY metadata block (`StreamTitle='...';StreamUrl='...';`).
+fn parse_stream_title(block: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(block);
+    let text = text.trim_end_matches('\0');
+    let start = text.find("StreamTitle='")? + "StreamTitle='".len();
+    let rest = &text[start..];
+    // Titles may contain apostrophes, so the value ends at the `';` closing the field.
+    let end = rest
+        .find("';")
+        .or_else(|| rest.rfind('\''))
+        .unwrap_or(rest.len());
+    Some(rest[..end].trim().to_string())
+}
+
+/// Live HTTP source that reconnects when the stream drops.
+pub(crate) struct LiveStreamSource {
+    url: String,
+    /// `url` with the access token redacted, for logs.
+    log_url: String,
+    config: HttpRangeConfig,
+    agent: ureq::Agent,
+    reader: Option<Mutex<IcyReader<ureq::BodyReader<'static>>>>,
+    title: StreamTitle,
+    /// Whether the current connection has delivered any audio.
+    received: bool,
+    /// Consecutive connections that failed or ended without audio.
+    failures: usize,
+    cancel: Option<Arc<AtomicBool>>,
+    error_flag: Option<Arc<AtomicBool>>,
+}
+
+impl LiveStreamSource {
+    /// Create a live source; the connection is opened on first read.
+    pub(crate) fn new(
+        url: String,
+        config: HttpRangeConfig,
+        title: StreamTitle,
+        cancel: Option<Arc<AtomicBool>>,
+        error_flag: Option<Arc<AtomicBool>>,
+    ) -> Self {
+        // No overall timeout: the body never ends. Connect/response timeouts are set per call.
+        let agent = crate::net::hub_agent(&config.connect, None);
+        Self {
+            log_url: crate::net::redact_url(&url),
+            url,
+            config,
+            agent,
+            reader: None,
+            title,
+            received: false,
+            failures: 0,
+            cancel,
+            error_flag,
+        }
+    }
+
+    fn is_canceled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .map(|c| c.load(Ordering::Relaxed))
+            .unwrap_or(false)
+    }
+
+    /// Mark the source as failed so upstream status can surface stream errors.
+    fn mark_error(&self) {
+        if let Some(flag) = &self.error_flag {
+            flag.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Open the stream and read its ICY headers.
+    fn connect(&mut self) -> io::Result<()> {
+        let resp = self
+            .agent
+            .get(&self.url)
+            .config()
+            .timeout_connect(Some(self.config.timeout))
+            .timeout_recv_response(Some(self.config.timeout))
+            .build()
+            .header("Icy-MetaData", "1")
+            .call()
+            .map_err(|e| io::Error::other(format!("live stream request failed: {e}")))?;
+        let header = |name: &str| {
+            resp.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let metaint = header("icy-metaint").and_then(|v| v.trim().parse::<usize>().ok());
+        tracing::info!(
+            url = %self.log_url,
+            station = header("icy-name").as_deref().unwrap_or(""),
+            content_type = header("Content-Type").as_deref().unwrap_or(""),
+            metaint = ?metaint,
+            "live stream connected"
+        );
+        let (_, body) = resp.into_parts();
+        self.reader = Some(Mutex::new(IcyReader::new(
+            body.into_reader(),
+            metaint,
+            self.title.clone(),
+        )));
+        self.received = false;
+        Ok(())
+    }
+
+    /// Drop the connection and back off; fails once reconnects keep producing no audio.
+    fn dropped(&mut self, reason: &str) -> io::Result<()> {
+        self.reader = None;
+        if self.received {
+            self.failures = 0;
+        }
+        self.received = false;
+        self.failures += 1;
+        let attempts = self.config.retry_attempts.max(1);
+        if self.failures >= attempts {
+            self.mark_error();
+            tracing::error!(url = %self.log_url, reason, "live stream failed");
+            return Err(io::Error::other(format!("live stream failed: {reason}")));
+        }
+        let backoff = self
+            .config
+            .retry_backoff
+            .saturating_mul(self.failures as u32);
+        tracing::warn!(
+            url = %self.log_url,
+            reason,
+            attempt = self.failures,
+            backoff_ms = backoff.as_millis(),
+            "live stream dropped; reconnecting"
+        );
+        std::thread::sleep(backoff);
+        Ok(())
+    }
+}
+
+impl Read for LiveStreamSource {
+    /// Read audio bytes, reconnecting transparently when the server drops the stream.
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if out.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            if self.is_canceled() {
+                return Ok(0);
+            }
+            let Some(reader) = self.reader.as_mut() else {
+                if let Err(e) = self.connect() {
+                    self.dropped(&e.to_string())?;
+                }
+                continue;
+            };
+            let reader = reader.get_mut().unwrap_or_else(PoisonError::into_inner);
+            match reader.read(out) {
+                Ok(0) => self.dropped("stream ended")?,
+                Ok(read) => {
+                    self.received = true;
+                    return Ok(read);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => self.dropped(&e.to_string())?,
+            }
+        }
+    }
+}
+
+impl Seek for LiveStreamSource {
+    /// Live streams have no positions to seek to.
+    fn seek(&mut self, _pos: SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "live streams cannot seek",
+        ))
+    }
+}
+
+impl MediaSource for LiveStreamSource {
+    /// Live streams are read front to back only.
+    fn is_seekable(&self) -> bool {
+        false
+    }
+
+    /// Live streams have no length.
+    fn byte_len(&self) -> Option<u64> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Build an ICY metadata block (length byte + NUL-padded text).
+    fn meta_block(text: &str) -> Vec<u8> {
+        let blocks = text.len().div_ceil(16);
+        let mut out = vec![blocks as u8];
+        out.extend_from_slice(text.as_bytes());
+        out.resize(1 + blocks * 16, 0);
+        out
+    }
+
+    #[test]
+    fn parse_stream_title_reads_value() {
+        let block = b"StreamTitle='Artist - It's A Song';StreamUrl='';\0\0";
+        assert_eq!(
+            parse_stream_title(block).as_deref(),
+            Some("Artist - It's A Song")
+        );
+        assert_eq!(parse_stream_title(b"StreamUrl='x';"), None);
+    }
+
+    #[test]
+    fn icy_reader_strips_metadata_and_publishes_title() {
+        let mut stream = vec![1u8, 2, 3, 4];
+        stream.extend(meta_block("StreamTitle='First';"));
+        stream.extend([5u8, 6, 7, 8]);
+        stream.push(0);
+        stream.extend([9u8, 10]);
+        let title = StreamTitle::default();
+        let mut reader = IcyReader::new(Cursor::new(stream), Some(4), title.clone());
+
+        let mut audio = Vec::new();
+        let mut buf = [0u8; 3];
+        loop {
+            let read = reader.read(&mut buf).unwrap();
+            if read == 0 {
+                break;
+            }
+            audio.extend_from_slice(&buf[..read]);
+        }
+        assert_eq!(audio, (1u8..=10).collect::<Vec<_>>());
+        assert_eq!(title.lock().unwrap().as_deref(), Some("First"));
+    }
+
+    #[test]
+    fn icy_reader_passes_through_without_metaint() {
+        let title = StreamTitle::default();
+        let mut reader = IcyReader::new(Cursor::new(vec![1u8, 2, 3]), None, title.clone());
+        let mut audio = Vec::new();
+        reader.read_to_end(&mut audio).unwrap();
+        assert_eq!(audio, vec![1, 2, 3]);
+        assert!(title.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn live_source_is_not_seekable_and_stops_when_canceled() {
+        let cancel = Arc::new(AtomicBool::new(true));
+        let mut source = LiveStreamSource::new(
+            "http://example/radio".to_string(),
+            HttpRangeConfig::default(),
+            StreamTitle::default(),
+            Some(cancel),
+            None,
+        );
+        assert!(!source.is_seekable());
+        assert!(source.seek(SeekFrom::Start(0)).is_err());
+        assert_eq!(source.read(&mut [0u8; 4]).unwrap(), 0);
+    }
+}
@@ -6,13 +6,25 @@
 pub mod cli;
 /// Runtime configuration types for listen/play modes.
 pub mod config;
+/// User-assigned output device aliases.
+pub mod device_aliases;
+/// In-memory log ring exposed over the HTTP API.
+pub mod logs;
+/// Offline replay of hub stream captures.
+pub mod replay;
 /// Top-level execution helpers for bridge commands.
 pub mod runtime;
+/// launchd / Windows service registration and hosting.
+pub mod service;
 
 mod dummy_output;
 mod exclusive;
+mod fallback;
+mod hidden_devices;
 mod http_api;
 mod http_stream;
+mod icy;
 mod mdns;
+mod net;
 mod player;
 mod status;
@@ -0,0 +1,267 @@
+//! In-memory log ring for remote debugging.
+//!
+//! Captures tracing events into a bounded buffer so headless bridges can be
+//! inspected over HTTP (`/logs`, `/logs/stream`) without shell access.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tracing::field::{Field, Visit};
+use tracing::{Level, Subscriber};
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::Context;
+
+/// Default number of log entries retained in memory.
+pub const DEFAULT_LOG_CAPACITY: usize = 500;
+
+/// One captured log line.
+#[derive(Clone, Debug, serde::Serialize, PartialEq, Eq)]
+pub struct LogEntry {
+    /// Monotonic sequence number (used by stream consumers to resume).
+    pub seq: u64,
+    /// Log level string (`ERROR`, `WARN`, `INFO`, `DEBUG`, `TRACE`).
+    pub level: String,
+    /// Tracing target/module.
+    pub target: String,
+    /// Formatted message + selected fields.
+    pub message: String,
+    /// Event timestamp (unix millis).
+    pub timestamp_ms: i64,
+}
+
+/// Bounded, thread-safe ring of recent log entries.
+#[derive(Debug)]
+pub struct LogBuffer {
+    inner: Mutex<LogBufferInner>,
+    capacity: usize,
+}
+
+#[derive(Debug)]
+struct LogBufferInner {
+    entries: VecDeque<LogEntry>,
+    next_seq: u64,
+    /// Newest `ERROR` entry, kept after it leaves the ring or the ring is cleared.
+    last_error: Option<LogEntry>,
+}
+
+impl LogBuffer {
+    /// Create a log ring retaining at most `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(LogBufferInner {
+                entries: VecDeque::with_capacity(capacity),
+                next_seq: 1,
+                last_error: None,
+            }),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Append one entry, evicting the oldest when full.
+    pub fn push(&self, level: Level, target: &str, message: String, timestamp_ms: i64) {
+        if let Ok(mut g) = self.inner.lock() {
+            let seq = g.next_seq;
+            g.next_seq = g.next_seq.saturating_add(1);
+            let entry = LogEntry {
+                seq,
+                level: level.to_string(),
+                target: target.to_string(),
+                message,
+                timestamp_ms,
+            };
+            if level == Level::ERROR {
+                g.last_error = Some(entry.clone());
+            }
+            g.entries.push_back(entry);
+            while g.entries.len() > self.capacity {
+                g.entries.pop_front();
+            }
+        }
+    }
+
+    /// Return buffered entries newer than `after_seq` at or above `min_level` severity.
+    pub fn snapshot(&self, min_level: Level, after_seq: u64) -> Vec<LogEntry> {
+        self.snapshot_with_seq(min_level, after_seq).0
+    }
+
+    /// Like [`Self::snapshot`], plus the newest sequence number read under the same lock, so
+    /// a follow-up `snapshot(_, seq)` returns exactly the entries pushed since.
+    pub fn snapshot_with_seq(&self, min_level: Level, after_seq: u64) -> (Vec<LogEntry>, u64) {
+        self.inner
+            .lock()
+            .map(|g| {
+                let entries = g
+                    .entries
+                    .iter()
+                    .filter(|e| e.seq > after_seq && level_passes(&e.level, min_level))
+                    .cloned()
+                    .collect();
+                (entries, g.next_seq.saturating_sub(1))
+            })
+            .unwrap_or_default()
+    }
+
+    /// Sequence number of the newest entry (0 when empty).
+    pub fn last_seq(&self) -> u64 {
+        self.inner
+            .lock()
+            .map(|g| g.next_seq.saturating_sub(1))
+            .unwrap_or(0)
+    }
+
+    /// Newest `ERROR` entry since startup, even if it has left the ring.
+    pub fn last_error(&self) -> Option<LogEntry> {
+        self.inner.lock().ok().and_then(|g| g.last_error.clone())
+    }
+
+    /// Drop all buffered entries (sequence numbers keep increasing).
+    pub fn clear(&self) {
+        if let Ok(mut g) = self.inner.lock() {
+            g.entries.clear();
+        }
+    }
+}
+
+/// Parse a user-supplied level filter (`error`, `warn`, `info`, `debug`, `trace`).
+pub fn parse_level(value: &str) -> Option<Level> {
+    value.trim().parse::<Level>().ok()
+}
+
+/// Whether an entry with `entry_level` is at least as severe as `min_level`.
+fn level_passes(entry_level: &str, min_level: Level) -> bool {
+    // tracing orders levels by verbosity: ERROR < WARN < INFO < DEBUG < TRACE.
+    parse_level(entry_level)
+        .map(|lvl| lvl <= min_level)
+        .unwrap_or(true)
+}
+
+/// Tracing layer that forwards events into a shared [`LogBuffer`].
+pub struct LogLayer {
+    buffer: std::sync::Arc<LogBuffer>,
+}
+
+impl LogLayer {
+    /// Create a tracing layer backed by the given log ring.
+    pub fn new(buffer: std::sync::Arc<LogBuffer>) -> Self {
+        Self { buffer }
+    }
+}
+
+impl<S> Layer<S> for LogLayer
+where
+    S: Subscriber,
+{
+    /// Convert a tracing event into a [`LogEntry`] and store it.
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = LogVisitor::default();
+        event.record(&mut visitor);
+        let mut message = visitor.message.unwrap_or_else(|| "log event".to_string());
+        if !visitor.fields.is_empty() {
+            message = format!("{message} {}", visitor.fields.join(" "));
+        }
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        let meta = event.metadata();
+        self.buffer
+            .push(*meta.level(), meta.target(), message, timestamp_ms);
+    }
+}
+
+/// Visitor collecting primary message and key/value fields from tracing events.
+#[derive(Default)]
+struct LogVisitor {
+    message: Option<String>,
+    fields: Vec<String>,
+}
+
+impl Visit for LogVisitor {
+    /// Record string fields from tracing events.
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = Some(value.to_string());
+        } else {
+            self.fields.push(format!("{}={}", field.name(), value));
+        }
+    }
+
+    /// Record debug-formatted fields from tracing events.
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        let formatted = format!("{value:?}");
+        if field.name() == "message" {
+            self.message = Some(formatted.trim_matches('"').to_string());
+        } else {
+            self.fields.push(format!("{}={}", field.name(), formatted));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_evicts_oldest_when_full() {
+        let buf = LogBuffer::new(2);
+        buf.push(Level::INFO, "t", "a".to_string(), 1);
+        buf.push(Level::INFO, "t", "b".to_string(), 2);
+        buf.push(Level::INFO, "t", "c".to_string(), 3);
+        let all = buf.snapshot(Level::TRACE, 0);
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].message, "b");
+        assert_eq!(all[1].seq, 3);
+        assert_eq!(buf.last_seq(), 3);
+    }
+
+    #[test]
+    fn snapshot_filters_by_level_and_seq() {
+        let buf = LogBuffer::new(10);
+        buf.push(Level::DEBUG, "t", "dbg".to_string(), 1);
+        buf.push(Level::WARN, "t", "warn".to_string(), 2);
+        buf.push(Level::ERROR, "t", "err".to_string(), 3);
+
+        let warn = buf.snapshot(Level::WARN, 0);
+        assert_eq!(
+            warn.iter().map(|e| e.message.as_str()).collect::<Vec<_>>(),
+            vec!["warn", "err"]
+        );
+        let (entries, seq) = buf.snapshot_with_seq(Level::WARN, 0);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(seq, 3);
+        let after = buf.snapshot(Level::TRACE, 2);
+        assert_eq!(after.len(), 1);
+        assert_eq!(after[0].message, "err");
+    }
+
+    #[test]
+    fn last_error_outlives_eviction_and_clear() {
+        let buf = LogBuffer::new(1);
+        assert!(buf.last_error().is_none());
+        buf.push(Level::ERROR, "t", "boom".to_string(), 1);
+        buf.push(Level::WARN, "t", "later".to_string(), 2);
+        buf.clear();
+        let last = buf.last_error().unwrap();
+        assert_eq!(last.message, "boom");
+        assert_eq!(last.seq, 1);
+    }
+
+    #[test]
+    fn parse_level_is_case_insensitive() {
+        assert_eq!(parse_level("warn"), Some(Level::WARN));
+        assert_eq!(parse_level(" ERROR "), Some(Level::ERROR));
+        assert_eq!(parse_level("loud"), None);
+    }
+
+    #[test]
+    fn clear_keeps_sequence_monotonic() {
+        let buf = LogBuffer::new(4);
+        buf.push(Level::INFO, "t", "a".to_string(), 1);
+        buf.clear();
+        buf.push(Level::INFO, "t", "b".to_string(), 2);
+        let all = buf.snapshot(Level::TRACE, 0);
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].seq, 2);
+    }
+}
@@ -1,10 +1,17 @@
 use anyhow::Result;
+use audio_player::log_filter::LogFilterControl;
+use audio_player::mirror::MirrorTarget;
+use audio_player::record::Recorder;
 use clap::Parser;
-use tracing_subscriber::EnvFilter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{EnvFilter, reload};
 
 use bridge::cli;
-use bridge::config::{BridgeListenConfig, BridgePlayConfig, PlaybackConfig};
-use bridge::runtime;
+use bridge::config::{
+    BridgeListenConfig, BridgeLocation, BridgePlayConfig, BridgeReplayConfig, PlaybackConfig,
+};
+use bridge::logs::{DEFAULT_LOG_CAPACITY, LogBuffer, LogLayer};
+use bridge::{runtime, service};
 
 const VERSION: &str = concat!(
     env!("CARGO_PKG_VERSION"),
@@ -18,13 +25,25 @@ const VERSION: &str = concat!(
 /// Parse CLI args, configure logging, and run the selected bridge command.
 fn main() -> Result<()> {
     let args = cli::Args::parse();
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| EnvFilter::new("info,bridge=info")),
-        )
+    let log_buffer = std::sync::Arc::new(LogBuffer::new(DEFAULT_LOG_CAPACITY));
+    let env_filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info,bridge=info"));
+    let initial_filter = env_filter.to_string();
+    let (env_filter, filter_handle) = reload::Layer::new(env_filter);
+    let log_filter = std::sync::Arc::new(LogFilterControl::new(filter_handle, initial_filter));
+    tracing_subscriber::registry()
+        .with(env_filter)
+        .with(tracing_subscriber::fmt::layer())
+        .with(LogLayer::new(log_buffer.clone()))
         .init();
 
+    audio_player::device::set_output_backend(args.output_backend());
+
+    if args.check_config {
+        let ok = runtime::check_config(&args);
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+
     if args.list_devices {
         runtime::list_devices(args.enable_dummy_outputs)?;
         return Ok(());
@@ -34,6 +53,8 @@ fn main() -> Result<()> {
         version = VERSION,
         http_bind = %args.http_bind,
         device = ?args.device,
+        mirror_device = ?args.mirror_device,
+        backend = args.backend.as_str(),
         enable_dummy_outputs = args.enable_dummy_outputs,
         "bridge starting"
     );
@@ -42,6 +63,26 @@ fn main() -> Result<()> {
         chunk_frames: args.chunk_frames,
         refill_max_frames: args.refill_max_frames,
         buffer_seconds: args.buffer_seconds,
+        record: match args.record.as_deref() {
+            Some(path) => Some(std::sync::Arc::new(Recorder::new(path)?)),
+            None => None,
+        },
+        mirror: args
+            .mirror_device
+            .as_deref()
+            .map(|selector| std::sync::Arc::new(MirrorTarget::new(selector))),
+        rate_switch: args.rate_switch.into(),
+        resample_quality: args.resample_quality.into(),
+        resample_backend: args.resampler.into(),
+        underrun_concealment: args.underrun_concealment.into(),
+        preroll_silence_ms: args.preroll_silence_ms,
+        min_prebuffer_ms: args.min_prebuffer_ms,
+        silence_trim: args.silence_trim(),
+        decode_errors: args.decode_errors(),
+        dsd_output: args.dsd.into(),
+        dop: false,
+        pre_gain_db: 0.0,
+        channel_mix: Default::default(),
     };
 
     match &args.cmd {
@@ -54,18 +95,66 @@ fn main() -> Result<()> {
             };
             runtime::run_play(cfg)?;
         }
-        cli::Command::Listen => {
-            let cfg = BridgeListenConfig {
-                http_bind: args.http_bind,
+        cli::Command::Replay {
+            capture,
+            speed,
+            serve_only,
+            bind,
+        } => {
+            runtime::run_replay(BridgeReplayConfig {
+                capture: capture.clone(),
+                speed: *speed,
+                serve_only: *serve_only,
+                bind: *bind,
                 device: args.device.clone(),
                 playback,
-                tls_insecure: args.tls_insecure,
-                hub_url: args.hub_url.clone(),
-                enable_dummy_outputs: args.enable_dummy_outputs,
-            };
+            })?;
+        }
+        cli::Command::Listen => {
+            let cfg = listen_config(&args, playback, log_buffer, log_filter);
             runtime::run_listen(cfg, true)?;
         }
+        cli::Command::Service { action } => match action {
+            cli::ServiceAction::Install { name } => service::install(name, &args.service_args())?,
+            cli::ServiceAction::Uninstall { name } => service::uninstall(name)?,
+            cli::ServiceAction::Run { name } => {
+                let cfg = listen_config(&args, playback, log_buffer, log_filter);
+                service::run(name, cfg)?;
+            }
+        },
     }
 
     Ok(())
 }
+
+/// Listener config shared by `listen` and `service run`.
+fn listen_config(
+    args: &cli::Args,
+    playback: PlaybackConfig,
+    log_buffer: std::sync::Arc<LogBuffer>,
+    log_filter: std::sync::Arc<LogFilterControl>,
+) -> BridgeListenConfig {
+    BridgeListenConfig {
+        http_bind: args.http_bind,
+        device: args.device.clone(),
+        device_aliases: args.device_aliases_path(),
+        hidden_devices: args.hide_devices.clone(),
+        fallback_devices: args.fallback_devices.clone(),
+        fallback_return_to_preferred: args.fallback_return == cli::FallbackReturnArg::Track,
+        playback,
+        tls_insecure: args.tls_insecure,
+        source_ip: args.source_ip,
+        dscp: args
+            .dscp
+            .as_deref()
+            .and_then(audio_bridge_types::parse_dscp),
+        hub_url: args.hub_url.clone(),
+        location: BridgeLocation {
+            room: args.room.clone(),
+            zone: args.zone.clone(),
+        },
+        enable_dummy_outputs: args.enable_dummy_outputs,
+        log_buffer,
+        log_filter,
+    }
+}
@@ -1,9 +1,11 @@
 //! mDNS advertisement for bridge discovery.
 //!
-//! Publishes the bridge API address with id/name metadata.
+//! Publishes the bridge API address with id/name (and optional room/zone) metadata.
 
 use mdns_sd::{ServiceDaemon, ServiceInfo};
 
+use crate::config::BridgeLocation;
+
 /// Handle for an active mDNS advertisement.
 pub(crate) struct MdnsAdvertiser {
     daemon: ServiceDaemon,
@@ -11,7 +13,10 @@ pub(crate) struct MdnsAdvertiser {
 }
 
 /// Start advertising the bridge via mDNS.
-pub(crate) fn spawn_mdns_advertiser(http_bind: std::net::SocketAddr) -> Option<MdnsAdvertiser> {
+pub(crate) fn spawn_mdns_advertiser(
+    http_bind: std::net::SocketAddr,
+    location: &BridgeLocation,
+) -> Option<MdnsAdvertiser> {
     let daemon = match ServiceDaemon::new() {
         Ok(d) => d,
         Err(e) => {
@@ -25,7 +30,7 @@ pub(crate) fn spawn_mdns_advertiser(http_bind: std::net::SocketAddr) -> Option<M
     let id = resolve_bridge_id(&host_base);
     let name = resolve_bridge_name(&host_base);
     let instance = format!("{id}");
-    let properties: std::collections::HashMap<String, String> = [
+    let mut properties: std::collections::HashMap<String, String> = [
         ("id".to_string(), id.clone()),
         ("name".to_string(), name.clone()),
         ("api_port".to_string(), http_bind.port().to_string()),
@@ -33,6 +38,7 @@ pub(crate) fn spawn_mdns_advertiser(http_bind: std::net::SocketAddr) -> Option<M
     ]
     .into_iter()
     .collect();
+    properties.extend(location_properties(location));
     let ip = if http_bind.ip().is_unspecified() {
         local_ip().unwrap_or(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST))
     } else {
@@ -106,6 +112,17 @@ fn resolve_bridge_name(host_base: &str) -> String {
     std::env::var("BRIDGE_NAME").unwrap_or_else(|_| host_base.to_string())
 }
 
+/// TXT entries for the configured room/zone labels; blank labels are left out.
+fn location_properties(location: &BridgeLocation) -> Vec<(String, String)> {
+    [("room", &location.room), ("zone", &location.zone)]
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let value = value.as_deref()?.trim();
+            (!value.is_empty()).then(|| (key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
 /// Determine a best-effort local IP for advertisement.
 fn local_ip() -> Option<std::net::IpAddr> {
     let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
@@ -124,4 +141,16 @@ mod tests {
         assert_eq!(format_host("bridge"), "bridge.local.");
         assert_eq!(format_host("bridge.local."), "bridge.local.");
     }
+
+    #[test]
+    fn location_properties_skip_blank_labels() {
+        let location = BridgeLocation {
+            room: Some(" Kitchen ".to_string()),
+            zone: Some("  ".to_string()),
+        };
+        assert_eq!(
+            location_properties(&location),
+            [("room".to_string(), "Kitchen".to_string())]
+        );
+    }
 }
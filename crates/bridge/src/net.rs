@@ -0,0 +1,291 @@
+//! Network options for bridge-to-hub connections.
+//!
+//! Audio stream fetches and hub unregister calls go through [`hub_agent`], which can bind the
+//! socket to a source IP (multi-homed hosts) and mark it with a DSCP class.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::time::Duration;
+
+use socket2::{Domain, Protocol, SockRef, Socket, Type};
+use ureq::unversioned::transport::{
+    Buffers, ConnectionDetails, Connector, LazyBuffers, NextTimeout, RustlsConnector, TcpConnector,
+    Transport,
+};
+
+/// Connection settings for requests to the hub.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct HubConnectOptions {
+    /// Allow insecure TLS (self-signed certs).
+    pub(crate) tls_insecure: bool,
+    /// Local address outgoing connections bind to.
+    pub(crate) source_ip: Option<IpAddr>,
+    /// DSCP code point applied to outgoing connections.
+    pub(crate) dscp: Option<u8>,
+}
+
+/// Build the HTTP client used for hub requests.
+pub(crate) fn hub_agent(options: &HubConnectOptions, timeout: Option<Duration>) -> ureq::Agent {
+    let mut tls_builder = ureq::tls::TlsConfig::builder()
+        .provider(ureq::tls::TlsProvider::Rustls)
+        .root_certs(ureq::tls::RootCerts::PlatformVerifier);
+    if options.tls_insecure {
+        tls_builder = tls_builder.disable_verification(true);
+    }
+    let config = ureq::Agent::config_builder()
+        .tls_config(tls_builder.build())
+        .timeout_global(timeout)
+        .build();
+    if options.source_ip.is_none() && options.dscp.is_none() {
+        return config.new_agent();
+    }
+    // The bound connector opens the socket; TcpConnector passes it through untouched.
+    let connector = ()
+        .chain(BoundTcpConnector {
+            source_ip: options.source_ip,
+            dscp: options.dscp,
+        })
+        .chain(TcpConnector::default())
+        .chain(RustlsConnector::default());
+    ureq::Agent::with_parts(
+        config,
+        connector,
+        ureq::unversioned::resolver::DefaultResolver::default(),
+    )
+}
+
+/// Connector that opens TCP sockets bound to a source IP and/or marked with DSCP.
+#[derive(Debug)]
+struct BoundTcpConnector {
+    source_ip: Option<IpAddr>,
+    dscp: Option<u8>,
+}
+
+impl Connector<()> for BoundTcpConnector {
+    type Out = BoundTcpTransport;
+
+    fn connect(
+        &self,
+        details: &ConnectionDetails,
+        _chained: Option<()>,
+    ) -> Result<Option<Self::Out>, ureq::Error> {
+        let config = details.config;
+        let mut last_err = None;
+        for addr in &details.addrs {
+            // A source IP only applies to targets of the same address family.
+            if self
+                .source_ip
+                .is_some_and(|ip| ip.is_ipv4() != addr.is_ipv4())
+            {
+                continue;
+            }
+            match self.connect_single(*addr, details.timeout) {
+                Ok(stream) => {
+                    if config.no_delay() {
+                        stream.set_nodelay(true)?;
+                    }
+                    let buffers =
+                        LazyBuffers::new(config.input_buffer_size(), config.output_buffer_size());
+                    return Ok(Some(BoundTcpTransport::new(stream, buffers)));
+                }
+                Err(err) if err.kind() == io::ErrorKind::TimedOut => {
+                    return Err(ureq::Error::Timeout(details.timeout.reason));
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err
+            .unwrap_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::AddrNotAvailable,
+                    "no hub address matches the configured source IP family",
+                )
+            })
+            .into())
+    }
+}
+
+impl BoundTcpConnector {
+    fn connect_single(&self, addr: SocketAddr, timeout: NextTimeout) -> io::Result<TcpStream> {
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+        if let Some(dscp) = self.dscp {
+            set_dscp(&SockRef::from(&socket), addr.is_ipv4(), dscp)?;
+        }
+        if let Some(ip) = self.source_ip {
+            socket.bind(&SocketAddr::new(ip, 0).into())?;
+        }
+        match timeout.not_zero() {
+            Some(after) => socket.connect_timeout(&addr.into(), *after)?,
+            None => socket.connect(&addr.into())?,
+        }
+        Ok(socket.into())
+    }
+}
+
+/// Apply a DSCP code point to a socket (IPv4 TOS / IPv6 traffic class).
+fn set_dscp(socket: &SockRef<'_>, ipv4: bool, dscp: u8) -> io::Result<()> {
+    let tos = u32::from(dscp) << 2;
+    if ipv4 {
+        return socket.set_tos_v4(tos);
+    }
+    #[cfg(unix)]
+    {
+        socket.set_tclass_v6(tos)
+    }
+    #[cfg(not(unix))]
+    {
+        Ok(())
+    }
+}
+
+/// Blocking TCP transport for sockets opened by [`BoundTcpConnector`].
+struct BoundTcpTransport {
+    stream: TcpStream,
+    buffers: LazyBuffers,
+    timeout_read: Option<Duration>,
+    timeout_write: Option<Duration>,
+}
+
+impl BoundTcpTransport {
+    fn new(stream: TcpStream, buffers: LazyBuffers) -> Self {
+        Self {
+            stream,
+            buffers,
+            timeout_read: None,
+            timeout_write: None,
+        }
+    }
+}
+
+impl fmt::Debug for BoundTcpTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BoundTcpTransport")
+            .field("addr", &self.stream.peer_addr().ok())
+            .finish()
+    }
+}
+
+/// Map an I/O error to ureq's timeout error when the socket timed out.
+fn map_io(err: io::Error, timeout: NextTimeout) -> ureq::Error {
+    match err.kind() {
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => ureq::Error::Timeout(timeout.reason),
+        _ => err.into(),
+    }
+}
+
+impl Transport for BoundTcpTransport {
+    fn buffers(&mut self) -> &mut dyn Buffers {
+        &mut self.buffers
+    }
+
+    fn transmit_output(&mut self, amount: usize, timeout: NextTimeout) -> Result<(), ureq::Error> {
+        let after = timeout.not_zero().map(|d| *d);
+        if after != self.timeout_write {
+            self.stream.set_write_timeout(after)?;
+            self.timeout_write = after;
+        }
+        let output = &self.buffers.output()[..amount];
+        self.stream
+            .write_all(output)
+            .map_err(|e| map_io(e, timeout))
+    }
+
+    fn await_input(&mut self, timeout: NextTimeout) -> Result<bool, ureq::Error> {
+        let after = timeout.not_zero().map(|d| *d);
+        if after != self.timeout_read {
+            self.stream.set_read_timeout(after)?;
+            self.timeout_read = after;
+        }
+        let input = self.buffers.input_append_buf();
+        let amount = self.stream.read(input).map_err(|e| map_io(e, timeout))?;
+        self.buffers.input_appended(amount);
+        Ok(amount > 0)
+    }
+
+    fn is_open(&mut self) -> bool {
+        // Same probe as ureq's TCP transport: a pooled socket must have no pending bytes.
+        if self.stream.set_nonblocking(true).is_err() {
+            return false;
+        }
+        let mut buf = [0];
+        let open = matches!(
+            self.stream.read(&mut buf),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock
+        );
+        open && self.stream.set_nonblocking(false).is_ok()
+    }
+}
+
+/// `url` with the value of any `access_token` query parameter replaced, for logging.
+///
+/// Hub stream URLs carry the hub's stream token, and the bridge log ring is readable remotely.
+pub(crate) fn redact_url(url: &str) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_string();
+    };
+    let (query, fragment) = match query.split_once('#') {
+        Some((query, fragment)) => (query, Some(fragment)),
+        None => (query, None),
+    };
+    let query = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some(("access_token", _)) => "access_token=redacted",
+            _ => pair,
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    match fragment {
+        Some(fragment) => format!("{base}?{query}#{fragment}"),
+        None => format!("{base}?{query}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn redact_url_hides_access_token_only() {
+        assert_eq!(
+            redact_url("http://hub:8080/stream/track/7?access_token=s3cret&x=1"),
+            "http://hub:8080/stream/track/7?access_token=redacted&x=1"
+        );
+        assert_eq!(
+            redact_url("http://hub/stream/track/7?a=1&access_token=s3cret"),
+            "http://hub/stream/track/7?a=1&access_token=redacted"
+        );
+        assert_eq!(redact_url("http://radio/live"), "http://radio/live");
+    }
+
+    #[test]
+    fn hub_agent_connects_with_source_ip_and_dscp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, peer) = listener.accept().unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+                .unwrap();
+            peer.ip()
+        });
+        let options = HubConnectOptions {
+            tls_insecure: false,
+            source_ip: Some("127.0.0.1".parse().unwrap()),
+            dscp: Some(46),
+        };
+        let body = hub_agent(&options, Some(Duration::from_secs(5)))
+            .get(&format!("http://{addr}/"))
+            .call()
+            .unwrap()
+            .body_mut()
+            .read_to_string()
+            .unwrap();
+        assert_eq!(body, "ok");
+        assert_eq!(server.join().unwrap(), options.source_ip.unwrap());
+    }
+}
@@ -4,22 +4,37 @@
 
 use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use cpal::traits::DeviceTrait;
 use crossbeam_channel::{Receiver, Sender};
+use symphonia::core::io::MediaSource;
 use symphonia::core::probe::Hint;
 
 use crate::dummy_output;
+use crate::fallback::FallbackChain;
 use crate::http_stream::{HttpRangeConfig, HttpRangeSource};
+use crate::icy::{LiveStreamSource, StreamTitle};
+use crate::net::HubConnectOptions;
 use crate::status::BridgeStatusState;
-use audio_bridge_types::PlaybackEndReason;
-use audio_player::config::PlaybackConfig;
+use audio_bridge_types::{Chapter, PlaybackEndReason};
+use audio_player::config::{DsdOutput, PlaybackConfig};
+use audio_player::cue::TrackRange;
 use audio_player::decode;
-use audio_player::device;
+use audio_player::device::{self, OutputFormatLimits};
+use audio_player::dsd;
+use audio_player::meter::LevelMeter;
+use audio_player::null_output::{self, NullPace};
 use audio_player::pipeline;
-use audio_player::queue::{self, PopStrategy};
-use audio_player::resample;
+use audio_player::playback::bit_exact_format;
+use audio_player::queue;
+use audio_player::resample::ResampleQuality;
+
+/// How often to look for a disconnected output device to come back.
+const DEVICE_RECONNECT_POLL: Duration = Duration::from_secs(1);
+/// How long playback waits for a disconnected output device before giving up.
+const DEVICE_RECONNECT_TIMEOUT: Duration = Duration::from_secs(300);
 
 /// Commands accepted by the playback worker thread.
 #[derive(Debug, Clone)]
@@ -29,6 +44,12 @@ pub(crate) enum PlayerCommand {
         ext_hint: Option<String>,
         title: Option<String>,
         seek_ms: Option<u64>,
+        /// Normalization gain (dB) for this track; `None` plays it unadjusted.
+        gain_db: Option<f32>,
+        /// Part of the source to play; seeks and elapsed time are relative to its start.
+        range: TrackRange,
+        /// Endless live stream (internet radio).
+        live: bool,
     },
     PauseToggle,
     Resume,
@@ -95,10 +116,26 @@ impl BridgeVolumeState {
     }
 }
 
+/// Per-output options the hub sends along with a device selection.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct OutputOptions {
+    /// Request exclusive device access.
+    pub(crate) exclusive: bool,
+    /// Pre-roll silence from the last device selection; `None` keeps `--preroll-silence-ms`.
+    pub(crate) preroll_silence_ms: Option<u32>,
+    /// Resampler preset from the last device selection; `None` keeps `--resample-quality`.
+    pub(crate) resample_quality: Option<ResampleQuality>,
+    /// Rate cap and sample width preference from the last device selection.
+    pub(crate) format_limits: OutputFormatLimits,
+}
+
 struct CurrentTrack {
     url: String,
     ext_hint: Option<String>,
     title: Option<String>,
+    gain_db: Option<f32>,
+    range: TrackRange,
+    live: bool,
 }
 
 struct SessionHandle {
@@ -107,26 +144,29 @@ struct SessionHandle {
     join: std::thread::JoinHandle<()>,
 }
 
+#[allow(clippy::too_many_arguments)]
 /// Spawn the playback worker thread.
 pub(crate) fn spawn_player(
     device_selected: Arc<Mutex<Option<String>>>,
-    exclusive_selected: Arc<Mutex<bool>>,
+    output_options: Arc<Mutex<OutputOptions>>,
     enable_dummy_outputs: bool,
+    fallback: Arc<FallbackChain>,
     status: Arc<Mutex<BridgeStatusState>>,
     volume: Arc<BridgeVolumeState>,
     playback: PlaybackConfig,
-    tls_insecure: bool,
+    hub: HubConnectOptions,
 ) -> PlayerHandle {
     let (cmd_tx, cmd_rx) = crossbeam_channel::unbounded();
     std::thread::spawn(move || {
         player_thread_main(
             device_selected,
-            exclusive_selected,
+            output_options,
             enable_dummy_outputs,
+            fallback,
             status,
             volume,
             playback,
-            tls_insecure,
+            hub,
             cmd_rx,
         )
     });
@@ -136,12 +176,13 @@ pub(crate) fn spawn_player(
 /// Main loop for the playback worker.
 fn player_thread_main(
     device_selected: Arc<Mutex<Option<String>>>,
-    exclusive_selected: Arc<Mutex<bool>>,
+    output_options: Arc<Mutex<OutputOptions>>,
     enable_dummy_outputs: bool,
+    fallback: Arc<FallbackChain>,
     status: Arc<Mutex<BridgeStatusState>>,
     volume: Arc<BridgeVolumeState>,
     playback: PlaybackConfig,
-    tls_insecure: bool,
+    hub: HubConnectOptions,
     cmd_rx: Receiver<PlayerCommand>,
 ) {
     let session_id = Arc::new(AtomicU64::new(0));
@@ -178,23 +219,32 @@ fn player_thread_main(
                 let Some(track) = current.as_ref() else {
                     continue;
                 };
+                if track.live {
+                    tracing::debug!(ms, "ignoring seek on live stream");
+                    continue;
+                }
                 let url = track.url.clone();
                 let ext_hint = track.ext_hint.clone();
                 let title = track.title.clone();
+                let range = track.range;
+                let track_playback = playback_with_gain(&playback, track.gain_db);
                 start_new_session(
                     &device_selected,
-                    &exclusive_selected,
+                    &output_options,
                     enable_dummy_outputs,
+                    &fallback,
                     &status,
                     &volume,
-                    &playback,
-                    tls_insecure,
+                    &track_playback,
+                    hub,
                     &session_id,
                     &mut session,
                     url,
                     ext_hint,
                     title,
                     Some(ms),
+                    range,
+                    false,
                     paused,
                     false,
                 );
@@ -204,11 +254,17 @@ fn player_thread_main(
                 ext_hint,
                 title,
                 seek_ms,
+                gain_db,
+                range,
+                live,
             } => {
                 tracing::info!(
-                    url = %url,
+                    url = %crate::net::redact_url(&url),
                     title = title.as_deref().unwrap_or(""),
                     seek_ms = ?seek_ms,
+                    gain_db = ?gain_db,
+                    range = ?range,
+                    live,
                     "bridge play received"
                 );
                 preupdate_status_on_play(&status, title.as_ref().unwrap_or(&url));
@@ -216,22 +272,29 @@ fn player_thread_main(
                     url: url.clone(),
                     ext_hint: ext_hint.clone(),
                     title: title.clone(),
+                    gain_db,
+                    range,
+                    live,
                 });
                 paused = false;
+                let track_playback = playback_with_gain(&playback, gain_db);
                 start_new_session(
                     &device_selected,
-                    &exclusive_selected,
+                    &output_options,
                     enable_dummy_outputs,
+                    &fallback,
                     &status,
                     &volume,
-                    &playback,
-                    tls_insecure,
+                    &track_playback,
+                    hub,
                     &session_id,
                     &mut session,
                     url,
                     ext_hint,
                     title,
                     seek_ms,
+                    range,
+                    live,
                     paused,
                     true,
                 );
@@ -277,18 +340,21 @@ fn cancel_session_async(session: &mut Option<SessionHandle>) {
 /// Start a new playback session for the current URL.
 fn start_new_session(
     device_selected: &Arc<Mutex<Option<String>>>,
-    exclusive_selected: &Arc<Mutex<bool>>,
+    output_options: &Arc<Mutex<OutputOptions>>,
     enable_dummy_outputs: bool,
+    fallback: &Arc<FallbackChain>,
     status: &Arc<Mutex<BridgeStatusState>>,
     volume: &Arc<BridgeVolumeState>,
     playback: &PlaybackConfig,
-    tls_insecure: bool,
+    hub: HubConnectOptions,
     session_id: &Arc<AtomicU64>,
     session: &mut Option<SessionHandle>,
     url: String,
     ext_hint: Option<String>,
     title: Option<String>,
     seek_ms: Option<u64>,
+    range: TrackRange,
+    live: bool,
     paused: bool,
     wait_for_cancel: bool,
 ) {
@@ -303,7 +369,8 @@ fn start_new_session(
     let my_id = session_id.fetch_add(1, Ordering::Relaxed).saturating_add(1);
 
     let device_selected = device_selected.clone();
-    let exclusive_selected = exclusive_selected.clone();
+    let fallback = fallback.clone();
+    let output_options = output_options.clone();
     let status = status.clone();
     let volume = volume.clone();
     let playback = playback.clone();
@@ -312,20 +379,29 @@ fn start_new_session(
     let paused_for_thread = paused_flag.clone();
 
     let join = std::thread::spawn(move || {
-        let host = cpal::default_host();
+        let host = match device::output_host() {
+            Ok(host) => host,
+            Err(e) => {
+                tracing::warn!("http playback error: {e:#}");
+                return;
+            }
+        };
         if let Err(e) = play_one_http(
             &host,
             &device_selected,
-            &exclusive_selected,
+            &output_options,
             enable_dummy_outputs,
+            &fallback,
             &status,
             &volume,
             &playback,
-            tls_insecure,
+            hub,
             url,
             ext_hint,
             title,
             seek_ms,
+            range,
+            live,
             cancel_for_thread,
             paused_for_thread,
             my_id,
@@ -347,16 +423,19 @@ fn start_new_session(
 fn play_one_http(
     host: &cpal::Host,
     device_selected: &Arc<Mutex<Option<String>>>,
-    exclusive_selected: &Arc<Mutex<bool>>,
+    output_options: &Arc<Mutex<OutputOptions>>,
     enable_dummy_outputs: bool,
+    fallback: &Arc<FallbackChain>,
     status: &Arc<Mutex<BridgeStatusState>>,
     volume: &Arc<BridgeVolumeState>,
     playback: &PlaybackConfig,
-    tls_insecure: bool,
+    hub: HubConnectOptions,
     url: String,
     ext_hint: Option<String>,
     title: Option<String>,
     seek_ms: Option<u64>,
+    range: TrackRange,
+    live: bool,
     cancel: Arc<AtomicBool>,
     paused_flag: Arc<AtomicBool>,
     my_id: u64,
@@ -372,37 +451,71 @@ fn play_one_http(
         hint.with_extension(&ext);
     }
 
-    let playback_eff = effective_playback_for_seek(playback, seek_ms);
+    let options = output_options.lock().map(|g| *g).unwrap_or_default();
+    let mut playback_eff = effective_playback_for_seek(playback, seek_ms);
+    if let Some(ms) = options.preroll_silence_ms {
+        playback_eff.preroll_silence_ms = ms;
+    }
+    if let Some(quality) = options.resample_quality {
+        playback_eff.resample_quality = quality;
+    }
+    if live {
+        // Holding back "trailing" silence would stall an endless stream.
+        playback_eff.silence_trim = None;
+    }
 
     tracing::debug!(
-        url = %url,
-        tls_insecure,
+        url = %crate::net::redact_url(&url),
+        tls_insecure = hub.tls_insecure,
+        source_ip = ?hub.source_ip,
+        dscp = ?hub.dscp,
         "bridge http stream start"
     );
     let stream_error = Arc::new(AtomicBool::new(false));
-    let source = HttpRangeSource::new(
-        url.clone(),
-        HttpRangeConfig {
-            tls_insecure,
-            ..HttpRangeConfig::default()
-        },
-        Some(cancel.clone()),
-        Some(stream_error.clone()),
-    );
+    let http_config = HttpRangeConfig {
+        connect: hub,
+        ..HttpRangeConfig::default()
+    };
+    let stream_title = StreamTitle::default();
+    let source: Box<dyn MediaSource> = if live {
+        Box::new(LiveStreamSource::new(
+            url.clone(),
+            http_config,
+            stream_title.clone(),
+            Some(cancel.clone()),
+            Some(stream_error.clone()),
+        ))
+    } else {
+        Box::new(HttpRangeSource::new(
+            url.clone(),
+            http_config,
+            Some(cancel.clone()),
+            Some(stream_error.clone()),
+        ))
+    };
     let (src_spec, srcq, duration_ms, source_info) =
-        decode::start_streaming_decode_from_media_source_at(
-            Box::new(source),
+        decode::start_streaming_decode_range_from_media_source(
+            source,
             hint,
             playback_eff.buffer_seconds,
             seek_ms,
+            range,
+            playback_eff.decode_errors,
         )
         .context("decode from http")?;
+    let duration_ms = duration_ms.filter(|_| !live);
+    if live && let Ok(mut s) = status.lock() {
+        s.live = Some(true);
+        s.stream_title = Some(stream_title);
+    }
 
     let selected = device_selected.lock().unwrap().clone();
     if enable_dummy_outputs {
         if let Some(dummy) = selected.as_deref().and_then(dummy_output::by_name) {
+            let (src_spec, srcq) =
+                dsd::ensure_pcm(src_spec, srcq, &source_info, playback_eff.buffer_seconds);
             return play_one_http_dummy(
-                exclusive_selected,
+                output_options,
                 status,
                 volume,
                 &playback_eff,
@@ -424,17 +537,70 @@ fn play_one_http(
         }
     }
 
-    let device = device::pick_device(host, selected.as_deref())?;
-    let exclusive_mode = exclusive_selected.lock().map(|g| *g).unwrap_or(false);
-    let config = device::pick_output_config(&device, Some(src_spec.rate))?;
+    let device = fallback.pick(host, selected.as_deref())?;
+    // Exclusive/hog modes target the platform host; JACK owns the device itself.
+    let exclusive_mode =
+        options.exclusive && device::output_backend() == device::OutputBackend::System;
+    // DSD plays as DoP when the device takes integer PCM at the DoP rate, else as PCM.
+    let dop_config = (source_info.dsd_rate.is_some() && playback_eff.dsd_output == DsdOutput::Auto)
+        .then(|| {
+            device::pick_dop_output_config(&device, src_spec.rate, src_spec.channels.count() as u16)
+        })
+        .flatten();
+    playback_eff.dop = dop_config.is_some();
+    let (src_spec, srcq) = if playback_eff.dop {
+        (src_spec, srcq)
+    } else {
+        dsd::ensure_pcm(src_spec, srcq, &source_info, playback_eff.buffer_seconds)
+    };
+    let config = match dop_config {
+        Some(config) => config,
+        None => device::pick_limited_output_config(
+            &device,
+            src_spec.rate,
+            playback_eff.rate_switch,
+            options.format_limits,
+        )?,
+    };
     let target_output_rate = config.sample_rate();
     let nominal_before = crate::exclusive::current_nominal_rate(&device);
-    let _exclusive = crate::exclusive::maybe_acquire(&device, target_output_rate, exclusive_mode);
+    let hog_guard = crate::exclusive::maybe_acquire(&device, target_output_rate, exclusive_mode);
     let nominal_rate = crate::exclusive::current_nominal_rate(&device);
     let mut stream_config: cpal::StreamConfig = config.clone().into();
     if let Some(buf) = device::pick_buffer_size(&config) {
         stream_config.buffer_size = buf;
     }
+    let mut exclusive_output = crate::exclusive::open_exclusive_output(
+        &device,
+        options
+            .format_limits
+            .max_rate
+            .map_or(src_spec.rate, |max| src_spec.rate.min(max)),
+        src_spec.channels.count() as u16,
+        exclusive_mode,
+    );
+    if playback_eff.dop
+        && exclusive_output.as_ref().is_some_and(|out| {
+            out.sample_rate != src_spec.rate
+                || usize::from(out.channels) != src_spec.channels.count()
+                || !matches!(out.sample_format, "I24" | "I32")
+        })
+    {
+        tracing::info!("exclusive output cannot carry DoP; using the shared stream");
+        exclusive_output = None;
+    }
+    let mut output_sample_format = Some(format!("{:?}", config.sample_format()));
+    if let Some(out) = &exclusive_output {
+        stream_config.sample_rate = out.sample_rate;
+        stream_config.channels = out.channels;
+        stream_config.buffer_size = cpal::BufferSize::Default;
+        output_sample_format = Some(out.sample_format.to_string());
+    }
+    let output_mode = if exclusive_output.is_some() || hog_guard.is_some() {
+        crate::exclusive::OUTPUT_MODE_EXCLUSIVE
+    } else {
+        crate::exclusive::OUTPUT_MODE_SHARED
+    };
 
     let played_frames = Arc::new(AtomicU64::new(0));
     if let Some(ms) = seek_ms {
@@ -446,19 +612,31 @@ fn play_one_http(
     let underrun_events = Arc::new(AtomicU64::new(0));
     let buffered_frames = Arc::new(AtomicU64::new(0));
     let buffer_capacity_frames = Arc::new(AtomicU64::new(0));
-    let output_sample_format = Some(format!("{:?}", config.sample_format()));
+    let output_disconnected = Arc::new(AtomicBool::new(false));
+    let levels = Arc::new(LevelMeter::new(
+        stream_config.channels as usize,
+        stream_config.sample_rate,
+    ));
     let container = ext_hint
         .clone()
         .or_else(|| infer_ext_from_url(&url))
         .map(|s| s.to_ascii_uppercase());
     let resampling = src_spec.rate != stream_config.sample_rate;
+    let bit_perfect = Arc::new(AtomicBool::new(false));
+    let bit_exact = !resampling
+        && output_sample_format.as_deref().is_some_and(|format| {
+            bit_exact_format(pcm_source_bits(&source_info, playback_eff.dop), format)
+        });
     tracing::info!(
         device = %device.description().map(|d| d.to_string()).unwrap_or_else(|_| "<unknown>".to_string()),
         exclusive_mode,
+        output_mode,
         source_rate_hz = src_spec.rate,
         stream_rate_hz = stream_config.sample_rate,
         nominal_before_hz = ?nominal_before,
         nominal_after_hz = ?nominal_rate,
+        bit_exact,
+        dop = playback_eff.dop,
         "bridge playback stream configured"
     );
     {
@@ -474,9 +652,15 @@ fn play_one_http(
             s.source_bit_depth = source_info.bit_depth;
             s.container = container.or_else(|| source_info.container.clone());
             s.output_sample_format = output_sample_format.clone();
+            s.output_mode = Some(output_mode.to_string());
             s.resampling = Some(resampling);
             s.resample_from_hz = Some(src_spec.rate);
             s.resample_to_hz = Some(stream_config.sample_rate);
+            s.resample_quality = resampling.then(|| {
+                playback_eff
+                    .resample_backend
+                    .label(playback_eff.resample_quality)
+            });
             s.played_frames = Some(played_frames.clone());
             s.paused_flag = Some(paused_flag.clone());
             s.underrun_frames = Some(underrun_frames.clone());
@@ -487,10 +671,16 @@ fn play_one_http(
             };
             s.buffered_frames = Some(buffered_frames.clone());
             s.buffer_capacity_frames = Some(buffer_capacity_frames.clone());
+            s.output_disconnected = Some(output_disconnected.clone());
+            s.levels = Some(levels.clone());
+            s.bit_perfect = Some(bit_perfect.clone());
+            s.dop = source_info.dsd_rate.map(|_| playback_eff.dop);
+            s.decode_errors = Some(source_info.decode_errors.clone());
+            s.chapters = chapter_list(&source_info);
         }
     }
     tracing::info!(
-        url = %url,
+        url = %crate::net::redact_url(&url),
         seek_ms = ?seek_ms,
         paused = paused_flag.load(Ordering::Relaxed),
         "bridge status updated from decoder"
@@ -498,6 +688,7 @@ fn play_one_http(
 
     let cancel_for_status = cancel.clone();
     let stream_error_for_status = stream_error.clone();
+    let decode_errors = source_info.decode_errors.clone();
     let result = pipeline::play_decoded_source(
         &device,
         &config,
@@ -515,6 +706,19 @@ fn play_one_http(
             buffer_capacity_frames: Some(buffer_capacity_frames),
             volume_percent: Some(volume.volume_percent_handle()),
             muted: Some(volume.muted_handle()),
+            levels: Some(levels),
+            bit_perfect: bit_exact.then_some(bit_perfect),
+            hotplug: Some(pipeline::HotplugOptions {
+                follow_default: device::is_default_follow(selected.as_deref()),
+                reopen: {
+                    let fallback = fallback.clone();
+                    Box::new(move || fallback.reopen(selected.as_deref()))
+                },
+                poll_interval: DEVICE_RECONNECT_POLL,
+                timeout: Some(DEVICE_RECONNECT_TIMEOUT),
+                disconnected: Some(output_disconnected),
+            }),
+            output: exclusive_output.map(|out| out.open),
         },
     );
 
@@ -523,7 +727,8 @@ fn play_one_http(
             let should_set = s.end_reason.is_none();
             if should_set {
                 let cancelled = cancel_for_status.load(Ordering::Relaxed);
-                let had_error = stream_error_for_status.load(Ordering::Relaxed);
+                let had_error = stream_error_for_status.load(Ordering::Relaxed)
+                    || decode_errors.aborted.load(Ordering::Relaxed);
                 s.end_reason = Some(if result.is_ok() && !cancelled && !had_error {
                     PlaybackEndReason::Eof
                 } else {
@@ -540,9 +745,9 @@ fn play_one_http(
 #[allow(clippy::too_many_arguments)]
 /// Execute playback on a synthetic dummy output device.
 fn play_one_http_dummy(
-    exclusive_selected: &Arc<Mutex<bool>>,
+    output_options: &Arc<Mutex<OutputOptions>>,
     status: &Arc<Mutex<BridgeStatusState>>,
-    _volume: &Arc<BridgeVolumeState>,
+    volume: &Arc<BridgeVolumeState>,
     playback: &PlaybackConfig,
     url: String,
     ext_hint: Option<String>,
@@ -559,7 +764,7 @@ fn play_one_http_dummy(
     source_info: audio_player::decode::SourceInfo,
     dummy: dummy_output::DummyOutputDevice,
 ) -> Result<()> {
-    let exclusive_mode = exclusive_selected.lock().map(|g| *g).unwrap_or(false);
+    let exclusive_mode = output_options.lock().map(|g| g.exclusive).unwrap_or(false);
     let nominal_before = Some(dummy.normal_rate_hz);
     let stream_rate = dummy.stream_rate_hz(exclusive_mode);
     let nominal_rate = Some(stream_rate);
@@ -569,12 +774,18 @@ fn play_one_http_dummy(
     let underrun_events = Arc::new(AtomicU64::new(0));
     let buffered_frames = Arc::new(AtomicU64::new(0));
     let buffer_capacity_frames = Arc::new(AtomicU64::new(0));
+    let levels = Arc::new(LevelMeter::new(src_spec.channels.count(), stream_rate));
     let output_sample_format = Some("F32".to_string());
     let container = ext_hint
         .clone()
         .or_else(|| infer_ext_from_url(&url))
         .map(|s| s.to_ascii_uppercase());
     let resampling = src_spec.rate != stream_rate;
+    let bit_perfect = Arc::new(AtomicBool::new(false));
+    let bit_exact = !resampling
+        && output_sample_format
+            .as_deref()
+            .is_some_and(|format| bit_exact_format(pcm_source_bits(&source_info, false), format));
 
     tracing::info!(
         device = dummy.name,
@@ -598,9 +809,19 @@ fn play_one_http_dummy(
         s.source_bit_depth = source_info.bit_depth;
         s.container = container.or_else(|| source_info.container.clone());
         s.output_sample_format = output_sample_format.clone();
+        s.output_mode = Some(
+            if exclusive_mode {
+                crate::exclusive::OUTPUT_MODE_EXCLUSIVE
+            } else {
+                crate::exclusive::OUTPUT_MODE_SHARED
+            }
+            .to_string(),
+        );
         s.resampling = Some(resampling);
         s.resample_from_hz = Some(src_spec.rate);
         s.resample_to_hz = Some(stream_rate);
+        s.resample_quality =
+            resampling.then(|| playback.resample_backend.label(playback.resample_quality));
         s.played_frames = Some(played_frames.clone());
         s.paused_flag = Some(paused_flag.clone());
         s.underrun_frames = Some(underrun_frames.clone());
@@ -608,10 +829,16 @@ fn play_one_http_dummy(
         s.buffer_size_frames = None;
         s.buffered_frames = Some(buffered_frames.clone());
         s.buffer_capacity_frames = Some(buffer_capacity_frames.clone());
+        s.levels = Some(levels.clone());
+        s.bit_perfect = Some(bit_perfect.clone());
+        s.dop = source_info.dsd_rate.map(|_| false);
+        s.decode_errors = Some(source_info.decode_errors.clone());
+        s.chapters = chapter_list(&source_info);
     }
 
     let cancel_for_status = cancel.clone();
     let stream_error_for_status = stream_error.clone();
+    let decode_errors = source_info.decode_errors.clone();
     let result = play_decoded_on_dummy_output(
         playback,
         src_spec,
@@ -625,8 +852,12 @@ fn play_one_http_dummy(
             underrun_events: Some(underrun_events),
             buffered_frames: Some(buffered_frames),
             buffer_capacity_frames: Some(buffer_capacity_frames),
-            volume_percent: None,
-            muted: None,
+            volume_percent: Some(volume.volume_percent_handle()),
+            muted: Some(volume.muted_handle()),
+            levels: Some(levels),
+            bit_perfect: bit_exact.then_some(bit_perfect),
+            hotplug: None,
+            output: None,
         },
     );
 
@@ -635,7 +866,8 @@ fn play_one_http_dummy(
             let should_set = s.end_reason.is_none();
             if should_set {
                 let cancelled = cancel_for_status.load(Ordering::Relaxed);
-                let had_error = stream_error_for_status.load(Ordering::Relaxed);
+                let had_error = stream_error_for_status.load(Ordering::Relaxed)
+                    || decode_errors.aborted.load(Ordering::Relaxed);
                 s.end_reason = Some(if result.is_ok() && !cancelled && !had_error {
                     PlaybackEndReason::Eof
                 } else {
@@ -649,7 +881,7 @@ fn play_one_http_dummy(
     result
 }
 
-/// Simulate output playback for synthetic devices while preserving queue semantics.
+/// Play synthetic devices through the regular pipeline on the null output device.
 fn play_decoded_on_dummy_output(
     playback: &PlaybackConfig,
     src_spec: symphonia::core::audio::SignalSpec,
@@ -657,70 +889,26 @@ fn play_decoded_on_dummy_output(
     dst_rate: u32,
     opts: pipeline::PlaybackSessionOptions,
 ) -> Result<()> {
-    let paused = opts.paused;
-    let cancel = opts.cancel;
-    let played_frames = opts.played_frames;
-    let buffered_frames = opts.buffered_frames;
-    let buffer_capacity_frames = opts.buffer_capacity_frames;
-
-    let dstq = if src_spec.rate == dst_rate {
-        srcq.clone()
-    } else {
-        resample::start_resampler(
-            srcq,
-            src_spec,
-            dst_rate,
-            resample::ResampleConfig {
-                chunk_frames: playback.chunk_frames,
-                buffer_seconds: playback.buffer_seconds,
-            },
-        )?
-    };
-
-    if let Some(cap) = &buffer_capacity_frames {
-        cap.store(dstq.max_frames() as u64, Ordering::Relaxed);
-    }
-
-    loop {
-        if let Some(flag) = &cancel {
-            if flag.load(Ordering::Relaxed) {
-                dstq.close();
-                break;
-            }
-        }
-
-        if let Some(p) = &paused {
-            if p.load(Ordering::Relaxed) {
-                if let Some(counter) = &buffered_frames {
-                    counter.store(dstq.len_frames() as u64, Ordering::Relaxed);
-                }
-                std::thread::sleep(std::time::Duration::from_millis(20));
-                continue;
-            }
-        }
-
-        let Some(samples) = dstq.pop(PopStrategy::BlockingUpTo {
-            max_frames: playback.refill_max_frames.max(1),
-        }) else {
-            break;
-        };
-        let channels = dstq.channels().max(1);
-        let frames = samples.len() / channels;
-
-        if let Some(counter) = &played_frames {
-            counter.fetch_add(frames as u64, Ordering::Relaxed);
-        }
-        if let Some(counter) = &buffered_frames {
-            counter.store(dstq.len_frames() as u64, Ordering::Relaxed);
-        }
+    let device = null_output::device(NullPace::Realtime);
+    let config = device::pick_output_config(&device, Some(dst_rate))?;
+    let stream_config: cpal::StreamConfig = config.clone().into();
+    pipeline::play_decoded_source(
+        &device,
+        &config,
+        &stream_config,
+        playback,
+        src_spec,
+        srcq,
+        opts,
+    )
+}
 
-        let sleep_ms = ((frames as f64 * 1000.0) / (dst_rate as f64))
-            .max(1.0)
-            .round() as u64;
-        std::thread::sleep(std::time::Duration::from_millis(sleep_ms));
+/// Apply a per-track normalization gain on top of the bridge's playback config.
+fn playback_with_gain(playback: &PlaybackConfig, gain_db: Option<f32>) -> PlaybackConfig {
+    PlaybackConfig {
+        pre_gain_db: gain_db.unwrap_or(0.0),
+        ..playback.clone()
     }
-
-    Ok(())
 }
 
 /// Derive effective playback buffering settings for the current command.
@@ -729,6 +917,10 @@ fn play_decoded_on_dummy_output(
 fn effective_playback_for_seek(playback: &PlaybackConfig, seek_ms: Option<u64>) -> PlaybackConfig {
     let mut playback_eff = playback.clone();
     if seek_ms.is_some() {
+        // Silence at a seek target is part of the track, not lead-in.
+        if let Some(trim) = playback_eff.silence_trim.as_mut() {
+            trim.leading = false;
+        }
         playback_eff.buffer_seconds = playback_eff.buffer_seconds.min(1.0);
         playback_eff.refill_max_frames = playback_eff.refill_max_frames.min(2048);
         playback_eff.chunk_frames = playback_eff.chunk_frames.min(1024);
@@ -749,6 +941,21 @@ fn played_frames_from_seek(
     Some(target_ms.saturating_mul(sample_rate_hz as u64) / 1000)
 }
 
+/// Source bit depth for bit-exactness checks: DSD converted to PCM has none.
+/// Chapters of a decoded file in wire form, if it has any.
+fn chapter_list(source_info: &decode::SourceInfo) -> Option<Vec<Chapter>> {
+    (!source_info.chapters.is_empty())
+        .then(|| source_info.chapters.iter().map(Into::into).collect())
+}
+
+fn pcm_source_bits(source_info: &decode::SourceInfo, dop: bool) -> Option<u16> {
+    if source_info.dsd_rate.is_some() && !dop {
+        None
+    } else {
+        source_info.bit_depth
+    }
+}
+
 /// Pick the status sample rate used for elapsed-time reporting.
 fn status_sample_rate(stream_sample_rate: u32, _nominal_rate: Option<u32>) -> u32 {
     // Elapsed time is derived from played_frames / sample_rate. We must use the
@@ -780,11 +987,27 @@ mod tests {
             buffer_seconds: 3.0,
             refill_max_frames: 8192,
             chunk_frames: 4096,
+            record: None,
+            mirror: None,
+            rate_switch: Default::default(),
+            resample_quality: Default::default(),
+            resample_backend: Default::default(),
+            pre_gain_db: 0.0,
+            channel_mix: Default::default(),
+            underrun_concealment: Default::default(),
+            preroll_silence_ms: 0,
+            min_prebuffer_ms: 0,
+            silence_trim: Some(audio_player::config::SilenceTrim::default()),
+            decode_errors: Default::default(),
+            dsd_output: Default::default(),
+            dop: false,
         };
         let eff = effective_playback_for_seek(&playback, Some(1000));
         assert_eq!(eff.buffer_seconds, 1.0);
         assert_eq!(eff.refill_max_frames, 2048);
         assert_eq!(eff.chunk_frames, 1024);
+        let trim = eff.silence_trim.unwrap();
+        assert!(!trim.leading && trim.trailing);
     }
 
     #[test]
@@ -793,6 +1016,20 @@ mod tests {
             buffer_seconds: 2.5,
             refill_max_frames: 4096,
             chunk_frames: 2048,
+            record: None,
+            mirror: None,
+            rate_switch: Default::default(),
+            resample_quality: Default::default(),
+            resample_backend: Default::default(),
+            pre_gain_db: 0.0,
+            channel_mix: Default::default(),
+            underrun_concealment: Default::default(),
+            preroll_silence_ms: 0,
+            min_prebuffer_ms: 0,
+            silence_trim: None,
+            decode_errors: Default::default(),
+            dsd_output: Default::default(),
+            dop: false,
         };
         let eff = effective_playback_for_seek(&playback, None);
         assert_eq!(eff.buffer_seconds, 2.5);
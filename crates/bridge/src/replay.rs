@@ -0,0 +1,491 @@
+//! Offline replay of hub stream captures (`[stream_capture]` on the hub).
+//!
+//! Each capture is a `.log` (request, response status/headers, per-chunk timing) plus a `.bin`
+//! payload. [`ReplayServer`] serves a set of captures over HTTP so the normal range-reading
+//! playback path can run against exactly what the hub sent:
+//! - a request whose path and `Range` match a capture replays that response: same status and
+//!   headers, chunks paced at their original send times (scaled by `speed`), and a body cut
+//!   short wherever the original was;
+//! - repeated identical requests (client retries) replay the matching captures in order;
+//! - other ranges are assembled from the captured bytes and sent unpaced.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow};
+use serde_json::Value;
+
+/// One captured stream response.
+#[derive(Debug)]
+struct CapturedResponse {
+    started_at_ms: u64,
+    uri: String,
+    range: Option<String>,
+    status: u16,
+    headers: Vec<(String, String)>,
+    chunks: Vec<CapturedChunk>,
+    /// Body bytes as captured (may stop short of the last chunk).
+    payload: Vec<u8>,
+    /// Whether the original body was sent to the end.
+    complete: bool,
+}
+
+#[derive(Debug)]
+struct CapturedChunk {
+    t_ms: u64,
+    offset: usize,
+    len: usize,
+}
+
+impl CapturedResponse {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Absolute offset of the first body byte within the resource.
+    fn body_start(&self) -> u64 {
+        self.header("content-range")
+            .and_then(parse_content_range)
+            .map(|(start, _)| start)
+            .unwrap_or(0)
+    }
+
+    /// Total resource length, when the response says.
+    fn total_len(&self) -> Option<u64> {
+        match self.header("content-range") {
+            Some(range) => parse_content_range(range).and_then(|(_, total)| total),
+            None if self.status == 200 => self
+                .header("content-length")
+                .and_then(|v| v.trim().parse().ok()),
+            None => None,
+        }
+    }
+}
+
+/// Captures loaded from a `.log` file or a directory of them, ordered by start time.
+#[derive(Debug)]
+pub struct CaptureSet {
+    responses: Vec<CapturedResponse>,
+}
+
+impl CaptureSet {
+    /// Load `path`: one capture `.log` (its `.bin` alongside) or a directory of captures.
+    pub fn load(path: &Path) -> Result<Self> {
+        let logs: Vec<PathBuf> = if path.is_dir() {
+            std::fs::read_dir(path)
+                .with_context(|| format!("read capture dir {}", path.display()))?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "log"))
+                .collect()
+        } else {
+            vec![path.to_path_buf()]
+        };
+        let mut responses = logs
+            .iter()
+            .map(|log| parse_capture(log).with_context(|| format!("load {}", log.display())))
+            .collect::<Result<Vec<_>>>()?;
+        if responses.is_empty() {
+            return Err(anyhow!("no captures found in {}", path.display()));
+        }
+        responses.sort_by_key(|r| r.started_at_ms);
+        Ok(Self { responses })
+    }
+
+    /// Path of the earliest captured request (the track to play).
+    pub fn first_uri(&self) -> &str {
+        &self.responses[0].uri
+    }
+
+    /// Number of captured responses.
+    pub fn len(&self) -> usize {
+        self.responses.len()
+    }
+
+    /// Whether no responses were loaded.
+    pub fn is_empty(&self) -> bool {
+        self.responses.is_empty()
+    }
+
+    /// Bytes `start..=end` of `uri`, stitched from every capture of it.
+    fn bytes(&self, uri: &str, start: u64, end: u64) -> Option<Vec<u8>> {
+        let mut out = Vec::with_capacity(end.saturating_sub(start) as usize + 1);
+        let mut pos = start;
+        while pos <= end {
+            let (seg_start, payload) = self
+                .responses
+                .iter()
+                .filter(|r| r.uri == uri)
+                .map(|r| (r.body_start(), &r.payload))
+                .find(|(seg_start, payload)| {
+                    *seg_start <= pos && pos < seg_start + payload.len() as u64
+                })?;
+            let from = (pos - seg_start) as usize;
+            let to = ((end - seg_start + 1) as usize).min(payload.len());
+            out.extend_from_slice(&payload[from..to]);
+            pos = seg_start + to as u64;
+        }
+        Some(out)
+    }
+
+    fn total_len(&self, uri: &str) -> Option<u64> {
+        self.responses
+            .iter()
+            .filter(|r| r.uri == uri)
+            .find_map(CapturedResponse::total_len)
+    }
+}
+
+/// Parse one capture `.log` and its `.bin` payload.
+fn parse_capture(log: &Path) -> Result<CapturedResponse> {
+    let text = std::fs::read_to_string(log)?;
+    let payload = std::fs::read(log.with_extension("bin")).unwrap_or_default();
+    let mut response: Option<CapturedResponse> = None;
+    for (idx, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: Value =
+            serde_json::from_str(line).with_context(|| format!("line {}", idx + 1))?;
+        match entry["event"].as_str() {
+            Some("request") => {
+                let headers = |key: &str| -> Vec<(String, String)> {
+                    entry[key]
+                        .as_object()
+                        .map(|map| {
+                            map.iter()
+                                .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                                .collect()
+                        })
+                        .unwrap_or_default()
+                };
+                let range = headers("headers")
+                    .into_iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case("range"))
+                    .map(|(_, v)| v);
+                response = Some(CapturedResponse {
+                    started_at_ms: entry["started_at_ms"].as_u64().unwrap_or(0),
+                    uri: entry["uri"]
+                        .as_str()
+                        .ok_or_else(|| anyhow!("request entry without uri"))?
+                        .to_string(),
+                    range,
+                    status: entry["status"].as_u64().unwrap_or(200) as u16,
+                    headers: headers("response_headers"),
+                    chunks: Vec::new(),
+                    payload: Vec::new(),
+                    complete: false,
+                });
+            }
+            Some("chunk") => {
+                let response = response
+                    .as_mut()
+                    .ok_or_else(|| anyhow!("chunk before request entry"))?;
+                response.chunks.push(CapturedChunk {
+                    t_ms: entry["t_ms"].as_u64().unwrap_or(0),
+                    offset: entry["offset"].as_u64().unwrap_or(0) as usize,
+                    len: entry["len"].as_u64().unwrap_or(0) as usize,
+                });
+            }
+            Some("end") => {
+                if let Some(response) = response.as_mut() {
+                    response.complete = entry["complete"].as_bool().unwrap_or(false);
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut response = response.ok_or_else(|| anyhow!("no request entry"))?;
+    response.payload = payload;
+    Ok(response)
+}
+
+/// `bytes a-b/N` (or `bytes a-b/*`) -> `(a, Some(N))`.
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let rest = value.trim().strip_prefix("bytes ")?;
+    let (span, total) = rest.split_once('/')?;
+    let start = span.split_once('-')?.0.trim().parse().ok()?;
+    Some((start, total.trim().parse().ok()))
+}
+
+/// `bytes=a-b` / `bytes=a-` -> `(a, b)`, clamped to `total`.
+fn parse_range(value: &str, total: u64) -> Option<(u64, u64)> {
+    let (start, end) = value.trim().strip_prefix("bytes=")?.split_once('-')?;
+    let start: u64 = start.trim().parse().ok()?;
+    let last = total.checked_sub(1)?;
+    let end = match end.trim() {
+        "" => last,
+        end => end.parse::<u64>().ok()?.min(last),
+    };
+    (start <= end).then_some((start, end))
+}
+
+/// Local HTTP listener serving a [`CaptureSet`].
+pub struct ReplayServer {
+    addr: SocketAddr,
+}
+
+struct ReplayState {
+    captures: CaptureSet,
+    speed: f64,
+    /// Next capture index per `(uri, range)` so retries replay in captured order.
+    cursors: Mutex<HashMap<(String, Option<String>), usize>>,
+}
+
+impl ReplayServer {
+    /// Serve `captures` on `bind`; `speed` scales the original pacing (`0` = unpaced).
+    pub fn spawn(captures: CaptureSet, bind: SocketAddr, speed: f64) -> Result<Self> {
+        if !speed.is_finite() || speed < 0.0 {
+            return Err(anyhow!(
+                "replay speed must be a finite value >= 0 (got {speed})"
+            ));
+        }
+        let listener =
+            TcpListener::bind(bind).with_context(|| format!("bind replay listener {bind}"))?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(ReplayState {
+            captures,
+            speed,
+            cursors: Mutex::new(HashMap::new()),
+        });
+        std::thread::spawn(move || {
+            for conn in listener.incoming().flatten() {
+                let state = state.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = state.handle(conn) {
+                        tracing::debug!(error = %e, "replay connection ended");
+                    }
+                });
+            }
+        });
+        Ok(Self { addr })
+    }
+
+    /// Listener address.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// URL of `uri` on this listener.
+    pub fn url(&self, uri: &str) -> String {
+        format!("http://{}{uri}", self.addr)
+    }
+}
+
+impl ReplayState {
+    fn handle(&self, mut conn: TcpStream) -> Result<()> {
+        let mut reader = BufReader::new(conn.try_clone()?);
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let uri = line
+            .split_whitespace()
+            .nth(1)
+            .ok_or_else(|| anyhow!("malformed request line"))?
+            .to_string();
+        let mut range = None;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':')
+                && name.trim().eq_ignore_ascii_case("range")
+            {
+                range = Some(value.trim().to_string());
+            }
+        }
+
+        if let Some(response) = self.next_exact(&uri, range.as_deref()) {
+            tracing::info!(uri, range = ?range, status = response.status, "replaying captured response");
+            return self.replay(&mut conn, response);
+        }
+        self.assemble(&mut conn, &uri, range.as_deref())
+    }
+
+    /// Next capture of exactly this request, advancing the per-request cursor.
+    fn next_exact(&self, uri: &str, range: Option<&str>) -> Option<&CapturedResponse> {
+        let matches: Vec<&CapturedResponse> = self
+            .captures
+            .responses
+            .iter()
+            .filter(|r| r.uri == uri && r.range.as_deref() == range)
+            .collect();
+        if matches.is_empty() {
+            return None;
+        }
+        let mut cursors = self.cursors.lock().unwrap();
+        let cursor = cursors
+            .entry((uri.to_string(), range.map(str::to_string)))
+            .or_insert(0);
+        let response = matches[(*cursor).min(matches.len() - 1)];
+        *cursor += 1;
+        Some(response)
+    }
+
+    /// Send a captured response with its original status, headers, pacing and truncation.
+    fn replay(&self, conn: &mut TcpStream, response: &CapturedResponse) -> Result<()> {
+        write!(conn, "HTTP/1.1 {} Replay\r\n", response.status)?;
+        for (name, value) in &response.headers {
+            write!(conn, "{name}: {value}\r\n")?;
+        }
+        write!(conn, "Connection: close\r\n\r\n")?;
+        let started = Instant::now();
+        let body_start = response.body_start();
+        for chunk in &response.chunks {
+            if self.speed > 0.0 {
+                let due = Duration::from_secs_f64(chunk.t_ms as f64 / 1000.0 / self.speed);
+                if let Some(wait) = due.checked_sub(started.elapsed()) {
+                    std::thread::sleep(wait);
+                }
+            }
+            let end = chunk.offset + chunk.len;
+            let bytes = match response.payload.get(chunk.offset..end) {
+                Some(bytes) => bytes.to_vec(),
+                // Beyond this capture's size cap: borrow the bytes from another capture.
+                None => match self.captures.bytes(
+                    &response.uri,
+                    body_start + chunk.offset as u64,
+                    body_start + end as u64 - 1,
+                ) {
+                    Some(bytes) => bytes,
+                    None => {
+                        tracing::warn!(
+                            offset = chunk.offset,
+                            "captured payload ends here; closing replayed response"
+                        );
+                        return Ok(());
+                    }
+                },
+            };
+            conn.write_all(&bytes)?;
+        }
+        if !response.complete {
+            tracing::info!(uri = response.uri, "original response was cut short");
+        }
+        Ok(())
+    }
+
+    /// Answer a request with no exact capture from the captured bytes, unpaced.
+    fn assemble(&self, conn: &mut TcpStream, uri: &str, range: Option<&str>) -> Result<()> {
+        let Some(total) = self.captures.total_len(uri) else {
+            tracing::warn!(uri, "no capture for request");
+            write!(
+                conn,
+                "HTTP/1.1 404 Not Captured\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            )?;
+            return Ok(());
+        };
+        let span = match range {
+            Some(range) => parse_range(range, total),
+            None => total.checked_sub(1).map(|last| (0, last)),
+        };
+        let body = span.and_then(|(start, end)| {
+            self.captures
+                .bytes(uri, start, end)
+                .map(|body| (start, end, body))
+        });
+        let Some((start, end, body)) = body else {
+            tracing::warn!(uri, range = ?range, "requested range was not captured");
+            write!(
+                conn,
+                "HTTP/1.1 416 Range Not Captured\r\nContent-Range: bytes */{total}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            )?;
+            return Ok(());
+        };
+        write!(
+            conn,
+            "HTTP/1.1 206 Partial Content\r\nAccept-Ranges: bytes\r\nContent-Range: bytes {start}-{end}/{total}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        )?;
+        conn.write_all(&body)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "bridge-replay-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Write a capture of `range` covering `body` at `start` of a 10-byte resource.
+    fn write_capture(dir: &Path, name: &str, range: &str, start: u64, body: &[u8], complete: bool) {
+        let end = start + body.len() as u64 - 1;
+        let lines = [
+            serde_json::json!({
+                "event": "request",
+                "started_at_ms": start,
+                "uri": "/stream/track/7",
+                "headers": { "range": range },
+                "status": 206,
+                "response_headers": {
+                    "content-range": format!("bytes {start}-{end}/10"),
+                    "content-length": body.len().to_string(),
+                },
+            }),
+            serde_json::json!({ "event": "chunk", "t_ms": 0, "offset": 0, "len": body.len() }),
+            serde_json::json!({ "event": "end", "complete": complete }),
+        ];
+        let log: String = lines.iter().map(|l| format!("{l}\n")).collect();
+        std::fs::write(dir.join(format!("{name}.log")), log).unwrap();
+        std::fs::write(dir.join(format!("{name}.bin")), body).unwrap();
+    }
+
+    fn get(server: &ReplayServer, range: &str) -> String {
+        let mut conn = TcpStream::connect(server.addr()).unwrap();
+        write!(
+            conn,
+            "GET /stream/track/7 HTTP/1.1\r\nHost: x\r\nRange: {range}\r\n\r\n"
+        )
+        .unwrap();
+        let mut response = String::new();
+        conn.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn replays_exact_requests_and_assembles_other_ranges() {
+        let dir = temp_dir();
+        write_capture(&dir, "a", "bytes=0-4", 0, b"01234", true);
+        write_capture(&dir, "b", "bytes=5-9", 5, b"56789", true);
+        let captures = CaptureSet::load(&dir).unwrap();
+        assert_eq!(captures.len(), 2);
+        assert_eq!(captures.first_uri(), "/stream/track/7");
+
+        let server = ReplayServer::spawn(captures, "127.0.0.1:0".parse().unwrap(), 0.0).unwrap();
+        let exact = get(&server, "bytes=5-9");
+        assert!(exact.starts_with("HTTP/1.1 206"));
+        assert!(exact.contains("content-range: bytes 5-9/10"));
+        assert!(exact.ends_with("56789"));
+
+        let stitched = get(&server, "bytes=3-6");
+        assert!(stitched.contains("Content-Range: bytes 3-6/10"));
+        assert!(stitched.ends_with("3456"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn parse_range_clamps_open_and_oversized_ranges() {
+        assert_eq!(parse_range("bytes=0-", 10), Some((0, 9)));
+        assert_eq!(parse_range("bytes=2-99", 10), Some((2, 9)));
+        assert_eq!(parse_range("bytes=10-", 10), None);
+        assert_eq!(parse_content_range("bytes 5-9/10"), Some((5, Some(10))));
+    }
+}
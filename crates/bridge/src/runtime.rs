@@ -3,21 +3,30 @@
 //! Provides device enumeration, local playback, and HTTP listener startup.
 
 use anyhow::Result;
-use cpal::traits::DeviceTrait;
+use cpal::traits::{DeviceTrait, HostTrait};
 use serde_json::json;
 use std::collections::HashSet;
 
-use crate::config::{BridgeListenConfig, BridgePlayConfig};
+use crate::config::{BridgeListenConfig, BridgePlayConfig, BridgeReplayConfig};
+use crate::device_aliases::DeviceAliases;
 use crate::dummy_output;
+use crate::fallback::FallbackChain;
+use crate::hidden_devices::HiddenDevices;
+use crate::http_stream::{HttpRangeConfig, HttpRangeSource};
+use crate::net::{HubConnectOptions, hub_agent};
+use crate::replay::{CaptureSet, ReplayServer};
 use crate::{http_api, mdns, player};
-use audio_player::{config::PlaybackConfig, decode, device, pipeline, status::PlayerStatusState};
+use audio_player::{
+    config::PlaybackConfig, decode, device, dsd, pipeline, status::PlayerStatusState,
+};
 
 const MDNS_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+/// Upper bound on waiting for playback to stop during shutdown.
+const DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
 /// List output devices and print them to stdout.
 pub fn list_devices(enable_dummy_outputs: bool) -> Result<()> {
-    let host = cpal::default_host();
-    if let Err(e) = device::list_devices(&host) {
+    if let Err(e) = device::output_host().and_then(|host| device::list_devices(&host)) {
         tracing::warn!("failed to list physical output devices: {e:#}");
     }
     if enable_dummy_outputs {
@@ -28,21 +37,107 @@ pub fn list_devices(enable_dummy_outputs: bool) -> Result<()> {
     Ok(())
 }
 
+/// Check CLI configuration (ranges, paths, device), print all problems, and return validity.
+pub fn check_config(args: &crate::cli::Args) -> bool {
+    let mut problems = args.config_problems();
+    if let Some(name) = normalize_device_name(args.device.clone()) {
+        let name = DeviceAliases::load(args.device_aliases_path()).resolve(&name);
+        let is_dummy = args.enable_dummy_outputs && dummy_output::by_name(&name).is_some();
+        if !is_dummy
+            && let Err(e) =
+                device::output_host().and_then(|host| device::pick_device(&host, Some(&name)))
+        {
+            problems.push(format!("--device: {e}"));
+        }
+    }
+    if let Some(name) = args.mirror_device.as_deref()
+        && let Err(e) =
+            device::output_host().and_then(|host| device::pick_device(&host, Some(name)))
+    {
+        problems.push(format!("--mirror-device: {e}"));
+    }
+    if problems.is_empty() {
+        println!("config ok");
+        return true;
+    }
+    println!("config has {} problem(s):", problems.len());
+    for problem in &problems {
+        println!("  - {problem}");
+    }
+    false
+}
+
 /// Play a local file using the provided playback config.
 pub fn run_play(config: BridgePlayConfig) -> Result<()> {
-    let host = cpal::default_host();
+    let host = device::output_host()?;
     let device_name = normalize_device_name(config.device);
     let device = device::pick_device(&host, device_name.as_deref())?;
     tracing::info!(device = %device.description()?, "output device");
     play_one_local(&device, &config.playback, &config.path)
 }
 
+/// Serve a hub stream capture locally and play it like a hub stream (or only serve it).
+pub fn run_replay(config: BridgeReplayConfig) -> Result<()> {
+    let captures = CaptureSet::load(&config.capture)?;
+    let uri = captures.first_uri().to_string();
+    let responses = captures.len();
+    let server = ReplayServer::spawn(captures, config.bind, config.speed)?;
+    let url = server.url(&uri);
+    tracing::info!(url = %url, responses, speed = config.speed, "replay listener ready");
+    if config.serve_only {
+        println!("{url}");
+        loop {
+            std::thread::park();
+        }
+    }
+
+    let host = device::output_host()?;
+    let device_name = normalize_device_name(config.device);
+    let device = device::pick_device(&host, device_name.as_deref())?;
+    tracing::info!(device = %device.description()?, "output device");
+    let source = HttpRangeSource::new(url, HttpRangeConfig::default(), None, None);
+    let (src_spec, srcq, _duration_ms, source_info) =
+        decode::start_streaming_decode_from_media_source(
+            Box::new(source),
+            symphonia::core::probe::Hint::new(),
+            config.playback.buffer_seconds,
+        )?;
+    let (src_spec, srcq) =
+        dsd::ensure_pcm(src_spec, srcq, &source_info, config.playback.buffer_seconds);
+    play_decoded_local(&device, &config.playback, src_spec, srcq)
+}
+
 /// Run the bridge HTTP API and playback worker.
 pub fn run_listen(config: BridgeListenConfig, install_ctrlc: bool) -> Result<()> {
-    let device_selected = std::sync::Arc::new(std::sync::Mutex::new(normalize_device_name(
-        config.device.clone(),
-    )));
-    let exclusive_selected = std::sync::Arc::new(std::sync::Mutex::new(false));
+    run_listen_until(config, install_ctrlc, None)
+}
+
+/// Run the bridge until `stop` fires (or forever when `None`), then drain playback and return.
+///
+/// Used by service hosts that must report their own stop state instead of exiting the process.
+pub fn run_listen_until(
+    config: BridgeListenConfig,
+    install_ctrlc: bool,
+    stop: Option<crossbeam_channel::Receiver<()>>,
+) -> Result<()> {
+    let device_aliases = DeviceAliases::load(config.device_aliases.clone());
+    let hidden_devices = HiddenDevices::new(&config.hidden_devices);
+    let device_selected = std::sync::Arc::new(std::sync::Mutex::new(
+        normalize_device_name(config.device.clone())
+            .map(|name| device_aliases.resolve(&name))
+            .or_else(|| visible_default_device(&hidden_devices, &device_aliases)),
+    ));
+    let fallback = std::sync::Arc::new(FallbackChain::new(
+        config
+            .fallback_devices
+            .iter()
+            .map(|name| device_aliases.resolve(name))
+            .collect(),
+        config.fallback_return_to_preferred,
+    ));
+    let device_aliases = std::sync::Arc::new(std::sync::Mutex::new(device_aliases));
+    let output_options =
+        std::sync::Arc::new(std::sync::Mutex::new(player::OutputOptions::default()));
     let status = PlayerStatusState::shared();
     let volume = std::sync::Arc::new(player::BridgeVolumeState::new(100, false));
     let known_hub_origins = std::sync::Arc::new(std::sync::Mutex::new(HashSet::<String>::new()));
@@ -52,50 +147,71 @@ pub fn run_listen(config: BridgeListenConfig, install_ctrlc: bool) -> Result<()>
         }
     }
     let bridge_id = mdns::current_bridge_id();
+    let hub_connect = HubConnectOptions {
+        tls_insecure: config.tls_insecure,
+        source_ip: config.source_ip,
+        dscp: config.dscp,
+    };
 
     let mdns_handle: std::sync::Arc<std::sync::Mutex<Option<mdns::MdnsAdvertiser>>> =
         std::sync::Arc::new(std::sync::Mutex::new(None));
-    if install_ctrlc {
-        let mdns_for_signal = mdns_handle.clone();
-        let hubs_for_signal = known_hub_origins.clone();
-        let bridge_id_for_signal = bridge_id.clone();
-        let _ = ctrlc::set_handler(move || {
-            if let Ok(mut g) = mdns_for_signal.lock() {
-                if let Some(ad) = g.as_ref() {
-                    ad.shutdown();
-                }
-                *g = None;
-            }
-            notify_hubs_bridge_unavailable(&bridge_id_for_signal, &hubs_for_signal);
-            std::process::exit(130);
-        });
-    }
-
     let player_handle = player::spawn_player(
         device_selected.clone(),
-        exclusive_selected.clone(),
+        output_options.clone(),
         config.enable_dummy_outputs,
+        fallback,
         status.clone(),
         volume.clone(),
         config.playback.clone(),
-        config.tls_insecure,
+        hub_connect,
     );
     let _http = http_api::spawn_http_server(
         config.http_bind,
         status.clone(),
         volume,
         device_selected.clone(),
-        exclusive_selected.clone(),
+        device_aliases,
+        hidden_devices,
+        output_options.clone(),
         config.enable_dummy_outputs,
-        player_handle.cmd_tx,
+        player_handle.cmd_tx.clone(),
         known_hub_origins.clone(),
+        config.log_buffer.clone(),
+        config.log_filter.clone(),
+        config.playback.mirror.clone(),
     );
+    let shutdown = {
+        let cmd_tx = player_handle.cmd_tx;
+        let status = status.clone();
+        let mdns_handle = mdns_handle.clone();
+        let known_hub_origins = known_hub_origins.clone();
+        let bridge_id = bridge_id.clone();
+        move || {
+            drain_playback(&cmd_tx, &status);
+            if let Ok(mut g) = mdns_handle.lock() {
+                if let Some(ad) = g.as_ref() {
+                    ad.shutdown();
+                }
+                *g = None;
+            }
+            notify_hubs_bridge_unavailable(&bridge_id, &known_hub_origins, &hub_connect);
+        }
+    };
+    let shutdown = std::sync::Arc::new(shutdown);
+    if install_ctrlc {
+        let shutdown = shutdown.clone();
+        let _ = ctrlc::set_handler(move || {
+            shutdown();
+            std::process::exit(130);
+        });
+    }
     if let Ok(mut g) = mdns_handle.lock() {
-        *g = mdns::spawn_mdns_advertiser(config.http_bind);
+        *g = mdns::spawn_mdns_advertiser(config.http_bind, &config.location);
     }
     {
         let mdns_handle = mdns_handle.clone();
         let http_bind = config.http_bind;
+        let location = config.location.clone();
         std::thread::spawn(move || {
             loop {
                 std::thread::sleep(MDNS_REFRESH_INTERVAL);
@@ -103,16 +219,48 @@ pub fn run_listen(config: BridgeListenConfig, install_ctrlc: bool) -> Result<()>
                     if let Some(ad) = g.as_ref() {
                         ad.shutdown();
                     }
-                    *g = mdns::spawn_mdns_advertiser(http_bind);
+                    *g = mdns::spawn_mdns_advertiser(http_bind, &location);
                 }
             }
         });
     }
+    if let Some(stop) = stop {
+        let _ = stop.recv();
+        tracing::info!("bridge stop requested; draining playback");
+        shutdown();
+        return Ok(());
+    }
     let _ = _http.join();
-    notify_hubs_bridge_unavailable(&bridge_id, &known_hub_origins);
+    notify_hubs_bridge_unavailable(&bridge_id, &known_hub_origins, &hub_connect);
     Ok(())
 }
 
+/// Stop the current track and wait (bounded) for the player to release the device.
+fn drain_playback(
+    cmd_tx: &crossbeam_channel::Sender<player::PlayerCommand>,
+    status: &std::sync::Arc<std::sync::Mutex<PlayerStatusState>>,
+) {
+    let playing = status
+        .lock()
+        .map(|s| s.now_playing.is_some())
+        .unwrap_or(false);
+    if !playing || cmd_tx.send(player::PlayerCommand::Stop).is_err() {
+        return;
+    }
+    let deadline = std::time::Instant::now() + DRAIN_TIMEOUT;
+    while std::time::Instant::now() < deadline {
+        let idle = status
+            .lock()
+            .map(|s| s.now_playing.is_none())
+            .unwrap_or(true);
+        if idle {
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    tracing::warn!("playback did not stop within {DRAIN_TIMEOUT:?}; exiting anyway");
+}
+
 /// Normalize and retain only URL origin (`scheme://authority`).
 fn normalize_origin(url: Option<&str>) -> Option<String> {
     let value = url?.trim();
@@ -129,6 +277,7 @@ fn normalize_origin(url: Option<&str>) -> Option<String> {
 fn notify_hubs_bridge_unavailable(
     bridge_id: &str,
     known_hub_origins: &std::sync::Arc<std::sync::Mutex<HashSet<String>>>,
+    hub_connect: &HubConnectOptions,
 ) {
     let origins = known_hub_origins
         .lock()
@@ -139,10 +288,8 @@ fn notify_hubs_bridge_unavailable(
             "{}/providers/bridge/unregister",
             origin.trim_end_matches('/')
         );
-        let response = ureq::post(&url)
-            .config()
-            .timeout_global(Some(std::time::Duration::from_secs(2)))
-            .build()
+        let response = hub_agent(hub_connect, Some(std::time::Duration::from_secs(2)))
+            .post(&url)
             .send_json(json!({ "bridge_id": bridge_id }));
         match response {
             Ok(resp) if resp.status().is_success() => {
@@ -175,15 +322,55 @@ fn normalize_device_name(device: Option<String>) -> Option<String> {
     })
 }
 
+/// First visible device when the system default is hidden; `None` keeps the default.
+fn visible_default_device(hidden: &HiddenDevices, aliases: &DeviceAliases) -> Option<String> {
+    if hidden.is_empty() {
+        return None;
+    }
+    let host = device::output_host().ok()?;
+    let default = host.default_output_device()?;
+    let default_id = default.id().ok().map(|id| id.to_string());
+    let default_name = default.description().ok().map(|d| d.to_string());
+    let infos = device::list_device_infos(&host).ok()?;
+    let is_hidden =
+        |info: &device::DeviceInfo| hidden.hides(aliases, &info.stable_id, &info.id, &info.name);
+    let default_hidden = infos.iter().any(|info| {
+        (default_id.as_deref() == Some(info.id.as_str())
+            || default_name.as_deref() == Some(info.name.as_str()))
+            && is_hidden(info)
+    });
+    if !default_hidden {
+        return None;
+    }
+    let pick = infos.iter().find(|info| !is_hidden(info))?;
+    tracing::info!(
+        device = %pick.name,
+        stable_id = %pick.stable_id,
+        "system default output is hidden; using first visible device"
+    );
+    Some(pick.stable_id.clone())
+}
+
 /// Decode and play a single local file on the given device.
 fn play_one_local(
     device: &cpal::Device,
     playback: &PlaybackConfig,
     path: &std::path::PathBuf,
 ) -> Result<()> {
-    let (src_spec, srcq, _duration_ms, _source_info) =
+    let (src_spec, srcq, _duration_ms, source_info) =
         decode::start_streaming_decode(path, playback.buffer_seconds)?;
-    let config = device::pick_output_config(device, Some(src_spec.rate))?;
+    let (src_spec, srcq) = dsd::ensure_pcm(src_spec, srcq, &source_info, playback.buffer_seconds);
+    play_decoded_local(device, playback, src_spec, srcq)
+}
+
+/// Play an already-started decode on `device` without bridge session state.
+fn play_decoded_local(
+    device: &cpal::Device,
+    playback: &PlaybackConfig,
+    src_spec: symphonia::core::audio::SignalSpec,
+    srcq: std::sync::Arc<audio_player::queue::SharedAudio>,
+) -> Result<()> {
+    let config = device::pick_source_output_config(device, src_spec.rate, playback.rate_switch)?;
     let mut stream_config: cpal::StreamConfig = config.clone().into();
     if let Some(buf) = device::pick_buffer_size(&config) {
         stream_config.buffer_size = buf;
@@ -191,7 +378,7 @@ fn play_one_local(
     tracing::info!(
         channels = src_spec.channels.count(),
         rate_hz = src_spec.rate,
-        "source (local file)"
+        "source"
     );
 
     pipeline::play_decoded_source(
@@ -211,6 +398,10 @@ fn play_one_local(
             buffer_capacity_frames: None,
             volume_percent: None,
             muted: None,
+            levels: None,
+            bit_perfect: None,
+            hotplug: None,
+            output: None,
         },
     )
 }
@@ -0,0 +1,421 @@
+//! OS service registration for the bridge.
+//!
+//! `bridge service install` registers a launchd agent (macOS) or a Windows service that
+//! re-invokes the binary as `bridge <options> service run`. Stop requests from the service
+//! manager drain playback and unregister from known hubs before the process exits.
+
+use anyhow::{Context, Result, anyhow};
+
+use crate::config::BridgeListenConfig;
+use crate::runtime;
+
+/// Register the bridge as an OS service that runs with `options` and start it.
+pub fn install(name: &str, options: &[String]) -> Result<()> {
+    let exe = std::env::current_exe().context("resolve bridge executable path")?;
+    let mut program_args = vec![exe.display().to_string()];
+    program_args.extend(options.iter().cloned());
+    program_args.extend(["service", "run", "--name", name].map(String::from));
+    platform::install(name, &program_args)
+}
+
+/// Stop and remove a previously installed service.
+pub fn uninstall(name: &str) -> Result<()> {
+    platform::uninstall(name)
+}
+
+/// Run the bridge under the service manager until it asks us to stop.
+pub fn run(name: &str, config: BridgeListenConfig) -> Result<()> {
+    platform::run(name, config)
+}
+
+/// Run a helper tool (`launchctl`, `sc.exe`) and surface its output on failure.
+#[cfg(any(target_os = "macos", windows))]
+fn run_tool(cmd: &mut std::process::Command) -> Result<()> {
+    let output = cmd
+        .output()
+        .with_context(|| format!("spawn {:?}", cmd.get_program()))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let detail = if stderr.trim().is_empty() {
+        stdout.trim()
+    } else {
+        stderr.trim()
+    };
+    Err(anyhow!(
+        "{:?} failed ({}): {detail}",
+        cmd.get_program(),
+        output.status
+    ))
+}
+
+/// launchd label for a service name.
+#[cfg(any(target_os = "macos", test))]
+fn launchd_label(name: &str) -> String {
+    format!("com.audio-bridge.{name}")
+}
+
+/// Render a LaunchAgent plist that keeps the bridge running and logs to `log_path`.
+#[cfg(any(target_os = "macos", test))]
+fn launchd_plist(label: &str, program_args: &[String], log_path: &std::path::Path) -> String {
+    let args = program_args
+        .iter()
+        .map(|arg| format!("        <string>{}</string>\n", xml_escape(arg)))
+        .collect::<String>();
+    let log = xml_escape(&log_path.display().to_string());
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{args}    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>ExitTimeOut</key>
+    <integer>15</integer>
+    <key>StandardOutPath</key>
+    <string>{log}</string>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#,
+        label = xml_escape(label),
+    )
+}
+
+#[cfg(any(target_os = "macos", test))]
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Join arguments into a Windows command line (`CommandLineToArgvW` quoting rules).
+#[cfg(any(windows, test))]
+fn windows_command_line(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| quote_windows_arg(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(any(windows, test))]
+fn quote_windows_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_string();
+    }
+    let mut out = String::from("\"");
+    let mut backslashes = 0usize;
+    for ch in arg.chars() {
+        match ch {
+            '\\' => backslashes += 1,
+            '"' => {
+                out.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                out.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                out.extend(std::iter::repeat_n('\\', backslashes));
+                out.push(ch);
+                backslashes = 0;
+            }
+        }
+    }
+    out.extend(std::iter::repeat_n('\\', backslashes * 2));
+    out.push('"');
+    out
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::*;
+
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    /// Path of the per-user LaunchAgent plist for `label`.
+    fn launch_agent_path(home: &Path, label: &str) -> PathBuf {
+        home.join("Library/LaunchAgents")
+            .join(format!("{label}.plist"))
+    }
+
+    fn home() -> Result<PathBuf> {
+        std::env::var_os("HOME")
+            .map(PathBuf::from)
+            .ok_or_else(|| anyhow!("HOME is not set"))
+    }
+
+    pub(super) fn install(name: &str, program_args: &[String]) -> Result<()> {
+        let home = home()?;
+        let label = launchd_label(name);
+        let plist_path = launch_agent_path(&home, &label);
+        let log_path = home.join("Library/Logs").join(format!("{label}.log"));
+        if let Some(dir) = plist_path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+        }
+        if plist_path.exists() {
+            let _ = run_tool(Command::new("launchctl").arg("unload").arg(&plist_path));
+        }
+        std::fs::write(&plist_path, launchd_plist(&label, program_args, &log_path))
+            .with_context(|| format!("write {}", plist_path.display()))?;
+        run_tool(
+            Command::new("launchctl")
+                .args(["load", "-w"])
+                .arg(&plist_path),
+        )?;
+        println!("installed launchd agent {label} ({})", plist_path.display());
+        println!("logs: {}", log_path.display());
+        Ok(())
+    }
+
+    pub(super) fn uninstall(name: &str) -> Result<()> {
+        let label = launchd_label(name);
+        let plist_path = launch_agent_path(&home()?, &label);
+        if !plist_path.exists() {
+            return Err(anyhow!("launchd agent {label} is not installed"));
+        }
+        // `unload` sends SIGTERM, which drains playback via the termination handler.
+        run_tool(
+            Command::new("launchctl")
+                .args(["unload", "-w"])
+                .arg(&plist_path),
+        )?;
+        std::fs::remove_file(&plist_path)
+            .with_context(|| format!("remove {}", plist_path.display()))?;
+        println!("removed launchd agent {label}");
+        Ok(())
+    }
+
+    pub(super) fn run(_name: &str, config: BridgeListenConfig) -> Result<()> {
+        // launchd stops agents with SIGTERM; the ctrlc termination handler drains playback.
+        runtime::run_listen(config, true)
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+
+    use std::ffi::c_void;
+    use std::process::Command;
+    use std::sync::atomic::{AtomicPtr, Ordering};
+    use std::sync::{Mutex, OnceLock};
+
+    use windows_sys::Win32::Foundation::{
+        ERROR_CALL_NOT_IMPLEMENTED, ERROR_SERVICE_SPECIFIC_ERROR, NO_ERROR,
+    };
+    use windows_sys::Win32::System::Services::{
+        RegisterServiceCtrlHandlerExW, SERVICE_ACCEPT_SHUTDOWN, SERVICE_ACCEPT_STOP,
+        SERVICE_CONTROL_INTERROGATE, SERVICE_CONTROL_SHUTDOWN, SERVICE_CONTROL_STOP,
+        SERVICE_RUNNING, SERVICE_STATUS, SERVICE_STATUS_CURRENT_STATE, SERVICE_STATUS_HANDLE,
+        SERVICE_STOP_PENDING, SERVICE_STOPPED, SERVICE_TABLE_ENTRYW, SERVICE_WIN32_OWN_PROCESS,
+        SetServiceStatus, StartServiceCtrlDispatcherW,
+    };
+    use windows_sys::core::PWSTR;
+
+    /// Service name + config handed from `run` to the dispatcher-invoked `service_main`.
+    static PENDING: Mutex<Option<(Vec<u16>, BridgeListenConfig)>> = Mutex::new(None);
+    static STOP_TX: OnceLock<crossbeam_channel::Sender<()>> = OnceLock::new();
+    static STATUS_HANDLE: AtomicPtr<c_void> = AtomicPtr::new(std::ptr::null_mut());
+
+    /// Time the SCM should allow for draining playback before assuming a hang.
+    const STOP_WAIT_HINT_MS: u32 = 10_000;
+
+    fn wide(value: &str) -> Vec<u16> {
+        value.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    pub(super) fn install(name: &str, program_args: &[String]) -> Result<()> {
+        let bin_path = windows_command_line(program_args);
+        run_tool(
+            Command::new("sc.exe")
+                .args(["create", name, "binPath=", &bin_path, "start=", "auto"])
+                .args(["DisplayName=", &format!("Audio Bridge ({name})")]),
+        )?;
+        let _ = run_tool(Command::new("sc.exe").args([
+            "description",
+            name,
+            "audio-bridge playback endpoint",
+        ]));
+        run_tool(Command::new("sc.exe").args(["start", name]))?;
+        println!("installed Windows service {name}");
+        Ok(())
+    }
+
+    pub(super) fn uninstall(name: &str) -> Result<()> {
+        // Ignore "not started"; the SCM waits for the stop to drain before `delete` completes.
+        let _ = run_tool(Command::new("sc.exe").args(["stop", name]));
+        run_tool(Command::new("sc.exe").args(["delete", name]))?;
+        println!("removed Windows service {name}");
+        Ok(())
+    }
+
+    pub(super) fn run(name: &str, config: BridgeListenConfig) -> Result<()> {
+        let name = wide(name);
+        let mut table_name = name.clone();
+        if let Ok(mut g) = PENDING.lock() {
+            *g = Some((name, config));
+        }
+        let table = [
+            SERVICE_TABLE_ENTRYW {
+                lpServiceName: table_name.as_mut_ptr(),
+                lpServiceProc: Some(service_main),
+            },
+            SERVICE_TABLE_ENTRYW {
+                lpServiceName: std::ptr::null_mut(),
+                lpServiceProc: None,
+            },
+        ];
+        // Blocks until `service_main` returns.
+        if unsafe { StartServiceCtrlDispatcherW(table.as_ptr()) } == 0 {
+            return Err(anyhow!(
+                "service dispatcher failed (use `bridge listen` outside the service manager): {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
+
+    fn report(state: SERVICE_STATUS_CURRENT_STATE, exit_code: u32, wait_hint: u32) {
+        let handle: SERVICE_STATUS_HANDLE = STATUS_HANDLE.load(Ordering::Acquire);
+        if handle.is_null() {
+            return;
+        }
+        let controls = if state == SERVICE_RUNNING {
+            SERVICE_ACCEPT_STOP | SERVICE_ACCEPT_SHUTDOWN
+        } else {
+            0
+        };
+        let status = SERVICE_STATUS {
+            dwServiceType: SERVICE_WIN32_OWN_PROCESS,
+            dwCurrentState: state,
+            dwControlsAccepted: controls,
+            dwWin32ExitCode: exit_code,
+            dwServiceSpecificExitCode: u32::from(exit_code == ERROR_SERVICE_SPECIFIC_ERROR),
+            dwCheckPoint: 0,
+            dwWaitHint: wait_hint,
+        };
+        unsafe {
+            SetServiceStatus(handle, &status);
+        }
+    }
+
+    unsafe extern "system" fn service_main(_argc: u32, _argv: *mut PWSTR) {
+        let Some((name, config)) = PENDING.lock().ok().and_then(|mut g| g.take()) else {
+            return;
+        };
+        let (stop_tx, stop_rx) = crossbeam_channel::bounded(1);
+        let _ = STOP_TX.set(stop_tx);
+        let handle = unsafe {
+            RegisterServiceCtrlHandlerExW(name.as_ptr(), Some(control_handler), std::ptr::null())
+        };
+        if handle.is_null() {
+            tracing::error!(
+                "RegisterServiceCtrlHandlerExW failed: {}",
+                std::io::Error::last_os_error()
+            );
+            return;
+        }
+        STATUS_HANDLE.store(handle, Ordering::Release);
+        report(SERVICE_RUNNING, NO_ERROR, 0);
+        let exit_code = match runtime::run_listen_until(config, false, Some(stop_rx)) {
+            Ok(()) => NO_ERROR,
+            Err(err) => {
+                tracing::error!("bridge service failed: {err:#}");
+                ERROR_SERVICE_SPECIFIC_ERROR
+            }
+        };
+        report(SERVICE_STOPPED, exit_code, 0);
+    }
+
+    unsafe extern "system" fn control_handler(
+        control: u32,
+        _event_type: u32,
+        _event_data: *mut c_void,
+        _context: *mut c_void,
+    ) -> u32 {
+        match control {
+            SERVICE_CONTROL_STOP | SERVICE_CONTROL_SHUTDOWN => {
+                report(SERVICE_STOP_PENDING, NO_ERROR, STOP_WAIT_HINT_MS);
+                if let Some(tx) = STOP_TX.get() {
+                    let _ = tx.try_send(());
+                }
+                NO_ERROR
+            }
+            SERVICE_CONTROL_INTERROGATE => NO_ERROR,
+            _ => ERROR_CALL_NOT_IMPLEMENTED,
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use super::*;
+
+    pub(super) fn install(_name: &str, _program_args: &[String]) -> Result<()> {
+        Err(anyhow!(
+            "service install supports launchd (macOS) and Windows; use a systemd unit running `bridge listen` instead"
+        ))
+    }
+
+    pub(super) fn uninstall(_name: &str) -> Result<()> {
+        Err(anyhow!(
+            "service uninstall supports launchd (macOS) and Windows only"
+        ))
+    }
+
+    pub(super) fn run(_name: &str, config: BridgeListenConfig) -> Result<()> {
+        runtime::run_listen(config, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn launchd_plist_escapes_program_arguments() {
+        let plist = launchd_plist(
+            &launchd_label("audio-bridge"),
+            &[
+                "/opt/bridge".to_string(),
+                "--device".to_string(),
+                "A&B <DAC>".to_string(),
+            ],
+            Path::new("/tmp/bridge.log"),
+        );
+        assert!(plist.contains("<string>com.audio-bridge.audio-bridge</string>"));
+        assert!(plist.contains("<string>A&amp;B &lt;DAC&gt;</string>"));
+        assert!(plist.contains("<string>/tmp/bridge.log</string>"));
+    }
+
+    #[test]
+    fn windows_command_line_quotes_when_needed() {
+        let line = windows_command_line(&[
+            r"C:\Program Files\bridge.exe".to_string(),
+            "--device".to_string(),
+            r#"Speakers "USB""#.to_string(),
+            r"C:\trailing\".to_string(),
+            "listen".to_string(),
+        ]);
+        assert_eq!(
+            line,
+            r#""C:\Program Files\bridge.exe" --device "Speakers \"USB\"" C:\trailing\ listen"#
+        );
+    }
+}
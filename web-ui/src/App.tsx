@@ -195,7 +195,8 @@ export default function App() {
     handleRefreshProvider,
     handleToggleOutputSetting,
     handleRenameOutputSetting,
-    handleToggleExclusiveSetting
+    handleToggleExclusiveSetting,
+    handleSetPrerollSetting
   } = useOutputSettings({
     settingsOpen,
     settingsSection,
@@ -671,6 +672,7 @@ export default function App() {
           onToggleOutput={handleToggleOutputSetting}
           onRenameOutput={handleRenameOutputSetting}
           onToggleExclusive={handleToggleExclusiveSetting}
+          onSetPreroll={handleSetPrerollSetting}
           metadataEvents={metadataEvents}
           logEvents={logEvents}
           logsError={logsError}
@@ -108,6 +108,7 @@ type MainContentProps = {
   onToggleOutput: (outputId: string, enabled: boolean) => void;
   onRenameOutput: (outputId: string, name: string) => void;
   onToggleExclusive: (outputId: string, enabled: boolean) => void;
+  onSetPreroll: (outputId: string, ms: number) => void;
   metadataEvents: MetadataEventEntry[];
   logEvents: LogEventEntry[];
   logsError: string | null;
@@ -198,6 +199,7 @@ export default function MainContent({
   onToggleOutput,
   onRenameOutput,
   onToggleExclusive,
+  onSetPreroll,
   metadataEvents,
   logEvents,
   logsError,
@@ -327,6 +329,7 @@ export default function MainContent({
         onToggleOutput={onToggleOutput}
         onRenameOutput={onRenameOutput}
         onToggleExclusive={onToggleExclusive}
+        onSetPreroll={onSetPreroll}
         metadataEvents={metadataEvents}
         logEvents={logEvents}
         logsError={logsError}
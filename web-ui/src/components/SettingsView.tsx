@@ -2,6 +2,9 @@ import { useEffect, useState } from "react";
 import { RefreshCw, Edit2, Circle } from "lucide-react";
 import { LogEvent, MetadataEvent, OutputSettings, ProviderOutputs } from "../types";
 
+/** Matches the hub/bridge limit on per-output pre-roll silence. */
+const MAX_PREROLL_MS = 5000;
+
 interface MetadataEventEntry {
   id: number;
   time: Date;
@@ -40,6 +43,7 @@ interface SettingsViewProps {
   onToggleOutput: (outputId: string, enabled: boolean) => void;
   onRenameOutput: (outputId: string, name: string) => void;
   onToggleExclusive: (outputId: string, enabled: boolean) => void;
+  onSetPreroll: (outputId: string, ms: number) => void;
 }
 
 export default function SettingsView({
@@ -68,7 +72,8 @@ export default function SettingsView({
   onRefreshProvider,
   onToggleOutput,
   onRenameOutput,
-  onToggleExclusive
+  onToggleExclusive,
+  onSetPreroll
 }: SettingsViewProps) {
   const isMetadata = section === "metadata";
   const isLogs = section === "logs";
@@ -98,6 +103,16 @@ export default function SettingsView({
     if (!outputsSettings) return false;
     return outputsSettings.exclusive.includes(outputId);
   };
+  const prerollMs = (outputId: string) => {
+    return outputsSettings?.preroll_silence_ms?.[outputId] ?? 0;
+  };
+  const commitPreroll = (outputId: string, value: string) => {
+    const parsed = Math.round(Number(value));
+    const ms = Number.isFinite(parsed) ? Math.min(Math.max(parsed, 0), MAX_PREROLL_MS) : 0;
+    if (ms !== prerollMs(outputId)) {
+      onSetPreroll(outputId, ms);
+    }
+  };
   const startRename = (outputId: string, currentName: string) => {
     setRenamingOutput(outputId);
     setRenameDraft(currentName);
@@ -321,6 +336,27 @@ export default function SettingsView({
                                   <span>Exclusive mode</span>
                                 </label>
                               ) : null}
+                              {provider.kind === "bridge" ? (
+                                <label className="outputs-exclusive-row">
+                                  <span>Pre-roll silence</span>
+                                  <input
+                                    key={`${output.id}:${prerollMs(output.id)}`}
+                                    className="outputs-preroll-input"
+                                    type="number"
+                                    min={0}
+                                    max={MAX_PREROLL_MS}
+                                    step={50}
+                                    defaultValue={prerollMs(output.id)}
+                                    onBlur={(event) => commitPreroll(output.id, event.target.value)}
+                                    onKeyDown={(event) => {
+                                      if (event.key === "Enter") {
+                                        event.currentTarget.blur();
+                                      }
+                                    }}
+                                  />
+                                  <span>ms</span>
+                                </label>
+                              ) : null}
                             </div>
                             <div className="outputs-device-actions">
                               <label className="outputs-toggle">
@@ -120,6 +120,30 @@ export function useOutputSettings({
     [outputsSettings, updateOutputSettings]
   );
 
+  const handleSetPrerollSetting = useCallback(
+    async (outputId: string, ms: number) => {
+      if (!outputsSettings) return;
+      const preroll = { ...(outputsSettings.preroll_silence_ms ?? {}) };
+      if (ms > 0) {
+        preroll[outputId] = ms;
+      } else {
+        delete preroll[outputId];
+      }
+      const next: OutputSettings = {
+        ...outputsSettings,
+        preroll_silence_ms: preroll
+      };
+      setOutputsSettings(next);
+      try {
+        await updateOutputSettings(next);
+      } catch (error) {
+        setOutputsSettings(outputsSettings);
+        setOutputsError(error instanceof Error ? error.message : "Failed to update outputs");
+      }
+    },
+    [outputsSettings, updateOutputSettings]
+  );
+
   const handleRefreshProvider = useCallback(
     async (providerId: string) => {
       try {
@@ -146,6 +170,7 @@ export function useOutputSettings({
     handleRefreshProvider,
     handleToggleOutputSetting,
     handleRenameOutputSetting,
-    handleToggleExclusiveSetting
+    handleToggleExclusiveSetting,
+    handleSetPrerollSetting
   };
 }
@@ -51,7 +51,10 @@ const outputSettings: OutputSettings = {
   renames: {
     "bridge:living-room:device-1": "Living Room DAC"
   },
-  exclusive: ["bridge:living-room:device-1"]
+  exclusive: ["bridge:living-room:device-1"],
+  preroll_silence_ms: {
+    "bridge:living-room:device-1": 250
+  }
 };
 
 const outputProviders: ProviderOutputs[] = [
@@ -157,6 +160,7 @@ const Template = (args: SettingsViewArgs) => (
       onToggleOutput={action("toggle-output")}
       onRenameOutput={action("rename-output")}
       onToggleExclusive={action("toggle-exclusive")}
+      onSetPreroll={action("set-preroll")}
       metadataEvents={args.empty ? [] : metadataEvents}
       logEvents={args.empty ? [] : logEvents}
       logsError={args.logsError || null}
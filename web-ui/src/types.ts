@@ -5,6 +5,7 @@ export interface OutputInfo {
   state: string;
   provider_name?: string | null;
   supported_rates?: { min_hz: number; max_hz: number } | null;
+  formats?: { sample_rates: number[]; sample_formats: string[]; channels: number[] } | null;
 }
 
 export interface StatusResponse {
@@ -55,6 +56,7 @@ export interface OutputSettings {
   disabled: string[];
   renames: Record<string, string>;
   exclusive: string[];
+  preroll_silence_ms?: Record<string, number>;
 }
 
 export interface ProviderOutputs {